@@ -25,7 +25,7 @@
 //! This module also exposes some standalone functions for common operations when building
 //! aura-based collators.
 
-use codec::Codec;
+use codec::{Codec, Decode};
 use cumulus_client_collator::service::ServiceInterface as CollatorServiceInterface;
 use cumulus_client_consensus_common::{
 	self as consensus_common, ParachainBlockImportMarker, ParachainCandidate,
@@ -34,6 +34,7 @@ use cumulus_client_consensus_proposer::ProposerInterface;
 use cumulus_client_parachain_inherent::{ParachainInherentData, ParachainInherentDataProvider};
 use cumulus_primitives_core::{
 	relay_chain::Hash as PHash, DigestItem, ParachainBlockData, PersistedValidationData,
+	RelayStorageKeysApi,
 };
 use cumulus_relay_chain_interface::RelayChainInterface;
 
@@ -45,7 +46,7 @@ use futures::prelude::*;
 use sc_consensus::{BlockImport, BlockImportParams, ForkChoiceStrategy, StateAction};
 use sc_consensus_aura::standalone as aura_internal;
 use sc_network_types::PeerId;
-use sp_api::ProvideRuntimeApi;
+use sp_api::{ApiExt, CallApiAt, CallApiAtParams, CallContext, ProvideRuntimeApi};
 use sp_application_crypto::AppPublic;
 use sp_consensus::BlockOrigin;
 use sp_consensus_aura::{AuraApi, Slot, SlotDuration};
@@ -124,6 +125,9 @@ where
 	/// the timestamp inherent data with the one provided, if any. Additionally allows to specify
 	/// relay parent descendants that can be used to prevent authoring at the tip of the relay
 	/// chain.
+	///
+	/// `relay_storage_keys` are included in the relay chain state proof in addition to the keys
+	/// that are always included, see [`relay_storage_keys`].
 	pub async fn create_inherent_data_with_rp_offset(
 		&self,
 		relay_parent: PHash,
@@ -131,6 +135,7 @@ where
 		parent_hash: Block::Hash,
 		timestamp: impl Into<Option<Timestamp>>,
 		relay_parent_descendants: Option<RelayParentData>,
+		relay_storage_keys: Vec<Vec<u8>>,
 		collator_peer_id: PeerId,
 	) -> Result<(ParachainInherentData, InherentData), Box<dyn Error + Send + Sync + 'static>> {
 		let paras_inherent_data = ParachainInherentDataProvider::create_at(
@@ -141,7 +146,7 @@ where
 			relay_parent_descendants
 				.map(RelayParentData::into_inherent_descendant_list)
 				.unwrap_or_default(),
-			Vec::new(),
+			relay_storage_keys,
 			collator_peer_id,
 		)
 		.await;
@@ -178,6 +183,7 @@ where
 		validation_data: &PersistedValidationData,
 		parent_hash: Block::Hash,
 		timestamp: impl Into<Option<Timestamp>>,
+		relay_storage_keys: Vec<Vec<u8>>,
 		collator_peer_id: PeerId,
 	) -> Result<(ParachainInherentData, InherentData), Box<dyn Error + Send + Sync + 'static>> {
 		self.create_inherent_data_with_rp_offset(
@@ -186,6 +192,7 @@ where
 			parent_hash,
 			timestamp,
 			None,
+			relay_storage_keys,
 			collator_peer_id,
 		)
 		.await
@@ -400,6 +407,43 @@ where
 	Ok(Some(SlotClaim::unchecked::<P>(author_pub, slot_now, timestamp)))
 }
 
+/// Fetch the relay chain storage keys that the runtime at `parent_hash` wants to read.
+///
+/// Returns no keys if the runtime does not implement the [`RelayStorageKeysApi`]. The API is
+/// called by name, so that the runtime API of the client does not need to declare it.
+pub fn relay_storage_keys<B, C>(client: &C, parent_hash: B::Hash) -> Vec<Vec<u8>>
+where
+	B: BlockT,
+	C: ProvideRuntimeApi<B> + CallApiAt<B>,
+{
+	match client.runtime_api().has_api::<dyn RelayStorageKeysApi<B>>(parent_hash) {
+		Ok(true) => {},
+		_ => return Vec::new(),
+	}
+
+	client
+		.call_api_at(CallApiAtParams {
+			at: parent_hash,
+			function: "RelayStorageKeysApi_relay_storage_keys",
+			arguments: Vec::new(),
+			overlayed_changes: &Default::default(),
+			call_context: CallContext::Offchain,
+			recorder: &None,
+			extensions: &Default::default(),
+		})
+		.map_err(|error| error.to_string())
+		.and_then(|keys| Vec::<Vec<u8>>::decode(&mut &keys[..]).map_err(|error| error.to_string()))
+		.unwrap_or_else(|error| {
+			tracing::warn!(
+				target: crate::LOG_TARGET,
+				%error,
+				?parent_hash,
+				"Failed to fetch the relay chain storage keys requested by the runtime."
+			);
+			Vec::new()
+		})
+}
+
 /// Seal a block with a signature in the header.
 pub fn seal<B: BlockT, P>(
 	pre_sealed: B,
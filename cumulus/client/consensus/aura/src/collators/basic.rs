@@ -238,6 +238,7 @@ where
 						&validation_data,
 						parent_hash,
 						claim.timestamp(),
+						collator_util::relay_storage_keys(&*params.para_client, parent_hash),
 						params.collator_peer_id,
 					)
 					.await
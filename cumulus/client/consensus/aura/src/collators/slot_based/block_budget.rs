@@ -0,0 +1,420 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus. If not, see <https://www.gnu.org/licenses/>.
+
+//! Dynamic per-block budget of the slot-based collator.
+//!
+//! The proposer is given a time budget, which bounds the weight of the block, and a size budget,
+//! which bounds the size of the PoV. Both are derived from the relay chain limits and adjusted
+//! to what was observed for the blocks built recently:
+//!
+//! - The relay chain limits apply per core. When more blocks are built per relay chain slot than
+//!   cores are assigned, the blocks share the cores and both budgets are scaled by `cores /
+//!   blocks`.
+//! - The PoV budget targets a percentage of the maximum PoV size. The proposer can only estimate
+//!   the final PoV size, so whenever a recent PoV exceeded the target, the budget is scaled down by
+//!   the observed overshoot. Once blocks fill their budget again without overshooting, the budget
+//!   grows back step by step.
+//! - The time budget is the configured authoring duration, capped by the time left until the next
+//!   slot. With more cores assigned, slots are shorter and each block gets less time.
+//!
+//! The observation window spans all blocks that are built for the cores claimed at the same time,
+//! i.e. `blocks * (relay_parent_offset + 1)` blocks.
+
+use crate::LOG_TARGET;
+use prometheus_endpoint::{
+	linear_buckets, register, CounterVec, Gauge, Histogram, HistogramOpts, Opts, PrometheusError,
+	Registry, F64, U64,
+};
+use std::{collections::VecDeque, time::Duration};
+
+/// Percentage of the maximum PoV size that is targeted if none is configured.
+///
+/// Leaves room for the overhead that the proposer can't account for, see
+/// <https://github.com/paritytech/polkadot-sdk/issues/6020>.
+pub(crate) const DEFAULT_POV_TARGET_PERCENTAGE: u32 = 85;
+
+/// The lowest the correction of the PoV budget can go, in percent.
+const MIN_CORRECTION: u32 = 50;
+
+/// By how many percent the correction grows back after a block filled its budget.
+const CORRECTION_RECOVERY_STEP: u32 = 5;
+
+/// A block is considered full if its PoV uses at least this percentage of its budget.
+const FULL_BLOCK_PERCENTAGE: usize = 90;
+
+/// Upper bound of the observation window.
+const MAX_OBSERVATIONS: usize = 64;
+
+/// Why the PoV budget has its current value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PoVBudgetReason {
+	/// The budget equals the target.
+	Target,
+	/// A recent PoV exceeded the target, so the budget was reduced.
+	Overshoot,
+	/// Recent blocks filled their budget without overshooting, so the budget grows back.
+	Recovering,
+	/// The budget stays reduced, recent blocks didn't fill it.
+	Reduced,
+}
+
+impl PoVBudgetReason {
+	fn as_str(&self) -> &'static str {
+		match self {
+			Self::Target => "target",
+			Self::Overshoot => "overshoot",
+			Self::Recovering => "recovering",
+			Self::Reduced => "reduced",
+		}
+	}
+}
+
+/// Why the time budget has its current value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TimeBudgetReason {
+	/// The configured authoring duration.
+	Configured,
+	/// The configured authoring duration, shared with the other blocks built on the same cores.
+	SharedCores,
+	/// Capped by the time left until the next slot.
+	NextSlot,
+}
+
+impl TimeBudgetReason {
+	fn as_str(&self) -> &'static str {
+		match self {
+			Self::Configured => "configured",
+			Self::SharedCores => "shared_cores",
+			Self::NextSlot => "next_slot",
+		}
+	}
+}
+
+/// The budget for building a single block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct BlockBudget {
+	/// The maximum size of the block, passed to the proposer.
+	pub pov_size: usize,
+	/// The PoV size targeted for the block before the correction.
+	pub pov_target: usize,
+	/// Why `pov_size` has its value.
+	pub pov_reason: PoVBudgetReason,
+	/// The maximum time to spend on building the block.
+	pub authoring_duration: Duration,
+	/// Why `authoring_duration` has its value.
+	pub time_reason: TimeBudgetReason,
+}
+
+/// The inputs of a budget decision.
+#[derive(Debug, Clone)]
+pub(crate) struct BudgetInputs {
+	/// The maximum PoV size accepted by the relay chain.
+	pub max_pov_size: u32,
+	/// The number of cores assigned at the claim queue offset.
+	pub number_of_cores: u16,
+	/// The slot duration of the parachain.
+	pub para_slot_duration: Duration,
+	/// The slot duration of the relay chain.
+	pub relay_slot_duration: Duration,
+	/// The relay parent offset of the runtime.
+	pub relay_parent_offset: u32,
+	/// The time left until the next slot, if known.
+	pub time_until_next_slot: Option<Duration>,
+}
+
+/// A block that was built with a certain budget.
+#[derive(Debug, Clone, Copy)]
+struct Observation {
+	/// The PoV budget the block was built with.
+	budget: usize,
+	/// The PoV target at the time the block was built.
+	target: usize,
+	/// The size of the resulting PoV.
+	pov_size: usize,
+}
+
+/// Computes the [`BlockBudget`] of each block from the chain limits and recent PoV sizes.
+pub(crate) struct BlockBudgetController {
+	/// Percentage of the maximum PoV size that is targeted.
+	target_percentage: u32,
+	/// The configured time to spend authoring each block.
+	authoring_duration: Duration,
+	/// Correction applied to the PoV target, in percent.
+	correction: u32,
+	/// The most recent observations, newest last.
+	observations: VecDeque<Observation>,
+	/// The size of the observation window.
+	window: usize,
+	metrics: Option<BlockBudgetMetrics>,
+}
+
+impl BlockBudgetController {
+	/// Create a new controller.
+	///
+	/// `max_pov_percentage` overrides [`DEFAULT_POV_TARGET_PERCENTAGE`].
+	pub(crate) fn new(
+		max_pov_percentage: Option<u32>,
+		authoring_duration: Duration,
+		registry: Option<&Registry>,
+	) -> Self {
+		let metrics = registry.and_then(|registry| {
+			BlockBudgetMetrics::register(registry)
+				.inspect_err(|err| {
+					tracing::warn!(
+						target: LOG_TARGET,
+						?err,
+						"Failed to register block budget metrics",
+					)
+				})
+				.ok()
+		});
+
+		Self {
+			target_percentage: max_pov_percentage.unwrap_or(DEFAULT_POV_TARGET_PERCENTAGE),
+			authoring_duration,
+			correction: 100,
+			observations: VecDeque::new(),
+			window: 1,
+			metrics,
+		}
+	}
+
+	/// Decide on the budget of the next block.
+	pub(crate) fn budget(&mut self, inputs: &BudgetInputs) -> BlockBudget {
+		let cores = inputs.number_of_cores.max(1) as u32;
+		let blocks = blocks_per_relay_slot(inputs);
+		self.window = (blocks as usize)
+			.saturating_mul(inputs.relay_parent_offset as usize + 1)
+			.min(MAX_OBSERVATIONS);
+		while self.observations.len() > self.window {
+			self.observations.pop_front();
+		}
+
+		let pov_target = self.target(inputs.max_pov_size) * cores as usize / blocks as usize;
+		let pov_reason = self.update_correction(pov_target);
+		let pov_size = pov_target * self.correction as usize / 100;
+
+		let (shared_duration, shared_reason) = if blocks > cores {
+			(self.authoring_duration * cores / blocks, TimeBudgetReason::SharedCores)
+		} else {
+			(self.authoring_duration, TimeBudgetReason::Configured)
+		};
+		let (authoring_duration, time_reason) = match inputs.time_until_next_slot {
+			Some(left) if left < shared_duration => (left, TimeBudgetReason::NextSlot),
+			_ => (shared_duration, shared_reason),
+		};
+
+		let budget =
+			BlockBudget { pov_size, pov_target, pov_reason, authoring_duration, time_reason };
+
+		tracing::debug!(
+			target: LOG_TARGET,
+			max_pov_size = inputs.max_pov_size,
+			cores = inputs.number_of_cores,
+			blocks,
+			relay_parent_offset = inputs.relay_parent_offset,
+			correction = self.correction,
+			?budget,
+			"Block budget.",
+		);
+
+		if let Some(metrics) = &self.metrics {
+			metrics.on_budget(inputs, blocks, self.correction, &budget);
+		}
+
+		budget
+	}
+
+	/// Record the size of the PoV of a block that was built with `budget`.
+	pub(crate) fn on_block_built(
+		&mut self,
+		max_pov_size: u32,
+		budget: &BlockBudget,
+		pov_size: usize,
+	) {
+		self.observations.push_back(Observation {
+			budget: budget.pov_size,
+			target: budget.pov_target,
+			pov_size,
+		});
+		while self.observations.len() > self.window {
+			self.observations.pop_front();
+		}
+
+		if let Some(metrics) = &self.metrics {
+			metrics.on_block_built(max_pov_size, pov_size);
+		}
+	}
+
+	fn target(&self, max_pov_size: u32) -> usize {
+		(max_pov_size as usize).saturating_mul(self.target_percentage as usize) / 100
+	}
+}
+
+/// The number of blocks that are built per relay chain slot, on the assigned cores.
+///
+/// This mirrors the slot timer: it attempts to build a block in every parachain slot, and once
+/// per core if there are more cores than parachain slots per relay chain slot.
+fn blocks_per_relay_slot(inputs: &BudgetInputs) -> u32 {
+	let para_slot_millis = inputs.para_slot_duration.as_millis().max(1);
+	let para_slots = (inputs.relay_slot_duration.as_millis() / para_slot_millis).max(1) as u32;
+	para_slots.max(inputs.number_of_cores.max(1).into())
+}
+
+impl BlockBudgetController {
+	/// Adjust the correction to the recent observations.
+	fn update_correction(&mut self, target: usize) -> PoVBudgetReason {
+		let overshoot = self
+			.observations
+			.iter()
+			.filter(|o| o.pov_size > o.target)
+			.max_by_key(|o| o.pov_size * 100 / o.target.max(1))
+			.copied();
+
+		if let Some(Observation { pov_size, target: observed_target, .. }) = overshoot {
+			// Scale the correction by the overshoot. The overshooting observations are dropped,
+			// so that each of them reduces the correction only once.
+			self.correction = (self.correction as usize * observed_target / pov_size)
+				.clamp(MIN_CORRECTION as usize, 100) as u32;
+			self.observations.retain(|o| o.pov_size <= o.target);
+			return PoVBudgetReason::Overshoot
+		}
+
+		if self.correction == 100 {
+			return PoVBudgetReason::Target
+		}
+
+		let last_was_full = self
+			.observations
+			.back()
+			.map_or(false, |o| o.pov_size * 100 >= o.budget * FULL_BLOCK_PERCENTAGE);
+
+		if last_was_full && target > 0 {
+			self.correction = (self.correction + CORRECTION_RECOVERY_STEP).min(100);
+			PoVBudgetReason::Recovering
+		} else {
+			PoVBudgetReason::Reduced
+		}
+	}
+}
+
+/// Metrics explaining the budget decisions.
+struct BlockBudgetMetrics {
+	/// The PoV budget of the last block.
+	pov_budget: Gauge<U64>,
+	/// The correction applied to the PoV target.
+	pov_correction: Gauge<U64>,
+	/// The time budget of the last block.
+	authoring_duration: Gauge<F64>,
+	/// The number of cores the last budget was computed for.
+	cores: Gauge<U64>,
+	/// The relay parent offset the last budget was computed for.
+	relay_parent_offset: Gauge<U64>,
+	/// The number of blocks per relay chain slot the last budget was computed for.
+	blocks_per_relay_slot: Gauge<U64>,
+	/// Size of the built PoVs relative to the maximum PoV size.
+	pov_usage: Histogram,
+	/// Number of budget decisions, by resource and reason.
+	decisions: CounterVec<U64>,
+}
+
+impl BlockBudgetMetrics {
+	fn register(registry: &Registry) -> Result<Self, PrometheusError> {
+		Ok(Self {
+			pov_budget: register(
+				Gauge::new(
+					"parachain_slot_based_pov_budget_bytes",
+					"PoV size budget of the last block built by the slot-based collator",
+				)?,
+				registry,
+			)?,
+			pov_correction: register(
+				Gauge::new(
+					"parachain_slot_based_pov_budget_correction",
+					"Correction of the PoV size target after PoVs exceeded it, in percent",
+				)?,
+				registry,
+			)?,
+			authoring_duration: register(
+				Gauge::new(
+					"parachain_slot_based_authoring_duration_seconds",
+					"Time budget of the last block built by the slot-based collator",
+				)?,
+				registry,
+			)?,
+			cores: register(
+				Gauge::new(
+					"parachain_slot_based_cores",
+					"Number of cores the last block budget was computed for",
+				)?,
+				registry,
+			)?,
+			relay_parent_offset: register(
+				Gauge::new(
+					"parachain_slot_based_relay_parent_offset",
+					"Relay parent offset the last block budget was computed for",
+				)?,
+				registry,
+			)?,
+			blocks_per_relay_slot: register(
+				Gauge::new(
+					"parachain_slot_based_blocks_per_relay_slot",
+					"Number of blocks per relay chain slot the last block budget was computed for",
+				)?,
+				registry,
+			)?,
+			pov_usage: register(
+				Histogram::with_opts(
+					HistogramOpts::new(
+						"parachain_slot_based_pov_usage",
+						"Size of the built PoVs relative to the maximum PoV size",
+					)
+					.buckets(
+						linear_buckets(0.1, 0.1, 10)
+							.expect("function parameters are constant and always valid; qed"),
+					),
+				)?,
+				registry,
+			)?,
+			decisions: register(
+				CounterVec::new(
+					Opts::new(
+						"parachain_slot_based_budget_decisions",
+						"Number of block budget decisions by the slot-based collator",
+					),
+					&["resource", "reason"],
+				)?,
+				registry,
+			)?,
+		})
+	}
+
+	fn on_budget(&self, inputs: &BudgetInputs, blocks: u32, correction: u32, budget: &BlockBudget) {
+		self.pov_budget.set(budget.pov_size as u64);
+		self.pov_correction.set(correction.into());
+		self.authoring_duration.set(budget.authoring_duration.as_secs_f64());
+		self.cores.set(inputs.number_of_cores.into());
+		self.relay_parent_offset.set(inputs.relay_parent_offset.into());
+		self.blocks_per_relay_slot.set(blocks.into());
+		self.decisions.with_label_values(&["pov", budget.pov_reason.as_str()]).inc();
+		self.decisions.with_label_values(&["time", budget.time_reason.as_str()]).inc();
+	}
+
+	fn on_block_built(&self, max_pov_size: u32, pov_size: usize) {
+		self.pov_usage.observe(pov_size as f64 / max_pov_size.max(1) as f64);
+	}
+}
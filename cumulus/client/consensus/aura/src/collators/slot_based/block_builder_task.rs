@@ -23,6 +23,7 @@ use crate::{
 	collators::{
 		check_validation_code_or_log,
 		slot_based::{
+			block_budget::{BlockBudgetController, BudgetInputs},
 			relay_chain_data_cache::{RelayChainData, RelayChainDataCache},
 			slot_timer::{SlotInfo, SlotTimer},
 		},
@@ -31,30 +32,33 @@ use crate::{
 	LOG_TARGET,
 };
 use cumulus_client_collator::service::ServiceInterface as CollatorServiceInterface;
-use cumulus_client_consensus_common::{self as consensus_common, ParachainBlockImportMarker};
+use cumulus_client_consensus_common::{
+	self as consensus_common, ParachainBlockImportMarker, ParachainCandidate,
+};
 use cumulus_client_consensus_proposer::ProposerInterface;
 use cumulus_primitives_aura::{AuraUnincludedSegmentApi, Slot};
 use cumulus_primitives_core::{
 	extract_relay_parent, rpsr_digest, ClaimQueueOffset, CoreInfo, CoreSelector, CumulusDigestItem,
-	PersistedValidationData, RelayParentOffsetApi,
+	ParachainBlockData, PersistedValidationData, RelayParentOffsetApi,
 };
 use cumulus_relay_chain_interface::RelayChainInterface;
 use futures::prelude::*;
 use polkadot_primitives::{
 	Block as RelayBlock, CoreIndex, Hash as RelayHash, Header as RelayHeader, Id as ParaId,
 };
+use prometheus_endpoint::Registry;
 use sc_client_api::{backend::AuxStore, BlockBackend, BlockOf, UsageProvider};
 use sc_consensus::BlockImport;
 use sc_consensus_aura::SlotDuration;
 use sc_network_types::PeerId;
-use sp_api::ProvideRuntimeApi;
+use sp_api::{CallApiAt, ProvideRuntimeApi};
 use sp_application_crypto::AppPublic;
 use sp_blockchain::HeaderBackend;
 use sp_consensus_aura::AuraApi;
 use sp_core::crypto::Pair;
 use sp_inherents::CreateInherentDataProviders;
 use sp_keystore::KeystorePtr;
-use sp_runtime::traits::{Block as BlockT, Header as HeaderT, Member, Zero};
+use sp_runtime::traits::{Block as BlockT, HashingFor, Header as HeaderT, Member, Zero};
 use std::{collections::VecDeque, sync::Arc, time::Duration};
 
 /// Parameters for [`run_block_builder`].
@@ -110,6 +114,8 @@ pub struct BuilderTaskParams<
 	/// The maximum percentage of the maximum PoV size that the collator can use.
 	/// It will be removed once https://github.com/paritytech/polkadot-sdk/issues/6020 is fixed.
 	pub max_pov_percentage: Option<u32>,
+	/// The prometheus registry used to report the block budget decisions.
+	pub prometheus_registry: Option<Registry>,
 }
 
 /// Run block-builder.
@@ -124,6 +130,7 @@ where
 		+ AuxStore
 		+ HeaderBackend<Block>
 		+ BlockBackend<Block>
+		+ CallApiAt<Block>
 		+ Send
 		+ Sync
 		+ 'static,
@@ -160,6 +167,7 @@ where
 			para_backend,
 			slot_offset,
 			max_pov_percentage,
+			prometheus_registry,
 		} = params;
 
 		let mut slot_timer = SlotTimer::<_, _, P>::new_with_offset(
@@ -183,6 +191,12 @@ where
 			collator_util::Collator::<Block, P, _, _, _, _, _>::new(params)
 		};
 
+		let mut block_budget = BlockBudgetController::new(
+			max_pov_percentage,
+			authoring_duration,
+			prometheus_registry.as_ref(),
+		);
+
 		let mut relay_chain_data_cache = RelayChainDataCache::new(relay_client.clone(), para_id);
 		let mut connection_helper = BackingGroupConnectionHelper::new(
 			keystore.clone(),
@@ -367,6 +381,7 @@ where
 					parent_hash,
 					slot_claim.timestamp(),
 					Some(rp_data),
+					collator_util::relay_storage_keys(&*para_client, parent_hash),
 					collator_peer_id,
 				)
 				.await
@@ -394,22 +409,17 @@ where
 			)
 			.await;
 
-			let allowed_pov_size = if let Some(max_pov_percentage) = max_pov_percentage {
-				validation_data.max_pov_size * max_pov_percentage / 100
-			} else {
-				// Set the block limit to 85% of the maximum PoV size.
-				//
-				// Once https://github.com/paritytech/polkadot-sdk/issues/6020 issue is
-				// fixed, this should be removed.
-				validation_data.max_pov_size * 85 / 100
-			} as usize;
-
-			let adjusted_authoring_duration = match slot_timer.time_until_next_slot() {
-				Ok((duration, _slot)) => std::cmp::min(authoring_duration, duration),
-				Err(_) => authoring_duration,
-			};
-
-			tracing::debug!(target: crate::LOG_TARGET, duration = ?adjusted_authoring_duration, "Adjusted proposal duration.");
+			let budget = block_budget.budget(&BudgetInputs {
+				max_pov_size: validation_data.max_pov_size,
+				number_of_cores: core.total_cores(),
+				relay_parent_offset,
+				para_slot_duration: para_slot_duration.as_duration(),
+				relay_slot_duration: relay_chain_slot_duration,
+				time_until_next_slot: slot_timer
+					.time_until_next_slot()
+					.ok()
+					.map(|(duration, _slot)| duration),
+			});
 
 			let Ok(Some(candidate)) = collator
 				.build_block_and_import(
@@ -417,8 +427,8 @@ where
 					&slot_claim,
 					Some(vec![CumulusDigestItem::CoreInfo(core.core_info()).to_digest_item()]),
 					(parachain_inherent_data, other_inherent_data),
-					adjusted_authoring_duration,
-					allowed_pov_size,
+					budget.authoring_duration,
+					budget.pov_size,
 				)
 				.await
 			else {
@@ -426,6 +436,10 @@ where
 				continue;
 			};
 
+			if let Some(pov_size) = compact_pov_size(parent_header, &candidate) {
+				block_budget.on_block_built(validation_data.max_pov_size, &budget, pov_size);
+			}
+
 			let new_block_hash = candidate.block.header().hash();
 
 			// Announce the newly built block to our peers.
@@ -448,6 +462,23 @@ where
 	}
 }
 
+/// The size of the PoV of `candidate`, as it is sent to the validators before compression.
+fn compact_pov_size<Block: BlockT>(
+	parent_header: &Block::Header,
+	candidate: &ParachainCandidate<Block>,
+) -> Option<usize> {
+	let proof = candidate
+		.proof
+		.clone()
+		.into_compact_proof::<HashingFor<Block>>(*parent_header.state_root())
+		.map_err(
+			|err| tracing::debug!(target: LOG_TARGET, ?err, "Failed to compact the proof of the block."),
+		)
+		.ok()?;
+
+	Some(ParachainBlockData::<Block>::new(vec![candidate.block.clone()], proof).encoded_size())
+}
+
 /// Translate the slot of the relay parent to the slot of the parachain.
 fn adjust_para_to_relay_parent_slot(
 	relay_header: &RelayHeader,
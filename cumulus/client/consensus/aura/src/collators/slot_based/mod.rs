@@ -58,6 +58,15 @@
 //! | 12000             | 1      | 0, 6000, 12000, 18000  | 0, 0, 1, 1 |
 //! | 12000             | 3      | 0, 2000, 4000, 6000    | 0, 0, 0, 0 |
 //!
+//! # Block Budget
+//!
+//! Each block is built with a PoV size and a time budget. The PoV budget targets a percentage of
+//! the maximum PoV size and is reduced whenever recently built PoVs, measured with the compacted
+//! proof, exceeded that target. The time budget is capped by the time left until the next slot,
+//! which shrinks as more cores are assigned. When more blocks are built per relay chain slot than
+//! cores are assigned, both budgets are split between the blocks sharing a core. The decisions are
+//! reported as `parachain_slot_based_*` metrics.
+//!
 //! # Collator Task Details
 //!
 //! The collator task receives built blocks from the block builder task and performs two primary
@@ -80,11 +89,12 @@ use futures::FutureExt;
 use polkadot_primitives::{
 	CollatorPair, CoreIndex, Hash as RelayHash, Id as ParaId, ValidationCodeHash,
 };
+use prometheus_endpoint::Registry;
 use sc_client_api::{backend::AuxStore, BlockBackend, BlockOf, UsageProvider};
 use sc_consensus::BlockImport;
 use sc_network_types::PeerId;
 use sc_utils::mpsc::tracing_unbounded;
-use sp_api::ProvideRuntimeApi;
+use sp_api::{CallApiAt, ProvideRuntimeApi};
 use sp_application_crypto::AppPublic;
 use sp_blockchain::HeaderBackend;
 use sp_consensus_aura::AuraApi;
@@ -94,6 +104,7 @@ use sp_keystore::KeystorePtr;
 use sp_runtime::traits::{Block as BlockT, Member};
 use std::{path::PathBuf, sync::Arc, time::Duration};
 
+mod block_budget;
 mod block_builder_task;
 mod block_import;
 mod collation_task;
@@ -149,6 +160,8 @@ pub struct Params<Block, BI, CIDP, Client, Backend, RClient, CHP, Proposer, CS,
 	/// The maximum percentage of the maximum PoV size that the collator can use.
 	/// It will be removed once <https://github.com/paritytech/polkadot-sdk/issues/6020> is fixed.
 	pub max_pov_percentage: Option<u32>,
+	/// The prometheus registry used to report the block budget decisions of the collator.
+	pub prometheus_registry: Option<Registry>,
 }
 
 /// Run aura-based block building and collation task.
@@ -162,6 +175,7 @@ pub fn run<Block, P, BI, CIDP, Client, Backend, RClient, CHP, Proposer, CS, Spaw
 		+ HeaderBackend<Block>
 		+ BlockBackend<Block>
 		+ UsageProvider<Block>
+		+ CallApiAt<Block>
 		+ Send
 		+ Sync
 		+ 'static,
@@ -201,6 +215,7 @@ pub fn run<Block, P, BI, CIDP, Client, Backend, RClient, CHP, Proposer, CS, Spaw
 		export_pov,
 		relay_chain_slot_duration,
 		max_pov_percentage,
+		prometheus_registry,
 	} = params;
 
 	let (tx, rx) = tracing_unbounded("mpsc_builder_to_collator", 100);
@@ -234,6 +249,7 @@ pub fn run<Block, P, BI, CIDP, Client, Backend, RClient, CHP, Proposer, CS, Spaw
 		relay_chain_slot_duration,
 		slot_offset,
 		max_pov_percentage,
+		prometheus_registry,
 	};
 
 	let block_builder_fut =
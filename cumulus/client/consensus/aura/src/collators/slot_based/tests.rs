@@ -16,6 +16,7 @@
 // along with Cumulus. If not, see <https://www.gnu.org/licenses/>.
 
 use super::{
+	block_budget::{BlockBudgetController, BudgetInputs, PoVBudgetReason, TimeBudgetReason},
 	block_builder_task::{determine_core, offset_relay_parent_find_descendants},
 	relay_chain_data_cache::{RelayChainData, RelayChainDataCache},
 };
@@ -33,6 +34,7 @@ use sp_version::RuntimeVersion;
 use std::{
 	collections::{BTreeMap, HashMap, VecDeque},
 	pin::Pin,
+	time::Duration,
 };
 
 #[tokio::test]
@@ -413,6 +415,123 @@ async fn determine_core_no_last_claimed_core_selector() {
 	}
 }
 
+fn budget_inputs(number_of_cores: u16) -> BudgetInputs {
+	BudgetInputs {
+		max_pov_size: 10_000,
+		number_of_cores,
+		relay_parent_offset: 0,
+		para_slot_duration: Duration::from_secs(6),
+		relay_slot_duration: Duration::from_secs(6),
+		time_until_next_slot: None,
+	}
+}
+
+#[test]
+fn block_budget_targets_configured_percentage() {
+	let mut controller = BlockBudgetController::new(None, Duration::from_secs(2), None);
+	let budget = controller.budget(&budget_inputs(1));
+	assert_eq!(budget.pov_size, 8_500);
+	assert_eq!(budget.pov_reason, PoVBudgetReason::Target);
+
+	let mut controller = BlockBudgetController::new(Some(50), Duration::from_secs(2), None);
+	assert_eq!(controller.budget(&budget_inputs(1)).pov_size, 5_000);
+}
+
+#[test]
+fn block_budget_is_capped_by_next_slot() {
+	let mut controller = BlockBudgetController::new(None, Duration::from_secs(2), None);
+
+	let inputs =
+		BudgetInputs { time_until_next_slot: Some(Duration::from_secs(3)), ..budget_inputs(1) };
+	let budget = controller.budget(&inputs);
+	assert_eq!(budget.authoring_duration, Duration::from_secs(2));
+	assert_eq!(budget.time_reason, TimeBudgetReason::Configured);
+
+	let inputs =
+		BudgetInputs { time_until_next_slot: Some(Duration::from_millis(500)), ..budget_inputs(3) };
+	let budget = controller.budget(&inputs);
+	assert_eq!(budget.authoring_duration, Duration::from_millis(500));
+	assert_eq!(budget.time_reason, TimeBudgetReason::NextSlot);
+}
+
+#[test]
+fn block_budget_shrinks_on_overshoot_and_recovers() {
+	let mut controller = BlockBudgetController::new(None, Duration::from_secs(2), None);
+	let budget = controller.budget(&budget_inputs(1));
+
+	// The PoV came out 25% larger than the target.
+	controller.on_block_built(10_000, &budget, 10_625);
+	let budget = controller.budget(&budget_inputs(1));
+	assert_eq!(budget.pov_reason, PoVBudgetReason::Overshoot);
+	assert_eq!(budget.pov_size, 6_800);
+
+	// The reduced budget is kept while blocks don't fill it.
+	controller.on_block_built(10_000, &budget, 1_000);
+	let mut budget = controller.budget(&budget_inputs(1));
+	assert_eq!(budget.pov_reason, PoVBudgetReason::Reduced);
+	assert_eq!(budget.pov_size, 6_800);
+
+	// Full blocks that stay below the target let the budget grow back.
+	for _ in 0..4 {
+		let pov_size = budget.pov_size;
+		controller.on_block_built(10_000, &budget, pov_size);
+		let next = controller.budget(&budget_inputs(1));
+		assert_eq!(next.pov_reason, PoVBudgetReason::Recovering);
+		assert!(next.pov_size > budget.pov_size);
+		budget = next;
+	}
+	let budget = controller.budget(&budget_inputs(1));
+	assert_eq!(budget.pov_size, 8_500);
+	assert_eq!(budget.pov_reason, PoVBudgetReason::Target);
+}
+
+#[test]
+fn block_budget_window_follows_cores() {
+	let mut controller = BlockBudgetController::new(None, Duration::from_secs(2), None);
+	let budget = controller.budget(&budget_inputs(3));
+
+	// An overshoot followed by two blocks is still in the window of three cores.
+	controller.on_block_built(10_000, &budget, 10_625);
+	controller.on_block_built(10_000, &budget, 1_000);
+	controller.on_block_built(10_000, &budget, 1_000);
+	assert_eq!(controller.budget(&budget_inputs(3)).pov_reason, PoVBudgetReason::Overshoot);
+
+	// With a single core, only the last block is considered.
+	let mut controller = BlockBudgetController::new(None, Duration::from_secs(2), None);
+	let budget = controller.budget(&budget_inputs(1));
+	controller.on_block_built(10_000, &budget, 10_625);
+	controller.on_block_built(10_000, &budget, 1_000);
+	assert_eq!(controller.budget(&budget_inputs(1)).pov_reason, PoVBudgetReason::Target);
+}
+
+#[test]
+fn block_budget_is_shared_by_blocks_on_the_same_core() {
+	let mut controller = BlockBudgetController::new(None, Duration::from_millis(1_500), None);
+	let inputs = |number_of_cores| BudgetInputs {
+		para_slot_duration: Duration::from_secs(2),
+		..budget_inputs(number_of_cores)
+	};
+
+	// Three blocks per relay chain slot share a single core.
+	let budget = controller.budget(&inputs(1));
+	assert_eq!(budget.pov_size, 2_833);
+	assert_eq!(budget.authoring_duration, Duration::from_millis(500));
+	assert_eq!(budget.time_reason, TimeBudgetReason::SharedCores);
+
+	// With a core per block, every block gets the full budget.
+	let budget = controller.budget(&inputs(3));
+	assert_eq!(budget.pov_size, 8_500);
+	assert_eq!(budget.authoring_duration, Duration::from_millis(1_500));
+	assert_eq!(budget.time_reason, TimeBudgetReason::Configured);
+
+	// Overshooting the shared target reduces the budget as well.
+	let budget = controller.budget(&inputs(1));
+	controller.on_block_built(10_000, &budget, 3_541);
+	let budget = controller.budget(&inputs(1));
+	assert_eq!(budget.pov_reason, PoVBudgetReason::Overshoot);
+	assert!(budget.pov_size < 2_833);
+}
+
 #[derive(Clone)]
 struct TestRelayClient {
 	headers: HashMap<RelayHash, RelayHeader>,
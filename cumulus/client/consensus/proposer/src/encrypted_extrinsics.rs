@@ -0,0 +1,599 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus. If not, see <https://www.gnu.org/licenses/>.
+
+//! Inclusion of extrinsics that were submitted encrypted to the block author.
+//!
+//! Sensitive extrinsics are not submitted to the transaction pool, where they would be gossiped
+//! in plain. Instead they are submitted as statements to the statement store:
+//!
+//! * The data of the statement is the extrinsic, encrypted to the `ed25519` Aura key of the author
+//!   that should include it.
+//! * The only topic is [`ENCRYPTED_EXTRINSIC_TOPIC`].
+//!
+//! The statements are gossiped opaquely. Only the [`EncryptedExtrinsicsProposer`] of the author
+//! decrypts them, right before building a block. It applies the extrinsics before the
+//! transactions of the pool, followed by the extrinsic created with [`EncryptedExtrinsicsApi`]
+//! that lets the runtime verify the inclusion commitments of the extrinsics that were applied.
+//! A commitment binds the hash of the statement to the hash of the decrypted extrinsic, so the
+//! submitter can find the inclusion of its statement.
+//!
+//! The statement store decrypts with the `ed25519` keys of the `stmt` key type only, so the
+//! runtime has to use `ed25519` Aura keys and the Aura key of the author has to be inserted into
+//! the keystore under this key type as well. Statements can not be encrypted to `sr25519` keys.
+
+use crate::{propose_with_extrinsics, Error, ProposalOf, ProposerInterface};
+use async_trait::async_trait;
+use codec::{Decode, DecodeAll};
+use cumulus_primitives_core::{
+	relay_chain, EncryptedExtrinsicCommitment, EncryptedExtrinsicCommitments,
+	EncryptedExtrinsicsApi, ENCRYPTED_EXTRINSIC_TOPIC, MAX_ENCRYPTED_EXTRINSICS,
+	MAX_ENCRYPTED_EXTRINSIC_SIZE,
+};
+use cumulus_primitives_parachain_inherent::ParachainInherentData;
+use sc_basic_authorship::{ClosingExtrinsic, ProposerFactory};
+use sc_block_builder::BlockBuilderApi;
+use sc_transaction_pool_api::TransactionPool;
+use sp_api::{ApiExt, CallApiAt, ProvideRuntimeApi};
+use sp_blockchain::HeaderBackend;
+use sp_consensus::EnableProofRecording;
+use sp_core::crypto::key_types::AURA;
+use sp_inherents::InherentData;
+use sp_keystore::KeystorePtr;
+use sp_runtime::{
+	traits::{Block as BlockT, Header as HeaderT},
+	transaction_validity::{InvalidTransaction, TransactionSource, TransactionValidityError},
+	Digest,
+};
+use sp_statement_store::{Statement, StatementStore};
+use sp_transaction_pool::runtime_api::TaggedTransactionQueue;
+use std::{sync::Arc, time::Duration};
+
+const LOG_TARGET: &str = "encrypted-extrinsics";
+
+/// A [`ProposerInterface`] that includes the extrinsics that were submitted encrypted to the
+/// block author.
+///
+/// See the [module docs](self) for how the extrinsics are submitted. Without a statement store,
+/// only the transactions of the pool are included.
+pub struct EncryptedExtrinsicsProposer<A, C> {
+	inner: ProposerFactory<A, C, EnableProofRecording>,
+	client: Arc<C>,
+	keystore: KeystorePtr,
+	statement_store: Option<Arc<dyn StatementStore>>,
+}
+
+impl<A, C> EncryptedExtrinsicsProposer<A, C> {
+	/// Create a new instance that proposes blocks with the given `ProposerFactory`.
+	pub fn new(
+		inner: ProposerFactory<A, C, EnableProofRecording>,
+		client: Arc<C>,
+		keystore: KeystorePtr,
+		statement_store: Option<Arc<dyn StatementStore>>,
+	) -> Self {
+		if statement_store.is_some() && keystore.ed25519_public_keys(AURA).is_empty() {
+			tracing::warn!(
+				target: LOG_TARGET,
+				sr25519_keys = keystore.sr25519_public_keys(AURA).len(),
+				"No ed25519 Aura key in the keystore, encrypted extrinsics can only be decrypted \
+				with ed25519 keys and are not included until one is inserted",
+			);
+		}
+
+		Self { inner, client, keystore, statement_store }
+	}
+
+	/// The extrinsics to apply before the transactions of the pool.
+	///
+	/// These are the decrypted extrinsics, closed by the extrinsic that verifies the inclusion
+	/// commitments of those that were applied. Nothing is returned if the runtime doesn't support
+	/// encrypted extrinsics.
+	fn encrypted_extrinsics<Block>(
+		&self,
+		parent_hash: Block::Hash,
+	) -> (Vec<Block::Extrinsic>, Option<ClosingExtrinsic<Block>>)
+	where
+		Block: BlockT,
+		C: ProvideRuntimeApi<Block> + Send + Sync + 'static,
+		C::Api: TaggedTransactionQueue<Block> + EncryptedExtrinsicsApi<Block>,
+	{
+		let Some(statement_store) = &self.statement_store else { return (Vec::new(), None) };
+		let api = self.client.runtime_api();
+		if !api.has_api::<dyn EncryptedExtrinsicsApi<Block>>(parent_hash).unwrap_or(false) {
+			return (Vec::new(), None)
+		}
+
+		let (extrinsics, commitments) =
+			self.decrypted_extrinsics::<Block>(&**statement_store, parent_hash);
+		if commitments.is_empty() {
+			return (Vec::new(), None)
+		}
+
+		let client = self.client.clone();
+		let inclusion = move |applied: &[usize]| {
+			let commitments = EncryptedExtrinsicCommitments::truncate_from(
+				applied.iter().filter_map(|index| commitments.get(*index).cloned()).collect(),
+			);
+			if commitments.is_empty() {
+				return None
+			}
+
+			let count = commitments.len();
+			match client.runtime_api().encrypted_inclusion_extrinsic(parent_hash, commitments) {
+				Ok(inclusion) => {
+					tracing::debug!(target: LOG_TARGET, count, "Including encrypted extrinsics");
+					Some(inclusion)
+				},
+				Err(e) => {
+					tracing::warn!(
+						target: LOG_TARGET,
+						?e,
+						"Failed to create the inclusion commitment extrinsic",
+					);
+					None
+				},
+			}
+		};
+
+		(extrinsics, Some(Box::new(inclusion)))
+	}
+
+	/// Decrypt the extrinsics that were submitted to the keys of the author and are valid at
+	/// `parent_hash`, together with their inclusion commitments.
+	fn decrypted_extrinsics<Block>(
+		&self,
+		statement_store: &dyn StatementStore,
+		parent_hash: Block::Hash,
+	) -> (Vec<Block::Extrinsic>, EncryptedExtrinsicCommitments)
+	where
+		Block: BlockT,
+		C: ProvideRuntimeApi<Block>,
+		C::Api: TaggedTransactionQueue<Block>,
+	{
+		let mut extrinsics = Vec::new();
+		let mut commitments = EncryptedExtrinsicCommitments::default();
+
+		for key in self.keystore.ed25519_public_keys(AURA) {
+			let posted =
+				match statement_store.posted_clear_stmt(&[ENCRYPTED_EXTRINSIC_TOPIC], key.0) {
+					Ok(posted) => posted,
+					Err(e) => {
+						tracing::debug!(target: LOG_TARGET, ?e, "Failed to query statements");
+						continue
+					},
+				};
+
+			for entry in posted {
+				if extrinsics.len() >= MAX_ENCRYPTED_EXTRINSICS as usize {
+					break
+				}
+
+				let mut input = &entry[..];
+				let Ok(statement) = Statement::decode(&mut input) else { continue };
+				if let Some((extrinsic, commitment)) = self.decrypted_extrinsic::<Block>(
+					statement_store,
+					parent_hash,
+					&statement,
+					input,
+				) {
+					let duplicate = commitments
+						.iter()
+						.any(|included| included.extrinsic_hash == commitment.extrinsic_hash);
+					if !duplicate && commitments.try_push(commitment).is_ok() {
+						extrinsics.push(extrinsic);
+					}
+				}
+			}
+		}
+
+		(extrinsics, commitments)
+	}
+
+	/// Decode the decrypted `data` of `statement` and check it against the state at
+	/// `parent_hash`.
+	///
+	/// Statements that can never be included are removed from the statement store, they would
+	/// otherwise be decrypted again for every block.
+	fn decrypted_extrinsic<Block>(
+		&self,
+		statement_store: &dyn StatementStore,
+		parent_hash: Block::Hash,
+		statement: &Statement,
+		data: &[u8],
+	) -> Option<(Block::Extrinsic, EncryptedExtrinsicCommitment)>
+	where
+		Block: BlockT,
+		C: ProvideRuntimeApi<Block>,
+		C::Api: TaggedTransactionQueue<Block>,
+	{
+		let commitment = EncryptedExtrinsicCommitment {
+			statement_hash: relay_chain::Hash::from(statement.hash()),
+			extrinsic_hash: relay_chain::Hash::from(sp_core::blake2_256(data)),
+		};
+		let extrinsic = match Block::Extrinsic::decode_all(&mut &data[..]) {
+			Ok(extrinsic) if data.len() <= MAX_ENCRYPTED_EXTRINSIC_SIZE as usize => extrinsic,
+			_ => {
+				tracing::debug!(target: LOG_TARGET, ?commitment, "Malformed encrypted extrinsic");
+				let _ = statement_store.remove(&statement.hash());
+				return None
+			},
+		};
+
+		let validity = self.client.runtime_api().validate_transaction(
+			parent_hash,
+			TransactionSource::Local,
+			extrinsic.clone(),
+			parent_hash,
+		);
+		match validity {
+			Ok(Ok(_)) => Some((extrinsic, commitment)),
+			// Depends on another extrinsic of the sender, try again in a later block.
+			Ok(Err(TransactionValidityError::Invalid(InvalidTransaction::Future))) => None,
+			Ok(Err(e)) => {
+				tracing::debug!(target: LOG_TARGET, ?commitment, ?e, "Invalid encrypted extrinsic");
+				let _ = statement_store.remove(&statement.hash());
+				None
+			},
+			Err(e) => {
+				tracing::debug!(target: LOG_TARGET, ?e, "Failed to validate encrypted extrinsic");
+				None
+			},
+		}
+	}
+}
+
+#[async_trait]
+impl<Block, A, C> ProposerInterface<Block> for EncryptedExtrinsicsProposer<A, C>
+where
+	A: TransactionPool<Block = Block> + 'static,
+	C: HeaderBackend<Block> + ProvideRuntimeApi<Block> + CallApiAt<Block> + Send + Sync + 'static,
+	C::Api: ApiExt<Block>
+		+ BlockBuilderApi<Block>
+		+ TaggedTransactionQueue<Block>
+		+ EncryptedExtrinsicsApi<Block>,
+	Block: BlockT,
+{
+	async fn propose(
+		&mut self,
+		parent_header: &Block::Header,
+		paras_inherent_data: &ParachainInherentData,
+		other_inherent_data: InherentData,
+		inherent_digests: Digest,
+		max_duration: Duration,
+		block_size_limit: Option<usize>,
+	) -> Result<Option<ProposalOf<Block>>, Error> {
+		let (extrinsics, closing_extrinsic) =
+			self.encrypted_extrinsics::<Block>(parent_header.hash());
+
+		propose_with_extrinsics(
+			&mut self.inner,
+			parent_header,
+			paras_inherent_data,
+			other_inherent_data,
+			inherent_digests,
+			max_duration,
+			block_size_limit,
+			extrinsics,
+			closing_extrinsic,
+		)
+		.await
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use codec::Encode;
+	use cumulus_primitives_core::PersistedValidationData;
+	use cumulus_test_client::{
+		generate_extrinsic_with_pair,
+		runtime::{SystemCall, SLOT_DURATION},
+		transfer, Client, DefaultTestClientBuilderExt, TestClientBuilder, TestClientBuilderExt,
+	};
+	use cumulus_test_relay_sproof_builder::RelayStateSproofBuilder;
+	use sc_transaction_pool::BasicPool;
+	use sp_consensus_aura::{Slot, AURA_ENGINE_ID};
+	use sp_core::{ed25519, Pair};
+	use sp_keyring::Sr25519Keyring::{Alice, Bob};
+	use sp_keystore::{testing::MemoryKeystore, Keystore};
+	use sp_runtime::DigestItem;
+	use sp_statement_store::{Hash, Result, StatementSource, SubmitResult, Topic};
+	use std::sync::Mutex;
+
+	type Block = cumulus_test_client::runtime::Block;
+	type Extrinsic = <Block as BlockT>::Extrinsic;
+
+	/// The relay chain slot of the built blocks.
+	const RELAY_SLOT: u64 = 1_000;
+
+	/// Returns the decrypted data of the statements that were posted to it.
+	#[derive(Default)]
+	struct TestStatementStore {
+		posted: Mutex<Vec<(Statement, Vec<u8>)>>,
+	}
+
+	impl TestStatementStore {
+		/// Post a statement with the decrypted `data` to `dest`.
+		fn post(&self, dest: ed25519::Public, data: Vec<u8>) -> Statement {
+			let mut statement = Statement::new();
+			statement.set_topic(0, ENCRYPTED_EXTRINSIC_TOPIC);
+			statement.set_decryption_key(dest.0);
+			statement.set_plain_data(sp_core::blake2_256(&data).to_vec());
+			self.posted.lock().unwrap().push((statement.clone(), data));
+			statement
+		}
+
+		fn contains(&self, statement: &Statement) -> bool {
+			self.posted.lock().unwrap().iter().any(|(posted, _)| posted == statement)
+		}
+	}
+
+	impl StatementStore for TestStatementStore {
+		fn statements(&self) -> Result<Vec<(Hash, Statement)>> {
+			unimplemented!()
+		}
+
+		fn take_recent_statements(&self) -> Result<Vec<(Hash, Statement)>> {
+			unimplemented!()
+		}
+
+		fn statement(&self, _: &Hash) -> Result<Option<Statement>> {
+			unimplemented!()
+		}
+
+		fn has_statement(&self, _: &Hash) -> bool {
+			unimplemented!()
+		}
+
+		fn broadcasts(&self, _: &[Topic]) -> Result<Vec<Vec<u8>>> {
+			unimplemented!()
+		}
+
+		fn posted(&self, _: &[Topic], _: [u8; 32]) -> Result<Vec<Vec<u8>>> {
+			unimplemented!()
+		}
+
+		fn posted_clear(&self, _: &[Topic], _: [u8; 32]) -> Result<Vec<Vec<u8>>> {
+			unimplemented!()
+		}
+
+		fn broadcasts_stmt(&self, _: &[Topic]) -> Result<Vec<Vec<u8>>> {
+			unimplemented!()
+		}
+
+		fn posted_stmt(&self, _: &[Topic], _: [u8; 32]) -> Result<Vec<Vec<u8>>> {
+			unimplemented!()
+		}
+
+		fn posted_clear_stmt(
+			&self,
+			match_all_topics: &[Topic],
+			dest: [u8; 32],
+		) -> Result<Vec<Vec<u8>>> {
+			let posted = self.posted.lock().unwrap();
+			let matches = |statement: &Statement| {
+				statement.decryption_key() == Some(dest) &&
+					match_all_topics
+						.iter()
+						.enumerate()
+						.all(|(index, topic)| statement.topic(index) == Some(*topic))
+			};
+
+			Ok(posted
+				.iter()
+				.filter(|(statement, _)| matches(statement))
+				.map(|(statement, data)| {
+					let mut entry = statement.encode();
+					entry.extend_from_slice(data);
+					entry
+				})
+				.collect())
+		}
+
+		fn submit(&self, _: Statement, _: StatementSource) -> SubmitResult {
+			unimplemented!()
+		}
+
+		fn remove(&self, hash: &Hash) -> Result<()> {
+			self.posted.lock().unwrap().retain(|(statement, _)| statement.hash() != *hash);
+			Ok(())
+		}
+
+		fn remove_by(&self, _: [u8; 32]) -> Result<()> {
+			unimplemented!()
+		}
+	}
+
+	struct TestProposer {
+		client: Arc<Client>,
+		statement_store: Arc<TestStatementStore>,
+		author: Option<ed25519::Public>,
+		proposer: EncryptedExtrinsicsProposer<
+			BasicPool<sc_transaction_pool::FullChainApi<Client, Block>, Block>,
+			Client,
+		>,
+	}
+
+	impl TestProposer {
+		/// A proposer whose keystore holds the ed25519 Aura key of Alice, if `ed25519`.
+		fn new(ed25519: bool) -> Self {
+			let client = Arc::new(TestClientBuilder::new().build());
+			let spawner = sp_core::testing::TaskExecutor::new();
+			let pool = Arc::from(BasicPool::new_full(
+				Default::default(),
+				true.into(),
+				None,
+				spawner.clone(),
+				client.clone(),
+			));
+
+			let keystore = MemoryKeystore::new();
+			let author = if ed25519 {
+				Some(keystore.ed25519_generate_new(AURA, Some("//Alice")).unwrap())
+			} else {
+				keystore.sr25519_generate_new(AURA, Some("//Alice")).unwrap();
+				None
+			};
+
+			let statement_store = Arc::new(TestStatementStore::default());
+			let proposer = EncryptedExtrinsicsProposer::new(
+				ProposerFactory::with_proof_recording(spawner, client.clone(), pool, None, None),
+				client.clone(),
+				Arc::new(keystore),
+				Some(statement_store.clone() as Arc<dyn StatementStore>),
+			);
+
+			Self { client, statement_store, author, proposer }
+		}
+
+		/// Post `extrinsic` encrypted to the author, or to Alice if the author has no ed25519 key.
+		fn post(&self, extrinsic: Vec<u8>) -> Statement {
+			let dest = self
+				.author
+				.unwrap_or_else(|| ed25519::Pair::from_string("//Alice", None).unwrap().public());
+			self.statement_store.post(dest, extrinsic)
+		}
+
+		/// Propose a block on top of genesis and return its extrinsics.
+		fn propose(&mut self) -> Vec<Extrinsic> {
+			let parent = self.client.header(self.client.info().genesis_hash).unwrap().unwrap();
+
+			let mut sproof = RelayStateSproofBuilder::default();
+			sproof.current_slot = RELAY_SLOT.into();
+			let (relay_parent_storage_root, relay_chain_state) = sproof.into_state_root_and_proof();
+			let paras_inherent_data = ParachainInherentData {
+				validation_data: PersistedValidationData {
+					relay_parent_storage_root,
+					..Default::default()
+				},
+				relay_chain_state,
+				downward_messages: Default::default(),
+				horizontal_messages: Default::default(),
+				relay_parent_descendants: Default::default(),
+				collator_peer_id: None,
+			};
+
+			let timestamp = RELAY_SLOT * 6_000;
+			let mut other_inherent_data = InherentData::new();
+			other_inherent_data
+				.put_data(sp_timestamp::INHERENT_IDENTIFIER, &timestamp)
+				.unwrap();
+			let slot = Slot::from(timestamp / SLOT_DURATION);
+			let inherent_digests =
+				Digest { logs: vec![DigestItem::PreRuntime(AURA_ENGINE_ID, slot.encode())] };
+
+			let proposal = futures::executor::block_on(self.proposer.propose(
+				&parent,
+				&paras_inherent_data,
+				other_inherent_data,
+				inherent_digests,
+				Duration::from_secs(2),
+				None,
+			))
+			.unwrap()
+			.unwrap();
+
+			proposal.block.extrinsics().to_vec()
+		}
+
+		/// The extrinsic that verifies the inclusion of `included`.
+		fn inclusion(&self, included: &[(&Statement, &Extrinsic)]) -> Extrinsic {
+			let commitments = included
+				.iter()
+				.map(|(statement, extrinsic)| EncryptedExtrinsicCommitment {
+					statement_hash: statement.hash().into(),
+					extrinsic_hash: sp_core::blake2_256(&extrinsic.encode()).into(),
+				})
+				.collect::<Vec<_>>();
+			self.client
+				.runtime_api()
+				.encrypted_inclusion_extrinsic(
+					self.client.info().genesis_hash,
+					EncryptedExtrinsicCommitments::truncate_from(commitments),
+				)
+				.unwrap()
+		}
+	}
+	#[test]
+	fn decrypted_extrinsics_are_included_with_their_commitments() {
+		let mut proposer = TestProposer::new(true);
+		let inherents = proposer.propose();
+
+		let first = transfer(&proposer.client, Alice, Bob, 1_000);
+		let second = transfer(&proposer.client, Bob, Alice, 1_000);
+		let first_statement = proposer.post(first.encode());
+		let second_statement = proposer.post(second.encode());
+		// The same extrinsic submitted twice is only included once.
+		proposer.post(first.encode());
+
+		let inclusion =
+			proposer.inclusion(&[(&first_statement, &first), (&second_statement, &second)]);
+		assert_eq!(proposer.propose(), [inherents, vec![first, second, inclusion]].concat());
+	}
+
+	#[test]
+	fn only_applied_extrinsics_are_committed() {
+		let mut proposer = TestProposer::new(true);
+		let inherents = proposer.propose();
+
+		let first = transfer(&proposer.client, Alice, Bob, 1_000);
+		// Reuses the nonce of `first` and fails to apply after it.
+		let conflicting = transfer(&proposer.client, Alice, Bob, 2_000);
+		let third = transfer(&proposer.client, Bob, Alice, 1_000);
+		let first_statement = proposer.post(first.encode());
+		proposer.post(conflicting.encode());
+		let third_statement = proposer.post(third.encode());
+
+		let inclusion =
+			proposer.inclusion(&[(&first_statement, &first), (&third_statement, &third)]);
+		assert_eq!(proposer.propose(), [inherents, vec![first, third, inclusion]].concat());
+	}
+
+	#[test]
+	fn undecodable_and_invalid_extrinsics_are_removed() {
+		let mut proposer = TestProposer::new(true);
+		let inherents = proposer.propose();
+
+		let remark = |pair: sp_core::sr25519::Pair, remark: Vec<u8>, nonce: u32| {
+			let call = SystemCall::remark { remark };
+			generate_extrinsic_with_pair(&proposer.client, pair, call, Some(nonce)).encode()
+		};
+		let malformed = proposer.post(b"not an extrinsic".to_vec());
+		let oversized =
+			proposer.post(remark(Alice.pair(), vec![0; MAX_ENCRYPTED_EXTRINSIC_SIZE as usize], 0));
+		let unfunded =
+			proposer.post(remark(sp_core::sr25519::Pair::from_seed(&[7; 32]), vec![], 0));
+		let future = proposer.post(remark(Bob.pair(), Vec::new(), 1));
+
+		assert_eq!(proposer.propose(), inherents);
+		assert!(!proposer.statement_store.contains(&malformed));
+		assert!(!proposer.statement_store.contains(&oversized));
+		assert!(!proposer.statement_store.contains(&unfunded));
+		// Extrinsics that may become valid later are kept.
+		assert!(proposer.statement_store.contains(&future));
+	}
+
+	#[test]
+	fn extrinsics_are_not_decrypted_without_ed25519_aura_key() {
+		let mut proposer = TestProposer::new(false);
+		let inherents = proposer.propose();
+
+		let statement = proposer.post(transfer(&proposer.client, Alice, Bob, 1_000).encode());
+
+		assert_eq!(proposer.propose(), inherents);
+		assert!(proposer.statement_store.contains(&statement));
+	}
+}
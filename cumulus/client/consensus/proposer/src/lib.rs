@@ -19,10 +19,13 @@
 //! for creating new parachain blocks.
 //!
 //! This utility is designed to be composed within any collator consensus algorithm.
+//!
+//! [`EncryptedExtrinsicsProposer`] additionally includes the extrinsics that were submitted
+//! encrypted to the block author, see [`encrypted_extrinsics`].
 
 use async_trait::async_trait;
 use cumulus_primitives_parachain_inherent::ParachainInherentData;
-use sc_basic_authorship::{ProposeArgs, ProposerFactory};
+use sc_basic_authorship::{ClosingExtrinsic, ProposeArgs, ProposerFactory};
 use sc_block_builder::BlockBuilderApi;
 use sc_transaction_pool_api::TransactionPool;
 use sp_api::{ApiExt, CallApiAt, ProvideRuntimeApi};
@@ -33,6 +36,10 @@ use sp_runtime::{traits::Block as BlockT, Digest};
 use sp_state_machine::StorageProof;
 use std::{fmt::Debug, time::Duration};
 
+pub mod encrypted_extrinsics;
+
+pub use encrypted_extrinsics::EncryptedExtrinsicsProposer;
+
 /// Errors that can occur when proposing a parachain block.
 #[derive(thiserror::Error, Debug)]
 #[error(transparent)]
@@ -100,27 +107,62 @@ where
 		max_duration: Duration,
 		block_size_limit: Option<usize>,
 	) -> Result<Option<Proposal<Block, StorageProof>>, Error> {
-		let proposer = self
-			.init(parent_header)
-			.await
-			.map_err(|e| Error::proposer_creation(anyhow::Error::new(e)))?;
-
-		let mut inherent_data = other_inherent_data;
-		paras_inherent_data
-			.provide_inherent_data(&mut inherent_data)
-			.await
-			.map_err(|e| Error::proposing(anyhow::Error::new(e)))?;
-
-		proposer
-			.propose_block(ProposeArgs {
-				inherent_data,
-				inherent_digests,
-				max_duration,
-				block_size_limit,
-				ignored_nodes_by_proof_recording: None,
-			})
-			.await
-			.map(Some)
-			.map_err(|e| Error::proposing(anyhow::Error::new(e)).into())
+		propose_with_extrinsics(
+			self,
+			parent_header,
+			paras_inherent_data,
+			other_inherent_data,
+			inherent_digests,
+			max_duration,
+			block_size_limit,
+			Vec::new(),
+			None,
+		)
+		.await
 	}
 }
+
+/// Propose a block with the given `ProposerFactory` that applies `extrinsics`, followed by
+/// their `closing_extrinsic`, before the transactions of the pool.
+async fn propose_with_extrinsics<Block, A, C>(
+	factory: &mut ProposerFactory<A, C, EnableProofRecording>,
+	parent_header: &Block::Header,
+	paras_inherent_data: &ParachainInherentData,
+	other_inherent_data: InherentData,
+	inherent_digests: Digest,
+	max_duration: Duration,
+	block_size_limit: Option<usize>,
+	extrinsics: Vec<Block::Extrinsic>,
+	closing_extrinsic: Option<ClosingExtrinsic<Block>>,
+) -> Result<Option<Proposal<Block, StorageProof>>, Error>
+where
+	A: TransactionPool<Block = Block> + 'static,
+	C: HeaderBackend<Block> + ProvideRuntimeApi<Block> + CallApiAt<Block> + Send + Sync + 'static,
+	C::Api: ApiExt<Block> + BlockBuilderApi<Block>,
+	Block: sp_runtime::traits::Block,
+{
+	let proposer = factory
+		.init(parent_header)
+		.await
+		.map_err(|e| Error::proposer_creation(anyhow::Error::new(e)))?;
+
+	let mut inherent_data = other_inherent_data;
+	paras_inherent_data
+		.provide_inherent_data(&mut inherent_data)
+		.await
+		.map_err(|e| Error::proposing(anyhow::Error::new(e)))?;
+
+	proposer
+		.propose_block(ProposeArgs {
+			inherent_data,
+			inherent_digests,
+			max_duration,
+			block_size_limit,
+			ignored_nodes_by_proof_recording: None,
+			extrinsics,
+			closing_extrinsic,
+		})
+		.await
+		.map(Some)
+		.map_err(|e| Error::proposing(anyhow::Error::new(e)).into())
+}
@@ -0,0 +1,206 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus. If not, see <https://www.gnu.org/licenses/>.
+
+//! A handle to inspect a running [`PoVRecovery`](crate::PoVRecovery) and to force recoveries.
+
+use polkadot_primitives::Hash as RelayHash;
+use sp_runtime::{
+	traits::{Block as BlockT, NumberFor},
+	SaturatedConversion,
+};
+
+use futures::{
+	channel::{mpsc, oneshot},
+	SinkExt,
+};
+use serde::{Deserialize, Serialize};
+
+use std::{
+	collections::HashMap,
+	sync::{Arc, Mutex},
+};
+
+/// Size of the channel used to send forced recoveries.
+const FORCED_RECOVERY_CHAN_SIZE: usize = 8;
+
+/// The candidates to force the recovery of.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RecoveryTarget {
+	/// The candidate with the given candidate hash.
+	CandidateHash(RelayHash),
+	/// All candidates built on top of the given relay parent.
+	RelayParent(RelayHash),
+}
+
+/// Information about a candidate that is or was being recovered.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecoveryInfo<Hash> {
+	/// Hash of the parachain block.
+	pub block_hash: Hash,
+	/// Number of the parachain block.
+	pub block_number: u64,
+	/// Hash of the candidate.
+	pub candidate_hash: RelayHash,
+	/// Relay parent of the candidate.
+	pub relay_parent: RelayHash,
+	/// Number of recovery requests issued for the candidate.
+	pub attempts: u32,
+	/// Whether a recovery request is currently in flight.
+	pub in_progress: bool,
+}
+
+/// A request to force the recovery of the candidates matching `target`.
+pub(crate) struct ForcedRecovery<Block: BlockT> {
+	pub target: RecoveryTarget,
+	/// Receives the hashes of the blocks whose recovery was started.
+	pub response: oneshot::Sender<Vec<Block::Hash>>,
+}
+
+/// The recoveries that are pending or failed.
+pub(crate) struct RecoveryStatus<Block: BlockT> {
+	pending: HashMap<Block::Hash, RecoveryInfo<Block::Hash>>,
+	failed: HashMap<Block::Hash, RecoveryInfo<Block::Hash>>,
+}
+
+impl<Block: BlockT> Default for RecoveryStatus<Block> {
+	fn default() -> Self {
+		Self { pending: HashMap::new(), failed: HashMap::new() }
+	}
+}
+
+impl<Block: BlockT> RecoveryStatus<Block> {
+	/// The recovery of `block_hash` was queued.
+	pub fn queued(
+		&mut self,
+		block_hash: Block::Hash,
+		block_number: NumberFor<Block>,
+		candidate_hash: RelayHash,
+		relay_parent: RelayHash,
+	) {
+		let attempts = self.failed.remove(&block_hash).map_or(0, |info| info.attempts);
+		let info = self.pending.entry(block_hash).or_insert_with(|| RecoveryInfo {
+			block_hash,
+			block_number: block_number.saturated_into(),
+			candidate_hash,
+			relay_parent,
+			attempts,
+			in_progress: false,
+		});
+		info.in_progress = false;
+	}
+
+	/// A recovery request for `block_hash` was issued.
+	pub fn started(&mut self, block_hash: &Block::Hash) {
+		if let Some(info) = self.pending.get_mut(block_hash) {
+			info.attempts = info.attempts.saturating_add(1);
+			info.in_progress = true;
+		}
+	}
+
+	/// The recovery of `block_hash` succeeded, or is not required anymore as the block was
+	/// imported.
+	pub fn done(&mut self, block_hash: &Block::Hash) {
+		self.pending.remove(block_hash);
+		self.failed.remove(block_hash);
+	}
+
+	/// The recovery of `block_hash` failed and will not be retried.
+	pub fn failed(&mut self, block_hash: &Block::Hash) {
+		if let Some(mut info) = self.pending.remove(block_hash) {
+			info.in_progress = false;
+			self.failed.insert(*block_hash, info);
+		}
+	}
+
+	/// Forget about all blocks up to `block_number`, as they are finalized.
+	pub fn finalized(&mut self, block_number: NumberFor<Block>) {
+		let block_number: u64 = block_number.saturated_into();
+		self.pending.retain(|_, info| info.block_number > block_number);
+		self.failed.retain(|_, info| info.block_number > block_number);
+	}
+}
+
+/// A handle to inspect a running [`PoVRecovery`](crate::PoVRecovery) and to force the recovery of
+/// candidates.
+///
+/// A handle can be attached to a single [`PoVRecovery`](crate::PoVRecovery).
+pub struct PoVRecoveryHandle<Block: BlockT> {
+	pub(crate) status: Arc<Mutex<RecoveryStatus<Block>>>,
+	forced_tx: mpsc::Sender<ForcedRecovery<Block>>,
+	forced_rx: Arc<Mutex<Option<mpsc::Receiver<ForcedRecovery<Block>>>>>,
+}
+
+impl<Block: BlockT> Clone for PoVRecoveryHandle<Block> {
+	fn clone(&self) -> Self {
+		Self {
+			status: self.status.clone(),
+			forced_tx: self.forced_tx.clone(),
+			forced_rx: self.forced_rx.clone(),
+		}
+	}
+}
+
+impl<Block: BlockT> Default for PoVRecoveryHandle<Block> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl<Block: BlockT> PoVRecoveryHandle<Block> {
+	/// Create a new handle.
+	pub fn new() -> Self {
+		let (forced_tx, forced_rx) = mpsc::channel(FORCED_RECOVERY_CHAN_SIZE);
+		Self {
+			status: Default::default(),
+			forced_tx,
+			forced_rx: Arc::new(Mutex::new(Some(forced_rx))),
+		}
+	}
+
+	/// Take the receiving side of the forced recoveries, which is only possible once.
+	pub(crate) fn take_forced_recoveries(&self) -> Option<mpsc::Receiver<ForcedRecovery<Block>>> {
+		self.forced_rx.lock().expect("poisoned lock").take()
+	}
+
+	/// The candidates that are waiting to be recovered or are currently being recovered.
+	pub fn pending(&self) -> Vec<RecoveryInfo<Block::Hash>> {
+		self.status.lock().expect("poisoned lock").pending.values().cloned().collect()
+	}
+
+	/// The candidates whose recovery failed and that were not imported since.
+	pub fn failed(&self) -> Vec<RecoveryInfo<Block::Hash>> {
+		self.status.lock().expect("poisoned lock").failed.values().cloned().collect()
+	}
+
+	/// Immediately recover the candidates matching `target` whose blocks are unknown, even if
+	/// their recovery failed before.
+	///
+	/// Returns the hashes of the blocks whose recovery was started, or `None` if the PoV recovery
+	/// is not running.
+	pub async fn force_recovery(&self, target: RecoveryTarget) -> Option<Vec<Block::Hash>> {
+		if self.forced_rx.lock().expect("poisoned lock").is_some() {
+			// Not attached to a PoV recovery, nobody would answer.
+			return None
+		}
+
+		let (response, rx) = oneshot::channel();
+		self.forced_tx.clone().send(ForcedRecovery { target, response }).await.ok()?;
+		rx.await.ok()
+	}
+}
@@ -46,6 +46,9 @@
 //!
 //! If we need to recover multiple PoV blocks (which should hopefully not happen in real life), we
 //! make sure that the blocks are imported in the correct order.
+//!
+//! The pending and failed recoveries can be inspected through a [`PoVRecoveryHandle`], which can
+//! also force the recovery of a candidate. The handle is exposed over RPC by [`rpc`].
 
 use sc_client_api::{BlockBackend, BlockchainEvents, UsageProvider};
 use sc_consensus::import_queue::{ImportQueueService, IncomingBlock};
@@ -59,6 +62,7 @@ use polkadot_primitives::{
 	CandidateReceiptV2 as CandidateReceipt,
 	CommittedCandidateReceiptV2 as CommittedCandidateReceipt, Id as ParaId, SessionIndex,
 };
+use prometheus_endpoint::{PrometheusError, Registry};
 
 use cumulus_primitives_core::ParachainBlockData;
 use cumulus_relay_chain_interface::RelayChainInterface;
@@ -75,7 +79,7 @@ use std::{
 	collections::{HashMap, HashSet, VecDeque},
 	pin::Pin,
 	sync::Arc,
-	time::Duration,
+	time::{Duration, Instant},
 };
 
 #[cfg(test)]
@@ -84,6 +88,15 @@ mod tests;
 mod active_candidate_recovery;
 use active_candidate_recovery::ActiveCandidateRecovery;
 
+mod handle;
+use handle::ForcedRecovery;
+pub use handle::{PoVRecoveryHandle, RecoveryInfo, RecoveryTarget};
+
+mod metrics;
+use metrics::PoVRecoveryMetrics;
+
+pub mod rpc;
+
 const LOG_TARGET: &str = "cumulus-pov-recovery";
 
 /// Test-friendly wrapper trait for the overseer handle.
@@ -194,6 +207,18 @@ impl<Block: BlockT> RecoveryQueue<Block> {
 		);
 	}
 
+	/// Add hash of a block that should go to the front of the recovery queue.
+	/// A new recovery is signaled right away.
+	pub fn push_recovery_now(&mut self, hash: Block::Hash) {
+		tracing::debug!(
+			target: LOG_TARGET,
+			block_hash = ?hash,
+			"Adding block to the front of the queue",
+		);
+		self.recovery_queue.push_front(hash);
+		self.signaling_queue.push(async {}.boxed());
+	}
+
 	/// Get the next hash for block recovery.
 	pub async fn next_recovery(&mut self) -> Block::Hash {
 		loop {
@@ -235,7 +260,16 @@ pub struct PoVRecovery<Block: BlockT, PC, RC> {
 	recovery_chan_rx: Receiver<RecoveryRequest<Block>>,
 	/// Blocks that we are retrying currently
 	candidates_in_retry: HashSet<Block::Hash>,
+	/// Recovered candidates whose blocks are in `waiting_for_parent`.
+	recovered_waiting_for_parent: HashSet<Block::Hash>,
 	parachain_sync_service: Arc<dyn SyncOracle + Sync + Send>,
+	/// Start of the recovery requests that are in flight.
+	recoveries_in_flight: HashMap<Block::Hash, Instant>,
+	/// Handle that exposes the pending and failed recoveries.
+	handle: PoVRecoveryHandle<Block>,
+	/// Forced recovery requests sent through the `handle`.
+	forced_recoveries: Option<Receiver<ForcedRecovery<Block>>>,
+	metrics: Option<PoVRecoveryMetrics>,
 }
 
 impl<Block: BlockT, PC, RCInterface> PoVRecovery<Block, PC, RCInterface>
@@ -264,9 +298,34 @@ where
 			relay_chain_interface,
 			para_id,
 			candidates_in_retry: HashSet::new(),
+			recovered_waiting_for_parent: HashSet::new(),
 			recovery_chan_rx,
 			parachain_sync_service,
+			recoveries_in_flight: HashMap::new(),
+			handle: PoVRecoveryHandle::new(),
+			forced_recoveries: None,
+			metrics: None,
+		}
+	}
+
+	/// Attach the given `handle`, which can be used to inspect the recoveries and to force the
+	/// recovery of candidates.
+	pub fn with_handle(mut self, handle: PoVRecoveryHandle<Block>) -> Self {
+		self.forced_recoveries = handle.take_forced_recoveries();
+		if self.forced_recoveries.is_none() {
+			tracing::warn!(
+				target: LOG_TARGET,
+				"PoV recovery handle is already attached, forced recoveries are not supported.",
+			);
 		}
+		self.handle = handle;
+		self
+	}
+
+	/// Register the PoV recovery metrics in the given `registry`.
+	pub fn with_metrics(mut self, registry: &Registry) -> Result<Self, PrometheusError> {
+		self.metrics = Some(PoVRecoveryMetrics::register(registry)?);
+		Ok(self)
 	}
 
 	/// Handle a new pending candidate.
@@ -325,19 +384,106 @@ where
 	/// Handle a finalized block with the given `block_number`.
 	fn handle_block_finalized(&mut self, block_number: NumberFor<Block>) {
 		self.candidates.retain(|_, pc| pc.block_number > block_number);
+		self.handle.status.lock().expect("poisoned lock").finalized(block_number);
 	}
 
 	/// Recover the candidate for the given `block_hash`.
 	async fn recover_candidate(&mut self, block_hash: Block::Hash) {
+		if self.recoveries_in_flight.contains_key(&block_hash) {
+			tracing::debug!(target: LOG_TARGET, ?block_hash, "Recovery already in flight");
+			return
+		}
+
 		match self.candidates.get(&block_hash) {
 			Some(candidate) if candidate.waiting_recovery => {
 				tracing::debug!(target: LOG_TARGET, ?block_hash, "Issuing recovery request");
+				self.recoveries_in_flight.insert(block_hash, Instant::now());
+				self.handle.status.lock().expect("poisoned lock").started(&block_hash);
+				if let Some(metrics) = &self.metrics {
+					metrics.on_attempt();
+				}
 				self.active_candidate_recovery.recover_candidate(block_hash, candidate).await;
 			},
 			_ => (),
 		}
 	}
 
+	/// Queue the recovery of the candidate for `block_hash` and track it as pending.
+	fn queue_recovery(&mut self, block_hash: Block::Hash, now: bool) {
+		if let Some(candidate) = self.candidates.get(&block_hash) {
+			self.handle.status.lock().expect("poisoned lock").queued(
+				block_hash,
+				candidate.block_number,
+				candidate.receipt.hash().0,
+				candidate.receipt.descriptor.relay_parent(),
+			);
+		}
+
+		if now {
+			self.candidate_recovery_queue.push_recovery_now(block_hash);
+		} else {
+			self.candidate_recovery_queue.push_recovery(block_hash);
+		}
+	}
+
+	/// The recovery of the candidate for `block_hash` failed and will not be retried.
+	fn recovery_failed(&mut self, block_hash: Block::Hash) {
+		self.handle.status.lock().expect("poisoned lock").failed(&block_hash);
+		if let Some(metrics) = &self.metrics {
+			metrics.on_failure();
+		}
+		self.reset_candidate(block_hash);
+	}
+
+	/// Immediately recover the candidates matching `target` whose blocks are unknown.
+	///
+	/// Returns the hashes of the blocks whose recovery was started.
+	fn force_recovery(&mut self, target: RecoveryTarget) -> Vec<Block::Hash> {
+		let mut to_recover = self
+			.candidates
+			.iter()
+			.filter(|(_, candidate)| match target {
+				RecoveryTarget::CandidateHash(hash) => candidate.receipt.hash().0 == hash,
+				RecoveryTarget::RelayParent(hash) =>
+					candidate.receipt.descriptor.relay_parent() == hash,
+			})
+			.map(|(hash, candidate)| (candidate.block_number, *hash))
+			.collect::<Vec<_>>();
+		// Recover parents before their children.
+		to_recover.sort();
+
+		let mut recovering = Vec::new();
+		for (_, hash) in to_recover {
+			match self.parachain_client.block_status(hash) {
+				Ok(BlockStatus::Unknown) => (),
+				Ok(_) => continue,
+				Err(error) => {
+					tracing::debug!(
+						target: LOG_TARGET,
+						block_hash = ?hash,
+						?error,
+						"Error while checking block status",
+					);
+					continue
+				},
+			}
+
+			tracing::info!(target: LOG_TARGET, block_hash = ?hash, "Forcing recovery of block");
+			self.candidates_in_retry.remove(&hash);
+			if let Some(candidate) = self.candidates.get_mut(&hash) {
+				candidate.waiting_recovery = true;
+			}
+			recovering.push(hash);
+		}
+
+		// The queue is popped from the front, so push the children first.
+		for hash in recovering.iter().rev() {
+			self.queue_recovery(*hash, true);
+		}
+
+		recovering
+	}
+
 	/// Clear `waiting_for_parent` and `waiting_recovery` for the candidate with `hash`.
 	/// Also clears children blocks waiting for this parent.
 	fn reset_candidate(&mut self, hash: Block::Hash) {
@@ -345,7 +491,16 @@ where
 
 		while let Some(delete) = blocks_to_delete.pop() {
 			if let Some(children) = self.waiting_for_parent.remove(&delete) {
-				blocks_to_delete.extend(children.iter().map(BlockT::hash));
+				for child in children.iter().map(BlockT::hash) {
+					// The child was recovered, but is dropped without being imported.
+					if self.recovered_waiting_for_parent.remove(&child) {
+						self.handle.status.lock().expect("poisoned lock").failed(&child);
+						if let Some(metrics) = &self.metrics {
+							metrics.on_failure();
+						}
+					}
+					blocks_to_delete.push(child);
+				}
 			}
 		}
 		self.clear_waiting_recovery(&hash);
@@ -385,6 +540,11 @@ where
 
 	/// Handle a recovered candidate.
 	async fn handle_candidate_recovered(&mut self, block_hash: Block::Hash, pov: Option<&PoV>) {
+		let started = self.recoveries_in_flight.remove(&block_hash);
+		if let Some(metrics) = &self.metrics {
+			metrics.on_result(started.map(|started| started.elapsed()));
+		}
+
 		let pov = match pov {
 			Some(pov) => {
 				self.candidates_in_retry.remove(&block_hash);
@@ -393,7 +553,10 @@ where
 			None =>
 				if self.candidates_in_retry.insert(block_hash) {
 					tracing::debug!(target: LOG_TARGET, ?block_hash, "Recovery failed, retrying.");
-					self.candidate_recovery_queue.push_recovery(block_hash);
+					if let Some(metrics) = &self.metrics {
+						metrics.on_retry();
+					}
+					self.queue_recovery(block_hash, false);
 					return
 				} else {
 					tracing::warn!(
@@ -402,7 +565,7 @@ where
 						"Unable to recover block after retry.",
 					);
 					self.candidates_in_retry.remove(&block_hash);
-					self.reset_candidate(block_hash);
+					self.recovery_failed(block_hash);
 					return
 				},
 		};
@@ -413,14 +576,14 @@ where
 				Err(error) => {
 					tracing::debug!(target: LOG_TARGET, ?error, "Failed to decompress PoV");
 
-					self.reset_candidate(block_hash);
+					self.recovery_failed(block_hash);
 					return
 				},
 			};
 
 		let Some(block_data) = Self::decode_parachain_block_data(&raw_block_data, block_hash)
 		else {
-			self.reset_candidate(block_hash);
+			self.recovery_failed(block_hash);
 			return
 		};
 
@@ -433,7 +596,7 @@ where
 				"Recovered candidate doesn't contain any blocks.",
 			);
 
-			self.reset_candidate(block_hash);
+			self.recovery_failed(block_hash);
 			return;
 		};
 
@@ -459,6 +622,8 @@ where
 							.or_default()
 							.push(b);
 					});
+					// Succeeds once the blocks are handed to the import queue.
+					self.recovered_waiting_for_parent.insert(block_hash);
 					return
 				} else {
 					tracing::debug!(
@@ -468,7 +633,7 @@ where
 						"Parent not found while trying to import recovered block.",
 					);
 
-					self.reset_candidate(block_hash);
+					self.recovery_failed(block_hash);
 					return
 				}
 			},
@@ -480,16 +645,25 @@ where
 					"Error while checking block status",
 				);
 
-				self.reset_candidate(block_hash);
+				self.recovery_failed(block_hash);
 				return
 			},
 			// Any other status is fine to "ignore/accept"
 			_ => (),
 		}
 
+		self.recovery_succeeded(block_hash);
 		self.import_blocks(blocks.into_iter());
 	}
 
+	/// The recovery of the candidate for `block_hash` succeeded.
+	fn recovery_succeeded(&mut self, block_hash: Block::Hash) {
+		self.handle.status.lock().expect("poisoned lock").done(&block_hash);
+		if let Some(metrics) = &self.metrics {
+			metrics.on_success();
+		}
+	}
+
 	/// Import the given `blocks`.
 	///
 	/// This will also recursively drain `waiting_for_parent` and import them as well.
@@ -508,6 +682,10 @@ where
 			let block_hash = block.hash();
 			let (header, body) = block.deconstruct();
 
+			if self.recovered_waiting_for_parent.remove(&block_hash) {
+				self.recovery_succeeded(block_hash);
+			}
+
 			incoming_blocks.push(IncomingBlock {
 				hash: block_hash,
 				header: Some(header),
@@ -578,7 +756,7 @@ where
 		}
 
 		for hash in to_recover.into_iter().rev() {
-			self.candidate_recovery_queue.push_recovery(hash);
+			self.queue_recovery(hash, false);
 		}
 	}
 
@@ -601,6 +779,11 @@ where
 		};
 
 		futures::pin_mut!(pending_candidates);
+		let mut forced_recoveries = match self.forced_recoveries.take() {
+			Some(forced_recoveries) => forced_recoveries.boxed(),
+			None => futures::stream::pending().boxed(),
+		}
+		.fuse();
 		loop {
 			select! {
 				next_pending_candidates = pending_candidates.next() => {
@@ -613,6 +796,11 @@ where
 						return;
 					}
 				},
+				forced = forced_recoveries.next() => {
+					if let Some(ForcedRecovery { target, response }) = forced {
+						let _ = response.send(self.force_recovery(target));
+					}
+				},
 				recovery_req = self.recovery_chan_rx.next() => {
 					if let Some(req) = recovery_req {
 						self.recover(req);
@@ -624,6 +812,7 @@ where
 				imported = imported_blocks.next() => {
 					if let Some(imported) = imported {
 						self.clear_waiting_recovery(&imported.hash);
+						self.handle.status.lock().expect("poisoned lock").done(&imported.hash);
 
 						// We need to double check that no blocks are waiting for this block.
 						// Can happen when a waiting child block is queued to wait for parent while the parent block is still
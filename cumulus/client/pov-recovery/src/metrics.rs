@@ -0,0 +1,107 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus. If not, see <https://www.gnu.org/licenses/>.
+
+use prometheus_endpoint::{
+	exponential_buckets, register, Counter, Histogram, HistogramOpts, PrometheusError, Registry,
+	U64,
+};
+
+use std::time::Duration;
+
+/// Gathers metrics about the PoV recovery.
+pub(crate) struct PoVRecoveryMetrics {
+	/// Number of recovery requests issued.
+	attempted: Counter<U64>,
+	/// Number of recovered candidates whose blocks were handed to the import queue.
+	succeeded: Counter<U64>,
+	/// Number of recovery attempts that failed and were retried.
+	retried: Counter<U64>,
+	/// Number of candidates whose recovery was given up.
+	failed: Counter<U64>,
+	/// Time between issuing a recovery request and receiving its result.
+	duration: Histogram,
+}
+
+impl PoVRecoveryMetrics {
+	pub(crate) fn register(registry: &Registry) -> Result<Self, PrometheusError> {
+		Ok(Self {
+			attempted: register(
+				Counter::new(
+					"parachain_pov_recovery_attempted",
+					"Number of PoV recovery requests issued",
+				)?,
+				registry,
+			)?,
+			succeeded: register(
+				Counter::new(
+					"parachain_pov_recovery_succeeded",
+					"Number of recovered PoVs whose blocks were handed to the import queue",
+				)?,
+				registry,
+			)?,
+			retried: register(
+				Counter::new(
+					"parachain_pov_recovery_retried",
+					"Number of PoV recovery attempts that failed and were retried",
+				)?,
+				registry,
+			)?,
+			failed: register(
+				Counter::new(
+					"parachain_pov_recovery_failed",
+					"Number of PoV recoveries that were given up",
+				)?,
+				registry,
+			)?,
+			duration: register(
+				Histogram::with_opts(
+					HistogramOpts::new(
+						"parachain_pov_recovery_duration",
+						"Time between issuing a PoV recovery request and receiving its result",
+					)
+					.buckets(
+						exponential_buckets(0.1, 2.0, 10)
+							.expect("function parameters are constant and always valid; qed"),
+					),
+				)?,
+				registry,
+			)?,
+		})
+	}
+
+	pub(crate) fn on_attempt(&self) {
+		self.attempted.inc();
+	}
+
+	pub(crate) fn on_result(&self, duration: Option<Duration>) {
+		if let Some(duration) = duration {
+			self.duration.observe(duration.as_secs_f64());
+		}
+	}
+
+	pub(crate) fn on_success(&self) {
+		self.succeeded.inc();
+	}
+
+	pub(crate) fn on_retry(&self) {
+		self.retried.inc();
+	}
+
+	pub(crate) fn on_failure(&self) {
+		self.failed.inc();
+	}
+}
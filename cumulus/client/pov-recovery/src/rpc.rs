@@ -0,0 +1,86 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus. If not, see <https://www.gnu.org/licenses/>.
+
+//! RPC to inspect the PoV recovery and to force the recovery of candidates.
+
+use crate::{PoVRecoveryHandle, RecoveryInfo, RecoveryTarget};
+
+use jsonrpsee::{
+	core::{async_trait, RpcResult},
+	proc_macros::rpc,
+	types::error::ErrorObject,
+	Extensions,
+};
+use sc_rpc_api::check_if_safe;
+use sp_runtime::traits::Block as BlockT;
+
+/// Error code returned when the PoV recovery is not running.
+const NOT_RUNNING_ERROR: i32 = 10001;
+
+/// PoV recovery RPC methods.
+#[rpc(client, server)]
+pub trait PoVRecoveryApi<Hash> {
+	/// The candidates that are waiting to be recovered or are currently being recovered.
+	#[method(name = "povRecovery_pending")]
+	fn pending(&self) -> RpcResult<Vec<RecoveryInfo<Hash>>>;
+
+	/// The candidates whose recovery failed and whose blocks were not imported since.
+	#[method(name = "povRecovery_failed")]
+	fn failed(&self) -> RpcResult<Vec<RecoveryInfo<Hash>>>;
+
+	/// Immediately recover the candidates matching `target` whose blocks are unknown, even if
+	/// their recovery failed before.
+	///
+	/// Returns the hashes of the blocks whose recovery was started. This method is unsafe.
+	#[method(name = "povRecovery_forceRecovery", with_extensions)]
+	async fn force_recovery(&self, target: RecoveryTarget) -> RpcResult<Vec<Hash>>;
+}
+
+/// Implementation of [`PoVRecoveryApiServer`] on top of a [`PoVRecoveryHandle`].
+pub struct PoVRecoveryRpc<Block: BlockT> {
+	handle: PoVRecoveryHandle<Block>,
+}
+
+impl<Block: BlockT> PoVRecoveryRpc<Block> {
+	/// Create a new instance.
+	pub fn new(handle: PoVRecoveryHandle<Block>) -> Self {
+		Self { handle }
+	}
+}
+
+#[async_trait]
+impl<Block: BlockT> PoVRecoveryApiServer<Block::Hash> for PoVRecoveryRpc<Block> {
+	fn pending(&self) -> RpcResult<Vec<RecoveryInfo<Block::Hash>>> {
+		Ok(self.handle.pending())
+	}
+
+	fn failed(&self) -> RpcResult<Vec<RecoveryInfo<Block::Hash>>> {
+		Ok(self.handle.failed())
+	}
+
+	async fn force_recovery(
+		&self,
+		ext: &Extensions,
+		target: RecoveryTarget,
+	) -> RpcResult<Vec<Block::Hash>> {
+		check_if_safe(ext)?;
+
+		self.handle.force_recovery(target).await.ok_or_else(|| {
+			ErrorObject::owned(NOT_RUNNING_ERROR, "PoV recovery is not running", None::<()>)
+		})
+	}
+}
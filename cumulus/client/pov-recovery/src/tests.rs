@@ -583,6 +583,14 @@ fn dummy_pvd() -> PersistedValidationData {
 	}
 }
 
+fn counter(registry: &Registry, name: &str) -> u64 {
+	registry
+		.gather()
+		.into_iter()
+		.find(|family| family.get_name() == name)
+		.map_or(0, |family| family.get_metric()[0].get_counter().get_value() as u64)
+}
+
 #[tokio::test]
 async fn pending_candidate_height_lower_than_latest_finalized() {
 	sp_tracing::init_for_tests();
@@ -860,6 +868,7 @@ async fn single_pending_candidate_recovery_retry_fails() {
 	let (parachain_client, _import_notifications_tx, _finality_notifications_tx) =
 		ParachainClient::new(vec![dummy_usage_info(0)], Arc::new(Mutex::new(known_blocks)));
 	let (parachain_import_queue, mut import_requests_rx) = ParachainImportQueue::new();
+	let registry = Registry::new();
 
 	let pov_recovery = PoVRecovery::<Block, _, _>::new(
 		Box::new(recovery_subsystem_tx),
@@ -870,7 +879,9 @@ async fn single_pending_candidate_recovery_retry_fails() {
 		ParaId::new(1000),
 		explicit_recovery_chan_rx,
 		Arc::new(DummySyncOracle::default()),
-	);
+	)
+	.with_metrics(&registry)
+	.unwrap();
 
 	task::spawn(pov_recovery.run());
 
@@ -917,6 +928,12 @@ async fn single_pending_candidate_recovery_retry_fails() {
 	// After the second attempt, give up.
 	// No more recovery messages received.
 	assert_matches!(recovery_subsystem_rx.next().timeout(Duration::from_millis(100)).await, None);
+
+	// The candidate failed once, not once per attempt.
+	assert_eq!(counter(&registry, "parachain_pov_recovery_attempted"), 2);
+	assert_eq!(counter(&registry, "parachain_pov_recovery_retried"), 1);
+	assert_eq!(counter(&registry, "parachain_pov_recovery_failed"), 1);
+	assert_eq!(counter(&registry, "parachain_pov_recovery_succeeded"), 0);
 }
 
 #[tokio::test]
@@ -1373,6 +1390,7 @@ async fn chained_recovery_child_succeeds_before_parent() {
 	let (parachain_client, _import_notifications_tx, _finality_notifications_tx) =
 		ParachainClient::new(vec![dummy_usage_info(0)], known_blocks.clone());
 	let (parachain_import_queue, mut import_requests_rx) = ParachainImportQueue::new();
+	let registry = Registry::new();
 
 	let pov_recovery = PoVRecovery::<Block, _, _>::new(
 		Box::new(recovery_subsystem_tx),
@@ -1383,7 +1401,9 @@ async fn chained_recovery_child_succeeds_before_parent() {
 		ParaId::new(1000),
 		explicit_recovery_chan_rx,
 		Arc::new(DummySyncOracle::default()),
-	);
+	)
+	.with_metrics(&registry)
+	.unwrap();
 
 	task::spawn(pov_recovery.run());
 
@@ -1437,6 +1457,10 @@ async fn chained_recovery_child_succeeds_before_parent() {
 
 	// No more import requests received
 	assert_matches!(import_requests_rx.next().timeout(Duration::from_millis(100)).await, None);
+
+	// The child only counts once it was handed to the import queue together with its parent.
+	assert_eq!(counter(&registry, "parachain_pov_recovery_succeeded"), 2);
+	assert_eq!(counter(&registry, "parachain_pov_recovery_failed"), 0);
 }
 
 #[tokio::test]
@@ -1542,3 +1566,101 @@ async fn recovery_multiple_blocks_per_candidate() {
 	// No more import requests received
 	assert_matches!(import_requests_rx.next().timeout(Duration::from_millis(100)).await, None);
 }
+
+#[tokio::test]
+async fn failed_recovery_can_be_forced() {
+	sp_tracing::init_for_tests();
+
+	let (recovery_subsystem_tx, mut recovery_subsystem_rx) =
+		AvailabilityRecoverySubsystemHandle::new();
+	let recovery_delay_range =
+		RecoveryDelayRange { min: Duration::from_millis(0), max: Duration::from_millis(10) };
+	let (_explicit_recovery_chan_tx, explicit_recovery_chan_rx) = mpsc::channel(10);
+	let candidates = make_candidate_chain(1..2);
+	let candidate_hash = candidates[0].hash();
+	let block_hash =
+		Header::decode(&mut &candidates[0].commitments.head_data.0[..]).unwrap().hash();
+
+	let relay_chain_client = Relaychain::new(vec![(
+		PHeader {
+			parent_hash: PHash::from_low_u64_be(0),
+			number: 1,
+			state_root: PHash::random(),
+			extrinsics_root: PHash::random(),
+			digest: Default::default(),
+		},
+		candidates,
+	)]);
+	let mut known_blocks = HashMap::new();
+	known_blocks.insert(GENESIS_HASH, BlockStatus::InChainWithState);
+	let (parachain_client, _import_notifications_tx, _finality_notifications_tx) =
+		ParachainClient::new(vec![dummy_usage_info(0)], Arc::new(Mutex::new(known_blocks)));
+	let (parachain_import_queue, mut import_requests_rx) = ParachainImportQueue::new();
+
+	let handle = PoVRecoveryHandle::new();
+	// Not running yet.
+	assert_eq!(handle.force_recovery(RecoveryTarget::CandidateHash(candidate_hash.0)).await, None);
+
+	let pov_recovery = PoVRecovery::<Block, _, _>::new(
+		Box::new(recovery_subsystem_tx),
+		recovery_delay_range,
+		Arc::new(parachain_client),
+		Box::new(parachain_import_queue),
+		relay_chain_client,
+		ParaId::new(1000),
+		explicit_recovery_chan_rx,
+		Arc::new(DummySyncOracle::default()),
+	)
+	.with_handle(handle.clone());
+
+	task::spawn(pov_recovery.run());
+
+	// The recovery fails twice and is given up.
+	for _ in 0..2 {
+		assert_matches!(
+			recovery_subsystem_rx.next().await,
+			Some(AvailabilityRecoveryMessage::RecoverAvailableData(
+				receipt,
+				_,
+				None,
+				None,
+				response_tx
+			)) => {
+				assert_eq!(receipt.hash(), candidate_hash);
+				response_tx.send(Err(RecoveryError::Unavailable)).unwrap()
+			}
+		);
+	}
+	assert_matches!(recovery_subsystem_rx.next().timeout(Duration::from_millis(100)).await, None);
+	assert_matches!(import_requests_rx.next().timeout(Duration::from_millis(100)).await, None);
+
+	assert!(handle.pending().is_empty());
+	assert_matches!(&handle.failed()[..], [info] => {
+		assert_eq!(info.block_hash, block_hash);
+		assert_eq!(info.candidate_hash, candidate_hash.0);
+		assert_eq!(info.attempts, 2);
+	});
+
+	// Unknown candidates are not recovered.
+	assert_eq!(
+		handle.force_recovery(RecoveryTarget::CandidateHash(PHash::random())).await,
+		Some(vec![])
+	);
+
+	// Forcing the recovery issues a new request right away.
+	assert_eq!(
+		handle.force_recovery(RecoveryTarget::CandidateHash(candidate_hash.0)).await,
+		Some(vec![block_hash])
+	);
+	assert_matches!(
+		recovery_subsystem_rx.next().timeout(Duration::from_millis(100)).await,
+		Some(Some(AvailabilityRecoveryMessage::RecoverAvailableData(receipt, ..))) => {
+			assert_eq!(receipt.hash(), candidate_hash);
+		}
+	);
+	assert!(handle.failed().is_empty());
+	assert_matches!(&handle.pending()[..], [info] => {
+		assert_eq!(info.attempts, 3);
+		assert!(info.in_progress);
+	});
+}
@@ -21,7 +21,9 @@
 
 use cumulus_client_cli::CollatorOptions;
 use cumulus_client_network::{AssumeSybilResistance, RequireSecondedInBlockAnnounce};
-use cumulus_client_pov_recovery::{PoVRecovery, RecoveryDelayRange, RecoveryHandle};
+use cumulus_client_pov_recovery::{
+	PoVRecovery, PoVRecoveryHandle, RecoveryDelayRange, RecoveryHandle,
+};
 use cumulus_primitives_core::{CollectCollationInfo, ParaId};
 pub use cumulus_primitives_proof_size_hostfunction::storage_proof_size;
 use cumulus_relay_chain_inprocess_interface::build_inprocess_relay_chain;
@@ -100,6 +102,8 @@ pub struct StartRelayChainTasksParams<'a, Block: BlockT, Client, RCInterface> {
 	pub recovery_handle: Box<dyn RecoveryHandle>,
 	pub sync_service: Arc<SyncingService<Block>>,
 	pub prometheus_registry: Option<&'a Registry>,
+	/// Handle to inspect the PoV recovery and to force recoveries, e.g. over RPC.
+	pub pov_recovery_handle: Option<PoVRecoveryHandle<Block>>,
 }
 
 /// Start necessary consensus tasks related to the relay chain.
@@ -124,6 +128,7 @@ pub fn start_relay_chain_tasks<Block, Client, Backend, RCInterface>(
 		recovery_handle,
 		sync_service,
 		prometheus_registry,
+		pov_recovery_handle,
 	}: StartRelayChainTasksParams<Block, Client, RCInterface>,
 ) -> sc_service::error::Result<()>
 where
@@ -178,7 +183,7 @@ where
 		DARecoveryProfile::Other(profile) => profile,
 	};
 
-	let pov_recovery = PoVRecovery::new(
+	let mut pov_recovery = PoVRecovery::new(
 		recovery_handle,
 		da_recovery_profile,
 		client.clone(),
@@ -188,6 +193,12 @@ where
 		recovery_chan_rx,
 		sync_service.clone(),
 	);
+	if let Some(pov_recovery_handle) = pov_recovery_handle {
+		pov_recovery = pov_recovery.with_handle(pov_recovery_handle);
+	}
+	if let Some(prometheus_registry) = prometheus_registry {
+		pov_recovery = pov_recovery.with_metrics(prometheus_registry)?;
+	}
 
 	task_manager
 		.spawn_essential_handle()
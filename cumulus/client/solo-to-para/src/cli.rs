@@ -0,0 +1,237 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus. If not, see <https://www.gnu.org/licenses/>.
+
+//! Commands of the solochain node to migrate to a parachain.
+
+use crate::{
+	export_finalized_state, genesis_head_data, migrate_storage, parachain_chain_spec,
+	parachain_genesis_storage, verify_registered_head, Error, Extensions, MigrationConfig,
+	ParachainChainSpec, StoragePrefix,
+};
+use codec::{Decode, Encode};
+use cumulus_primitives_core::ParaId;
+use cumulus_relay_chain_rpc_interface::{create_client_and_start_worker, Url};
+use sc_cli::{CliConfiguration, DatabaseParams, PruningParams, SharedParams};
+use sc_client_api::{HeaderBackend, StorageProvider, UsageProvider};
+use sc_service::{ChainSpec, TaskManager};
+use sp_core::hexdisplay::HexDisplay;
+use sp_runtime::{traits::Block as BlockT, BuildStorage};
+use std::{
+	fs,
+	io::{self, Write},
+	path::PathBuf,
+	sync::Arc,
+};
+
+/// The `export-parachain-genesis` command used to create the chain spec of a parachain from the
+/// finalized state of the solochain.
+#[derive(Debug, Clone, clap::Parser)]
+pub struct ExportParachainGenesisCmd {
+	/// Id of the parachain on the relay chain.
+	#[arg(long)]
+	pub para_id: u32,
+
+	/// Name of the relay chain the parachain is registered on.
+	#[arg(long)]
+	pub relay_chain: String,
+
+	/// Path to the Wasm code of the parachain runtime.
+	#[arg(long, value_name = "PATH")]
+	pub parachain_runtime: PathBuf,
+
+	/// Name of the runtime genesis config preset that provides the parachain consensus keys.
+	#[arg(long, value_name = "NAME")]
+	pub genesis_preset: Option<String>,
+
+	/// Path to a JSON patch of the runtime genesis config, e.g. with the collators and their
+	/// session keys.
+	///
+	/// The id of the parachain is patched into the config of `parachainInfo`.
+	#[arg(long, value_name = "PATH")]
+	pub genesis_patch: Option<PathBuf>,
+
+	/// Storage of the solochain to remove, as `Pallet` or `Pallet::Item`.
+	#[arg(
+		long = "remove",
+		value_name = "PALLET[::ITEM]",
+		default_values_t = MigrationConfig::default().removed,
+	)]
+	pub removed: Vec<StoragePrefix>,
+
+	/// Storage of the solochain to replace with the parachain genesis storage, as `Pallet` or
+	/// `Pallet::Item`.
+	#[arg(
+		long = "reset",
+		value_name = "PALLET[::ITEM]",
+		default_values_t = MigrationConfig::default().reset,
+	)]
+	pub reset: Vec<StoragePrefix>,
+
+	/// Name of the parachain chain spec. Defaults to the name of the solochain.
+	#[arg(long)]
+	pub name: Option<String>,
+
+	/// Id of the parachain chain spec. Defaults to the id of the solochain.
+	#[arg(long)]
+	pub id: Option<String>,
+
+	/// Output file of the genesis head data, in hex. Not written if unspecified.
+	#[arg(long, value_name = "PATH")]
+	pub genesis_head: Option<PathBuf>,
+
+	/// Output file of the chain spec or stdout if unspecified.
+	#[arg()]
+	pub output: Option<PathBuf>,
+
+	#[allow(missing_docs)]
+	#[command(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[command(flatten)]
+	pub pruning_params: PruningParams,
+
+	#[allow(missing_docs)]
+	#[command(flatten)]
+	pub database_params: DatabaseParams,
+}
+
+impl ExportParachainGenesisCmd {
+	/// Run the `export-parachain-genesis` command.
+	///
+	/// The parachain must use the block header type `B` of the solochain, and its accounts must
+	/// have the `Nonce` and `AccountData` types of the solochain.
+	pub async fn run<B, BA, C, Nonce, AccountData>(
+		&self,
+		client: Arc<C>,
+		solochain_spec: Box<dyn ChainSpec>,
+	) -> sc_cli::Result<()>
+	where
+		B: BlockT,
+		BA: sc_client_api::backend::Backend<B>,
+		C: UsageProvider<B> + StorageProvider<B, BA> + HeaderBackend<B>,
+		Nonce: Encode + Decode + Default,
+		AccountData: Encode + Decode + Default,
+	{
+		let solochain = export_finalized_state(client)?;
+
+		let code = fs::read(&self.parachain_runtime)?;
+		let mut patch = match &self.genesis_patch {
+			Some(path) => serde_json::from_slice(&fs::read(path)?)
+				.map_err(|e| format!("Invalid genesis patch: {e}"))?,
+			None => serde_json::json!({}),
+		};
+		sc_chain_spec::json_merge(
+			&mut patch,
+			serde_json::json!({ "parachainInfo": { "parachainId": self.para_id } }),
+		);
+		let parachain_genesis =
+			parachain_genesis_storage(&code, self.genesis_preset.as_deref(), patch)?;
+
+		log::info!("Migrating the solochain state...");
+		let config = MigrationConfig { removed: self.removed.clone(), reset: self.reset.clone() };
+		let storage = migrate_storage::<Nonce, AccountData>(solochain, parachain_genesis, &config)?;
+
+		let head_data = genesis_head_data::<B>(&storage)?;
+		log::info!("Genesis head data: 0x{:?}", HexDisplay::from(&head_data.0));
+		if let Some(path) = &self.genesis_head {
+			fs::write(path, format!("0x{:?}", HexDisplay::from(&head_data.0)))?;
+		}
+
+		let chain_spec = parachain_chain_spec(
+			storage,
+			Extensions { relay_chain: self.relay_chain.clone(), para_id: Some(self.para_id) },
+			self.name.as_deref().unwrap_or(solochain_spec.name()),
+			self.id.as_deref().unwrap_or(solochain_spec.id()),
+			solochain_spec.chain_type(),
+			solochain_spec.properties(),
+		);
+		let json = sc_service::chain_ops::build_spec(&chain_spec, true)?;
+
+		if let Some(output) = &self.output {
+			fs::write(output, json)?;
+		} else {
+			io::stdout().write_all(json.as_bytes())?;
+		}
+
+		Ok(())
+	}
+}
+
+impl CliConfiguration for ExportParachainGenesisCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn pruning_params(&self) -> Option<&PruningParams> {
+		Some(&self.pruning_params)
+	}
+
+	fn database_params(&self) -> Option<&DatabaseParams> {
+		Some(&self.database_params)
+	}
+}
+
+/// The `verify-parachain-head` command used to check the head data registered on the relay chain
+/// against the genesis of the parachain.
+#[derive(Debug, Clone, clap::Parser)]
+pub struct VerifyParachainHeadCmd {
+	/// Path to the chain spec of the parachain, as exported by `export-parachain-genesis`.
+	#[arg(long, value_name = "PATH")]
+	pub parachain_spec: PathBuf,
+
+	/// RPC endpoint of a relay chain node.
+	#[arg(long, value_name = "URL")]
+	pub relay_chain_rpc_url: Url,
+
+	#[allow(missing_docs)]
+	#[command(flatten)]
+	pub shared_params: SharedParams,
+}
+
+impl VerifyParachainHeadCmd {
+	/// Run the `verify-parachain-head` command.
+	///
+	/// The parachain must use the block header type `B` of the solochain.
+	pub async fn run<B: BlockT>(&self, task_manager: &mut TaskManager) -> sc_cli::Result<()> {
+		let chain_spec = ParachainChainSpec::from_json_file(self.parachain_spec.clone())?;
+		let para_id = Extensions::try_get(&chain_spec)
+			.and_then(|extensions| extensions.para_id)
+			.map(ParaId::from)
+			.ok_or("The parachain chain spec has no `para_id` extension")?;
+		let expected = genesis_head_data::<B>(&chain_spec.build_storage()?)?;
+
+		let relay_chain_rpc_client = create_client_and_start_worker(
+			vec![self.relay_chain_rpc_url.clone()],
+			task_manager,
+			None,
+		)
+		.await
+		.map_err(Error::from)?;
+
+		verify_registered_head(&relay_chain_rpc_client, para_id, &expected).await?;
+		log::info!("Head data registered for parachain {para_id} matches the genesis");
+
+		Ok(())
+	}
+}
+
+impl CliConfiguration for VerifyParachainHeadCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+}
@@ -0,0 +1,126 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus. If not, see <https://www.gnu.org/licenses/>.
+
+//! Genesis of the parachain.
+
+use crate::Error;
+use codec::Encode;
+use cumulus_primitives_core::relay_chain::HeadData;
+use sc_chain_spec::{
+	construct_genesis_block, resolve_state_version_from_wasm, ChainSpecExtension, ChainSpecGroup,
+	ChainType,
+};
+use sc_executor::WasmExecutor;
+use serde::{Deserialize, Serialize};
+use sp_core::storage::Storage;
+use sp_runtime::{
+	traits::{Block as BlockT, HashingFor},
+	BuildStorage,
+};
+use sp_state_machine::{Backend, InMemoryBackend};
+
+/// The chain spec of the parachain.
+pub type ParachainChainSpec = sc_chain_spec::GenericChainSpec<Extensions>;
+
+/// The extensions of the [`ParachainChainSpec`].
+#[derive(
+	Debug, Clone, Default, PartialEq, Serialize, Deserialize, ChainSpecGroup, ChainSpecExtension,
+)]
+pub struct Extensions {
+	/// The relay chain of the parachain.
+	#[serde(alias = "relayChain", alias = "RelayChain")]
+	pub relay_chain: String,
+	/// The id of the parachain.
+	#[serde(alias = "paraId", alias = "ParaId")]
+	pub para_id: Option<u32>,
+}
+
+impl Extensions {
+	/// Try to get the extension from the given `ChainSpec`.
+	pub fn try_get(chain_spec: &dyn sc_chain_spec::ChainSpec) -> Option<&Self> {
+		sc_chain_spec::get_extension(chain_spec.extensions())
+	}
+}
+
+/// Build the genesis storage of the parachain runtime `code`.
+///
+/// The genesis config is built from the runtime `preset`, or from the default config if no preset
+/// is given, and the `patch` is applied to it.
+pub fn parachain_genesis_storage(
+	code: &[u8],
+	preset: Option<&str>,
+	patch: serde_json::Value,
+) -> Result<Storage, Error> {
+	let mut builder = ParachainChainSpec::builder(code, Extensions::default());
+	if let Some(preset) = preset {
+		builder = builder.with_genesis_config_preset_name(preset);
+	}
+
+	builder
+		.with_genesis_config_patch(patch)
+		.build()
+		.build_storage()
+		.map_err(Error::GenesisStorage)
+}
+
+/// Create the raw chain spec of the parachain with the given genesis `storage`.
+pub fn parachain_chain_spec(
+	storage: Storage,
+	extensions: Extensions,
+	name: &str,
+	id: &str,
+	chain_type: ChainType,
+	properties: sc_chain_spec::Properties,
+) -> ParachainChainSpec {
+	let code = storage
+		.top
+		.get(sp_core::storage::well_known_keys::CODE)
+		.cloned()
+		.unwrap_or_default();
+
+	let mut chain_spec = ParachainChainSpec::builder(&code, extensions)
+		.with_name(name)
+		.with_id(id)
+		.with_chain_type(chain_type)
+		.with_properties(properties)
+		.build();
+	sc_chain_spec::ChainSpec::set_storage(&mut chain_spec, storage);
+	chain_spec
+}
+
+/// The head data of the genesis block of a parachain with the given genesis `storage`.
+///
+/// This is the head data to register on the relay chain.
+pub fn genesis_head_data<Block: BlockT>(storage: &Storage) -> Result<HeadData, Error> {
+	let executor = WasmExecutor::<sp_io::SubstrateHostFunctions>::builder()
+		.with_allow_missing_host_functions(true)
+		.build();
+	let state_version =
+		resolve_state_version_from_wasm::<_, HashingFor<Block>>(storage, &executor)?;
+
+	let child_delta = storage.children_default.values().map(|child| {
+		(&child.child_info, child.data.iter().map(|(k, v)| (k.as_ref(), Some(v.as_ref()))))
+	});
+	let (state_root, _) = InMemoryBackend::<HashingFor<Block>>::default().full_storage_root(
+		storage.top.iter().map(|(k, v)| (k.as_ref(), Some(v.as_ref()))),
+		child_delta,
+		state_version,
+	);
+
+	let block: Block = construct_genesis_block(state_root, state_version);
+	Ok(HeadData(block.header().encode()))
+}
@@ -0,0 +1,111 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus. If not, see <https://www.gnu.org/licenses/>.
+
+//! Tooling to migrate a solochain to a parachain.
+//!
+//! The state of the solochain is carried over into the genesis of a new parachain:
+//!
+//! 1. [`export_finalized_state`] exports the state of the last finalized solochain block.
+//! 2. [`parachain_genesis_storage`] builds the genesis storage of the parachain runtime. It
+//!    provides the storage of the pallets that only exist on the parachain, and the parachain
+//!    consensus keys: collators, Aura authorities and session keys.
+//! 3. [`migrate_storage`] merges both. The storage of the solochain is kept, except for the
+//!    consensus-specific storage described by the [`MigrationConfig`]. The accounts of the
+//!    solochain get the references of the parachain genesis, e.g. of the collator session keys.
+//! 4. [`parachain_chain_spec`] creates the raw chain spec of the parachain and
+//!    [`genesis_head_data`] the genesis head data to register on the relay chain.
+//! 5. [`verify_registered_head`] checks that the head data registered on the relay chain matches
+//!    the genesis of the parachain.
+//!
+//! [`ExportParachainGenesisCmd`] and [`VerifyParachainHeadCmd`] run these steps from the command
+//! line of the solochain node.
+
+#![warn(missing_docs)]
+
+use cumulus_primitives_core::ParaId;
+use cumulus_relay_chain_interface::RelayChainError;
+
+mod cli;
+mod genesis;
+mod storage;
+#[cfg(test)]
+mod tests;
+mod verify;
+
+pub use cli::{ExportParachainGenesisCmd, VerifyParachainHeadCmd};
+pub use genesis::{
+	genesis_head_data, parachain_chain_spec, parachain_genesis_storage, Extensions,
+	ParachainChainSpec,
+};
+pub use storage::{export_finalized_state, migrate_storage, MigrationConfig, StoragePrefix};
+pub use verify::{check_registered_head, verify_registered_head};
+
+/// Errors that can occur while migrating a solochain to a parachain.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+	/// A storage prefix could not be parsed.
+	#[error("Invalid storage prefix `{0}`, expected `Pallet` or `Pallet::Item`")]
+	InvalidStoragePrefix(String),
+	/// The genesis storage of the parachain could not be built.
+	#[error("Failed to build the parachain genesis storage: {0}")]
+	GenesisStorage(String),
+	/// A `System::Account` entry is not an account of the parachain runtime.
+	#[error("Failed to decode the account at 0x{key}: {error}")]
+	InvalidAccount {
+		/// Hex encoded storage key of the account.
+		key: String,
+		/// The decoding error.
+		error: codec::Error,
+	},
+	/// An account has consumers but no providers after the migration.
+	#[error("The account at 0x{0} has consumers but no providers")]
+	NoProviders(String),
+	/// Reading the state of the solochain or the runtime version of the parachain failed.
+	#[error(transparent)]
+	Blockchain(#[from] sp_blockchain::Error),
+	/// Exporting the state of the solochain failed.
+	#[error(transparent)]
+	Service(#[from] sc_service::Error),
+	/// The parachain has no head data registered on the relay chain.
+	#[error("Parachain {0} has no head data registered on the relay chain")]
+	NotRegistered(ParaId),
+	/// The head data registered on the relay chain is not the genesis head data of the parachain.
+	#[error(
+		"Head data registered for parachain {para_id} does not match the genesis: \
+		registered 0x{registered}, expected 0x{expected}"
+	)]
+	HeadDataMismatch {
+		/// The parachain.
+		para_id: ParaId,
+		/// Hex encoded head data registered on the relay chain.
+		registered: String,
+		/// Hex encoded genesis head data of the parachain.
+		expected: String,
+	},
+	/// The registered head data could not be decoded.
+	#[error("Failed to decode the registered head data: {0}")]
+	Codec(#[from] codec::Error),
+	/// Querying the relay chain failed.
+	#[error(transparent)]
+	RelayChain(#[from] RelayChainError),
+}
+
+impl From<Error> for sc_cli::Error {
+	fn from(e: Error) -> Self {
+		sc_cli::Error::Application(Box::new(e))
+	}
+}
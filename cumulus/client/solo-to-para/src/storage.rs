@@ -0,0 +1,264 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus. If not, see <https://www.gnu.org/licenses/>.
+
+//! Export and migration of the solochain state.
+
+use crate::Error;
+use codec::{Decode, DecodeAll, Encode};
+use frame_system::AccountInfo;
+use sc_client_api::{HeaderBackend, StorageProvider, UsageProvider};
+use sp_core::{
+	hexdisplay::HexDisplay,
+	storage::{well_known_keys, Storage},
+	twox_128,
+};
+use sp_runtime::traits::Block as BlockT;
+use std::{
+	collections::{BTreeMap, BTreeSet},
+	fmt,
+	str::FromStr,
+	sync::Arc,
+};
+
+/// Export the state of the last finalized block of the solochain.
+pub fn export_finalized_state<B, BA, C>(client: Arc<C>) -> Result<Storage, Error>
+where
+	B: BlockT,
+	BA: sc_client_api::backend::Backend<B>,
+	C: UsageProvider<B> + StorageProvider<B, BA> + HeaderBackend<B>,
+{
+	let finalized = client.info().finalized_hash;
+	log::info!("Exporting the state of the finalized block {finalized:?}");
+	Ok(sc_service::chain_ops::export_raw_state(client, finalized)?)
+}
+
+/// The storage of a pallet or of a single storage item of a pallet.
+///
+/// Parsed from `Pallet` or `Pallet::Item`, using the names of the pallet and of the storage item
+/// in the runtime.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoragePrefix {
+	name: String,
+	prefix: Vec<u8>,
+}
+
+impl StoragePrefix {
+	/// The storage of the pallet `pallet`.
+	pub fn pallet(pallet: &str) -> Self {
+		Self { name: pallet.into(), prefix: twox_128(pallet.as_bytes()).to_vec() }
+	}
+
+	/// The storage item `item` of the pallet `pallet`.
+	pub fn item(pallet: &str, item: &str) -> Self {
+		let mut prefix = twox_128(pallet.as_bytes()).to_vec();
+		prefix.extend(twox_128(item.as_bytes()));
+		Self { name: format!("{pallet}::{item}"), prefix }
+	}
+
+	/// Whether `key` belongs to this storage.
+	pub fn matches(&self, key: &[u8]) -> bool {
+		key.starts_with(&self.prefix)
+	}
+}
+
+impl FromStr for StoragePrefix {
+	type Err = Error;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let is_name = |name: &str| !name.is_empty() && !name.contains(':');
+		match s.split_once("::") {
+			None if is_name(s) => Ok(Self::pallet(s)),
+			Some((pallet, item)) if is_name(pallet) && is_name(item) =>
+				Ok(Self::item(pallet, item)),
+			_ => Err(Error::InvalidStoragePrefix(s.into())),
+		}
+	}
+}
+
+impl fmt::Display for StoragePrefix {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(&self.name)
+	}
+}
+
+/// Describes how the solochain storage is migrated by [`migrate_storage`].
+#[derive(Debug, Clone)]
+pub struct MigrationConfig {
+	/// Storage of the solochain that is removed without replacement.
+	///
+	/// Defaults to the storage of GRANDPA, parachains are finalized by the relay chain.
+	pub removed: Vec<StoragePrefix>,
+	/// Storage of the solochain that is replaced with the parachain genesis storage.
+	///
+	/// Defaults to the consensus-specific storage of Aura and the session keys, and to the storage
+	/// of `frame_system` that refers to the blocks of the solochain.
+	pub reset: Vec<StoragePrefix>,
+}
+
+impl Default for MigrationConfig {
+	fn default() -> Self {
+		Self {
+			removed: vec![StoragePrefix::pallet("Grandpa")],
+			reset: vec![
+				StoragePrefix::pallet("Aura"),
+				StoragePrefix::pallet("AuraExt"),
+				StoragePrefix::pallet("Session"),
+				StoragePrefix::pallet("CollatorSelection"),
+				StoragePrefix::item("System", "BlockHash"),
+				StoragePrefix::item("System", "Number"),
+				StoragePrefix::item("System", "ParentHash"),
+				StoragePrefix::item("System", "LastRuntimeUpgrade"),
+			],
+		}
+	}
+}
+
+/// Merge the `solochain` state into the `parachain_genesis` storage.
+///
+/// The storage of the solochain is kept, with the following exceptions:
+/// * The well-known keys, like `:code` and `:grandpa_authorities`, are taken from the parachain.
+/// * The storage in [`MigrationConfig::removed`] is dropped.
+/// * The storage in [`MigrationConfig::reset`] is taken from the parachain.
+/// * The storage of pallets that only exist on the parachain is taken from the parachain.
+/// * The `System::Account` entries of the solochain get the consumer and sufficient references of
+///   the parachain genesis, e.g. of the session keys of the collators. Accounts that only exist in
+///   the parachain genesis are created without balance, with a provider if they have consumers.
+///   Balances and providers of the parachain genesis, e.g. of the endowed accounts of a preset, are
+///   dropped in favour of the balances of the solochain.
+///
+/// The parachain runtime must use the `System::Account` type of the solochain, with the given
+/// `Nonce` and `AccountData`. The references that the removed and reset solochain pallets hold
+/// are kept, since they can't be told apart from the other references of an account. Returns an
+/// error if an account can't be decoded or has consumers but no providers after the migration.
+pub fn migrate_storage<Nonce, AccountData>(
+	solochain: Storage,
+	parachain_genesis: Storage,
+	config: &MigrationConfig,
+) -> Result<Storage, Error>
+where
+	Nonce: Encode + Decode + Default,
+	AccountData: Encode + Decode + Default,
+{
+	let Storage { mut top, mut children_default } = solochain;
+
+	let solochain_pallets = top
+		.keys()
+		.filter(|key| !is_well_known(key))
+		.filter_map(|key| pallet_prefix(key).map(|pallet| pallet.to_vec()))
+		.collect::<BTreeSet<_>>();
+
+	top.retain(|key, _| {
+		!is_well_known(key) &&
+			!config.removed.iter().chain(&config.reset).any(|prefix| prefix.matches(key))
+	});
+
+	let accounts = StoragePrefix::item("System", "Account");
+	let accounts_kept = !config
+		.removed
+		.iter()
+		.chain(&config.reset)
+		.any(|prefix| prefix.matches(&accounts.prefix));
+
+	for (key, value) in parachain_genesis.top {
+		if accounts_kept && accounts.matches(&key) {
+			merge_account_refs::<Nonce, AccountData>(&mut top, key, &value)?;
+			continue
+		}
+
+		let from_parachain = is_well_known(&key) ||
+			config.reset.iter().any(|prefix| prefix.matches(&key)) ||
+			pallet_prefix(&key).is_some_and(|pallet| !solochain_pallets.contains(pallet));
+		if from_parachain && !config.removed.iter().any(|prefix| prefix.matches(&key)) {
+			top.insert(key, value);
+		}
+	}
+
+	for (storage_key, child) in parachain_genesis.children_default {
+		children_default.entry(storage_key).or_insert(child);
+	}
+
+	for (key, value) in top.iter().filter(|(key, _)| accounts.matches(key)) {
+		let account = decode_account::<Nonce, AccountData>(key, value)?;
+		if account.consumers > 0 && account.providers == 0 {
+			return Err(Error::NoProviders(HexDisplay::from(key).to_string()))
+		}
+	}
+
+	Ok(Storage { top, children_default })
+}
+
+/// Add the consumer and sufficient references of the parachain genesis account at `key` to the
+/// account of the solochain, or create the account if it only exists on the parachain.
+fn merge_account_refs<Nonce, AccountData>(
+	top: &mut BTreeMap<Vec<u8>, Vec<u8>>,
+	key: Vec<u8>,
+	parachain_account: &[u8],
+) -> Result<(), Error>
+where
+	Nonce: Encode + Decode + Default,
+	AccountData: Encode + Decode + Default,
+{
+	let parachain_account = decode_account::<Nonce, AccountData>(&key, parachain_account)?;
+	if parachain_account.consumers == 0 && parachain_account.sufficients == 0 {
+		return Ok(())
+	}
+
+	let mut account = match top.get(&key) {
+		Some(value) => decode_account::<Nonce, AccountData>(&key, value)?,
+		// Like the session genesis does for accounts without balance, the consumers get a
+		// provider that is never released.
+		None => AccountInfo {
+			providers: (parachain_account.consumers > 0).into(),
+			..Default::default()
+		},
+	};
+	account.consumers = account.consumers.saturating_add(parachain_account.consumers);
+	account.sufficients = account.sufficients.saturating_add(parachain_account.sufficients);
+	top.insert(key, account.encode());
+
+	Ok(())
+}
+
+fn decode_account<Nonce: Decode, AccountData: Decode>(
+	key: &[u8],
+	value: &[u8],
+) -> Result<AccountInfo<Nonce, AccountData>, Error> {
+	AccountInfo::decode_all(&mut &value[..])
+		.map_err(|error| Error::InvalidAccount { key: HexDisplay::from(&key).to_string(), error })
+}
+
+/// The well-known keys that may be in the state.
+///
+/// Pallet storage keys start with the hash of the pallet name, which may start with a colon as
+/// well. So keys are matched exactly instead of by their leading colon.
+const WELL_KNOWN_KEYS: &[&[u8]] = &[
+	well_known_keys::CODE,
+	well_known_keys::HEAP_PAGES,
+	well_known_keys::EXTRINSIC_INDEX,
+	well_known_keys::INTRABLOCK_ENTROPY,
+	// Set by the GRANDPA pallet of older solochain runtimes.
+	b":grandpa_authorities",
+	b":transaction_level:",
+];
+
+fn is_well_known(key: &[u8]) -> bool {
+	WELL_KNOWN_KEYS.contains(&key) || well_known_keys::is_child_storage_key(key)
+}
+
+fn pallet_prefix(key: &[u8]) -> Option<&[u8]> {
+	key.get(..16)
+}
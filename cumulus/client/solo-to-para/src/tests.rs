@@ -0,0 +1,429 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus. If not, see <https://www.gnu.org/licenses/>.
+
+//! Migrates the genesis state of the solochain template to the parachain template and to the
+//! Cumulus test runtime, without running a relay chain.
+
+use super::*;
+use codec::{Decode, Encode};
+use cumulus_primitives_core::PersistedValidationData;
+use cumulus_test_client::{
+	seal_block, transfer, BlockBuilderAndSupportData, BlockData, BlockOrigin,
+	BuildParachainBlockData, ClientBlockImportExt, InitBlockBuilder, ValidationParams,
+};
+use cumulus_test_relay_sproof_builder::RelayStateSproofBuilder;
+use parachain_template_runtime::{opaque::Block, Nonce, Runtime};
+use sc_chain_spec::{ChainType, GenericChainSpec, NoExtension, DEV_RUNTIME_PRESET};
+use sc_client_api::StorageProvider;
+use sc_executor::WasmExecutor;
+use sp_blockchain::HeaderBackend;
+use sp_consensus_aura::sr25519::AuthorityId as AuraId;
+use sp_core::{
+	blake2_128,
+	hexdisplay::HexDisplay,
+	storage::{well_known_keys, Storage, StorageKey},
+	traits::{CallContext, CodeExecutor, RuntimeCode, WrappedRuntimeCode},
+	twox_128,
+};
+use sp_keyring::Sr25519Keyring;
+use sp_runtime::{traits::Header as HeaderT, AccountId32, BuildStorage};
+use sp_state_machine::BasicExternalities;
+use substrate_test_client::GenesisInit;
+
+const PARA_ID: u32 = 2000;
+
+type AccountData = <Runtime as frame_system::Config>::AccountData;
+type AccountInfo = frame_system::AccountInfo<Nonce, AccountData>;
+
+fn solochain_state() -> Storage {
+	let code = solochain_template_runtime::WASM_BINARY.expect("Wasm binary was built");
+	GenericChainSpec::<NoExtension>::builder(code, None)
+		.with_genesis_config_preset_name(DEV_RUNTIME_PRESET)
+		.build()
+		.build_storage()
+		.expect("Solochain genesis storage is built")
+}
+
+fn parachain_genesis() -> Storage {
+	let code = parachain_template_runtime::WASM_BINARY.expect("Wasm binary was built");
+	parachain_genesis_storage(
+		code,
+		Some(DEV_RUNTIME_PRESET),
+		serde_json::json!({ "parachainInfo": { "parachainId": PARA_ID } }),
+	)
+	.expect("Parachain genesis storage is built")
+}
+
+fn migrated_state() -> Storage {
+	migrate_storage::<Nonce, AccountData>(
+		solochain_state(),
+		parachain_genesis(),
+		&MigrationConfig::default(),
+	)
+	.expect("Solochain state is migrated")
+}
+
+fn account_key(who: &AccountId32) -> Vec<u8> {
+	let who = who.encode();
+	[&twox_128(b"System")[..], &twox_128(b"Account"), &blake2_128(&who), &who].concat()
+}
+
+fn account(storage: &Storage, who: &AccountId32) -> Option<AccountInfo> {
+	storage
+		.top
+		.get(&account_key(who))
+		.map(|value| AccountInfo::decode(&mut &value[..]).unwrap())
+}
+
+fn keys_with_prefix<'a>(storage: &'a Storage, prefix: &'a StoragePrefix) -> Vec<&'a Vec<u8>> {
+	storage.top.keys().filter(|key| prefix.matches(key)).collect()
+}
+
+/// Call `method` of the runtime in `storage` without arguments and return the encoded result.
+fn call_runtime(storage: &Storage, method: &str) -> Vec<u8> {
+	let executor = WasmExecutor::<sp_io::SubstrateHostFunctions>::builder()
+		.with_allow_missing_host_functions(true)
+		.build();
+	let code = storage.top.get(well_known_keys::CODE).expect("Runtime code is in storage");
+	let code_fetcher = WrappedRuntimeCode(code.into());
+	let runtime_code = RuntimeCode {
+		code_fetcher: &code_fetcher,
+		heap_pages: None,
+		hash: sp_core::blake2_256(code).to_vec(),
+	};
+
+	let mut ext = BasicExternalities::new(storage.clone());
+	executor
+		.call(&mut ext, &runtime_code, method, &[], CallContext::Offchain)
+		.0
+		.expect("Runtime call succeeds")
+}
+
+#[test]
+fn storage_prefix_parsing() {
+	assert_eq!("Grandpa".parse::<StoragePrefix>().unwrap(), StoragePrefix::pallet("Grandpa"));
+	assert_eq!(
+		"System::Number".parse::<StoragePrefix>().unwrap(),
+		StoragePrefix::item("System", "Number"),
+	);
+	assert_eq!(StoragePrefix::item("System", "Number").to_string(), "System::Number");
+
+	for invalid in ["", "::Number", "System::", "System::Number::Key", "System:Number"] {
+		assert!(matches!(
+			invalid.parse::<StoragePrefix>(),
+			Err(Error::InvalidStoragePrefix(prefix)) if prefix == invalid
+		));
+	}
+}
+
+#[test]
+fn solochain_state_is_kept() {
+	let solochain = solochain_state();
+	let migrated = migrated_state();
+
+	for prefix in [StoragePrefix::pallet("Balances"), StoragePrefix::pallet("Sudo")] {
+		let keys = keys_with_prefix(&solochain, &prefix);
+		assert!(!keys.is_empty(), "{prefix} is in the solochain state");
+		assert_eq!(keys, keys_with_prefix(&migrated, &prefix), "{prefix} is kept");
+		for key in keys {
+			assert_eq!(solochain.top.get(key), migrated.top.get(key));
+		}
+	}
+}
+
+#[test]
+fn solochain_accounts_get_the_parachain_references() {
+	let solochain = solochain_state();
+	let migrated = migrated_state();
+
+	let accounts = StoragePrefix::item("System", "Account");
+	assert_eq!(keys_with_prefix(&solochain, &accounts), keys_with_prefix(&migrated, &accounts));
+
+	for key in keys_with_prefix(&solochain, &accounts) {
+		let solochain_account = AccountInfo::decode(&mut &solochain.top[key][..]).unwrap();
+		let mut migrated_account = AccountInfo::decode(&mut &migrated.top[key][..]).unwrap();
+
+		// The collators of the parachain preset hold a consumer reference for their session keys.
+		let collators = [Sr25519Keyring::Alice, Sr25519Keyring::Bob]
+			.map(|collator| account_key(&collator.to_account_id()));
+		if collators.contains(key) {
+			assert_eq!(migrated_account.consumers, solochain_account.consumers + 1);
+			migrated_account.consumers -= 1;
+		}
+		assert_eq!(migrated_account, solochain_account);
+	}
+}
+
+#[test]
+fn account_references_are_merged() {
+	let [solochain_only, both, parachain_only, endowed] =
+		[Sr25519Keyring::Alice, Sr25519Keyring::Bob, Sr25519Keyring::Charlie, Sr25519Keyring::Dave]
+			.map(|keyring| keyring.to_account_id());
+	let account_info = |consumers, providers, sufficients, free| {
+		let data = AccountData { free, ..Default::default() };
+		AccountInfo { nonce: 1, consumers, providers, sufficients, data }.encode()
+	};
+	let storage = |accounts: Vec<(&AccountId32, Vec<u8>)>| Storage {
+		top: accounts.into_iter().map(|(who, info)| (account_key(who), info)).collect(),
+		children_default: Default::default(),
+	};
+	let migrate = |solochain, parachain_genesis| {
+		migrate_storage::<Nonce, AccountData>(
+			solochain,
+			parachain_genesis,
+			&MigrationConfig::default(),
+		)
+	};
+
+	let solochain = storage(vec![
+		(&solochain_only, account_info(0, 1, 0, 10)),
+		(&both, account_info(1, 1, 0, 20)),
+	]);
+	let parachain_genesis = storage(vec![
+		(&both, account_info(1, 1, 1, 30)),
+		(&parachain_only, account_info(1, 1, 0, 40)),
+		(&endowed, account_info(0, 1, 0, 50)),
+	]);
+	let migrated = migrate(solochain.clone(), parachain_genesis.clone()).unwrap();
+
+	assert_eq!(migrated.top.len(), 3);
+	assert_eq!(account(&migrated, &solochain_only), account(&solochain, &solochain_only));
+	let data = AccountData { free: 20, ..Default::default() };
+	assert_eq!(
+		account(&migrated, &both),
+		Some(AccountInfo { nonce: 1, consumers: 2, providers: 1, sufficients: 1, data })
+	);
+	assert_eq!(
+		account(&migrated, &parachain_only),
+		Some(AccountInfo { consumers: 1, providers: 1, ..Default::default() })
+	);
+	assert_eq!(account(&migrated, &endowed), None);
+
+	let solochain = storage(vec![(&both, account_info(0, 0, 1, 20))]);
+	assert!(matches!(
+		migrate(solochain, parachain_genesis.clone()),
+		Err(Error::NoProviders(key)) if key == HexDisplay::from(&account_key(&both)).to_string()
+	));
+
+	let solochain = storage(vec![(&both, vec![1, 2, 3])]);
+	assert!(matches!(
+		migrate(solochain, parachain_genesis),
+		Err(Error::InvalidAccount { key, .. })
+			if key == HexDisplay::from(&account_key(&both)).to_string()
+	));
+}
+
+#[test]
+fn pallets_with_a_colon_prefix_are_kept() {
+	// The hash of the pallet name starts with a colon, like a well-known key.
+	let pallet = [b':'; 16];
+	let key = |item: &[u8]| [&pallet[..], item].concat();
+	let storage = |entries: Vec<(Vec<u8>, &str)>| Storage {
+		top: entries.into_iter().map(|(key, value)| (key, value.into())).collect(),
+		children_default: Default::default(),
+	};
+
+	let solochain =
+		storage(vec![(key(b"solochain"), "kept"), (well_known_keys::CODE.to_vec(), "solo")]);
+	let parachain_genesis =
+		storage(vec![(key(b"parachain"), "dropped"), (well_known_keys::CODE.to_vec(), "para")]);
+	let migrated = migrate_storage::<Nonce, AccountData>(
+		solochain,
+		parachain_genesis,
+		&MigrationConfig::default(),
+	)
+	.unwrap();
+
+	assert_eq!(
+		migrated.top,
+		storage(vec![(key(b"solochain"), "kept"), (well_known_keys::CODE.to_vec(), "para")]).top,
+	);
+}
+
+#[test]
+fn consensus_storage_is_migrated() {
+	let parachain_genesis = parachain_genesis();
+	let migrated = migrated_state();
+
+	assert!(keys_with_prefix(&migrated, &StoragePrefix::pallet("Grandpa")).is_empty());
+	assert!(!migrated.top.contains_key(b":grandpa_authorities".as_slice()));
+	assert_eq!(
+		migrated.top.get(well_known_keys::CODE).map(|code| &code[..]),
+		parachain_template_runtime::WASM_BINARY,
+	);
+
+	for prefix in [
+		StoragePrefix::pallet("Aura"),
+		StoragePrefix::pallet("AuraExt"),
+		StoragePrefix::pallet("Session"),
+		StoragePrefix::pallet("CollatorSelection"),
+		StoragePrefix::pallet("ParachainInfo"),
+		StoragePrefix::pallet("ParachainSystem"),
+	] {
+		let keys = keys_with_prefix(&parachain_genesis, &prefix);
+		assert_eq!(
+			keys,
+			keys_with_prefix(&migrated, &prefix),
+			"{prefix} is taken from the parachain"
+		);
+		for key in keys {
+			assert_eq!(parachain_genesis.top.get(key), migrated.top.get(key));
+		}
+	}
+
+	let authorities =
+		Vec::<AuraId>::decode(&mut &call_runtime(&migrated, "AuraApi_authorities")[..]).unwrap();
+	assert_eq!(
+		authorities,
+		vec![Sr25519Keyring::Alice.public().into(), Sr25519Keyring::Bob.public().into()],
+	);
+	let para_id =
+		ParaId::decode(&mut &call_runtime(&migrated, "GetParachainInfo_parachain_id")[..]).unwrap();
+	assert_eq!(para_id, ParaId::from(PARA_ID));
+}
+
+#[test]
+fn genesis_head_data_matches_chain_spec() {
+	let migrated = migrated_state();
+	let head_data = genesis_head_data::<Block>(&migrated).unwrap();
+
+	let header = <Block as sp_runtime::traits::Block>::Header::decode(&mut &head_data.0[..])
+		.expect("Head data is a header");
+	assert_eq!(*header.number(), 0);
+
+	let chain_spec = parachain_chain_spec(
+		migrated,
+		Extensions { relay_chain: "rococo-local".into(), para_id: Some(PARA_ID) },
+		"Parachain",
+		"parachain",
+		ChainType::Local,
+		Default::default(),
+	);
+	let json = chain_spec.as_json(true).unwrap();
+	let chain_spec = ParachainChainSpec::from_json_bytes(json.into_bytes()).unwrap();
+
+	assert_eq!(Extensions::try_get(&chain_spec).and_then(|e| e.para_id), Some(PARA_ID));
+	assert_eq!(
+		genesis_head_data::<Block>(&chain_spec.build_storage().unwrap()).unwrap(),
+		head_data
+	);
+}
+
+#[test]
+fn registered_head_data_is_checked() {
+	let para_id = ParaId::from(PARA_ID);
+	let head_data = genesis_head_data::<Block>(&migrated_state()).unwrap();
+
+	assert!(check_registered_head(para_id, Some(&head_data.encode()), &head_data).is_ok());
+	assert!(matches!(
+		check_registered_head(para_id, None, &head_data),
+		Err(Error::NotRegistered(id)) if id == para_id
+	));
+
+	let solochain_head = genesis_head_data::<Block>(&solochain_state()).unwrap();
+	assert!(matches!(
+		check_registered_head(para_id, Some(&solochain_head.encode()), &head_data),
+		Err(Error::HeadDataMismatch { .. })
+	));
+}
+
+/// Uses the migrated state as the genesis of the test client.
+#[derive(Default)]
+struct MigratedGenesis(Storage);
+
+impl GenesisInit for MigratedGenesis {
+	fn genesis_storage(&self) -> Storage {
+		self.0.clone()
+	}
+}
+
+#[test]
+fn blocks_are_built_and_validated_on_the_migrated_state() {
+	const TRANSFER: u128 = 1 << 50;
+
+	let code = cumulus_test_runtime::WASM_BINARY.expect("Wasm binary was built");
+	let para_id = cumulus_test_runtime::PARACHAIN_ID;
+	let parachain_genesis = parachain_genesis_storage(
+		code,
+		Some(DEV_RUNTIME_PRESET),
+		serde_json::json!({ "parachainInfo": { "parachainId": para_id } }),
+	)
+	.expect("Parachain genesis storage is built");
+	let solochain = solochain_state();
+	let charlie = Sr25519Keyring::Charlie.to_account_id();
+	assert_eq!(account(&solochain, &charlie), None);
+
+	let migrated = migrate_storage::<
+		cumulus_test_runtime::Nonce,
+		<cumulus_test_runtime::Runtime as frame_system::Config>::AccountData,
+	>(solochain, parachain_genesis, &MigrationConfig::default())
+	.expect("Solochain state is migrated");
+	let head_data = genesis_head_data::<cumulus_test_runtime::Block>(&migrated).unwrap();
+
+	let mut builder = substrate_test_client::TestClientBuilder::<
+		cumulus_test_runtime::Block,
+		cumulus_test_client::Executor,
+		cumulus_test_client::Backend,
+		MigratedGenesis,
+	>::with_default_backend();
+	*builder.genesis_init_mut() = MigratedGenesis(migrated);
+	let (client, _) =
+		builder.build_with_native_executor::<cumulus_test_runtime::RuntimeApi, _>(None);
+	let parent_head = client.header(client.info().genesis_hash).unwrap().unwrap();
+	assert_eq!(head_data.0, parent_head.encode());
+
+	let sproof_builder = RelayStateSproofBuilder {
+		para_id: para_id.into(),
+		included_para_head: Some(head_data.clone()),
+		..Default::default()
+	};
+	let validation_data = PersistedValidationData {
+		relay_parent_number: 1,
+		parent_head: head_data.clone(),
+		..Default::default()
+	};
+	let BlockBuilderAndSupportData { mut block_builder, persisted_validation_data } =
+		client.init_block_builder(Some(validation_data), sproof_builder);
+	// Alice pays the transfer and its fees with her balance on the solochain.
+	block_builder
+		.push(transfer(&client, Sr25519Keyring::Alice, Sr25519Keyring::Charlie, TRANSFER))
+		.expect("Transfer is applied");
+	let mut block = block_builder.build_parachain_block(*parent_head.state_root());
+	block.blocks_mut()[0] = seal_block(block.blocks()[0].clone(), &client);
+	let header = block.blocks()[0].header().clone();
+
+	let result = cumulus_test_client::validate_block(
+		ValidationParams {
+			block_data: BlockData(block.encode()),
+			parent_head: head_data,
+			relay_parent_number: 1,
+			relay_parent_storage_root: persisted_validation_data.relay_parent_storage_root,
+		},
+		code,
+	)
+	.expect("Block is validated");
+	assert_eq!(result.head_data.0, header.encode());
+
+	futures::executor::block_on(client.import(BlockOrigin::Own, block.blocks()[0].clone()))
+		.expect("Block is imported");
+	let charlie = client
+		.storage(header.hash(), &StorageKey(account_key(&charlie)))
+		.unwrap()
+		.map(|value| AccountInfo::decode(&mut &value.0[..]).unwrap())
+		.expect("Charlie's account is created");
+	assert_eq!(charlie.data.free, TRANSFER);
+}
@@ -0,0 +1,63 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus. If not, see <https://www.gnu.org/licenses/>.
+
+//! Verification of the head data registered on the relay chain.
+
+use crate::Error;
+use codec::Decode;
+use cumulus_primitives_core::{
+	relay_chain::{well_known_keys, HeadData},
+	ParaId,
+};
+use cumulus_relay_chain_rpc_interface::RelayChainRpcClient;
+use sp_core::{hexdisplay::HexDisplay, storage::StorageKey};
+
+/// Check that the head data registered for `para_id` at the finalized relay chain block is the
+/// `expected` genesis head data.
+pub async fn verify_registered_head(
+	relay_chain_rpc_client: &RelayChainRpcClient,
+	para_id: ParaId,
+	expected: &HeadData,
+) -> Result<(), Error> {
+	let relay_parent = relay_chain_rpc_client.chain_get_finalized_head().await?;
+	let registered = relay_chain_rpc_client
+		.state_get_storage(StorageKey(well_known_keys::para_head(para_id)), Some(relay_parent))
+		.await?;
+
+	check_registered_head(para_id, registered.as_ref().map(|data| &data.0[..]), expected)
+}
+
+/// Check that the `registered` value of the `Paras::Heads` storage entry of `para_id` is the
+/// `expected` genesis head data.
+pub fn check_registered_head(
+	para_id: ParaId,
+	registered: Option<&[u8]>,
+	expected: &HeadData,
+) -> Result<(), Error> {
+	let Some(mut registered) = registered else { return Err(Error::NotRegistered(para_id)) };
+	let registered = HeadData::decode(&mut registered)?;
+
+	if registered != *expected {
+		return Err(Error::HeadDataMismatch {
+			para_id,
+			registered: HexDisplay::from(&registered.0).to_string(),
+			expected: HexDisplay::from(&expected.0).to_string(),
+		})
+	}
+
+	Ok(())
+}
@@ -226,6 +226,26 @@ mod benchmarks {
 		Ok(())
 	}
 
+	#[benchmark]
+	fn set_scoring_parameters() -> Result<(), BenchmarkError> {
+		let parameters = ScoringParameters {
+			authored_weight: 10,
+			missed_slot_weight: 10,
+			pov_size_weight: 1,
+			min_score: 0,
+			max_strikes: 3,
+			slash: sp_runtime::Perbill::from_percent(10),
+		};
+		let origin =
+			T::UpdateOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+
+		#[extrinsic_call]
+		_(origin as T::RuntimeOrigin, parameters.clone());
+
+		assert_last_event::<T>(Event::NewScoringParameters { parameters }.into());
+		Ok(())
+	}
+
 	#[benchmark]
 	fn set_candidacy_bond(
 		c: Linear<0, { T::MaxCandidates::get() }>,
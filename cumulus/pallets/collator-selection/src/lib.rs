@@ -78,6 +78,22 @@
 //!
 //! Note: Eventually the Pot distribution may be modified as discussed in [this
 //! issue](https://github.com/paritytech/statemint/issues/21#issuecomment-810481073).
+//!
+//! ### Performance
+//!
+//! Besides kicking candidates that did not author a block within `KickThreshold`, the pallet
+//! accounts the performance of every collator within a session: blocks authored, slots missed
+//! and the proof size of the authored blocks. Missed slots and proof sizes are derived from
+//! [`Config::AuthoringInfo`]. Slots are assigned to the collators that `pallet_session` started the
+//! session with, which leaves out the collators without session keys.
+//!
+//! At the end of every session the performance is scored with the [`ScoringParameters`] set by
+//! governance. A candidate whose score stays below the minimum for `max_strikes` consecutive
+//! sessions is demoted: it is removed from the [`CandidateList`] and part of its deposit is
+//! slashed into the Pot. Invulnerables are scored but never demoted.
+//!
+//! The scores of the current session are exposed through the
+//! [`runtime_api::CollatorSelectionApi`].
 
 #![cfg_attr(not(feature = "std"), no_std)]
 
@@ -86,6 +102,7 @@ extern crate alloc;
 use core::marker::PhantomData;
 use frame_support::traits::TypedGet;
 pub use pallet::*;
+pub use performance::{AuthoringInfo, CollatorPerformance, CollatorScore, ScoringParameters};
 
 #[cfg(test)]
 mod mock;
@@ -96,6 +113,8 @@ mod tests;
 #[cfg(feature = "runtime-benchmarks")]
 mod benchmarking;
 pub mod migration;
+pub mod performance;
+pub mod runtime_api;
 pub mod weights;
 
 const LOG_TARGET: &str = "runtime::collator-selection";
@@ -103,14 +122,15 @@ const LOG_TARGET: &str = "runtime::collator-selection";
 #[frame_support::pallet]
 pub mod pallet {
 	pub use crate::weights::WeightInfo;
+	use crate::{AuthoringInfo, CollatorPerformance, CollatorScore, ScoringParameters};
 	use alloc::vec::Vec;
 	use core::ops::Div;
 	use frame_support::{
 		dispatch::{DispatchClass, DispatchResultWithPostInfo},
 		pallet_prelude::*,
 		traits::{
-			Currency, EnsureOrigin, ExistenceRequirement::KeepAlive, ReservableCurrency,
-			ValidatorRegistration,
+			BalanceStatus, Currency, EnsureOrigin, ExistenceRequirement::KeepAlive,
+			ReservableCurrency, ValidatorRegistration,
 		},
 		BoundedVec, DefaultNoBound, PalletId,
 	};
@@ -118,7 +138,7 @@ pub mod pallet {
 	use pallet_session::SessionManager;
 	use sp_runtime::{
 		traits::{AccountIdConversion, CheckedSub, Convert, Saturating, Zero},
-		RuntimeDebug,
+		Perbill, RuntimeDebug,
 	};
 	use sp_staking::SessionIndex;
 
@@ -185,6 +205,12 @@ pub mod pallet {
 		/// Validate a user is registered
 		type ValidatorRegistration: ValidatorRegistration<Self::ValidatorId>;
 
+		/// Information about the block being authored, used to account missed slots and proof
+		/// sizes.
+		///
+		/// Use `()` if this is not available; only authored blocks are accounted then.
+		type AuthoringInfo: AuthoringInfo;
+
 		/// The weight information of this pallet.
 		type WeightInfo: WeightInfo;
 	}
@@ -197,6 +223,14 @@ pub mod pallet {
 		}
 	}
 
+	/// The maximum number of collators in a session: all invulnerables plus all candidates.
+	pub struct MaxCollators<T>(PhantomData<T>);
+	impl<T: Config> Get<u32> for MaxCollators<T> {
+		fn get() -> u32 {
+			T::MaxInvulnerables::get().saturating_add(T::MaxCandidates::get())
+		}
+	}
+
 	/// Basic information about a collation candidate.
 	#[derive(
 		PartialEq, Eq, Clone, Encode, Decode, RuntimeDebug, scale_info::TypeInfo, MaxEncodedLen,
@@ -246,6 +280,45 @@ pub mod pallet {
 	#[pallet::storage]
 	pub type CandidacyBond<T> = StorageValue<_, BalanceOf<T>, ValueQuery>;
 
+	/// The parameters used to score the performance of collators.
+	#[pallet::storage]
+	pub type Scoring<T> = StorageValue<_, ScoringParameters, ValueQuery>;
+
+	/// The collators of the current session, in the order in which they are assigned slots.
+	///
+	/// Unlike [`QueuedCollators`], this only contains the collators with session keys.
+	#[pallet::storage]
+	pub type SessionCollators<T: Config> =
+		StorageValue<_, BoundedVec<T::AccountId, MaxCollators<T>>, ValueQuery>;
+
+	/// The collators assembled for upcoming sessions, keyed by session index.
+	#[pallet::storage]
+	pub type QueuedCollators<T: Config> = StorageMap<
+		_,
+		Twox64Concat,
+		SessionIndex,
+		BoundedVec<T::AccountId, MaxCollators<T>>,
+		OptionQuery,
+	>;
+
+	/// The performance of collators within the current session.
+	#[pallet::storage]
+	pub type Performance<T: Config> =
+		StorageMap<_, Twox64Concat, T::AccountId, CollatorPerformance, ValueQuery>;
+
+	/// Number of consecutive sessions in which a collator under-performed.
+	#[pallet::storage]
+	pub type Strikes<T: Config> = StorageMap<_, Twox64Concat, T::AccountId, u32, ValueQuery>;
+
+	/// The slot of the last authored block.
+	#[pallet::storage]
+	pub type LastSlot<T> = StorageValue<_, u64, OptionQuery>;
+
+	/// The author of the current block, whose performance is accounted when the block is
+	/// finalized.
+	#[pallet::storage]
+	pub type BlockAuthor<T: Config> = StorageValue<_, T::AccountId, OptionQuery>;
+
 	#[pallet::genesis_config]
 	#[derive(DefaultNoBound)]
 	pub struct GenesisConfig<T: Config> {
@@ -306,6 +379,13 @@ pub mod pallet {
 		/// An account was unable to be added to the Invulnerables because they did not have keys
 		/// registered. Other Invulnerables may have been set.
 		InvalidInvulnerableSkipped { account_id: T::AccountId },
+		/// The scoring parameters were set.
+		NewScoringParameters { parameters: ScoringParameters },
+		/// A collator under-performed in the session that just ended.
+		CollatorUnderPerformed { account_id: T::AccountId, score: i64, strikes: u32 },
+		/// A candidate was demoted for persistent under-performance and part of its deposit was
+		/// slashed into the pot.
+		CandidateDemoted { account_id: T::AccountId, slashed: BalanceOf<T> },
 	}
 
 	#[pallet::error]
@@ -357,6 +437,12 @@ pub mod pallet {
 			);
 		}
 
+		fn on_finalize(_: BlockNumberFor<T>) {
+			if let Some(author) = BlockAuthor::<T>::take() {
+				Self::note_performance(author);
+			}
+		}
+
 		#[cfg(feature = "try-runtime")]
 		fn try_state(_: BlockNumberFor<T>) -> Result<(), sp_runtime::TryRuntimeError> {
 			Self::do_try_state()
@@ -809,6 +895,24 @@ pub mod pallet {
 			Self::deposit_event(Event::CandidateReplaced { old: target, new: who, deposit });
 			Ok(Some(T::WeightInfo::take_candidate_slot(length as u32)).into())
 		}
+
+		/// Set the parameters used to score the performance of collators at the end of every
+		/// session.
+		///
+		/// Strikes that were accumulated under the previous parameters are kept.
+		///
+		/// The origin for this call must be the `UpdateOrigin`.
+		#[pallet::call_index(9)]
+		#[pallet::weight(T::WeightInfo::set_scoring_parameters())]
+		pub fn set_scoring_parameters(
+			origin: OriginFor<T>,
+			parameters: ScoringParameters,
+		) -> DispatchResult {
+			T::UpdateOrigin::ensure_origin(origin)?;
+			Scoring::<T>::put(&parameters);
+			Self::deposit_event(Event::NewScoringParameters { parameters });
+			Ok(())
+		}
 	}
 
 	impl<T: Config> Pallet<T> {
@@ -841,7 +945,8 @@ pub mod pallet {
 				T::Currency::unreserve(who, deposit);
 				candidates.remove(idx);
 				if remove_last_authored {
-					LastAuthoredBlock::<T>::remove(who.clone())
+					LastAuthoredBlock::<T>::remove(who.clone());
+					Strikes::<T>::remove(who);
 				};
 				Ok(())
 			})?;
@@ -907,6 +1012,137 @@ pub mod pallet {
 				.expect("filter_map operation can't result in a bounded vec larger than its original; qed")
 		}
 
+		/// The performance, score and strikes of every collator of the current session.
+		pub fn collator_scores() -> Vec<CollatorScore<T::AccountId>> {
+			let scoring = Scoring::<T>::get();
+			SessionCollators::<T>::get()
+				.into_iter()
+				.map(|who| {
+					let performance = Performance::<T>::get(&who);
+					let score = scoring.score(&performance);
+					let strikes = Strikes::<T>::get(&who);
+					CollatorScore { who, performance, score, strikes }
+				})
+				.collect()
+		}
+
+		/// Account a block authored by `author`, and the slots that were missed since the
+		/// previous block.
+		///
+		/// This runs when the block is finalized, so that [`Config::AuthoringInfo`] can rely on
+		/// pallets that are initialized after the authorship pallet.
+		fn note_performance(author: T::AccountId) {
+			if let Some(slot) = T::AuthoringInfo::current_slot() {
+				let collators = SessionCollators::<T>::get();
+				if let Some(last_slot) = LastSlot::<T>::get() {
+					let missed = slot.saturating_sub(last_slot).saturating_sub(1);
+					let count = collators.len() as u64;
+					// Every collator is assigned at most `missed / count` rounded up of the missed
+					// slots, so there is no need to walk over more than `count` of them.
+					for offset in 0..missed.min(count) {
+						let expected = (last_slot + 1 + offset) % count;
+						let slots = (missed - offset).div_ceil(count);
+						Performance::<T>::mutate(&collators[expected as usize], |performance| {
+							performance.missed_slots = performance
+								.missed_slots
+								.saturating_add(slots.try_into().unwrap_or(u32::MAX))
+						});
+					}
+				}
+				LastSlot::<T>::put(slot);
+			}
+
+			let proof_size = T::AuthoringInfo::proof_size();
+			Performance::<T>::mutate(&author, |performance| {
+				performance.authored.saturating_inc();
+				if let Some(proof_size) = proof_size {
+					performance.pov_size = performance.pov_size.saturating_add(proof_size);
+				}
+			});
+		}
+
+		/// The worst case weight of [`Self::note_performance`], which is registered together
+		/// with the weight of `note_author`.
+		fn note_performance_weight() -> Weight {
+			// `BlockAuthor` is written in `note_author` and taken when the block is finalized.
+			let collators = SessionCollators::<T>::decode_len().unwrap_or_default() as u64;
+			T::DbWeight::get()
+				.reads_writes(collators.saturating_add(4), collators.saturating_add(4))
+		}
+
+		/// Score the performance of the collators of the session that just ended, demote the
+		/// candidates that under-performed for too long and reset the performance accounting.
+		///
+		/// Returns the weight consumed.
+		fn evaluate_performance() -> Weight {
+			let scoring = Scoring::<T>::get();
+			let collators = SessionCollators::<T>::get();
+			let invulnerables = Invulnerables::<T>::get();
+			let mut reads = 3;
+			let mut writes = 1;
+			for who in collators {
+				reads += 2;
+				writes += 1;
+				let score = scoring.score(&Performance::<T>::get(&who));
+				if !scoring.is_under_performing(score) {
+					Strikes::<T>::remove(&who);
+					continue
+				}
+
+				let strikes = Strikes::<T>::mutate(&who, |strikes| {
+					strikes.saturating_inc();
+					*strikes
+				});
+				Self::deposit_event(Event::CollatorUnderPerformed {
+					account_id: who.clone(),
+					score,
+					strikes,
+				});
+				if strikes < scoring.max_strikes || invulnerables.contains(&who) {
+					continue
+				}
+				// Demoting is subject to the same minimum as kicking stale candidates.
+				if Self::eligible_collators() <= T::MinEligibleCollators::get() {
+					continue
+				}
+				reads += 2;
+				writes += 3;
+				if Self::demote_candidate(&who, scoring.slash).is_ok() {
+					Strikes::<T>::remove(&who);
+				}
+			}
+			let _ = Performance::<T>::clear(MaxCollators::<T>::get(), None);
+
+			T::DbWeight::get().reads_writes(reads, writes)
+		}
+
+		/// Removes a candidate, slashing `slash` of its deposit into the pot and sending back the
+		/// rest.
+		fn demote_candidate(who: &T::AccountId, slash: Perbill) -> Result<(), DispatchError> {
+			let deposit =
+				CandidateList::<T>::try_mutate(|candidates| -> Result<_, DispatchError> {
+					let idx = candidates
+						.iter()
+						.position(|candidate_info| candidate_info.who == *who)
+						.ok_or(Error::<T>::NotCandidate)?;
+					Ok(candidates.remove(idx).deposit)
+				})?;
+			let to_slash = slash * deposit;
+			let not_slashed = T::Currency::repatriate_reserved(
+				who,
+				&Self::account_id(),
+				to_slash,
+				BalanceStatus::Free,
+			)
+			.unwrap_or(to_slash);
+			let slashed = to_slash.saturating_sub(not_slashed);
+			T::Currency::unreserve(who, deposit.saturating_sub(slashed));
+			LastAuthoredBlock::<T>::remove(who);
+
+			Self::deposit_event(Event::CandidateDemoted { account_id: who.clone(), slashed });
+			Ok(())
+		}
+
 		/// Ensure the correctness of the state of this pallet.
 		///
 		/// This should be valid before or after each state transition of this pallet.
@@ -952,17 +1188,18 @@ pub mod pallet {
 			// `reward` is half of pot account minus ED, this should never fail.
 			let _success = T::Currency::transfer(&pot, &author, reward, KeepAlive);
 			debug_assert!(_success.is_ok());
-			LastAuthoredBlock::<T>::insert(author, frame_system::Pallet::<T>::block_number());
+			LastAuthoredBlock::<T>::insert(&author, frame_system::Pallet::<T>::block_number());
+			BlockAuthor::<T>::put(author);
 
 			frame_system::Pallet::<T>::register_extra_weight_unchecked(
-				T::WeightInfo::note_author(),
+				T::WeightInfo::note_author().saturating_add(Self::note_performance_weight()),
 				DispatchClass::Mandatory,
 			);
 		}
 	}
 
 	/// Play the role of the session manager.
-	impl<T: Config> SessionManager<T::AccountId> for Pallet<T> {
+	impl<T: Config + pallet_session::Config> SessionManager<T::AccountId> for Pallet<T> {
 		fn new_session(index: SessionIndex) -> Option<Vec<T::AccountId>> {
 			log::info!(
 				"assembling new collators for new session {} at #{:?}",
@@ -984,18 +1221,38 @@ pub mod pallet {
 			);
 			let removed = candidates_len_before.saturating_sub(active_candidates_count);
 			let result = Self::assemble_collators();
+			QueuedCollators::<T>::insert(index, BoundedVec::truncate_from(result.clone()));
 
 			frame_system::Pallet::<T>::register_extra_weight_unchecked(
-				T::WeightInfo::new_session(removed, candidates_len_before),
+				T::WeightInfo::new_session(removed, candidates_len_before)
+					.saturating_add(T::DbWeight::get().writes(1)),
 				DispatchClass::Mandatory,
 			);
 			Some(result)
 		}
-		fn start_session(_: SessionIndex) {
-			// we don't care.
+		fn start_session(index: SessionIndex) {
+			// `pallet_session` starts the session without the queued collators that have no
+			// session keys, and Aura assigns the slots over the remaining ones in the same order.
+			let validators = pallet_session::Validators::<T>::get();
+			let collators = QueuedCollators::<T>::take(index)
+				.unwrap_or_default()
+				.into_iter()
+				.filter(|who| {
+					<T as pallet_session::Config>::ValidatorIdOf::convert(who.clone())
+						.is_some_and(|validator| validators.contains(&validator))
+				})
+				.collect::<Vec<_>>();
+			SessionCollators::<T>::put(BoundedVec::truncate_from(collators));
+			frame_system::Pallet::<T>::register_extra_weight_unchecked(
+				T::DbWeight::get().reads_writes(2, 2),
+				DispatchClass::Mandatory,
+			);
 		}
 		fn end_session(_: SessionIndex) {
-			// we don't care.
+			frame_system::Pallet::<T>::register_extra_weight_unchecked(
+				Self::evaluate_performance(),
+				DispatchClass::Mandatory,
+			);
 		}
 	}
 }
@@ -159,6 +159,21 @@ impl ValidatorRegistration<u64> for IsRegistered {
 	}
 }
 
+parameter_types! {
+	pub static AuthoringSlot: Option<u64> = None;
+	pub static AuthoringProofSize: Option<u64> = None;
+}
+
+pub struct MockAuthoringInfo;
+impl AuthoringInfo for MockAuthoringInfo {
+	fn current_slot() -> Option<u64> {
+		AuthoringSlot::get()
+	}
+	fn proof_size() -> Option<u64> {
+		AuthoringProofSize::get()
+	}
+}
+
 impl Config for Test {
 	type RuntimeEvent = RuntimeEvent;
 	type Currency = Balances;
@@ -171,6 +186,7 @@ impl Config for Test {
 	type ValidatorId = <Self as frame_system::Config>::AccountId;
 	type ValidatorIdOf = IdentityCollator;
 	type ValidatorRegistration = IsRegistered;
+	type AuthoringInfo = MockAuthoringInfo;
 	type WeightInfo = ();
 }
 
@@ -204,5 +220,6 @@ pub fn initialize_to_block(n: u64) {
 	for i in System::block_number() + 1..=n {
 		System::set_block_number(i);
 		<AllPalletsWithSystem as frame_support::traits::OnInitialize<u64>>::on_initialize(i);
+		<CollatorSelection as frame_support::traits::OnFinalize<u64>>::on_finalize(i);
 	}
 }
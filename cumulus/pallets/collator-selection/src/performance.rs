@@ -0,0 +1,123 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Accounting of collator performance and the governance configured scoring function.
+
+use codec::{Decode, DecodeWithMemTracking, Encode, MaxEncodedLen};
+use scale_info::TypeInfo;
+use sp_runtime::{Perbill, RuntimeDebug};
+
+/// Information about the block that is currently being authored.
+///
+/// This is used to account the performance of the block author and of the collators that were
+/// expected to author a block in the slots since the previous one. It is queried when the block is
+/// finalized, after all pallets were initialized and all extrinsics were applied.
+pub trait AuthoringInfo {
+	/// The slot of the block that is currently being authored, if known.
+	///
+	/// The author of a slot is expected to be the collator at index `slot % collators` of the
+	/// session's collators with session keys, which is the order used by Aura.
+	fn current_slot() -> Option<u64>;
+
+	/// The size of the storage proof recorded for the block so far, if it is recorded.
+	fn proof_size() -> Option<u64>;
+}
+
+impl AuthoringInfo for () {
+	fn current_slot() -> Option<u64> {
+		None
+	}
+
+	fn proof_size() -> Option<u64> {
+		None
+	}
+}
+
+/// The performance of a collator within the current session.
+#[derive(Clone, Default, PartialEq, Eq, Encode, Decode, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct CollatorPerformance {
+	/// Number of blocks authored.
+	pub authored: u32,
+	/// Number of slots assigned to the collator in which no block was authored.
+	pub missed_slots: u32,
+	/// Total size of the storage proofs recorded for the authored blocks, in bytes.
+	pub pov_size: u64,
+}
+
+/// The parameters of the scoring function, set by governance.
+///
+/// The score of a collator for a session is:
+///
+/// ```text
+/// authored * authored_weight + pov_size_kib * pov_size_weight - missed_slots * missed_slot_weight
+/// ```
+#[derive(
+	Clone,
+	Default,
+	PartialEq,
+	Eq,
+	Encode,
+	Decode,
+	DecodeWithMemTracking,
+	RuntimeDebug,
+	TypeInfo,
+	MaxEncodedLen,
+)]
+pub struct ScoringParameters {
+	/// Points awarded for each authored block.
+	pub authored_weight: u32,
+	/// Points deducted for each missed slot.
+	pub missed_slot_weight: u32,
+	/// Points awarded for each KiB of proof produced.
+	pub pov_size_weight: u32,
+	/// A session with a score below this value counts as under-performing.
+	pub min_score: i64,
+	/// Number of consecutive under-performing sessions after which a candidate is demoted.
+	///
+	/// Zero disables demotion.
+	pub max_strikes: u32,
+	/// The part of the deposit of a demoted candidate that is slashed into the pot.
+	pub slash: Perbill,
+}
+
+impl ScoringParameters {
+	/// Compute the score of the given session performance.
+	pub fn score(&self, performance: &CollatorPerformance) -> i64 {
+		let points = |count: u64, weight: u32| -> i64 {
+			i64::try_from(count.saturating_mul(weight as u64)).unwrap_or(i64::MAX)
+		};
+		points(performance.authored as u64, self.authored_weight)
+			.saturating_add(points(performance.pov_size / 1024, self.pov_size_weight))
+			.saturating_sub(points(performance.missed_slots as u64, self.missed_slot_weight))
+	}
+
+	/// Whether a session with the given score counts as under-performing.
+	pub fn is_under_performing(&self, score: i64) -> bool {
+		self.max_strikes > 0 && score < self.min_score
+	}
+}
+
+/// The score of a collator of the current session, as exposed by the runtime API.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, RuntimeDebug, TypeInfo)]
+pub struct CollatorScore<AccountId> {
+	/// The collator.
+	pub who: AccountId,
+	/// Its performance so far within the current session.
+	pub performance: CollatorPerformance,
+	/// The score of `performance`.
+	pub score: i64,
+	/// Number of consecutive previous sessions in which the collator under-performed.
+	pub strikes: u32,
+}
@@ -15,15 +15,18 @@
 
 use crate as collator_selection;
 use crate::{
-	mock::*, CandidacyBond, CandidateInfo, CandidateList, DesiredCandidates, Error, Invulnerables,
-	LastAuthoredBlock,
+	mock::*, CandidacyBond, CandidateInfo, CandidateList, CollatorPerformance, DesiredCandidates,
+	Error, Invulnerables, LastAuthoredBlock, Performance, Scoring, ScoringParameters,
+	SessionCollators, Strikes,
 };
 use frame_support::{
 	assert_noop, assert_ok,
-	traits::{Currency, OnInitialize},
+	dispatch::DispatchClass,
+	traits::{Currency, OnFinalize, OnInitialize},
+	weights::Weight,
 };
 use pallet_balances::Error as BalancesError;
-use sp_runtime::{testing::UintAuthorityId, traits::BadOrigin, BuildStorage};
+use sp_runtime::{testing::UintAuthorityId, traits::BadOrigin, BuildStorage, Perbill};
 
 #[test]
 fn basic_setup_works() {
@@ -1575,6 +1578,144 @@ fn should_kick_invulnerables_from_candidates_on_session_change() {
 	});
 }
 
+#[test]
+fn set_scoring_parameters_works() {
+	new_test_ext().execute_with(|| {
+		let parameters = ScoringParameters {
+			authored_weight: 10,
+			missed_slot_weight: 10,
+			max_strikes: 3,
+			slash: Perbill::from_percent(10),
+			..Default::default()
+		};
+
+		// can only be set by the update origin.
+		assert_noop!(
+			CollatorSelection::set_scoring_parameters(RuntimeOrigin::signed(1), parameters.clone()),
+			BadOrigin
+		);
+
+		assert_ok!(CollatorSelection::set_scoring_parameters(
+			RuntimeOrigin::signed(RootAccount::get()),
+			parameters.clone()
+		));
+		System::assert_last_event(RuntimeEvent::CollatorSelection(
+			crate::Event::NewScoringParameters { parameters: parameters.clone() },
+		));
+		assert_eq!(Scoring::<Test>::get(), parameters);
+	});
+}
+
+#[test]
+fn performance_is_accounted() {
+	new_test_ext().execute_with(|| {
+		// the genesis session is run by the invulnerables.
+		assert_eq!(SessionCollators::<Test>::get(), vec![1, 2]);
+
+		AuthoringSlot::set(Some(10));
+		// triggers `note_author`, 4 is the default author.
+		Authorship::on_initialize(1);
+		// the performance is only accounted when the block is finalized.
+		assert_eq!(Performance::<Test>::get(4), CollatorPerformance::default());
+		CollatorSelection::on_finalize(1);
+		assert_eq!(
+			Performance::<Test>::get(4),
+			CollatorPerformance { authored: 1, ..Default::default() }
+		);
+
+		// slots 11, 12 and 13 were missed, which are assigned to 2, 1 and 2.
+		AuthoringSlot::set(Some(14));
+		AuthoringProofSize::set(Some(2048));
+		Authorship::on_initialize(2);
+		// the benchmarked proof size of the block is not what is accounted.
+		System::register_extra_weight_unchecked(Weight::from_parts(0, 4096), DispatchClass::Normal);
+		CollatorSelection::on_finalize(2);
+
+		assert_eq!(
+			Performance::<Test>::get(4),
+			CollatorPerformance { authored: 2, missed_slots: 0, pov_size: 2048 }
+		);
+		assert_eq!(Performance::<Test>::get(1).missed_slots, 1);
+		assert_eq!(Performance::<Test>::get(2).missed_slots, 2);
+
+		assert_ok!(CollatorSelection::set_scoring_parameters(
+			RuntimeOrigin::signed(RootAccount::get()),
+			ScoringParameters { authored_weight: 1, missed_slot_weight: 3, ..Default::default() }
+		));
+		let scores = CollatorSelection::collator_scores();
+		assert_eq!(
+			scores.iter().map(|score| (score.who, score.score)).collect::<Vec<_>>(),
+			vec![(1, -3), (2, -6)]
+		);
+	});
+}
+
+#[test]
+fn under_performing_candidate_is_demoted() {
+	new_test_ext().execute_with(|| {
+		// nothing earns points, so every session is under-performing.
+		assert_ok!(CollatorSelection::set_scoring_parameters(
+			RuntimeOrigin::signed(RootAccount::get()),
+			ScoringParameters {
+				min_score: 1,
+				max_strikes: 2,
+				slash: Perbill::from_percent(50),
+				..Default::default()
+			}
+		));
+		assert_ok!(CollatorSelection::register_as_candidate(RuntimeOrigin::signed(4)));
+
+		// 4 is elected at block 10 and collates in the session starting at block 20.
+		initialize_to_block(20);
+		assert_eq!(SessionCollators::<Test>::get(), vec![1, 2, 4]);
+		assert_eq!(Strikes::<Test>::get(4), 0);
+
+		initialize_to_block(30);
+		assert_eq!(Strikes::<Test>::get(4), 1);
+		assert_eq!(Strikes::<Test>::get(1), 1);
+		assert_eq!(CandidateList::<Test>::get().iter().count(), 1);
+
+		initialize_to_block(40);
+		System::assert_has_event(RuntimeEvent::CollatorSelection(crate::Event::CandidateDemoted {
+			account_id: 4,
+			slashed: 5,
+		}));
+		assert_eq!(CandidateList::<Test>::get().iter().count(), 0);
+		assert_eq!(Strikes::<Test>::get(4), 0);
+		// half of the deposit went to the pot, the rest was returned.
+		assert_eq!(Balances::reserved_balance(4), 0);
+		assert_eq!(Balances::free_balance(4), 95);
+		assert_eq!(Balances::free_balance(CollatorSelection::account_id()), 5);
+		// invulnerables are scored, but never demoted.
+		assert_eq!(Strikes::<Test>::get(1), 2);
+		assert_eq!(Invulnerables::<Test>::get(), vec![1, 2]);
+	});
+}
+
+#[test]
+fn missed_slots_are_assigned_to_collators_with_session_keys() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(CollatorSelection::register_as_candidate(RuntimeOrigin::signed(4)));
+		// 1 stays invulnerable, but without session keys it can't author any block.
+		assert_ok!(Session::purge_keys(RuntimeOrigin::signed(1)));
+
+		// the session starting at block 20 is collated by 2 and 4 only.
+		initialize_to_block(20);
+		assert_eq!(Session::validators(), vec![2, 4]);
+		assert_eq!(SessionCollators::<Test>::get(), vec![2, 4]);
+
+		// slots 14 and 15 were missed, which Aura assigns to 2 and 4.
+		AuthoringSlot::set(Some(13));
+		initialize_to_block(21);
+		AuthoringSlot::set(Some(16));
+		initialize_to_block(22);
+
+		assert_eq!(Performance::<Test>::get(1), CollatorPerformance::default());
+		assert_eq!(Performance::<Test>::get(2).missed_slots, 1);
+		assert_eq!(Performance::<Test>::get(4).missed_slots, 1);
+	});
+}
+
 #[test]
 #[should_panic = "duplicate invulnerables in genesis."]
 fn cannot_set_genesis_value_twice() {
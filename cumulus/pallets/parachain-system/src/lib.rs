@@ -35,9 +35,10 @@ use core::cmp;
 use cumulus_primitives_core::{
 	relay_chain::{self, UMPSignal, UMP_SEPARATOR},
 	AbridgedHostConfiguration, ChannelInfo, ChannelStatus, CollationInfo, CumulusDigestItem,
-	GetChannelInfo, ListChannelInfos, MessageSendError, OutboundHrmpMessage, ParaId,
-	PersistedValidationData, UpwardMessage, UpwardMessageSender, XcmpMessageHandler,
-	XcmpMessageSource,
+	EncryptedExtrinsicCommitment, EncryptedExtrinsicCommitments, GetChannelInfo, ListChannelInfos,
+	MessageSendError, OutboundHrmpMessage, ParaId, PersistedValidationData,
+	UpgradePreflightOutcome, UpgradePreflightReport, UpwardMessage, UpwardMessageSender,
+	XcmpMessageHandler, XcmpMessageSource,
 };
 use cumulus_primitives_parachain_inherent::{v0, MessageQueueChain, ParachainInherentData};
 use frame_support::{
@@ -57,6 +58,7 @@ use polkadot_runtime_parachains::{FeeTracker, GetMinFeeFactor};
 use scale_info::TypeInfo;
 use sp_runtime::{
 	traits::{BlockNumberProvider, Hash},
+	transaction_validity::{InvalidTransaction, TransactionValidityError},
 	FixedU128, RuntimeDebug, SaturatedConversion,
 };
 use xcm::{latest::XcmHash, VersionedLocation, VersionedXcm, MAX_XCM_DECODE_DEPTH};
@@ -740,6 +742,102 @@ pub mod pallet {
 
 		// WARNING: call indices 2 and 3 were used in a former version of this pallet. Using them
 		// again will require to bump the transaction version of runtimes using this pallet.
+
+		/// Require validation code upgrades to pass pre-flight checks before being scheduled.
+		///
+		/// While required, code that is set through `frame_system` is staged in
+		/// [`StagedValidationCode`] until its pre-flight checks are reported with
+		/// [`Pallet::report_upgrade_preflight`].
+		///
+		/// The dispatch origin for this call must be `Root`.
+		#[pallet::call_index(4)]
+		#[pallet::weight((T::DbWeight::get().writes(1), DispatchClass::Operational))]
+		pub fn set_upgrade_preflight_required(
+			origin: OriginFor<T>,
+			required: bool,
+		) -> DispatchResult {
+			ensure_root(origin)?;
+			UpgradePreflightRequired::<T>::put(required);
+			Ok(())
+		}
+
+		/// Report the outcome of the pre-flight checks of the staged validation code.
+		///
+		/// If the checks passed, the upgrade is scheduled. A failure reported by the block author
+		/// leaves the code staged, so that one collator can't veto an upgrade authorized by
+		/// governance: another collator may still report it as passed. A failure reported by
+		/// `Root` discards the staged code, and the upgrade has to be authorized again.
+		///
+		/// The dispatch origin for this call must be `None`, in which case the report is only
+		/// accepted from the block author, or `Root`.
+		#[pallet::call_index(5)]
+		#[pallet::weight((
+			Pallet::<T>::report_upgrade_preflight_weight(),
+			DispatchClass::Operational,
+		))]
+		pub fn report_upgrade_preflight(
+			origin: OriginFor<T>,
+			report: UpgradePreflightReport,
+		) -> DispatchResult {
+			let is_root = ensure_none(origin.clone()).is_err();
+			if is_root {
+				ensure_root(origin)?;
+			}
+			Self::ensure_staged(&report.code_hash)?;
+
+			let code_hash = report.code_hash;
+			match report.outcome.clone() {
+				UpgradePreflightOutcome::Passed => {
+					StagedValidationCodeHash::<T>::kill();
+					let code = StagedValidationCode::<T>::take().unwrap_or_default();
+					Self::schedule_code_upgrade(code)?;
+					Self::deposit_event(Event::UpgradePreflightPassed { code_hash });
+				},
+				UpgradePreflightOutcome::Failed(error) => {
+					if is_root {
+						StagedValidationCodeHash::<T>::kill();
+						StagedValidationCode::<T>::kill();
+					}
+					Self::deposit_event(Event::UpgradePreflightFailed {
+						code_hash,
+						error,
+						discarded: is_root,
+					});
+				},
+			}
+			LastUpgradePreflight::<T>::put(report);
+
+			Ok(())
+		}
+
+		/// Verify that the extrinsics with the given inclusion commitments are part of the block.
+		///
+		/// The block author applies this right after the extrinsics that were submitted encrypted
+		/// to it, see [`cumulus_primitives_core::ENCRYPTED_EXTRINSIC_TOPIC`]. The commitments
+		/// match these extrinsics in order.
+		///
+		/// The dispatch origin for this call must be `None`.
+		#[pallet::call_index(6)]
+		#[pallet::weight((
+			Pallet::<T>::note_encrypted_extrinsics_weight(commitments.len() as u32),
+			DispatchClass::Operational,
+		))]
+		pub fn note_encrypted_extrinsics(
+			origin: OriginFor<T>,
+			commitments: EncryptedExtrinsicCommitments,
+		) -> DispatchResult {
+			ensure_none(origin)?;
+			Self::ensure_included_in_block(&commitments)?;
+
+			for EncryptedExtrinsicCommitment { statement_hash, extrinsic_hash } in commitments {
+				Self::deposit_event(Event::EncryptedExtrinsicIncluded {
+					statement_hash,
+					extrinsic_hash,
+				});
+			}
+
+			Ok(())
+		}
 	}
 
 	#[pallet::event]
@@ -757,6 +855,23 @@ pub mod pallet {
 		DownwardMessagesProcessed { weight_used: Weight, dmq_head: relay_chain::Hash },
 		/// An upward message was sent to the relay chain.
 		UpwardMessageSent { message_hash: Option<XcmHash> },
+		/// The validation function has been staged and waits for its pre-flight checks.
+		ValidationFunctionStaged { code_hash: relay_chain::Hash },
+		/// The pre-flight checks of the staged validation function passed.
+		UpgradePreflightPassed { code_hash: relay_chain::Hash },
+		/// The pre-flight checks of the staged validation function failed.
+		///
+		/// The function stays staged unless `discarded` is set.
+		UpgradePreflightFailed {
+			code_hash: relay_chain::Hash,
+			error: cumulus_primitives_core::UpgradePreflightError,
+			discarded: bool,
+		},
+		/// An extrinsic that was submitted encrypted to the block author was included.
+		EncryptedExtrinsicIncluded {
+			statement_hash: relay_chain::Hash,
+			extrinsic_hash: relay_chain::Hash,
+		},
 	}
 
 	#[pallet::error]
@@ -774,6 +889,12 @@ pub mod pallet {
 		HostConfigurationNotAvailable,
 		/// No validation function upgrade is currently scheduled.
 		NotScheduled,
+		/// A validation function is already staged for its pre-flight checks.
+		UpgradePreflightPending,
+		/// The report does not match the staged validation function.
+		UnknownStagedCode,
+		/// The extrinsics applied right before do not match the inclusion commitments.
+		EncryptedExtrinsicNotIncluded,
 	}
 
 	/// Latest included block descendants the runtime accepted. In other words, these are
@@ -961,6 +1082,64 @@ pub mod pallet {
 	#[pallet::storage]
 	pub type CustomValidationHeadData<T: Config> = StorageValue<_, Vec<u8>, OptionQuery>;
 
+	/// Whether validation code upgrades have to pass pre-flight checks before being scheduled.
+	#[pallet::storage]
+	pub type UpgradePreflightRequired<T: Config> = StorageValue<_, bool, ValueQuery>;
+
+	/// Validation code that was set, but waits for its pre-flight checks before it is scheduled.
+	///
+	/// Only used while [`UpgradePreflightRequired`] is set.
+	#[pallet::storage]
+	pub type StagedValidationCode<T: Config> = StorageValue<_, Vec<u8>, OptionQuery>;
+
+	/// The `blake2_256` hash of [`StagedValidationCode`].
+	///
+	/// Kept separately, so that reports can be checked without reading the code.
+	#[pallet::storage]
+	pub type StagedValidationCodeHash<T: Config> = StorageValue<_, relay_chain::Hash, OptionQuery>;
+
+	/// The last reported outcome of the pre-flight checks.
+	#[pallet::storage]
+	pub type LastUpgradePreflight<T: Config> = StorageValue<_, UpgradePreflightReport, OptionQuery>;
+
+	#[pallet::validate_unsigned]
+	impl<T: Config> ValidateUnsigned for Pallet<T> {
+		type Call = Call<T>;
+
+		fn validate_unsigned(source: TransactionSource, call: &Self::Call) -> TransactionValidity {
+			// Inclusion commitments are only valid inside the block that includes the extrinsics,
+			// they never enter the transaction pool.
+			let Call::report_upgrade_preflight { report } = call else {
+				return InvalidTransaction::Call.into()
+			};
+
+			// The checks are trusted to the same extent as the block author, who could censor the
+			// upgrade anyway. Reports of other nodes are not accepted.
+			if source == TransactionSource::External {
+				return InvalidTransaction::Call.into()
+			}
+			Self::ensure_new_report(report)?;
+
+			ValidTransaction::with_tag_prefix("UpgradePreflight")
+				.priority(TransactionPriority::MAX)
+				.and_provides(report.code_hash)
+				.longevity(64)
+				.propagate(false)
+				.build()
+		}
+
+		fn pre_dispatch(call: &Self::Call) -> Result<(), TransactionValidityError> {
+			match call {
+				Call::report_upgrade_preflight { report } => Self::ensure_new_report(report),
+				Call::note_encrypted_extrinsics { commitments } =>
+					Self::ensure_included_in_block(commitments)
+						.map_err(|_| InvalidTransaction::BadProof.into()),
+				// The validation data inherent is checked by `ProvideInherent`.
+				_ => Ok(()),
+			}
+		}
+	}
+
 	#[pallet::inherent]
 	impl<T: Config> ProvideInherent for Pallet<T> {
 		type Call = Call<T>;
@@ -1491,6 +1670,98 @@ impl<T: Config> Pallet<T> {
 		Ok(())
 	}
 
+	/// Stage a validation code upgrade until its pre-flight checks are reported.
+	///
+	/// The same restrictions as for [`Self::schedule_code_upgrade`] apply, so that an upgrade that
+	/// can't be scheduled is rejected right away.
+	pub fn stage_code_upgrade(validation_function: Vec<u8>) -> DispatchResult {
+		ensure!(<ValidationData<T>>::exists(), Error::<T>::ValidationDataNotAvailable,);
+		ensure!(<UpgradeRestrictionSignal<T>>::get().is_none(), Error::<T>::ProhibitedByPolkadot);
+		ensure!(!<PendingValidationCode<T>>::exists(), Error::<T>::OverlappingUpgrades);
+		ensure!(!StagedValidationCode::<T>::exists(), Error::<T>::UpgradePreflightPending);
+		let cfg = HostConfiguration::<T>::get().ok_or(Error::<T>::HostConfigurationNotAvailable)?;
+		ensure!(validation_function.len() <= cfg.max_code_size as usize, Error::<T>::TooBig);
+
+		let code_hash = sp_io::hashing::blake2_256(&validation_function).into();
+		StagedValidationCode::<T>::put(validation_function);
+		StagedValidationCodeHash::<T>::put(code_hash);
+		Self::deposit_event(Event::ValidationFunctionStaged { code_hash });
+
+		Ok(())
+	}
+
+	/// The validation code that waits for its pre-flight checks.
+	///
+	/// This is expected to be used by the
+	/// [`UpgradePreflightApi`](cumulus_primitives_core::UpgradePreflightApi) runtime api.
+	pub fn staged_validation_code() -> Option<Vec<u8>> {
+		StagedValidationCode::<T>::get()
+	}
+
+	/// Ensure that the validation code with `code_hash` is staged.
+	fn ensure_staged(code_hash: &relay_chain::Hash) -> DispatchResult {
+		ensure!(
+			StagedValidationCodeHash::<T>::get().as_ref() == Some(code_hash),
+			Error::<T>::UnknownStagedCode
+		);
+		Ok(())
+	}
+
+	/// Ensure that `report` is about the staged validation code and differs from the last report.
+	///
+	/// Failed checks leave the code staged, so the block author would otherwise report the same
+	/// failure in every block.
+	fn ensure_new_report(report: &UpgradePreflightReport) -> Result<(), TransactionValidityError> {
+		Self::ensure_staged(&report.code_hash).map_err(|_| InvalidTransaction::Stale)?;
+		if LastUpgradePreflight::<T>::get().as_ref() == Some(report) {
+			return Err(InvalidTransaction::Stale.into())
+		}
+		Ok(())
+	}
+
+	/// The weight of [`Pallet::report_upgrade_preflight`].
+	///
+	/// A passing report moves the staged code to [`PendingValidationCode`] and has to read it, so
+	/// the proof size accounts for code of the maximum size the relay chain accepts.
+	fn report_upgrade_preflight_weight() -> Weight {
+		T::DbWeight::get()
+			.reads_writes(7, 7)
+			.saturating_add(Weight::from_parts(0, relay_chain::MAX_CODE_SIZE as u64))
+	}
+
+	/// The weight of [`Pallet::note_encrypted_extrinsics`] with `commitments` commitments.
+	///
+	/// Every commitment reads and hashes an extrinsic of up to
+	/// [`MAX_ENCRYPTED_EXTRINSIC_SIZE`](cumulus_primitives_core::MAX_ENCRYPTED_EXTRINSIC_SIZE)
+	/// bytes and deposits an event. Not benchmarked, hashing is estimated at one nanosecond per
+	/// byte.
+	fn note_encrypted_extrinsics_weight(commitments: u32) -> Weight {
+		let size = cumulus_primitives_core::MAX_ENCRYPTED_EXTRINSIC_SIZE as u64;
+		T::DbWeight::get()
+			.reads_writes(1, 1)
+			.saturating_add(Weight::from_parts(1_000 * size, size))
+			.saturating_mul(commitments as u64)
+			.saturating_add(T::DbWeight::get().reads(1))
+	}
+
+	/// Ensure that the extrinsics applied right before the current one match `commitments`.
+	fn ensure_included_in_block(commitments: &[EncryptedExtrinsicCommitment]) -> DispatchResult {
+		let current = frame_system::Pallet::<T>::extrinsic_index().unwrap_or_default();
+		let first = current
+			.checked_sub(commitments.len() as u32)
+			.ok_or(Error::<T>::EncryptedExtrinsicNotIncluded)?;
+
+		for (index, commitment) in (first..current).zip(commitments) {
+			let extrinsic = frame_system::Pallet::<T>::extrinsic_data(index);
+			ensure!(
+				extrinsic.len() <= cumulus_primitives_core::MAX_ENCRYPTED_EXTRINSIC_SIZE as usize &&
+					sp_io::hashing::blake2_256(&extrinsic) == commitment.extrinsic_hash.0,
+				Error::<T>::EncryptedExtrinsicNotIncluded,
+			);
+		}
+		Ok(())
+	}
+
 	/// Returns the [`CollationInfo`] of the current active block.
 	///
 	/// The given `header` is the header of the built block we are collecting the collation info
@@ -1615,7 +1886,11 @@ impl<T: Config> Pallet<T> {
 pub struct ParachainSetCode<T>(core::marker::PhantomData<T>);
 impl<T: Config> frame_system::SetCode<T> for ParachainSetCode<T> {
 	fn set_code(code: Vec<u8>) -> DispatchResult {
-		Pallet::<T>::schedule_code_upgrade(code)
+		if UpgradePreflightRequired::<T>::get() {
+			Pallet::<T>::stage_code_upgrade(code)
+		} else {
+			Pallet::<T>::schedule_code_upgrade(code)
+		}
 	}
 }
 
@@ -1799,6 +2074,68 @@ pub trait RelaychainStateProvider {
 	/// It allows for setting a custom RelayChainState.
 	#[cfg(feature = "runtime-benchmarks")]
 	fn set_current_relay_chain_state(_state: RelayChainState) {}
+
+	/// Read a relay chain storage item from the relay chain state proof of the current block.
+	///
+	/// The storage key of the item must be requested through [`RelayStorageKeys`], otherwise it is
+	/// not part of the proof. Returns `Ok(None)` if the item does not exist on the relay chain.
+	///
+	/// Implementations may have to decode the whole proof for every call, so callers must weigh
+	/// each call, e.g. with [`RelaychainDataProvider::read_relay_storage_weight`].
+	fn read_relay_storage<I: RelayStorageItem>(
+		_key: &I::Key,
+	) -> Result<Option<I::Value>, RelayStorageError> {
+		Err(RelayStorageError::Unavailable)
+	}
+}
+
+/// A typed relay chain storage item that can be read from the relay chain state proof.
+///
+/// For storage values `Key` is `()`, for storage maps it is the key of the map.
+pub trait RelayStorageItem {
+	/// The key of the item in the storage map.
+	type Key;
+	/// The type of the stored value.
+	type Value: Decode;
+
+	/// The raw relay chain storage key of the item.
+	fn storage_key(key: &Self::Key) -> Vec<u8>;
+}
+
+/// Relay chain storage keys that should be included in the relay chain state proof.
+///
+/// Pallets that read relay chain storage through [`RelaychainStateProvider::read_relay_storage`]
+/// implement this trait. The runtime exposes the keys of all such pallets through the
+/// [`RelayStorageKeysApi`](cumulus_primitives_core::RelayStorageKeysApi), which the collator
+/// calls on the parent block before building the parachain inherent. Keys are allowed to depend
+/// on the state, e.g. to only request the relay chain identities of registered accounts.
+///
+/// Every requested key increases the size of the proof of validity, so pallets should only
+/// request the keys they actually read.
+pub trait RelayStorageKeys {
+	/// The relay chain storage keys to include in the relay chain state proof.
+	fn relay_storage_keys() -> Vec<Vec<u8>>;
+}
+
+#[impl_trait_for_tuples::impl_for_tuples(30)]
+impl RelayStorageKeys for Tuple {
+	fn relay_storage_keys() -> Vec<Vec<u8>> {
+		let mut keys = Vec::new();
+		for_tuples!( #( keys.extend(Tuple::relay_storage_keys()); )* );
+		keys
+	}
+}
+
+/// Errors while reading relay chain storage from the relay chain state proof.
+#[derive(Debug, PartialEq, Eq)]
+pub enum RelayStorageError {
+	/// There is no relay chain state proof, e.g. because the parachain inherent was not applied
+	/// yet in this block.
+	Unavailable,
+	/// The key is not part of the proof, it was not requested through [`RelayStorageKeys`].
+	NotProven,
+	/// The value can not be decoded.
+	Decode,
 }
 
 /// Implements [`BlockNumberProvider`] that returns relay chain block number fetched from validation
@@ -1846,6 +2183,29 @@ impl<T: Config> BlockNumberProvider for RelaychainDataProvider<T> {
 	}
 }
 
+impl<T: Config> RelaychainDataProvider<T> {
+	/// The reference time of [`read_relay_storage`](RelaychainStateProvider::read_relay_storage)
+	/// without the proof.
+	const READ_RELAY_STORAGE_BASE: u64 = 10_000_000;
+
+	/// The reference time per byte of the relay chain state proof that
+	/// [`read_relay_storage`](RelaychainStateProvider::read_relay_storage) decodes and hashes.
+	const READ_RELAY_STORAGE_PER_BYTE: u64 = 10_000;
+
+	/// The weight of one [`read_relay_storage`](RelaychainStateProvider::read_relay_storage)
+	/// call with a relay chain state proof of `proof_size` bytes.
+	///
+	/// Nothing is cached between calls: every call reads the proof from [`RelayStateProof`],
+	/// decodes it and builds a trie from all of its nodes before looking up the key. The cost is
+	/// therefore linear in the size of the proof, which is only known once the parachain inherent
+	/// was applied. The reference time is a conservative estimate, not a benchmark result.
+	pub fn read_relay_storage_weight(proof_size: u32) -> Weight {
+		let per_byte = Self::READ_RELAY_STORAGE_PER_BYTE.saturating_mul(proof_size.into());
+		Weight::from_parts(Self::READ_RELAY_STORAGE_BASE.saturating_add(per_byte), 0)
+			.saturating_add(T::DbWeight::get().reads(2))
+	}
+}
+
 impl<T: Config> RelaychainStateProvider for RelaychainDataProvider<T> {
 	fn current_relay_chain_state() -> RelayChainState {
 		ValidationData::<T>::get()
@@ -1870,4 +2230,23 @@ impl<T: Config> RelaychainStateProvider for RelaychainDataProvider<T> {
 		validation_data.relay_parent_storage_root = state.state_root;
 		ValidationData::<T>::put(validation_data)
 	}
+
+	fn read_relay_storage<I: RelayStorageItem>(
+		key: &I::Key,
+	) -> Result<Option<I::Value>, RelayStorageError> {
+		let (Some(vfp), Some(proof)) = (ValidationData::<T>::get(), RelayStateProof::<T>::get())
+		else {
+			return Err(RelayStorageError::Unavailable)
+		};
+		let proof =
+			RelayChainStateProof::new(T::SelfParaId::get(), vfp.relay_parent_storage_root, proof)
+				.map_err(|_| RelayStorageError::Unavailable)?;
+
+		proof.read_optional_entry(&I::storage_key(key)).map_err(|e| match e {
+			relay_state_snapshot::Error::ReadOptionalEntry(
+				relay_state_snapshot::ReadEntryErr::Decode,
+			) => RelayStorageError::Decode,
+			_ => RelayStorageError::NotProven,
+		})
+	}
 }
@@ -28,14 +28,19 @@ use cumulus_primitives_core::{
 use cumulus_primitives_parachain_inherent::{
 	v0, INHERENT_IDENTIFIER, PARACHAIN_INHERENT_IDENTIFIER_V0,
 };
-use frame_support::{assert_ok, parameter_types, weights::Weight};
+use frame_support::{
+	assert_ok,
+	pallet_prelude::{InvalidTransaction, TransactionSource, ValidateUnsigned},
+	parameter_types,
+	weights::Weight,
+};
 use frame_system::RawOrigin;
 use hex_literal::hex;
 use rand::Rng;
 use relay_chain::HrmpChannelId;
 use sp_core::H256;
 use sp_inherents::InherentDataProvider;
-use sp_runtime::DigestItem;
+use sp_runtime::{transaction_validity::TransactionValidityError, DigestItem};
 use sp_trie::StorageProof;
 
 #[test]
@@ -998,6 +1003,225 @@ fn checks_code_size() {
 		});
 }
 
+#[test]
+fn upgrade_is_staged_until_preflight_passes() {
+	let code_hash: relay_chain::Hash = sp_io::hashing::blake2_256(&[]).into();
+
+	BlockTests::new()
+		.with_relay_sproof_builder(|_, block_number, builder| {
+			if block_number > 2 {
+				builder.upgrade_go_ahead = Some(relay_chain::UpgradeGoAhead::GoAhead);
+			}
+		})
+		.add(1, move || {
+			assert_ok!(ParachainSystem::set_upgrade_preflight_required(
+				RawOrigin::Root.into(),
+				true
+			));
+			assert_ok!(System::set_code(RawOrigin::Root.into(), Default::default()));
+			assert!(!<PendingValidationCode<Test>>::exists());
+			assert_eq!(ParachainSystem::staged_validation_code(), Some(Vec::new()));
+			assert_eq!(StagedValidationCodeHash::<Test>::get(), Some(code_hash));
+			assert_eq!(
+				System::set_code(RawOrigin::Root.into(), Default::default()),
+				Err(Error::<Test>::UpgradePreflightPending.into()),
+			);
+		})
+		.add(2, move || {
+			assert_eq!(
+				ParachainSystem::report_upgrade_preflight(
+					RawOrigin::None.into(),
+					UpgradePreflightReport {
+						code_hash: H256::repeat_byte(1),
+						outcome: UpgradePreflightOutcome::Passed,
+					},
+				),
+				Err(Error::<Test>::UnknownStagedCode.into()),
+			);
+
+			let report =
+				UpgradePreflightReport { code_hash, outcome: UpgradePreflightOutcome::Passed };
+			assert_ok!(ParachainSystem::report_upgrade_preflight(
+				RawOrigin::None.into(),
+				report.clone()
+			));
+			assert!(<PendingValidationCode<Test>>::exists());
+			assert!(ParachainSystem::staged_validation_code().is_none());
+			assert!(StagedValidationCodeHash::<Test>::get().is_none());
+			assert_eq!(LastUpgradePreflight::<Test>::get(), Some(report));
+			System::assert_has_event(crate::Event::UpgradePreflightPassed { code_hash }.into());
+		})
+		.add_with_post_test(
+			3,
+			|| {},
+			|| {
+				assert!(!<PendingValidationCode<Test>>::exists());
+			},
+		);
+}
+
+#[test]
+fn failed_preflight_keeps_staged_upgrade_until_root_discards_it() {
+	let code_hash: relay_chain::Hash = sp_io::hashing::blake2_256(&[]).into();
+	let error = cumulus_primitives_core::UpgradePreflightError::SpecVersionNotIncreased;
+	let failed = UpgradePreflightReport {
+		code_hash,
+		outcome: UpgradePreflightOutcome::Failed(error.clone()),
+	};
+
+	BlockTests::new()
+		.add(1, || {
+			assert_ok!(ParachainSystem::set_upgrade_preflight_required(
+				RawOrigin::Root.into(),
+				true
+			));
+			assert_ok!(System::set_code(RawOrigin::Root.into(), Default::default()));
+		})
+		.add(2, {
+			let failed = failed.clone();
+			let error = error.clone();
+			move || {
+				assert_eq!(
+					ParachainSystem::validate_unsigned(
+						TransactionSource::External,
+						&crate::Call::report_upgrade_preflight {
+							report: UpgradePreflightReport {
+								code_hash,
+								outcome: UpgradePreflightOutcome::Passed,
+							},
+						},
+					),
+					InvalidTransaction::Call.into(),
+				);
+
+				// The block author can't veto the upgrade.
+				assert_ok!(ParachainSystem::report_upgrade_preflight(
+					RawOrigin::None.into(),
+					failed.clone()
+				));
+				assert_eq!(ParachainSystem::staged_validation_code(), Some(Vec::new()));
+				assert_eq!(StagedValidationCodeHash::<Test>::get(), Some(code_hash));
+				System::assert_has_event(
+					crate::Event::UpgradePreflightFailed {
+						code_hash,
+						error: error.clone(),
+						discarded: false,
+					}
+					.into(),
+				);
+
+				// The same failure is only reported once.
+				let call = crate::Call::report_upgrade_preflight { report: failed.clone() };
+				assert_eq!(
+					ParachainSystem::validate_unsigned(TransactionSource::Local, &call),
+					InvalidTransaction::Stale.into(),
+				);
+				assert_eq!(
+					ParachainSystem::pre_dispatch(&call),
+					Err(InvalidTransaction::Stale.into())
+				);
+			}
+		})
+		.add(3, move || {
+			assert_ok!(ParachainSystem::report_upgrade_preflight(
+				RawOrigin::Root.into(),
+				failed.clone()
+			));
+			assert!(!<PendingValidationCode<Test>>::exists());
+			assert!(ParachainSystem::staged_validation_code().is_none());
+			assert!(StagedValidationCodeHash::<Test>::get().is_none());
+			System::assert_has_event(
+				crate::Event::UpgradePreflightFailed {
+					code_hash,
+					error: error.clone(),
+					discarded: true,
+				}
+				.into(),
+			);
+		});
+}
+
+#[test]
+fn encrypted_extrinsics_must_be_applied_right_before_their_commitments() {
+	let commitment_of = |extrinsic: &[u8], statement: u8| EncryptedExtrinsicCommitment {
+		statement_hash: H256::repeat_byte(statement),
+		extrinsic_hash: sp_io::hashing::blake2_256(extrinsic).into(),
+	};
+	let apply = |extrinsic: &[u8]| {
+		let index = System::extrinsic_index().unwrap_or_default();
+		System::note_extrinsic(extrinsic.to_vec());
+		System::set_extrinsic_index(index + 1);
+	};
+	let note =
+		|commitments: Vec<EncryptedExtrinsicCommitment>| -> Result<(), TransactionValidityError> {
+			let commitments = EncryptedExtrinsicCommitments::truncate_from(commitments);
+			let call = crate::Call::note_encrypted_extrinsics { commitments: commitments.clone() };
+			ParachainSystem::pre_dispatch(&call)?;
+			ParachainSystem::note_encrypted_extrinsics(RawOrigin::None.into(), commitments)
+				.map_err(|_| InvalidTransaction::BadProof.into())
+		};
+
+	BlockTests::new().add(1, move || {
+		let first = commitment_of(b"first", 1);
+		let second = commitment_of(b"second", 2);
+
+		let call = crate::Call::note_encrypted_extrinsics {
+			commitments: EncryptedExtrinsicCommitments::truncate_from(vec![first.clone()]),
+		};
+		assert_eq!(
+			ParachainSystem::validate_unsigned(TransactionSource::Local, &call),
+			InvalidTransaction::Call.into(),
+		);
+		assert_eq!(note(vec![first.clone()]), Err(InvalidTransaction::BadProof.into()));
+
+		apply(b"first");
+		apply(b"second");
+
+		// The commitments have to match the extrinsics applied right before, in order.
+		assert_eq!(note(vec![first.clone()]), Err(InvalidTransaction::BadProof.into()));
+		assert_eq!(
+			note(vec![second.clone(), first.clone()]),
+			Err(InvalidTransaction::BadProof.into())
+		);
+		assert_eq!(
+			note(vec![commitment_of(b"other", 3), second.clone()]),
+			Err(InvalidTransaction::BadProof.into())
+		);
+
+		assert_ok!(note(vec![first.clone(), second.clone()]));
+		for commitment in [first, second] {
+			System::assert_has_event(
+				crate::Event::EncryptedExtrinsicIncluded {
+					statement_hash: commitment.statement_hash,
+					extrinsic_hash: commitment.extrinsic_hash,
+				}
+				.into(),
+			);
+		}
+	});
+}
+
+#[test]
+fn oversized_encrypted_extrinsics_are_not_accepted() {
+	let extrinsic = vec![0u8; cumulus_primitives_core::MAX_ENCRYPTED_EXTRINSIC_SIZE as usize + 1];
+	let commitments =
+		EncryptedExtrinsicCommitments::truncate_from(vec![EncryptedExtrinsicCommitment {
+			statement_hash: H256::zero(),
+			extrinsic_hash: sp_io::hashing::blake2_256(&extrinsic).into(),
+		}]);
+
+	BlockTests::new().add(1, move || {
+		let index = System::extrinsic_index().unwrap_or_default();
+		System::note_extrinsic(extrinsic.clone());
+		System::set_extrinsic_index(index + 1);
+
+		assert_eq!(
+			ParachainSystem::note_encrypted_extrinsics(RawOrigin::None.into(), commitments.clone()),
+			Err(Error::<Test>::EncryptedExtrinsicNotIncluded.into()),
+		);
+	});
+}
+
 #[test]
 fn send_upward_message_num_per_candidate() {
 	BlockTests::new()
@@ -1732,3 +1956,47 @@ fn ump_signals_are_sent_correctly() {
 			);
 	}
 }
+
+/// A relay chain storage map used to test [`RelaychainStateProvider::read_relay_storage`].
+struct RelayTestMap;
+
+impl RelayStorageItem for RelayTestMap {
+	type Key = u32;
+	type Value = u128;
+
+	fn storage_key(key: &u32) -> Vec<u8> {
+		(b"relay_test_map", key).encode()
+	}
+}
+
+#[test]
+fn relay_storage_is_read_from_state_proof() {
+	type Provider = RelaychainDataProvider<Test>;
+
+	new_test_ext().execute_with(|| {
+		assert_eq!(
+			Provider::read_relay_storage::<RelayTestMap>(&1),
+			Err(RelayStorageError::Unavailable)
+		);
+	});
+
+	BlockTests::new()
+		.with_relay_sproof_builder(|_, _, sproof| {
+			sproof.additional_key_values = vec![
+				(RelayTestMap::storage_key(&1), 42u128.encode()),
+				(RelayTestMap::storage_key(&2), vec![1]),
+			];
+		})
+		.add(1, || {
+			assert_eq!(Provider::read_relay_storage::<RelayTestMap>(&1), Ok(Some(42)));
+			assert_eq!(
+				Provider::read_relay_storage::<RelayTestMap>(&2),
+				Err(RelayStorageError::Decode)
+			);
+
+			// Every read decodes the whole proof.
+			let proof_size = RelayStateProof::<Test>::get().unwrap().encoded_size() as u32;
+			assert!(Provider::read_relay_storage_weight(proof_size)
+				.all_gt(Provider::read_relay_storage_weight(0)));
+		});
+}
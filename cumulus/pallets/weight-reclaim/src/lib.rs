@@ -20,6 +20,8 @@
 //! This crate provides:
 //! * [`StorageWeightReclaim`] transaction extension: it must wrap the whole transaction extension
 //!   pipeline.
+//! * [`StorageWeightReclaimProcessor`] and [`StorageWeightReclaimDispatcher`]: reclaim for messages
+//!   processed by the message queue and for the calls they dispatch with XCM `Transact`.
 //! * The pallet required for the transaction extensions weight information and benchmarks.
 
 #![cfg_attr(not(feature = "std"), no_std)]
@@ -44,10 +46,12 @@ use sp_runtime::{
 
 #[cfg(feature = "runtime-benchmarks")]
 pub mod benchmarks;
+mod messages;
 #[cfg(test)]
 mod tests;
 mod weights;
 
+pub use messages::{StorageWeightReclaimDispatcher, StorageWeightReclaimProcessor};
 pub use pallet::*;
 pub use weights::WeightInfo;
 
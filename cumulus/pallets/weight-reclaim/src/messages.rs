@@ -0,0 +1,120 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Storage weight reclaim for messages processed by the message queue.
+//!
+//! Messages are not extrinsics, so the [`StorageWeightReclaim`](crate::StorageWeightReclaim)
+//! transaction extension never sees them. Their weight is consumed from the `ServiceWeight` budget
+//! of the message queue instead. The wrappers of this module measure the proof size that is
+//! actually used and give the unused benchmarked proof size back:
+//!
+//! * [`StorageWeightReclaimProcessor`] wraps the `MessageProcessor` of the message queue and
+//!   reclaims per processed message. The reclaimed weight is returned to the `ServiceWeight`
+//!   budget, so more messages can be processed in the same block.
+//! * [`StorageWeightReclaimDispatcher`] wraps the `CallDispatcher` of the XCM executor and reclaims
+//!   per `Transact`. The XCM executor accounts the reclaimed weight as surplus of the message.
+
+use crate::LOG_TARGET;
+use core::marker::PhantomData;
+use cumulus_primitives_storage_weight_reclaim::{get_proof_size, StorageWeightReclaimer};
+use frame_support::{
+	dispatch::{GetDispatchInfo, PostDispatchInfo},
+	traits::{ProcessMessage, ProcessMessageError},
+	weights::WeightMeter,
+};
+use sp_runtime::{traits::Dispatchable, DispatchErrorWithPostInfo};
+use xcm_executor::traits::CallDispatcher;
+
+/// Message processor that reclaims the unused proof size of every message processed by `P`.
+///
+/// Use it as the `MessageProcessor` of `pallet-message-queue`:
+/// ```ignore
+/// type MessageProcessor = StorageWeightReclaimProcessor<
+/// 	xcm_builder::ProcessXcmMessage<AggregateMessageOrigin, XcmExecutor<XcmConfig>, RuntimeCall>,
+/// >;
+/// ```
+pub struct StorageWeightReclaimProcessor<P>(PhantomData<P>);
+
+impl<P: ProcessMessage> ProcessMessage for StorageWeightReclaimProcessor<P> {
+	type Origin = P::Origin;
+
+	fn process_message(
+		message: &[u8],
+		origin: Self::Origin,
+		meter: &mut WeightMeter,
+		id: &mut [u8; 32],
+	) -> Result<bool, ProcessMessageError> {
+		let mut reclaimer = StorageWeightReclaimer::new(meter);
+		let result = P::process_message(message, origin, meter, id);
+
+		if let Some(reclaimed) = reclaimer.reclaim_with_meter(meter) {
+			log::trace!(
+				target: LOG_TARGET,
+				"Reclaimed {} bytes of proof size after processing message {id:?}",
+				reclaimed.proof_size(),
+			);
+		}
+
+		result
+	}
+}
+
+/// Call dispatcher that reports the proof size actually used by the calls dispatched by `D`.
+///
+/// Use it as the `CallDispatcher` of the XCM executor:
+/// ```ignore
+/// type CallDispatcher = StorageWeightReclaimDispatcher<RuntimeCall>;
+/// ```
+///
+/// The reported proof size is only ever lowered. If the benchmark underestimated the proof size,
+/// the post dispatch weight is left as is.
+pub struct StorageWeightReclaimDispatcher<D>(PhantomData<D>);
+
+impl<Call, D> CallDispatcher<Call> for StorageWeightReclaimDispatcher<D>
+where
+	Call: Dispatchable<PostInfo = PostDispatchInfo> + GetDispatchInfo,
+	D: CallDispatcher<Call>,
+{
+	fn dispatch(
+		call: Call,
+		origin: Call::RuntimeOrigin,
+	) -> Result<PostDispatchInfo, DispatchErrorWithPostInfo<PostDispatchInfo>> {
+		let info = call.get_dispatch_info();
+		let proof_size_before = get_proof_size();
+		let mut result = D::dispatch(call, origin);
+
+		let (Some(before), Some(after)) = (proof_size_before, get_proof_size()) else {
+			return result
+		};
+		let used_proof_size = after.saturating_sub(before);
+
+		let post_info = match &mut result {
+			Ok(post_info) => post_info,
+			Err(err) => &mut err.post_info,
+		};
+		let actual_weight = post_info.calc_actual_weight(&info);
+		if used_proof_size < actual_weight.proof_size() {
+			log::trace!(
+				target: LOG_TARGET,
+				"Reclaimed {} bytes of proof size after dispatching call",
+				actual_weight.proof_size() - used_proof_size,
+			);
+			post_info.actual_weight = Some(actual_weight.set_proof_size(used_proof_size));
+		}
+
+		result
+	}
+}
@@ -19,14 +19,19 @@
 use super::*;
 use cumulus_primitives_proof_size_hostfunction::PROOF_RECORDING_DISABLED;
 use frame_support::{
-	assert_ok, derive_impl, dispatch::GetDispatchInfo, pallet_prelude::DispatchClass,
+	assert_ok, derive_impl,
+	dispatch::GetDispatchInfo,
+	pallet_prelude::DispatchClass,
+	traits::{ProcessMessage, ProcessMessageError},
+	weights::WeightMeter,
 };
 use sp_runtime::{
 	generic,
 	traits::{Applyable, BlakeTwo256, DispatchTransaction, Get},
-	BuildStorage,
+	BuildStorage, DispatchError, DispatchErrorWithPostInfo,
 };
 use sp_trie::proof_size_extension::ProofSizeExt;
+use xcm_executor::traits::CallDispatcher;
 
 thread_local! {
 	static CHECK_WEIGHT_WEIGHT: core::cell::RefCell<Weight> = Default::default();
@@ -155,7 +160,7 @@ impl frame_system::WeightInfo for MockWeightInfo {
 		Weight::zero()
 	}
 	fn remark_with_event(_b: u32) -> Weight {
-		Weight::zero()
+		Weight::from_parts(400, 1000)
 	}
 	fn authorize_upgrade() -> Weight {
 		Weight::zero()
@@ -1051,3 +1056,138 @@ fn test_metadata() {
 		vec!["CheckWeight", "StorageWeightReclaim"]
 	);
 }
+
+/// Message processor that consumes a fixed weight for every message.
+struct MockProcessor;
+
+impl ProcessMessage for MockProcessor {
+	type Origin = u32;
+
+	fn process_message(
+		_message: &[u8],
+		_origin: Self::Origin,
+		meter: &mut WeightMeter,
+		_id: &mut [u8; 32],
+	) -> Result<bool, ProcessMessageError> {
+		meter.consume(Weight::from_parts(100, 1000));
+		Ok(true)
+	}
+}
+
+#[test]
+fn processor_reclaims_unused_proof_size() {
+	let mut test_ext = setup_test_externalities(&[100, 400]);
+
+	test_ext.execute_with(|| {
+		let mut meter = WeightMeter::with_limit(Weight::from_parts(1000, 5000));
+		assert_eq!(
+			StorageWeightReclaimProcessor::<MockProcessor>::process_message(
+				b"message",
+				0,
+				&mut meter,
+				&mut [0; 32],
+			),
+			Ok(true)
+		);
+
+		assert_eq!(meter.consumed(), Weight::from_parts(100, 300));
+	});
+}
+
+#[test]
+fn processor_keeps_benchmarked_proof_size_if_lower() {
+	let mut test_ext = setup_test_externalities(&[100, 1500]);
+
+	test_ext.execute_with(|| {
+		let mut meter = WeightMeter::with_limit(Weight::from_parts(1000, 5000));
+		assert_eq!(
+			StorageWeightReclaimProcessor::<MockProcessor>::process_message(
+				b"message",
+				0,
+				&mut meter,
+				&mut [0; 32],
+			),
+			Ok(true)
+		);
+
+		assert_eq!(meter.consumed(), Weight::from_parts(100, 1000));
+	});
+}
+
+/// Call dispatcher that fails every call without reporting a post dispatch weight.
+struct FailingDispatcher;
+
+impl CallDispatcher<RuntimeCall> for FailingDispatcher {
+	fn dispatch(
+		_call: RuntimeCall,
+		_origin: RuntimeOrigin,
+	) -> Result<PostDispatchInfo, DispatchErrorWithPostInfo<PostDispatchInfo>> {
+		Err(DispatchErrorWithPostInfo {
+			post_info: Default::default(),
+			error: DispatchError::Other("failed"),
+		})
+	}
+}
+
+const REMARK_WITH_EVENT: RuntimeCall =
+	RuntimeCall::System(frame_system::Call::remark_with_event { remark: Vec::new() });
+
+#[test]
+fn dispatcher_reports_used_proof_size() {
+	let mut test_ext = setup_test_externalities(&[100, 300]);
+
+	test_ext.execute_with(|| {
+		let post_info = StorageWeightReclaimDispatcher::<RuntimeCall>::dispatch(
+			REMARK_WITH_EVENT,
+			RuntimeOrigin::signed(99),
+		)
+		.expect("remark succeeds");
+
+		assert_eq!(post_info.actual_weight, Some(Weight::from_parts(400, 200)));
+	});
+}
+
+#[test]
+fn dispatcher_keeps_benchmarked_proof_size_if_lower() {
+	let mut test_ext = setup_test_externalities(&[100, 1500]);
+
+	test_ext.execute_with(|| {
+		let post_info = StorageWeightReclaimDispatcher::<RuntimeCall>::dispatch(
+			REMARK_WITH_EVENT,
+			RuntimeOrigin::signed(99),
+		)
+		.expect("remark succeeds");
+
+		assert_eq!(post_info.actual_weight, None);
+	});
+}
+
+#[test]
+fn dispatcher_reports_used_proof_size_of_failed_calls() {
+	let mut test_ext = setup_test_externalities(&[100, 300]);
+
+	test_ext.execute_with(|| {
+		let err = StorageWeightReclaimDispatcher::<FailingDispatcher>::dispatch(
+			REMARK_WITH_EVENT,
+			RuntimeOrigin::signed(99),
+		)
+		.expect_err("dispatcher fails");
+
+		assert_eq!(err.post_info.actual_weight, Some(Weight::from_parts(400, 200)));
+	});
+}
+
+#[test]
+fn dispatcher_without_proof_recording() {
+	let mut test_ext = setup_test_externalities(&[PROOF_RECORDING_DISABLED as usize; 2]);
+
+	test_ext.execute_with(|| {
+		let post_info = StorageWeightReclaimDispatcher::<RuntimeCall>::dispatch(
+			REMARK_WITH_EVENT,
+			RuntimeOrigin::signed(99),
+		)
+		.expect("remark succeeds");
+
+		assert_eq!(post_info.actual_weight, None);
+	});
+}
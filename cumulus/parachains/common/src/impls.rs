@@ -18,6 +18,7 @@
 
 use alloc::boxed::Box;
 use core::marker::PhantomData;
+use cumulus_primitives_proof_size_hostfunction::{storage_proof_size, PROOF_RECORDING_DISABLED};
 use frame_support::traits::{
 	fungible, fungibles, tokens::imbalance::ResolveTo, Contains, ContainsPair, Currency, Defensive,
 	Get, Imbalance, OnUnbalanced, OriginTrait,
@@ -192,6 +193,24 @@ where
 	}
 }
 
+/// Information about the block being authored for `pallet_collator_selection`, taken from Aura
+/// and from the storage proof recorded by the node.
+///
+/// The slot is read from `pallet_aura::CurrentSlot` when the block is finalized, after Aura
+/// stored the slot of the block. The proof size is only known when the node records the storage
+/// proof, as collators and nodes with proof recording on import do.
+pub struct AuraAuthoringInfo<T>(PhantomData<T>);
+impl<T: pallet_aura::Config> pallet_collator_selection::AuthoringInfo for AuraAuthoringInfo<T> {
+	fn current_slot() -> Option<u64> {
+		Some(*pallet_aura::CurrentSlot::<T>::get())
+	}
+
+	fn proof_size() -> Option<u64> {
+		let proof_size = storage_proof_size::storage_proof_size();
+		(proof_size != PROOF_RECORDING_DISABLED).then_some(proof_size)
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -290,6 +309,7 @@ mod tests {
 		type ValidatorId = <Self as frame_system::Config>::AccountId;
 		type ValidatorIdOf = IdentityCollator;
 		type ValidatorRegistration = IsRegistered;
+		type AuthoringInfo = ();
 		type KickThreshold = ();
 		type WeightInfo = ();
 	}
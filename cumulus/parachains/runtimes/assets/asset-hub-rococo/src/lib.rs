@@ -112,6 +112,7 @@ use xcm::{
 use xcm_runtime_apis::{
 	dry_run::{CallDryRunEffects, Error as XcmDryRunApiError, XcmDryRunEffects},
 	fees::Error as XcmPaymentApiError,
+	weight_breakdown::InstructionWeight,
 };
 
 #[cfg(feature = "runtime-benchmarks")]
@@ -879,6 +880,7 @@ impl pallet_collator_selection::Config for Runtime {
 	type ValidatorId = <Self as frame_system::Config>::AccountId;
 	type ValidatorIdOf = pallet_collator_selection::IdentityCollator;
 	type ValidatorRegistration = Session;
+	type AuthoringInfo = parachains_common::impls::AuraAuthoringInfo<Runtime>;
 	type WeightInfo = weights::pallet_collator_selection::WeightInfo<Runtime>;
 }
 
@@ -1583,6 +1585,12 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl xcm_runtime_apis::weight_breakdown::XcmWeightBreakdownApi<Block> for Runtime {
+		fn query_xcm_weight_breakdown(message: VersionedXcm<()>) -> Result<Vec<InstructionWeight>, XcmPaymentApiError> {
+			PolkadotXcm::query_xcm_weight_breakdown::<weights::xcm::AssetHubRococoXcmWeight<RuntimeCall>>(message)
+		}
+	}
+
 	impl xcm_runtime_apis::dry_run::DryRunApi<Block, RuntimeCall, RuntimeEvent, OriginCaller> for Runtime {
 		fn dry_run_call(origin: OriginCaller, call: RuntimeCall, result_xcms_version: XcmVersion) -> Result<CallDryRunEffects<RuntimeEvent>, XcmDryRunApiError> {
 			PolkadotXcm::dry_run_call::<Runtime, xcm_config::XcmRouter, OriginCaller, RuntimeCall>(origin, call, result_xcms_version)
@@ -1605,6 +1613,12 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl pallet_collator_selection::runtime_api::CollatorSelectionApi<Block, AccountId> for Runtime {
+		fn collator_scores() -> Vec<pallet_collator_selection::CollatorScore<AccountId>> {
+			CollatorSelection::collator_scores()
+		}
+	}
+
 	impl cumulus_primitives_core::CollectCollationInfo<Block> for Runtime {
 		fn collect_collation_info(header: &<Block as BlockT>::Header) -> cumulus_primitives_core::CollationInfo {
 			ParachainSystem::collect_collation_info(header)
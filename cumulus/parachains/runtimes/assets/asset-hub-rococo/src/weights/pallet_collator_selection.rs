@@ -277,4 +277,13 @@ impl<T: frame_system::Config> pallet_collator_selection::WeightInfo for WeightIn
 			.saturating_add(Weight::from_parts(0, 2519).saturating_mul(c.into()))
 			.saturating_add(Weight::from_parts(0, 2603).saturating_mul(r.into()))
 	}
+	/// Storage: `CollatorSelection::Scoring` (r:0 w:1)
+	/// Proof: `CollatorSelection::Scoring` (`max_values`: Some(1), `max_size`: Some(32), added: 527, mode: `MaxEncodedLen`)
+	fn set_scoring_parameters() -> Weight {
+		// Not benchmarked on this runtime yet. The weight of `set_desired_candidates` is used
+		// instead: it is measured and also only writes one small value.
+		Weight::from_parts(5_554_000, 0)
+			.saturating_add(Weight::from_parts(0, 0))
+			.saturating_add(T::DbWeight::get().writes(1))
+	}
 }
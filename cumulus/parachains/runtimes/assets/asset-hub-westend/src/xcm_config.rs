@@ -464,7 +464,8 @@ impl xcm_executor::Config for XcmConfig {
 	type MessageExporter = ();
 	type UniversalAliases =
 		(bridging::to_rococo::UniversalAliases, bridging::to_ethereum::UniversalAliases);
-	type CallDispatcher = RuntimeCall;
+	type CallDispatcher =
+		cumulus_pallet_weight_reclaim::StorageWeightReclaimDispatcher<RuntimeCall>;
 	type SafeCallFilter = Everything;
 	type Aliasers = TrustedAliasers;
 	type TransactionalProcessor = FrameTransactionalProcessor;
@@ -1677,6 +1677,29 @@ fn weight_of_message_increases_when_dealing_with_erc20s() {
 	assert_eq!(weight, crate::xcm_config::ERC20TransferGasLimit::get());
 }
 
+#[test]
+fn xcm_weight_breakdown_adds_up_to_the_message_weight() {
+	use xcm_runtime_apis::{
+		fees::runtime_decl_for_xcm_payment_api::XcmPaymentApiV2,
+		weight_breakdown::{
+			runtime_decl_for_xcm_weight_breakdown_api::XcmWeightBreakdownApiV1, WeightSource,
+		},
+	};
+	let message = Xcm::<()>::builder_unsafe()
+		.withdraw_asset((Parent, 100u128))
+		.set_appendix(Xcm::builder_unsafe().clear_origin().build())
+		.deposit_asset(AllCounted(1), Here)
+		.build();
+	let versioned = VersionedXcm::<()>::V5(message);
+
+	let breakdown = Runtime::query_xcm_weight_breakdown(versioned.clone()).unwrap();
+	assert_eq!(breakdown.len(), 3);
+	assert!(breakdown.iter().all(|i| i.source == WeightSource::Benchmarked));
+	assert_eq!(breakdown[1].additional_weight, breakdown[1].nested[0].total());
+	let total = breakdown.iter().fold(Weight::zero(), |acc, i| acc.saturating_add(i.total()));
+	assert_eq!(Runtime::query_xcm_weight(versioned), Ok(total));
+}
+
 #[test]
 fn withdraw_and_deposit_erc20s() {
 	let sender: AccountId = ALICE.into();
@@ -97,6 +97,7 @@ use xcm::{latest::prelude::*, prelude::*, Version as XcmVersion};
 use xcm_runtime_apis::{
 	dry_run::{CallDryRunEffects, Error as XcmDryRunApiError, XcmDryRunEffects},
 	fees::Error as XcmPaymentApiError,
+	weight_breakdown::InstructionWeight,
 };
 
 use weights::{BlockExecutionWeight, ExtrinsicBaseWeight, RocksDbWeight};
@@ -538,6 +539,7 @@ impl pallet_collator_selection::Config for Runtime {
 	type ValidatorId = <Self as frame_system::Config>::AccountId;
 	type ValidatorIdOf = pallet_collator_selection::IdentityCollator;
 	type ValidatorRegistration = Session;
+	type AuthoringInfo = parachains_common::impls::AuraAuthoringInfo<Runtime>;
 	type WeightInfo = weights::pallet_collator_selection::WeightInfo<Runtime>;
 }
 
@@ -888,6 +890,12 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl xcm_runtime_apis::weight_breakdown::XcmWeightBreakdownApi<Block> for Runtime {
+		fn query_xcm_weight_breakdown(message: VersionedXcm<()>) -> Result<Vec<InstructionWeight>, XcmPaymentApiError> {
+			PolkadotXcm::query_xcm_weight_breakdown::<weights::xcm::BridgeHubRococoXcmWeight<RuntimeCall>>(message)
+		}
+	}
+
 	impl xcm_runtime_apis::dry_run::DryRunApi<Block, RuntimeCall, RuntimeEvent, OriginCaller> for Runtime {
 		fn dry_run_call(origin: OriginCaller, call: RuntimeCall, result_xcms_version: XcmVersion) -> Result<CallDryRunEffects<RuntimeEvent>, XcmDryRunApiError> {
 			PolkadotXcm::dry_run_call::<Runtime, xcm_config::XcmRouter, OriginCaller, RuntimeCall>(origin, call, result_xcms_version)
@@ -910,6 +918,12 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl pallet_collator_selection::runtime_api::CollatorSelectionApi<Block, AccountId> for Runtime {
+		fn collator_scores() -> Vec<pallet_collator_selection::CollatorScore<AccountId>> {
+			CollatorSelection::collator_scores()
+		}
+	}
+
 	impl cumulus_primitives_core::CollectCollationInfo<Block> for Runtime {
 		fn collect_collation_info(header: &<Block as BlockT>::Header) -> cumulus_primitives_core::CollationInfo {
 			ParachainSystem::collect_collation_info(header)
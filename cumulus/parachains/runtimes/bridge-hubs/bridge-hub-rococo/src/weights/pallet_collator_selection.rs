@@ -277,4 +277,13 @@ impl<T: frame_system::Config> pallet_collator_selection::WeightInfo for WeightIn
 			.saturating_add(Weight::from_parts(0, 2519).saturating_mul(c.into()))
 			.saturating_add(Weight::from_parts(0, 2603).saturating_mul(r.into()))
 	}
+	/// Storage: `CollatorSelection::Scoring` (r:0 w:1)
+	/// Proof: `CollatorSelection::Scoring` (`max_values`: Some(1), `max_size`: Some(32), added: 527, mode: `MaxEncodedLen`)
+	fn set_scoring_parameters() -> Weight {
+		// No benchmark results for this call yet; it borrows the measured weight of
+		// `set_desired_candidates`, the other call that only puts one value.
+		Weight::from_parts(5_704_000, 0)
+			.saturating_add(Weight::from_parts(0, 0))
+			.saturating_add(T::DbWeight::get().writes(1))
+	}
 }
@@ -277,4 +277,13 @@ impl<T: frame_system::Config> pallet_collator_selection::WeightInfo for WeightIn
 			.saturating_add(Weight::from_parts(0, 2519).saturating_mul(c.into()))
 			.saturating_add(Weight::from_parts(0, 2603).saturating_mul(r.into()))
 	}
+	/// Storage: `CollatorSelection::Scoring` (r:0 w:1)
+	/// Proof: `CollatorSelection::Scoring` (`max_values`: Some(1), `max_size`: Some(32), added: 527, mode: `MaxEncodedLen`)
+	fn set_scoring_parameters() -> Weight {
+		// Estimate, not a measurement: `set_desired_candidates` performs the same single write,
+		// so its benchmarked weight stands in until this call is benchmarked here.
+		Weight::from_parts(5_640_000, 0)
+			.saturating_add(Weight::from_parts(0, 0))
+			.saturating_add(T::DbWeight::get().writes(1))
+	}
 }
@@ -277,4 +277,13 @@ impl<T: frame_system::Config> pallet_collator_selection::WeightInfo for WeightIn
 			.saturating_add(Weight::from_parts(0, 2519).saturating_mul(c.into()))
 			.saturating_add(Weight::from_parts(0, 2603).saturating_mul(r.into()))
 	}
+	/// Storage: `CollatorSelection::Scoring` (r:0 w:1)
+	/// Proof: `CollatorSelection::Scoring` (`max_values`: Some(1), `max_size`: Some(32), added: 527, mode: `MaxEncodedLen`)
+	fn set_scoring_parameters() -> Weight {
+		// Unmeasured. Taken over from `set_desired_candidates` (one write, no reads) until
+		// `set_scoring_parameters` is benchmarked on this chain.
+		Weight::from_parts(5_863_000, 0)
+			.saturating_add(Weight::from_parts(0, 0))
+			.saturating_add(T::DbWeight::get().writes(1))
+	}
 }
@@ -223,6 +223,15 @@ pub struct Cli<Config: CliConfig> {
 	#[arg(long)]
 	pub enable_statement_store: bool,
 
+	/// Include the extrinsics that were submitted encrypted to the Aura key of this collator.
+	///
+	/// The extrinsics are received through the statement store and decrypted with the `stmt`
+	/// key that has the same public key as the Aura key. The runtime has to provide the
+	/// `EncryptedExtrinsicsApi` and to use `ed25519` Aura keys, the node refuses to start with
+	/// `sr25519` Aura keys.
+	#[arg(long, requires = "enable_statement_store")]
+	pub enable_encrypted_extrinsics: bool,
+
 	#[arg(skip)]
 	pub(crate) _phantom: PhantomData<Config>,
 }
@@ -268,6 +277,7 @@ impl<Config: CliConfig> Cli<Config> {
 			export_pov: self.export_pov_to_path.clone(),
 			max_pov_percentage: self.run.experimental_max_pov_percentage,
 			enable_statement_store: self.enable_statement_store,
+			enable_encrypted_extrinsics: self.enable_encrypted_extrinsics,
 			storage_monitor: self.storage_monitor.clone(),
 		}
 	}
@@ -358,7 +368,7 @@ impl<Config: CliConfig> RelayChainCli<Config> {
 		let base = FromArgMatches::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
 
 		let extension = Extensions::try_get(&*para_config.chain_spec);
-		let chain_id = extension.map(|e| e.relay_chain());
+		let chain_id = extension.and_then(|e| e.relay_chain());
 
 		let base_path = para_config.base_path.path().join("polkadot");
 		Self { base, chain_id, base_path: Some(base_path), _phantom: Default::default() }
@@ -20,7 +20,7 @@ use crate::{
 		chain_spec::LoadSpec,
 		runtime::{
 			AuraConsensusId, Consensus, Runtime, RuntimeResolver as RuntimeResolverT,
-			RuntimeResolver,
+			RuntimeResolver, SolochainConsensus,
 		},
 		spec::DynNodeSpec,
 		types::Block,
@@ -28,6 +28,7 @@ use crate::{
 	},
 	extra_subcommand::DefaultExtraSubcommands,
 	fake_runtime_api,
+	node_extension::{NoNodeExtension, NodeExtension},
 	runtime::BlockNumber,
 };
 use clap::{CommandFactory, FromArgMatches};
@@ -58,41 +59,73 @@ impl RunConfig {
 	}
 }
 
-pub fn new_aura_node_spec<Block>(
+pub fn new_aura_node_spec<Block, Extension>(
 	aura_id: AuraConsensusId,
 	extra_args: &NodeExtraArgs,
-) -> Box<dyn DynNodeSpec>
+) -> Box<dyn DynNodeSpec<Extension>>
 where
 	Block: NodeBlock,
+	Extension: NodeExtension,
 {
 	match aura_id {
 		AuraConsensusId::Sr25519 => crate::nodes::aura::new_aura_node_spec::<
 			Block,
 			fake_runtime_api::aura_sr25519::RuntimeApi,
 			sp_consensus_aura::sr25519::AuthorityId,
+			Extension,
 		>(extra_args),
 		AuraConsensusId::Ed25519 => crate::nodes::aura::new_aura_node_spec::<
 			Block,
 			fake_runtime_api::aura_ed25519::RuntimeApi,
 			sp_consensus_aura::ed25519::AuthorityId,
+			Extension,
 		>(extra_args),
 	}
 }
 
-fn new_node_spec(
+pub fn new_solochain_node_spec<Block, Extension>(
+	consensus: SolochainConsensus,
+) -> std::result::Result<Box<dyn DynNodeSpec<Extension>>, sc_cli::Error>
+where
+	Block: NodeBlock,
+	Extension: NodeExtension,
+{
+	Ok(match consensus {
+		SolochainConsensus::AuraGrandpa(AuraConsensusId::Sr25519) =>
+			crate::nodes::solochain::new_aura_grandpa_node_spec::<
+				Block,
+				fake_runtime_api::aura_grandpa::RuntimeApi,
+				sp_consensus_aura::sr25519::AuthorityId,
+				Extension,
+			>(),
+		SolochainConsensus::AuraGrandpa(AuraConsensusId::Ed25519) =>
+			return Err("Solochains with Ed25519 Aura authorities are not supported".into()),
+		SolochainConsensus::BabeGrandpa => crate::nodes::solochain::new_babe_grandpa_node_spec::<
+			Block,
+			fake_runtime_api::babe_grandpa::RuntimeApi,
+			Extension,
+		>(),
+	})
+}
+
+fn new_node_spec<Extension: NodeExtension>(
 	config: &sc_service::Configuration,
 	runtime_resolver: &Box<dyn RuntimeResolverT>,
 	extra_args: &NodeExtraArgs,
-) -> std::result::Result<Box<dyn DynNodeSpec>, sc_cli::Error> {
+) -> std::result::Result<Box<dyn DynNodeSpec<Extension>>, sc_cli::Error> {
 	let runtime = runtime_resolver.runtime(config.chain_spec.as_ref())?;
 
 	Ok(match runtime {
 		Runtime::Omni(block_number, consensus) => match (block_number, consensus) {
 			(BlockNumber::U32, Consensus::Aura(aura_id)) =>
-				new_aura_node_spec::<Block<u32>>(aura_id, extra_args),
+				new_aura_node_spec::<Block<u32>, Extension>(aura_id, extra_args),
 			(BlockNumber::U64, Consensus::Aura(aura_id)) =>
-				new_aura_node_spec::<Block<u64>>(aura_id, extra_args),
+				new_aura_node_spec::<Block<u64>, Extension>(aura_id, extra_args),
 		},
+		Runtime::Solochain(BlockNumber::U32, consensus) =>
+			new_solochain_node_spec::<Block<u32>, Extension>(consensus)?,
+		Runtime::Solochain(BlockNumber::U64, consensus) =>
+			new_solochain_node_spec::<Block<u64>, Extension>(consensus)?,
 	})
 }
 
@@ -121,9 +154,34 @@ pub fn run_with_custom_cli<CliConfig, ExtraSubcommand>(cmd_config: RunConfig) ->
 where
 	CliConfig: crate::cli::CliConfig,
 	ExtraSubcommand: crate::extra_subcommand::ExtraSubcommand,
+{
+	run_with_extension::<CliConfig, ExtraSubcommand, NoNodeExtension>(cmd_config)
+}
+
+/// Same as [`run_with_custom_cli`], but additionally extends the node with a
+/// [`NodeExtension`].
+///
+/// The arguments of the `Extension` are added to the node CLI. They are parsed before the node is
+/// started and the resulting value is used to register the RPC modules and to spawn the services
+/// of the extension.
+///
+/// # Type Parameters
+/// * `CliConfig` – customization trait supplying user‑facing info (name, description, version) for
+///   the binary.
+/// * `ExtraSubcommand` – an implementation of `ExtraSubcommand`.
+/// * `Extension` – an implementation of `NodeExtension`. Use *`NoNodeExtension`* if the node should
+///   not be extended.
+pub fn run_with_extension<CliConfig, ExtraSubcommand, Extension>(
+	cmd_config: RunConfig,
+) -> Result<()>
+where
+	CliConfig: crate::cli::CliConfig,
+	ExtraSubcommand: crate::extra_subcommand::ExtraSubcommand,
+	Extension: NodeExtension,
 {
 	let cli_command = Cli::<CliConfig>::command();
 	let cli_command = ExtraSubcommand::augment_subcommands(cli_command);
+	let cli_command = Extension::augment_args(cli_command);
 	let cli_command = Cli::<CliConfig>::setup_command(cli_command);
 
 	// Get matches for all CLI, including extra args.
@@ -141,6 +199,8 @@ where
 	let mut cli =
 		Cli::<CliConfig>::from_arg_matches(&matches).map_err(|e| sc_cli::Error::Cli(e.into()))?;
 	cli.chain_spec_loader = Some(cmd_config.chain_spec_loader);
+	let extension =
+		Extension::from_arg_matches(&matches).map_err(|e| sc_cli::Error::Cli(e.into()))?;
 
 	#[allow(deprecated)]
 	match &cli.subcommand {
@@ -151,40 +211,55 @@ where
 		Some(Subcommand::CheckBlock(cmd)) => {
 			let runner = cli.create_runner(cmd)?;
 			runner.async_run(|config| {
-				let node =
-					new_node_spec(&config, &cmd_config.runtime_resolver, &cli.node_extra_args())?;
+				let node = new_node_spec::<Extension>(
+					&config,
+					&cmd_config.runtime_resolver,
+					&cli.node_extra_args(),
+				)?;
 				node.prepare_check_block_cmd(config, cmd)
 			})
 		},
 		Some(Subcommand::ExportBlocks(cmd)) => {
 			let runner = cli.create_runner(cmd)?;
 			runner.async_run(|config| {
-				let node =
-					new_node_spec(&config, &cmd_config.runtime_resolver, &cli.node_extra_args())?;
+				let node = new_node_spec::<Extension>(
+					&config,
+					&cmd_config.runtime_resolver,
+					&cli.node_extra_args(),
+				)?;
 				node.prepare_export_blocks_cmd(config, cmd)
 			})
 		},
 		Some(Subcommand::ExportState(cmd)) => {
 			let runner = cli.create_runner(cmd)?;
 			runner.async_run(|config| {
-				let node =
-					new_node_spec(&config, &cmd_config.runtime_resolver, &cli.node_extra_args())?;
+				let node = new_node_spec::<Extension>(
+					&config,
+					&cmd_config.runtime_resolver,
+					&cli.node_extra_args(),
+				)?;
 				node.prepare_export_state_cmd(config, cmd)
 			})
 		},
 		Some(Subcommand::ImportBlocks(cmd)) => {
 			let runner = cli.create_runner(cmd)?;
 			runner.async_run(|config| {
-				let node =
-					new_node_spec(&config, &cmd_config.runtime_resolver, &cli.node_extra_args())?;
+				let node = new_node_spec::<Extension>(
+					&config,
+					&cmd_config.runtime_resolver,
+					&cli.node_extra_args(),
+				)?;
 				node.prepare_import_blocks_cmd(config, cmd)
 			})
 		},
 		Some(Subcommand::Revert(cmd)) => {
 			let runner = cli.create_runner(cmd)?;
 			runner.async_run(|config| {
-				let node =
-					new_node_spec(&config, &cmd_config.runtime_resolver, &cli.node_extra_args())?;
+				let node = new_node_spec::<Extension>(
+					&config,
+					&cmd_config.runtime_resolver,
+					&cli.node_extra_args(),
+				)?;
 				node.prepare_revert_cmd(config, cmd)
 			})
 		},
@@ -210,8 +285,11 @@ where
 		Some(Subcommand::ExportGenesisHead(cmd)) => {
 			let runner = cli.create_runner(cmd)?;
 			runner.sync_run(|config| {
-				let node =
-					new_node_spec(&config, &cmd_config.runtime_resolver, &cli.node_extra_args())?;
+				let node = new_node_spec::<Extension>(
+					&config,
+					&cmd_config.runtime_resolver,
+					&cli.node_extra_args(),
+				)?;
 				node.run_export_genesis_head_cmd(config, cmd)
 			})
 		},
@@ -241,7 +319,7 @@ where
 					// chain spec, given via the `--chain` flag.
 					let runner = cli.create_runner(cmd)?;
 					runner.sync_run(|config| {
-						let node = new_node_spec(
+						let node = new_node_spec::<Extension>(
 							&config,
 							&cmd_config.runtime_resolver,
 							&cli.node_extra_args(),
@@ -256,7 +334,7 @@ where
 					// the `--chain` flag to be passed.
 					let runner = cli.create_runner(cmd)?;
 					runner.sync_run(|config| {
-						let node = new_node_spec(
+						let node = new_node_spec::<Extension>(
 							&config,
 							&cmd_config.runtime_resolver,
 							&cli.node_extra_args(),
@@ -285,8 +363,6 @@ where
 		Some(Subcommand::Key(cmd)) => Ok(cmd.run(&cli)?),
 		None => {
 			let runner = cli.create_runner(&cli.run.normalize())?;
-			let polkadot_cli =
-				RelayChainCli::<CliConfig>::new(runner.config(), cli.relay_chain_args.iter());
 			let collator_options = cli.run.collator_options();
 
 			if cli.experimental_use_slot_based {
@@ -298,11 +374,16 @@ where
 			}
 
 			runner.run_node_until_exit(|config| async move {
-				let node_spec =
-					new_node_spec(&config, &cmd_config.runtime_resolver, &cli.node_extra_args())?;
+				let node_spec = new_node_spec::<Extension>(
+					&config,
+					&cmd_config.runtime_resolver,
+					&cli.node_extra_args(),
+				)?;
 
 				if let Some(dev_mode) = cli.dev_mode() {
-					return node_spec.start_dev_node(config, dev_mode).map_err(Into::into);
+					return node_spec
+						.start_dev_node(config, dev_mode, extension)
+						.map_err(Into::into);
 				}
 
 				// If Statemint (Statemine, Westmint, Rockmine) DB exists and we're using the
@@ -355,10 +436,25 @@ where
 						})
 					})
 					.flatten();
-				let tokio_handle = config.tokio_handle.clone();
-				let polkadot_config =
-					SubstrateCli::create_configuration(&polkadot_cli, &polkadot_cli, tokio_handle)
-						.map_err(|err| format!("Relay chain argument error: {}", err))?;
+				let polkadot_config = if node_spec.requires_relay_chain() {
+					let polkadot_cli =
+						RelayChainCli::<CliConfig>::new(&config, cli.relay_chain_args.iter());
+					let tokio_handle = config.tokio_handle.clone();
+					let polkadot_config = SubstrateCli::create_configuration(
+						&polkadot_cli,
+						&polkadot_cli,
+						tokio_handle,
+					)
+					.map_err(|err| format!("Relay chain argument error: {}", err))?;
+					Some(polkadot_config)
+				} else {
+					if !cli.relay_chain_args.is_empty() {
+						log::warn!(
+							"The chain has no relay chain, ignoring the relay chain arguments"
+						);
+					}
+					None
+				};
 
 				info!("✍️ Is collating: {}", if config.role.is_authority() { "yes" } else { "no" });
 
@@ -369,6 +465,7 @@ where
 						collator_options,
 						hwbench,
 						cli.node_extra_args(),
+						extension,
 					)
 					.await
 					.map_err(Into::into)
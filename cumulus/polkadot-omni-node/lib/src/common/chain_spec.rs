@@ -42,8 +42,15 @@ pub struct Extensions {
 	/// The relay chain of the Parachain. It is kept here only for compatibility reasons until
 	/// people migrate to using the new `Extensions` struct and associated logic in the node
 	/// corresponding to pulling the parachain id from the runtime.
-	#[serde(alias = "relayChain", alias = "RelayChain")]
-	relay_chain: String,
+	///
+	/// Chain specs of solochains don't have a relay chain.
+	#[serde(
+		alias = "relayChain",
+		alias = "RelayChain",
+		default,
+		skip_serializing_if = "Option::is_none"
+	)]
+	relay_chain: Option<String>,
 	/// The id of the Parachain.
 	#[serde(alias = "paraId", alias = "ParaId")]
 	para_id: Option<u32>,
@@ -57,12 +64,12 @@ impl Extensions {
 
 	/// Create the extensions only with the relay_chain.
 	pub fn new_with_relay_chain(relay_chain: String) -> Self {
-		Extensions { relay_chain, para_id: None }
+		Extensions { relay_chain: Some(relay_chain), para_id: None }
 	}
 
 	/// Initialize extensions based on given parameters.
 	pub fn new(relay_chain: String, para_id: u32) -> Self {
-		Extensions { relay_chain, para_id: Some(para_id) }
+		Extensions { relay_chain: Some(relay_chain), para_id: Some(para_id) }
 	}
 
 	/// Para id field getter
@@ -71,7 +78,7 @@ impl Extensions {
 	}
 
 	/// Relay chain field getter
-	pub fn relay_chain(&self) -> String {
+	pub fn relay_chain(&self) -> Option<String> {
 		self.relay_chain.clone()
 	}
 }
@@ -96,7 +103,27 @@ mod tests {
 		let missing_paraid_extension: Extensions = serde_json::from_str(para_id_missing).unwrap();
 		assert_eq!(camel_case_extension, snake_case_extension);
 		assert_eq!(snake_case_extension, pascal_case_extension);
-		assert_eq!(missing_paraid_extension.relay_chain, "westend".to_string());
+		assert_eq!(missing_paraid_extension.relay_chain, Some("westend".to_string()));
 		assert!(missing_paraid_extension.para_id.is_none());
 	}
+
+	#[test]
+	fn can_load_solochain_spec_without_extensions() {
+		let spec = r#"{
+			"name": "Solochain",
+			"id": "solochain",
+			"chainType": "Local",
+			"bootNodes": [],
+			"telemetryEndpoints": null,
+			"protocolId": null,
+			"properties": null,
+			"codeSubstitutes": {},
+			"genesis": { "raw": { "top": {}, "childrenDefault": {} } }
+		}"#;
+
+		let spec = GenericChainSpec::from_json_bytes(spec.as_bytes().to_vec()).unwrap();
+		let extension = Extensions::try_get(&spec).unwrap();
+		assert!(extension.relay_chain().is_none());
+		assert!(extension.para_id().is_none());
+	}
 }
@@ -26,10 +26,14 @@ pub mod runtime;
 pub mod spec;
 pub(crate) mod statement_store;
 pub mod types;
+pub(crate) mod upgrade_preflight;
 
 use crate::cli::AuthoringPolicy;
 
-use cumulus_primitives_core::{CollectCollationInfo, GetParachainInfo, RelayParentOffsetApi};
+use cumulus_primitives_core::{
+	CollectCollationInfo, EncryptedExtrinsicsApi, GetParachainInfo, RelayParentOffsetApi,
+	UpgradePreflightApi,
+};
 use sc_client_db::DbHash;
 use sc_offchain::OffchainWorkerApi;
 use serde::de::DeserializeOwned;
@@ -75,6 +79,8 @@ pub trait NodeRuntimeApi<Block: BlockT>:
 	+ ValidateStatement<Block>
 	+ GetParachainInfo<Block>
 	+ RelayParentOffsetApi<Block>
+	+ UpgradePreflightApi<Block>
+	+ EncryptedExtrinsicsApi<Block>
 	+ Sized
 {
 }
@@ -87,6 +93,8 @@ impl<T, Block: BlockT> NodeRuntimeApi<Block> for T where
 		+ TaggedTransactionQueue<Block>
 		+ OffchainWorkerApi<Block>
 		+ RelayParentOffsetApi<Block>
+		+ UpgradePreflightApi<Block>
+		+ EncryptedExtrinsicsApi<Block>
 		+ CollectCollationInfo<Block>
 		+ ValidateStatement<Block>
 		+ GetParachainInfo<Block>
@@ -127,6 +135,9 @@ pub struct NodeExtraArgs {
 	/// If true then the statement store will be enabled.
 	pub enable_statement_store: bool,
 
+	/// If true then extrinsics that were submitted encrypted to the collator are included.
+	pub enable_encrypted_extrinsics: bool,
+
 	/// Parameters for storage monitoring.
 	pub storage_monitor: sc_storage_monitor::StorageMonitorParams,
 }
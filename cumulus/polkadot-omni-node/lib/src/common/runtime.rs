@@ -29,9 +29,15 @@ use subxt_metadata::{Metadata, StorageEntryType};
 pub const DEFAULT_PARACHAIN_SYSTEM_PALLET_NAME: &str = "ParachainSystem";
 /// Expected frame system pallet runtime type name.
 pub const DEFAULT_FRAME_SYSTEM_PALLET_NAME: &str = "System";
+/// Name of the runtime API exposed by Aura runtimes.
+pub const AURA_RUNTIME_API_NAME: &str = "AuraApi";
+/// Name of the runtime API exposed by BABE runtimes.
+pub const BABE_RUNTIME_API_NAME: &str = "BabeApi";
+/// Name of the runtime API exposed by GRANDPA runtimes.
+pub const GRANDPA_RUNTIME_API_NAME: &str = "GrandpaApi";
 
 /// The Aura ID used by the Aura consensus
-#[derive(PartialEq)]
+#[derive(PartialEq, Debug)]
 pub enum AuraConsensusId {
 	/// Ed25519
 	Ed25519,
@@ -46,6 +52,24 @@ pub enum Consensus {
 	Aura(AuraConsensusId),
 }
 
+/// The choice of consensus for a solochain run by the omni-node.
+#[derive(PartialEq, Debug)]
+pub enum SolochainConsensus {
+	/// Aura block production with GRANDPA finality.
+	AuraGrandpa(AuraConsensusId),
+	/// BABE block production with GRANDPA finality.
+	BabeGrandpa,
+}
+
+impl Display for SolochainConsensus {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			SolochainConsensus::AuraGrandpa(_) => write!(f, "Aura + GRANDPA"),
+			SolochainConsensus::BabeGrandpa => write!(f, "BABE + GRANDPA"),
+		}
+	}
+}
+
 /// The choice of block number for the parachain omni-node.
 #[derive(PartialEq, Debug)]
 pub enum BlockNumber {
@@ -89,6 +113,9 @@ pub enum Runtime {
 	/// None of the system-chain runtimes, rather the node will act agnostic to the runtime ie. be
 	/// an omni-node, and simply run a node with the given consensus algorithm.
 	Omni(BlockNumber, Consensus),
+	/// A standalone chain that doesn't include the parachain system pallet. The node runs it
+	/// without a relay chain, using the given consensus.
+	Solochain(BlockNumber, SolochainConsensus),
 }
 
 /// Helper trait used for extracting the Runtime variant from the chain spec ID.
@@ -97,8 +124,11 @@ pub trait RuntimeResolver {
 	fn runtime(&self, chain_spec: &dyn ChainSpec) -> sc_cli::Result<Runtime>;
 }
 
-/// Default implementation for `RuntimeResolver` that just returns
-/// `Runtime::Omni(BlockNumber::U32, Consensus::Aura(AuraConsensusId::Sr25519))`.
+/// Default implementation for `RuntimeResolver` that returns `Runtime::Omni` with Aura consensus,
+/// using the block number and the Aura authority crypto found in the runtime metadata.
+///
+/// Falls back to `Runtime::Omni(BlockNumber::U32, Consensus::Aura(AuraConsensusId::Sr25519))` if
+/// the metadata can't be inspected.
 pub struct DefaultRuntimeResolver;
 
 impl RuntimeResolver for DefaultRuntimeResolver {
@@ -122,6 +152,14 @@ impl RuntimeResolver for DefaultRuntimeResolver {
 		};
 
 		if !metadata_inspector.pallet_exists(DEFAULT_PARACHAIN_SYSTEM_PALLET_NAME) {
+			if let Some(consensus) = metadata_inspector.solochain_consensus() {
+				log::info!(
+					"The runtime doesn't include the parachain system pallet. Running it as a \
+					solochain with {consensus} consensus."
+				);
+				return Ok(Runtime::Solochain(block_number, consensus))
+			}
+
 			log::warn!(
 				r#"⚠️  The parachain system pallet (https://docs.rs/crate/cumulus-pallet-parachain-system/latest) is
 			   missing from the runtime’s metadata. Please check Omni Node docs for runtime conventions:
@@ -129,7 +167,7 @@ impl RuntimeResolver for DefaultRuntimeResolver {
 			);
 		}
 
-		Ok(Runtime::Omni(block_number, Consensus::Aura(AuraConsensusId::Sr25519)))
+		Ok(Runtime::Omni(block_number, Consensus::Aura(metadata_inspector.aura_consensus_id())))
 	}
 }
 
@@ -144,6 +182,47 @@ impl MetadataInspector {
 		self.0.pallet_by_name(name).is_some()
 	}
 
+	fn runtime_api_exists(&self, name: &str) -> bool {
+		self.0.runtime_api_trait_by_name(name).is_some()
+	}
+
+	/// The consensus of a solochain runtime, detected from the runtime APIs it exposes.
+	fn solochain_consensus(&self) -> Option<SolochainConsensus> {
+		if !self.runtime_api_exists(GRANDPA_RUNTIME_API_NAME) {
+			return None
+		}
+
+		if self.runtime_api_exists(BABE_RUNTIME_API_NAME) {
+			Some(SolochainConsensus::BabeGrandpa)
+		} else if self.runtime_api_exists(AURA_RUNTIME_API_NAME) {
+			Some(SolochainConsensus::AuraGrandpa(self.aura_consensus_id()))
+		} else {
+			None
+		}
+	}
+
+	/// The crypto of the Aura authorities, detected from the `AuraApi` of the runtime.
+	///
+	/// Defaults to `sr25519` if the authority type can't be inspected.
+	fn aura_consensus_id(&self) -> AuraConsensusId {
+		let types = self.0.types();
+		let authority = self
+			.0
+			.runtime_api_trait_by_name(AURA_RUNTIME_API_NAME)
+			.and_then(|api| api.method_by_name("authorities").map(|method| method.output_ty()))
+			.and_then(|ty_id| types.resolve(ty_id))
+			.and_then(|authorities| match &authorities.type_def {
+				TypeDef::Sequence(sequence) => types.resolve(sequence.type_param.id),
+				_ => None,
+			});
+
+		match authority {
+			Some(authority) if authority.path.segments.iter().any(|s| s == "ed25519") =>
+				AuraConsensusId::Ed25519,
+			_ => AuraConsensusId::Sr25519,
+		}
+	}
+
 	fn block_number(&self) -> Option<BlockNumber> {
 		let pallet_metadata = self.0.pallet_by_name(DEFAULT_FRAME_SYSTEM_PALLET_NAME);
 		pallet_metadata
@@ -178,26 +257,31 @@ impl MetadataInspector {
 #[cfg(test)]
 mod tests {
 	use crate::runtime::{
-		BlockNumber, MetadataInspector, DEFAULT_FRAME_SYSTEM_PALLET_NAME,
-		DEFAULT_PARACHAIN_SYSTEM_PALLET_NAME,
+		AuraConsensusId, BlockNumber, MetadataInspector, SolochainConsensus, AURA_RUNTIME_API_NAME,
+		BABE_RUNTIME_API_NAME, DEFAULT_FRAME_SYSTEM_PALLET_NAME,
+		DEFAULT_PARACHAIN_SYSTEM_PALLET_NAME, GRANDPA_RUNTIME_API_NAME,
 	};
 	use codec::Decode;
 	use cumulus_client_service::ParachainHostFunctions;
 	use sc_executor::WasmExecutor;
 	use sc_runtime_utilities::fetch_latest_metadata_from_code_blob;
 
-	fn cumulus_test_runtime_metadata() -> subxt_metadata::Metadata {
+	fn runtime_metadata(code: &'static [u8]) -> subxt_metadata::Metadata {
 		let opaque_metadata = fetch_latest_metadata_from_code_blob(
 			&WasmExecutor::<ParachainHostFunctions>::builder()
 				.with_allow_missing_host_functions(true)
 				.build(),
-			sp_runtime::Cow::Borrowed(cumulus_test_runtime::WASM_BINARY.unwrap()),
+			sp_runtime::Cow::Borrowed(code),
 		)
 		.unwrap();
 
 		subxt_metadata::Metadata::decode(&mut (*opaque_metadata).as_slice()).unwrap()
 	}
 
+	fn cumulus_test_runtime_metadata() -> subxt_metadata::Metadata {
+		runtime_metadata(cumulus_test_runtime::WASM_BINARY.unwrap())
+	}
+
 	#[test]
 	fn test_pallet_exists() {
 		let metadata_inspector = MetadataInspector(cumulus_test_runtime_metadata());
@@ -210,4 +294,48 @@ mod tests {
 		let metadata_inspector = MetadataInspector(cumulus_test_runtime_metadata());
 		assert_eq!(metadata_inspector.block_number().unwrap(), BlockNumber::U32);
 	}
+
+	#[test]
+	fn test_solochain_consensus() {
+		let metadata_inspector = MetadataInspector(cumulus_test_runtime_metadata());
+		assert!(metadata_inspector.runtime_api_exists(AURA_RUNTIME_API_NAME));
+		assert!(!metadata_inspector.runtime_api_exists(BABE_RUNTIME_API_NAME));
+		assert!(!metadata_inspector.runtime_api_exists(GRANDPA_RUNTIME_API_NAME));
+		// Aura without GRANDPA is not a solochain the omni-node knows how to run.
+		assert_eq!(metadata_inspector.solochain_consensus(), None);
+	}
+
+	#[test]
+	fn test_aura_consensus_id() {
+		let metadata_inspector = MetadataInspector(cumulus_test_runtime_metadata());
+		assert_eq!(metadata_inspector.aura_consensus_id(), AuraConsensusId::Sr25519);
+
+		let metadata_inspector = MetadataInspector(runtime_metadata(
+			cumulus_test_runtime::encrypted_extrinsics::WASM_BINARY.unwrap(),
+		));
+		assert_eq!(metadata_inspector.aura_consensus_id(), AuraConsensusId::Ed25519);
+	}
+
+	#[test]
+	fn test_aura_grandpa_solochain_is_detected() {
+		let metadata_inspector =
+			MetadataInspector(runtime_metadata(solochain_template_runtime::WASM_BINARY.unwrap()));
+		assert!(!metadata_inspector.pallet_exists(DEFAULT_PARACHAIN_SYSTEM_PALLET_NAME));
+		assert_eq!(
+			metadata_inspector.solochain_consensus(),
+			Some(SolochainConsensus::AuraGrandpa(AuraConsensusId::Sr25519))
+		);
+		assert_eq!(metadata_inspector.block_number().unwrap(), BlockNumber::U32);
+	}
+
+	#[test]
+	fn test_babe_grandpa_solochain_is_detected() {
+		let metadata_inspector =
+			MetadataInspector(runtime_metadata(substrate_test_runtime::wasm_binary_unwrap()));
+		assert!(!metadata_inspector.pallet_exists(DEFAULT_PARACHAIN_SYSTEM_PALLET_NAME));
+		// BABE is preferred by runtimes that expose both BABE and Aura.
+		assert!(metadata_inspector.runtime_api_exists(AURA_RUNTIME_API_NAME));
+		assert_eq!(metadata_inspector.solochain_consensus(), Some(SolochainConsensus::BabeGrandpa));
+		assert_eq!(metadata_inspector.block_number().unwrap(), BlockNumber::U64);
+	}
 }
@@ -25,12 +25,18 @@ use crate::{
 			ParachainBackend, ParachainBlockImport, ParachainClient, ParachainHostFunctions,
 			ParachainService,
 		},
+		upgrade_preflight::run_upgrade_preflight,
 		ConstructNodeRuntimeApi, NodeBlock, NodeExtraArgs,
 	},
+	node_extension::{NodeExtension, NodeExtensionContext},
 };
 use codec::Encode;
 use cumulus_client_bootnodes::{start_bootnode_tasks, StartBootnodeTasksParams};
 use cumulus_client_cli::CollatorOptions;
+use cumulus_client_pov_recovery::{
+	rpc::{PoVRecoveryApiServer, PoVRecoveryRpc},
+	PoVRecoveryHandle,
+};
 use cumulus_client_service::{
 	build_network, build_relay_chain_interface, prepare_node_config, start_relay_chain_tasks,
 	BuildNetworkParams, CollatorSybilResistance, DARecoveryProfile, ParachainTracingExecuteBlock,
@@ -89,6 +95,7 @@ where
 		relay_chain_interface: Arc<dyn RelayChainInterface>,
 		transaction_pool: Arc<TransactionPoolHandle<Block, ParachainClient<Block, RuntimeApi>>>,
 		keystore: KeystorePtr,
+		statement_store: Option<Arc<Store>>,
 		relay_chain_slot_duration: Duration,
 		para_id: ParaId,
 		collator_key: CollatorPair,
@@ -305,9 +312,10 @@ pub(crate) trait NodeSpec: BaseNodeSpec {
 
 	const SYBIL_RESISTANCE: CollatorSybilResistance;
 
-	fn start_dev_node(
+	fn start_dev_node<Extension: NodeExtension>(
 		_config: Configuration,
 		_mode: DevSealMode,
+		_extension: Extension,
 	) -> sc_service::error::Result<TaskManager> {
 		Err(sc_service::Error::Other("Dev not supported for this node type".into()))
 	}
@@ -315,15 +323,17 @@ pub(crate) trait NodeSpec: BaseNodeSpec {
 	/// Start a node with the given parachain spec.
 	///
 	/// This is the actual implementation that is abstract over the executor and the runtime api.
-	fn start_node<Net>(
+	fn start_node<Net, Extension>(
 		parachain_config: Configuration,
 		polkadot_config: Configuration,
 		collator_options: CollatorOptions,
 		hwbench: Option<sc_sysinfo::HwBench>,
 		node_extra_args: NodeExtraArgs,
+		extension: Extension,
 	) -> Pin<Box<dyn Future<Output = sc_service::error::Result<TaskManager>>>>
 	where
 		Net: NetworkBackend<Self::Block, Hash>,
+		Extension: NodeExtension,
 	{
 		let fut = async move {
 			let parachain_config = prepare_node_config(parachain_config);
@@ -433,19 +443,38 @@ pub(crate) trait NodeSpec: BaseNodeSpec {
 				);
 			}
 
+			let pov_recovery_handle = PoVRecoveryHandle::new();
+			let extension_context = NodeExtensionContext {
+				client: client.clone(),
+				backend: backend.clone(),
+				transaction_pool: transaction_pool.clone(),
+				spawn_handle: task_manager.spawn_handle(),
+				prometheus_registry: prometheus_registry.clone(),
+			};
+
 			let rpc_builder = {
 				let client = client.clone();
 				let transaction_pool = transaction_pool.clone();
 				let backend_for_rpc = backend.clone();
 				let statement_store = statement_store.clone();
+				let pov_recovery_handle = pov_recovery_handle.clone();
+				let extension = extension.clone();
+				let extension_context = extension_context.clone();
 
 				Box::new(move |_| {
-					Self::BuildRpcExtensions::build_rpc_extensions(
+					let mut module = Self::BuildRpcExtensions::build_rpc_extensions(
 						client.clone(),
 						backend_for_rpc.clone(),
 						transaction_pool.clone(),
 						statement_store.clone(),
-					)
+					)?;
+					module
+						.merge(PoVRecoveryRpc::new(pov_recovery_handle.clone()).into_rpc())
+						.map_err(|e| sc_service::Error::Application(Box::new(e)))?;
+					module
+						.merge(extension.rpc_modules(&extension_context)?)
+						.map_err(|e| sc_service::Error::Application(Box::new(e)))?;
+					Ok(module)
 				})
 			};
 
@@ -469,6 +498,8 @@ pub(crate) trait NodeSpec: BaseNodeSpec {
 				))),
 			})?;
 
+			extension.spawn_tasks(&extension_context)?;
+
 			// Spawn the storage monitor
 			if let Some(database_path) = database_path {
 				sc_storage_monitor::StorageMonitorService::try_spawn(
@@ -522,6 +553,7 @@ pub(crate) trait NodeSpec: BaseNodeSpec {
 				recovery_handle: Box::new(overseer_handle.clone()),
 				sync_service,
 				prometheus_registry: prometheus_registry.as_ref(),
+				pov_recovery_handle: Some(pov_recovery_handle),
 			})?;
 
 			start_bootnode_tasks(StartBootnodeTasksParams {
@@ -541,6 +573,19 @@ pub(crate) trait NodeSpec: BaseNodeSpec {
 			});
 
 			if validator {
+				// The checks execute the staged code, which may take a while.
+				task_manager.spawn_handle().spawn_blocking(
+					"upgrade-preflight",
+					None,
+					run_upgrade_preflight(
+						client.clone(),
+						transaction_pool.clone(),
+						params.keystore_container.keystore(),
+						para_id,
+						relay_chain_slot_duration,
+					),
+				);
+
 				Self::StartConsensus::start_consensus(
 					client.clone(),
 					block_import,
@@ -550,6 +595,7 @@ pub(crate) trait NodeSpec: BaseNodeSpec {
 					relay_chain_interface.clone(),
 					transaction_pool,
 					params.keystore_container.keystore(),
+					statement_store.filter(|_| node_extra_args.enable_encrypted_extrinsics),
 					relay_chain_slot_duration,
 					para_id,
 					collator_key.expect("Command line arguments do not allow this. qed"),
@@ -575,61 +621,82 @@ pub(crate) trait NodeSpec: BaseNodeSpec {
 	}
 }
 
-pub(crate) trait DynNodeSpec: NodeCommandRunner {
+pub(crate) trait DynNodeSpec<Extension: NodeExtension>: NodeCommandRunner {
 	/// Start node with manual or instant seal consensus.
 	fn start_dev_node(
 		self: Box<Self>,
 		config: Configuration,
 		mode: DevSealMode,
+		extension: Extension,
 	) -> sc_service::error::Result<TaskManager>;
 
+	/// Whether the node follows a relay chain, and therefore needs the relay chain configuration
+	/// to be started.
+	fn requires_relay_chain(&self) -> bool {
+		true
+	}
+
 	/// Start the node.
+	///
+	/// `polkadot_config` is only built, and required, if [`Self::requires_relay_chain`].
 	fn start_node(
 		self: Box<Self>,
 		parachain_config: Configuration,
-		polkadot_config: Configuration,
+		polkadot_config: Option<Configuration>,
 		collator_options: CollatorOptions,
 		hwbench: Option<HwBench>,
 		node_extra_args: NodeExtraArgs,
+		extension: Extension,
 	) -> Pin<Box<dyn Future<Output = sc_service::error::Result<TaskManager>>>>;
 }
 
-impl<T> DynNodeSpec for T
+impl<T, Extension> DynNodeSpec<Extension> for T
 where
 	T: NodeSpec + NodeCommandRunner,
+	Extension: NodeExtension,
 {
 	fn start_dev_node(
 		self: Box<Self>,
 		config: Configuration,
 		mode: DevSealMode,
+		extension: Extension,
 	) -> sc_service::error::Result<TaskManager> {
-		<Self as NodeSpec>::start_dev_node(config, mode)
+		<Self as NodeSpec>::start_dev_node(config, mode, extension)
 	}
 
 	fn start_node(
 		self: Box<Self>,
 		parachain_config: Configuration,
-		polkadot_config: Configuration,
+		polkadot_config: Option<Configuration>,
 		collator_options: CollatorOptions,
 		hwbench: Option<HwBench>,
 		node_extra_args: NodeExtraArgs,
+		extension: Extension,
 	) -> Pin<Box<dyn Future<Output = sc_service::error::Result<TaskManager>>>> {
+		let Some(polkadot_config) = polkadot_config else {
+			return Box::pin(futures::future::ready(Err(sc_service::Error::Other(
+				"A parachain node can not be started without the relay chain configuration".into(),
+			))))
+		};
+
 		match parachain_config.network.network_backend {
 			sc_network::config::NetworkBackendType::Libp2p =>
-				<Self as NodeSpec>::start_node::<sc_network::NetworkWorker<_, _>>(
+				<Self as NodeSpec>::start_node::<sc_network::NetworkWorker<_, _>, _>(
 					parachain_config,
 					polkadot_config,
 					collator_options,
 					hwbench,
 					node_extra_args,
+					extension,
 				),
 			sc_network::config::NetworkBackendType::Litep2p =>
-				<Self as NodeSpec>::start_node::<sc_network::Litep2pNetworkBackend>(
+				<Self as NodeSpec>::start_node::<sc_network::Litep2pNetworkBackend, _>(
 					parachain_config,
 					polkadot_config,
 					collator_options,
 					hwbench,
 					node_extra_args,
+					extension,
 				),
 		}
 	}
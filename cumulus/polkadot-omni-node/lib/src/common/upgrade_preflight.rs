@@ -0,0 +1,563 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Pre-flight checks of staged validation code upgrades.
+//!
+//! When a runtime requires pre-flight checks, `set_code` only stages the new validation code.
+//! Collators run the checks below against the staged code and report the outcome on-chain. The
+//! upgrade is scheduled once a report with a passed outcome was included.
+//!
+//! The staged code has to:
+//!
+//! - report a runtime version with the same `spec_name` and a higher `spec_version`,
+//! - build a block on top of the best block, which also runs its runtime upgrade hooks,
+//! - accept that block in `validate_block`, called with the parent head and the PoV the way a relay
+//!   chain validator calls it,
+//! - pass its `try-runtime` upgrade checks, if it was built with them.
+//!
+//! The relay chain state of the built block is mocked and the block is sealed with an Aura key of
+//! the collator. If the collator can't author such a block, it doesn't report an outcome.
+
+use crate::common::{
+	types::{ParachainClient, ParachainHostFunctions},
+	ConstructNodeRuntimeApi, NodeBlock,
+};
+use codec::{Decode, Encode};
+use cumulus_client_parachain_inherent::ParachainInherentData;
+use cumulus_primitives_core::{
+	relay_chain::{self, Hash as PHash, HeadData, Slot},
+	ParaId, ParachainBlockData, PersistedValidationData, UpgradePreflightApi,
+	UpgradePreflightError, UpgradePreflightOutcome, UpgradePreflightReport, ValidationParams,
+};
+use cumulus_test_relay_sproof_builder::RelayStateSproofBuilder;
+use frame_support::traits::UpgradeCheckSelect;
+use futures::StreamExt;
+use polkadot_parachain_primitives::primitives::{BlockData, ValidationResult};
+use sc_client_api::{BlockchainEvents, HeaderBackend, StorageProvider};
+use sc_executor::{
+	error::Error as ExecutorError, HeapAllocStrategy, RuntimeVersionOf, WasmExecutor,
+};
+use sc_transaction_pool_api::{TransactionPool, TransactionSource};
+use sp_api::{ApiExt, ProvideRuntimeApi};
+use sp_consensus_aura::AURA_ENGINE_ID;
+use sp_core::{
+	crypto::key_types::AURA,
+	ed25519, sr25519,
+	traits::{CallContext, CodeExecutor, RuntimeCode, WrappedRuntimeCode},
+};
+use sp_externalities::Extensions;
+use sp_inherents::InherentDataProvider;
+use sp_keystore::KeystorePtr;
+use sp_runtime::{
+	traits::{Block as BlockT, HashingFor, Header as HeaderT, One},
+	ApplyExtrinsicResult, Digest, DigestItem,
+};
+use sp_state_machine::{
+	backend::AsTrieBackend, BasicExternalities, OverlayedChanges, StateMachine, TrieBackendBuilder,
+};
+use sp_storage::StorageKey;
+use sp_trie::{proof_size_extension::ProofSizeExt, recorder::Recorder};
+use sp_version::RuntimeVersion;
+use std::{collections::BTreeMap, error::Error, sync::Arc, time::Duration};
+
+const LOG_TARGET: &str = "upgrade-preflight";
+
+/// Check every validation code that is staged at the best block and report the outcome.
+///
+/// The outcome is cached per code hash, so the checks run once per staged code. The report is
+/// resubmitted on every new best block for as long as the code stays staged, which it does after a
+/// failure. The runtime rejects a report that repeats the last one.
+pub(crate) async fn run_upgrade_preflight<Block, RuntimeApi, Pool>(
+	client: Arc<ParachainClient<Block, RuntimeApi>>,
+	transaction_pool: Arc<Pool>,
+	keystore: KeystorePtr,
+	para_id: ParaId,
+	relay_chain_slot_duration: Duration,
+) where
+	Block: NodeBlock,
+	RuntimeApi: ConstructNodeRuntimeApi<Block, ParachainClient<Block, RuntimeApi>>,
+	Pool: TransactionPool<Block = Block> + 'static,
+{
+	// Validators execute `validate_block` with additional heap pages, the same is done here.
+	let heap_pages = HeapAllocStrategy::Static { extra_pages: 2048 };
+	let executor = WasmExecutor::<ParachainHostFunctions>::builder()
+		.with_onchain_heap_alloc_strategy(heap_pages)
+		.with_offchain_heap_alloc_strategy(heap_pages)
+		.build();
+	let checker = PreflightChecker {
+		client: client.clone(),
+		executor,
+		keystore,
+		para_id,
+		relay_chain_slot_duration,
+	};
+	let mut imports = client.import_notification_stream();
+	let mut last_checked: Option<(PHash, UpgradePreflightOutcome)> = None;
+
+	while let Some(notification) = imports.next().await {
+		if !notification.is_new_best {
+			continue
+		}
+
+		let best_hash = notification.hash;
+		let api = client.runtime_api();
+		match api.has_api::<dyn UpgradePreflightApi<Block>>(best_hash) {
+			Ok(true) => {},
+			_ => continue,
+		}
+
+		let code = match api.staged_validation_code(best_hash) {
+			Ok(Some(code)) => code,
+			Ok(None) => continue,
+			Err(e) => {
+				log::debug!(
+					target: LOG_TARGET,
+					"Failed to fetch the staged validation code at {best_hash:?}: {e:?}",
+				);
+				continue
+			},
+		};
+
+		let code_hash: PHash = sp_core::blake2_256(&code).into();
+		let outcome = match &last_checked {
+			Some((checked_hash, outcome)) if *checked_hash == code_hash => outcome.clone(),
+			_ => match checker.check(best_hash, &code, code_hash).await {
+				Ok(outcome) => {
+					log::info!(
+						target: LOG_TARGET,
+						"Pre-flight checks of staged validation code {code_hash:?}: {outcome:?}",
+					);
+					last_checked = Some((code_hash, outcome.clone()));
+					outcome
+				},
+				// Retried on the next best block, e.g. once a key of this collator can author it.
+				Err(e) => {
+					log::debug!(
+						target: LOG_TARGET,
+						"Unable to run the pre-flight checks of {code_hash:?} at {best_hash:?}: {e}",
+					);
+					continue
+				},
+			},
+		};
+
+		let report = UpgradePreflightReport { code_hash, outcome };
+		let extrinsic = match api.upgrade_preflight_report_extrinsic(best_hash, report) {
+			Ok(extrinsic) => extrinsic,
+			Err(e) => {
+				log::warn!(
+					target: LOG_TARGET,
+					"Failed to create the pre-flight report extrinsic at {best_hash:?}: {e:?}",
+				);
+				continue
+			},
+		};
+
+		// The report of a previous block may still be in the pool, which is rejected as a
+		// duplicate. It is only a problem if the code stays staged.
+		if let Err(e) = transaction_pool
+			.submit_one(best_hash, TransactionSource::Local, extrinsic)
+			.await
+		{
+			log::debug!(
+				target: LOG_TARGET,
+				"Failed to submit the pre-flight report for {code_hash:?}: {e:?}",
+			);
+		}
+	}
+}
+
+/// Runs the pre-flight checks of staged code on top of the blocks of a parachain.
+struct PreflightChecker<Block: NodeBlock, RuntimeApi> {
+	client: Arc<ParachainClient<Block, RuntimeApi>>,
+	executor: WasmExecutor<ParachainHostFunctions>,
+	keystore: KeystorePtr,
+	para_id: ParaId,
+	relay_chain_slot_duration: Duration,
+}
+
+/// The reason why the checks could not be run. Nothing is reported in this case.
+type Inconclusive = Box<dyn Error + Send + Sync>;
+
+/// A slot in which one of our keys can author a block with the staged code.
+struct SlotClaim {
+	/// The parachain slot of the block.
+	slot: Slot,
+	/// The relay chain slot the parachain slot is derived from.
+	relay_slot: Slot,
+	/// The timestamp of the block.
+	timestamp: u64,
+	/// The Aura key that authors the slot.
+	author: [u8; 32],
+}
+
+impl<Block, RuntimeApi> PreflightChecker<Block, RuntimeApi>
+where
+	Block: NodeBlock,
+	RuntimeApi: ConstructNodeRuntimeApi<Block, ParachainClient<Block, RuntimeApi>>,
+{
+	/// Run the pre-flight checks of `code` on top of the block `at`.
+	async fn check(
+		&self,
+		at: Block::Hash,
+		code: &[u8],
+		code_hash: PHash,
+	) -> Result<UpgradePreflightOutcome, Inconclusive> {
+		use UpgradePreflightOutcome::Failed;
+
+		let code_fetcher = WrappedRuntimeCode(code.into());
+		let runtime_code = RuntimeCode {
+			code_fetcher: &code_fetcher,
+			heap_pages: None,
+			hash: code_hash.0.to_vec(),
+		};
+
+		let current_version = self.client.runtime_version_at(at)?;
+		if let Err(error) = self.check_version(&runtime_code, &current_version) {
+			return Ok(Failed(error))
+		}
+
+		let parent = self.client.header(at)?.ok_or("the best block is unknown")?;
+		let claim = self.claim_slot(&parent, &runtime_code)?;
+
+		if let Err(error) = self.check_try_runtime(at, &runtime_code) {
+			return Ok(Failed(error))
+		}
+
+		let (relay_parent_number, relay_parent_storage_root, block_data) =
+			match self.build_block(&parent, &runtime_code, claim).await? {
+				Ok(built) => built,
+				Err(error) => return Ok(Failed(error)),
+			};
+
+		let params = ValidationParams {
+			parent_head: HeadData(parent.encode()),
+			block_data: BlockData(block_data.encode()),
+			relay_parent_number,
+			relay_parent_storage_root,
+		};
+		let (result, _) = self.executor.call(
+			&mut BasicExternalities::default(),
+			&runtime_code,
+			"validate_block",
+			&params.encode(),
+			CallContext::Onchain,
+		);
+		let head = match result {
+			Ok(result) => ValidationResult::decode(&mut &result[..])
+				.ok()
+				.and_then(|result| Block::Header::decode(&mut &result.head_data.0[..]).ok()),
+			Err(ExecutorError::MethodNotFound(_)) =>
+				return Ok(Failed(UpgradePreflightError::ValidateBlockUnavailable)),
+			Err(e) => {
+				log::debug!(target: LOG_TARGET, "`validate_block` of {code_hash:?} failed: {e:?}");
+				None
+			},
+		};
+
+		match head {
+			Some(head) if *head.number() == *parent.number() + One::one() =>
+				Ok(UpgradePreflightOutcome::Passed),
+			_ => Ok(Failed(UpgradePreflightError::ValidateBlockFailed)),
+		}
+	}
+
+	/// Check the runtime version of the code against the runtime that is currently in use.
+	fn check_version(
+		&self,
+		runtime_code: &RuntimeCode,
+		current_version: &RuntimeVersion,
+	) -> Result<(), UpgradePreflightError> {
+		let mut ext = BasicExternalities::default();
+		let version = match self.executor.runtime_version(&mut ext, runtime_code) {
+			Ok(version) => version,
+			Err(ExecutorError::MethodNotFound(_)) =>
+				return Err(UpgradePreflightError::MissingVersion),
+			Err(e) => {
+				log::debug!(target: LOG_TARGET, "Failed to instantiate the staged code: {e:?}");
+				return Err(UpgradePreflightError::InvalidCode)
+			},
+		};
+
+		if version.spec_name != current_version.spec_name {
+			return Err(UpgradePreflightError::SpecNameMismatch)
+		}
+		if version.spec_version <= current_version.spec_version {
+			return Err(UpgradePreflightError::SpecVersionNotIncreased)
+		}
+		Ok(())
+	}
+
+	/// Run the `try-runtime` upgrade checks of the code on the state of the block `at`.
+	///
+	/// Code that was built without `try-runtime` passes.
+	fn check_try_runtime(
+		&self,
+		at: Block::Hash,
+		runtime_code: &RuntimeCode,
+	) -> Result<(), UpgradePreflightError> {
+		let Ok(state) = self.client.state_at(at) else { return Ok(()) };
+		let result = StateMachine::new(
+			state.as_trie_backend(),
+			&mut OverlayedChanges::default(),
+			&self.executor,
+			"TryRuntime_on_runtime_upgrade",
+			&UpgradeCheckSelect::All.encode(),
+			&mut Extensions::new(),
+			runtime_code,
+			CallContext::Offchain,
+		)
+		.execute();
+
+		match result {
+			Ok(_) => Ok(()),
+			Err(e) if is_method_not_found(&*e) => {
+				log::debug!(target: LOG_TARGET, "The staged code has no `try-runtime` checks.");
+				Ok(())
+			},
+			Err(e) => {
+				log::debug!(target: LOG_TARGET, "`try-runtime` checks failed: {e:?}");
+				Err(UpgradePreflightError::TryRuntimeFailed)
+			},
+		}
+	}
+
+	/// Find the next slot after the one of `parent` that one of our Aura keys can author with the
+	/// staged code.
+	fn claim_slot(
+		&self,
+		parent: &Block::Header,
+		runtime_code: &RuntimeCode,
+	) -> Result<SlotClaim, Inconclusive> {
+		let call = |method: &str| -> Result<Vec<u8>, Inconclusive> {
+			let state = self.client.state_at(parent.hash())?;
+			StateMachine::new(
+				state.as_trie_backend(),
+				&mut OverlayedChanges::default(),
+				&self.executor,
+				method,
+				&[],
+				&mut Extensions::new(),
+				runtime_code,
+				CallContext::Offchain,
+			)
+			.execute()
+			.map_err(|e| format!("`{method}` failed: {e}").into())
+		};
+
+		// The relay chain state mocked by `build_block` has no descendants of the relay parent.
+		let offset = call("RelayParentOffsetApi_relay_parent_offset")
+			.ok()
+			.and_then(|offset| u32::decode(&mut &offset[..]).ok())
+			.unwrap_or_default();
+		if offset > 0 {
+			return Err("blocks with a relay parent offset can't be built".into())
+		}
+
+		// Only Aura keys of 32 bytes, i.e. `sr25519` and `ed25519`, are supported.
+		let authorities = Vec::<[u8; 32]>::decode(&mut &call("AuraApi_authorities")?[..])?;
+		let slot_duration =
+			sp_consensus_aura::SlotDuration::decode(&mut &call("AuraApi_slot_duration")?[..])?
+				.as_millis()
+				.max(1);
+		let relay_slot_duration = (self.relay_chain_slot_duration.as_millis() as u64).max(1);
+		if authorities.is_empty() {
+			return Err("the staged code has no Aura authorities".into())
+		}
+
+		let current_slot = parent
+			.digest()
+			.logs()
+			.iter()
+			.find_map(|log| log.pre_runtime_try_to::<Slot>(&AURA_ENGINE_ID))
+			.unwrap_or_default();
+
+		// The slot has to be derived from a relay chain slot, every author has a turn within
+		// this many slots.
+		let slots_per_relay_slot = (relay_slot_duration / slot_duration).max(1);
+		let search = authorities.len() as u64 * slots_per_relay_slot;
+		(*current_slot + 1..=*current_slot + search)
+			.find_map(|slot| {
+				let author = authorities[(slot % authorities.len() as u64) as usize];
+				let relay_slot = (slot * slot_duration).div_ceil(relay_slot_duration);
+				let derived = relay_slot * relay_slot_duration / slot_duration;
+				(derived == slot && self.keystore.has_keys(&[(author.to_vec(), AURA)])).then(|| {
+					SlotClaim {
+						slot: slot.into(),
+						relay_slot: relay_slot.into(),
+						timestamp: slot * slot_duration,
+						author,
+					}
+				})
+			})
+			.ok_or_else(|| "no Aura key of this node can author a block".into())
+	}
+
+	/// Build a block with the staged code on top of `parent` and return the [`ValidationParams`]
+	/// to validate it, apart from the parent head.
+	///
+	/// Returns an error of the code if any of its calls fail.
+	async fn build_block(
+		&self,
+		parent: &Block::Header,
+		runtime_code: &RuntimeCode,
+		claim: SlotClaim,
+	) -> Result<
+		Result<(relay_chain::BlockNumber, PHash, ParachainBlockData<Block>), UpgradePreflightError>,
+		Inconclusive,
+	> {
+		let parent_head = HeadData(parent.encode());
+		let relay_parent_number = self
+			.parachain_system_value::<relay_chain::BlockNumber>(
+				parent.hash(),
+				b"LastRelayChainBlockNumber",
+			)?
+			.unwrap_or_default() +
+			1;
+
+		// The relay chain state that the parachain expects: the parent is included and all
+		// messages it knows about are processed.
+		let mut sproof_builder = RelayStateSproofBuilder {
+			para_id: self.para_id,
+			current_slot: claim.relay_slot,
+			included_para_head: Some(parent_head.clone()),
+			dmq_mqc_head: Some(
+				self.parachain_system_value(parent.hash(), b"LastDmqMqcHead")?
+					.unwrap_or_default(),
+			),
+			..Default::default()
+		};
+		let hrmp_heads: BTreeMap<ParaId, PHash> = self
+			.parachain_system_value(parent.hash(), b"LastHrmpMqcHeads")?
+			.unwrap_or_default();
+		for (sender, mqc_head) in hrmp_heads {
+			sproof_builder.upsert_inbound_channel(sender).mqc_head = Some(mqc_head);
+		}
+		let (relay_parent_storage_root, relay_chain_state) =
+			sproof_builder.into_state_root_and_proof();
+
+		let parachain_inherent = ParachainInherentData {
+			validation_data: PersistedValidationData {
+				parent_head,
+				relay_parent_number,
+				relay_parent_storage_root,
+				max_pov_size: relay_chain::MAX_POV_SIZE,
+			},
+			relay_chain_state,
+			downward_messages: Vec::new(),
+			horizontal_messages: Default::default(),
+			relay_parent_descendants: Vec::new(),
+			collator_peer_id: None,
+		};
+		let inherent_data =
+			(sp_timestamp::InherentDataProvider::new(claim.timestamp.into()), parachain_inherent)
+				.create_inherent_data()
+				.await?;
+
+		let header = Block::Header::new(
+			*parent.number() + One::one(),
+			Default::default(),
+			Default::default(),
+			parent.hash(),
+			Digest { logs: vec![DigestItem::PreRuntime(AURA_ENGINE_ID, claim.slot.encode())] },
+		);
+
+		let state = self.client.state_at(parent.hash())?;
+		let recorder = Recorder::<HashingFor<Block>>::default();
+		let backend = TrieBackendBuilder::wrap(state.as_trie_backend())
+			.with_recorder(recorder.clone())
+			.build();
+		let mut overlay = OverlayedChanges::default();
+		let mut extensions = Extensions::new();
+		extensions.register(ProofSizeExt::new(recorder));
+		let mut call = |method: &str, data: &[u8]| {
+			StateMachine::new(
+				&backend,
+				&mut overlay,
+				&self.executor,
+				method,
+				data,
+				&mut extensions,
+				runtime_code,
+				CallContext::Onchain,
+			)
+			.execute()
+		};
+
+		let built = (|| -> Result<_, Box<dyn Error>> {
+			call("Core_initialize_block", &header.encode())?;
+			let inherents = call("BlockBuilder_inherent_extrinsics", &inherent_data.encode())?;
+			let extrinsics = Vec::<Block::Extrinsic>::decode(&mut &inherents[..])?;
+			for extrinsic in &extrinsics {
+				let result = call("BlockBuilder_apply_extrinsic", &extrinsic.encode())?;
+				match ApplyExtrinsicResult::decode(&mut &result[..])? {
+					Ok(Ok(())) => {},
+					result => return Err(format!("an inherent failed: {result:?}").into()),
+				}
+			}
+			let header = call("BlockBuilder_finalize_block", &[])?;
+			Ok((Block::Header::decode(&mut &header[..])?, extrinsics))
+		})();
+		let (mut header, extrinsics) = match built {
+			Ok(built) => built,
+			Err(e) => {
+				log::debug!(target: LOG_TARGET, "Failed to build a block: {e}");
+				return Ok(Err(UpgradePreflightError::BlockBuildingFailed))
+			},
+		};
+
+		let proof = backend
+			.extract_proof()
+			.expect("A recorder was set and thus, a storage proof can be extracted; qed")
+			.into_compact_proof::<HashingFor<Block>>(*parent.state_root())
+			.map_err(|e| format!("failed to compact the proof: {e:?}"))?;
+
+		let pre_hash = header.hash();
+		let signature = [sr25519::CRYPTO_ID, ed25519::CRYPTO_ID]
+			.into_iter()
+			.find_map(|crypto_id| {
+				self.keystore
+					.sign_with(AURA, crypto_id, &claim.author, pre_hash.as_ref())
+					.ok()
+					.flatten()
+			})
+			.ok_or("the block could not be sealed")?;
+		header.digest_mut().push(DigestItem::Seal(AURA_ENGINE_ID, signature));
+
+		let block_data =
+			ParachainBlockData::<Block>::new(vec![Block::new(header, extrinsics)], proof);
+		Ok(Ok((relay_parent_number, relay_parent_storage_root, block_data)))
+	}
+
+	/// Read a storage value of `cumulus_pallet_parachain_system` at the block `at`.
+	fn parachain_system_value<T: Decode>(
+		&self,
+		at: Block::Hash,
+		name: &[u8],
+	) -> Result<Option<T>, Inconclusive> {
+		let key = [sp_core::twox_128(b"ParachainSystem"), sp_core::twox_128(name)].concat();
+		self.client
+			.storage(at, &StorageKey(key))?
+			.map(|value| T::decode(&mut &value.0[..]))
+			.transpose()
+			.map_err(Into::into)
+	}
+}
+
+/// Whether a runtime call failed, because the code does not export the called function.
+fn is_method_not_found(error: &(dyn Error + 'static)) -> bool {
+	matches!(error.downcast_ref::<ExecutorError>(), Some(ExecutorError::MethodNotFound(_)))
+}
@@ -19,7 +19,7 @@
 
 mod utils;
 
-use utils::{impl_node_runtime_apis, imports::*};
+use utils::{impl_node_runtime_apis, impl_solochain_runtime_apis, imports::*};
 
 #[allow(dead_code)]
 type CustomBlock = crate::common::types::Block<u32>;
@@ -37,3 +37,61 @@ pub mod aura_ed25519 {
 	struct FakeRuntime;
 	impl_node_runtime_apis!(FakeRuntime, CustomBlock, sp_consensus_aura::ed25519::AuthorityId);
 }
+
+pub mod aura_grandpa {
+	use super::*;
+	#[allow(dead_code)]
+	struct FakeRuntime;
+	impl_solochain_runtime_apis!(FakeRuntime, CustomBlock, {
+		impl sp_consensus_aura::AuraApi<CustomBlock, sp_consensus_aura::sr25519::AuthorityId>
+			for FakeRuntime
+		{
+			fn slot_duration() -> sp_consensus_aura::SlotDuration {
+				unimplemented!()
+			}
+
+			fn authorities() -> Vec<sp_consensus_aura::sr25519::AuthorityId> {
+				unimplemented!()
+			}
+		}
+	});
+}
+
+pub mod babe_grandpa {
+	use super::*;
+	#[allow(dead_code)]
+	struct FakeRuntime;
+	impl_solochain_runtime_apis!(FakeRuntime, CustomBlock, {
+		impl sp_consensus_babe::BabeApi<CustomBlock> for FakeRuntime {
+			fn configuration() -> sp_consensus_babe::BabeConfiguration {
+				unimplemented!()
+			}
+
+			fn current_epoch_start() -> sp_consensus_babe::Slot {
+				unimplemented!()
+			}
+
+			fn current_epoch() -> sp_consensus_babe::Epoch {
+				unimplemented!()
+			}
+
+			fn next_epoch() -> sp_consensus_babe::Epoch {
+				unimplemented!()
+			}
+
+			fn generate_key_ownership_proof(
+				_: sp_consensus_babe::Slot,
+				_: sp_consensus_babe::AuthorityId,
+			) -> Option<sp_consensus_babe::OpaqueKeyOwnershipProof> {
+				unimplemented!()
+			}
+
+			fn submit_report_equivocation_unsigned_extrinsic(
+				_: sp_consensus_babe::EquivocationProof<<CustomBlock as BlockT>::Header>,
+				_: sp_consensus_babe::OpaqueKeyOwnershipProof,
+			) -> Option<()> {
+				unimplemented!()
+			}
+		}
+	});
+}
@@ -65,6 +65,26 @@ macro_rules! impl_node_runtime_apis {
 				}
 			}
 
+			impl cumulus_primitives_core::UpgradePreflightApi<$block> for $runtime {
+				fn staged_validation_code() -> Option<Vec<u8>> {
+					unimplemented!()
+				}
+
+				fn upgrade_preflight_report_extrinsic(
+					_: cumulus_primitives_core::UpgradePreflightReport,
+				) -> <$block as sp_runtime::traits::Block>::Extrinsic {
+					unimplemented!()
+				}
+			}
+
+			impl cumulus_primitives_core::EncryptedExtrinsicsApi<$block> for $runtime {
+				fn encrypted_inclusion_extrinsic(
+					_: cumulus_primitives_core::EncryptedExtrinsicCommitments,
+				) -> <$block as sp_runtime::traits::Block>::Extrinsic {
+					unimplemented!()
+				}
+			}
+
 			impl sp_consensus_aura::AuraApi<$block, $aura_id> for $runtime {
 				fn slot_duration() -> sp_consensus_aura::SlotDuration {
 					unimplemented!()
@@ -251,4 +271,208 @@ macro_rules! impl_node_runtime_apis {
 	};
 }
 
+/// Implements the runtime APIs of a solochain with GRANDPA finality. The runtime APIs of the block
+/// production consensus are passed in `$consensus_apis`.
+macro_rules! impl_solochain_runtime_apis {
+	($runtime: ty, $block: tt, { $($consensus_apis: tt)* }) => {
+		sp_api::impl_runtime_apis! {
+			impl sp_api::Core<$block> for $runtime {
+				fn version() -> sp_version::RuntimeVersion {
+					unimplemented!()
+				}
+
+				fn execute_block(_: <$block as BlockT>::LazyBlock) {
+					unimplemented!()
+				}
+
+				fn initialize_block(
+					_: &<$block as BlockT>::Header
+				) -> sp_runtime::ExtrinsicInclusionMode {
+					unimplemented!()
+				}
+			}
+
+			impl sp_api::Metadata<$block> for $runtime {
+				fn metadata() -> OpaqueMetadata {
+					unimplemented!()
+				}
+
+				fn metadata_at_version(_: u32) -> Option<OpaqueMetadata> {
+					unimplemented!()
+				}
+
+				fn metadata_versions() -> Vec<u32> {
+					unimplemented!()
+				}
+			}
+
+			impl sp_block_builder::BlockBuilder<$block> for $runtime {
+				fn apply_extrinsic(_: <$block as BlockT>::Extrinsic) -> ApplyExtrinsicResult {
+					unimplemented!()
+				}
+
+				fn finalize_block() -> <$block as BlockT>::Header {
+					unimplemented!()
+				}
+
+				fn inherent_extrinsics(
+					_: sp_inherents::InherentData
+				) -> Vec<<$block as BlockT>::Extrinsic> {
+					unimplemented!()
+				}
+
+				fn check_inherents(
+					_: <$block as BlockT>::LazyBlock,
+					_: sp_inherents::InherentData
+				) -> sp_inherents::CheckInherentsResult {
+					unimplemented!()
+				}
+			}
+
+			impl sp_transaction_pool::runtime_api::TaggedTransactionQueue<$block> for $runtime {
+				fn validate_transaction(
+					_: TransactionSource,
+					_: <$block as BlockT>::Extrinsic,
+					_: <$block as BlockT>::Hash,
+				) -> TransactionValidity {
+					unimplemented!()
+				}
+			}
+
+			impl sp_offchain::OffchainWorkerApi<$block> for $runtime {
+				fn offchain_worker(_: &<$block as BlockT>::Header) {
+					unimplemented!()
+				}
+			}
+
+			impl sp_session::SessionKeys<$block> for $runtime {
+				fn generate_session_keys(_: Option<Vec<u8>>) -> Vec<u8> {
+					unimplemented!()
+				}
+
+				fn decode_session_keys(
+					_: Vec<u8>,
+				) -> Option<Vec<(Vec<u8>, KeyTypeId)>> {
+					unimplemented!()
+				}
+			}
+
+			impl sp_consensus_grandpa::GrandpaApi<$block> for $runtime {
+				fn grandpa_authorities() -> sp_consensus_grandpa::AuthorityList {
+					unimplemented!()
+				}
+
+				fn current_set_id() -> sp_consensus_grandpa::SetId {
+					unimplemented!()
+				}
+
+				fn submit_report_equivocation_unsigned_extrinsic(
+					_: sp_consensus_grandpa::EquivocationProof<
+						<$block as BlockT>::Hash,
+						sp_runtime::traits::NumberFor<$block>,
+					>,
+					_: sp_consensus_grandpa::OpaqueKeyOwnershipProof,
+				) -> Option<()> {
+					unimplemented!()
+				}
+
+				fn generate_key_ownership_proof(
+					_: sp_consensus_grandpa::SetId,
+					_: sp_consensus_grandpa::AuthorityId,
+				) -> Option<sp_consensus_grandpa::OpaqueKeyOwnershipProof> {
+					unimplemented!()
+				}
+			}
+
+			impl
+				pallet_transaction_payment_rpc_runtime_api::TransactionPaymentApi<
+					$block,
+					Balance,
+				> for $runtime
+			{
+				fn query_info(
+					_: <$block as BlockT>::Extrinsic,
+					_: u32,
+				) -> pallet_transaction_payment_rpc_runtime_api::RuntimeDispatchInfo<Balance> {
+					unimplemented!()
+				}
+				fn query_fee_details(
+					_: <$block as BlockT>::Extrinsic,
+					_: u32,
+				) -> pallet_transaction_payment::FeeDetails<Balance> {
+					unimplemented!()
+				}
+				fn query_weight_to_fee(_: Weight) -> Balance {
+					unimplemented!()
+				}
+				fn query_length_to_fee(_: u32) -> Balance {
+					unimplemented!()
+				}
+			}
+
+			#[cfg(feature = "try-runtime")]
+			impl frame_try_runtime::TryRuntime<$block> for $runtime {
+				fn on_runtime_upgrade(
+					_: frame_try_runtime::UpgradeCheckSelect
+				) -> (Weight, Weight) {
+					unimplemented!()
+				}
+
+				fn execute_block(
+					_: <$block as BlockT>::LazyBlock,
+					_: bool,
+					_: bool,
+					_: frame_try_runtime::TryStateSelect,
+				) -> Weight {
+					unimplemented!()
+				}
+			}
+
+			impl frame_system_rpc_runtime_api::AccountNonceApi<
+				$block,
+				AccountId,
+				Nonce
+			> for $runtime {
+				fn account_nonce(_: AccountId) -> Nonce {
+					unimplemented!()
+				}
+			}
+
+			#[cfg(feature = "runtime-benchmarks")]
+			impl frame_benchmarking::Benchmark<$block> for $runtime {
+				fn benchmark_metadata(_: bool) -> (
+					Vec<frame_benchmarking::BenchmarkList>,
+					Vec<frame_support::traits::StorageInfo>,
+				) {
+					unimplemented!()
+				}
+
+				#[allow(non_local_definitions)]
+				fn dispatch_benchmark(
+					_: frame_benchmarking::BenchmarkConfig
+				) -> Result<Vec<frame_benchmarking::BenchmarkBatch>, String> {
+					unimplemented!()
+				}
+			}
+
+			impl sp_genesis_builder::GenesisBuilder<$block> for $runtime {
+				fn build_state(_: Vec<u8>) -> sp_genesis_builder::Result {
+					unimplemented!()
+				}
+
+				fn get_preset(_id: &Option<sp_genesis_builder::PresetId>) -> Option<Vec<u8>> {
+					unimplemented!()
+				}
+
+				fn preset_names() -> Vec<sp_genesis_builder::PresetId> {
+					unimplemented!()
+				}
+			}
+
+			$($consensus_apis)*
+		}
+	};
+}
+
 pub(crate) use impl_node_runtime_apis;
+pub(crate) use impl_solochain_runtime_apis;
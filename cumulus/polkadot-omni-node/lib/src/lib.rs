@@ -22,9 +22,10 @@ mod command;
 mod common;
 pub mod extra_subcommand;
 mod fake_runtime_api;
+pub mod node_extension;
 mod nodes;
 
 pub use cli::CliConfig;
-pub use command::{run, run_with_custom_cli, RunConfig};
+pub use command::{run, run_with_custom_cli, run_with_extension, RunConfig};
 pub use common::{chain_spec, runtime};
 pub use nodes::NODE_VERSION;
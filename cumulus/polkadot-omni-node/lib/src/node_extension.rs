@@ -0,0 +1,183 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Extension point for binaries built with `polkadot-omni-node-lib` that need custom RPC
+//! modules, background services or CLI arguments on top of the node built by the library.
+//!
+//! * Binaries that need them implement [`NodeExtension`] and pass it to `run_with_extension`.
+//! * Binaries that don't need any keep using `run` or `run_with_custom_cli`, which pass
+//!   [`NoNodeExtension`].
+
+use clap::Args;
+use jsonrpsee::RpcModule;
+use prometheus_endpoint::Registry;
+use sc_client_api::{
+	BlockBackend, BlockchainEvents, HeaderBackend, StorageProvider, UsageProvider,
+};
+use sc_service::SpawnTaskHandle;
+use sc_transaction_pool_api::TransactionPool;
+use sp_api::ProvideRuntimeApi;
+use sp_runtime::traits::Block as BlockT;
+use std::sync::Arc;
+
+/// The client functionality available to a [`NodeExtension`].
+///
+/// The runtime API of the client only covers the APIs that the omni-node requires from every
+/// runtime. Custom runtime APIs can be called through
+/// [`CallApiAt`](sp_api::CallApiAt) or by reading the storage directly.
+pub trait NodeClient<Block: BlockT, Backend>:
+	ProvideRuntimeApi<Block>
+	+ HeaderBackend<Block>
+	+ BlockBackend<Block>
+	+ BlockchainEvents<Block>
+	+ StorageProvider<Block, Backend>
+	+ UsageProvider<Block>
+	+ Send
+	+ Sync
+	+ 'static
+where
+	Backend: sc_client_api::Backend<Block>,
+{
+}
+
+impl<T, Block, Backend> NodeClient<Block, Backend> for T
+where
+	T: ProvideRuntimeApi<Block>
+		+ HeaderBackend<Block>
+		+ BlockBackend<Block>
+		+ BlockchainEvents<Block>
+		+ StorageProvider<Block, Backend>
+		+ UsageProvider<Block>
+		+ Send
+		+ Sync
+		+ 'static,
+	Block: BlockT,
+	Backend: sc_client_api::Backend<Block>,
+{
+}
+
+/// The components of the running node that are handed to a [`NodeExtension`].
+pub struct NodeExtensionContext<Client, Backend, Pool> {
+	/// The client of the parachain node.
+	pub client: Arc<Client>,
+	/// The database backend of the parachain node.
+	pub backend: Arc<Backend>,
+	/// The transaction pool of the parachain node.
+	pub transaction_pool: Arc<Pool>,
+	/// Handle to spawn tasks that are stopped together with the node.
+	pub spawn_handle: SpawnTaskHandle,
+	/// The prometheus registry of the node, if metrics are enabled.
+	pub prometheus_registry: Option<Registry>,
+}
+
+impl<Client, Backend, Pool> Clone for NodeExtensionContext<Client, Backend, Pool> {
+	fn clone(&self) -> Self {
+		Self {
+			client: self.client.clone(),
+			backend: self.backend.clone(),
+			transaction_pool: self.transaction_pool.clone(),
+			spawn_handle: self.spawn_handle.clone(),
+			prometheus_registry: self.prometheus_registry.clone(),
+		}
+	}
+}
+
+/// A trait for plugging custom RPC modules, services and CLI arguments into the node built by
+/// `polkadot-omni-node-lib`.
+///
+/// The implementing type is parsed from the command line as a set of [`clap::Args`] that are
+/// added to the arguments of the node, so it carries the configuration of the extension. The
+/// node service is still constructed by the library, which calls into the extension once the
+/// client, backend and transaction pool exist.
+///
+/// ## Example
+///
+/// ```ignore
+/// use polkadot_omni_node_lib::{
+/// 	extra_subcommand::DefaultExtraSubcommands,
+/// 	node_extension::{NodeClient, NodeExtension, NodeExtensionContext},
+/// 	run_with_extension, RunConfig,
+/// };
+///
+/// #[derive(Debug, Clone, clap::Args)]
+/// pub struct Indexer {
+/// 	/// Serve the record indexer RPC.
+/// 	#[arg(long)]
+/// 	enable_indexer: bool,
+/// }
+///
+/// impl NodeExtension for Indexer {
+/// 	fn rpc_modules<Block, Client, Backend, Pool>(
+/// 		&self,
+/// 		ctx: &NodeExtensionContext<Client, Backend, Pool>,
+/// 	) -> sc_service::error::Result<RpcModule<()>>
+/// 	where
+/// 		Block: BlockT,
+/// 		Client: NodeClient<Block, Backend>,
+/// 		Backend: sc_client_api::Backend<Block> + 'static,
+/// 		Pool: TransactionPool<Block = Block> + 'static,
+/// 	{
+/// 		let mut module = RpcModule::new(());
+/// 		if self.enable_indexer {
+/// 			module
+/// 				.merge(IndexerRpc::new(ctx.client.clone()).into_rpc())
+/// 				.map_err(|e| sc_service::Error::Application(Box::new(e)))?;
+/// 		}
+/// 		Ok(module)
+/// 	}
+/// }
+///
+/// run_with_extension::<CliConfig, DefaultExtraSubcommands, Indexer>(config)?;
+/// ```
+pub trait NodeExtension: Args + Clone + Send + Sync + 'static {
+	/// Build the RPC modules that are merged into the RPC server of the node.
+	///
+	/// Called every time the RPC server is (re)started.
+	fn rpc_modules<Block, Client, Backend, Pool>(
+		&self,
+		_ctx: &NodeExtensionContext<Client, Backend, Pool>,
+	) -> sc_service::error::Result<RpcModule<()>>
+	where
+		Block: BlockT,
+		Client: NodeClient<Block, Backend>,
+		Backend: sc_client_api::Backend<Block> + 'static,
+		Pool: TransactionPool<Block = Block> + 'static,
+	{
+		Ok(RpcModule::new(()))
+	}
+
+	/// Spawn the services of the extension.
+	///
+	/// Called once, after the core services of the node were started.
+	fn spawn_tasks<Block, Client, Backend, Pool>(
+		&self,
+		_ctx: &NodeExtensionContext<Client, Backend, Pool>,
+	) -> sc_service::error::Result<()>
+	where
+		Block: BlockT,
+		Client: NodeClient<Block, Backend>,
+		Backend: sc_client_api::Backend<Block> + 'static,
+		Pool: TransactionPool<Block = Block> + 'static,
+	{
+		Ok(())
+	}
+}
+
+/// No-op extension. Use this when a binary does not need to extend the node.
+#[derive(Debug, Clone, Args)]
+pub struct NoNodeExtension {}
+
+impl NodeExtension for NoNodeExtension {}
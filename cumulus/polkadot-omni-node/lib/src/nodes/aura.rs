@@ -29,6 +29,7 @@ use crate::{
 		},
 		ConstructNodeRuntimeApi, NodeBlock, NodeExtraArgs,
 	},
+	node_extension::{NodeExtension, NodeExtensionContext},
 };
 use codec::Encode;
 use cumulus_client_collator::service::{
@@ -45,7 +46,7 @@ use cumulus_client_consensus_aura::{
 	},
 	equivocation_import_queue::Verifier as EquivocationVerifier,
 };
-use cumulus_client_consensus_proposer::ProposerInterface;
+use cumulus_client_consensus_proposer::{EncryptedExtrinsicsProposer, ProposerInterface};
 use cumulus_client_consensus_relay_chain::Verifier as RelayChainVerifier;
 use cumulus_client_parachain_inherent::MockValidationDataInherentDataProvider;
 use cumulus_client_service::CollatorSybilResistance;
@@ -65,19 +66,38 @@ use sc_consensus::{
 use sc_consensus_manual_seal::consensus::aura::AuraConsensusDataProvider;
 use sc_network::{config::FullNetworkConfiguration, NotificationMetrics, PeerId};
 use sc_service::{Configuration, Error, PartialComponents, TaskManager};
+use sc_statement_store::Store;
 use sc_telemetry::TelemetryHandle;
 use sc_transaction_pool::TransactionPoolHandle;
 use sc_transaction_pool_api::OffchainTransactionPoolFactory;
 use sp_api::ProvideRuntimeApi;
-use sp_core::traits::SpawnEssentialNamed;
+use sp_core::{ed25519, traits::SpawnEssentialNamed};
 use sp_inherents::CreateInherentDataProviders;
 use sp_keystore::KeystorePtr;
 use sp_runtime::{
 	app_crypto::AppCrypto,
 	traits::{Block as BlockT, Header as HeaderT, UniqueSaturatedInto},
 };
+use sp_statement_store::StatementStore;
 use std::{marker::PhantomData, sync::Arc, time::Duration};
 
+/// The statement store to receive the encrypted extrinsics from, if they are enabled.
+///
+/// Fails if the runtime doesn't use `ed25519` Aura keys, the statement store can't decrypt
+/// statements with any other keys.
+fn encrypted_extrinsics_store<AuraId: AppCrypto>(
+	statement_store: Option<Arc<Store>>,
+) -> Result<Option<Arc<dyn StatementStore>>, Error> {
+	let Some(statement_store) = statement_store else { return Ok(None) };
+	if AuraId::CRYPTO_ID != ed25519::CRYPTO_ID {
+		return Err(Error::Other(
+			"Encrypted extrinsics require a runtime with ed25519 Aura keys".into(),
+		))
+	}
+
+	Ok(Some(statement_store as Arc<dyn StatementStore>))
+}
+
 struct Verifier<Block, Client, AuraId> {
 	client: Arc<Client>,
 	aura_verifier: Box<dyn VerifierT<Block>>,
@@ -211,9 +231,10 @@ where
 	type StartConsensus = StartConsensus;
 	const SYBIL_RESISTANCE: CollatorSybilResistance = CollatorSybilResistance::Resistant;
 
-	fn start_dev_node(
+	fn start_dev_node<Extension: NodeExtension>(
 		mut config: Configuration,
 		mode: DevSealMode,
+		extension: Extension,
 	) -> sc_service::error::Result<TaskManager> {
 		let PartialComponents {
 			client,
@@ -349,18 +370,31 @@ where
 			},
 		}
 
+		let extension_context = NodeExtensionContext {
+			client: client.clone(),
+			backend: backend.clone(),
+			transaction_pool: transaction_pool.clone(),
+			spawn_handle: task_manager.spawn_handle(),
+			prometheus_registry: config.prometheus_registry().cloned(),
+		};
+
 		let rpc_extensions_builder = {
 			let client = client.clone();
 			let transaction_pool = transaction_pool.clone();
 			let backend_for_rpc = backend.clone();
+			let extension = extension.clone();
+			let extension_context = extension_context.clone();
 
 			Box::new(move |_| {
-				let module = Self::BuildRpcExtensions::build_rpc_extensions(
+				let mut module = Self::BuildRpcExtensions::build_rpc_extensions(
 					client.clone(),
 					backend_for_rpc.clone(),
 					transaction_pool.clone(),
 					None,
 				)?;
+				module
+					.merge(extension.rpc_modules(&extension_context)?)
+					.map_err(|e| sc_service::Error::Application(Box::new(e)))?;
 				Ok(module)
 			})
 		};
@@ -381,6 +415,8 @@ where
 			tracing_execute_block: None,
 		})?;
 
+		extension.spawn_tasks(&extension_context)?;
+
 		Ok(task_manager)
 	}
 }
@@ -451,9 +487,9 @@ where
 	}
 }
 
-pub fn new_aura_node_spec<Block, RuntimeApi, AuraId>(
+pub fn new_aura_node_spec<Block, RuntimeApi, AuraId, Extension>(
 	extra_args: &NodeExtraArgs,
-) -> Box<dyn DynNodeSpec>
+) -> Box<dyn DynNodeSpec<Extension>>
 where
 	Block: NodeBlock,
 	RuntimeApi: ConstructNodeRuntimeApi<Block, ParachainClient<Block, RuntimeApi>>,
@@ -463,6 +499,7 @@ where
 		+ GetParachainInfo<Block>,
 	AuraId: AuraIdT + Sync + Send,
 	<AuraId as AppCrypto>::Pair: Send + Sync,
+	Extension: NodeExtension,
 {
 	if extra_args.authoring_policy == AuthoringPolicy::SlotBased {
 		Box::new(AuraNode::<
@@ -564,6 +601,7 @@ where
 		relay_chain_interface: Arc<dyn RelayChainInterface>,
 		transaction_pool: Arc<TransactionPoolHandle<Block, ParachainClient<Block, RuntimeApi>>>,
 		keystore: KeystorePtr,
+		statement_store: Option<Arc<Store>>,
 		relay_chain_slot_duration: Duration,
 		para_id: ParaId,
 		collator_key: CollatorPair,
@@ -574,12 +612,17 @@ where
 		node_extra_args: NodeExtraArgs,
 		block_import_handle: SlotBasedBlockImportHandle<Block>,
 	) -> Result<(), Error> {
-		let proposer = sc_basic_authorship::ProposerFactory::with_proof_recording(
-			task_manager.spawn_handle(),
+		let proposer = EncryptedExtrinsicsProposer::new(
+			sc_basic_authorship::ProposerFactory::with_proof_recording(
+				task_manager.spawn_handle(),
+				client.clone(),
+				transaction_pool,
+				prometheus_registry,
+				telemetry.clone(),
+			),
 			client.clone(),
-			transaction_pool,
-			prometheus_registry,
-			telemetry.clone(),
+			keystore.clone(),
+			encrypted_extrinsics_store::<AuraId>(statement_store)?,
 		);
 
 		let collator_service = CollatorService::new(
@@ -613,6 +656,7 @@ where
 			spawner: task_manager.spawn_essential_handle(),
 			export_pov: node_extra_args.export_pov,
 			max_pov_percentage: node_extra_args.max_pov_percentage,
+			prometheus_registry: prometheus_registry.cloned(),
 		};
 
 		// We have a separate function only to be able to use `docify::export` on this piece of
@@ -691,6 +735,7 @@ where
 		relay_chain_interface: Arc<dyn RelayChainInterface>,
 		transaction_pool: Arc<TransactionPoolHandle<Block, ParachainClient<Block, RuntimeApi>>>,
 		keystore: KeystorePtr,
+		statement_store: Option<Arc<Store>>,
 		relay_chain_slot_duration: Duration,
 		para_id: ParaId,
 		collator_key: CollatorPair,
@@ -701,12 +746,17 @@ where
 		node_extra_args: NodeExtraArgs,
 		_: (),
 	) -> Result<(), Error> {
-		let proposer = sc_basic_authorship::ProposerFactory::with_proof_recording(
-			task_manager.spawn_handle(),
+		let proposer = EncryptedExtrinsicsProposer::new(
+			sc_basic_authorship::ProposerFactory::with_proof_recording(
+				task_manager.spawn_handle(),
+				client.clone(),
+				transaction_pool,
+				prometheus_registry,
+				telemetry.clone(),
+			),
 			client.clone(),
-			transaction_pool,
-			prometheus_registry,
-			telemetry.clone(),
+			keystore.clone(),
+			encrypted_extrinsics_store::<AuraId>(statement_store)?,
 		);
 		let collator_service = CollatorService::new(
 			client.clone(),
@@ -0,0 +1,1185 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Standalone chains that are run without a relay chain, using GRANDPA for finality and either
+//! Aura or BABE for block production.
+
+use crate::{
+	cli::DevSealMode,
+	common::{
+		aura::AuraIdT,
+		command::NodeCommandRunner,
+		rpc::RpcExtension,
+		spec::DynNodeSpec,
+		types::{
+			AccountId, Balance, Hash, Nonce, ParachainBackend, ParachainClient,
+			ParachainHostFunctions,
+		},
+		NodeBlock, NodeExtraArgs,
+	},
+	node_extension::{NodeExtension, NodeExtensionContext},
+};
+use cumulus_client_cli::{CollatorOptions, ExportGenesisHeadCommand};
+use frame_benchmarking_cli::BlockCmd;
+#[cfg(any(feature = "runtime-benchmarks"))]
+use frame_benchmarking_cli::StorageCmd;
+use futures::FutureExt;
+use pallet_transaction_payment_rpc::{TransactionPayment, TransactionPaymentApiServer};
+use prometheus_endpoint::Registry;
+use sc_cli::{CheckBlockCmd, ExportBlocksCmd, ExportStateCmd, ImportBlocksCmd, RevertCmd};
+use sc_client_api::{Backend, BlockBackend, HeaderBackend};
+use sc_consensus::{DefaultImportQueue, LongestChain};
+use sc_consensus_grandpa::SharedVoterState;
+use sc_consensus_manual_seal::consensus::{aura::AuraConsensusDataProvider, ConsensusDataProvider};
+use sc_executor::{HeapAllocStrategy, DEFAULT_HEAP_ALLOC_STRATEGY};
+use sc_network::{config::FullNetworkConfiguration, NetworkBackend, NotificationMetrics};
+use sc_network_sync::SyncingService;
+use sc_rpc::dev::{Dev, DevApiServer};
+use sc_service::{Configuration, PartialComponents, TaskManager, WarpSyncConfig};
+use sc_storage_monitor::StorageMonitorParams;
+use sc_sysinfo::HwBench;
+use sc_telemetry::{Telemetry, TelemetryHandle, TelemetryWorker};
+use sc_transaction_pool::TransactionPoolHandle;
+use sc_transaction_pool_api::OffchainTransactionPoolFactory;
+use sp_api::{ApiExt, CallApiAt, ConstructRuntimeApi, Metadata};
+use sp_block_builder::BlockBuilder;
+use sp_consensus_aura::{
+	inherents::InherentDataProvider as AuraInherentDataProvider, AuraApi, SlotDuration,
+};
+use sp_consensus_babe::{
+	inherents::{
+		BabeCreateInherentDataProviders, InherentDataProvider as BabeInherentDataProvider,
+	},
+	BabeApi,
+};
+use sp_consensus_grandpa::GrandpaApi;
+use sp_keystore::KeystorePtr;
+use sp_offchain::OffchainWorkerApi;
+use sp_runtime::{
+	app_crypto::Pair,
+	traits::{NumberFor, UniqueSaturatedInto},
+};
+use sp_session::SessionKeys;
+use sp_transaction_pool::runtime_api::TaggedTransactionQueue;
+use std::{future::Future, marker::PhantomData, pin::Pin, sync::Arc, time::Duration};
+use substrate_frame_rpc_system::{System, SystemApiServer};
+
+/// The minimum period of blocks on which justifications will be imported and generated.
+const GRANDPA_JUSTIFICATION_PERIOD: u32 = 512;
+
+/// Convenience trait that defines the basic bounds for the `RuntimeApi` of a solochain node.
+pub trait SolochainRuntimeApi<Block: NodeBlock>:
+	ApiExt<Block>
+	+ Metadata<Block>
+	+ SessionKeys<Block>
+	+ BlockBuilder<Block>
+	+ TaggedTransactionQueue<Block>
+	+ OffchainWorkerApi<Block>
+	+ GrandpaApi<Block>
+	+ pallet_transaction_payment_rpc::TransactionPaymentRuntimeApi<Block, Balance>
+	+ substrate_frame_rpc_system::AccountNonceApi<Block, AccountId, Nonce>
+	+ Sized
+{
+}
+
+impl<T, Block: NodeBlock> SolochainRuntimeApi<Block> for T where
+	T: ApiExt<Block>
+		+ Metadata<Block>
+		+ SessionKeys<Block>
+		+ BlockBuilder<Block>
+		+ TaggedTransactionQueue<Block>
+		+ OffchainWorkerApi<Block>
+		+ GrandpaApi<Block>
+		+ pallet_transaction_payment_rpc::TransactionPaymentRuntimeApi<Block, Balance>
+		+ substrate_frame_rpc_system::AccountNonceApi<Block, AccountId, Nonce>
+{
+}
+
+/// Convenience trait that defines the basic bounds for the `ConstructRuntimeApi` of a solochain
+/// node.
+pub trait ConstructSolochainRuntimeApi<Block: NodeBlock, C: CallApiAt<Block>>:
+	ConstructRuntimeApi<Block, C, RuntimeApi = Self::BoundedRuntimeApi> + Send + Sync + 'static
+{
+	/// Basic bounds for the `RuntimeApi` of a solochain node.
+	type BoundedRuntimeApi: SolochainRuntimeApi<Block>;
+}
+
+impl<T, Block: NodeBlock, C: CallApiAt<Block>> ConstructSolochainRuntimeApi<Block, C> for T
+where
+	T: ConstructRuntimeApi<Block, C> + Send + Sync + 'static,
+	T::RuntimeApi: SolochainRuntimeApi<Block>,
+{
+	type BoundedRuntimeApi = T::RuntimeApi;
+}
+
+type SelectChain<Block> = LongestChain<ParachainBackend<Block>, Block>;
+
+type GrandpaBlockImport<Block, RuntimeApi> = sc_consensus_grandpa::GrandpaBlockImport<
+	ParachainBackend<Block>,
+	Block,
+	ParachainClient<Block, RuntimeApi>,
+	SelectChain<Block>,
+>;
+
+type GrandpaLink<Block, RuntimeApi> =
+	sc_consensus_grandpa::LinkHalf<Block, ParachainClient<Block, RuntimeApi>, SelectChain<Block>>;
+
+type SolochainTransactionPool<Block, RuntimeApi> =
+	TransactionPoolHandle<Block, ParachainClient<Block, RuntimeApi>>;
+
+/// Assembly of PartialComponents (enough to run chain ops subcommands)
+type SolochainService<Block, RuntimeApi, BlockImport, Link> = PartialComponents<
+	ParachainClient<Block, RuntimeApi>,
+	ParachainBackend<Block>,
+	SelectChain<Block>,
+	DefaultImportQueue<Block>,
+	SolochainTransactionPool<Block, RuntimeApi>,
+	(BlockImport, Link, GrandpaLink<Block, RuntimeApi>, Option<Telemetry>),
+>;
+
+/// The consensus data provider and slot duration used to seal blocks in dev mode.
+type DevSealConsensus<Block, Proof> =
+	(Box<dyn ConsensusDataProvider<Block, Proof = Proof>>, SlotDuration);
+
+/// The block production consensus of a solochain.
+pub(crate) trait SolochainConsensus<Block: NodeBlock, RuntimeApi>: 'static {
+	/// The block import used by the import queue and by block authoring.
+	type BlockImport: sc_consensus::BlockImport<Block, Error = sp_consensus::Error>
+		+ Clone
+		+ Send
+		+ Sync
+		+ 'static;
+
+	/// Data shared between the import queue and block authoring.
+	type Link: Send + 'static;
+
+	/// Build the import queue on top of the GRANDPA block import.
+	fn build_import_queue(
+		client: Arc<ParachainClient<Block, RuntimeApi>>,
+		grandpa_block_import: GrandpaBlockImport<Block, RuntimeApi>,
+		select_chain: SelectChain<Block>,
+		transaction_pool: Arc<SolochainTransactionPool<Block, RuntimeApi>>,
+		config: &Configuration,
+		telemetry: Option<TelemetryHandle>,
+		task_manager: &TaskManager,
+	) -> sc_service::error::Result<(DefaultImportQueue<Block>, Self::BlockImport, Self::Link)>;
+
+	/// Start authoring blocks.
+	fn start_authoring(
+		client: Arc<ParachainClient<Block, RuntimeApi>>,
+		block_import: Self::BlockImport,
+		link: Self::Link,
+		select_chain: SelectChain<Block>,
+		transaction_pool: Arc<SolochainTransactionPool<Block, RuntimeApi>>,
+		sync_service: Arc<SyncingService<Block>>,
+		keystore: KeystorePtr,
+		force_authoring: bool,
+		prometheus_registry: Option<&Registry>,
+		telemetry: Option<TelemetryHandle>,
+		task_manager: &TaskManager,
+	) -> sc_service::error::Result<()>;
+
+	/// Whether blocks of this consensus can be sealed manually in dev mode.
+	const SUPPORTS_DEV_SEAL: bool;
+
+	/// The consensus data provider and the slot duration used to seal blocks in dev mode.
+	fn dev_seal_consensus<P: Send + Sync + 'static>(
+		client: &ParachainClient<Block, RuntimeApi>,
+	) -> sc_service::error::Result<DevSealConsensus<Block, P>>;
+
+	/// Revert the consensus data kept in the aux db when the last `blocks` blocks are reverted.
+	///
+	/// The GRANDPA data is reverted by the caller.
+	fn revert(
+		client: Arc<ParachainClient<Block, RuntimeApi>>,
+		backend: Arc<ParachainBackend<Block>>,
+		blocks: NumberFor<Block>,
+	) -> sc_client_api::blockchain::Result<()>;
+}
+
+/// Aura block production.
+pub(crate) struct AuraGrandpaConsensus<AuraId>(PhantomData<AuraId>);
+
+impl<Block, RuntimeApi, AuraId> SolochainConsensus<Block, RuntimeApi>
+	for AuraGrandpaConsensus<AuraId>
+where
+	Block: NodeBlock,
+	RuntimeApi: ConstructSolochainRuntimeApi<Block, ParachainClient<Block, RuntimeApi>>,
+	RuntimeApi::RuntimeApi: AuraApi<Block, <AuraId::BoundedPair as Pair>::Public>,
+	AuraId: AuraIdT + Sync + 'static,
+{
+	type BlockImport = GrandpaBlockImport<Block, RuntimeApi>;
+	type Link = ();
+
+	const SUPPORTS_DEV_SEAL: bool = true;
+
+	fn build_import_queue(
+		client: Arc<ParachainClient<Block, RuntimeApi>>,
+		grandpa_block_import: GrandpaBlockImport<Block, RuntimeApi>,
+		_select_chain: SelectChain<Block>,
+		_transaction_pool: Arc<SolochainTransactionPool<Block, RuntimeApi>>,
+		config: &Configuration,
+		telemetry: Option<TelemetryHandle>,
+		task_manager: &TaskManager,
+	) -> sc_service::error::Result<(DefaultImportQueue<Block>, Self::BlockImport, Self::Link)> {
+		let cidp_client = client.clone();
+		let import_queue = sc_consensus_aura::import_queue::<AuraId::BoundedPair, _, _, _, _, _>(
+			sc_consensus_aura::ImportQueueParams {
+				block_import: grandpa_block_import.clone(),
+				justification_import: Some(Box::new(grandpa_block_import.clone())),
+				client,
+				create_inherent_data_providers: move |parent_hash, _| {
+					let cidp_client = cidp_client.clone();
+					async move {
+						let slot_duration = sc_consensus_aura::standalone::slot_duration_at(
+							&*cidp_client,
+							parent_hash,
+						)?;
+						let timestamp = sp_timestamp::InherentDataProvider::from_system_time();
+						let slot = AuraInherentDataProvider::from_timestamp_and_slot_duration(
+							*timestamp,
+							slot_duration,
+						);
+
+						Ok((slot, timestamp))
+					}
+				},
+				spawner: &task_manager.spawn_essential_handle(),
+				registry: config.prometheus_registry(),
+				check_for_equivocation: Default::default(),
+				telemetry,
+				compatibility_mode: Default::default(),
+			},
+		)?;
+
+		Ok((import_queue, grandpa_block_import, ()))
+	}
+
+	fn start_authoring(
+		client: Arc<ParachainClient<Block, RuntimeApi>>,
+		block_import: Self::BlockImport,
+		_link: Self::Link,
+		select_chain: SelectChain<Block>,
+		transaction_pool: Arc<SolochainTransactionPool<Block, RuntimeApi>>,
+		sync_service: Arc<SyncingService<Block>>,
+		keystore: KeystorePtr,
+		force_authoring: bool,
+		prometheus_registry: Option<&Registry>,
+		telemetry: Option<TelemetryHandle>,
+		task_manager: &TaskManager,
+	) -> sc_service::error::Result<()> {
+		let proposer_factory = sc_basic_authorship::ProposerFactory::new(
+			task_manager.spawn_handle(),
+			client.clone(),
+			transaction_pool,
+			prometheus_registry,
+			telemetry.clone(),
+		);
+		let slot_duration = sc_consensus_aura::slot_duration(&*client)?;
+
+		let aura =
+			sc_consensus_aura::start_aura::<AuraId::BoundedPair, _, _, _, _, _, _, _, _, _, _>(
+				sc_consensus_aura::StartAuraParams {
+					slot_duration,
+					client,
+					select_chain,
+					block_import,
+					proposer_factory,
+					create_inherent_data_providers: move |_, ()| async move {
+						let timestamp = sp_timestamp::InherentDataProvider::from_system_time();
+						let slot = AuraInherentDataProvider::from_timestamp_and_slot_duration(
+							*timestamp,
+							slot_duration,
+						);
+
+						Ok((slot, timestamp))
+					},
+					force_authoring,
+					backoff_authoring_blocks: None::<()>,
+					keystore,
+					sync_oracle: sync_service.clone(),
+					justification_sync_link: sync_service,
+					block_proposal_slot_portion: sc_consensus_aura::SlotProportion::new(
+						2f32 / 3f32,
+					),
+					max_block_proposal_slot_portion: None,
+					telemetry,
+					compatibility_mode: Default::default(),
+				},
+			)?;
+
+		task_manager
+			.spawn_essential_handle()
+			.spawn_blocking("aura", Some("block-authoring"), aura);
+
+		Ok(())
+	}
+
+	fn dev_seal_consensus<P: Send + Sync + 'static>(
+		client: &ParachainClient<Block, RuntimeApi>,
+	) -> sc_service::error::Result<DevSealConsensus<Block, P>> {
+		let slot_duration = sc_consensus_aura::slot_duration(client)?;
+		let provider = AuraConsensusDataProvider::<Block, P>::new_with_slot_duration(slot_duration);
+
+		Ok((Box::new(provider), slot_duration))
+	}
+
+	fn revert(
+		_client: Arc<ParachainClient<Block, RuntimeApi>>,
+		_backend: Arc<ParachainBackend<Block>>,
+		_blocks: NumberFor<Block>,
+	) -> sc_client_api::blockchain::Result<()> {
+		// Aura keeps nothing in the aux db.
+		Ok(())
+	}
+}
+
+/// BABE block production.
+pub(crate) struct BabeGrandpaConsensus;
+
+impl<Block, RuntimeApi> SolochainConsensus<Block, RuntimeApi> for BabeGrandpaConsensus
+where
+	Block: NodeBlock,
+	RuntimeApi: ConstructSolochainRuntimeApi<Block, ParachainClient<Block, RuntimeApi>>,
+	RuntimeApi::RuntimeApi: BabeApi<Block>,
+{
+	type BlockImport = sc_consensus_babe::BabeBlockImport<
+		Block,
+		ParachainClient<Block, RuntimeApi>,
+		GrandpaBlockImport<Block, RuntimeApi>,
+		BabeCreateInherentDataProviders<Block>,
+		SelectChain<Block>,
+	>;
+	type Link = sc_consensus_babe::BabeLink<Block>;
+
+	// Sealing BABE blocks requires claiming the slots of an epoch, so in dev mode the regular node
+	// is started instead and authors blocks with the development keys.
+	const SUPPORTS_DEV_SEAL: bool = false;
+
+	fn build_import_queue(
+		client: Arc<ParachainClient<Block, RuntimeApi>>,
+		grandpa_block_import: GrandpaBlockImport<Block, RuntimeApi>,
+		select_chain: SelectChain<Block>,
+		transaction_pool: Arc<SolochainTransactionPool<Block, RuntimeApi>>,
+		config: &Configuration,
+		telemetry: Option<TelemetryHandle>,
+		task_manager: &TaskManager,
+	) -> sc_service::error::Result<(DefaultImportQueue<Block>, Self::BlockImport, Self::Link)> {
+		let babe_config = sc_consensus_babe::configuration(&*client)?;
+		let slot_duration = babe_config.slot_duration();
+		let (block_import, babe_link) = sc_consensus_babe::block_import(
+			babe_config,
+			grandpa_block_import.clone(),
+			client.clone(),
+			Arc::new(move |_, _| async move {
+				let timestamp = sp_timestamp::InherentDataProvider::from_system_time();
+				let slot = BabeInherentDataProvider::from_timestamp_and_slot_duration(
+					*timestamp,
+					slot_duration,
+				);
+
+				Ok((slot, timestamp))
+			}) as BabeCreateInherentDataProviders<Block>,
+			select_chain,
+			OffchainTransactionPoolFactory::new(transaction_pool),
+		)?;
+
+		let (import_queue, _babe_worker_handle) =
+			sc_consensus_babe::import_queue(sc_consensus_babe::ImportQueueParams {
+				link: babe_link.clone(),
+				block_import: block_import.clone(),
+				justification_import: Some(Box::new(grandpa_block_import)),
+				client,
+				slot_duration,
+				spawner: &task_manager.spawn_essential_handle(),
+				registry: config.prometheus_registry(),
+				telemetry,
+			})?;
+
+		Ok((import_queue, block_import, babe_link))
+	}
+
+	fn start_authoring(
+		client: Arc<ParachainClient<Block, RuntimeApi>>,
+		block_import: Self::BlockImport,
+		link: Self::Link,
+		select_chain: SelectChain<Block>,
+		transaction_pool: Arc<SolochainTransactionPool<Block, RuntimeApi>>,
+		sync_service: Arc<SyncingService<Block>>,
+		keystore: KeystorePtr,
+		force_authoring: bool,
+		prometheus_registry: Option<&Registry>,
+		telemetry: Option<TelemetryHandle>,
+		task_manager: &TaskManager,
+	) -> sc_service::error::Result<()> {
+		let proposer = sc_basic_authorship::ProposerFactory::new(
+			task_manager.spawn_handle(),
+			client.clone(),
+			transaction_pool,
+			prometheus_registry,
+			telemetry.clone(),
+		);
+		let slot_duration = link.config().slot_duration();
+
+		let babe = sc_consensus_babe::start_babe(sc_consensus_babe::BabeParams {
+			keystore,
+			client,
+			select_chain,
+			env: proposer,
+			block_import,
+			sync_oracle: sync_service.clone(),
+			justification_sync_link: sync_service,
+			create_inherent_data_providers: move |_, ()| async move {
+				let timestamp = sp_timestamp::InherentDataProvider::from_system_time();
+				let slot = BabeInherentDataProvider::from_timestamp_and_slot_duration(
+					*timestamp,
+					slot_duration,
+				);
+
+				Ok((slot, timestamp))
+			},
+			force_authoring,
+			backoff_authoring_blocks: None::<()>,
+			babe_link: link,
+			block_proposal_slot_portion: sc_consensus_babe::SlotProportion::new(0.5),
+			max_block_proposal_slot_portion: None,
+			telemetry,
+		})?;
+
+		task_manager.spawn_essential_handle().spawn_blocking(
+			"babe-proposer",
+			Some("block-authoring"),
+			babe,
+		);
+
+		Ok(())
+	}
+
+	fn dev_seal_consensus<P: Send + Sync + 'static>(
+		_client: &ParachainClient<Block, RuntimeApi>,
+	) -> sc_service::error::Result<DevSealConsensus<Block, P>> {
+		Err("Blocks of BABE runtimes can't be sealed in dev mode".into())
+	}
+
+	fn revert(
+		client: Arc<ParachainClient<Block, RuntimeApi>>,
+		backend: Arc<ParachainBackend<Block>>,
+		blocks: NumberFor<Block>,
+	) -> sc_client_api::blockchain::Result<()> {
+		sc_consensus_babe::revert(client, backend, blocks)
+	}
+}
+
+/// A solochain node, built from the `Consensus` used for block production and GRANDPA.
+pub(crate) struct SolochainNode<Block, RuntimeApi, Consensus>(
+	PhantomData<(Block, RuntimeApi, Consensus)>,
+);
+
+impl<Block, RuntimeApi, Consensus> Default for SolochainNode<Block, RuntimeApi, Consensus> {
+	fn default() -> Self {
+		Self(Default::default())
+	}
+}
+
+impl<Block, RuntimeApi, Consensus> SolochainNode<Block, RuntimeApi, Consensus>
+where
+	Block: NodeBlock,
+	RuntimeApi: ConstructSolochainRuntimeApi<Block, ParachainClient<Block, RuntimeApi>>,
+	Consensus: SolochainConsensus<Block, RuntimeApi>,
+{
+	/// Starts a `ServiceBuilder` for a full service.
+	fn new_partial(
+		config: &Configuration,
+	) -> sc_service::error::Result<
+		SolochainService<Block, RuntimeApi, Consensus::BlockImport, Consensus::Link>,
+	> {
+		let telemetry = config
+			.telemetry_endpoints
+			.clone()
+			.filter(|x| !x.is_empty())
+			.map(|endpoints| -> Result<_, sc_telemetry::Error> {
+				let worker = TelemetryWorker::new(16)?;
+				let telemetry = worker.handle().new_telemetry(endpoints);
+				Ok((worker, telemetry))
+			})
+			.transpose()?;
+
+		let heap_pages =
+			config.executor.default_heap_pages.map_or(DEFAULT_HEAP_ALLOC_STRATEGY, |h| {
+				HeapAllocStrategy::Static { extra_pages: h as _ }
+			});
+
+		let executor = sc_executor::WasmExecutor::<ParachainHostFunctions>::builder()
+			.with_execution_method(config.executor.wasm_method)
+			.with_max_runtime_instances(config.executor.max_runtime_instances)
+			.with_runtime_cache_size(config.executor.runtime_cache_size)
+			.with_onchain_heap_alloc_strategy(heap_pages)
+			.with_offchain_heap_alloc_strategy(heap_pages)
+			.build();
+
+		let (client, backend, keystore_container, task_manager) =
+			sc_service::new_full_parts::<Block, RuntimeApi, _>(
+				config,
+				telemetry.as_ref().map(|(_, telemetry)| telemetry.handle()),
+				executor,
+			)?;
+		let client = Arc::new(client);
+
+		let telemetry = telemetry.map(|(worker, telemetry)| {
+			task_manager.spawn_handle().spawn("telemetry", None, worker.run());
+			telemetry
+		});
+
+		let select_chain = LongestChain::new(backend.clone());
+
+		let transaction_pool = Arc::from(
+			sc_transaction_pool::Builder::new(
+				task_manager.spawn_essential_handle(),
+				client.clone(),
+				config.role.is_authority().into(),
+			)
+			.with_options(config.transaction_pool.clone())
+			.with_prometheus(config.prometheus_registry())
+			.build(),
+		);
+
+		let (grandpa_block_import, grandpa_link) = sc_consensus_grandpa::block_import(
+			client.clone(),
+			GRANDPA_JUSTIFICATION_PERIOD,
+			&client,
+			select_chain.clone(),
+			telemetry.as_ref().map(|telemetry| telemetry.handle()),
+		)?;
+
+		let (import_queue, block_import, link) = Consensus::build_import_queue(
+			client.clone(),
+			grandpa_block_import,
+			select_chain.clone(),
+			transaction_pool.clone(),
+			config,
+			telemetry.as_ref().map(|telemetry| telemetry.handle()),
+			&task_manager,
+		)?;
+
+		Ok(PartialComponents {
+			backend,
+			client,
+			import_queue,
+			keystore_container,
+			task_manager,
+			transaction_pool,
+			select_chain,
+			other: (block_import, link, grandpa_link, telemetry),
+		})
+	}
+
+	fn build_rpc_extensions(
+		client: Arc<ParachainClient<Block, RuntimeApi>>,
+		pool: Arc<SolochainTransactionPool<Block, RuntimeApi>>,
+	) -> sc_service::error::Result<RpcExtension> {
+		let build = || -> Result<RpcExtension, Box<dyn std::error::Error + Send + Sync>> {
+			let mut module = RpcExtension::new(());
+
+			module.merge(System::new(client.clone(), pool).into_rpc())?;
+			module.merge(TransactionPayment::new(client.clone()).into_rpc())?;
+			module.merge(Dev::new(client).into_rpc())?;
+
+			Ok(module)
+		};
+		build().map_err(Into::into)
+	}
+
+	/// Start a full node that authors blocks with `Consensus` and finalizes them with GRANDPA.
+	fn start_node<Net, Extension>(
+		config: Configuration,
+		hwbench: Option<HwBench>,
+		storage_monitor: StorageMonitorParams,
+		extension: Extension,
+	) -> sc_service::error::Result<TaskManager>
+	where
+		Net: NetworkBackend<Block, Hash>,
+		Extension: NodeExtension,
+	{
+		let PartialComponents {
+			client,
+			backend,
+			mut task_manager,
+			import_queue,
+			keystore_container,
+			select_chain,
+			transaction_pool,
+			other: (block_import, link, grandpa_link, mut telemetry),
+		} = Self::new_partial(&config)?;
+
+		let mut net_config = FullNetworkConfiguration::<Block, Hash, Net>::new(
+			&config.network,
+			config.prometheus_registry().cloned(),
+		);
+		let metrics = Net::register_notification_metrics(config.prometheus_registry());
+
+		let peer_store_handle = net_config.peer_store_handle();
+		let grandpa_protocol_name = sc_consensus_grandpa::protocol_standard_name(
+			&client
+				.block_hash(0u32.into())
+				.ok()
+				.flatten()
+				.expect("Genesis block exists; qed"),
+			&config.chain_spec,
+		);
+		let (grandpa_protocol_config, grandpa_notification_service) =
+			sc_consensus_grandpa::grandpa_peers_set_config::<_, Net>(
+				grandpa_protocol_name.clone(),
+				metrics.clone(),
+				peer_store_handle,
+			);
+		net_config.add_notification_protocol(grandpa_protocol_config);
+
+		let warp_sync = Arc::new(sc_consensus_grandpa::warp_proof::NetworkProvider::new(
+			backend.clone(),
+			grandpa_link.shared_authority_set().clone(),
+			Vec::default(),
+		));
+
+		let (network, system_rpc_tx, tx_handler_controller, sync_service) =
+			sc_service::build_network(sc_service::BuildNetworkParams {
+				config: &config,
+				net_config,
+				client: client.clone(),
+				transaction_pool: transaction_pool.clone(),
+				spawn_handle: task_manager.spawn_handle(),
+				import_queue,
+				block_announce_validator_builder: None,
+				warp_sync_config: Some(WarpSyncConfig::WithProvider(warp_sync)),
+				block_relay: None,
+				metrics,
+			})?;
+
+		if config.offchain_worker.enabled {
+			let offchain_workers =
+				sc_offchain::OffchainWorkers::new(sc_offchain::OffchainWorkerOptions {
+					runtime_api_provider: client.clone(),
+					keystore: Some(keystore_container.keystore()),
+					offchain_db: backend.offchain_storage(),
+					transaction_pool: Some(OffchainTransactionPoolFactory::new(
+						transaction_pool.clone(),
+					)),
+					network_provider: Arc::new(network.clone()),
+					is_validator: config.role.is_authority(),
+					enable_http_requests: true,
+					custom_extensions: |_| vec![],
+				})?;
+			task_manager.spawn_handle().spawn(
+				"offchain-workers-runner",
+				"offchain-work",
+				offchain_workers.run(client.clone(), task_manager.spawn_handle()).boxed(),
+			);
+		}
+
+		let role = config.role;
+		let force_authoring = config.force_authoring;
+		let name = config.network.node_name.clone();
+		let enable_grandpa = !config.disable_grandpa;
+		let prometheus_registry = config.prometheus_registry().cloned();
+		let database_path = config.database.path().map(|p| p.to_path_buf());
+
+		let extension_context = NodeExtensionContext {
+			client: client.clone(),
+			backend: backend.clone(),
+			transaction_pool: transaction_pool.clone(),
+			spawn_handle: task_manager.spawn_handle(),
+			prometheus_registry: prometheus_registry.clone(),
+		};
+
+		let rpc_builder = {
+			let client = client.clone();
+			let transaction_pool = transaction_pool.clone();
+			let extension = extension.clone();
+			let extension_context = extension_context.clone();
+
+			Box::new(move |_| {
+				let mut module =
+					Self::build_rpc_extensions(client.clone(), transaction_pool.clone())?;
+				module
+					.merge(extension.rpc_modules(&extension_context)?)
+					.map_err(|e| sc_service::Error::Application(Box::new(e)))?;
+				Ok(module)
+			})
+		};
+
+		sc_service::spawn_tasks(sc_service::SpawnTasksParams {
+			network: network.clone(),
+			client: client.clone(),
+			keystore: keystore_container.keystore(),
+			task_manager: &mut task_manager,
+			transaction_pool: transaction_pool.clone(),
+			rpc_builder,
+			backend,
+			system_rpc_tx,
+			tx_handler_controller,
+			sync_service: sync_service.clone(),
+			config,
+			telemetry: telemetry.as_mut(),
+			tracing_execute_block: None,
+		})?;
+
+		extension.spawn_tasks(&extension_context)?;
+
+		// Spawn the storage monitor
+		if let Some(database_path) = database_path {
+			sc_storage_monitor::StorageMonitorService::try_spawn(
+				storage_monitor,
+				database_path,
+				&task_manager.spawn_essential_handle(),
+			)
+			.map_err(|e| sc_service::Error::Application(Box::new(e) as Box<_>))?;
+		}
+
+		if let Some(hwbench) = hwbench {
+			sc_sysinfo::print_hwbench(&hwbench);
+
+			if let Some(ref mut telemetry) = telemetry {
+				let telemetry_handle = telemetry.handle();
+				task_manager.spawn_handle().spawn(
+					"telemetry_hwbench",
+					None,
+					sc_sysinfo::initialize_hwbench_telemetry(telemetry_handle, hwbench),
+				);
+			}
+		}
+
+		if role.is_authority() {
+			Consensus::start_authoring(
+				client,
+				block_import,
+				link,
+				select_chain,
+				transaction_pool.clone(),
+				sync_service.clone(),
+				keystore_container.keystore(),
+				force_authoring,
+				prometheus_registry.as_ref(),
+				telemetry.as_ref().map(|telemetry| telemetry.handle()),
+				&task_manager,
+			)?;
+		}
+
+		if enable_grandpa {
+			// If the node isn't actively participating in consensus then it doesn't need a
+			// keystore.
+			let keystore = role.is_authority().then(|| keystore_container.keystore());
+
+			let grandpa_config = sc_consensus_grandpa::Config {
+				gossip_duration: Duration::from_millis(333),
+				justification_generation_period: GRANDPA_JUSTIFICATION_PERIOD,
+				name: Some(name),
+				observer_enabled: false,
+				keystore,
+				local_role: role,
+				telemetry: telemetry.as_ref().map(|telemetry| telemetry.handle()),
+				protocol_name: grandpa_protocol_name,
+			};
+
+			let grandpa_params = sc_consensus_grandpa::GrandpaParams {
+				config: grandpa_config,
+				link: grandpa_link,
+				network,
+				sync: sync_service,
+				notification_service: grandpa_notification_service,
+				voting_rule: sc_consensus_grandpa::VotingRulesBuilder::default().build(),
+				prometheus_registry,
+				shared_voter_state: SharedVoterState::empty(),
+				telemetry: telemetry.as_ref().map(|telemetry| telemetry.handle()),
+				offchain_tx_pool_factory: OffchainTransactionPoolFactory::new(transaction_pool),
+			};
+
+			task_manager.spawn_essential_handle().spawn_blocking(
+				"grandpa-voter",
+				None,
+				sc_consensus_grandpa::run_grandpa_voter(grandpa_params)?,
+			);
+		}
+
+		Ok(task_manager)
+	}
+
+	/// Start a node that seals blocks on its own, without running GRANDPA.
+	fn start_dev_node<Extension: NodeExtension>(
+		mut config: Configuration,
+		mode: DevSealMode,
+		extension: Extension,
+	) -> sc_service::error::Result<TaskManager> {
+		if !Consensus::SUPPORTS_DEV_SEAL {
+			log::warn!(
+				"Blocks of this runtime can't be sealed in dev mode, starting a node that authors \
+				blocks with the development keys instead."
+			);
+			return Self::start_node_with_network_backend(
+				config,
+				None,
+				Default::default(),
+				extension,
+			)
+		}
+
+		let PartialComponents {
+			client,
+			backend,
+			mut task_manager,
+			import_queue,
+			keystore_container,
+			select_chain,
+			transaction_pool,
+			other: (block_import, _, _, mut telemetry),
+		} = Self::new_partial(&config)?;
+
+		let (consensus_data_provider, slot_duration) = Consensus::dev_seal_consensus(&client)?;
+
+		// Since this is a dev node, prevent it from connecting to peers.
+		config.network.default_peers_set.in_peers = 0;
+		config.network.default_peers_set.out_peers = 0;
+		let net_config = FullNetworkConfiguration::<_, _, sc_network::Litep2pNetworkBackend>::new(
+			&config.network,
+			None,
+		);
+
+		let (network, system_rpc_tx, tx_handler_controller, sync_service) =
+			sc_service::build_network(sc_service::BuildNetworkParams {
+				config: &config,
+				client: client.clone(),
+				transaction_pool: transaction_pool.clone(),
+				spawn_handle: task_manager.spawn_handle(),
+				import_queue,
+				net_config,
+				block_announce_validator_builder: None,
+				warp_sync_config: None,
+				block_relay: None,
+				metrics: NotificationMetrics::new(None),
+			})?;
+
+		let proposer = sc_basic_authorship::ProposerFactory::new(
+			task_manager.spawn_handle(),
+			client.clone(),
+			transaction_pool.clone(),
+			None,
+			None,
+		);
+
+		// Derive the timestamp from the block number, so that every block lands in a new slot.
+		let create_inherent_data_providers = {
+			let client = client.clone();
+			move |parent: Hash, ()| {
+				let parent_number = client
+					.number(parent)
+					.ok()
+					.flatten()
+					.map_or(0, UniqueSaturatedInto::<u64>::unique_saturated_into);
+				let timestamp = sp_timestamp::InherentDataProvider::new(
+					(slot_duration.as_millis() * (parent_number + 1)).into(),
+				);
+
+				futures::future::ready(Ok(timestamp))
+			}
+		};
+
+		match mode {
+			DevSealMode::InstantSeal => {
+				let params = sc_consensus_manual_seal::InstantSealParams {
+					block_import,
+					env: proposer,
+					client: client.clone(),
+					pool: transaction_pool.clone(),
+					select_chain,
+					consensus_data_provider: Some(consensus_data_provider),
+					create_inherent_data_providers,
+				};
+
+				let authorship_future = sc_consensus_manual_seal::run_instant_seal(params);
+				task_manager.spawn_essential_handle().spawn_blocking(
+					"instant-seal",
+					None,
+					authorship_future,
+				);
+			},
+			DevSealMode::ManualSeal(block_time) => {
+				let (mut manual_seal_sink, manual_seal_stream) =
+					futures::channel::mpsc::channel(1024);
+				task_manager
+					.spawn_essential_handle()
+					.spawn("block_authoring", None, async move {
+						loop {
+							futures_timer::Delay::new(std::time::Duration::from_millis(block_time))
+								.await;
+							manual_seal_sink
+								.try_send(sc_consensus_manual_seal::EngineCommand::SealNewBlock {
+									create_empty: true,
+									finalize: true,
+									parent_hash: None,
+									sender: None,
+								})
+								.unwrap();
+						}
+					});
+
+				let params = sc_consensus_manual_seal::ManualSealParams {
+					block_import,
+					env: proposer,
+					client: client.clone(),
+					pool: transaction_pool.clone(),
+					select_chain,
+					commands_stream: Box::pin(manual_seal_stream),
+					consensus_data_provider: Some(consensus_data_provider),
+					create_inherent_data_providers,
+				};
+
+				let authorship_future = sc_consensus_manual_seal::run_manual_seal(params);
+				task_manager.spawn_essential_handle().spawn_blocking(
+					"manual-seal",
+					None,
+					authorship_future,
+				);
+			},
+		}
+
+		let extension_context = NodeExtensionContext {
+			client: client.clone(),
+			backend: backend.clone(),
+			transaction_pool: transaction_pool.clone(),
+			spawn_handle: task_manager.spawn_handle(),
+			prometheus_registry: config.prometheus_registry().cloned(),
+		};
+
+		let rpc_builder = {
+			let client = client.clone();
+			let transaction_pool = transaction_pool.clone();
+			let extension = extension.clone();
+			let extension_context = extension_context.clone();
+
+			Box::new(move |_| {
+				let mut module =
+					Self::build_rpc_extensions(client.clone(), transaction_pool.clone())?;
+				module
+					.merge(extension.rpc_modules(&extension_context)?)
+					.map_err(|e| sc_service::Error::Application(Box::new(e)))?;
+				Ok(module)
+			})
+		};
+
+		sc_service::spawn_tasks(sc_service::SpawnTasksParams {
+			network,
+			client,
+			keystore: keystore_container.keystore(),
+			task_manager: &mut task_manager,
+			transaction_pool,
+			rpc_builder,
+			backend,
+			system_rpc_tx,
+			tx_handler_controller,
+			sync_service,
+			config,
+			telemetry: telemetry.as_mut(),
+			tracing_execute_block: None,
+		})?;
+
+		extension.spawn_tasks(&extension_context)?;
+
+		Ok(task_manager)
+	}
+
+	fn start_node_with_network_backend<Extension: NodeExtension>(
+		config: Configuration,
+		hwbench: Option<HwBench>,
+		storage_monitor: StorageMonitorParams,
+		extension: Extension,
+	) -> sc_service::error::Result<TaskManager> {
+		match config.network.network_backend {
+			sc_network::config::NetworkBackendType::Libp2p =>
+				Self::start_node::<sc_network::NetworkWorker<_, _>, _>(
+					config,
+					hwbench,
+					storage_monitor,
+					extension,
+				),
+			sc_network::config::NetworkBackendType::Litep2p =>
+				Self::start_node::<sc_network::Litep2pNetworkBackend, _>(
+					config,
+					hwbench,
+					storage_monitor,
+					extension,
+				),
+		}
+	}
+}
+
+impl<Block, RuntimeApi, Consensus, Extension> DynNodeSpec<Extension>
+	for SolochainNode<Block, RuntimeApi, Consensus>
+where
+	Block: NodeBlock,
+	RuntimeApi: ConstructSolochainRuntimeApi<Block, ParachainClient<Block, RuntimeApi>>,
+	Consensus: SolochainConsensus<Block, RuntimeApi>,
+	Extension: NodeExtension,
+{
+	fn start_dev_node(
+		self: Box<Self>,
+		config: Configuration,
+		mode: DevSealMode,
+		extension: Extension,
+	) -> sc_service::error::Result<TaskManager> {
+		Self::start_dev_node(config, mode, extension)
+	}
+
+	fn requires_relay_chain(&self) -> bool {
+		false
+	}
+
+	fn start_node(
+		self: Box<Self>,
+		parachain_config: Configuration,
+		_polkadot_config: Option<Configuration>,
+		_collator_options: CollatorOptions,
+		hwbench: Option<HwBench>,
+		node_extra_args: NodeExtraArgs,
+		extension: Extension,
+	) -> Pin<Box<dyn Future<Output = sc_service::error::Result<TaskManager>>>> {
+		Box::pin(futures::future::ready(Self::start_node_with_network_backend(
+			parachain_config,
+			hwbench,
+			node_extra_args.storage_monitor,
+			extension,
+		)))
+	}
+}
+
+type SyncCmdResult = sc_cli::Result<()>;
+
+type AsyncCmdResult<'a> =
+	sc_cli::Result<(Pin<Box<dyn Future<Output = SyncCmdResult> + 'a>>, TaskManager)>;
+
+impl<Block, RuntimeApi, Consensus> NodeCommandRunner for SolochainNode<Block, RuntimeApi, Consensus>
+where
+	Block: NodeBlock,
+	RuntimeApi: ConstructSolochainRuntimeApi<Block, ParachainClient<Block, RuntimeApi>>,
+	Consensus: SolochainConsensus<Block, RuntimeApi>,
+{
+	fn prepare_check_block_cmd(
+		self: Box<Self>,
+		config: Configuration,
+		cmd: &CheckBlockCmd,
+	) -> AsyncCmdResult<'_> {
+		let partial = Self::new_partial(&config).map_err(sc_cli::Error::Service)?;
+		Ok((Box::pin(cmd.run(partial.client, partial.import_queue)), partial.task_manager))
+	}
+
+	fn prepare_export_blocks_cmd(
+		self: Box<Self>,
+		config: Configuration,
+		cmd: &ExportBlocksCmd,
+	) -> AsyncCmdResult<'_> {
+		let partial = Self::new_partial(&config).map_err(sc_cli::Error::Service)?;
+		Ok((Box::pin(cmd.run(partial.client, config.database)), partial.task_manager))
+	}
+
+	fn prepare_export_state_cmd(
+		self: Box<Self>,
+		config: Configuration,
+		cmd: &ExportStateCmd,
+	) -> AsyncCmdResult<'_> {
+		let partial = Self::new_partial(&config).map_err(sc_cli::Error::Service)?;
+		Ok((Box::pin(cmd.run(partial.client, config.chain_spec)), partial.task_manager))
+	}
+
+	fn prepare_import_blocks_cmd(
+		self: Box<Self>,
+		config: Configuration,
+		cmd: &ImportBlocksCmd,
+	) -> AsyncCmdResult<'_> {
+		let partial = Self::new_partial(&config).map_err(sc_cli::Error::Service)?;
+		Ok((Box::pin(cmd.run(partial.client, partial.import_queue)), partial.task_manager))
+	}
+
+	fn prepare_revert_cmd(
+		self: Box<Self>,
+		config: Configuration,
+		cmd: &RevertCmd,
+	) -> AsyncCmdResult<'_> {
+		let partial = Self::new_partial(&config).map_err(sc_cli::Error::Service)?;
+		let aux_revert =
+			Box::new(|client: Arc<ParachainClient<Block, RuntimeApi>>, backend, blocks| {
+				Consensus::revert(client.clone(), backend, blocks)?;
+				sc_consensus_grandpa::revert(client, blocks)?;
+				Ok(())
+			});
+		Ok((
+			Box::pin(cmd.run(partial.client, partial.backend, Some(aux_revert))),
+			partial.task_manager,
+		))
+	}
+
+	fn run_export_genesis_head_cmd(
+		self: Box<Self>,
+		config: Configuration,
+		cmd: &ExportGenesisHeadCommand,
+	) -> SyncCmdResult {
+		let partial = Self::new_partial(&config).map_err(sc_cli::Error::Service)?;
+		cmd.run(partial.client)
+	}
+
+	fn run_benchmark_block_cmd(
+		self: Box<Self>,
+		config: Configuration,
+		cmd: &BlockCmd,
+	) -> SyncCmdResult {
+		let partial = Self::new_partial(&config).map_err(sc_cli::Error::Service)?;
+		cmd.run(partial.client)
+	}
+
+	#[cfg(any(feature = "runtime-benchmarks"))]
+	fn run_benchmark_storage_cmd(
+		self: Box<Self>,
+		config: Configuration,
+		cmd: &StorageCmd,
+	) -> SyncCmdResult {
+		let partial = Self::new_partial(&config).map_err(sc_cli::Error::Service)?;
+		let db = partial.backend.expose_db();
+		let storage = partial.backend.expose_storage();
+		let shared_trie_cache = partial.backend.expose_shared_trie_cache();
+
+		cmd.run(config, partial.client, db, storage, shared_trie_cache)
+	}
+}
+
+/// Create the node spec of a solochain that uses Aura for block production.
+pub fn new_aura_grandpa_node_spec<Block, RuntimeApi, AuraId, Extension>(
+) -> Box<dyn DynNodeSpec<Extension>>
+where
+	Block: NodeBlock,
+	RuntimeApi: ConstructSolochainRuntimeApi<Block, ParachainClient<Block, RuntimeApi>>,
+	RuntimeApi::RuntimeApi: AuraApi<Block, <AuraId::BoundedPair as Pair>::Public>,
+	AuraId: AuraIdT + Sync + 'static,
+	Extension: NodeExtension,
+{
+	Box::new(SolochainNode::<Block, RuntimeApi, AuraGrandpaConsensus<AuraId>>::default())
+}
+
+/// Create the node spec of a solochain that uses BABE for block production.
+pub fn new_babe_grandpa_node_spec<Block, RuntimeApi, Extension>() -> Box<dyn DynNodeSpec<Extension>>
+where
+	Block: NodeBlock,
+	RuntimeApi: ConstructSolochainRuntimeApi<Block, ParachainClient<Block, RuntimeApi>>,
+	RuntimeApi::RuntimeApi: BabeApi<Block>,
+	Extension: NodeExtension,
+{
+	Box::new(SolochainNode::<Block, RuntimeApi, BabeGrandpaConsensus>::default())
+}
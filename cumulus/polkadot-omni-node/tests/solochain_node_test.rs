@@ -0,0 +1,94 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Integration test that spawns the actual binary `polkadot-omni-node` with the chain spec of a
+//! solochain and checks that it produces and finalizes blocks without a relay chain.
+
+#![cfg(unix)]
+
+use assert_cmd::cargo::cargo_bin;
+use nix::{
+	sys::signal::{kill, Signal::SIGINT},
+	unistd::Pid,
+};
+use sc_chain_spec::{ChainType, GenericChainSpec, NoExtension};
+use std::{
+	io::{BufRead, BufReader},
+	process::{Command, Stdio},
+	sync::mpsc,
+	time::Duration,
+};
+
+/// How long the node may take to import and finalize its first blocks.
+const TIMEOUT: Duration = Duration::from_secs(120);
+
+#[test]
+fn solochain_node_produces_and_finalizes_blocks() {
+	let dir = tempfile::tempdir().expect("a temporary directory can be created");
+
+	// The chain spec of a solochain has no relay chain or para id extension.
+	let chain_spec = GenericChainSpec::<NoExtension>::builder(
+		solochain_template_runtime::WASM_BINARY.expect("the runtime is built with its wasm"),
+		None,
+	)
+	.with_name("Solochain")
+	.with_id("solochain")
+	.with_chain_type(ChainType::Development)
+	.with_genesis_config_preset_name(sp_genesis_builder::DEV_RUNTIME_PRESET)
+	.build();
+	let chain_spec_path = dir.path().join("solochain.json");
+	std::fs::write(&chain_spec_path, chain_spec.as_json(true).expect("the spec can be built"))
+		.expect("the chain spec can be written");
+
+	let mut node = Command::new(cargo_bin("polkadot-omni-node"))
+		.stderr(Stdio::piped())
+		.arg("--chain")
+		.arg(&chain_spec_path)
+		.arg("--base-path")
+		.arg(dir.path().join("node"))
+		.args(["--alice", "--rpc-port", "0", "--port", "0", "--no-hardware-benchmarks"])
+		.spawn()
+		.expect("the node can be started");
+
+	// Alice is the only Aura and GRANDPA authority of the development preset.
+	let stderr = node.stderr.take().expect("stderr is piped");
+	let (sender, receiver) = mpsc::channel();
+	std::thread::spawn(move || {
+		for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+			if sender.send(line).is_err() {
+				break
+			}
+		}
+	});
+
+	let mut detected = false;
+	let mut finalized = false;
+	let mut output = String::new();
+	while !finalized {
+		let Ok(line) = receiver.recv_timeout(TIMEOUT) else { break };
+		output.push_str(&line);
+		output.push('\n');
+		detected |= line.contains("Running it as a solochain with Aura + GRANDPA consensus");
+		finalized |= line.contains("finalized #2");
+	}
+
+	kill(Pid::from_raw(node.id() as i32), SIGINT).expect("the node can be interrupted");
+	let status = node.wait().expect("the node exits");
+
+	assert!(detected, "the solochain was not detected:\n{output}");
+	assert!(finalized, "the node did not finalize blocks in time:\n{output}");
+	assert!(status.success(), "the node did not exit cleanly:\n{output}");
+}
@@ -507,6 +507,84 @@ impl NextSlotSchedule {
 	}
 }
 
+/// Why the pre-flight checks of a staged validation code upgrade failed.
+#[derive(
+	Encode, Decode, DecodeWithMemTracking, MaxEncodedLen, Clone, Eq, PartialEq, TypeInfo, Debug,
+)]
+pub enum UpgradePreflightError {
+	/// The code can not be instantiated with the host functions available to validation.
+	InvalidCode,
+	/// The code does not report a runtime version.
+	MissingVersion,
+	/// The `spec_name` of the code differs from the current runtime.
+	SpecNameMismatch,
+	/// The `spec_version` of the code is not higher than the one of the current runtime.
+	SpecVersionNotIncreased,
+	/// The code does not export `validate_block`.
+	ValidateBlockUnavailable,
+	/// The `try-runtime` upgrade checks of the code failed.
+	TryRuntimeFailed,
+	/// The code failed to build a block on top of the current head.
+	BlockBuildingFailed,
+	/// `validate_block` of the code rejected the block that was built with it.
+	ValidateBlockFailed,
+}
+
+/// The outcome of the pre-flight checks of a staged validation code upgrade.
+#[derive(
+	Encode, Decode, DecodeWithMemTracking, MaxEncodedLen, Clone, Eq, PartialEq, TypeInfo, Debug,
+)]
+pub enum UpgradePreflightOutcome {
+	/// All checks passed, the upgrade can be scheduled.
+	Passed,
+	/// A check failed, the upgrade must not be scheduled.
+	Failed(UpgradePreflightError),
+}
+
+/// The report of the pre-flight checks that the node side ran on a staged validation code.
+#[derive(
+	Encode, Decode, DecodeWithMemTracking, MaxEncodedLen, Clone, Eq, PartialEq, TypeInfo, Debug,
+)]
+pub struct UpgradePreflightReport {
+	/// The `blake2_256` hash of the checked validation code.
+	pub code_hash: relay_chain::Hash,
+	/// The outcome of the checks.
+	pub outcome: UpgradePreflightOutcome,
+}
+
+/// The statement store topic of extrinsics that are encrypted to the key of a block author.
+///
+/// The statements carry the extrinsic encrypted to the `ed25519` key of the author and no other
+/// topic, so nothing about the plain extrinsic is gossiped.
+pub const ENCRYPTED_EXTRINSIC_TOPIC: [u8; 32] = *b"cumulus-encrypted-extrinsics-v01";
+
+/// The maximum number of encrypted extrinsics that are included in a single block.
+pub const MAX_ENCRYPTED_EXTRINSICS: u32 = 64;
+
+/// The maximum encoded size of an extrinsic that is submitted encrypted.
+///
+/// Bounds the data the runtime hashes to verify an inclusion commitment.
+pub const MAX_ENCRYPTED_EXTRINSIC_SIZE: u32 = 16 * 1024;
+
+/// The inclusion commitment of an extrinsic that was submitted encrypted to a block author.
+#[derive(
+	Encode, Decode, DecodeWithMemTracking, MaxEncodedLen, Clone, Eq, PartialEq, TypeInfo, Debug,
+)]
+pub struct EncryptedExtrinsicCommitment {
+	/// The hash of the statement that carried the encrypted extrinsic.
+	///
+	/// Lets the submitter find the inclusion of its statement.
+	pub statement_hash: relay_chain::Hash,
+	/// The `blake2_256` hash of the decrypted extrinsic.
+	pub extrinsic_hash: relay_chain::Hash,
+}
+
+/// The inclusion commitments of the encrypted extrinsics of a block.
+pub type EncryptedExtrinsicCommitments = sp_runtime::BoundedVec<
+	EncryptedExtrinsicCommitment,
+	sp_runtime::traits::ConstU32<MAX_ENCRYPTED_EXTRINSICS>,
+>;
+
 sp_api::decl_runtime_apis! {
 	/// Runtime api to collect information about a collation.
 	///
@@ -540,6 +618,15 @@ sp_api::decl_runtime_apis! {
 		fn relay_parent_offset() -> u32;
 	}
 
+	/// API to tell the node side which relay chain storage keys the runtime wants to read.
+	///
+	/// The node includes these keys in the relay chain state proof of the parachain inherent,
+	/// in addition to the keys that are always included.
+	pub trait RelayStorageKeysApi {
+		/// Fetch the relay chain storage keys that should be proven.
+		fn relay_storage_keys() -> Vec<Vec<u8>>;
+	}
+
 	/// API for parachain slot scheduling.
 	///
 	/// This runtime API allows the parachain runtime to communicate the block interval
@@ -553,6 +640,33 @@ sp_api::decl_runtime_apis! {
 		/// Returns a [`NextSlotSchedule`].
 		fn next_slot_schedule(num_cores: u32) -> NextSlotSchedule;
 	}
+
+	/// API to run the pre-flight checks of a staged validation code upgrade on the node side.
+	///
+	/// While an upgrade is staged, the node executes the staged code and reports the outcome of
+	/// its checks with the extrinsic created by this API. The upgrade is only scheduled once the
+	/// checks passed.
+	pub trait UpgradePreflightApi {
+		/// The validation code that is staged and waits for its pre-flight checks.
+		fn staged_validation_code() -> Option<Vec<u8>>;
+
+		/// Create the unsigned extrinsic that reports the outcome of the pre-flight checks.
+		fn upgrade_preflight_report_extrinsic(
+			report: UpgradePreflightReport,
+		) -> <Block as BlockT>::Extrinsic;
+	}
+
+	/// API to include extrinsics that were submitted encrypted to the block author.
+	///
+	/// The author decrypts the extrinsics while building the block, applies them and then
+	/// applies the extrinsic created by this API. It verifies that the extrinsics applied right
+	/// before it match the given inclusion commitments, in order.
+	pub trait EncryptedExtrinsicsApi {
+		/// Create the unsigned extrinsic that verifies the given inclusion commitments.
+		fn encrypted_inclusion_extrinsic(
+			commitments: EncryptedExtrinsicCommitments,
+		) -> <Block as BlockT>::Extrinsic;
+	}
 }
 
 #[cfg(test)]
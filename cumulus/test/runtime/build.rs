@@ -76,6 +76,13 @@ fn main() {
 		.import_memory()
 		.set_file_name("wasm_binary_elastic_scaling_12s_slot.rs")
 		.build();
+
+	WasmBuilder::new()
+		.with_current_project()
+		.enable_feature("encrypted-extrinsics")
+		.import_memory()
+		.set_file_name("wasm_binary_encrypted_extrinsics.rs")
+		.build();
 }
 
 #[cfg(not(feature = "std"))]
@@ -43,9 +43,18 @@ fn cumulus_test_runtime(
 fn testnet_genesis_with_default_endowed(self_para_id: ParaId) -> serde_json::Value {
 	let endowed = Sr25519Keyring::well_known().map(|x| x.to_account_id()).collect::<Vec<_>>();
 
-	let invulnerables =
-		Sr25519Keyring::invulnerable().map(|x| x.public().into()).collect::<Vec<_>>();
-	cumulus_test_runtime(invulnerables, endowed, self_para_id)
+	cumulus_test_runtime(invulnerables(), endowed, self_para_id)
+}
+
+#[cfg(not(feature = "encrypted-extrinsics"))]
+fn invulnerables() -> Vec<AuraId> {
+	Sr25519Keyring::invulnerable().map(|x| x.public().into()).collect()
+}
+
+/// Only Alice authors, with her `ed25519` key.
+#[cfg(feature = "encrypted-extrinsics")]
+fn invulnerables() -> Vec<AuraId> {
+	vec![sp_keyring::Ed25519Keyring::Alice.public().into()]
 }
 
 /// List of supported presets.
@@ -56,6 +56,11 @@ pub mod sync_backing {
 	include!(concat!(env!("OUT_DIR"), "/wasm_binary_sync_backing.rs"));
 }
 
+pub mod encrypted_extrinsics {
+	#[cfg(feature = "std")]
+	include!(concat!(env!("OUT_DIR"), "/wasm_binary_encrypted_extrinsics.rs"));
+}
+
 pub mod async_backing {
 	#[cfg(feature = "std")]
 	include!(concat!(env!("OUT_DIR"), "/wasm_binary.rs"));
@@ -69,6 +74,9 @@ extern crate alloc;
 use alloc::{vec, vec::Vec};
 use frame_support::{derive_impl, traits::OnRuntimeUpgrade, PalletId};
 use sp_api::{decl_runtime_apis, impl_runtime_apis};
+#[cfg(feature = "encrypted-extrinsics")]
+pub use sp_consensus_aura::ed25519::AuthorityId as AuraId;
+#[cfg(not(feature = "encrypted-extrinsics"))]
 pub use sp_consensus_aura::sr25519::AuthorityId as AuraId;
 use sp_core::{ConstBool, ConstU32, ConstU64, OpaqueMetadata};
 
@@ -520,6 +528,37 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl cumulus_primitives_core::RelayStorageKeysApi<Block> for Runtime {
+		fn relay_storage_keys() -> Vec<Vec<u8>> {
+			Vec::new()
+		}
+	}
+
+	impl cumulus_primitives_core::UpgradePreflightApi<Block> for Runtime {
+		fn staged_validation_code() -> Option<Vec<u8>> {
+			ParachainSystem::staged_validation_code()
+		}
+
+		fn upgrade_preflight_report_extrinsic(
+			report: cumulus_primitives_core::UpgradePreflightReport,
+		) -> <Block as BlockT>::Extrinsic {
+			UncheckedExtrinsic::new_bare(
+				cumulus_pallet_parachain_system::Call::report_upgrade_preflight { report }.into(),
+			)
+		}
+	}
+
+	impl cumulus_primitives_core::EncryptedExtrinsicsApi<Block> for Runtime {
+		fn encrypted_inclusion_extrinsic(
+			commitments: cumulus_primitives_core::EncryptedExtrinsicCommitments,
+		) -> <Block as BlockT>::Extrinsic {
+			UncheckedExtrinsic::new_bare(
+				cumulus_pallet_parachain_system::Call::note_encrypted_extrinsics { commitments }
+					.into(),
+			)
+		}
+	}
+
 	impl sp_consensus_aura::AuraApi<Block, AuraId> for Runtime {
 		fn slot_duration() -> sp_consensus_aura::SlotDuration {
 			sp_consensus_aura::SlotDuration::from_millis(SLOT_DURATION)
@@ -580,6 +619,29 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl sp_statement_store::runtime_api::ValidateStatement<Block> for Runtime {
+		fn validate_statement(
+			_source: sp_statement_store::runtime_api::StatementSource,
+			statement: sp_statement_store::Statement,
+		) -> Result<
+			sp_statement_store::runtime_api::ValidStatement,
+			sp_statement_store::runtime_api::InvalidStatement,
+		> {
+			use sp_statement_store::{
+				runtime_api::{InvalidStatement, ValidStatement},
+				SignatureVerificationResult,
+			};
+
+			// Every signed statement is accepted, up to a small allowance per account.
+			match statement.verify_signature() {
+				SignatureVerificationResult::Valid(_) =>
+					Ok(ValidStatement { max_count: 16, max_size: 64 * 1024 }),
+				SignatureVerificationResult::Invalid => Err(InvalidStatement::BadProof),
+				SignatureVerificationResult::NoSignature => Err(InvalidStatement::NoProof),
+			}
+		}
+	}
+
 	impl sp_offchain::OffchainWorkerApi<Block> for Runtime {
 		fn offchain_worker(header: &<Block as BlockT>::Header) {
 			Executive::offchain_worker(header)
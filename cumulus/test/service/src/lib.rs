@@ -429,6 +429,7 @@ where
 		recovery_handle,
 		sync_service: sync_service.clone(),
 		prometheus_registry: None,
+		pov_recovery_handle: None,
 	})?;
 
 	let collator_peer_id = network.local_peer_id();
@@ -474,6 +475,7 @@ where
 				spawner: task_manager.spawn_essential_handle(),
 				export_pov: None,
 				max_pov_percentage: None,
+				prometheus_registry: prometheus_registry.clone(),
 				collator_peer_id,
 			};
 
@@ -0,0 +1,159 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Test that a single collator includes an extrinsic that was submitted to it encrypted through
+// the statement store, together with the inclusion commitment of the statement.
+
+use anyhow::anyhow;
+use codec::Encode;
+use sc_chain_spec::{ChainType, GenericChainSpec, NoExtension};
+use sp_core::{blake2_256, Bytes};
+use sp_keyring::{Ed25519Keyring, Sr25519Keyring};
+use std::path::{Path, PathBuf};
+
+use crate::utils::initialize_network;
+
+use zombienet_sdk::{
+	subxt::{self, dynamic::Value, ext::subxt_rpcs::rpc_params, OnlineClient, PolkadotConfig},
+	subxt_signer::sr25519::dev,
+	NetworkConfig, NetworkConfigBuilder,
+};
+
+/// The para id of the genesis preset of the test runtime.
+const PARA_ID: u32 = 100;
+
+/// The number of blocks the collator may take to include the extrinsic.
+const MAX_BLOCKS: usize = 20;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn encrypted_extrinsics() -> Result<(), anyhow::Error> {
+	let _ = env_logger::try_init_from_env(
+		env_logger::Env::default().filter_or(env_logger::DEFAULT_FILTER_ENV, "info"),
+	);
+
+	let chain_spec = std::env::temp_dir().join("encrypted-extrinsics-parachain-spec.json");
+	write_chain_spec(&chain_spec)?;
+
+	log::info!("Spawning network");
+	let config = build_network_config(chain_spec)?;
+	let network = initialize_network(config).await?;
+
+	let charlie = network.get_node("charlie")?;
+	let charlie_rpc = charlie.rpc().await?;
+	let charlie_client: OnlineClient<PolkadotConfig> = charlie.wait_client().await?;
+
+	// Alice is the only Aura authority, with her ed25519 key. The statement store decrypts with
+	// the same key under the `stmt` key type.
+	let author = Ed25519Keyring::Alice.public();
+	for key_type in ["aura", "stmt"] {
+		let _: () = charlie_rpc
+			.request("author_insertKey", rpc_params![key_type, "//Alice", Bytes(author.to_vec())])
+			.await?;
+	}
+
+	log::info!("Submitting an encrypted extrinsic");
+	let call = subxt::dynamic::tx(
+		"System",
+		"remark_with_event",
+		vec![Value::from_bytes("encrypted".as_bytes())],
+	);
+	let extrinsic = charlie_client
+		.tx()
+		.create_signed(&call, &dev::bob(), Default::default())
+		.await?
+		.encoded()
+		.to_vec();
+
+	let mut statement = sp_statement_store::Statement::new();
+	statement.set_topic(0, cumulus_primitives_core::ENCRYPTED_EXTRINSIC_TOPIC);
+	statement
+		.encrypt(&extrinsic, &author)
+		.map_err(|e| anyhow!("Failed to encrypt the extrinsic: {e:?}"))?;
+	statement.sign_sr25519_private(&Sr25519Keyring::Bob.pair());
+	let statement_hash = statement.hash();
+	let _: () = charlie_rpc
+		.request("statement_submit", rpc_params![Bytes(statement.encode())])
+		.await?;
+
+	log::info!("Waiting for the inclusion of the encrypted extrinsic");
+	let mut blocks = charlie_client.blocks().subscribe_best().await?;
+	for _ in 0..MAX_BLOCKS {
+		let Some(block) = blocks.next().await else { break };
+		let events = block?.events().await?;
+
+		let included = events.iter().filter_map(Result::ok).any(|event| {
+			event.pallet_name() == "ParachainSystem" &&
+				event.variant_name() == "EncryptedExtrinsicIncluded" &&
+				event.field_bytes() ==
+					[statement_hash, blake2_256(&extrinsic)].concat().as_slice()
+		});
+		if included {
+			return Ok(())
+		}
+	}
+
+	Err(anyhow!("The encrypted extrinsic was not included in {MAX_BLOCKS} blocks"))
+}
+
+/// Write the raw chain spec of the test runtime with `ed25519` Aura keys to `path`.
+fn write_chain_spec(path: &Path) -> Result<(), anyhow::Error> {
+	let code = cumulus_test_runtime::encrypted_extrinsics::WASM_BINARY
+		.ok_or(anyhow!("The test runtime was built without its wasm"))?;
+	let chain_spec = GenericChainSpec::<NoExtension>::builder(code, None)
+		.with_name("Encrypted extrinsics")
+		.with_id("encrypted_extrinsics")
+		.with_chain_type(ChainType::Local)
+		.with_genesis_config_preset_name(sp_genesis_builder::LOCAL_TESTNET_RUNTIME_PRESET)
+		.build();
+
+	let mut json: serde_json::Value =
+		serde_json::from_str(&chain_spec.as_json(true).map_err(|e| anyhow!(e))?)?;
+	json["relay_chain"] = "rococo_local_testnet".into();
+	json["para_id"] = PARA_ID.into();
+	std::fs::write(path, serde_json::to_string_pretty(&json)?)?;
+
+	Ok(())
+}
+
+fn build_network_config(chain_spec: PathBuf) -> Result<NetworkConfig, anyhow::Error> {
+	// images are not relevant for `native`, but we leave it here in case we use `k8s` some day
+	let images = zombienet_sdk::environment::get_images_from_env();
+	log::info!("Using images: {images:?}");
+
+	// Network setup:
+	// - relaychain nodes:
+	// 	 - alice   - validator
+	// 	 - bob     - validator
+	// - parachain nodes:
+	//   - charlie - the only collator, includes the encrypted extrinsics
+	NetworkConfigBuilder::new()
+		.with_relaychain(|r| {
+			r.with_chain("rococo-local")
+				.with_default_command("polkadot")
+				.with_default_image(images.polkadot.as_str())
+				.with_default_args(vec![("-lparachain=debug").into()])
+				.with_node(|node| node.with_name("alice"))
+				.with_node(|node| node.with_name("bob"))
+		})
+		.with_parachain(|p| {
+			p.with_id(PARA_ID)
+				.with_default_command("polkadot-omni-node")
+				.with_default_image(images.cumulus.as_str())
+				.with_chain_spec_path(chain_spec)
+				.with_default_args(vec![
+					("-lparachain=debug,encrypted-extrinsics=debug").into(),
+					("--enable-statement-store").into(),
+					("--enable-encrypted-extrinsics").into(),
+				])
+				.with_collator(|n| n.with_name("charlie"))
+		})
+		.with_global_settings(|global_settings| match std::env::var("ZOMBIENET_SDK_BASE_DIR") {
+			Ok(val) => global_settings.with_base_dir(val),
+			_ => global_settings,
+		})
+		.build()
+		.map_err(|e| {
+			let errs = e.into_iter().map(|e| e.to_string()).collect::<Vec<_>>().join(" ");
+			anyhow!("config errs: {errs}")
+		})
+}
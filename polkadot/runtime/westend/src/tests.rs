@@ -73,6 +73,39 @@ fn sanity_check_teleport_assets_weight() {
 	assert!((weight * 50).all_lt(BlockWeights::get().max_block));
 }
 
+#[test]
+fn xcm_weight_breakdown_matches_weigher() {
+	use xcm_runtime_apis::weight_breakdown::WeightSource;
+	type Weights = weights::xcm::WestendXcmWeight<RuntimeCall>;
+
+	let message = Xcm::<()>::builder_unsafe()
+		.withdraw_asset((Here, 100u128))
+		.set_appendix(Xcm::builder_unsafe().clear_origin().build())
+		.deposit_asset(AllCounted(1), Here)
+		.build();
+	let breakdown =
+		XcmPallet::query_xcm_weight_breakdown::<Weights>(VersionedXcm::from(message.clone()))
+			.unwrap();
+
+	assert_eq!(breakdown.len(), 3);
+	assert!(breakdown.iter().all(|i| i.source == WeightSource::Benchmarked));
+	assert_eq!(breakdown[1].nested.len(), 1);
+	assert_eq!(breakdown[1].additional_weight, breakdown[1].nested[0].total());
+	let total = breakdown.iter().fold(Weight::zero(), |acc, i| acc.saturating_add(i.total()));
+	assert_eq!(XcmPallet::query_xcm_weight(VersionedXcm::from(message)), Ok(total));
+
+	// Westend does not support exchanging assets, the weigher rejects such programs.
+	let message = Xcm::<()>::builder_unsafe()
+		.withdraw_asset((Here, 100u128))
+		.exchange_asset(AllCounted(1), (Here, 50u128), false)
+		.build();
+	let breakdown =
+		XcmPallet::query_xcm_weight_breakdown::<Weights>(VersionedXcm::from(message)).unwrap();
+
+	assert_eq!(breakdown[0].source, WeightSource::Benchmarked);
+	assert_eq!(breakdown[1].source, WeightSource::Unsupported);
+}
+
 #[test]
 fn check_whitelist() {
 	let whitelist: HashSet<String> = AllPalletsWithSystem::whitelisted_storage_keys()
@@ -0,0 +1,84 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Benchmarks for the XCM audit pallet.
+
+use super::*;
+use frame_benchmarking::v2::*;
+
+fn summary() -> InstructionsSummary {
+	let message = Xcm::<()>(alloc::vec![ClearOrigin; MAX_SUMMARY_INSTRUCTIONS as usize]);
+	InstructionsSummary::from(&message)
+}
+
+#[benchmarks]
+mod benchmarks {
+	use super::*;
+
+	#[benchmark]
+	fn record() {
+		let location = Location::new(1, [Parachain(1000)]);
+		let per_page = T::RecordsPerPage::get();
+		// The history is full and its oldest record is the last one on a full page, so
+		// recording deletes that page.
+		let sent = AuditRecord {
+			block: frame_system::Pallet::<T>::block_number(),
+			direction: Direction::Sent,
+			location: location.clone().into(),
+			topic: [1; 32],
+			instructions: summary(),
+			outcome: None,
+		};
+		for _ in 0..per_page {
+			Records::<T>::append_one(sent.clone());
+		}
+		Records::<T>::drain().take(per_page as usize - 1).for_each(drop);
+		for _ in 1..per_page {
+			Records::<T>::append_one(sent.clone());
+		}
+		RecordCount::<T>::put(T::MaxRecords::get());
+		let outcome = ExecutionOutcome { used: Weight::MAX, error: Some((0, XcmError::Barrier)) };
+
+		#[block]
+		{
+			Pallet::<T>::record(Direction::Executed, location, [1; 32], summary(), Some(outcome));
+		}
+
+		assert_eq!(RecordCount::<T>::get(), T::MaxRecords::get());
+	}
+
+	#[benchmark]
+	fn prune_record() {
+		let location = Location::new(1, [Parachain(1000)]);
+		// Fill a page, so that pruning the first record deletes it.
+		for _ in 0..T::RecordsPerPage::get() {
+			Pallet::<T>::record(Direction::Sent, location.clone(), [1; 32], summary(), None);
+		}
+		Pallet::<T>::record(Direction::Sent, location, [1; 32], summary(), None);
+		let count = RecordCount::<T>::get();
+		RecordCount::<T>::put(T::MaxRecords::get().max(count) + 1);
+		let mut meter = WeightMeter::with_limit(T::WeightInfo::prune_record());
+
+		#[block]
+		{
+			Pallet::<T>::prune(frame_system::Pallet::<T>::block_number(), &mut meter);
+		}
+
+		assert_eq!(RecordCount::<T>::get(), T::MaxRecords::get().max(count));
+	}
+
+	impl_benchmark_test_suite!(Pallet, crate::mock::new_test_ext(), crate::mock::Test);
+}
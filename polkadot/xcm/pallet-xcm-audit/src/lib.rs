@@ -0,0 +1,494 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! # XCM Audit Pallet
+//!
+//! Keeps a bounded history of the XCMs a chain executed and sent.
+//!
+//! ## Overview
+//!
+//! Every executed and every sent message is stored as an [`AuditRecord`] with its origin or
+//! destination, topic, a summary of its instructions and, for executed messages, the outcome and
+//! used weight. The history is stored in a [`StoragePagedList`] and can be queried with the
+//! [`runtime_api::XcmAuditApi`].
+//!
+//! Messages are recorded by wrapping the message processor, the executor and the router of the
+//! runtime:
+//!
+//! ```ignore
+//! impl pallet_message_queue::Config for Runtime {
+//! 	type MessageProcessor = AuditedProcessor<
+//! 		Runtime,
+//! 		ProcessXcmMessage<
+//! 			AggregateMessageOrigin,
+//! 			AuditedExecutor<Runtime, XcmExecutor<XcmConfig>>,
+//! 			RuntimeCall,
+//! 		>,
+//! 	>;
+//! 	// ...
+//! }
+//!
+//! impl xcm_executor::Config for XcmConfig {
+//! 	type XcmSender = AuditedRouter<Runtime, XcmRouter>;
+//! 	// ...
+//! }
+//! ```
+//!
+//! The message queue rolls back everything a message changed if processing it fails with an error,
+//! which would include its record. [`AuditedProcessor`] records such messages after the rollback,
+//! together with messages that never reached the executor because they could not be decoded or
+//! weighed.
+//!
+//! The executor wrapper charges the weight of recording as part of the weight of the message. The
+//! price of sending a message is paid in assets, so the router wrapper registers the weight of
+//! recording a sent message as extra weight of the block instead.
+//!
+//! The pallet does not implement `xcm_executor::traits::RecordXcm`. The executor calls that trait
+//! before the barrier with nothing but the message, so it can neither tell the origin and outcome
+//! of an executed message nor see sent messages. Keep `pallet_xcm` as the `XcmRecorder` of the
+//! runtime for dry-runs.
+//!
+//! ## Retention
+//!
+//! Recording a message while there are [`Config::MaxRecords`] records removes the oldest record,
+//! so the history never grows beyond `MaxRecords`. The oldest records are pruned in `on_idle`
+//! once they are older than [`Config::RetentionPeriod`] blocks, or when `MaxRecords` was lowered.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use codec::{Decode, DecodeLimit, Encode, MaxEncodedLen};
+use core::marker::PhantomData;
+use frame_support::{
+	dispatch::DispatchClass,
+	pallet_prelude::*,
+	storage::{with_transaction, StorageList, TransactionOutcome},
+	traits::{PalletInfoAccess, ProcessMessage, ProcessMessageError, StorageInstance},
+	weights::WeightMeter,
+};
+use frame_system::pallet_prelude::*;
+use pallet_paged_list::StoragePagedList;
+use scale_info::TypeInfo;
+use sp_runtime::{traits::Saturating, RuntimeDebug};
+use xcm::{prelude::*, MAX_XCM_DECODE_DEPTH};
+
+pub use pallet::*;
+pub use weights::WeightInfo;
+
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+#[cfg(test)]
+mod mock;
+pub mod runtime_api;
+#[cfg(test)]
+mod tests;
+pub mod weights;
+
+/// The maximum number of instructions in the summary of a message.
+pub const MAX_SUMMARY_INSTRUCTIONS: u32 = 32;
+
+/// Whether a message was executed or sent.
+#[derive(Clone, Copy, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub enum Direction {
+	/// The message was executed by this chain.
+	Executed,
+	/// The message was sent by this chain.
+	Sent,
+}
+
+/// A summary of the instructions of a message.
+#[derive(Clone, Default, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct InstructionsSummary {
+	/// The number of instructions.
+	pub count: u32,
+	/// The SCALE variant index of the first [`MAX_SUMMARY_INSTRUCTIONS`] instructions.
+	pub kinds: BoundedVec<u8, ConstU32<MAX_SUMMARY_INSTRUCTIONS>>,
+}
+
+impl<Call> From<&Xcm<Call>> for InstructionsSummary {
+	fn from(message: &Xcm<Call>) -> Self {
+		let kinds = message
+			.inner()
+			.iter()
+			.take(MAX_SUMMARY_INSTRUCTIONS as usize)
+			.map(|instruction| instruction.using_encoded(|encoded| encoded[0]))
+			.collect::<Vec<_>>();
+		Self {
+			count: message.len() as u32,
+			kinds: kinds.try_into().expect("at most `MAX_SUMMARY_INSTRUCTIONS` are taken; qed"),
+		}
+	}
+}
+
+/// The outcome of an executed message.
+#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct ExecutionOutcome {
+	/// The weight used by the execution, including recording it.
+	pub used: Weight,
+	/// The index of the failed instruction and its error. `None` if the message completed.
+	pub error: Option<(u8, XcmError)>,
+}
+
+impl From<&Outcome> for ExecutionOutcome {
+	fn from(outcome: &Outcome) -> Self {
+		match outcome {
+			Outcome::Complete { used } => Self { used: *used, error: None },
+			Outcome::Incomplete { used, error } =>
+				Self { used: *used, error: Some((error.index, error.error)) },
+			Outcome::Error(error) =>
+				Self { used: Weight::zero(), error: Some((error.index, error.error)) },
+		}
+	}
+}
+
+/// A message in the audit history.
+#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct AuditRecord<BlockNumber> {
+	/// The block in which the message was executed or sent.
+	pub block: BlockNumber,
+	/// Whether the message was executed or sent.
+	pub direction: Direction,
+	/// The origin of an executed message, or the destination of a sent message.
+	pub location: VersionedLocation,
+	/// The topic of the message.
+	///
+	/// This is the id set with `SetTopic`. Messages without a topic are identified by their hash.
+	pub topic: XcmHash,
+	/// The instructions of the message.
+	pub instructions: InstructionsSummary,
+	/// The outcome of an executed message.
+	pub outcome: Option<ExecutionOutcome>,
+}
+
+/// The [`AuditRecord`] of a runtime.
+pub type AuditRecordOf<T> = AuditRecord<BlockNumberFor<T>>;
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+
+	#[pallet::pallet]
+	pub struct Pallet<T>(_);
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// The maximum number of records that are kept.
+		#[pallet::constant]
+		type MaxRecords: Get<u32>;
+
+		/// The number of blocks records are kept for.
+		#[pallet::constant]
+		type RetentionPeriod: Get<BlockNumberFor<Self>>;
+
+		/// The number of records in one page of the history.
+		#[pallet::constant]
+		type RecordsPerPage: Get<u32>;
+
+		/// Weight information for the operations of this pallet.
+		type WeightInfo: WeightInfo;
+	}
+
+	/// The number of records in the history.
+	#[pallet::storage]
+	pub type RecordCount<T: Config> = StorageValue<_, u32, ValueQuery>;
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		fn on_idle(now: BlockNumberFor<T>, limit: Weight) -> Weight {
+			let mut meter = WeightMeter::with_limit(limit);
+			Self::prune(now, &mut meter);
+			meter.consumed()
+		}
+
+		fn integrity_test() {
+			assert!(T::MaxRecords::get() > 0, "`MaxRecords` must not be zero");
+			assert!(T::RecordsPerPage::get() > 0, "`RecordsPerPage` must not be zero");
+		}
+	}
+}
+
+/// The storage prefix of the history.
+pub struct RecordsPrefix<T>(PhantomData<T>);
+
+impl<T: Config> StorageInstance for RecordsPrefix<T> {
+	fn pallet_prefix() -> &'static str {
+		Pallet::<T>::name()
+	}
+
+	const STORAGE_PREFIX: &'static str = "Records";
+}
+
+/// The history of recorded messages, oldest first.
+pub type Records<T> =
+	StoragePagedList<RecordsPrefix<T>, AuditRecordOf<T>, <T as Config>::RecordsPerPage>;
+
+impl<T: Config> Pallet<T> {
+	/// Appends a record to the history, removing the oldest record if the history is full.
+	pub fn record(
+		direction: Direction,
+		location: Location,
+		topic: XcmHash,
+		instructions: InstructionsSummary,
+		outcome: Option<ExecutionOutcome>,
+	) {
+		let mut count = RecordCount::<T>::get();
+		// Only one record is removed, so that the weight stays bounded if `MaxRecords` was
+		// lowered. `on_idle` removes the rest.
+		if count >= T::MaxRecords::get() && Records::<T>::drain().next().is_some() {
+			count.saturating_dec();
+		}
+		Records::<T>::append_one(AuditRecord {
+			block: frame_system::Pallet::<T>::block_number(),
+			direction,
+			location: location.into(),
+			topic,
+			instructions,
+			outcome,
+		});
+		RecordCount::<T>::put(count.saturating_add(1));
+	}
+
+	/// Removes the oldest records while there are too many or they are too old.
+	pub fn prune(now: BlockNumberFor<T>, meter: &mut WeightMeter) {
+		let oldest_allowed = now.saturating_sub(T::RetentionPeriod::get());
+		let mut count = RecordCount::<T>::get();
+		while count > 0 && meter.try_consume(T::WeightInfo::prune_record()).is_ok() {
+			// Peeking at a record with `drain` would already remove it.
+			let Some(oldest) = Records::<T>::iter().next() else { break };
+			if count <= T::MaxRecords::get() && oldest.block >= oldest_allowed {
+				break
+			}
+			Records::<T>::drain().next();
+			count.saturating_dec();
+		}
+		RecordCount::<T>::put(count);
+	}
+
+	/// The records with `topic`, oldest first.
+	pub fn records_by_topic(topic: XcmHash) -> Vec<AuditRecordOf<T>> {
+		Records::<T>::iter().filter(|record| record.topic == topic).collect()
+	}
+
+	/// The records from or to `location`, oldest first.
+	///
+	/// Skips the first `skip` records and returns at most `max` records.
+	pub fn records_by_location(
+		location: VersionedLocation,
+		skip: u32,
+		max: u32,
+	) -> Vec<AuditRecordOf<T>> {
+		let Ok(location) = Location::try_from(location) else { return Vec::new() };
+		let location = VersionedLocation::from(location);
+		Records::<T>::iter()
+			.filter(|record| record.location == location)
+			.skip(skip as usize)
+			.take(max as usize)
+			.collect()
+	}
+}
+
+/// A message prepared by [`AuditedExecutor`].
+pub struct AuditedMessage<T, Prepared> {
+	inner: Prepared,
+	instructions: InstructionsSummary,
+	_phantom: PhantomData<T>,
+}
+
+impl<T: Config, Prepared: PreparedMessage> PreparedMessage for AuditedMessage<T, Prepared> {
+	fn weight_of(&self) -> Weight {
+		self.inner.weight_of().saturating_add(T::WeightInfo::record())
+	}
+}
+
+/// Executes messages with `Inner` and records them in the history of pallet `T`.
+pub struct AuditedExecutor<T, Inner>(PhantomData<(T, Inner)>);
+
+impl<T: Config, Inner: ExecuteXcm<Call>, Call> ExecuteXcm<Call> for AuditedExecutor<T, Inner> {
+	type Prepared = AuditedMessage<T, Inner::Prepared>;
+
+	fn prepare(
+		message: Xcm<Call>,
+		weight_limit: Weight,
+	) -> Result<Self::Prepared, InstructionError> {
+		let instructions = InstructionsSummary::from(&message);
+		let weight_limit = weight_limit.saturating_sub(T::WeightInfo::record());
+		let inner = Inner::prepare(message, weight_limit)?;
+		Ok(AuditedMessage { inner, instructions, _phantom: PhantomData })
+	}
+
+	fn execute(
+		origin: impl Into<Location>,
+		pre: Self::Prepared,
+		id: &mut XcmHash,
+		weight_credit: Weight,
+	) -> Outcome {
+		let origin = origin.into();
+		let mut outcome = Inner::execute(origin.clone(), pre.inner, id, weight_credit);
+		match &mut outcome {
+			Outcome::Complete { used } | Outcome::Incomplete { used, .. } =>
+				used.saturating_accrue(T::WeightInfo::record()),
+			Outcome::Error(_) => {},
+		}
+		if matches!(outcome, Outcome::Error(_)) {
+			// The message queue rolls the record back, `AuditedProcessor` records it again.
+			failed_message::with(|failed| {
+				*failed = Some(FailedMessage {
+					origin: origin.clone(),
+					topic: *id,
+					instructions: pre.instructions.clone(),
+					outcome: (&outcome).into(),
+				})
+			});
+		}
+		// The executor sets `id` to the topic of the message, if it has one.
+		Pallet::<T>::record(
+			Direction::Executed,
+			origin,
+			*id,
+			pre.instructions,
+			Some((&outcome).into()),
+		);
+		outcome
+	}
+
+	fn charge_fees(location: impl Into<Location>, fees: Assets) -> XcmResult {
+		Inner::charge_fees(location, fees)
+	}
+}
+
+/// A message that failed to be processed, recorded by [`AuditedProcessor`].
+struct FailedMessage {
+	origin: Location,
+	topic: XcmHash,
+	instructions: InstructionsSummary,
+	outcome: ExecutionOutcome,
+}
+
+impl FailedMessage {
+	/// The record of `message`, which failed before it was executed.
+	fn unexecuted(origin: Location, topic: XcmHash, message: &[u8]) -> Self {
+		let (instructions, error) = match VersionedXcm::<()>::decode_all_with_depth_limit(
+			MAX_XCM_DECODE_DEPTH,
+			&mut &message[..],
+		) {
+			Err(_) => (InstructionsSummary::default(), XcmError::FailedToDecode),
+			Ok(message) => match Xcm::<()>::try_from(message) {
+				Ok(message) => ((&message).into(), XcmError::WeightNotComputable),
+				Err(()) => (InstructionsSummary::default(), XcmError::UnhandledXcmVersion),
+			},
+		};
+		let outcome = ExecutionOutcome { used: Weight::zero(), error: Some((0, error)) };
+		Self { origin, topic, instructions, outcome }
+	}
+}
+
+environmental::environmental!(failed_message: Option<FailedMessage>);
+
+/// Processes messages with `Processor` and records the messages that fail with an error.
+///
+/// Such a message is rolled back, recorded and reported to the message queue as processed
+/// unsuccessfully, so that its record is kept. Messages that fail with an error the queue retries,
+/// like `ProcessMessageError::Overweight`, are passed on unchanged.
+pub struct AuditedProcessor<T, Processor>(PhantomData<(T, Processor)>);
+
+impl<T: Config, Processor> ProcessMessage for AuditedProcessor<T, Processor>
+where
+	Processor: ProcessMessage,
+	Processor::Origin: Into<Location>,
+{
+	type Origin = Processor::Origin;
+
+	fn process_message(
+		message: &[u8],
+		origin: Self::Origin,
+		meter: &mut WeightMeter,
+		id: &mut [u8; 32],
+	) -> Result<bool, ProcessMessageError> {
+		let location = origin.clone().into();
+		let mut failed = None;
+		let result = failed_message::using(&mut failed, || {
+			with_transaction(|| {
+				let result = Processor::process_message(message, origin, meter, id);
+				if result.is_ok() {
+					TransactionOutcome::Commit(Ok(result))
+				} else {
+					TransactionOutcome::Rollback(Ok(result))
+				}
+			})
+		})
+		.map_err(|_: DispatchError| ProcessMessageError::StackLimitReached)?;
+
+		match result {
+			Err(
+				ProcessMessageError::Unsupported |
+				ProcessMessageError::Corrupt |
+				ProcessMessageError::BadFormat,
+			) => {
+				// `AuditedExecutor` already charged recording as part of the message.
+				let failed = failed.unwrap_or_else(|| {
+					meter.consume(T::WeightInfo::record());
+					FailedMessage::unexecuted(location, *id, message)
+				});
+				Pallet::<T>::record(
+					Direction::Executed,
+					failed.origin,
+					failed.topic,
+					failed.instructions,
+					Some(failed.outcome),
+				);
+				Ok(false)
+			},
+			result => result,
+		}
+	}
+}
+
+/// Sends messages with `Inner` and records them in the history of pallet `T`.
+pub struct AuditedRouter<T, Inner>(PhantomData<(T, Inner)>);
+
+impl<T: Config, Inner: SendXcm> SendXcm for AuditedRouter<T, Inner> {
+	type Ticket = (Inner::Ticket, Location, InstructionsSummary);
+
+	fn validate(
+		destination: &mut Option<Location>,
+		message: &mut Option<Xcm<()>>,
+	) -> SendResult<Self::Ticket> {
+		let dest = destination.clone().ok_or(SendError::MissingArgument)?;
+		let instructions = message
+			.as_ref()
+			.map(InstructionsSummary::from)
+			.ok_or(SendError::MissingArgument)?;
+		let (ticket, price) = Inner::validate(destination, message)?;
+		Ok(((ticket, dest, instructions), price))
+	}
+
+	fn deliver((ticket, destination, instructions): Self::Ticket) -> Result<XcmHash, SendError> {
+		let topic = Inner::deliver(ticket)?;
+		Pallet::<T>::record(Direction::Sent, destination, topic, instructions, None);
+		frame_system::Pallet::<T>::register_extra_weight_unchecked(
+			T::WeightInfo::record(),
+			DispatchClass::Mandatory,
+		);
+		Ok(topic)
+	}
+
+	#[cfg(feature = "runtime-benchmarks")]
+	fn ensure_successful_delivery(location: Option<Location>) {
+		Inner::ensure_successful_delivery(location)
+	}
+}
@@ -0,0 +1,165 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Test environment for the XCM audit pallet.
+
+use crate as pallet_xcm_audit;
+use frame_support::{construct_runtime, derive_impl, parameter_types};
+use sp_runtime::BuildStorage;
+use xcm::prelude::*;
+use xcm_builder::ProcessXcmMessage;
+
+type Block = frame_system::mocking::MockBlock<Test>;
+
+construct_runtime!(
+	pub enum Test {
+		System: frame_system,
+		XcmAudit: pallet_xcm_audit,
+		MessageQueue: pallet_message_queue,
+	}
+);
+
+#[derive_impl(frame_system::config_preludes::TestDefaultConfig)]
+impl frame_system::Config for Test {
+	type Block = Block;
+}
+
+parameter_types! {
+	pub static MaxRecords: u32 = 5;
+	pub const RetentionPeriod: u64 = 10;
+	pub const RecordsPerPage: u32 = 2;
+}
+
+impl pallet_xcm_audit::Config for Test {
+	type MaxRecords = MaxRecords;
+	type RetentionPeriod = RetentionPeriod;
+	type RecordsPerPage = RecordsPerPage;
+	type WeightInfo = ();
+}
+
+/// The weight of every instruction executed by [`TestExecutor`].
+pub const INSTRUCTION_WEIGHT: Weight = Weight::from_parts(10, 10);
+
+/// A message prepared by [`TestExecutor`].
+pub struct TestMessage(Xcm<()>);
+
+impl PreparedMessage for TestMessage {
+	fn weight_of(&self) -> Weight {
+		INSTRUCTION_WEIGHT.saturating_mul(self.0.len() as u64)
+	}
+}
+
+/// Executes messages up to the first `Trap`, and sets the id to the topic of the message.
+/// Executes messages instruction by instruction, failing at the first `Trap`.
+///
+/// Messages from the relay chain fail with an error before they are executed, as they would if a
+/// barrier rejected them.
+pub struct TestExecutor;
+
+impl ExecuteXcm<()> for TestExecutor {
+	type Prepared = TestMessage;
+
+	fn prepare(message: Xcm<()>, weight_limit: Weight) -> Result<TestMessage, InstructionError> {
+		let message = TestMessage(message);
+		let weight = message.weight_of();
+		if weight.any_gt(weight_limit) {
+			return Err(InstructionError { index: 0, error: XcmError::WeightLimitReached(weight) })
+		}
+		Ok(message)
+	}
+
+	fn execute(
+		origin: impl Into<Location>,
+		TestMessage(message): TestMessage,
+		id: &mut XcmHash,
+		_weight_credit: Weight,
+	) -> Outcome {
+		if origin.into() == Location::parent() {
+			return Outcome::Error(InstructionError { index: 0, error: XcmError::Barrier })
+		}
+		if let Some(SetTopic(topic)) = message.last() {
+			*id = *topic;
+		}
+		for (index, instruction) in message.inner().iter().enumerate() {
+			if let Trap(code) = instruction {
+				return Outcome::Incomplete {
+					used: INSTRUCTION_WEIGHT.saturating_mul(index as u64 + 1),
+					error: InstructionError { index: index as u8, error: XcmError::Trap(*code) },
+				}
+			}
+		}
+		Outcome::Complete { used: INSTRUCTION_WEIGHT.saturating_mul(message.len() as u64) }
+	}
+
+	fn charge_fees(_location: impl Into<Location>, _fees: Assets) -> XcmResult {
+		Ok(())
+	}
+}
+
+/// Delivers messages to sibling parachains and returns their topic as hash.
+pub struct TestRouter;
+
+impl SendXcm for TestRouter {
+	type Ticket = XcmHash;
+
+	fn validate(
+		destination: &mut Option<Location>,
+		message: &mut Option<Xcm<()>>,
+	) -> SendResult<XcmHash> {
+		match destination.as_ref().map(|d| d.unpack()) {
+			Some((1, [Parachain(_)])) => {},
+			_ => return Err(SendError::NotApplicable),
+		}
+		let message = message.take().ok_or(SendError::MissingArgument)?;
+		let topic = match message.last() {
+			Some(SetTopic(topic)) => *topic,
+			_ => message.using_encoded(sp_io::hashing::blake2_256),
+		};
+		Ok((topic, Assets::new()))
+	}
+
+	fn deliver(topic: XcmHash) -> Result<XcmHash, SendError> {
+		Ok(topic)
+	}
+}
+
+parameter_types! {
+	pub const HeapSize: u32 = 1024;
+	pub const MaxStale: u32 = 8;
+}
+
+impl pallet_message_queue::Config for Test {
+	type RuntimeEvent = RuntimeEvent;
+	type WeightInfo = ();
+	type MessageProcessor = pallet_xcm_audit::AuditedProcessor<
+		Test,
+		ProcessXcmMessage<Location, pallet_xcm_audit::AuditedExecutor<Test, TestExecutor>, ()>,
+	>;
+	type Size = u32;
+	type QueueChangeHandler = ();
+	type QueuePausedQuery = ();
+	type HeapSize = HeapSize;
+	type MaxStale = MaxStale;
+	type ServiceWeight = ();
+	type IdleMaxServiceWeight = ();
+}
+
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	let t = frame_system::GenesisConfig::<Test>::default().build_storage().unwrap();
+	let mut ext = sp_io::TestExternalities::new(t);
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
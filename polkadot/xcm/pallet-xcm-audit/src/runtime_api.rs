@@ -0,0 +1,39 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Runtime API definition for querying the XCM audit history.
+
+use crate::AuditRecord;
+use alloc::vec::Vec;
+use codec::Codec;
+use xcm::{latest::XcmHash, VersionedLocation};
+
+sp_api::decl_runtime_apis! {
+	/// API for querying the history of executed and sent XCMs.
+	pub trait XcmAuditApi<BlockNumber> where BlockNumber: Codec {
+		/// Returns the records with `topic`, oldest first.
+		fn records_by_topic(topic: XcmHash) -> Vec<AuditRecord<BlockNumber>>;
+
+		/// Returns the records from or to `location`, oldest first.
+		///
+		/// Skips the first `skip` records and returns at most `max` records.
+		fn records_by_location(
+			location: VersionedLocation,
+			skip: u32,
+			max: u32,
+		) -> Vec<AuditRecord<BlockNumber>>;
+	}
+}
@@ -0,0 +1,329 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Tests for the XCM audit pallet.
+
+use crate::{
+	mock::*, AuditRecord, AuditedExecutor, AuditedRouter, Direction, ExecutionOutcome,
+	InstructionsSummary, RecordCount, Records, WeightInfo,
+};
+use codec::Encode;
+use frame_support::{
+	dispatch::DispatchClass,
+	storage::StorageList,
+	traits::{EnqueueMessage, Hooks, ServiceQueues},
+	BoundedSlice,
+};
+use xcm::prelude::*;
+
+type Executor = AuditedExecutor<Test, TestExecutor>;
+type Router = AuditedRouter<Test, TestRouter>;
+
+fn sibling(id: u32) -> Location {
+	Location::new(1, [Parachain(id)])
+}
+
+fn record_weight() -> Weight {
+	<() as WeightInfo>::record()
+}
+
+fn execute(origin: Location, message: Xcm<()>) -> Outcome {
+	let mut id = message.using_encoded(sp_io::hashing::blake2_256);
+	Executor::prepare_and_execute(origin, message, &mut id, Weight::MAX, Weight::zero())
+}
+
+fn send(destination: Location, message: Xcm<()>) {
+	assert!(send_xcm::<Router>(destination, message).is_ok());
+}
+
+/// Enqueues `message` from `origin` and services the message queue.
+fn enqueue(origin: Location, message: &[u8]) {
+	MessageQueue::enqueue_message(BoundedSlice::defensive_truncate_from(message), origin);
+	MessageQueue::service_queues(Weight::MAX);
+}
+
+fn records() -> Vec<AuditRecord<u64>> {
+	Records::<Test>::iter().collect()
+}
+
+#[test]
+fn executed_messages_are_recorded() {
+	new_test_ext().execute_with(|| {
+		let message = Xcm::<()>(vec![ClearOrigin, SetTopic([7; 32])]);
+		let summary = InstructionsSummary {
+			count: 2,
+			kinds: vec![ClearOrigin::<()>.encode()[0], SetTopic::<()>([7; 32]).encode()[0]]
+				.try_into()
+				.unwrap(),
+		};
+
+		let used = INSTRUCTION_WEIGHT.saturating_mul(2).saturating_add(record_weight());
+		assert_eq!(execute(sibling(2000), message), Outcome::Complete { used });
+
+		assert_eq!(
+			records(),
+			vec![AuditRecord {
+				block: 1,
+				direction: Direction::Executed,
+				location: sibling(2000).into(),
+				topic: [7; 32],
+				instructions: summary,
+				outcome: Some(ExecutionOutcome { used, error: None }),
+			}]
+		);
+		assert_eq!(RecordCount::<Test>::get(), 1);
+	});
+}
+
+#[test]
+fn failed_messages_are_recorded() {
+	new_test_ext().execute_with(|| {
+		let message = Xcm::<()>(vec![ClearOrigin, Trap(3), ClearOrigin]);
+		let hash = message.using_encoded(sp_io::hashing::blake2_256);
+
+		let used = INSTRUCTION_WEIGHT.saturating_mul(2).saturating_add(record_weight());
+		let error = InstructionError { index: 1, error: XcmError::Trap(3) };
+		assert_eq!(execute(sibling(2000), message), Outcome::Incomplete { used, error });
+
+		let records = records();
+		assert_eq!(records.len(), 1);
+		// Messages without a topic are recorded with their hash.
+		assert_eq!(records[0].topic, hash);
+		assert_eq!(records[0].instructions.count, 3);
+		assert_eq!(
+			records[0].outcome,
+			Some(ExecutionOutcome { used, error: Some((1, XcmError::Trap(3))) })
+		);
+	});
+}
+
+#[test]
+fn messages_failing_with_an_error_are_kept_by_the_message_queue() {
+	new_test_ext().execute_with(|| {
+		let message = Xcm::<()>(vec![ClearOrigin, SetTopic([7; 32])]);
+		let encoded = VersionedXcm::from(message.clone()).encode();
+
+		enqueue(sibling(2000), &encoded);
+		// The executor rejects messages from the relay chain with an error.
+		enqueue(Location::parent(), &encoded);
+
+		let records = records();
+		assert_eq!(records.len(), 2);
+		assert_eq!(records[1].location, Location::parent().into());
+		assert_eq!(records[1].instructions, InstructionsSummary::from(&message));
+		assert_eq!(
+			records[1].outcome,
+			Some(ExecutionOutcome { used: Weight::zero(), error: Some((0, XcmError::Barrier)) })
+		);
+		System::assert_last_event(
+			pallet_message_queue::Event::Processed {
+				id: sp_io::hashing::blake2_256(&encoded).into(),
+				origin: Location::parent(),
+				weight_used: INSTRUCTION_WEIGHT.saturating_mul(2).saturating_add(record_weight()),
+				success: false,
+			}
+			.into(),
+		);
+	});
+}
+
+#[test]
+fn undecodable_messages_are_recorded() {
+	new_test_ext().execute_with(|| {
+		enqueue(sibling(2000), b"junk");
+
+		assert_eq!(
+			records(),
+			vec![AuditRecord {
+				block: 1,
+				direction: Direction::Executed,
+				location: sibling(2000).into(),
+				topic: sp_io::hashing::blake2_256(b"junk"),
+				instructions: InstructionsSummary::default(),
+				outcome: Some(ExecutionOutcome {
+					used: Weight::zero(),
+					error: Some((0, XcmError::FailedToDecode)),
+				}),
+			}]
+		);
+		System::assert_last_event(
+			pallet_message_queue::Event::Processed {
+				id: sp_io::hashing::blake2_256(b"junk").into(),
+				origin: sibling(2000),
+				weight_used: record_weight(),
+				success: false,
+			}
+			.into(),
+		);
+	});
+}
+
+#[test]
+fn prepare_reserves_the_weight_of_recording() {
+	new_test_ext().execute_with(|| {
+		let message = Xcm::<()>(vec![ClearOrigin]);
+		let limit = INSTRUCTION_WEIGHT.saturating_add(record_weight());
+
+		let prepared = Executor::prepare(message.clone(), limit).unwrap();
+		assert_eq!(prepared.weight_of(), limit);
+
+		let limit = limit.saturating_sub(Weight::from_parts(1, 1));
+		assert!(Executor::prepare(message, limit).is_err());
+	});
+}
+
+#[test]
+fn sent_messages_are_recorded() {
+	new_test_ext().execute_with(|| {
+		let message = Xcm::<()>(vec![ClearOrigin, SetTopic([7; 32])]);
+		let summary = InstructionsSummary::from(&message);
+
+		assert_eq!(
+			send_xcm::<Router>(sibling(2000), message.clone()),
+			Ok(([7; 32], Assets::new()))
+		);
+		assert_eq!(
+			records(),
+			vec![AuditRecord {
+				block: 1,
+				direction: Direction::Sent,
+				location: sibling(2000).into(),
+				topic: [7; 32],
+				instructions: summary,
+				outcome: None,
+			}]
+		);
+
+		// Messages that can not be routed are not recorded.
+		assert_eq!(send_xcm::<Router>(Location::parent(), message), Err(SendError::NotApplicable));
+		assert_eq!(RecordCount::<Test>::get(), 1);
+
+		// Recording is weighed as part of the block.
+		assert_eq!(*System::block_weight().get(DispatchClass::Mandatory), record_weight());
+	});
+}
+
+#[test]
+fn summary_is_bounded() {
+	let message = Xcm::<()>(vec![ClearOrigin; 100]);
+	let summary = InstructionsSummary::from(&message);
+	assert_eq!(summary.count, 100);
+	assert_eq!(summary.kinds.len(), crate::MAX_SUMMARY_INSTRUCTIONS as usize);
+}
+
+#[test]
+fn history_is_bounded_on_write() {
+	new_test_ext().execute_with(|| {
+		for id in 0..7 {
+			execute(sibling(id), Xcm(vec![ClearOrigin]));
+		}
+		send(sibling(7), Xcm(vec![ClearOrigin]));
+
+		assert_eq!(RecordCount::<Test>::get(), 5);
+		let locations = records().into_iter().map(|r| r.location).collect::<Vec<_>>();
+		assert_eq!(locations, (3..8).map(|id| sibling(id).into()).collect::<Vec<_>>());
+
+		// Nothing is left for `on_idle` to prune.
+		assert_eq!(XcmAudit::on_idle(1, Weight::MAX), <() as WeightInfo>::prune_record());
+		assert_eq!(RecordCount::<Test>::get(), 5);
+	});
+}
+
+#[test]
+fn excess_records_are_pruned() {
+	new_test_ext().execute_with(|| {
+		for id in 0..5 {
+			execute(sibling(id), Xcm(vec![ClearOrigin]));
+		}
+
+		// A runtime upgrade lowered the maximum. Recording removes one record at a time.
+		MaxRecords::set(2);
+		execute(sibling(5), Xcm(vec![ClearOrigin]));
+		assert_eq!(RecordCount::<Test>::get(), 5);
+
+		let used = XcmAudit::on_idle(1, Weight::MAX);
+		assert_eq!(used, <() as WeightInfo>::prune_record().saturating_mul(4));
+
+		assert_eq!(RecordCount::<Test>::get(), 2);
+		let locations = records().into_iter().map(|r| r.location).collect::<Vec<_>>();
+		assert_eq!(locations, (4..6).map(|id| sibling(id).into()).collect::<Vec<_>>());
+	});
+}
+
+#[test]
+fn old_records_are_pruned() {
+	new_test_ext().execute_with(|| {
+		execute(sibling(1), Xcm(vec![ClearOrigin]));
+		System::set_block_number(5);
+		execute(sibling(2), Xcm(vec![ClearOrigin]));
+		execute(sibling(3), Xcm(vec![ClearOrigin]));
+
+		// Nothing is older than the retention period yet.
+		XcmAudit::on_idle(11, Weight::MAX);
+		assert_eq!(RecordCount::<Test>::get(), 3);
+
+		XcmAudit::on_idle(12, Weight::MAX);
+		assert_eq!(RecordCount::<Test>::get(), 2);
+		assert_eq!(records()[0].location, sibling(2).into());
+
+		XcmAudit::on_idle(16, Weight::MAX);
+		assert_eq!(RecordCount::<Test>::get(), 0);
+		assert!(records().is_empty());
+	});
+}
+
+#[test]
+fn pruning_respects_the_weight_limit() {
+	new_test_ext().execute_with(|| {
+		for id in 0..5 {
+			execute(sibling(id), Xcm(vec![ClearOrigin]));
+		}
+
+		let limit = <() as WeightInfo>::prune_record().saturating_mul(2);
+		assert_eq!(XcmAudit::on_idle(12, limit), limit);
+		assert_eq!(RecordCount::<Test>::get(), 3);
+		assert_eq!(records()[0].location, sibling(2).into());
+
+		XcmAudit::on_idle(12, Weight::zero());
+		assert_eq!(RecordCount::<Test>::get(), 3);
+	});
+}
+
+#[test]
+fn records_can_be_queried() {
+	new_test_ext().execute_with(|| {
+		execute(sibling(1), Xcm(vec![ClearOrigin, SetTopic([1; 32])]));
+		send(sibling(2), Xcm(vec![ClearOrigin, SetTopic([1; 32])]));
+		execute(sibling(1), Xcm(vec![ClearOrigin, SetTopic([2; 32])]));
+		execute(sibling(1), Xcm(vec![ClearOrigin, SetTopic([3; 32])]));
+
+		let by_topic = XcmAudit::records_by_topic([1; 32]);
+		assert_eq!(
+			by_topic.iter().map(|r| r.direction).collect::<Vec<_>>(),
+			vec![Direction::Executed, Direction::Sent]
+		);
+		assert!(XcmAudit::records_by_topic([4; 32]).is_empty());
+
+		let by_location = XcmAudit::records_by_location(sibling(1).into(), 1, 5);
+		assert_eq!(by_location.iter().map(|r| r.topic).collect::<Vec<_>>(), vec![[2; 32], [3; 32]]);
+		let by_location = XcmAudit::records_by_location(sibling(1).into(), 0, 1);
+		assert_eq!(by_location.iter().map(|r| r.topic).collect::<Vec<_>>(), vec![[1; 32]]);
+
+		// Locations of older versions are converted before they are compared.
+		let v4_location = VersionedLocation::V4(sibling(2).try_into().unwrap());
+		assert_eq!(XcmAudit::records_by_location(v4_location, 0, 5).len(), 1);
+	});
+}
@@ -0,0 +1,71 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Weights for `pallet_xcm_audit`.
+//!
+//! Placeholders until they are generated with the benchmarks of this pallet. The proof sizes
+//! assume pages of up to 16 records of at most 706 bytes, and both benchmarks read two pages.
+
+#![cfg_attr(rustfmt, rustfmt_skip)]
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+#![allow(missing_docs)]
+
+use frame_support::{traits::Get, weights::{Weight, constants::RocksDbWeight}};
+use core::marker::PhantomData;
+
+/// Weight functions needed for `pallet_xcm_audit`.
+pub trait WeightInfo {
+	fn record() -> Weight;
+	fn prune_record() -> Weight;
+}
+
+/// Weights for `pallet_xcm_audit` using the Substrate node and recommended hardware.
+pub struct SubstrateWeight<T>(PhantomData<T>);
+impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+	/// Storage: `XcmAudit::Records` (r:3 w:3)
+	/// Storage: `XcmAudit::RecordCount` (r:1 w:1)
+	/// Proof: `XcmAudit::RecordCount` (`max_values`: Some(1), `max_size`: Some(4), added: 499, mode: `MaxEncodedLen`)
+	fn record() -> Weight {
+		Weight::from_parts(40_000_000, 24_087)
+			.saturating_add(T::DbWeight::get().reads(4_u64))
+			.saturating_add(T::DbWeight::get().writes(4_u64))
+	}
+	/// Storage: `XcmAudit::Records` (r:3 w:2)
+	fn prune_record() -> Weight {
+		Weight::from_parts(30_000_000, 23_587)
+			.saturating_add(T::DbWeight::get().reads(3_u64))
+			.saturating_add(T::DbWeight::get().writes(2_u64))
+	}
+}
+
+// For backwards compatibility and tests.
+impl WeightInfo for () {
+	/// Storage: `XcmAudit::Records` (r:3 w:3)
+	/// Storage: `XcmAudit::RecordCount` (r:1 w:1)
+	/// Proof: `XcmAudit::RecordCount` (`max_values`: Some(1), `max_size`: Some(4), added: 499, mode: `MaxEncodedLen`)
+	fn record() -> Weight {
+		Weight::from_parts(40_000_000, 24_087)
+			.saturating_add(RocksDbWeight::get().reads(4_u64))
+			.saturating_add(RocksDbWeight::get().writes(4_u64))
+	}
+	/// Storage: `XcmAudit::Records` (r:3 w:2)
+	fn prune_record() -> Weight {
+		Weight::from_parts(30_000_000, 23_587)
+			.saturating_add(RocksDbWeight::get().reads(3_u64))
+			.saturating_add(RocksDbWeight::get().writes(2_u64))
+	}
+}
@@ -0,0 +1,220 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Benchmarks for the XCM collateral pallet.
+
+use super::*;
+use frame_benchmarking::v2::*;
+use frame_system::RawOrigin;
+use xcm_executor::traits::AssetLock;
+
+/// The location of the local account `who`, as converted by the `ExecuteXcmOrigin` of
+/// `pallet_xcm`.
+fn location_of<T: Config>(who: &T::AccountId) -> Result<Location, BenchmarkError> {
+	<T as pallet_xcm::Config>::ExecuteXcmOrigin::ensure_origin(
+		RawOrigin::Signed(who.clone()).into(),
+	)
+	.map_err(|_| BenchmarkError::Weightless)
+}
+
+/// Funds the account of `owner` with twice the amount of `asset` and returns the amount.
+fn fund<T: Config>(owner: &Location, asset: &Asset) -> Result<u128, BenchmarkError> {
+	let amount = <T as pallet_xcm::Config>::CurrencyMatcher::matches_fungible(asset)
+		.ok_or(BenchmarkError::Weightless)?;
+	let account = <T as pallet_xcm::Config>::SovereignAccountOf::convert_location(owner)
+		.ok_or(BenchmarkError::Weightless)?;
+	<T as pallet_xcm::Config>::Currency::make_free_balance_be(&account, amount.saturating_mul(2));
+	Ok(amount)
+}
+
+/// Opens two deals of `buyer` with `seller`, backed by a lock of the collateral of
+/// [`BenchmarkHelper::remote_lock`] that is large enough for a third deal. Returns the second deal.
+fn open<T: Config>(
+	buyer: &T::AccountId,
+	seller: &T::AccountId,
+) -> Result<(DealId, Location, Asset), BenchmarkError> {
+	let (locker, collateral) = T::BenchmarkHelper::remote_lock();
+	let Fungible(amount) = collateral.fun else { return Err(BenchmarkError::Weightless) };
+	let owner = location_of::<T>(buyer)?;
+	let lock = (collateral.id.clone(), amount.saturating_mul(3)).into();
+	pallet_xcm::Pallet::<T>::note_unlockable(locker.clone(), lock, owner)
+		.map_err(|_| BenchmarkError::Weightless)?;
+
+	let deadline = frame_system::Pallet::<T>::block_number() + 10u32.into();
+	for _ in 0..2 {
+		Pallet::<T>::open_deal(
+			RawOrigin::Signed(buyer.clone()).into(),
+			Box::new(locker.clone().into()),
+			Box::new(collateral.clone().into()),
+			seller.clone(),
+			deadline,
+		)?;
+	}
+	Ok((NextDealId::<T>::get() - 1, locker, collateral))
+}
+
+/// Opens a deal and confirms its delivery.
+fn deliver<T: Config>(
+	buyer: &T::AccountId,
+	seller: &T::AccountId,
+) -> Result<(DealId, Location), BenchmarkError> {
+	let (deal_id, locker, _) = open::<T>(buyer, seller)?;
+	let proof = T::BenchmarkHelper::proof(deal_id, buyer, seller);
+	Pallet::<T>::confirm_delivery(
+		RawOrigin::Signed(seller.clone()).into(),
+		deal_id,
+		proof,
+		Box::new(location_of::<T>(seller)?.into()),
+	)?;
+	Ok((deal_id, locker))
+}
+
+#[benchmarks]
+mod benchmarks {
+	use super::*;
+
+	#[benchmark]
+	fn lock() -> Result<(), BenchmarkError> {
+		let owner: T::AccountId = whitelisted_caller();
+		let location = location_of::<T>(&owner)?;
+		let asset = T::BenchmarkHelper::lockable_asset();
+		fund::<T>(&location, &asset)?;
+		let (unlocker, _) = T::BenchmarkHelper::remote_lock();
+		T::BenchmarkHelper::ensure_delivery(&location, &unlocker);
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(owner), Box::new(asset.clone().into()), Box::new(unlocker.into()));
+
+		Ok(())
+	}
+
+	#[benchmark]
+	fn claim() -> Result<(), BenchmarkError> {
+		let origin =
+			T::UnlockerOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+		let unlocker = T::UnlockerOrigin::ensure_origin(origin.clone())
+			.map_err(|_| BenchmarkError::Weightless)?;
+		let owner = location_of::<T>(&whitelisted_caller())?;
+		let beneficiary = location_of::<T>(&account("beneficiary", 0, 0))?;
+		let asset = T::BenchmarkHelper::lockable_asset();
+		fund::<T>(&owner, &asset)?;
+		pallet_xcm::Pallet::<T>::prepare_lock(unlocker, asset.clone(), owner.clone())
+			.and_then(Enact::enact)
+			.map_err(|_| BenchmarkError::Weightless)?;
+
+		#[extrinsic_call]
+		_(
+			origin as <T as frame_system::Config>::RuntimeOrigin,
+			Box::new(owner.into()),
+			Box::new(asset.into()),
+			Box::new(beneficiary.into()),
+		);
+
+		Ok(())
+	}
+
+	#[benchmark]
+	fn open_deal() -> Result<(), BenchmarkError> {
+		let buyer: T::AccountId = whitelisted_caller();
+		let seller: T::AccountId = account("seller", 0, 0);
+		// The buyer already has deals backed by the same lock.
+		let (_, locker, collateral) = open::<T>(&buyer, &seller)?;
+		let deal_id = NextDealId::<T>::get();
+		let deadline = frame_system::Pallet::<T>::block_number() + 10u32.into();
+
+		#[extrinsic_call]
+		_(
+			RawOrigin::Signed(buyer),
+			Box::new(locker.into()),
+			Box::new(collateral.into()),
+			seller,
+			deadline,
+		);
+
+		assert!(Deals::<T>::contains_key(deal_id));
+		Ok(())
+	}
+
+	#[benchmark]
+	fn confirm_delivery() -> Result<(), BenchmarkError> {
+		let buyer: T::AccountId = whitelisted_caller();
+		let seller: T::AccountId = account("seller", 0, 0);
+		let (deal_id, _, _) = open::<T>(&buyer, &seller)?;
+		let proof = T::BenchmarkHelper::proof(deal_id, &buyer, &seller);
+		let beneficiary = location_of::<T>(&seller)?;
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(seller), deal_id, proof, Box::new(beneficiary.into()));
+
+		assert!(matches!(
+			Deals::<T>::get(deal_id).map(|deal| deal.status),
+			Some(DealStatus::Delivered { .. })
+		));
+		Ok(())
+	}
+
+	#[benchmark]
+	fn dispute() -> Result<(), BenchmarkError> {
+		let buyer: T::AccountId = whitelisted_caller();
+		let (deal_id, _) = deliver::<T>(&buyer, &account("seller", 0, 0))?;
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(buyer), deal_id);
+
+		assert!(matches!(
+			Deals::<T>::get(deal_id).map(|deal| deal.status),
+			Some(DealStatus::Disputed { .. })
+		));
+		Ok(())
+	}
+
+	#[benchmark]
+	fn resolve_dispute() -> Result<(), BenchmarkError> {
+		let origin =
+			T::DisputeOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+		let buyer: T::AccountId = whitelisted_caller();
+		let (deal_id, _) = deliver::<T>(&buyer, &account("seller", 0, 0))?;
+		Pallet::<T>::dispute(RawOrigin::Signed(buyer).into(), deal_id)?;
+
+		#[extrinsic_call]
+		_(origin as <T as frame_system::Config>::RuntimeOrigin, deal_id, true);
+
+		assert!(matches!(
+			Deals::<T>::get(deal_id).map(|deal| deal.status),
+			Some(DealStatus::Resolved { beneficiary: Some(_) })
+		));
+		Ok(())
+	}
+
+	/// Claims the collateral of a deal while another deal of the buyer keeps holding the lock.
+	#[benchmark]
+	fn settle() -> Result<(), BenchmarkError> {
+		let buyer: T::AccountId = account("buyer", 0, 0);
+		let (deal_id, locker) = deliver::<T>(&buyer, &account("seller", 0, 0))?;
+		let caller: T::AccountId = whitelisted_caller();
+		T::BenchmarkHelper::ensure_delivery(&location_of::<T>(&caller)?, &locker);
+		let now = frame_system::Pallet::<T>::block_number();
+		frame_system::Pallet::<T>::set_block_number(now + T::DisputePeriod::get());
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller), deal_id);
+
+		assert!(!Deals::<T>::contains_key(deal_id));
+		Ok(())
+	}
+
+	impl_benchmark_test_suite!(Pallet, crate::mock::new_test_ext(), crate::mock::Test);
+}
@@ -0,0 +1,693 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! # XCM Collateral Pallet
+//!
+//! Collateral for deals on one chain, backed by fungible assets locked on another chain.
+//!
+//! ## Overview
+//!
+//! A buyer locks funds on a chain like Asset Hub, the locker, for a marketplace parachain, the
+//! unlocker. The buyer then opens a deal with a seller on the marketplace, which holds the
+//! collateral until the deal is settled:
+//!
+//! 1. The buyer calls [`Pallet::lock`] on the locker. The funds are locked with `pallet_xcm` and a
+//!    `NoteUnlockable` is sent to the marketplace, which records the remote lock.
+//! 2. The buyer calls [`Pallet::open_deal`] on the marketplace. The collateral of the deal is held
+//!    from the remote lock, so the buyer can not unlock it with `RequestUnlock`.
+//! 3. The seller delivers and calls [`Pallet::confirm_delivery`] with a proof of delivery, which is
+//!    checked by [`Config::VerifyDelivery`].
+//! 4. If the buyer does not [`Pallet::dispute`] the delivery within [`Config::DisputePeriod`], the
+//!    deal is settled by claiming the collateral for the seller. Otherwise
+//!    [`Config::DisputeOrigin`] decides with [`Pallet::resolve_dispute`].
+//!
+//! Deals that are not delivered before their deadline are settled by releasing the collateral
+//! back to the buyer. Deals are not settled in hooks, anyone can settle them with
+//! [`Pallet::settle`] once the deadline or dispute period is over or the dispute is resolved.
+//!
+//! All deals of a buyer that are backed by the same remote lock hold it together, under the
+//! [`Config::LockConsumer`] of this pallet. The lock can not be reduced below the collateral of
+//! the open deals, and settling a deal only frees its own collateral.
+//!
+//! ## Settlement
+//!
+//! Releasing sends `UnlockAsset` to the locker. Claiming sends a `Transact` of [`Pallet::claim`]
+//! with the `Xcm` origin kind, so the locker must convert it with `XcmPassthrough`. `claim`
+//! unlocks the collateral and transfers it to a beneficiary chosen by the seller. Both use the
+//! `pallet_xcm` asset locker, which has to be the `AssetLocker` of the XCM executor on both chains.
+//!
+//! A claim that fails on the locker, for example because the buyer no longer has the funds, leaves
+//! them locked for the marketplace. The marketplace can still release them with `UnlockAsset`.
+//!
+//! ## Fees
+//!
+//! The delivery fees of the messages are charged to the caller: the owner of the lock pays for the
+//! `NoteUnlockable` of [`Pallet::lock`] and whoever calls [`Pallet::settle`] pays for the
+//! settlement. Callers are converted to locations with the `ExecuteXcmOrigin` of `pallet_xcm`.
+//! Execution on the locker is paid with the [`CollateralLockers::fees`] of the locker, from the
+//! sovereign account of this chain.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+use alloc::{boxed::Box, vec};
+use codec::{Decode, Encode, MaxEncodedLen};
+use frame_support::{
+	pallet_prelude::*,
+	traits::{Currency, EnsureOrigin, ExistenceRequirement},
+};
+use frame_system::pallet_prelude::*;
+use scale_info::TypeInfo;
+use sp_runtime::{ArithmeticError, RuntimeDebug, Saturating};
+use xcm::prelude::*;
+use xcm_executor::traits::{AssetLock, ConvertLocation, Enact, MatchesFungible};
+
+pub use pallet::*;
+pub use weights::WeightInfo;
+
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+#[cfg(test)]
+mod mock;
+#[cfg(test)]
+mod tests;
+pub mod weights;
+
+/// The identifier of a deal.
+pub type DealId = u64;
+
+/// The chains that lock collateral for deals on this chain.
+pub trait CollateralLockers {
+	/// The index of this pallet in the runtime of `locker`, `None` if `locker` is not supported.
+	fn pallet_index(locker: &Location) -> Option<u8>;
+
+	/// The asset, as seen from `locker`, that pays for settling deals on `locker`.
+	///
+	/// Settlements are sent as unpaid messages if `None`.
+	fn fees(locker: &Location) -> Option<Asset>;
+}
+
+impl CollateralLockers for () {
+	fn pallet_index(_: &Location) -> Option<u8> {
+		None
+	}
+
+	fn fees(_: &Location) -> Option<Asset> {
+		None
+	}
+}
+
+/// Provides what the benchmarks need from the runtime.
+#[cfg(feature = "runtime-benchmarks")]
+pub trait BenchmarkHelper<AccountId, Proof> {
+	/// An asset that `pallet_xcm` can lock on this chain.
+	fn lockable_asset() -> Asset;
+
+	/// One of [`Config::Lockers`] and an asset, as seen from this chain, that it is trusted to
+	/// lock.
+	fn remote_lock() -> (Location, Asset);
+
+	/// A proof that [`Config::VerifyDelivery`] accepts for the delivery of deal `deal_id`.
+	fn proof(deal_id: DealId, buyer: &AccountId, seller: &AccountId) -> Proof;
+
+	/// Prepares everything for `payer` to send a message to `destination` and pay its delivery.
+	fn ensure_delivery(payer: &Location, destination: &Location);
+}
+
+#[cfg(feature = "runtime-benchmarks")]
+impl<AccountId, Proof: Default> BenchmarkHelper<AccountId, Proof> for () {
+	fn lockable_asset() -> Asset {
+		(Here, 1u128).into()
+	}
+
+	fn remote_lock() -> (Location, Asset) {
+		(Location::parent(), (Parent, 1u128).into())
+	}
+
+	fn proof(_: DealId, _: &AccountId, _: &AccountId) -> Proof {
+		Proof::default()
+	}
+
+	fn ensure_delivery(_: &Location, _: &Location) {}
+}
+
+/// Verifies that the seller of a deal delivered what was bought.
+pub trait VerifyDelivery<AccountId> {
+	/// The proof of delivery that the seller submits.
+	type Proof: Parameter;
+
+	/// The maximum weight of [`Self::verify`].
+	fn weight() -> Weight;
+
+	/// Returns whether `proof` proves that `seller` delivered deal `deal_id` to `buyer`.
+	fn verify(deal_id: DealId, buyer: &AccountId, seller: &AccountId, proof: &Self::Proof) -> bool;
+}
+
+impl<AccountId> VerifyDelivery<AccountId> for () {
+	type Proof = ();
+
+	fn weight() -> Weight {
+		Weight::zero()
+	}
+
+	fn verify(_: DealId, _: &AccountId, _: &AccountId, _: &()) -> bool {
+		false
+	}
+}
+
+/// Where a deal is in its life cycle.
+#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub enum DealStatus<BlockNumber> {
+	/// Waiting for the seller to deliver before the deadline.
+	Open,
+	/// Delivered. The collateral is claimed for `beneficiary` unless the buyer disputes the
+	/// delivery before `dispute_until`.
+	Delivered { beneficiary: VersionedLocation, dispute_until: BlockNumber },
+	/// The buyer disputes the delivery, [`Config::DisputeOrigin`] decides.
+	Disputed { beneficiary: VersionedLocation },
+	/// [`Config::DisputeOrigin`] decided the dispute. The collateral is claimed for `beneficiary`,
+	/// or released to the buyer if `None`.
+	Resolved { beneficiary: Option<VersionedLocation> },
+}
+
+/// A deal backed by collateral locked on another chain.
+#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct Deal<AccountId, BlockNumber> {
+	/// The buyer, who owns the remote lock.
+	pub buyer: AccountId,
+	/// The seller, who gets the collateral on delivery.
+	pub seller: AccountId,
+	/// The owner of the remote lock, as seen from this chain.
+	pub owner: VersionedLocation,
+	/// The chain that holds the lock.
+	pub locker: VersionedLocation,
+	/// The collateral, as seen from this chain.
+	pub collateral: VersionedAsset,
+	/// The block from which the deal can be released if it was not delivered.
+	pub deadline: BlockNumber,
+	/// Where the deal is in its life cycle.
+	pub status: DealStatus<BlockNumber>,
+}
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+
+	#[pallet::pallet]
+	pub struct Pallet<T>(_);
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config + pallet_xcm::Config {
+		/// The overarching event type.
+		#[allow(deprecated)]
+		type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+
+		/// The origin of claims from unlockers, which is `pallet_xcm::EnsureXcm`.
+		type UnlockerOrigin: EnsureOrigin<
+			<Self as frame_system::Config>::RuntimeOrigin,
+			Success = Location,
+		>;
+
+		/// The chains that lock collateral for deals on this chain.
+		type Lockers: CollateralLockers;
+
+		/// The consumer under which deals hold remote locks.
+		///
+		/// No other consumer of the remote locks of `pallet_xcm` may use the same identifier.
+		type LockConsumer: Get<<Self as pallet_xcm::Config>::RemoteLockConsumerIdentifier>;
+
+		/// Verifies the proofs of delivery of sellers.
+		type VerifyDelivery: VerifyDelivery<Self::AccountId>;
+
+		/// The origin that resolves disputes.
+		type DisputeOrigin: EnsureOrigin<<Self as frame_system::Config>::RuntimeOrigin>;
+
+		/// How long the buyer can dispute a delivery.
+		#[pallet::constant]
+		type DisputePeriod: Get<BlockNumberFor<Self>>;
+
+		/// Weight information for the operations of this pallet.
+		type WeightInfo: WeightInfo;
+
+		/// Provides what the benchmarks need from the runtime.
+		#[cfg(feature = "runtime-benchmarks")]
+		type BenchmarkHelper: BenchmarkHelper<
+			Self::AccountId,
+			<Self::VerifyDelivery as VerifyDelivery<Self::AccountId>>::Proof,
+		>;
+	}
+
+	/// The id of the next deal.
+	#[pallet::storage]
+	pub type NextDealId<T: Config> = StorageValue<_, DealId, ValueQuery>;
+
+	/// The deals that are not settled yet.
+	#[pallet::storage]
+	pub type Deals<T: Config> =
+		StorageMap<_, Twox64Concat, DealId, Deal<T::AccountId, BlockNumberFor<T>>>;
+
+	/// The total collateral of the open deals of a buyer, per asset.
+	#[pallet::storage]
+	pub type HeldCollateral<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		T::AccountId,
+		Blake2_128Concat,
+		VersionedAssetId,
+		u128,
+		ValueQuery,
+	>;
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// Collateral was locked on this chain for `unlocker`.
+		Locked { owner: Location, unlocker: Location, asset: Asset },
+		/// Locked collateral was claimed by its unlocker.
+		Claimed { owner: Location, unlocker: Location, asset: Asset, beneficiary: Location },
+		/// A deal was opened.
+		DealOpened { deal_id: DealId, buyer: T::AccountId, seller: T::AccountId, collateral: Asset },
+		/// The seller proved the delivery of a deal.
+		DeliveryConfirmed { deal_id: DealId, dispute_until: BlockNumberFor<T> },
+		/// The buyer disputes the delivery of a deal.
+		DealDisputed { deal_id: DealId },
+		/// The dispute of a deal was resolved. The deal is settled in favour of the seller if
+		/// `claim`, otherwise in favour of the buyer.
+		DisputeResolved { deal_id: DealId, claim: bool },
+		/// A deal was settled. The collateral was claimed for the seller if `claimed`, otherwise
+		/// it was released to the buyer.
+		DealSettled { deal_id: DealId, claimed: bool },
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// The version of the `Versioned` value used is not able to be interpreted.
+		BadVersion,
+		/// The collateral is not fungible.
+		NotFungible,
+		/// The asset can not be locked on this chain.
+		CannotLock,
+		/// The collateral is not locked for the unlocker.
+		NotLocked,
+		/// The given location does not have an account on this chain.
+		AccountNotSovereign,
+		/// The locker does not support claiming collateral.
+		UnsupportedLocker,
+		/// The deadline is not in the future.
+		BadDeadline,
+		/// The deal does not exist or is settled.
+		UnknownDeal,
+		/// The origin is not the seller of the deal.
+		NotSeller,
+		/// The origin is not the buyer of the deal.
+		NotBuyer,
+		/// The deal is not in the right status for the operation.
+		WrongStatus,
+		/// The deadline of the deal has passed.
+		DeadlinePassed,
+		/// The proof of delivery is not valid.
+		InvalidProof,
+		/// The dispute period of the delivery is over.
+		DisputePeriodOver,
+		/// The deal can not be settled yet.
+		NotSettleable,
+		/// A location could not be reanchored to the locker.
+		CannotReanchor,
+		/// The message could not be sent.
+		SendFailure,
+		/// The caller could not pay the delivery fees of the message.
+		FeesNotMet,
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Lock `asset` on this chain for `unlocker`.
+		///
+		/// The origin is converted to the owner of the lock with the `ExecuteXcmOrigin` of
+		/// `pallet_xcm`. The unlocker is notified with a `NoteUnlockable`, whose delivery fees
+		/// the owner pays.
+		#[pallet::call_index(0)]
+		#[pallet::weight(<T as Config>::WeightInfo::lock())]
+		pub fn lock(
+			origin: OriginFor<T>,
+			asset: Box<VersionedAsset>,
+			unlocker: Box<VersionedLocation>,
+		) -> DispatchResult {
+			let owner = <T as pallet_xcm::Config>::ExecuteXcmOrigin::ensure_origin(origin)?;
+			let asset: Asset = (*asset).try_into().map_err(|()| Error::<T>::BadVersion)?;
+			let unlocker: Location = (*unlocker).try_into().map_err(|()| Error::<T>::BadVersion)?;
+
+			let context = <T as pallet_xcm::Config>::UniversalLocation::get();
+			let note = NoteUnlockable {
+				asset: asset
+					.clone()
+					.reanchored(&unlocker, &context)
+					.map_err(|_| Error::<T>::CannotReanchor)?,
+				owner: owner
+					.clone()
+					.reanchored(&unlocker, &context)
+					.map_err(|_| Error::<T>::CannotReanchor)?,
+			};
+			pallet_xcm::Pallet::<T>::prepare_lock(unlocker.clone(), asset.clone(), owner.clone())
+				.and_then(Enact::enact)
+				.map_err(|_| Error::<T>::CannotLock)?;
+			Self::send(owner.clone(), unlocker.clone(), Xcm(vec![note]))?;
+
+			Self::deposit_event(Event::Locked { owner, unlocker, asset });
+			Ok(())
+		}
+
+		/// Claim collateral that `owner` locked for the origin and transfer it to `beneficiary`.
+		///
+		/// Sent by unlockers to settle a deal in favour of its seller.
+		#[pallet::call_index(1)]
+		#[pallet::weight(<T as Config>::WeightInfo::claim())]
+		pub fn claim(
+			origin: OriginFor<T>,
+			owner: Box<VersionedLocation>,
+			asset: Box<VersionedAsset>,
+			beneficiary: Box<VersionedLocation>,
+		) -> DispatchResult {
+			let unlocker = T::UnlockerOrigin::ensure_origin(origin)?;
+			let owner: Location = (*owner).try_into().map_err(|()| Error::<T>::BadVersion)?;
+			let asset: Asset = (*asset).try_into().map_err(|()| Error::<T>::BadVersion)?;
+			let beneficiary: Location =
+				(*beneficiary).try_into().map_err(|()| Error::<T>::BadVersion)?;
+
+			let amount = <T as pallet_xcm::Config>::CurrencyMatcher::matches_fungible(&asset)
+				.ok_or(Error::<T>::NotFungible)?;
+			let account = |location: &Location| {
+				<T as pallet_xcm::Config>::SovereignAccountOf::convert_location(location)
+					.ok_or(Error::<T>::AccountNotSovereign)
+			};
+			let (from, to) = (account(&owner)?, account(&beneficiary)?);
+
+			pallet_xcm::Pallet::<T>::prepare_unlock(unlocker.clone(), asset.clone(), owner.clone())
+				.and_then(Enact::enact)
+				.map_err(|_| Error::<T>::NotLocked)?;
+			<T as pallet_xcm::Config>::Currency::transfer(
+				&from,
+				&to,
+				amount,
+				ExistenceRequirement::AllowDeath,
+			)?;
+
+			Self::deposit_event(Event::Claimed { owner, unlocker, asset, beneficiary });
+			Ok(())
+		}
+
+		/// Open a deal with `seller`, backed by `collateral` that the origin locked on `locker`.
+		///
+		/// The collateral is held from the remote lock until the deal is settled, on top of the
+		/// collateral of the other open deals of the origin. The deal can be released if it is not
+		/// delivered before block `deadline`.
+		#[pallet::call_index(2)]
+		#[pallet::weight(<T as Config>::WeightInfo::open_deal())]
+		pub fn open_deal(
+			origin: OriginFor<T>,
+			locker: Box<VersionedLocation>,
+			collateral: Box<VersionedAsset>,
+			seller: T::AccountId,
+			deadline: BlockNumberFor<T>,
+		) -> DispatchResult {
+			let buyer = ensure_signed(origin)?;
+			let locker: Location = (*locker).try_into().map_err(|()| Error::<T>::BadVersion)?;
+			let collateral: Asset =
+				(*collateral).try_into().map_err(|()| Error::<T>::BadVersion)?;
+			ensure!(T::Lockers::pallet_index(&locker).is_some(), Error::<T>::UnsupportedLocker);
+			ensure!(deadline > frame_system::Pallet::<T>::block_number(), Error::<T>::BadDeadline);
+			let Fungible(amount) = collateral.fun else {
+				return Err(Error::<T>::NotFungible.into())
+			};
+
+			let asset_id = VersionedAssetId::from(collateral.id.clone());
+			let held = HeldCollateral::<T>::get(&buyer, &asset_id)
+				.checked_add(amount)
+				.ok_or(ArithmeticError::Overflow)?;
+			let owner = pallet_xcm::Pallet::<T>::hold_remote_lock(
+				&buyer,
+				&locker,
+				&(collateral.id.clone(), held).into(),
+				T::LockConsumer::get(),
+			)?;
+			let deal_id = NextDealId::<T>::get();
+			NextDealId::<T>::put(deal_id.wrapping_add(1));
+			HeldCollateral::<T>::insert(&buyer, &asset_id, held);
+			Deals::<T>::insert(
+				deal_id,
+				Deal {
+					buyer: buyer.clone(),
+					seller: seller.clone(),
+					owner: owner.into(),
+					locker: locker.into(),
+					collateral: collateral.clone().into(),
+					deadline,
+					status: DealStatus::Open,
+				},
+			);
+
+			Self::deposit_event(Event::DealOpened { deal_id, buyer, seller, collateral });
+			Ok(())
+		}
+
+		/// Prove the delivery of a deal.
+		///
+		/// The collateral is claimed for `beneficiary`, as seen from this chain, unless the buyer
+		/// disputes the delivery within the dispute period.
+		#[pallet::call_index(3)]
+		#[pallet::weight(
+			<T as Config>::WeightInfo::confirm_delivery().saturating_add(T::VerifyDelivery::weight())
+		)]
+		pub fn confirm_delivery(
+			origin: OriginFor<T>,
+			deal_id: DealId,
+			proof: <T::VerifyDelivery as VerifyDelivery<T::AccountId>>::Proof,
+			beneficiary: Box<VersionedLocation>,
+		) -> DispatchResult {
+			let seller = ensure_signed(origin)?;
+			let beneficiary =
+				(*beneficiary).into_version(XCM_VERSION).map_err(|()| Error::<T>::BadVersion)?;
+			let now = frame_system::Pallet::<T>::block_number();
+
+			Deals::<T>::try_mutate(deal_id, |maybe_deal| {
+				let deal = maybe_deal.as_mut().ok_or(Error::<T>::UnknownDeal)?;
+				ensure!(deal.seller == seller, Error::<T>::NotSeller);
+				ensure!(deal.status == DealStatus::Open, Error::<T>::WrongStatus);
+				ensure!(now < deal.deadline, Error::<T>::DeadlinePassed);
+				ensure!(
+					T::VerifyDelivery::verify(deal_id, &deal.buyer, &seller, &proof),
+					Error::<T>::InvalidProof
+				);
+
+				let dispute_until = now.saturating_add(T::DisputePeriod::get());
+				deal.status = DealStatus::Delivered { beneficiary, dispute_until };
+				Self::deposit_event(Event::DeliveryConfirmed { deal_id, dispute_until });
+				Ok(())
+			})
+		}
+
+		/// Dispute the delivery of a deal.
+		///
+		/// The deal is then settled by [`Config::DisputeOrigin`].
+		#[pallet::call_index(4)]
+		#[pallet::weight(<T as Config>::WeightInfo::dispute())]
+		pub fn dispute(origin: OriginFor<T>, deal_id: DealId) -> DispatchResult {
+			let buyer = ensure_signed(origin)?;
+			let now = frame_system::Pallet::<T>::block_number();
+
+			Deals::<T>::try_mutate(deal_id, |maybe_deal| {
+				let deal = maybe_deal.as_mut().ok_or(Error::<T>::UnknownDeal)?;
+				ensure!(deal.buyer == buyer, Error::<T>::NotBuyer);
+				let DealStatus::Delivered { beneficiary, dispute_until } = &deal.status else {
+					return Err(Error::<T>::WrongStatus.into())
+				};
+				ensure!(now < *dispute_until, Error::<T>::DisputePeriodOver);
+
+				deal.status = DealStatus::Disputed { beneficiary: beneficiary.clone() };
+				Self::deposit_event(Event::DealDisputed { deal_id });
+				Ok(())
+			})
+		}
+
+		/// Resolve the dispute of a deal. The collateral is claimed for the seller if `claim`,
+		/// otherwise it is released to the buyer.
+		///
+		/// The deal can then be settled with [`Pallet::settle`].
+		#[pallet::call_index(5)]
+		#[pallet::weight(<T as Config>::WeightInfo::resolve_dispute())]
+		pub fn resolve_dispute(
+			origin: OriginFor<T>,
+			deal_id: DealId,
+			claim: bool,
+		) -> DispatchResult {
+			T::DisputeOrigin::ensure_origin(origin)?;
+
+			Deals::<T>::try_mutate(deal_id, |maybe_deal| {
+				let deal = maybe_deal.as_mut().ok_or(Error::<T>::UnknownDeal)?;
+				let DealStatus::Disputed { beneficiary } = &deal.status else {
+					return Err(Error::<T>::WrongStatus.into())
+				};
+
+				deal.status =
+					DealStatus::Resolved { beneficiary: claim.then(|| beneficiary.clone()) };
+				Self::deposit_event(Event::DisputeResolved { deal_id, claim });
+				Ok(())
+			})
+		}
+
+		/// Settle a deal whose deadline or dispute period is over, or whose dispute is resolved.
+		///
+		/// Deals that were not delivered before their deadline are released to the buyer,
+		/// undisputed deliveries are claimed for the seller. Anyone can settle deals. The origin
+		/// is converted to a location with the `ExecuteXcmOrigin` of `pallet_xcm` and pays the
+		/// delivery fees of the settlement.
+		#[pallet::call_index(6)]
+		#[pallet::weight(<T as Config>::WeightInfo::settle())]
+		pub fn settle(origin: OriginFor<T>, deal_id: DealId) -> DispatchResult {
+			let payer = <T as pallet_xcm::Config>::ExecuteXcmOrigin::ensure_origin(origin)?;
+			let deal = Deals::<T>::get(deal_id).ok_or(Error::<T>::UnknownDeal)?;
+			let now = frame_system::Pallet::<T>::block_number();
+
+			let beneficiary = match &deal.status {
+				DealStatus::Open if now >= deal.deadline => None,
+				DealStatus::Delivered { beneficiary, dispute_until } if now >= *dispute_until =>
+					Some(beneficiary.clone()),
+				DealStatus::Resolved { beneficiary } => beneficiary.clone(),
+				_ => return Err(Error::<T>::NotSettleable.into()),
+			};
+
+			Self::do_settle(payer, deal_id, deal, beneficiary)
+		}
+	}
+}
+
+impl<T: Config> Pallet<T> {
+	/// Settle a deal by claiming its collateral for `beneficiary`, or by releasing it if `None`.
+	///
+	/// `payer` pays the delivery fees of the settlement.
+	fn do_settle(
+		payer: Location,
+		deal_id: DealId,
+		deal: Deal<T::AccountId, BlockNumberFor<T>>,
+		beneficiary: Option<VersionedLocation>,
+	) -> DispatchResult {
+		let bad_version = |()| Error::<T>::BadVersion;
+		let locker: Location = deal.locker.try_into().map_err(bad_version)?;
+		let owner: Location = deal.owner.try_into().map_err(bad_version)?;
+		let collateral: Asset = deal.collateral.try_into().map_err(bad_version)?;
+		let Fungible(amount) = collateral.fun else { return Err(Error::<T>::NotFungible.into()) };
+
+		// Only the collateral of this deal is freed, the other open deals keep holding theirs.
+		let asset_id = VersionedAssetId::from(collateral.id.clone());
+		let held = HeldCollateral::<T>::get(&deal.buyer, &asset_id).saturating_sub(amount);
+		if held > 0 {
+			pallet_xcm::Pallet::<T>::hold_remote_lock(
+				&deal.buyer,
+				&locker,
+				&(collateral.id.clone(), held).into(),
+				T::LockConsumer::get(),
+			)?;
+			HeldCollateral::<T>::insert(&deal.buyer, &asset_id, held);
+		} else {
+			pallet_xcm::Pallet::<T>::release_remote_lock(
+				&deal.buyer,
+				&collateral.id,
+				T::LockConsumer::get(),
+			)?;
+			HeldCollateral::<T>::remove(&deal.buyer, &asset_id);
+		}
+		pallet_xcm::Pallet::<T>::prepare_reduce_unlockable(
+			locker.clone(),
+			collateral.clone(),
+			owner.clone(),
+		)
+		.and_then(Enact::enact)
+		.map_err(|_| Error::<T>::NotLocked)?;
+
+		let context = <T as pallet_xcm::Config>::UniversalLocation::get();
+		let reanchor = |location: Location| {
+			location.reanchored(&locker, &context).map_err(|_| Error::<T>::CannotReanchor)
+		};
+		let asset = collateral
+			.reanchored(&locker, &context)
+			.map_err(|_| Error::<T>::CannotReanchor)?;
+		let owner = reanchor(owner)?;
+		let claimed = beneficiary.is_some();
+		let instruction = match beneficiary {
+			Some(beneficiary) => {
+				let beneficiary = reanchor(beneficiary.try_into().map_err(bad_version)?)?;
+				let index =
+					T::Lockers::pallet_index(&locker).ok_or(Error::<T>::UnsupportedLocker)?;
+				let call = Call::<T>::claim {
+					owner: Box::new(owner.into()),
+					asset: Box::new(asset.into()),
+					beneficiary: Box::new(beneficiary.into()),
+				};
+				Transact {
+					origin_kind: OriginKind::Xcm,
+					fallback_max_weight: None,
+					call: (index, call).encode().into(),
+				}
+			},
+			None => UnlockAsset { asset, target: owner },
+		};
+
+		let destination =
+			context.invert_target(&locker).map_err(|()| Error::<T>::CannotReanchor)?;
+		let message = Self::settle_message(instruction, T::Lockers::fees(&locker), destination);
+		Self::send(payer, locker, message)?;
+		Deals::<T>::remove(deal_id);
+
+		Self::deposit_event(Event::DealSettled { deal_id, claimed });
+		Ok(())
+	}
+
+	/// Sends `message` to `destination` as this chain and charges its delivery fees to `payer`.
+	fn send(payer: Location, destination: Location, message: Xcm<()>) -> DispatchResult {
+		let (ticket, price) =
+			validate_send::<<T as pallet_xcm::Config>::XcmRouter>(destination, message)
+				.map_err(|_| Error::<T>::SendFailure)?;
+		<T as pallet_xcm::Config>::XcmExecutor::charge_fees(payer, price)
+			.map_err(|_| Error::<T>::FeesNotMet)?;
+		<T as pallet_xcm::Config>::XcmRouter::deliver(ticket)
+			.map_err(|_| Error::<T>::SendFailure)?;
+		Ok(())
+	}
+
+	/// The message that executes `instruction` on the locker, paid with `fees`.
+	///
+	/// Unused fees are deposited back to `destination`, which is this chain.
+	fn settle_message(
+		instruction: Instruction<()>,
+		fees: Option<Asset>,
+		destination: Location,
+	) -> Xcm<()> {
+		let Some(fees) = fees else {
+			return Xcm(vec![
+				UnpaidExecution { weight_limit: Unlimited, check_origin: None },
+				instruction,
+			])
+		};
+		Xcm(vec![
+			WithdrawAsset(fees.clone().into()),
+			BuyExecution { fees, weight_limit: Unlimited },
+			instruction,
+			RefundSurplus,
+			DepositAsset { assets: Wild(AllCounted(1)), beneficiary: destination },
+		])
+	}
+}
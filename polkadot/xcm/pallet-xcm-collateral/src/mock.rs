@@ -0,0 +1,267 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Test environment for the XCM collateral pallet.
+//!
+//! The test chain plays both roles: it locks its native asset for other chains and it hosts deals
+//! backed by the asset of its relay chain, locked on the relay chain.
+
+use crate as pallet_xcm_collateral;
+use crate::{CollateralLockers, DealId, VerifyDelivery};
+use codec::Encode;
+use core::cell::RefCell;
+use frame_support::{
+	construct_runtime, derive_impl, parameter_types,
+	traits::{ConstU32, ConstU64, ConstU8, Disabled, Everything, Nothing},
+};
+use frame_system::EnsureRoot;
+use sp_runtime::BuildStorage;
+use xcm::prelude::*;
+use xcm_builder::{
+	AccountId32Aliases, AllowUnpaidExecutionFrom, EnsureXcmOrigin, FixedWeightBounds,
+	FrameTransactionalProcessor, FungibleAdapter, IsConcrete, SignedToAccountId32,
+};
+use xcm_executor::{traits::ConvertLocation, XcmExecutor};
+
+pub type AccountId = sp_runtime::AccountId32;
+pub type Balance = u128;
+
+pub const ALICE: AccountId = AccountId::new([1; 32]);
+pub const BOB: AccountId = AccountId::new([2; 32]);
+pub const CHARLIE: AccountId = AccountId::new([3; 32]);
+pub const INITIAL_BALANCE: Balance = 1_000;
+/// The delivery fee of every message, in the native asset.
+pub const DELIVERY_FEE: Balance = 10;
+/// The index of the collateral pallet on the relay chain.
+pub const RELAY_PALLET_INDEX: u8 = 40;
+
+type Block = frame_system::mocking::MockBlock<Test>;
+
+construct_runtime!(
+	pub enum Test {
+		System: frame_system,
+		Balances: pallet_balances,
+		XcmPallet: pallet_xcm,
+		Collateral: pallet_xcm_collateral,
+	}
+);
+
+#[derive_impl(frame_system::config_preludes::TestDefaultConfig)]
+impl frame_system::Config for Test {
+	type Block = Block;
+	type AccountId = AccountId;
+	type Lookup = sp_runtime::traits::IdentityLookup<AccountId>;
+	type AccountData = pallet_balances::AccountData<Balance>;
+}
+
+#[derive_impl(pallet_balances::config_preludes::TestDefaultConfig)]
+impl pallet_balances::Config for Test {
+	type Balance = Balance;
+	type AccountStore = System;
+}
+
+thread_local! {
+	pub static SENT_XCM: RefCell<Vec<(Location, Xcm<()>)>> = RefCell::new(Vec::new());
+}
+
+/// The messages sent so far.
+pub fn sent_xcm() -> Vec<(Location, Xcm<()>)> {
+	SENT_XCM.with(|q| q.borrow().clone())
+}
+
+/// Sends every message for [`DELIVERY_FEE`] and remembers it.
+pub struct TestSendXcm;
+impl SendXcm for TestSendXcm {
+	type Ticket = (Location, Xcm<()>);
+
+	fn validate(
+		destination: &mut Option<Location>,
+		message: &mut Option<Xcm<()>>,
+	) -> SendResult<Self::Ticket> {
+		let pair = (destination.take().unwrap(), message.take().unwrap());
+		Ok((pair, (Here, DELIVERY_FEE).into()))
+	}
+
+	fn deliver(pair: Self::Ticket) -> Result<XcmHash, SendError> {
+		let hash = pair.1.using_encoded(sp_io::hashing::blake2_256);
+		SENT_XCM.with(|q| q.borrow_mut().push(pair));
+		Ok(hash)
+	}
+}
+
+/// Accounts of the relay chain have the same account on this chain.
+pub struct RelayAccounts;
+impl ConvertLocation<AccountId> for RelayAccounts {
+	fn convert_location(location: &Location) -> Option<AccountId> {
+		match location.unpack() {
+			(1, [AccountId32 { id, .. }]) => Some((*id).into()),
+			_ => None,
+		}
+	}
+}
+
+/// The relay chain locks collateral for deals on this chain.
+pub struct RelayLocker;
+impl CollateralLockers for RelayLocker {
+	fn pallet_index(locker: &Location) -> Option<u8> {
+		(*locker == Location::parent()).then_some(RELAY_PALLET_INDEX)
+	}
+
+	fn fees(_: &Location) -> Option<Asset> {
+		None
+	}
+}
+
+/// Accepts a proof of delivery if it is `true`.
+pub struct MockDeliveryProof;
+impl VerifyDelivery<AccountId> for MockDeliveryProof {
+	type Proof = bool;
+
+	fn weight() -> Weight {
+		Weight::zero()
+	}
+
+	fn verify(_: DealId, _: &AccountId, _: &AccountId, proof: &bool) -> bool {
+		*proof
+	}
+}
+
+parameter_types! {
+	pub const HereLocation: Location = Location::here();
+	pub UniversalLocation: InteriorLocation = [GlobalConsensus(Polkadot), Parachain(1000)].into();
+	pub const BaseXcmWeight: Weight = Weight::from_parts(1_000, 1_000);
+	pub const MaxInstructions: u32 = 100;
+	pub const MaxAssetsIntoHolding: u32 = 64;
+}
+
+pub type SovereignAccountOf = (AccountId32Aliases<(), AccountId>, RelayAccounts);
+pub type LocalOriginToLocation = SignedToAccountId32<RuntimeOrigin, AccountId, ()>;
+type Weigher = FixedWeightBounds<BaseXcmWeight, RuntimeCall, MaxInstructions>;
+
+pub struct XcmConfig;
+impl xcm_executor::Config for XcmConfig {
+	type RuntimeCall = RuntimeCall;
+	type XcmSender = TestSendXcm;
+	type XcmEventEmitter = XcmPallet;
+	type AssetTransactor =
+		FungibleAdapter<Balances, IsConcrete<HereLocation>, SovereignAccountOf, AccountId, ()>;
+	type OriginConverter = pallet_xcm::XcmPassthrough<RuntimeOrigin>;
+	type IsReserve = ();
+	type IsTeleporter = ();
+	type UniversalLocation = UniversalLocation;
+	type Barrier = AllowUnpaidExecutionFrom<Everything>;
+	type Weigher = Weigher;
+	type Trader = ();
+	type ResponseHandler = XcmPallet;
+	type AssetTrap = XcmPallet;
+	type AssetLocker = XcmPallet;
+	type AssetExchanger = ();
+	type AssetClaims = XcmPallet;
+	type SubscriptionService = XcmPallet;
+	type PalletInstancesInfo = AllPalletsWithSystem;
+	type MaxAssetsIntoHolding = MaxAssetsIntoHolding;
+	type FeeManager = ();
+	type MessageExporter = ();
+	type UniversalAliases = Nothing;
+	type CallDispatcher = RuntimeCall;
+	type SafeCallFilter = Everything;
+	type Aliasers = Nothing;
+	type TransactionalProcessor = FrameTransactionalProcessor;
+	type HrmpNewChannelOpenRequestHandler = ();
+	type HrmpChannelAcceptedHandler = ();
+	type HrmpChannelClosingHandler = ();
+	type XcmRecorder = XcmPallet;
+}
+
+impl pallet_xcm::Config for Test {
+	type RuntimeEvent = RuntimeEvent;
+	type SendXcmOrigin = EnsureXcmOrigin<RuntimeOrigin, LocalOriginToLocation>;
+	type XcmRouter = TestSendXcm;
+	type ExecuteXcmOrigin = EnsureXcmOrigin<RuntimeOrigin, LocalOriginToLocation>;
+	type XcmExecuteFilter = Everything;
+	type XcmExecutor = XcmExecutor<XcmConfig>;
+	type XcmTeleportFilter = Everything;
+	type XcmReserveTransferFilter = Everything;
+	type Weigher = Weigher;
+	type UniversalLocation = UniversalLocation;
+	type RuntimeOrigin = RuntimeOrigin;
+	type RuntimeCall = RuntimeCall;
+	const VERSION_DISCOVERY_QUEUE_SIZE: u32 = 100;
+	type AdvertisedXcmVersion = pallet_xcm::CurrentXcmVersion;
+	type Currency = Balances;
+	type CurrencyMatcher = IsConcrete<HereLocation>;
+	type TrustedLockers = Everything;
+	type SovereignAccountOf = SovereignAccountOf;
+	type MaxLockers = ConstU32<8>;
+	type MaxRemoteLockConsumers = ConstU32<2>;
+	type RemoteLockConsumerIdentifier = u8;
+	type WeightInfo = pallet_xcm::TestWeightInfo;
+	type AdminOrigin = EnsureRoot<AccountId>;
+	type AuthorizedAliasConsideration = Disabled;
+}
+
+impl pallet_xcm_collateral::Config for Test {
+	type RuntimeEvent = RuntimeEvent;
+	type UnlockerOrigin = pallet_xcm::EnsureXcm<Everything>;
+	type Lockers = RelayLocker;
+	type LockConsumer = ConstU8<1>;
+	type VerifyDelivery = MockDeliveryProof;
+	type DisputeOrigin = EnsureRoot<AccountId>;
+	type DisputePeriod = ConstU64<5>;
+	type WeightInfo = ();
+	#[cfg(feature = "runtime-benchmarks")]
+	type BenchmarkHelper = TestBenchmarkHelper;
+}
+
+#[cfg(feature = "runtime-benchmarks")]
+pub struct TestBenchmarkHelper;
+#[cfg(feature = "runtime-benchmarks")]
+impl crate::BenchmarkHelper<AccountId, bool> for TestBenchmarkHelper {
+	fn lockable_asset() -> Asset {
+		(Here, 100).into()
+	}
+
+	fn remote_lock() -> (Location, Asset) {
+		(Location::parent(), (Parent, 100).into())
+	}
+
+	fn proof(_: DealId, _: &AccountId, _: &AccountId) -> bool {
+		true
+	}
+
+	fn ensure_delivery(payer: &Location, _: &Location) {
+		use frame_support::traits::fungible::Mutate;
+		let account = SovereignAccountOf::convert_location(payer).expect("payer has an account");
+		Balances::mint_into(&account, DELIVERY_FEE).expect("the fee can be minted");
+	}
+}
+
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	let mut t = frame_system::GenesisConfig::<Test>::default().build_storage().unwrap();
+	pallet_balances::GenesisConfig::<Test> {
+		balances: vec![
+			(ALICE, INITIAL_BALANCE),
+			(BOB, INITIAL_BALANCE),
+			(CHARLIE, INITIAL_BALANCE),
+		],
+		..Default::default()
+	}
+	.assimilate_storage(&mut t)
+	.unwrap();
+	let mut ext = sp_io::TestExternalities::new(t);
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
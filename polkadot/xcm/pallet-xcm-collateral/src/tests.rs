@@ -0,0 +1,393 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Tests for the XCM collateral pallet.
+
+use crate::{mock::*, Call, DealId, DealStatus, Deals, Error, Event, HeldCollateral};
+use codec::Encode;
+use frame_support::{assert_noop, assert_ok, traits::Currency};
+use sp_runtime::DispatchError;
+use xcm::prelude::*;
+use xcm_executor::traits::{AssetLock, LockError};
+
+fn account(id: AccountId) -> Location {
+	AccountId32 { network: None, id: id.into() }.into()
+}
+
+fn sibling() -> Location {
+	Location::new(1, [Parachain(2000)])
+}
+
+/// `ALICE` on the relay chain, as seen from this chain.
+fn alice_on_relay() -> Location {
+	Location::new(1, [AccountId32 { network: None, id: ALICE.into() }])
+}
+
+/// Records that the relay chain locked `amount` of `ALICE` for this chain.
+fn note_relay_lock(amount: u128) {
+	assert_ok!(XcmPallet::note_unlockable(
+		Location::parent(),
+		(Parent, amount).into(),
+		alice_on_relay()
+	));
+}
+
+/// Opens a deal of `ALICE` with `BOB`, backed by `amount` of the relay lock.
+fn open_deal(amount: u128, deadline: u64) -> DealId {
+	let deal_id = crate::NextDealId::<Test>::get();
+	assert_ok!(Collateral::open_deal(
+		RuntimeOrigin::signed(ALICE),
+		Box::new(Parent.into()),
+		Box::new((Parent, amount).into()),
+		BOB,
+		deadline,
+	));
+	deal_id
+}
+
+fn deliver(deal_id: DealId) {
+	assert_ok!(Collateral::confirm_delivery(
+		RuntimeOrigin::signed(BOB),
+		deal_id,
+		true,
+		Box::new(account(BOB).into()),
+	));
+}
+
+/// Whether `amount` of the relay lock could be unlocked, i.e. is not held by deals.
+fn can_reduce_relay_lock(amount: u128) -> Result<(), LockError> {
+	XcmPallet::prepare_reduce_unlockable(
+		Location::parent(),
+		(Parent, amount).into(),
+		alice_on_relay(),
+	)
+	.map(|_| ())
+}
+
+fn unlock_message(amount: u128) -> Xcm<()> {
+	Xcm(vec![
+		UnpaidExecution { weight_limit: Unlimited, check_origin: None },
+		UnlockAsset { asset: (Here, amount).into(), target: account(ALICE) },
+	])
+}
+
+fn claim_message(amount: u128) -> Xcm<()> {
+	let beneficiary =
+		Location::new(0, [Parachain(1000), AccountId32 { network: None, id: BOB.into() }]);
+	let call = Call::<Test>::claim {
+		owner: Box::new(account(ALICE).into()),
+		asset: Box::new((Here, amount).into()),
+		beneficiary: Box::new(beneficiary.into()),
+	};
+	Xcm(vec![
+		UnpaidExecution { weight_limit: Unlimited, check_origin: None },
+		Transact {
+			origin_kind: OriginKind::Xcm,
+			fallback_max_weight: None,
+			call: (RELAY_PALLET_INDEX, call).encode().into(),
+		},
+	])
+}
+
+#[test]
+fn lock_notifies_the_unlocker_at_the_cost_of_the_owner() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Collateral::lock(
+			RuntimeOrigin::signed(ALICE),
+			Box::new((Here, 100).into()),
+			Box::new(sibling().into()),
+		));
+
+		assert_eq!(Balances::locks(&ALICE)[0].amount, 100);
+		assert_eq!(Balances::free_balance(&ALICE), INITIAL_BALANCE - DELIVERY_FEE);
+		let note = NoteUnlockable {
+			asset: (Location::new(1, [Parachain(1000)]), 100).into(),
+			owner: Location::new(
+				1,
+				[Parachain(1000), AccountId32 { network: None, id: ALICE.into() }],
+			),
+		};
+		assert_eq!(sent_xcm(), vec![(sibling(), Xcm(vec![note]))]);
+		System::assert_last_event(
+			Event::Locked { owner: account(ALICE), unlocker: sibling(), asset: (Here, 100).into() }
+				.into(),
+		);
+	});
+}
+
+#[test]
+fn lock_fails_if_the_owner_can_not_pay_the_delivery() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			Collateral::lock(
+				RuntimeOrigin::signed(ALICE),
+				Box::new((Here, INITIAL_BALANCE).into()),
+				Box::new(sibling().into()),
+			),
+			Error::<Test>::FeesNotMet
+		);
+		assert!(sent_xcm().is_empty());
+	});
+}
+
+#[test]
+fn claim_transfers_the_collateral() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Collateral::lock(
+			RuntimeOrigin::signed(ALICE),
+			Box::new((Here, 100).into()),
+			Box::new(sibling().into()),
+		));
+		let claim = |unlocker: Location| {
+			Collateral::claim(
+				pallet_xcm::Origin::Xcm(unlocker).into(),
+				Box::new(account(ALICE).into()),
+				Box::new((Here, 100).into()),
+				Box::new(account(CHARLIE).into()),
+			)
+		};
+
+		assert_noop!(claim(Location::new(1, [Parachain(3000)])), Error::<Test>::NotLocked);
+		assert_noop!(
+			Collateral::claim(
+				RuntimeOrigin::signed(BOB),
+				Box::new(account(ALICE).into()),
+				Box::new((Here, 100).into()),
+				Box::new(account(CHARLIE).into()),
+			),
+			DispatchError::BadOrigin
+		);
+		assert_ok!(claim(sibling()));
+
+		assert!(Balances::locks(&ALICE).is_empty());
+		assert_eq!(Balances::free_balance(&ALICE), INITIAL_BALANCE - DELIVERY_FEE - 100);
+		assert_eq!(Balances::free_balance(&CHARLIE), INITIAL_BALANCE + 100);
+		// The lock is gone, it can not be claimed twice.
+		assert_noop!(claim(sibling()), Error::<Test>::NotLocked);
+	});
+}
+
+#[test]
+fn open_deal_checks_its_parameters() {
+	new_test_ext().execute_with(|| {
+		let open = |locker: Location, amount: u128, deadline: u64| {
+			Collateral::open_deal(
+				RuntimeOrigin::signed(ALICE),
+				Box::new(locker.into()),
+				Box::new((Parent, amount).into()),
+				BOB,
+				deadline,
+			)
+		};
+
+		assert_noop!(open(Location::parent(), 10, 5), pallet_xcm::Error::<Test>::LockNotFound);
+		note_relay_lock(100);
+		assert_noop!(open(sibling(), 10, 5), Error::<Test>::UnsupportedLocker);
+		assert_noop!(open(Location::parent(), 10, 1), Error::<Test>::BadDeadline);
+		assert_noop!(open(Location::parent(), 101, 5), pallet_xcm::Error::<Test>::LowBalance);
+	});
+}
+
+#[test]
+fn deals_hold_their_total_collateral() {
+	new_test_ext().execute_with(|| {
+		note_relay_lock(100);
+		open_deal(40, 10);
+		open_deal(50, 10);
+
+		let asset_id = VersionedAssetId::from(AssetId(Location::parent()));
+		assert_eq!(HeldCollateral::<Test>::get(&ALICE, &asset_id), 90);
+		assert!(matches!(can_reduce_relay_lock(11), Err(LockError::InUse)));
+		assert_ok!(can_reduce_relay_lock(10));
+		// Only 10 are left for another deal.
+		assert_noop!(
+			Collateral::open_deal(
+				RuntimeOrigin::signed(ALICE),
+				Box::new(Parent.into()),
+				Box::new((Parent, 11).into()),
+				BOB,
+				10,
+			),
+			pallet_xcm::Error::<Test>::LowBalance
+		);
+		open_deal(10, 10);
+	});
+}
+
+#[test]
+fn deals_sharing_a_lock_are_settled_in_any_order() {
+	new_test_ext().execute_with(|| {
+		note_relay_lock(100);
+		let first = open_deal(40, 10);
+		let second = open_deal(60, 10);
+
+		// The first deal is released while the second one still holds its collateral.
+		System::set_block_number(10);
+		assert_ok!(Collateral::settle(RuntimeOrigin::signed(CHARLIE), first));
+		let asset_id = VersionedAssetId::from(AssetId(Location::parent()));
+		assert_eq!(HeldCollateral::<Test>::get(&ALICE, &asset_id), 60);
+		assert!(matches!(can_reduce_relay_lock(1), Err(LockError::InUse)));
+
+		assert_ok!(Collateral::settle(RuntimeOrigin::signed(CHARLIE), second));
+		assert!(!HeldCollateral::<Test>::contains_key(&ALICE, &asset_id));
+		// Both deals reduced the lock by their collateral, nothing is left of it.
+		assert!(matches!(can_reduce_relay_lock(1), Err(LockError::NotLocked)));
+
+		assert_eq!(
+			sent_xcm(),
+			vec![
+				(Location::parent(), unlock_message(40)),
+				(Location::parent(), unlock_message(60))
+			]
+		);
+	});
+}
+
+#[test]
+fn delivered_deal_is_claimed_after_the_dispute_period() {
+	new_test_ext().execute_with(|| {
+		note_relay_lock(100);
+		let released = open_deal(30, 10);
+		let claimed = open_deal(70, 10);
+
+		assert_noop!(
+			Collateral::confirm_delivery(
+				RuntimeOrigin::signed(CHARLIE),
+				claimed,
+				true,
+				Box::new(account(BOB).into()),
+			),
+			Error::<Test>::NotSeller
+		);
+		assert_noop!(
+			Collateral::confirm_delivery(
+				RuntimeOrigin::signed(BOB),
+				claimed,
+				false,
+				Box::new(account(BOB).into()),
+			),
+			Error::<Test>::InvalidProof
+		);
+		deliver(claimed);
+		System::assert_last_event(
+			Event::DeliveryConfirmed { deal_id: claimed, dispute_until: 6 }.into(),
+		);
+
+		System::set_block_number(5);
+		assert_noop!(
+			Collateral::settle(RuntimeOrigin::signed(BOB), claimed),
+			Error::<Test>::NotSettleable
+		);
+		System::set_block_number(6);
+		assert_noop!(
+			Collateral::dispute(RuntimeOrigin::signed(ALICE), claimed),
+			Error::<Test>::DisputePeriodOver
+		);
+		assert_ok!(Collateral::settle(RuntimeOrigin::signed(BOB), claimed));
+		System::assert_last_event(Event::DealSettled { deal_id: claimed, claimed: true }.into());
+		assert_eq!(sent_xcm(), vec![(Location::parent(), claim_message(70))]);
+		assert!(Deals::<Test>::get(claimed).is_none());
+
+		// The other deal still holds its collateral until its deadline.
+		assert!(matches!(can_reduce_relay_lock(1), Err(LockError::InUse)));
+		assert_noop!(
+			Collateral::settle(RuntimeOrigin::signed(BOB), released),
+			Error::<Test>::NotSettleable
+		);
+		System::set_block_number(10);
+		assert_noop!(
+			Collateral::confirm_delivery(
+				RuntimeOrigin::signed(BOB),
+				released,
+				true,
+				Box::new(account(BOB).into()),
+			),
+			Error::<Test>::DeadlinePassed
+		);
+		assert_ok!(Collateral::settle(RuntimeOrigin::signed(BOB), released));
+		assert_eq!(sent_xcm()[1], (Location::parent(), unlock_message(30)));
+	});
+}
+
+#[test]
+fn settle_charges_the_caller() {
+	new_test_ext().execute_with(|| {
+		note_relay_lock(100);
+		let deal_id = open_deal(100, 2);
+		System::set_block_number(2);
+
+		assert_ok!(Collateral::settle(RuntimeOrigin::signed(CHARLIE), deal_id));
+		assert_eq!(Balances::free_balance(&CHARLIE), INITIAL_BALANCE - DELIVERY_FEE);
+		assert_eq!(Balances::free_balance(&ALICE), INITIAL_BALANCE);
+	});
+}
+
+#[test]
+fn settle_fails_if_the_caller_can_not_pay_the_delivery() {
+	new_test_ext().execute_with(|| {
+		note_relay_lock(100);
+		let deal_id = open_deal(100, 2);
+		System::set_block_number(2);
+		let poor = AccountId::new([9; 32]);
+
+		assert_noop!(
+			Collateral::settle(RuntimeOrigin::signed(poor), deal_id),
+			Error::<Test>::FeesNotMet
+		);
+		assert_noop!(Collateral::settle(RuntimeOrigin::root(), deal_id), DispatchError::BadOrigin);
+	});
+}
+
+#[test]
+fn disputed_deal_is_settled_as_resolved() {
+	new_test_ext().execute_with(|| {
+		note_relay_lock(100);
+		let deal_id = open_deal(100, 10);
+		deliver(deal_id);
+
+		assert_noop!(
+			Collateral::dispute(RuntimeOrigin::signed(BOB), deal_id),
+			Error::<Test>::NotBuyer
+		);
+		assert_noop!(
+			Collateral::resolve_dispute(RuntimeOrigin::root(), deal_id, false),
+			Error::<Test>::WrongStatus
+		);
+		assert_ok!(Collateral::dispute(RuntimeOrigin::signed(ALICE), deal_id));
+		System::assert_last_event(Event::DealDisputed { deal_id }.into());
+
+		System::set_block_number(20);
+		assert_noop!(
+			Collateral::settle(RuntimeOrigin::signed(BOB), deal_id),
+			Error::<Test>::NotSettleable
+		);
+		assert_noop!(
+			Collateral::resolve_dispute(RuntimeOrigin::signed(ALICE), deal_id, false),
+			DispatchError::BadOrigin
+		);
+		assert_ok!(Collateral::resolve_dispute(RuntimeOrigin::root(), deal_id, true));
+		System::assert_last_event(Event::DisputeResolved { deal_id, claim: true }.into());
+		assert_eq!(
+			Deals::<Test>::get(deal_id).unwrap().status,
+			DealStatus::Resolved { beneficiary: Some(account(BOB).into()) }
+		);
+		assert!(sent_xcm().is_empty());
+
+		assert_ok!(Collateral::settle(RuntimeOrigin::signed(ALICE), deal_id));
+		assert_eq!(sent_xcm(), vec![(Location::parent(), claim_message(100))]);
+		assert_eq!(Balances::free_balance(&ALICE), INITIAL_BALANCE - DELIVERY_FEE);
+	});
+}
@@ -0,0 +1,121 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Weights for `pallet_xcm_collateral`.
+//!
+//! PLACEHOLDERS: nothing in this file was benchmarked. Each weight is a hand picked constant plus
+//! the storage accesses counted from the code, meant to overestimate the call until the benchmarks
+//! of this crate are run with `frame-omni-bencher`. The proof sizes are rough upper bounds. `lock`
+//! and `settle` send a message, which is included with a guess of five reads and two writes, as
+//! its real cost depends on the router of the runtime.
+
+#![cfg_attr(rustfmt, rustfmt_skip)]
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+#![allow(missing_docs)]
+
+use frame_support::{traits::Get, weights::{Weight, constants::RocksDbWeight}};
+use core::marker::PhantomData;
+
+/// Weight functions needed for `pallet_xcm_collateral`.
+pub trait WeightInfo {
+	fn lock() -> Weight;
+	fn claim() -> Weight;
+	fn open_deal() -> Weight;
+	fn confirm_delivery() -> Weight;
+	fn dispute() -> Weight;
+	fn resolve_dispute() -> Weight;
+	fn settle() -> Weight;
+}
+
+/// Placeholder weights for `pallet_xcm_collateral`, see the module docs.
+pub struct SubstrateWeight<T>(PhantomData<T>);
+impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+	fn lock() -> Weight {
+		Weight::from_parts(70_000_000, 12135)
+			.saturating_add(T::DbWeight::get().reads(9_u64))
+			.saturating_add(T::DbWeight::get().writes(5_u64))
+	}
+	fn claim() -> Weight {
+		Weight::from_parts(35_000_000, 8458)
+			.saturating_add(T::DbWeight::get().reads(5_u64))
+			.saturating_add(T::DbWeight::get().writes(4_u64))
+	}
+	fn open_deal() -> Weight {
+		Weight::from_parts(20_000_000, 5557)
+			.saturating_add(T::DbWeight::get().reads(3_u64))
+			.saturating_add(T::DbWeight::get().writes(4_u64))
+	}
+	fn confirm_delivery() -> Weight {
+		Weight::from_parts(10_000_000, 5997)
+			.saturating_add(T::DbWeight::get().reads(1_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	fn dispute() -> Weight {
+		Weight::from_parts(10_000_000, 5997)
+			.saturating_add(T::DbWeight::get().reads(1_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	fn resolve_dispute() -> Weight {
+		Weight::from_parts(10_000_000, 5997)
+			.saturating_add(T::DbWeight::get().reads(1_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	fn settle() -> Weight {
+		Weight::from_parts(75_000_000, 9674)
+			.saturating_add(T::DbWeight::get().reads(9_u64))
+			.saturating_add(T::DbWeight::get().writes(6_u64))
+	}
+}
+
+// For backwards compatibility and tests.
+impl WeightInfo for () {
+	fn lock() -> Weight {
+		Weight::from_parts(70_000_000, 12135)
+			.saturating_add(RocksDbWeight::get().reads(9_u64))
+			.saturating_add(RocksDbWeight::get().writes(5_u64))
+	}
+	fn claim() -> Weight {
+		Weight::from_parts(35_000_000, 8458)
+			.saturating_add(RocksDbWeight::get().reads(5_u64))
+			.saturating_add(RocksDbWeight::get().writes(4_u64))
+	}
+	fn open_deal() -> Weight {
+		Weight::from_parts(20_000_000, 5557)
+			.saturating_add(RocksDbWeight::get().reads(3_u64))
+			.saturating_add(RocksDbWeight::get().writes(4_u64))
+	}
+	fn confirm_delivery() -> Weight {
+		Weight::from_parts(10_000_000, 5997)
+			.saturating_add(RocksDbWeight::get().reads(1_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	fn dispute() -> Weight {
+		Weight::from_parts(10_000_000, 5997)
+			.saturating_add(RocksDbWeight::get().reads(1_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	fn resolve_dispute() -> Weight {
+		Weight::from_parts(10_000_000, 5997)
+			.saturating_add(RocksDbWeight::get().reads(1_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	fn settle() -> Weight {
+		Weight::from_parts(75_000_000, 9674)
+			.saturating_add(RocksDbWeight::get().reads(9_u64))
+			.saturating_add(RocksDbWeight::get().writes(6_u64))
+	}
+}
@@ -0,0 +1,84 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Benchmarks for the XCM rate limiter pallet.
+
+use super::*;
+use frame_benchmarking::v2::*;
+
+#[benchmarks]
+mod benchmarks {
+	use super::*;
+
+	#[benchmark]
+	fn set_quota() -> Result<(), BenchmarkError> {
+		let origin =
+			T::UpdateOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+		let location = VersionedLocation::from(Location::new(1, [Parachain(1000)]));
+		let quota = Quota { max_messages: 10, max_weight: Weight::from_parts(1_000_000, 1_000) };
+
+		#[extrinsic_call]
+		_(origin as T::RuntimeOrigin, Box::new(location.clone()), Some(quota));
+
+		assert_eq!(Quotas::<T>::get(&location), Some(quota));
+		Ok(())
+	}
+
+	#[benchmark]
+	fn deny_execution_unlimited() {
+		let origin = Location::new(1, [Parachain(1000)]);
+		let mut message = Xcm::<()>(vec![ClearOrigin]);
+		let mut properties = Properties { weight_credit: Weight::zero(), message_id: None };
+
+		#[block]
+		{
+			assert!(Pallet::<T>::deny_execution(
+				&origin,
+				message.inner_mut(),
+				Weight::zero(),
+				&mut properties,
+			)
+			.is_ok());
+		}
+	}
+
+	#[benchmark]
+	fn deny_execution_limited() {
+		let origin = Location::new(1, [Parachain(1000)]);
+		let location = VersionedLocation::from(origin.clone());
+		let quota = Quota { max_messages: u32::MAX, max_weight: Weight::MAX };
+		Quotas::<T>::insert(&location, quota);
+		// A message was recorded in the current window, so the usage is read and written back.
+		assert!(Pallet::<T>::try_record(&location, quota, Weight::from_parts(1, 1)).is_ok());
+		let mut message = Xcm::<()>(vec![ClearOrigin]);
+		let mut properties = Properties { weight_credit: Weight::zero(), message_id: None };
+
+		#[block]
+		{
+			assert!(Pallet::<T>::deny_execution(
+				&origin,
+				message.inner_mut(),
+				Weight::from_parts(1, 1),
+				&mut properties,
+			)
+			.is_ok());
+		}
+
+		assert_eq!(Usages::<T>::get(&location).current.messages, 2);
+	}
+
+	impl_benchmark_test_suite!(Pallet, crate::mock::new_test_ext(), crate::mock::Test);
+}
@@ -0,0 +1,312 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! # XCM Rate Limiter Pallet
+//!
+//! Limits how many XCM messages, and how much weight, an origin may execute within a sliding
+//! window of blocks.
+//!
+//! ## Overview
+//!
+//! Governance sets a [`Quota`] per `Location` with [`Pallet::set_quota`]. Origins without a quota
+//! are not limited.
+//!
+//! The pallet implements [`DenyExecution`], so it is added to the XCM barrier in front of the
+//! barriers that allow execution:
+//!
+//! ```ignore
+//! type Barrier = DenyThenTry<
+//! 	XcmRateLimiter,
+//! 	(TakeWeightCredit, AllowTopLevelPaidExecutionFrom<Everything>, ...),
+//! >;
+//! ```
+//!
+//! Every message of a limited origin that passes the rate limit counts against its quota, even
+//! if it is rejected by a later barrier. A message that would exceed the quota is rejected and
+//! [`Event::Throttled`] is emitted.
+//!
+//! A rejected message is not kept for later: the executor fails it with `XcmError::Barrier` and the
+//! message queue treats it as processed. The sender has to send it again once its usage slid out
+//! of the window.
+//!
+//! The message queue rolls back the storage changes of a message only if processing it returns an
+//! error. The executor reports a message rejected by a barrier as incomplete instead, which
+//! `ProcessXcmMessage` passes on as processed unsuccessfully, so the recorded usage, the event and
+//! the registered weight are kept for rejected messages as well.
+//!
+//! ## Weight
+//!
+//! The weight of a message does not cover the barriers it passes. The storage accesses of the rate
+//! limiter are therefore registered as mandatory weight of the current block, see
+//! [`WeightInfo::deny_execution_unlimited`] and [`WeightInfo::deny_execution_limited`].
+//!
+//! ## Sliding window
+//!
+//! The usage of an origin is tracked in windows of [`Config::Period`] blocks. The usage over the
+//! last `Period` blocks is estimated as the usage of the current window plus the share of the
+//! previous window that still overlaps with the last `Period` blocks. This avoids the bursts a
+//! fixed window allows at its boundary, while only storing two counters per origin.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+use alloc::boxed::Box;
+use codec::{Decode, DecodeWithMemTracking, Encode, MaxEncodedLen};
+use frame_support::{
+	dispatch::DispatchClass,
+	pallet_prelude::*,
+	traits::{EnsureOrigin, ProcessMessageError},
+};
+use frame_system::pallet_prelude::*;
+use scale_info::TypeInfo;
+use sp_runtime::{
+	traits::{One, Saturating, Zero},
+	Perbill, RuntimeDebug,
+};
+use xcm::prelude::*;
+use xcm_executor::traits::{DenyExecution, Properties};
+
+pub use pallet::*;
+pub use weights::WeightInfo;
+
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+#[cfg(test)]
+mod mock;
+#[cfg(test)]
+mod tests;
+pub mod weights;
+
+const LOG_TARGET: &str = "xcm::rate-limiter";
+
+/// How many messages, and how much weight, an origin may execute within [`Config::Period`] blocks.
+#[derive(
+	Clone,
+	Copy,
+	Encode,
+	Decode,
+	DecodeWithMemTracking,
+	Eq,
+	PartialEq,
+	RuntimeDebug,
+	TypeInfo,
+	MaxEncodedLen,
+)]
+pub struct Quota {
+	/// The maximum number of messages.
+	pub max_messages: u32,
+	/// The maximum sum of the weight of all messages.
+	pub max_weight: Weight,
+}
+
+/// What an origin executed within one window.
+#[derive(
+	Clone, Copy, Default, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen,
+)]
+pub struct Counter {
+	/// The number of executed messages.
+	pub messages: u32,
+	/// The sum of the weight of all executed messages.
+	pub weight: Weight,
+}
+
+/// The usage of an origin in the current and previous window.
+#[derive(
+	Clone, Copy, Default, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen,
+)]
+pub struct Usage<BlockNumber> {
+	/// The index of the current window, which is the block number divided by the period.
+	pub window: BlockNumber,
+	/// The usage within the current window.
+	pub current: Counter,
+	/// The usage within the previous window.
+	pub previous: Counter,
+}
+
+impl<BlockNumber: sp_runtime::traits::AtLeast32BitUnsigned + Copy> Usage<BlockNumber> {
+	/// Moves the usage to `window`, forgetting windows that are too old to matter.
+	fn advance(&mut self, window: BlockNumber) {
+		if window == self.window {
+			return
+		}
+		self.previous = if window == self.window.saturating_add(One::one()) {
+			self.current
+		} else {
+			Counter::default()
+		};
+		self.current = Counter::default();
+		self.window = window;
+	}
+
+	/// Estimates the usage over the last `period` blocks, `elapsed` blocks into the current window.
+	fn estimate(&self, elapsed: BlockNumber, period: BlockNumber) -> Counter {
+		let overlap = Perbill::from_rational(period.saturating_sub(elapsed), period);
+		Counter {
+			messages: self.current.messages.saturating_add(overlap * self.previous.messages),
+			weight: self.current.weight.saturating_add(overlap * self.previous.weight),
+		}
+	}
+}
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+
+	#[pallet::pallet]
+	pub struct Pallet<T>(_);
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// The overarching event type.
+		#[allow(deprecated)]
+		type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+
+		/// The origin that can set quotas.
+		type UpdateOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+
+		/// The length of a window in blocks. Must not be zero.
+		#[pallet::constant]
+		type Period: Get<BlockNumberFor<Self>>;
+
+		/// Weight information for extrinsics in this pallet.
+		type WeightInfo: WeightInfo;
+	}
+
+	/// The quota of every limited origin.
+	#[pallet::storage]
+	pub type Quotas<T: Config> = StorageMap<_, Blake2_128Concat, VersionedLocation, Quota>;
+
+	/// The usage of every limited origin.
+	#[pallet::storage]
+	pub type Usages<T: Config> =
+		StorageMap<_, Blake2_128Concat, VersionedLocation, Usage<BlockNumberFor<T>>, ValueQuery>;
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// The quota of `location` was set. `None` means the location is no longer limited.
+		QuotaSet { location: VersionedLocation, quota: Option<Quota> },
+		/// A message from `origin` was rejected because it would exceed the quota.
+		///
+		/// `messages` and `weight` are the estimated usage over the last period, without the
+		/// rejected message.
+		Throttled { origin: VersionedLocation, messages: u32, weight: Weight },
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// The location can not be converted to the latest XCM version.
+		BadVersion,
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		fn integrity_test() {
+			assert!(!T::Period::get().is_zero(), "`Period` must not be zero");
+		}
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Set the quota of `location`, or remove it with `None`.
+		///
+		/// Removing the quota also removes the recorded usage of `location`.
+		///
+		/// The dispatch origin of this call must be `UpdateOrigin`.
+		#[pallet::call_index(0)]
+		#[pallet::weight(T::WeightInfo::set_quota())]
+		pub fn set_quota(
+			origin: OriginFor<T>,
+			location: Box<VersionedLocation>,
+			quota: Option<Quota>,
+		) -> DispatchResult {
+			T::UpdateOrigin::ensure_origin(origin)?;
+			let location: Location = (*location).try_into().map_err(|()| Error::<T>::BadVersion)?;
+			let location = VersionedLocation::from(location);
+
+			match quota {
+				Some(quota) => Quotas::<T>::insert(&location, quota),
+				None => {
+					Quotas::<T>::remove(&location);
+					Usages::<T>::remove(&location);
+				},
+			}
+			Self::deposit_event(Event::QuotaSet { location, quota });
+			Ok(())
+		}
+	}
+}
+
+impl<T: Config> Pallet<T> {
+	/// Records a message of `max_weight` from `origin`, which is limited by `quota`.
+	///
+	/// Returns the estimated usage without the message as error if the message would exceed the
+	/// quota of `origin`.
+	fn try_record(
+		origin: &VersionedLocation,
+		quota: Quota,
+		max_weight: Weight,
+	) -> Result<(), Counter> {
+		let now = frame_system::Pallet::<T>::block_number();
+		let period = T::Period::get();
+
+		let mut usage = Usages::<T>::get(origin);
+		usage.advance(now / period);
+		let estimate = usage.estimate(now % period, period);
+		if estimate.messages >= quota.max_messages ||
+			estimate.weight.saturating_add(max_weight).any_gt(quota.max_weight)
+		{
+			return Err(estimate)
+		}
+
+		usage.current.messages.saturating_inc();
+		usage.current.weight.saturating_accrue(max_weight);
+		Usages::<T>::insert(origin, usage);
+		Ok(())
+	}
+}
+
+impl<T: Config> DenyExecution for Pallet<T> {
+	fn deny_execution<RuntimeCall>(
+		origin: &Location,
+		_instructions: &mut [Instruction<RuntimeCall>],
+		max_weight: Weight,
+		_properties: &mut Properties,
+	) -> Result<(), ProcessMessageError> {
+		let origin = VersionedLocation::from(origin.clone());
+		let Some(quota) = Quotas::<T>::get(&origin) else {
+			register_weight::<T>(T::WeightInfo::deny_execution_unlimited());
+			return Ok(())
+		};
+		register_weight::<T>(T::WeightInfo::deny_execution_limited());
+
+		Self::try_record(&origin, quota, max_weight).map_err(|estimate| {
+			tracing::debug!(target: LOG_TARGET, ?origin, ?estimate, ?max_weight, "Throttled");
+			Self::deposit_event(Event::Throttled {
+				origin,
+				messages: estimate.messages,
+				weight: estimate.weight,
+			});
+			ProcessMessageError::Unsupported
+		})
+	}
+}
+
+/// Registers the weight of the barrier, which is not part of the weight of the message.
+fn register_weight<T: Config>(weight: Weight) {
+	frame_system::Pallet::<T>::register_extra_weight_unchecked(weight, DispatchClass::Mandatory);
+}
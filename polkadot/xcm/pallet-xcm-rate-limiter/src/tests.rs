@@ -0,0 +1,278 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Tests for the XCM rate limiter pallet.
+
+use crate::{mock::*, Error, Event, Quota, Quotas, Usages, WeightInfo};
+use codec::Encode;
+use frame_support::{
+	assert_noop, assert_ok,
+	dispatch::DispatchClass,
+	traits::{EnqueueMessage, ProcessMessageError, ServiceQueues},
+	BoundedSlice,
+};
+use sp_runtime::traits::BadOrigin;
+use xcm::prelude::*;
+use xcm_executor::traits::{Properties, ShouldExecute};
+
+fn sibling() -> Location {
+	Location::new(1, [Parachain(2000)])
+}
+
+fn denied() -> Location {
+	Location::new(1, [Parachain(DENIED_PARA)])
+}
+
+fn set_quota(location: Location, max_messages: u32, max_weight: u64) {
+	let quota = Quota { max_messages, max_weight: Weight::from_parts(max_weight, max_weight) };
+	assert_ok!(XcmRateLimiter::set_quota(
+		RuntimeOrigin::root(),
+		Box::new(location.into()),
+		Some(quota)
+	));
+}
+
+fn execute(origin: Location, weight: u64) -> Result<(), ProcessMessageError> {
+	let mut message = Xcm::<()>(vec![ClearOrigin]);
+	let mut properties = Properties { weight_credit: Weight::zero(), message_id: None };
+	Barrier::should_execute(
+		&origin,
+		message.inner_mut(),
+		Weight::from_parts(weight, weight),
+		&mut properties,
+	)
+}
+
+/// The weight of the message [`enqueue`] sends.
+const MESSAGE_WEIGHT: u64 = 1_000;
+
+/// Enqueues a message from `origin` and services the message queue.
+///
+/// Returns the encoded message.
+fn enqueue(origin: Location) -> Vec<u8> {
+	let message = VersionedXcm::from(Xcm::<RuntimeCall>(vec![ClearOrigin])).encode();
+	MessageQueue::enqueue_message(BoundedSlice::defensive_truncate_from(&message[..]), origin);
+	MessageQueue::service_queues(Weight::MAX);
+	message
+}
+
+fn last_message_queue_event() -> pallet_message_queue::Event<Test> {
+	System::events()
+		.into_iter()
+		.rev()
+		.find_map(|record| match record.event {
+			RuntimeEvent::MessageQueue(event) => Some(event),
+			_ => None,
+		})
+		.expect("the message queue processed a message")
+}
+
+#[test]
+fn set_quota_works() {
+	new_test_ext().execute_with(|| {
+		let quota = Quota { max_messages: 3, max_weight: Weight::from_parts(10, 10) };
+		assert_noop!(
+			XcmRateLimiter::set_quota(
+				RuntimeOrigin::signed(1),
+				Box::new(sibling().into()),
+				Some(quota)
+			),
+			BadOrigin
+		);
+
+		assert_ok!(XcmRateLimiter::set_quota(
+			RuntimeOrigin::root(),
+			Box::new(sibling().into()),
+			Some(quota)
+		));
+		assert_eq!(Quotas::<Test>::get(VersionedLocation::from(sibling())), Some(quota));
+		System::assert_last_event(
+			Event::QuotaSet { location: sibling().into(), quota: Some(quota) }.into(),
+		);
+
+		assert_ok!(execute(sibling(), 1));
+		assert_ok!(XcmRateLimiter::set_quota(
+			RuntimeOrigin::root(),
+			Box::new(sibling().into()),
+			None
+		));
+		assert_eq!(Quotas::<Test>::get(VersionedLocation::from(sibling())), None);
+		assert!(!Usages::<Test>::contains_key(VersionedLocation::from(sibling())));
+	});
+}
+
+#[test]
+fn set_quota_converts_to_latest_version() {
+	new_test_ext().execute_with(|| {
+		let quota = Quota { max_messages: 3, max_weight: Weight::from_parts(10, 10) };
+		let v4 = VersionedLocation::V4(sibling().try_into().unwrap());
+		assert_ok!(XcmRateLimiter::set_quota(RuntimeOrigin::root(), Box::new(v4), Some(quota)));
+
+		// The quota is found for the latest version of the location.
+		assert_eq!(Quotas::<Test>::get(VersionedLocation::from(sibling())), Some(quota));
+	});
+}
+
+#[test]
+fn origins_without_quota_are_not_limited() {
+	new_test_ext().execute_with(|| {
+		set_quota(sibling(), 1, 10);
+		for _ in 0..10 {
+			assert_ok!(execute(Location::parent(), 100));
+		}
+	});
+}
+
+#[test]
+fn message_quota_works() {
+	new_test_ext().execute_with(|| {
+		set_quota(sibling(), 2, 100);
+		assert_ok!(execute(sibling(), 1));
+		assert_ok!(execute(sibling(), 1));
+
+		assert_eq!(execute(sibling(), 1), Err(ProcessMessageError::Unsupported));
+		System::assert_last_event(
+			Event::Throttled {
+				origin: sibling().into(),
+				messages: 2,
+				weight: Weight::from_parts(2, 2),
+			}
+			.into(),
+		);
+	});
+}
+
+#[test]
+fn weight_quota_works() {
+	new_test_ext().execute_with(|| {
+		set_quota(sibling(), 10, 100);
+		assert_ok!(execute(sibling(), 60));
+		assert_eq!(execute(sibling(), 41), Err(ProcessMessageError::Unsupported));
+		assert_ok!(execute(sibling(), 40));
+		assert_eq!(execute(sibling(), 1), Err(ProcessMessageError::Unsupported));
+	});
+}
+
+#[test]
+fn usage_slides_out_of_the_window() {
+	new_test_ext().execute_with(|| {
+		set_quota(sibling(), 4, 100);
+		// Block 1 is in the first window of 10 blocks.
+		for _ in 0..4 {
+			assert_ok!(execute(sibling(), 1));
+		}
+		assert!(execute(sibling(), 1).is_err());
+
+		// At block 10 the previous window still counts fully.
+		System::set_block_number(10);
+		assert!(execute(sibling(), 1).is_err());
+
+		// Halfway through the window, half of the previous window counts.
+		System::set_block_number(15);
+		assert_ok!(execute(sibling(), 1));
+		assert_ok!(execute(sibling(), 1));
+		assert!(execute(sibling(), 1).is_err());
+
+		// Once a whole window passed without messages, the quota is available again.
+		System::set_block_number(30);
+		for _ in 0..4 {
+			assert_ok!(execute(sibling(), 1));
+		}
+		assert!(execute(sibling(), 1).is_err());
+	});
+}
+
+#[test]
+fn barrier_weight_is_registered() {
+	new_test_ext().execute_with(|| {
+		let block_weight = || *System::block_weight().get(DispatchClass::Mandatory);
+		set_quota(sibling(), 1, 100);
+		let before = block_weight();
+
+		assert_ok!(execute(Location::parent(), 1));
+		let unlimited = <() as WeightInfo>::deny_execution_unlimited();
+		assert_eq!(block_weight(), before + unlimited);
+
+		// Throttled messages are weighed as well.
+		assert_ok!(execute(sibling(), 1));
+		assert!(execute(sibling(), 1).is_err());
+		let limited = <() as WeightInfo>::deny_execution_limited();
+		assert_eq!(block_weight(), before + unlimited + limited * 2);
+	});
+}
+
+#[test]
+fn throttling_is_kept_by_the_message_queue() {
+	new_test_ext().execute_with(|| {
+		let block_weight = || *System::block_weight().get(DispatchClass::Mandatory);
+		set_quota(sibling(), 1, 10_000);
+		let before = block_weight();
+
+		enqueue(sibling());
+		assert!(matches!(
+			last_message_queue_event(),
+			pallet_message_queue::Event::Processed { success: true, .. }
+		));
+
+		enqueue(sibling());
+		System::assert_has_event(
+			Event::Throttled {
+				origin: sibling().into(),
+				messages: 1,
+				weight: Weight::from_parts(MESSAGE_WEIGHT, MESSAGE_WEIGHT),
+			}
+			.into(),
+		);
+		assert!(matches!(
+			last_message_queue_event(),
+			pallet_message_queue::Event::Processed { success: false, .. }
+		));
+		assert_eq!(Usages::<Test>::get(VersionedLocation::from(sibling())).current.messages, 1);
+		let limited = <() as WeightInfo>::deny_execution_limited();
+		assert_eq!(block_weight(), before + limited * 2);
+	});
+}
+
+#[test]
+fn messages_rejected_by_a_later_barrier_count_through_the_message_queue() {
+	new_test_ext().execute_with(|| {
+		set_quota(denied(), 2, 10_000);
+		enqueue(denied());
+		enqueue(denied());
+		let usage = Usages::<Test>::get(VersionedLocation::from(denied()));
+		assert_eq!(usage.current.messages, 2);
+		assert_eq!(usage.current.weight, Weight::from_parts(MESSAGE_WEIGHT, MESSAGE_WEIGHT) * 2);
+
+		let message = enqueue(denied());
+		System::assert_last_event(
+			pallet_message_queue::Event::Processed {
+				id: sp_io::hashing::blake2_256(&message).into(),
+				origin: denied(),
+				weight_used: Weight::from_parts(MESSAGE_WEIGHT, MESSAGE_WEIGHT),
+				success: false,
+			}
+			.into(),
+		);
+		System::assert_has_event(
+			Event::Throttled {
+				origin: denied().into(),
+				messages: 2,
+				weight: Weight::from_parts(MESSAGE_WEIGHT, MESSAGE_WEIGHT) * 2,
+			}
+			.into(),
+		);
+	});
+}
@@ -0,0 +1,91 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Weights for `pallet_xcm_rate_limiter`.
+//!
+//! The proof sizes are the `MaxEncodedLen` bounds of the storage accessed by the benchmarks. The
+//! reference times are conservative placeholders until the weights are generated with
+//! `frame-omni-bencher v1 benchmark pallet --pallet pallet_xcm_rate_limiter`.
+
+#![cfg_attr(rustfmt, rustfmt_skip)]
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+#![allow(missing_docs)]
+
+use frame_support::{traits::Get, weights::{Weight, constants::RocksDbWeight}};
+use core::marker::PhantomData;
+
+/// Weight functions needed for `pallet_xcm_rate_limiter`.
+pub trait WeightInfo {
+	fn set_quota() -> Weight;
+	fn deny_execution_unlimited() -> Weight;
+	fn deny_execution_limited() -> Weight;
+}
+
+/// Weights for `pallet_xcm_rate_limiter` using the Substrate node and recommended hardware.
+pub struct SubstrateWeight<T>(PhantomData<T>);
+impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+	/// Storage: `XcmRateLimiter::Quotas` (r:0 w:1)
+	/// Proof: `XcmRateLimiter::Quotas` (`max_values`: None, `max_size`: Some(640), added: 3115, mode: `MaxEncodedLen`)
+	/// Storage: `XcmRateLimiter::Usages` (r:0 w:1)
+	/// Proof: `XcmRateLimiter::Usages` (`max_values`: None, `max_size`: Some(666), added: 3141, mode: `MaxEncodedLen`)
+	fn set_quota() -> Weight {
+		Weight::from_parts(10_000_000, 0)
+			.saturating_add(T::DbWeight::get().writes(2_u64))
+	}
+	/// Storage: `XcmRateLimiter::Quotas` (r:1 w:0)
+	/// Proof: `XcmRateLimiter::Quotas` (`max_values`: None, `max_size`: Some(640), added: 3115, mode: `MaxEncodedLen`)
+	fn deny_execution_unlimited() -> Weight {
+		Weight::from_parts(6_000_000, 4105)
+			.saturating_add(T::DbWeight::get().reads(1_u64))
+	}
+	/// Storage: `XcmRateLimiter::Quotas` (r:1 w:0)
+	/// Proof: `XcmRateLimiter::Quotas` (`max_values`: None, `max_size`: Some(640), added: 3115, mode: `MaxEncodedLen`)
+	/// Storage: `XcmRateLimiter::Usages` (r:1 w:1)
+	/// Proof: `XcmRateLimiter::Usages` (`max_values`: None, `max_size`: Some(666), added: 3141, mode: `MaxEncodedLen`)
+	fn deny_execution_limited() -> Weight {
+		Weight::from_parts(15_000_000, 4131)
+			.saturating_add(T::DbWeight::get().reads(2_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+}
+
+// For backwards compatibility and tests.
+impl WeightInfo for () {
+	/// Storage: `XcmRateLimiter::Quotas` (r:0 w:1)
+	/// Proof: `XcmRateLimiter::Quotas` (`max_values`: None, `max_size`: Some(640), added: 3115, mode: `MaxEncodedLen`)
+	/// Storage: `XcmRateLimiter::Usages` (r:0 w:1)
+	/// Proof: `XcmRateLimiter::Usages` (`max_values`: None, `max_size`: Some(666), added: 3141, mode: `MaxEncodedLen`)
+	fn set_quota() -> Weight {
+		Weight::from_parts(10_000_000, 0)
+			.saturating_add(RocksDbWeight::get().writes(2_u64))
+	}
+	/// Storage: `XcmRateLimiter::Quotas` (r:1 w:0)
+	/// Proof: `XcmRateLimiter::Quotas` (`max_values`: None, `max_size`: Some(640), added: 3115, mode: `MaxEncodedLen`)
+	fn deny_execution_unlimited() -> Weight {
+		Weight::from_parts(6_000_000, 4105)
+			.saturating_add(RocksDbWeight::get().reads(1_u64))
+	}
+	/// Storage: `XcmRateLimiter::Quotas` (r:1 w:0)
+	/// Proof: `XcmRateLimiter::Quotas` (`max_values`: None, `max_size`: Some(640), added: 3115, mode: `MaxEncodedLen`)
+	/// Storage: `XcmRateLimiter::Usages` (r:1 w:1)
+	/// Proof: `XcmRateLimiter::Usages` (`max_values`: None, `max_size`: Some(666), added: 3141, mode: `MaxEncodedLen`)
+	fn deny_execution_limited() -> Weight {
+		Weight::from_parts(15_000_000, 4131)
+			.saturating_add(RocksDbWeight::get().reads(2_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+}
@@ -0,0 +1,91 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Benchmarks for the XCM remote query pallet.
+
+use super::*;
+use frame_benchmarking::v2::*;
+
+/// Registers a query with `pallet_xcm` and this pallet, as [`Pallet::query`] does, without
+/// sending it.
+fn add_query<T: Config>(responder: Location, timeout: BlockNumberFor<T>) -> QueryId {
+	let notify = Call::<T>::on_response { query_id: 0, response: Response::Null };
+	let notify = <T as Config>::RuntimeCall::from(notify);
+	let query_id =
+		pallet_xcm::Pallet::<T>::new_notify_query(responder.clone(), notify, timeout, Here);
+	Timeouts::<T>::mutate(timeout, |ids| ids.try_push(query_id).expect("below the maximum"));
+	PendingQueries::<T>::insert(query_id, PendingQuery { responder: responder.into(), timeout });
+	query_id
+}
+
+#[benchmarks(where <T as Config>::RuntimeCall: From<frame_system::Call<T>>)]
+mod benchmarks {
+	use super::*;
+
+	#[benchmark]
+	fn on_initialize(n: Linear<0, { T::MaxQueriesPerBlock::get() }>) {
+		let timeout = frame_system::Pallet::<T>::block_number() + 1u32.into();
+		let ids = (0..n)
+			.map(|_| add_query::<T>(Location::new(1, [Parachain(1000)]), timeout))
+			.collect::<Vec<_>>();
+
+		#[block]
+		{
+			Pallet::<T>::on_initialize(timeout);
+		}
+
+		for id in ids {
+			assert!(PendingQueries::<T>::get(id).is_none());
+			assert!(pallet_xcm::Pallet::<T>::query(&id).is_none());
+		}
+	}
+
+	#[benchmark]
+	fn on_response() -> Result<(), BenchmarkError> {
+		let origin =
+			T::ResponseOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+		let responder = T::ResponseOrigin::ensure_origin(origin.clone())
+			.map_err(|_| BenchmarkError::Weightless)?;
+		let timeout = frame_system::Pallet::<T>::block_number() + 1u32.into();
+		// The query shares its timeout with as many others as possible.
+		for _ in 1..T::MaxQueriesPerBlock::get() {
+			add_query::<T>(responder.clone(), timeout);
+		}
+		let query_id = add_query::<T>(responder, timeout);
+		let response = Response::DispatchResult(MaybeErrorCode::Success);
+
+		#[extrinsic_call]
+		_(origin as <T as frame_system::Config>::RuntimeOrigin, query_id, response);
+
+		assert!(PendingQueries::<T>::get(query_id).is_none());
+		Ok(())
+	}
+
+	#[benchmark]
+	fn answer() {
+		let caller: T::AccountId = whitelisted_caller();
+		let call: <T as Config>::RuntimeCall =
+			frame_system::Call::<T>::remark { remark: vec![] }.into();
+		let origin = frame_system::RawOrigin::Signed(caller);
+
+		#[block]
+		{
+			let _ = Pallet::<T>::answer(origin.into(), Box::new(call));
+		}
+	}
+
+	impl_benchmark_test_suite!(Pallet, crate::mock::new_test_ext(), crate::mock::Test);
+}
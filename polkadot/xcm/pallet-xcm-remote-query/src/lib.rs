@@ -0,0 +1,365 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! # XCM Remote Query Pallet
+//!
+//! Asks another chain a yes/no question over XCM and resumes a callback with the answer.
+//!
+//! ## Overview
+//!
+//! A query is a call on the remote chain whose dispatch result is the answer. For example, an
+//! identity chain may have a call that fails unless an account has given consent for a purpose.
+//! [`Pallet::query`] sends the call in a `Transact`, followed by `ReportTransactStatus`. The remote
+//! chain reports the dispatch result back in a `QueryResponse`, which `pallet_xcm` routes to
+//! [`Pallet::on_response`]. The result is then passed to [`Config::OnQueryResult`]:
+//!
+//! ```ignore
+//! let query_id = RemoteQuery::query(identity_chain, check_consent.encode(), None, now + 10)?;
+//! PendingOrders::insert(query_id, order);
+//!
+//! impl OnQueryResult for Marketplace {
+//! 	fn on_query_result(query_id: QueryId, result: QueryResult) {
+//! 		let order = PendingOrders::take(query_id);
+//! 		// ...
+//! 	}
+//! }
+//! ```
+//!
+//! Queries that are not answered in time resolve to [`QueryError::TimedOut`], and are removed
+//! from `pallet_xcm` as well. A query is not answered if the message fails before the `Transact`
+//! is dispatched, for example because it is rejected by the barrier or the call can not be
+//! decoded.
+//!
+//! ## Read-only calls
+//!
+//! The remote chain is expected to answer with a call that does not change its state.
+//! [`Pallet::answer`] turns a call into such a call: it dispatches the call and reverts all of its
+//! changes, keeping only the result. Chains that answer queries add this pallet to their runtime,
+//! and the querier wraps its call into `answer`. Only the calls in [`Config::AnswerableCalls`] can
+//! be answered. Reverting the changes does not make every call safe to answer: its weight is still
+//! charged and it may depend on state that should not be disclosed.
+//!
+//! The `ResponseHandler` of the XCM executor of the querying chain must be `pallet_xcm`.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+use alloc::{boxed::Box, vec, vec::Vec};
+use codec::{Decode, DecodeWithMemTracking, Encode, MaxEncodedLen};
+use frame_support::{
+	dispatch::{GetDispatchInfo, PostDispatchInfo},
+	pallet_prelude::*,
+	storage::{with_storage_layer, with_transaction},
+	traits::{Contains, EnsureOrigin},
+};
+use frame_system::pallet_prelude::*;
+use scale_info::TypeInfo;
+use sp_runtime::{traits::Dispatchable, RuntimeDebug, TransactionOutcome};
+use xcm::prelude::*;
+
+pub use pallet::*;
+pub use weights::WeightInfo;
+
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+#[cfg(test)]
+mod mock;
+#[cfg(test)]
+mod tests;
+pub mod weights;
+
+/// Why a query did not succeed.
+#[derive(Clone, Encode, Decode, DecodeWithMemTracking, Eq, PartialEq, RuntimeDebug, TypeInfo)]
+pub enum QueryError {
+	/// The remote call failed with this error.
+	Dispatch(DispatchError),
+	/// The remote call failed, but its error could not be decoded.
+	Undecodable,
+	/// The responder answered with something other than a dispatch result.
+	UnexpectedResponse,
+	/// No answer arrived before the timeout.
+	TimedOut,
+}
+
+/// The answer to a query. `Ok` if the remote call succeeded.
+pub type QueryResult = Result<(), QueryError>;
+
+impl From<Response> for QueryResult {
+	fn from(response: Response) -> Self {
+		match response {
+			Response::DispatchResult(MaybeErrorCode::Success) => Ok(()),
+			Response::DispatchResult(MaybeErrorCode::Error(error)) =>
+				Err(DispatchError::decode(&mut &error[..])
+					.map_or(QueryError::Undecodable, QueryError::Dispatch)),
+			Response::DispatchResult(MaybeErrorCode::TruncatedError(_)) =>
+				Err(QueryError::Undecodable),
+			_ => Err(QueryError::UnexpectedResponse),
+		}
+	}
+}
+
+/// Handles the result of a query.
+pub trait OnQueryResult {
+	/// The maximum weight of [`Self::on_query_result`].
+	fn weight() -> Weight;
+
+	/// Called once the query with `query_id` is answered or timed out.
+	fn on_query_result(query_id: QueryId, result: QueryResult);
+}
+
+impl OnQueryResult for () {
+	fn weight() -> Weight {
+		Weight::zero()
+	}
+
+	fn on_query_result(_: QueryId, _: QueryResult) {}
+}
+
+/// A query that awaits its answer.
+#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct PendingQuery<BlockNumber> {
+	/// The chain that answers the query.
+	pub responder: VersionedLocation,
+	/// The block at whose start the query times out.
+	pub timeout: BlockNumber,
+}
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+
+	#[pallet::pallet]
+	pub struct Pallet<T>(_);
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config + pallet_xcm::Config {
+		/// The overarching event type.
+		#[allow(deprecated)]
+		type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+
+		/// The overarching call type.
+		type RuntimeCall: Parameter
+			+ Dispatchable<
+				RuntimeOrigin = <Self as frame_system::Config>::RuntimeOrigin,
+				PostInfo = PostDispatchInfo,
+			> + GetDispatchInfo
+			+ From<Call<Self>>
+			+ Into<<Self as pallet_xcm::Config>::RuntimeCall>;
+
+		/// The origin of responses, which is `pallet_xcm::EnsureResponse`.
+		type ResponseOrigin: EnsureOrigin<
+			<Self as frame_system::Config>::RuntimeOrigin,
+			Success = Location,
+		>;
+
+		/// Handles the results of queries.
+		type OnQueryResult: OnQueryResult;
+
+		/// The calls that can be dispatched with [`Pallet::answer`].
+		type AnswerableCalls: Contains<<Self as Config>::RuntimeCall>;
+
+		/// The maximum number of queries that time out in the same block.
+		#[pallet::constant]
+		type MaxQueriesPerBlock: Get<u32>;
+
+		/// Weight information for the operations of this pallet.
+		type WeightInfo: WeightInfo;
+	}
+
+	/// The queries that await their answer.
+	#[pallet::storage]
+	pub type PendingQueries<T: Config> =
+		StorageMap<_, Twox64Concat, QueryId, PendingQuery<BlockNumberFor<T>>>;
+
+	/// The queries that time out at the start of a block.
+	#[pallet::storage]
+	pub type Timeouts<T: Config> = StorageMap<
+		_,
+		Twox64Concat,
+		BlockNumberFor<T>,
+		BoundedVec<QueryId, T::MaxQueriesPerBlock>,
+		ValueQuery,
+	>;
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// A query was sent.
+		QuerySent { query_id: QueryId, responder: Location },
+		/// A query was answered or timed out.
+		QueryResolved { query_id: QueryId, result: QueryResult },
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// The timeout is not in the future.
+		BadTimeout,
+		/// Too many queries time out in the same block.
+		TooManyQueries,
+		/// The location of this chain can not be expressed from the responder.
+		LocationNotInvertible,
+		/// The query could not be sent.
+		SendFailure,
+		/// The query is not pending.
+		UnknownQuery,
+		/// The call is not in `AnswerableCalls`.
+		CallNotAnswerable,
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		fn on_initialize(now: BlockNumberFor<T>) -> Weight {
+			let expired = Timeouts::<T>::take(now);
+			for &query_id in &expired {
+				PendingQueries::<T>::remove(query_id);
+				pallet_xcm::Pallet::<T>::remove_pending_query(query_id);
+				Self::resolve(query_id, Err(QueryError::TimedOut));
+			}
+			let count = expired.len() as u32;
+			<T as Config>::WeightInfo::on_initialize(count)
+				.saturating_add(T::OnQueryResult::weight().saturating_mul(count.into()))
+		}
+
+		fn integrity_test() {
+			assert!(T::MaxQueriesPerBlock::get() > 0, "`MaxQueriesPerBlock` must not be zero");
+		}
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Handle the answer to a query.
+		///
+		/// Called by `pallet_xcm` when the `QueryResponse` of the responder arrives.
+		#[pallet::call_index(0)]
+		#[pallet::weight(
+			<T as Config>::WeightInfo::on_response().saturating_add(T::OnQueryResult::weight())
+		)]
+		pub fn on_response(
+			origin: OriginFor<T>,
+			query_id: QueryId,
+			response: Response,
+		) -> DispatchResult {
+			T::ResponseOrigin::ensure_origin(origin)?;
+			let query = PendingQueries::<T>::take(query_id).ok_or(Error::<T>::UnknownQuery)?;
+			Timeouts::<T>::mutate(query.timeout, |ids| ids.retain(|id| *id != query_id));
+			Self::resolve(query_id, response.into());
+			Ok(())
+		}
+
+		/// Dispatch `call` and revert all of its changes.
+		///
+		/// Succeeds if and only if `call` succeeds, so that it can answer a query without
+		/// changing state. `call` must be in `AnswerableCalls`.
+		#[pallet::call_index(1)]
+		#[pallet::weight({
+			let info = call.get_dispatch_info();
+			(<T as Config>::WeightInfo::answer().saturating_add(info.call_weight), info.class)
+		})]
+		pub fn answer(
+			origin: OriginFor<T>,
+			call: Box<<T as Config>::RuntimeCall>,
+		) -> DispatchResult {
+			ensure!(T::AnswerableCalls::contains(&call), Error::<T>::CallNotAnswerable);
+			with_transaction(|| {
+				let result = call.dispatch(origin).map(|_| ()).map_err(|e| e.error);
+				TransactionOutcome::Rollback(Ok::<_, DispatchError>(result))
+			})?
+		}
+	}
+}
+
+impl<T: Config> Pallet<T> {
+	/// Send a query to `responder`.
+	///
+	/// `call` is the encoded call on the responder, which is dispatched with the sovereign account
+	/// of this chain as origin. Its execution is paid with `fees`, which are withdrawn from the
+	/// sovereign account, or unpaid if `fees` is `None`. The query times out at the start of block
+	/// `timeout`.
+	///
+	/// Returns the id the result will be reported with to [`Config::OnQueryResult`].
+	pub fn query(
+		responder: Location,
+		call: Vec<u8>,
+		fees: Option<Asset>,
+		timeout: BlockNumberFor<T>,
+	) -> Result<QueryId, DispatchError> {
+		let now = frame_system::Pallet::<T>::block_number();
+		ensure!(timeout > now, Error::<T>::BadTimeout);
+		let destination = <T as pallet_xcm::Config>::UniversalLocation::get()
+			.invert_target(&responder)
+			.map_err(|()| Error::<T>::LocationNotInvertible)?;
+
+		with_storage_layer(|| {
+			let notify = Call::<T>::on_response { query_id: 0, response: Response::Null };
+			let notify = <T as Config>::RuntimeCall::from(notify);
+			let max_weight = notify.get_dispatch_info().call_weight;
+			let query_id =
+				pallet_xcm::Pallet::<T>::new_notify_query(responder.clone(), notify, timeout, Here);
+
+			Timeouts::<T>::try_mutate(timeout, |ids| ids.try_push(query_id))
+				.map_err(|_| Error::<T>::TooManyQueries)?;
+			PendingQueries::<T>::insert(
+				query_id,
+				PendingQuery { responder: responder.clone().into(), timeout },
+			);
+
+			let message = Self::query_message(call, fees, destination, query_id, max_weight);
+			pallet_xcm::Pallet::<T>::send_xcm(Here, responder.clone(), message)
+				.map_err(|_| Error::<T>::SendFailure)?;
+
+			Self::deposit_event(Event::QuerySent { query_id, responder });
+			Ok(query_id)
+		})
+	}
+
+	/// The message that dispatches `call` and reports its result to `destination`.
+	fn query_message(
+		call: Vec<u8>,
+		fees: Option<Asset>,
+		destination: Location,
+		query_id: QueryId,
+		max_weight: Weight,
+	) -> Xcm<()> {
+		let mut message = match &fees {
+			Some(fees) => vec![
+				WithdrawAsset(fees.clone().into()),
+				BuyExecution { fees: fees.clone(), weight_limit: Unlimited },
+			],
+			None => vec![UnpaidExecution { weight_limit: Unlimited, check_origin: None }],
+		};
+		message.push(Transact {
+			origin_kind: OriginKind::SovereignAccount,
+			fallback_max_weight: None,
+			call: call.into(),
+		});
+		message.push(ReportTransactStatus(QueryResponseInfo {
+			destination: destination.clone(),
+			query_id,
+			max_weight,
+		}));
+		if fees.is_some() {
+			message.push(RefundSurplus);
+			message.push(DepositAsset { assets: Wild(AllCounted(1)), beneficiary: destination });
+		}
+		Xcm(message)
+	}
+
+	fn resolve(query_id: QueryId, result: QueryResult) {
+		T::OnQueryResult::on_query_result(query_id, result.clone());
+		Self::deposit_event(Event::QueryResolved { query_id, result });
+	}
+}
@@ -0,0 +1,280 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Tests for the XCM remote query pallet.
+
+use crate::{
+	mock::*, Error, Event, PendingQueries, PendingQuery, QueryError, QueryResult, Timeouts,
+};
+use codec::Encode;
+use frame_support::{assert_noop, assert_ok, traits::Hooks};
+use pallet_xcm::QueryStatus;
+use sp_runtime::{AccountId32, DispatchError};
+use xcm::prelude::*;
+use xcm_executor::XcmExecutor;
+
+const CALL: [u8; 3] = [1, 2, 3];
+
+fn responder() -> Location {
+	Location::new(1, [Parachain(2000)])
+}
+
+/// This chain, as seen from the responder.
+fn querier() -> Location {
+	Location::new(1, [Parachain(1000)])
+}
+
+/// The `QueryResponse` the responder sends back, executed on this chain.
+fn respond(origin: Location, query_id: QueryId, response: Response) -> Outcome {
+	let message = Xcm::<RuntimeCall>(vec![QueryResponse {
+		query_id,
+		response,
+		max_weight: Weight::from_parts(1_000_000_000, 1_000_000),
+		querier: Some(Here.into()),
+	}]);
+	let mut id = message.using_encoded(sp_io::hashing::blake2_256);
+	XcmExecutor::<XcmConfig>::prepare_and_execute(
+		origin,
+		message,
+		&mut id,
+		Weight::MAX,
+		Weight::zero(),
+	)
+}
+
+fn last_event() -> RuntimeEvent {
+	System::events().pop().expect("an event was deposited").event
+}
+
+#[test]
+fn query_sends_the_call() {
+	new_test_ext().execute_with(|| {
+		let query_id = RemoteQuery::query(responder(), CALL.to_vec(), None, 5).unwrap();
+
+		let (destination, message) = sent_xcm().pop().unwrap();
+		assert_eq!(destination, responder());
+		let Some(ReportTransactStatus(info)) = message.0.get(2) else {
+			panic!("unexpected message: {message:?}")
+		};
+		assert_eq!(
+			message,
+			Xcm(vec![
+				UnpaidExecution { weight_limit: Unlimited, check_origin: None },
+				Transact {
+					origin_kind: OriginKind::SovereignAccount,
+					fallback_max_weight: None,
+					call: CALL.to_vec().into(),
+				},
+				ReportTransactStatus(QueryResponseInfo {
+					destination: querier(),
+					query_id,
+					max_weight: info.max_weight,
+				}),
+			])
+		);
+
+		assert_eq!(
+			PendingQueries::<Test>::get(query_id),
+			Some(PendingQuery { responder: responder().into(), timeout: 5 })
+		);
+		assert_eq!(Timeouts::<Test>::get(5).into_inner(), vec![query_id]);
+		assert!(matches!(XcmPallet::query(&query_id), Some(QueryStatus::Pending { .. })));
+		assert_eq!(last_event(), Event::QuerySent { query_id, responder: responder() }.into());
+	});
+}
+
+#[test]
+fn paid_query_refunds_the_surplus() {
+	new_test_ext().execute_with(|| {
+		let fees: Asset = (Parent, 1_000).into();
+		let query_id = RemoteQuery::query(responder(), CALL.to_vec(), Some(fees.clone()), 5);
+		assert_ok!(query_id);
+
+		let (_, message) = sent_xcm().pop().unwrap();
+		assert_eq!(message.0.len(), 6);
+		assert_eq!(message.0[0], WithdrawAsset(fees.clone().into()));
+		assert_eq!(message.0[1], BuyExecution { fees, weight_limit: Unlimited });
+		assert_eq!(message.0[4], RefundSurplus);
+		assert_eq!(
+			message.0[5],
+			DepositAsset { assets: Wild(AllCounted(1)), beneficiary: querier() }
+		);
+	});
+}
+
+#[test]
+fn query_checks_the_timeout() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			RemoteQuery::query(responder(), CALL.to_vec(), None, 1),
+			Error::<Test>::BadTimeout
+		);
+		assert!(sent_xcm().is_empty());
+	});
+}
+
+#[test]
+fn queries_per_block_are_bounded() {
+	new_test_ext().execute_with(|| {
+		for _ in 0..2 {
+			assert_ok!(RemoteQuery::query(responder(), CALL.to_vec(), None, 5));
+		}
+		assert_noop!(
+			RemoteQuery::query(responder(), CALL.to_vec(), None, 5),
+			Error::<Test>::TooManyQueries
+		);
+
+		// Nothing of the rejected query is left behind, not even in `pallet_xcm`.
+		assert_eq!(sent_xcm().len(), 2);
+		assert_eq!(PendingQueries::<Test>::iter().count(), 2);
+		assert!(XcmPallet::query(&2).is_none());
+
+		// Other blocks still have room.
+		assert_ok!(RemoteQuery::query(responder(), CALL.to_vec(), None, 6));
+	});
+}
+
+#[test]
+fn successful_response_resolves_the_query() {
+	new_test_ext().execute_with(|| {
+		let query_id = RemoteQuery::query(responder(), CALL.to_vec(), None, 5).unwrap();
+
+		let response = Response::DispatchResult(MaybeErrorCode::Success);
+		assert!(matches!(respond(responder(), query_id, response), Outcome::Complete { .. }));
+
+		assert_eq!(query_results(), vec![(query_id, Ok(()))]);
+		assert!(System::events()
+			.iter()
+			.any(|r| r.event == Event::QueryResolved { query_id, result: Ok(()) }.into()));
+		assert!(PendingQueries::<Test>::get(query_id).is_none());
+		assert!(Timeouts::<Test>::get(5).is_empty());
+		assert!(XcmPallet::query(&query_id).is_none());
+
+		// The query does not time out anymore.
+		RemoteQuery::on_initialize(5);
+		assert_eq!(query_results().len(), 1);
+	});
+}
+
+#[test]
+fn failed_call_resolves_with_its_error() {
+	new_test_ext().execute_with(|| {
+		let query_id = RemoteQuery::query(responder(), CALL.to_vec(), None, 5).unwrap();
+
+		let error = MaybeErrorCode::from(DispatchError::BadOrigin.encode());
+		respond(responder(), query_id, Response::DispatchResult(error));
+
+		let result: QueryResult = Err(QueryError::Dispatch(DispatchError::BadOrigin));
+		assert_eq!(query_results(), vec![(query_id, result)]);
+	});
+}
+
+#[test]
+fn responses_are_converted() {
+	let error = |bytes: Vec<u8>| Response::DispatchResult(MaybeErrorCode::from(bytes));
+	assert_eq!(QueryResult::from(error(vec![0xff])), Err(QueryError::Undecodable));
+	assert_eq!(QueryResult::from(error(vec![0; 200])), Err(QueryError::Undecodable));
+	assert_eq!(QueryResult::from(Response::Null), Err(QueryError::UnexpectedResponse));
+}
+
+#[test]
+fn response_of_another_location_is_ignored() {
+	new_test_ext().execute_with(|| {
+		let query_id = RemoteQuery::query(responder(), CALL.to_vec(), None, 5).unwrap();
+
+		let response = Response::DispatchResult(MaybeErrorCode::Success);
+		respond(Location::new(1, [Parachain(3000)]), query_id, response);
+
+		assert!(query_results().is_empty());
+		assert!(PendingQueries::<Test>::get(query_id).is_some());
+	});
+}
+
+#[test]
+fn unanswered_query_times_out() {
+	new_test_ext().execute_with(|| {
+		let query_id = RemoteQuery::query(responder(), CALL.to_vec(), None, 5).unwrap();
+
+		RemoteQuery::on_initialize(4);
+		assert!(query_results().is_empty());
+
+		RemoteQuery::on_initialize(5);
+		assert_eq!(query_results(), vec![(query_id, Err(QueryError::TimedOut))]);
+		assert!(PendingQueries::<Test>::get(query_id).is_none());
+		assert!(Timeouts::<Test>::get(5).is_empty());
+		assert!(XcmPallet::query(&query_id).is_none());
+
+		// A late response is not taken for the answer.
+		respond(responder(), query_id, Response::DispatchResult(MaybeErrorCode::Success));
+		assert_eq!(query_results().len(), 1);
+	});
+}
+
+#[test]
+fn on_response_checks_origin_and_query() {
+	new_test_ext().execute_with(|| {
+		let query_id = RemoteQuery::query(responder(), CALL.to_vec(), None, 5).unwrap();
+		let response = Response::DispatchResult(MaybeErrorCode::Success);
+
+		assert_noop!(
+			RemoteQuery::on_response(RuntimeOrigin::root(), query_id, response.clone()),
+			DispatchError::BadOrigin
+		);
+		let origin = pallet_xcm::Origin::Response(responder());
+		assert_noop!(
+			RemoteQuery::on_response(origin.into(), query_id + 1, response),
+			Error::<Test>::UnknownQuery
+		);
+	});
+}
+
+#[test]
+fn answer_reverts_the_call() {
+	new_test_ext().execute_with(|| {
+		let call = frame_system::Call::set_storage { items: vec![(b"key".to_vec(), vec![1])] };
+		assert_ok!(RemoteQuery::answer(RuntimeOrigin::root(), Box::new(call.into())));
+		assert_eq!(sp_io::storage::get(b"key"), None);
+
+		let call = frame_system::Call::remark_with_event { remark: vec![1] };
+		let origin = RuntimeOrigin::signed(AccountId32::new([1; 32]));
+		assert_ok!(RemoteQuery::answer(origin, Box::new(call.into())));
+		assert!(System::events().is_empty());
+	});
+}
+
+#[test]
+fn answer_fails_with_the_call() {
+	new_test_ext().execute_with(|| {
+		let call = frame_system::Call::set_heap_pages { pages: 64 };
+		let origin = RuntimeOrigin::signed(AccountId32::new([1; 32]));
+		assert_noop!(RemoteQuery::answer(origin, Box::new(call.into())), DispatchError::BadOrigin);
+	});
+}
+
+#[test]
+fn answer_only_dispatches_answerable_calls() {
+	new_test_ext().execute_with(|| {
+		let call = pallet_balances::Call::transfer_all {
+			dest: AccountId32::new([2; 32]),
+			keep_alive: false,
+		};
+		let origin = RuntimeOrigin::signed(AccountId32::new([1; 32]));
+		assert_noop!(
+			RemoteQuery::answer(origin, Box::new(call.into())),
+			Error::<Test>::CallNotAnswerable
+		);
+	});
+}
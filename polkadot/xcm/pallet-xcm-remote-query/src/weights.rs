@@ -0,0 +1,104 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Weights for `pallet_xcm_remote_query`.
+//!
+//! Not generated yet. The storage accesses and the proof sizes of this pallet's own items follow
+//! from the benchmarks and their `MaxEncodedLen` bounds, with at most 16 queries timing out per
+//! block. `PolkadotXcm::Queries` has no such bound, so its share of the proof is a guess of 2600
+//! bytes per query, and all reference times are rough guesses. Regenerate with
+//! `frame-omni-bencher v1 benchmark pallet --pallet pallet_xcm_remote_query` before relying on
+//! them.
+
+#![cfg_attr(rustfmt, rustfmt_skip)]
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+#![allow(missing_docs)]
+
+use frame_support::{traits::Get, weights::{Weight, constants::RocksDbWeight}};
+use core::marker::PhantomData;
+
+/// Weight functions needed for `pallet_xcm_remote_query`.
+pub trait WeightInfo {
+	fn on_initialize(n: u32, ) -> Weight;
+	fn on_response() -> Weight;
+	fn answer() -> Weight;
+}
+
+/// Weights for `pallet_xcm_remote_query` using the Substrate node and recommended hardware.
+pub struct SubstrateWeight<T>(PhantomData<T>);
+impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+	/// Storage: `RemoteQuery::Timeouts` (r:1 w:1)
+	/// Proof: `RemoteQuery::Timeouts` (`max_values`: None, `max_size`: Some(141), added: 2616, mode: `MaxEncodedLen`)
+	/// Storage: `PolkadotXcm::Queries` (r:n w:n)
+	/// Proof: `PolkadotXcm::Queries` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `RemoteQuery::PendingQueries` (r:0 w:n)
+	/// Proof: `RemoteQuery::PendingQueries` (`max_values`: None, `max_size`: Some(622), added: 3097, mode: `MaxEncodedLen`)
+	/// The range of component `n` is `[0, 16]`.
+	fn on_initialize(n: u32, ) -> Weight {
+		Weight::from_parts(5_000_000, 3606)
+			.saturating_add(Weight::from_parts(12_000_000, 0).saturating_mul(n.into()))
+			.saturating_add(T::DbWeight::get().reads(1_u64))
+			.saturating_add(T::DbWeight::get().reads((1_u64).saturating_mul(n.into())))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+			.saturating_add(T::DbWeight::get().writes((2_u64).saturating_mul(n.into())))
+			.saturating_add(Weight::from_parts(0, 2600).saturating_mul(n.into()))
+	}
+	/// Storage: `RemoteQuery::PendingQueries` (r:1 w:1)
+	/// Proof: `RemoteQuery::PendingQueries` (`max_values`: None, `max_size`: Some(622), added: 3097, mode: `MaxEncodedLen`)
+	/// Storage: `RemoteQuery::Timeouts` (r:1 w:1)
+	/// Proof: `RemoteQuery::Timeouts` (`max_values`: None, `max_size`: Some(141), added: 2616, mode: `MaxEncodedLen`)
+	fn on_response() -> Weight {
+		Weight::from_parts(15_000_000, 4087)
+			.saturating_add(T::DbWeight::get().reads(2_u64))
+			.saturating_add(T::DbWeight::get().writes(2_u64))
+	}
+	fn answer() -> Weight {
+		Weight::from_parts(5_000_000, 0)
+	}
+}
+
+// For backwards compatibility and tests.
+impl WeightInfo for () {
+	/// Storage: `RemoteQuery::Timeouts` (r:1 w:1)
+	/// Proof: `RemoteQuery::Timeouts` (`max_values`: None, `max_size`: Some(141), added: 2616, mode: `MaxEncodedLen`)
+	/// Storage: `PolkadotXcm::Queries` (r:n w:n)
+	/// Proof: `PolkadotXcm::Queries` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `RemoteQuery::PendingQueries` (r:0 w:n)
+	/// Proof: `RemoteQuery::PendingQueries` (`max_values`: None, `max_size`: Some(622), added: 3097, mode: `MaxEncodedLen`)
+	/// The range of component `n` is `[0, 16]`.
+	fn on_initialize(n: u32, ) -> Weight {
+		Weight::from_parts(5_000_000, 3606)
+			.saturating_add(Weight::from_parts(12_000_000, 0).saturating_mul(n.into()))
+			.saturating_add(RocksDbWeight::get().reads(1_u64))
+			.saturating_add(RocksDbWeight::get().reads((1_u64).saturating_mul(n.into())))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+			.saturating_add(RocksDbWeight::get().writes((2_u64).saturating_mul(n.into())))
+			.saturating_add(Weight::from_parts(0, 2600).saturating_mul(n.into()))
+	}
+	/// Storage: `RemoteQuery::PendingQueries` (r:1 w:1)
+	/// Proof: `RemoteQuery::PendingQueries` (`max_values`: None, `max_size`: Some(622), added: 3097, mode: `MaxEncodedLen`)
+	/// Storage: `RemoteQuery::Timeouts` (r:1 w:1)
+	/// Proof: `RemoteQuery::Timeouts` (`max_values`: None, `max_size`: Some(141), added: 2616, mode: `MaxEncodedLen`)
+	fn on_response() -> Weight {
+		Weight::from_parts(15_000_000, 4087)
+			.saturating_add(RocksDbWeight::get().reads(2_u64))
+			.saturating_add(RocksDbWeight::get().writes(2_u64))
+	}
+	fn answer() -> Weight {
+		Weight::from_parts(5_000_000, 0)
+	}
+}
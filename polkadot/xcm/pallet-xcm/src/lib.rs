@@ -77,6 +77,7 @@ use xcm_runtime_apis::{
 	dry_run::{CallDryRunEffects, Error as XcmDryRunApiError, XcmDryRunEffects},
 	fees::Error as XcmPaymentApiError,
 	trusted_query::Error as TrustedQueryApiError,
+	weight_breakdown::{InstructionWeight, WeightSource},
 };
 
 mod errors;
@@ -736,6 +737,9 @@ pub mod pallet {
 		/// instruction that caused the error.
 		#[codec(index = 28)]
 		LocalExecutionIncompleteWithError { index: InstructionIndex, error: ExecutionError },
+		/// Only fungible assets can be held by a consumer of a remote lock.
+		#[codec(index = 29)]
+		NotFungible,
 	}
 
 	impl<T: Config> From<SendError> for Error<T> {
@@ -1981,6 +1985,15 @@ impl<T: Config> Pallet<T> {
 		Queries::<T>::get(query_id)
 	}
 
+	/// Removes the query with `query_id` if it still awaits its response, e.g. once it timed out.
+	///
+	/// Answered queries and version notifiers are kept.
+	pub fn remove_pending_query(query_id: QueryId) {
+		if let Some(QueryStatus::Pending { .. }) = Queries::<T>::get(query_id) {
+			Queries::<T>::remove(query_id);
+		}
+	}
+
 	/// The existing asset traps.
 	///
 	/// Key is the blake2 256 hash of (origin, versioned `Assets`) pair.
@@ -3228,6 +3241,67 @@ impl<T: Config> Pallet<T> {
 		})
 	}
 
+	/// Given a message, returns the weight of every instruction as computed by `W`.
+	///
+	/// `W` has to be the `XcmWeightInfo` the runtime passes to `WeightInfoBounds` for its
+	/// `Weigher`, so that the weights of all instructions add up to the result of
+	/// [`Self::query_xcm_weight`]. Like `WeightInfoBounds`, the weight of the call of a `Transact`
+	/// and the weight of the program of a `SetErrorHandler` or `SetAppendix` is added on top of
+	/// the weight of the instruction.
+	pub fn query_xcm_weight_breakdown<W: XcmWeightInfo<<T as Config>::RuntimeCall>>(
+		message: VersionedXcm<()>,
+	) -> Result<Vec<InstructionWeight>, XcmPaymentApiError>
+	where
+		Instruction<<T as Config>::RuntimeCall>: xcm::latest::GetWeight<W>,
+	{
+		let message = Xcm::<()>::try_from(message.clone()).map_err(|e| {
+			tracing::debug!(target: "xcm::pallet_xcm::query_xcm_weight_breakdown", ?e, ?message, "Failed to convert versioned message");
+			XcmPaymentApiError::VersionedConversionFailed
+		})?;
+
+		Self::instructions_weight::<W>(&mut message.into())
+	}
+
+	fn instructions_weight<W: XcmWeightInfo<<T as Config>::RuntimeCall>>(
+		message: &mut Xcm<<T as Config>::RuntimeCall>,
+	) -> Result<Vec<InstructionWeight>, XcmPaymentApiError>
+	where
+		Instruction<<T as Config>::RuntimeCall>: xcm::latest::GetWeight<W>,
+	{
+		use xcm::latest::GetWeight;
+
+		message
+			.0
+			.iter_mut()
+			.map(|instruction| {
+				let weight = instruction.weight();
+				let (additional_weight, nested) = match instruction {
+					Transact { call, .. } => {
+						let call = call.ensure_decoded().map_err(|error| {
+							tracing::debug!(target: "xcm::pallet_xcm::query_xcm_weight_breakdown", ?error, "Failed to decode the call of a `Transact`");
+							XcmPaymentApiError::WeightNotComputable
+						})?;
+						(call.get_dispatch_info().call_weight, Vec::new())
+					},
+					SetErrorHandler(xcm) | SetAppendix(xcm) => {
+						let nested = Self::instructions_weight::<W>(xcm)?;
+						let weight = nested
+							.iter()
+							.fold(Weight::zero(), |acc, i| acc.saturating_add(i.total()));
+						(weight, nested)
+					},
+					_ => (Weight::zero(), Vec::new()),
+				};
+				Ok(InstructionWeight {
+					weight,
+					additional_weight,
+					source: WeightSource::of(weight),
+					nested,
+				})
+			})
+			.collect()
+	}
+
 	/// Computes the weight cost using the provided `WeightTrader`.
 	/// This function is supposed to be used ONLY in `XcmPaymentApi::query_weight_to_asset_fee`.
 	///
@@ -3855,6 +3929,58 @@ impl<T: Config> xcm_executor::traits::AssetLock for Pallet<T> {
 	}
 }
 
+impl<T: Config> Pallet<T> {
+	/// Sets the amount of the remote lock that `owner` has on `locker` which `consumer` holds to
+	/// the amount of `asset`.
+	///
+	/// The lock can not be reduced below the largest amount held by its consumers, so the owner
+	/// can not unlock the asset while it is in use on this chain. Like balance locks, the amounts
+	/// held by different consumers overlap, so a consumer that holds the lock for several purposes
+	/// has to hold their sum. Holding again replaces the amount the consumer held before. Returns
+	/// the location of the owner of the lock.
+	pub fn hold_remote_lock(
+		owner: &T::AccountId,
+		locker: &Location,
+		asset: &Asset,
+		consumer: T::RemoteLockConsumerIdentifier,
+	) -> Result<Location, Error<T>> {
+		let Fungible(amount) = asset.fun else { return Err(Error::<T>::NotFungible) };
+		let key = (XCM_VERSION, owner.clone(), VersionedAssetId::from(asset.id.clone()));
+		RemoteLockedFungibles::<T>::try_mutate(&key, |maybe_record| {
+			let record = maybe_record.as_mut().ok_or(Error::<T>::LockNotFound)?;
+			ensure!(record.locker == locker.clone().into(), Error::<T>::LockNotFound);
+			ensure!(record.amount >= amount, Error::<T>::LowBalance);
+			match record.consumers.iter_mut().find(|(c, _)| *c == consumer) {
+				Some((_, held)) => *held = amount,
+				None => record
+					.consumers
+					.try_push((consumer, amount))
+					.map_err(|_| Error::<T>::TooManyLocks)?,
+			}
+			Location::try_from(record.owner.clone()).map_err(|_| Error::<T>::BadVersion)
+		})
+	}
+
+	/// Releases what `consumer` holds of the remote lock of `asset_id` that `owner` has.
+	pub fn release_remote_lock(
+		owner: &T::AccountId,
+		asset_id: &AssetId,
+		consumer: T::RemoteLockConsumerIdentifier,
+	) -> Result<(), Error<T>> {
+		let key = (XCM_VERSION, owner.clone(), VersionedAssetId::from(asset_id.clone()));
+		RemoteLockedFungibles::<T>::try_mutate(&key, |maybe_record| {
+			let record = maybe_record.as_mut().ok_or(Error::<T>::LockNotFound)?;
+			let index = record
+				.consumers
+				.iter()
+				.position(|(c, _)| *c == consumer)
+				.ok_or(Error::<T>::LockNotFound)?;
+			record.consumers.remove(index);
+			Ok(())
+		})
+	}
+}
+
 impl<T: Config> WrapVersion for Pallet<T> {
 	fn wrap_version<RuntimeCall: Decode + GetDispatchInfo>(
 		dest: &Location,
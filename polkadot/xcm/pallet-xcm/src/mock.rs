@@ -580,8 +580,8 @@ impl pallet_xcm::Config for Test {
 	type Currency = Balances;
 	type CurrencyMatcher = IsConcrete<RelayLocation>;
 	type MaxLockers = frame_support::traits::ConstU32<8>;
-	type MaxRemoteLockConsumers = frame_support::traits::ConstU32<0>;
-	type RemoteLockConsumerIdentifier = ();
+	type MaxRemoteLockConsumers = frame_support::traits::ConstU32<2>;
+	type RemoteLockConsumerIdentifier = u8;
 	type WeightInfo = TestWeightInfo;
 	type AuthorizedAliasConsideration =
 		HoldConsideration<AccountId, Balances, AuthorizeAliasHoldReason, ConvertDeposit>;
@@ -195,6 +195,24 @@ fn report_outcome_works() {
 	});
 }
 
+#[test]
+fn remove_pending_query_works() {
+	new_test_ext_with_balances(vec![]).execute_with(|| {
+		let responder = Location::new(1, [Parachain(OTHER_PARA_ID)]);
+		let pending = <XcmPallet as QueryHandler>::new_query(responder.clone(), 10, Here);
+		let answered = <XcmPallet as QueryHandler>::new_query(responder, 10, Here);
+		let response = Response::ExecutionResult(None);
+		Queries::<Test>::insert(answered, QueryStatus::Ready { response: response.into(), at: 1 });
+
+		XcmPallet::remove_pending_query(pending);
+		XcmPallet::remove_pending_query(answered);
+		XcmPallet::remove_pending_query(answered + 1);
+
+		assert!(XcmPallet::query(&pending).is_none());
+		assert!(XcmPallet::query(&answered).is_some());
+	});
+}
+
 #[test]
 fn custom_querier_works() {
 	let balances = vec![
@@ -1785,3 +1803,75 @@ fn query_weight_to_asset_fee_noop() {
 		.unwrap());
 	})
 }
+
+#[test]
+fn remote_lock_holds_keep_the_lock_from_being_reduced() {
+	use xcm_executor::traits::{AssetLock, Enact, LockError};
+
+	new_test_ext_with_balances(vec![]).execute_with(|| {
+		let locker = Location::new(1, [Parachain(OTHER_PARA_ID)]);
+		let owner: Location = AccountId32 { network: None, id: ALICE.into() }.into();
+		let asset = |amount: u128| -> Asset { (Parent, amount).into() };
+		let key = (XCM_VERSION, ALICE, VersionedAssetId::from(AssetId(Location::parent())));
+
+		assert_eq!(
+			XcmPallet::hold_remote_lock(&ALICE, &locker, &asset(10), 0),
+			Err(Error::<Test>::LockNotFound)
+		);
+		RemoteLockedFungibles::<Test>::insert(
+			&key,
+			RemoteLockedFungibleRecord {
+				amount: 100,
+				owner: owner.clone().into(),
+				locker: locker.clone().into(),
+				consumers: BoundedVec::default(),
+			},
+		);
+		assert_eq!(
+			XcmPallet::hold_remote_lock(&ALICE, &Location::parent(), &asset(10), 0),
+			Err(Error::<Test>::LockNotFound)
+		);
+		assert_eq!(
+			XcmPallet::hold_remote_lock(&ALICE, &locker, &asset(101), 0),
+			Err(Error::<Test>::LowBalance)
+		);
+		let nft: Asset = (Parent, Index(1)).into();
+		assert_eq!(
+			XcmPallet::hold_remote_lock(&ALICE, &locker, &nft, 0),
+			Err(Error::<Test>::NotFungible)
+		);
+
+		assert_eq!(XcmPallet::hold_remote_lock(&ALICE, &locker, &asset(60), 0), Ok(owner.clone()));
+		assert_eq!(XcmPallet::hold_remote_lock(&ALICE, &locker, &asset(30), 1), Ok(owner.clone()));
+		assert_eq!(
+			XcmPallet::hold_remote_lock(&ALICE, &locker, &asset(30), 2),
+			Err(Error::<Test>::TooManyLocks)
+		);
+		let reduce = |amount| {
+			XcmPallet::prepare_reduce_unlockable(locker.clone(), asset(amount), owner.clone())
+		};
+		// The holds overlap, the lock can be reduced down to the largest one.
+		assert!(matches!(reduce(41), Err(LockError::InUse)));
+		assert_ok!(reduce(40));
+
+		// Holding again replaces the amount held before.
+		assert_ok!(XcmPallet::hold_remote_lock(&ALICE, &locker, &asset(20), 0));
+		assert_eq!(
+			RemoteLockedFungibles::<Test>::get(&key).unwrap().consumers.into_inner(),
+			vec![(0, 20), (1, 30)]
+		);
+		assert_ok!(reduce(70));
+		assert!(matches!(reduce(71), Err(LockError::InUse)));
+
+		assert_ok!(XcmPallet::release_remote_lock(&ALICE, &AssetId(Location::parent()), 1));
+		assert_eq!(
+			XcmPallet::release_remote_lock(&ALICE, &AssetId(Location::parent()), 1),
+			Err(Error::<Test>::LockNotFound)
+		);
+		assert_ok!(reduce(80).and_then(Enact::enact));
+		assert_eq!(RemoteLockedFungibles::<Test>::get(&key).unwrap().amount, 20);
+
+		assert_ok!(XcmPallet::release_remote_lock(&ALICE, &AssetId(Location::parent()), 0));
+		assert_ok!(reduce(20));
+	});
+}
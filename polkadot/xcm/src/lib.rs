@@ -46,6 +46,8 @@ pub mod latest {
 mod double_encoded;
 pub use double_encoded::DoubleEncoded;
 
+pub mod text;
+
 mod utils;
 
 #[cfg(test)]
@@ -0,0 +1,623 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! A human readable text format for XCM programs, for use in logs and test fixtures.
+//!
+//! The format is derived from the [`TypeInfo`] of a type. It therefore works for all XCM versions
+//! and for every type that is part of a message, like locations and assets. Values look like Rust
+//! expressions without type names:
+//!
+//! ```text
+//! [
+//!   WithdrawAsset([{ id: { parents: 1, interior: Here }, fun: Fungible(100) }]),
+//!   ClearOrigin,
+//!   DepositAsset { assets: Wild(AllCounted(1)), beneficiary: { parents: 0, interior: Here } },
+//!   SetTopic(0x0101010101010101010101010101010101010101010101010101010101010101),
+//! ]
+//! ```
+//!
+//! - Enums are written as `Variant`, `Variant(a, b)` or `Variant { field: a }`.
+//! - Structs are written as `{ field: a }`. Structs with a single unnamed field, like `Xcm` or
+//!   `BoundedVec`, are written as their inner value.
+//! - Sequences and arrays are written as `[a, b]`, byte sequences as `0x` prefixed hex.
+//! - Numbers are decimal and compact numbers are written like any other number.
+//!
+//! Use [`to_text`] or [`Text`] to print a value and [`from_text`] to parse it back.
+
+use crate::MAX_XCM_DECODE_DEPTH;
+use alloc::{
+	format,
+	string::{String, ToString},
+	vec::Vec,
+};
+use codec::{Compact, Decode, DecodeLimit, Encode};
+use core::fmt;
+use scale_info::{
+	form::PortableForm, Field, PortableRegistry, Registry, TypeDef, TypeDefPrimitive, TypeInfo,
+};
+
+/// Sequences that are longer than this are printed on multiple lines in pretty mode.
+const MAX_INLINE_WIDTH: usize = 80;
+
+/// An error while parsing a value from its text representation.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum ParseError {
+	/// The text does not match the type at the byte offset `position`.
+	Syntax { position: usize, expected: String },
+	/// The type uses an encoding that has no text representation, like bit sequences.
+	UnsupportedType,
+	/// The parsed value is not valid for the type, e.g. a bounded vector is too long.
+	Decode,
+}
+
+/// Displays a value in the text format.
+///
+/// The alternate flag (`{:#}`) breaks long sequences, like the instructions of a program, into
+/// one item per line.
+pub struct Text<'a, T>(pub &'a T);
+
+impl<T: Encode + TypeInfo + 'static> fmt::Display for Text<'_, T> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(&to_text(self.0, f.alternate()))
+	}
+}
+
+/// Returns the text representation of `value`.
+///
+/// With `pretty` set, long sequences are broken into one item per line.
+pub fn to_text<T: Encode + TypeInfo + 'static>(value: &T, pretty: bool) -> String {
+	let (registry, ty) = registry_of::<T>();
+	let encoded = value.encode();
+	let printer = Printer { registry: &registry, pretty };
+	printer.print(ty, &mut &encoded[..], 0).unwrap_or_else(|| "<invalid>".into())
+}
+
+/// Parses a value from its text representation.
+pub fn from_text<T: Decode + TypeInfo + 'static>(text: &str) -> Result<T, ParseError> {
+	let (registry, ty) = registry_of::<T>();
+	let mut parser = Parser { registry: &registry, text, position: 0 };
+	let mut encoded = Vec::new();
+	parser.parse(ty, &mut encoded)?;
+	parser.skip_whitespace();
+	if parser.position != text.len() {
+		return Err(parser.expected("end of input"))
+	}
+	T::decode_all_with_depth_limit(MAX_XCM_DECODE_DEPTH, &mut &encoded[..])
+		.map_err(|_| ParseError::Decode)
+}
+
+fn registry_of<T: TypeInfo + 'static>() -> (PortableRegistry, u32) {
+	let mut registry = Registry::new();
+	let ty = registry.register_type(&scale_info::meta_type::<T>()).id;
+	(registry.into(), ty)
+}
+
+/// Returns the primitive behind a number type, looking through compact and newtype wrappers.
+fn number_type(registry: &PortableRegistry, ty: u32) -> Option<TypeDefPrimitive> {
+	match &registry.resolve(ty)?.type_def {
+		TypeDef::Primitive(primitive) => Some(primitive.clone()),
+		TypeDef::Compact(compact) => number_type(registry, compact.type_param.id),
+		TypeDef::Composite(composite) if composite.fields.len() == 1 =>
+			number_type(registry, composite.fields[0].ty.id),
+		_ => None,
+	}
+}
+
+fn is_byte(registry: &PortableRegistry, ty: u32) -> bool {
+	matches!(
+		registry.resolve(ty).map(|ty| &ty.type_def),
+		Some(TypeDef::Primitive(TypeDefPrimitive::U8))
+	)
+}
+
+struct Printer<'a> {
+	registry: &'a PortableRegistry,
+	pretty: bool,
+}
+
+impl Printer<'_> {
+	fn print(&self, ty: u32, input: &mut &[u8], level: usize) -> Option<String> {
+		match &self.registry.resolve(ty)?.type_def {
+			TypeDef::Composite(composite) => match &composite.fields[..] {
+				[] => Some("()".into()),
+				[field] if field.name.is_none() => self.print(field.ty.id, input, level),
+				fields => self.print_fields(fields, input, level),
+			},
+			TypeDef::Variant(variant) => {
+				let index = u8::decode(input).ok()?;
+				let variant = variant.variants.iter().find(|v| v.index == index)?;
+				if variant.fields.is_empty() {
+					return Some(variant.name.clone())
+				}
+				let fields = self.print_fields(&variant.fields, input, level)?;
+				let separator = if variant.fields[0].name.is_some() { " " } else { "" };
+				Some(format!("{}{separator}{fields}", variant.name))
+			},
+			TypeDef::Sequence(sequence) => {
+				let len = Compact::<u32>::decode(input).ok()?.0;
+				self.print_items(sequence.type_param.id, len, input, level)
+			},
+			TypeDef::Array(array) => self.print_items(array.type_param.id, array.len, input, level),
+			TypeDef::Tuple(tuple) => {
+				let items = tuple
+					.fields
+					.iter()
+					.map(|field| self.print(field.id, input, level))
+					.collect::<Option<Vec<_>>>()?;
+				Some(format!("({})", items.join(", ")))
+			},
+			TypeDef::Primitive(primitive) => print_primitive(primitive, input),
+			TypeDef::Compact(_) => Some(Compact::<u128>::decode(input).ok()?.0.to_string()),
+			TypeDef::BitSequence(_) => None,
+		}
+	}
+
+	fn print_fields(
+		&self,
+		fields: &[Field<PortableForm>],
+		input: &mut &[u8],
+		level: usize,
+	) -> Option<String> {
+		let mut items = Vec::with_capacity(fields.len());
+		for field in fields {
+			let value = self.print(field.ty.id, input, level)?;
+			items.push(match &field.name {
+				Some(name) => format!("{name}: {value}"),
+				None => value,
+			});
+		}
+		Some(match fields[0].name {
+			Some(_) => format!("{{ {} }}", items.join(", ")),
+			None => format!("({})", items.join(", ")),
+		})
+	}
+
+	fn print_items(&self, ty: u32, len: u32, input: &mut &[u8], level: usize) -> Option<String> {
+		if is_byte(self.registry, ty) {
+			let bytes = input.get(..len as usize)?;
+			*input = &input[len as usize..];
+			return Some(array_bytes::bytes2hex("0x", bytes))
+		}
+
+		let items =
+			(0..len).map(|_| self.print(ty, input, level + 1)).collect::<Option<Vec<_>>>()?;
+		let inline = format!("[{}]", items.join(", "));
+		if !self.pretty ||
+			items.is_empty() ||
+			(inline.len() <= MAX_INLINE_WIDTH && !inline.contains('\n'))
+		{
+			return Some(inline)
+		}
+
+		let indent = "  ".repeat(level + 1);
+		let mut out = String::from("[\n");
+		for item in items {
+			out.push_str(&format!("{indent}{item},\n"));
+		}
+		out.push_str(&"  ".repeat(level));
+		out.push(']');
+		Some(out)
+	}
+}
+
+fn print_primitive(primitive: &TypeDefPrimitive, input: &mut &[u8]) -> Option<String> {
+	Some(match primitive {
+		TypeDefPrimitive::Bool => bool::decode(input).ok()?.to_string(),
+		TypeDefPrimitive::Str => {
+			let value = String::decode(input).ok()?;
+			format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+		},
+		TypeDefPrimitive::U8 => u8::decode(input).ok()?.to_string(),
+		TypeDefPrimitive::U16 => u16::decode(input).ok()?.to_string(),
+		TypeDefPrimitive::U32 => u32::decode(input).ok()?.to_string(),
+		TypeDefPrimitive::U64 => u64::decode(input).ok()?.to_string(),
+		TypeDefPrimitive::U128 => u128::decode(input).ok()?.to_string(),
+		TypeDefPrimitive::I8 => i8::decode(input).ok()?.to_string(),
+		TypeDefPrimitive::I16 => i16::decode(input).ok()?.to_string(),
+		TypeDefPrimitive::I32 => i32::decode(input).ok()?.to_string(),
+		TypeDefPrimitive::I64 => i64::decode(input).ok()?.to_string(),
+		TypeDefPrimitive::I128 => i128::decode(input).ok()?.to_string(),
+		TypeDefPrimitive::Char | TypeDefPrimitive::U256 | TypeDefPrimitive::I256 => return None,
+	})
+}
+
+struct Parser<'a> {
+	registry: &'a PortableRegistry,
+	text: &'a str,
+	position: usize,
+}
+
+impl<'a> Parser<'a> {
+	fn parse(&mut self, ty: u32, out: &mut Vec<u8>) -> Result<(), ParseError> {
+		let registry = self.registry;
+		let ty = registry.resolve(ty).ok_or(ParseError::UnsupportedType)?;
+		match &ty.type_def {
+			TypeDef::Composite(composite) => match &composite.fields[..] {
+				[] => self.parse_unit(),
+				[field] if field.name.is_none() => self.parse(field.ty.id, out),
+				fields => self.parse_fields(fields, out),
+			},
+			TypeDef::Variant(variant) => {
+				self.skip_whitespace();
+				let start = self.position;
+				let name = self.identifier()?;
+				let variant =
+					variant.variants.iter().find(|v| v.name == name).ok_or_else(|| {
+						self.position = start;
+						let variants =
+							variant.variants.iter().map(|v| v.name.as_str()).collect::<Vec<_>>();
+						self.expected(&format!("one of {}", variants.join(", ")))
+					})?;
+				out.push(variant.index);
+				if variant.fields.is_empty() {
+					return Ok(())
+				}
+				self.parse_fields(&variant.fields, out)
+			},
+			TypeDef::Sequence(sequence) => {
+				let items = self.parse_items(sequence.type_param.id, None)?;
+				Compact(items.0).encode_to(out);
+				out.extend(items.1);
+				Ok(())
+			},
+			TypeDef::Array(array) => {
+				let items = self.parse_items(array.type_param.id, Some(array.len))?;
+				out.extend(items.1);
+				Ok(())
+			},
+			TypeDef::Tuple(tuple) => {
+				if tuple.fields.is_empty() {
+					return self.parse_unit()
+				}
+				self.punctuation("(")?;
+				for (i, field) in tuple.fields.iter().enumerate() {
+					if i > 0 {
+						self.punctuation(",")?;
+					}
+					self.parse(field.id, out)?;
+				}
+				self.optional(",");
+				self.punctuation(")")
+			},
+			TypeDef::Primitive(primitive) => self.parse_primitive(primitive, out),
+			TypeDef::Compact(compact) => {
+				let primitive = number_type(registry, compact.type_param.id)
+					.ok_or(ParseError::UnsupportedType)?;
+				let value = self.unsigned(&primitive)?;
+				Compact(value).encode_to(out);
+				Ok(())
+			},
+			TypeDef::BitSequence(_) => Err(ParseError::UnsupportedType),
+		}
+	}
+
+	fn parse_fields(
+		&mut self,
+		fields: &[Field<PortableForm>],
+		out: &mut Vec<u8>,
+	) -> Result<(), ParseError> {
+		let named = fields[0].name.is_some();
+		self.punctuation(if named { "{" } else { "(" })?;
+		for (i, field) in fields.iter().enumerate() {
+			if i > 0 {
+				self.punctuation(",")?;
+			}
+			if let Some(name) = &field.name {
+				self.skip_whitespace();
+				let start = self.position;
+				if self.identifier()? != *name {
+					self.position = start;
+					return Err(self.expected(&format!("field `{name}`")))
+				}
+				self.punctuation(":")?;
+			}
+			self.parse(field.ty.id, out)?;
+		}
+		self.optional(",");
+		self.punctuation(if named { "}" } else { ")" })
+	}
+
+	/// Parses the items of a sequence or an array and returns their number and encoding.
+	fn parse_items(&mut self, ty: u32, len: Option<u32>) -> Result<(u32, Vec<u8>), ParseError> {
+		let mut out = Vec::new();
+		let mut count = 0u32;
+		self.skip_whitespace();
+		if is_byte(self.registry, ty) && self.rest().starts_with("0x") {
+			let start = self.position;
+			self.position += 2;
+			let digits = self.take_while(|c| c.is_ascii_hexdigit());
+			out = array_bytes::hex2bytes(digits).map_err(|_| {
+				self.position = start;
+				self.expected("hex encoded bytes")
+			})?;
+			count = out.len() as u32;
+		} else {
+			self.punctuation("[")?;
+			while !self.optional("]") {
+				if count > 0 {
+					self.punctuation(",")?;
+					if self.optional("]") {
+						break
+					}
+				}
+				self.parse(ty, &mut out)?;
+				count += 1;
+			}
+		}
+
+		match len {
+			Some(len) if len != count => Err(self.expected(&format!("{len} items"))),
+			_ => Ok((count, out)),
+		}
+	}
+
+	fn parse_primitive(
+		&mut self,
+		primitive: &TypeDefPrimitive,
+		out: &mut Vec<u8>,
+	) -> Result<(), ParseError> {
+		match primitive {
+			TypeDefPrimitive::Bool => match self.identifier()?.as_str() {
+				"true" => true.encode_to(out),
+				"false" => false.encode_to(out),
+				_ => return Err(self.expected("`true` or `false`")),
+			},
+			TypeDefPrimitive::Str => self.string()?.encode_to(out),
+			TypeDefPrimitive::U8 => (self.unsigned(primitive)? as u8).encode_to(out),
+			TypeDefPrimitive::U16 => (self.unsigned(primitive)? as u16).encode_to(out),
+			TypeDefPrimitive::U32 => (self.unsigned(primitive)? as u32).encode_to(out),
+			TypeDefPrimitive::U64 => (self.unsigned(primitive)? as u64).encode_to(out),
+			TypeDefPrimitive::U128 => self.unsigned(primitive)?.encode_to(out),
+			TypeDefPrimitive::I8 => (self.signed(primitive)? as i8).encode_to(out),
+			TypeDefPrimitive::I16 => (self.signed(primitive)? as i16).encode_to(out),
+			TypeDefPrimitive::I32 => (self.signed(primitive)? as i32).encode_to(out),
+			TypeDefPrimitive::I64 => (self.signed(primitive)? as i64).encode_to(out),
+			TypeDefPrimitive::I128 => self.signed(primitive)?.encode_to(out),
+			TypeDefPrimitive::Char | TypeDefPrimitive::U256 | TypeDefPrimitive::I256 =>
+				return Err(ParseError::UnsupportedType),
+		}
+		Ok(())
+	}
+
+	fn parse_unit(&mut self) -> Result<(), ParseError> {
+		self.punctuation("(")?;
+		self.punctuation(")")
+	}
+
+	/// Parses an unsigned number that fits into `primitive`.
+	fn unsigned(&mut self, primitive: &TypeDefPrimitive) -> Result<u128, ParseError> {
+		let max = match primitive {
+			TypeDefPrimitive::U8 => u8::MAX as u128,
+			TypeDefPrimitive::U16 => u16::MAX as u128,
+			TypeDefPrimitive::U32 => u32::MAX as u128,
+			TypeDefPrimitive::U64 => u64::MAX as u128,
+			TypeDefPrimitive::U128 => u128::MAX,
+			_ => return Err(ParseError::UnsupportedType),
+		};
+		self.skip_whitespace();
+		let start = self.position;
+		let digits = self.take_while(|c| c.is_ascii_digit() || c == '_');
+		match digits.replace('_', "").parse::<u128>() {
+			Ok(value) if value <= max => Ok(value),
+			_ => {
+				self.position = start;
+				Err(self.expected(&format!("a number up to {max}")))
+			},
+		}
+	}
+
+	/// Parses a signed number that fits into `primitive`.
+	fn signed(&mut self, primitive: &TypeDefPrimitive) -> Result<i128, ParseError> {
+		let (min, max) = match primitive {
+			TypeDefPrimitive::I8 => (i8::MIN as i128, i8::MAX as i128),
+			TypeDefPrimitive::I16 => (i16::MIN as i128, i16::MAX as i128),
+			TypeDefPrimitive::I32 => (i32::MIN as i128, i32::MAX as i128),
+			TypeDefPrimitive::I64 => (i64::MIN as i128, i64::MAX as i128),
+			TypeDefPrimitive::I128 => (i128::MIN, i128::MAX),
+			_ => return Err(ParseError::UnsupportedType),
+		};
+		self.skip_whitespace();
+		let start = self.position;
+		let negative = self.optional("-");
+		let digits = self.take_while(|c| c.is_ascii_digit() || c == '_').replace('_', "");
+		let digits = if negative { format!("-{digits}") } else { digits };
+		match digits.parse::<i128>() {
+			Ok(value) if (min..=max).contains(&value) => Ok(value),
+			_ => {
+				self.position = start;
+				Err(self.expected(&format!("a number between {min} and {max}")))
+			},
+		}
+	}
+
+	/// Parses a double quoted string with `\"` and `\\` escapes.
+	fn string(&mut self) -> Result<String, ParseError> {
+		self.punctuation("\"")?;
+		let mut value = String::new();
+		let mut chars = self.rest().char_indices();
+		while let Some((offset, c)) = chars.next() {
+			match c {
+				'"' => {
+					self.position += offset + 1;
+					return Ok(value)
+				},
+				'\\' => match chars.next() {
+					Some((_, escaped @ ('"' | '\\'))) => value.push(escaped),
+					_ => {
+						self.position += offset;
+						return Err(self.expected("`\\\"` or `\\\\`"))
+					},
+				},
+				c => value.push(c),
+			}
+		}
+		self.position = self.text.len();
+		Err(self.expected("`\"`"))
+	}
+
+	fn identifier(&mut self) -> Result<String, ParseError> {
+		self.skip_whitespace();
+		let identifier = self.take_while(|c| c.is_ascii_alphanumeric() || c == '_');
+		if identifier.is_empty() || identifier.starts_with(|c: char| c.is_ascii_digit()) {
+			return Err(self.expected("an identifier"))
+		}
+		Ok(identifier.into())
+	}
+
+	fn punctuation(&mut self, token: &str) -> Result<(), ParseError> {
+		if self.optional(token) {
+			Ok(())
+		} else {
+			Err(self.expected(&format!("`{token}`")))
+		}
+	}
+
+	fn optional(&mut self, token: &str) -> bool {
+		self.skip_whitespace();
+		let found = self.rest().starts_with(token);
+		if found {
+			self.position += token.len();
+		}
+		found
+	}
+
+	fn take_while(&mut self, predicate: impl Fn(char) -> bool) -> &'a str {
+		let start = self.position;
+		let len = self.rest().find(|c| !predicate(c)).unwrap_or(self.text.len() - start);
+		self.position += len;
+		&self.text[start..self.position]
+	}
+
+	fn skip_whitespace(&mut self) {
+		self.take_while(char::is_whitespace);
+	}
+
+	fn rest(&self) -> &'a str {
+		&self.text[self.position..]
+	}
+
+	fn expected(&self, expected: &str) -> ParseError {
+		ParseError::Syntax { position: self.position, expected: expected.into() }
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{latest::prelude::*, VersionedXcm};
+
+	fn program() -> Xcm<()> {
+		Xcm(vec![
+			WithdrawAsset((Parent, 100u128).into()),
+			BuyExecution { fees: (Parent, 100u128).into(), weight_limit: Unlimited },
+			Transact {
+				origin_kind: OriginKind::SovereignAccount,
+				fallback_max_weight: Some(Weight::from_parts(1_000, 10)),
+				call: vec![0, 1, 2].into(),
+			},
+			SetAppendix(Xcm(vec![
+				RefundSurplus,
+				DepositAsset {
+					assets: Wild(AllCounted(1)),
+					beneficiary: AccountId32 { network: None, id: [1; 32] }.into(),
+				},
+			])),
+			SetTopic([2; 32]),
+		])
+	}
+
+	#[test]
+	fn print_works() {
+		let location: Location = (Parent, Parachain(1000), GeneralIndex(42)).into();
+		assert_eq!(
+			to_text(&location, false),
+			"{ parents: 1, interior: X2([Parachain(1000), GeneralIndex(42)]) }",
+		);
+
+		let xcm = Xcm::<()>(vec![ClearOrigin, ExpectTransactStatus(MaybeErrorCode::Success)]);
+		assert_eq!(to_text(&xcm, true), "[ClearOrigin, ExpectTransactStatus(Success)]");
+		assert_eq!(
+			Text(&VersionedXcm::from(xcm)).to_string(),
+			"V5([ClearOrigin, ExpectTransactStatus(Success)])",
+		);
+	}
+
+	#[test]
+	fn pretty_print_breaks_long_sequences() {
+		let text = format!("{:#}", Text(&program()));
+		let lines = text.lines().collect::<Vec<_>>();
+		assert_eq!(lines[0], "[");
+		assert!(lines[1].starts_with("  WithdrawAsset("));
+		assert!(lines.contains(&"  SetAppendix(["));
+		assert!(lines.contains(&"    RefundSurplus,"));
+		assert_eq!(lines[lines.len() - 1], "]");
+	}
+
+	#[test]
+	fn roundtrip_works() {
+		let xcm = program();
+		assert_eq!(from_text::<Xcm<()>>(&to_text(&xcm, false)), Ok(xcm.clone()));
+		assert_eq!(from_text::<Xcm<()>>(&to_text(&xcm, true)), Ok(xcm.clone()));
+
+		let versioned = VersionedXcm::from(xcm);
+		assert_eq!(from_text::<VersionedXcm<()>>(&to_text(&versioned, true)), Ok(versioned));
+	}
+
+	#[test]
+	fn parse_works() {
+		let xcm = from_text::<Xcm<()>>(
+			"[
+				ReceiveTeleportedAsset([{ id: { parents: 1, interior: Here }, fun: Fungible(1_000) }]),
+				ClearOrigin,
+				DepositAsset {
+					assets: Wild(All),
+					beneficiary: { parents: 0, interior: X1([Parachain(2000)]) },
+				},
+			]",
+		);
+		assert_eq!(
+			xcm,
+			Ok(Xcm(vec![
+				ReceiveTeleportedAsset((Parent, 1_000u128).into()),
+				ClearOrigin,
+				DepositAsset { assets: Wild(All), beneficiary: Parachain(2000).into() },
+			])),
+		);
+	}
+
+	#[test]
+	fn parse_errors_are_reported() {
+		assert!(matches!(
+			from_text::<Xcm<()>>("[ClearOrigin, Unknown]"),
+			Err(ParseError::Syntax { position: 14, .. }),
+		));
+		assert!(matches!(
+			from_text::<Xcm<()>>("[Trap(18446744073709551616)]"),
+			Err(ParseError::Syntax { position: 6, .. }),
+		));
+		assert!(matches!(
+			from_text::<Location>("{ interior: Here, parents: 1 }"),
+			Err(ParseError::Syntax { position: 2, .. }),
+		));
+		assert!(matches!(
+			from_text::<Xcm<()>>("[ClearOrigin] ClearOrigin"),
+			Err(ParseError::Syntax { position: 14, .. }),
+		));
+
+		let too_many = format!("[{}]", vec!["ClearOrigin"; 101].join(", "));
+		assert_eq!(from_text::<Xcm<()>>(&too_many), Err(ParseError::Decode));
+	}
+}
@@ -0,0 +1,469 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Static checks for common mistakes in XCM programs.
+//!
+//! The checks only look at the program itself, they know nothing about the chain that executes
+//! it. A program that passes them may still fail, but a program that fails them is almost
+//! certainly wrong. Messages sent to other chains (like the `xcm` of `DepositReserveAsset`) are not
+//! checked, since the destination prepends its own instructions to them.
+
+use super::{
+	AnythingGoes, Asset, AssetFilter, AssetId, AssetInstance, Assets, Fungibility, Instruction,
+	WildAsset, WildFungibility, Xcm, XcmBuilder,
+};
+use alloc::{
+	collections::{BTreeMap, BTreeSet},
+	vec::Vec,
+};
+
+/// A mistake found in an XCM program.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Lint {
+	/// The index of the offending instruction.
+	///
+	/// Mistakes inside of nested programs, like the one of `SetAppendix`, are reported at the
+	/// index of the instruction that holds the nested program. Assets left in holding are
+	/// reported at the index one past the last instruction.
+	pub index: usize,
+	/// What is wrong.
+	pub kind: LintKind,
+}
+
+/// The kinds of mistakes found by [`Xcm::lint`].
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum LintKind {
+	/// `BuyExecution`, `PayFees` or `UnpaidExecution` comes after an instruction that already
+	/// needs execution to be paid for. Barriers reject such programs.
+	LateFeePayment,
+	/// The instruction uses the holding register, but nothing was put into it.
+	EmptyHolding,
+	/// The fees of `BuyExecution` or `PayFees` are not in the holding register.
+	FeesNotInHolding,
+	/// The instruction uses more of an asset than the holding register may contain.
+	InsufficientHolding(AssetId),
+	/// Assets remain in the holding register when the program ends and would be trapped.
+	TrappedAssets,
+	/// `SetTopic` is not the last instruction of the program.
+	MisplacedTopic,
+}
+
+impl<Call> Xcm<Call> {
+	/// Checks the program for common mistakes.
+	///
+	/// This checks that
+	/// - fees are paid before any instruction that needs execution to be paid for,
+	/// - instructions only use assets that were put into the holding register before,
+	/// - no assets are left in the holding register once the program (and its appendix) ends,
+	/// - `SetTopic` is the last instruction.
+	///
+	/// Returns all mistakes found, in instruction order.
+	pub fn lint(&self) -> Result<(), Vec<Lint>> {
+		let mut linter = Linter { holding: Some(Holding::default()), lints: Vec::new() };
+		linter.check_fees(self);
+		if let Some((index, appendix)) = linter.check_program(self, None) {
+			linter.check_program(appendix, Some(index));
+		}
+		if linter.holding.as_ref().map_or(false, |holding| !holding.is_empty()) {
+			linter.push(self.len(), LintKind::TrappedAssets);
+		}
+		linter.check_topic(self, None);
+
+		if linter.lints.is_empty() {
+			Ok(())
+		} else {
+			linter.lints.sort_by_key(|lint| lint.index);
+			Err(linter.lints)
+		}
+	}
+}
+
+impl<Call> XcmBuilder<Call, AnythingGoes> {
+	/// Builds the program and checks it with [`Xcm::lint`].
+	pub fn try_build(self) -> Result<Xcm<Call>, Vec<Lint>> {
+		let xcm = self.build();
+		xcm.lint()?;
+		Ok(xcm)
+	}
+}
+
+/// An upper bound of the contents of the holding register.
+#[derive(Default)]
+struct Holding {
+	fungible: BTreeMap<AssetId, u128>,
+	non_fungible: BTreeSet<(AssetId, AssetInstance)>,
+}
+
+impl Holding {
+	fn is_empty(&self) -> bool {
+		self.fungible.is_empty() && self.non_fungible.is_empty()
+	}
+
+	fn len(&self) -> usize {
+		self.fungible.len() + self.non_fungible.len()
+	}
+
+	fn contains(&self, asset: &Asset) -> bool {
+		match &asset.fun {
+			Fungibility::Fungible(amount) =>
+				self.fungible.get(&asset.id).map_or(false, |held| held >= amount),
+			Fungibility::NonFungible(instance) =>
+				self.non_fungible.contains(&(asset.id.clone(), *instance)),
+		}
+	}
+
+	fn subsume(&mut self, assets: &Assets) {
+		for asset in assets.inner() {
+			match asset.fun {
+				Fungibility::Fungible(amount) => {
+					let held = self.fungible.entry(asset.id.clone()).or_default();
+					*held = held.saturating_add(amount);
+				},
+				Fungibility::NonFungible(instance) => {
+					self.non_fungible.insert((asset.id.clone(), instance));
+				},
+			}
+		}
+	}
+
+	fn take(&mut self, asset: &Asset) {
+		match asset.fun {
+			Fungibility::Fungible(amount) =>
+				if let Some(held) = self.fungible.get_mut(&asset.id) {
+					*held = held.saturating_sub(amount);
+					if *held == 0 {
+						self.fungible.remove(&asset.id);
+					}
+				},
+			Fungibility::NonFungible(instance) => {
+				self.non_fungible.remove(&(asset.id.clone(), instance));
+			},
+		}
+	}
+
+	fn take_all_of(&mut self, id: &AssetId, fun: &WildFungibility) {
+		match fun {
+			WildFungibility::Fungible => {
+				self.fungible.remove(id);
+			},
+			WildFungibility::NonFungible => self.non_fungible.retain(|(held, _)| held != id),
+		}
+	}
+
+	fn count_of(&self, id: &AssetId, fun: &WildFungibility) -> usize {
+		match fun {
+			WildFungibility::Fungible => self.fungible.contains_key(id) as usize,
+			WildFungibility::NonFungible =>
+				self.non_fungible.iter().filter(|(held, _)| held == id).count(),
+		}
+	}
+}
+
+struct Linter {
+	/// The holding register, `None` once its contents can no longer be known.
+	holding: Option<Holding>,
+	lints: Vec<Lint>,
+}
+
+impl Linter {
+	fn push(&mut self, index: usize, kind: LintKind) {
+		self.lints.push(Lint { index, kind })
+	}
+
+	/// Checks that fees are paid before any instruction that needs execution to be paid for.
+	fn check_fees<Call>(&mut self, xcm: &Xcm<Call>) {
+		use Instruction::*;
+		let mut paid_prefix = true;
+		let mut unpaid_prefix = true;
+		for (index, instruction) in xcm.inner().iter().enumerate() {
+			match instruction {
+				BuyExecution { .. } | PayFees { .. } | UnpaidExecution { .. } => {
+					let in_time = match instruction {
+						UnpaidExecution { .. } => unpaid_prefix,
+						_ => paid_prefix,
+					};
+					if !in_time {
+						self.push(index, LintKind::LateFeePayment);
+					}
+					return
+				},
+				UniversalOrigin(_) | DescendOrigin(_) => {},
+				WithdrawAsset(_) |
+				ReserveAssetDeposited(_) |
+				ReceiveTeleportedAsset(_) |
+				ClaimAsset { .. } |
+				ClearOrigin |
+				AliasOrigin(_) |
+				SetHints { .. } => unpaid_prefix = false,
+				_ => {
+					paid_prefix = false;
+					unpaid_prefix = false;
+				},
+			}
+		}
+	}
+
+	/// Checks the use of the holding register.
+	///
+	/// `parent` is the index of the instruction that holds `xcm`, if it is nested. Returns the
+	/// appendix set by the program, together with the index of its `SetAppendix`.
+	fn check_program<'a, Call>(
+		&mut self,
+		xcm: &'a Xcm<Call>,
+		parent: Option<usize>,
+	) -> Option<(usize, &'a Xcm<Call>)> {
+		use Instruction::*;
+		let mut appendix = None;
+		for (index, instruction) in xcm.inner().iter().enumerate() {
+			let index = parent.unwrap_or(index);
+			match instruction {
+				WithdrawAsset(assets) |
+				ReserveAssetDeposited(assets) |
+				ReceiveTeleportedAsset(assets) |
+				ClaimAsset { assets, .. } =>
+					if let Some(holding) = &mut self.holding {
+						holding.subsume(assets);
+					},
+				BuyExecution { fees, .. } | PayFees { asset: fees } =>
+					if let Some(holding) = &self.holding {
+						if !holding.contains(fees) {
+							self.push(index, LintKind::FeesNotInHolding);
+						}
+					},
+				DepositAsset { assets, .. } |
+				DepositReserveAsset { assets, .. } |
+				InitiateReserveWithdraw { assets, .. } |
+				InitiateTeleport { assets, .. } => self.take(index, assets),
+				InitiateTransfer { remote_fees, assets, .. } =>
+					for filter in remote_fees.iter().chain(assets.iter()) {
+						self.take(index, filter.inner());
+					},
+				BurnAsset(assets) => self.take(index, &AssetFilter::Definite(assets.clone())),
+				ExchangeAsset { give, .. } => {
+					self.take(index, give);
+					// The amount received is only known at execution time.
+					self.holding = None;
+				},
+				ExecuteWithOrigin { xcm, .. } => {
+					// Appendices of nested programs are executed when the nested program ends.
+					if let Some((_, nested_appendix)) = self.check_program(xcm, Some(index)) {
+						self.check_program(nested_appendix, Some(index));
+					}
+				},
+				SetAppendix(xcm) => appendix = Some((index, xcm)),
+				_ => {},
+			}
+		}
+		appendix
+	}
+
+	/// Takes the assets matching `filter` from the holding register.
+	fn take(&mut self, index: usize, filter: &AssetFilter) {
+		let Some(holding) = &mut self.holding else { return };
+		if holding.is_empty() {
+			return self.push(index, LintKind::EmptyHolding)
+		}
+
+		match filter {
+			AssetFilter::Definite(assets) => {
+				let mut missing = Vec::new();
+				for asset in assets.inner() {
+					if !holding.contains(asset) {
+						missing.push(asset.id.clone());
+					}
+					holding.take(asset);
+				}
+				for id in missing {
+					self.push(index, LintKind::InsufficientHolding(id));
+				}
+			},
+			AssetFilter::Wild(WildAsset::All) => *holding = Holding::default(),
+			AssetFilter::Wild(WildAsset::AllCounted(count)) =>
+				if holding.len() <= *count as usize {
+					*holding = Holding::default();
+				} else {
+					// Which assets are taken depends on their order at execution time.
+					self.holding = None;
+				},
+			AssetFilter::Wild(WildAsset::AllOf { id, fun }) => holding.take_all_of(id, fun),
+			AssetFilter::Wild(WildAsset::AllOfCounted { id, fun, count }) =>
+				if holding.count_of(id, fun) <= *count as usize {
+					holding.take_all_of(id, fun);
+				} else {
+					self.holding = None;
+				},
+		}
+	}
+
+	/// Checks that `SetTopic` is only used as the last instruction.
+	fn check_topic<Call>(&mut self, xcm: &Xcm<Call>, parent: Option<usize>) {
+		use Instruction::*;
+		let last = xcm.len().saturating_sub(1);
+		for (index, instruction) in xcm.inner().iter().enumerate() {
+			let at = parent.unwrap_or(index);
+			match instruction {
+				SetTopic(_) if index != last => self.push(at, LintKind::MisplacedTopic),
+				SetAppendix(xcm) | SetErrorHandler(xcm) | ExecuteWithOrigin { xcm, .. } =>
+					self.check_topic(xcm, Some(at)),
+				_ => {},
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::latest::prelude::*;
+
+	fn lint(instructions: Vec<Instruction<()>>) -> Result<(), Vec<(usize, LintKind)>> {
+		Xcm(instructions)
+			.lint()
+			.map_err(|lints| lints.into_iter().map(|lint| (lint.index, lint.kind)).collect())
+	}
+
+	fn beneficiary() -> Location {
+		AccountId32 { network: None, id: [1; 32] }.into()
+	}
+
+	#[test]
+	fn valid_programs_pass() {
+		assert_eq!(
+			lint(vec![
+				WithdrawAsset((Parent, 100u128).into()),
+				ClearOrigin,
+				BuyExecution { fees: (Parent, 10u128).into(), weight_limit: Unlimited },
+				DepositAsset { assets: Wild(AllCounted(1)), beneficiary: beneficiary() },
+				SetTopic([1; 32]),
+			]),
+			Ok(()),
+		);
+		assert_eq!(
+			lint(vec![
+				DescendOrigin(Parachain(1000).into()),
+				UnpaidExecution { weight_limit: Unlimited, check_origin: None },
+				Transact {
+					origin_kind: OriginKind::Native,
+					fallback_max_weight: None,
+					call: vec![].into(),
+				},
+			]),
+			Ok(()),
+		);
+		// Leftovers are deposited by the appendix.
+		assert_eq!(
+			lint(vec![
+				WithdrawAsset((Parent, 100u128).into()),
+				PayFees { asset: (Parent, 10u128).into() },
+				SetAppendix(Xcm(vec![
+					RefundSurplus,
+					DepositAsset { assets: Wild(All), beneficiary: beneficiary() },
+				])),
+				DepositAsset {
+					assets: Definite((Parent, 50u128).into()),
+					beneficiary: Here.into()
+				},
+			]),
+			Ok(()),
+		);
+	}
+
+	#[test]
+	fn late_fee_payment_is_detected() {
+		assert_eq!(
+			lint(vec![
+				WithdrawAsset((Parent, 100u128).into()),
+				Transact {
+					origin_kind: OriginKind::SovereignAccount,
+					fallback_max_weight: None,
+					call: vec![].into(),
+				},
+				BuyExecution { fees: (Parent, 100u128).into(), weight_limit: Unlimited },
+				DepositAsset { assets: Wild(All), beneficiary: beneficiary() },
+			]),
+			Err(vec![(2, LintKind::LateFeePayment)]),
+		);
+		assert_eq!(
+			lint(vec![
+				ClearOrigin,
+				UnpaidExecution { weight_limit: Unlimited, check_origin: None }
+			]),
+			Err(vec![(1, LintKind::LateFeePayment)]),
+		);
+	}
+
+	#[test]
+	fn holding_misuse_is_detected() {
+		assert_eq!(
+			lint(vec![
+				UnpaidExecution { weight_limit: Unlimited, check_origin: None },
+				DepositAsset { assets: Wild(All), beneficiary: beneficiary() },
+			]),
+			Err(vec![(1, LintKind::EmptyHolding)]),
+		);
+		assert_eq!(
+			lint(vec![
+				WithdrawAsset((Parent, 100u128).into()),
+				BuyExecution { fees: (Here, 1u128).into(), weight_limit: Unlimited },
+				DepositAsset {
+					assets: Definite((Parent, 150u128).into()),
+					beneficiary: Here.into()
+				},
+			]),
+			Err(vec![
+				(1, LintKind::FeesNotInHolding),
+				(2, LintKind::InsufficientHolding(Parent.into())),
+			]),
+		);
+		assert_eq!(
+			lint(vec![
+				WithdrawAsset(vec![(Parent, 100u128).into(), (Here, 100u128).into()].into()),
+				BuyExecution { fees: (Parent, 100u128).into(), weight_limit: Unlimited },
+				DepositAsset {
+					assets: Wild(AllOf { id: Here.into(), fun: WildFungible }),
+					beneficiary: beneficiary()
+				},
+			]),
+			Err(vec![(3, LintKind::TrappedAssets)]),
+		);
+	}
+
+	#[test]
+	fn misplaced_topic_is_detected() {
+		assert_eq!(
+			lint(vec![SetTopic([1; 32]), ClearOrigin]),
+			Err(vec![(0, LintKind::MisplacedTopic)]),
+		);
+		assert_eq!(
+			lint(vec![SetErrorHandler(Xcm(vec![SetTopic([1; 32]), ClearError])), ClearOrigin]),
+			Err(vec![(0, LintKind::MisplacedTopic)]),
+		);
+	}
+
+	#[test]
+	fn try_build_works() {
+		let xcm = Xcm::<()>::builder_unsafe().clear_origin().set_topic([1; 32]).try_build();
+		assert_eq!(xcm, Ok(Xcm(vec![ClearOrigin, SetTopic([1; 32])])));
+
+		let xcm = Xcm::<()>::builder()
+			.withdraw_asset((Parent, 100u128))
+			.buy_execution((Parent, 10u128), Unlimited)
+			.set_topic([1; 32])
+			.deposit_asset(AllCounted(1), beneficiary())
+			.try_build();
+		assert_eq!(xcm.map_err(|lints| lints[0].kind.clone()), Err(LintKind::MisplacedTopic));
+	}
+}
@@ -35,6 +35,7 @@ use scale_info::TypeInfo;
 mod asset;
 mod junction;
 pub(crate) mod junctions;
+mod lint;
 mod location;
 mod traits;
 
@@ -46,6 +47,7 @@ pub use junction::{
 	BodyId, BodyPart, Junction, NetworkId, ROCOCO_GENESIS_HASH, WESTEND_GENESIS_HASH,
 };
 pub use junctions::Junctions;
+pub use lint::{Lint, LintKind};
 pub use location::{Ancestor, AncestorThen, InteriorLocation, Location, Parent, ParentThen};
 pub use traits::{
 	send_xcm, validate_send, Error, ExecuteXcm, InstructionError, InstructionIndex, Outcome,
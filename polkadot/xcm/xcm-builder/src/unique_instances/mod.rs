@@ -24,6 +24,9 @@ use xcm::latest::prelude::*;
 pub mod adapter;
 pub use adapter::*;
 
+pub mod restrictions;
+pub use restrictions::*;
+
 /// An XCM ID for unique instances (non-fungible assets).
 pub type NonFungibleAsset = (AssetId, AssetInstance);
 
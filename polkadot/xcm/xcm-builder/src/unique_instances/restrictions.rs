@@ -0,0 +1,503 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Usage restrictions of unique instances that are enforced when they are transacted over XCM.
+//!
+//! A unique instance, e.g. a data-access licence, may carry restrictions on how it can be used:
+//! until when it is valid, for which purposes it may be used and whether it may change its owner
+//! at all. [`RestrictedInstancesAdapter`] wraps the [`TransactAsset`] of such instances and rejects
+//! deposits, withdrawals and transfers that violate the restrictions.
+//!
+//! The restrictions of an instance are read with [`InspectUsageRestrictions`].
+//! [`UsageRestrictionsFromAttributes`] reads them from the collection owner attributes of a
+//! [`nonfungibles_v2`] implementation like `pallet_nfts`.
+//!
+//! ## Other chains
+//!
+//! A chain can only enforce the restrictions of instances it holds. XCM identifies an instance by
+//! its id alone and carries none of its attributes, so a chain that receives an instance, e.g. as
+//! a derivative or by a teleport, does not learn its restrictions from the message. Neither can
+//! this chain check what the other chain does with it once the message is sent.
+//!
+//! Instead, restricted instances only leave this chain for chains that are trusted to enforce the
+//! restrictions, which are configured with `Enforcers`. Such a chain runs the same adapter and
+//! knows the restrictions of the instances it receives, e.g. because the collection owner sets the
+//! same attributes on its derivatives, or because its [`InspectUsageRestrictions`] reads them from
+//! a copy of the reserve's state. Sending a restricted instance to any other chain fails.
+
+use codec::Decode;
+use core::marker::PhantomData;
+use frame_support::traits::{tokens::nonfungibles_v2, Contains, Get, UnixTime};
+use xcm::latest::prelude::*;
+use xcm_executor::{
+	traits::{MatchesInstance, TransactAsset},
+	AssetsInHolding,
+};
+
+const LOG_TARGET: &str = "xcm::unique_instances::restrictions";
+
+/// The attribute with the UNIX time in seconds at which an instance expires, as SCALE encoded
+/// `u64`.
+pub const EXPIRY_ATTRIBUTE: &[u8] = b"restriction:expiry";
+
+/// The attribute with the purposes an instance may be used for, as SCALE encoded `u32` bitmask.
+pub const PURPOSES_ATTRIBUTE: &[u8] = b"restriction:purposes";
+
+/// The attribute that marks an instance as non-transferable, as SCALE encoded `bool`.
+pub const NON_TRANSFERABLE_ATTRIBUTE: &[u8] = b"restriction:non-transferable";
+
+/// How a unique instance may be used.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct UsageRestrictions {
+	/// The UNIX time in seconds at which the instance expires. `None` if it never expires.
+	pub expiry: Option<u64>,
+	/// A bitmask of the purposes the instance may be used for. `None` if it may be used for all
+	/// purposes.
+	pub purposes: Option<u32>,
+	/// Whether the instance must not change its owner.
+	pub non_transferable: bool,
+}
+
+impl UsageRestrictions {
+	/// Whether the instance may be used in any way.
+	pub fn is_unrestricted(&self) -> bool {
+		*self == Self::default()
+	}
+}
+
+/// Reads the [`UsageRestrictions`] of unique instances.
+pub trait InspectUsageRestrictions<Id> {
+	/// The restrictions of the instance `id`.
+	fn usage_restrictions(id: &Id) -> UsageRestrictions;
+}
+
+/// Reads the [`UsageRestrictions`] of items from their collection owner attributes.
+///
+/// See [`EXPIRY_ATTRIBUTE`], [`PURPOSES_ATTRIBUTE`] and [`NON_TRANSFERABLE_ATTRIBUTE`]. Attributes
+/// that can not be decoded restrict the item as much as possible.
+pub struct UsageRestrictionsFromAttributes<Nfts, AccountId>(PhantomData<(Nfts, AccountId)>);
+
+impl<Nfts: nonfungibles_v2::Inspect<AccountId>, AccountId>
+	InspectUsageRestrictions<(Nfts::CollectionId, Nfts::ItemId)>
+	for UsageRestrictionsFromAttributes<Nfts, AccountId>
+{
+	fn usage_restrictions(
+		(collection, item): &(Nfts::CollectionId, Nfts::ItemId),
+	) -> UsageRestrictions {
+		let attribute = |key| Nfts::attribute(collection, item, key);
+		UsageRestrictions {
+			expiry: attribute(EXPIRY_ATTRIBUTE)
+				.map(|value| u64::decode(&mut &value[..]).unwrap_or_default()),
+			purposes: attribute(PURPOSES_ATTRIBUTE)
+				.map(|value| u32::decode(&mut &value[..]).unwrap_or_default()),
+			non_transferable: attribute(NON_TRANSFERABLE_ATTRIBUTE)
+				.map_or(false, |value| bool::decode(&mut &value[..]).unwrap_or(true)),
+		}
+	}
+}
+
+/// What is done with an instance.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Operation {
+	/// The instance arrives on this chain.
+	Deposit,
+	/// The instance leaves this chain.
+	Withdraw,
+	/// The instance changes its owner on this chain.
+	Transfer,
+}
+
+/// Wraps the [`TransactAsset`] `Inner` of unique instances and enforces their
+/// [`UsageRestrictions`].
+///
+/// * Non-transferable instances can not be deposited, withdrawn or transferred at all. This fails
+///   with [`XcmError::NoPermission`].
+/// * Expired instances can not be deposited, withdrawn or transferred. Depositing fails with
+///   [`XcmError::NotDepositable`], withdrawing and transferring with [`XcmError::NotWithdrawable`].
+/// * Instances can only be deposited if they may be used for at least one of the purposes in
+///   `AllowedPurposes`, which are the purposes of this chain. Otherwise this fails with
+///   [`XcmError::NotDepositable`].
+/// * Restricted instances can only be deposited into or transferred to the sovereign account of
+///   another chain, or teleported to it, if the chain is in `Enforcers`. Otherwise depositing and
+///   transferring fail with [`XcmError::NotDepositable`] and teleporting with
+///   [`XcmError::UntrustedTeleportLocation`]. See [the module docs](self) for why.
+///
+/// The current time is read from `Time`. Assets that are not matched by `Matcher` are passed to
+/// `Inner` without checks.
+pub struct RestrictedInstancesAdapter<
+	Inner,
+	Id,
+	Matcher,
+	Restrictions,
+	AllowedPurposes,
+	Enforcers,
+	Time,
+>(PhantomData<(Inner, Id, Matcher, Restrictions, AllowedPurposes, Enforcers, Time)>);
+
+impl<Inner, Id, Matcher, Restrictions, AllowedPurposes, Enforcers, Time>
+	RestrictedInstancesAdapter<Inner, Id, Matcher, Restrictions, AllowedPurposes, Enforcers, Time>
+where
+	Matcher: MatchesInstance<Id>,
+	Restrictions: InspectUsageRestrictions<Id>,
+	AllowedPurposes: Get<u32>,
+	Enforcers: Contains<Location>,
+	Time: UnixTime,
+{
+	/// Checks the restrictions of `what` and, if it moves to another chain, that the chain
+	/// enforces them.
+	fn ensure_allowed_to(what: &Asset, operation: Operation, to: &Location) -> XcmResult {
+		let Some(restrictions) = Self::ensure_allowed(what, operation)? else { return Ok(()) };
+		if restrictions.is_unrestricted() || !is_chain(to) || Enforcers::contains(to) {
+			return Ok(())
+		}
+		let error = match operation {
+			Operation::Withdraw => XcmError::UntrustedTeleportLocation,
+			Operation::Deposit | Operation::Transfer => XcmError::NotDepositable,
+		};
+		tracing::debug!(
+			target: LOG_TARGET,
+			?what,
+			?to,
+			?restrictions,
+			?error,
+			"Restricted instance sent to a chain that does not enforce its restrictions",
+		);
+		Err(error)
+	}
+
+	/// Checks the restrictions of `what` and returns them, or `None` if `what` is not matched.
+	fn ensure_allowed(
+		what: &Asset,
+		operation: Operation,
+	) -> Result<Option<UsageRestrictions>, XcmError> {
+		let Ok(id) = Matcher::matches_instance(what) else { return Ok(None) };
+		let restrictions = Restrictions::usage_restrictions(&id);
+
+		let error = if restrictions.non_transferable {
+			XcmError::NoPermission
+		} else if restrictions.expiry.map_or(false, |expiry| Time::now().as_secs() >= expiry) {
+			match operation {
+				Operation::Deposit => XcmError::NotDepositable,
+				Operation::Withdraw | Operation::Transfer => XcmError::NotWithdrawable,
+			}
+		} else if operation == Operation::Deposit &&
+			restrictions
+				.purposes
+				.map_or(false, |purposes| purposes & AllowedPurposes::get() == 0)
+		{
+			XcmError::NotDepositable
+		} else {
+			return Ok(Some(restrictions))
+		};
+
+		tracing::debug!(
+			target: LOG_TARGET,
+			?what,
+			?operation,
+			?restrictions,
+			?error,
+			"Usage restrictions violated",
+		);
+		Err(error)
+	}
+}
+
+/// Whether `location` is another chain, or the sovereign account of one, rather than an account
+/// on this chain.
+fn is_chain(location: &Location) -> bool {
+	location.parent_count() > 0 ||
+		matches!(location.first_interior(), Some(Parachain(_) | GlobalConsensus(_)))
+}
+
+impl<Inner, Id, Matcher, Restrictions, AllowedPurposes, Enforcers, Time> TransactAsset
+	for RestrictedInstancesAdapter<Inner, Id, Matcher, Restrictions, AllowedPurposes, Enforcers, Time>
+where
+	Inner: TransactAsset,
+	Matcher: MatchesInstance<Id>,
+	Restrictions: InspectUsageRestrictions<Id>,
+	AllowedPurposes: Get<u32>,
+	Enforcers: Contains<Location>,
+	Time: UnixTime,
+{
+	fn can_check_in(origin: &Location, what: &Asset, context: &XcmContext) -> XcmResult {
+		Self::ensure_allowed(what, Operation::Deposit)?;
+		Inner::can_check_in(origin, what, context)
+	}
+
+	fn check_in(origin: &Location, what: &Asset, context: &XcmContext) {
+		Inner::check_in(origin, what, context)
+	}
+
+	fn can_check_out(dest: &Location, what: &Asset, context: &XcmContext) -> XcmResult {
+		Self::ensure_allowed_to(what, Operation::Withdraw, dest)?;
+		Inner::can_check_out(dest, what, context)
+	}
+
+	fn check_out(dest: &Location, what: &Asset, context: &XcmContext) {
+		Inner::check_out(dest, what, context)
+	}
+
+	fn deposit_asset(what: &Asset, who: &Location, context: Option<&XcmContext>) -> XcmResult {
+		Self::ensure_allowed_to(what, Operation::Deposit, who)?;
+		Inner::deposit_asset(what, who, context)
+	}
+
+	fn deposit_asset_with_surplus(
+		what: &Asset,
+		who: &Location,
+		context: Option<&XcmContext>,
+	) -> Result<Weight, XcmError> {
+		Self::ensure_allowed_to(what, Operation::Deposit, who)?;
+		Inner::deposit_asset_with_surplus(what, who, context)
+	}
+
+	fn withdraw_asset(
+		what: &Asset,
+		who: &Location,
+		maybe_context: Option<&XcmContext>,
+	) -> Result<AssetsInHolding, XcmError> {
+		Self::ensure_allowed(what, Operation::Withdraw)?;
+		Inner::withdraw_asset(what, who, maybe_context)
+	}
+
+	fn withdraw_asset_with_surplus(
+		what: &Asset,
+		who: &Location,
+		maybe_context: Option<&XcmContext>,
+	) -> Result<(AssetsInHolding, Weight), XcmError> {
+		Self::ensure_allowed(what, Operation::Withdraw)?;
+		Inner::withdraw_asset_with_surplus(what, who, maybe_context)
+	}
+
+	fn internal_transfer_asset(
+		asset: &Asset,
+		from: &Location,
+		to: &Location,
+		context: &XcmContext,
+	) -> Result<AssetsInHolding, XcmError> {
+		Self::ensure_allowed_to(asset, Operation::Transfer, to)?;
+		Inner::internal_transfer_asset(asset, from, to, context)
+	}
+
+	fn internal_transfer_asset_with_surplus(
+		asset: &Asset,
+		from: &Location,
+		to: &Location,
+		context: &XcmContext,
+	) -> Result<(AssetsInHolding, Weight), XcmError> {
+		Self::ensure_allowed_to(asset, Operation::Transfer, to)?;
+		Inner::internal_transfer_asset_with_surplus(asset, from, to, context)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use codec::Encode;
+	use core::time::Duration;
+	use frame_support::{parameter_types, traits::Equals};
+	use std::{cell::RefCell, collections::BTreeMap};
+
+	type Id = (u32, u32);
+
+	thread_local! {
+		static ATTRIBUTES: RefCell<BTreeMap<(Id, Vec<u8>), Vec<u8>>> = RefCell::new(BTreeMap::new());
+		static NOW: RefCell<u64> = RefCell::new(0);
+	}
+
+	struct TestNfts;
+	impl nonfungibles_v2::Inspect<u64> for TestNfts {
+		type ItemId = u32;
+		type CollectionId = u32;
+
+		fn owner(_collection: &u32, _item: &u32) -> Option<u64> {
+			Some(1)
+		}
+
+		fn attribute(collection: &u32, item: &u32, key: &[u8]) -> Option<Vec<u8>> {
+			ATTRIBUTES.with(|a| a.borrow().get(&((*collection, *item), key.to_vec())).cloned())
+		}
+	}
+
+	struct TestTime;
+	impl UnixTime for TestTime {
+		fn now() -> Duration {
+			Duration::from_secs(NOW.with(|now| *now.borrow()))
+		}
+	}
+
+	/// Matches `(GeneralIndex(collection), Index(item))` instances.
+	struct TestMatcher;
+	impl MatchesInstance<Id> for TestMatcher {
+		fn matches_instance(a: &Asset) -> Result<Id, xcm_executor::traits::Error> {
+			match (a.id.0.unpack(), &a.fun) {
+				((0, [GeneralIndex(collection)]), NonFungible(Index(item))) =>
+					Ok((*collection as u32, *item as u32)),
+				_ => Err(xcm_executor::traits::Error::AssetNotHandled),
+			}
+		}
+	}
+
+	/// Accepts everything.
+	struct TestTransactor;
+	impl TransactAsset for TestTransactor {
+		fn can_check_in(_origin: &Location, _what: &Asset, _context: &XcmContext) -> XcmResult {
+			Ok(())
+		}
+
+		fn deposit_asset(_what: &Asset, _who: &Location, _: Option<&XcmContext>) -> XcmResult {
+			Ok(())
+		}
+
+		fn withdraw_asset(
+			what: &Asset,
+			_who: &Location,
+			_: Option<&XcmContext>,
+		) -> Result<AssetsInHolding, XcmError> {
+			Ok(what.clone().into())
+		}
+
+		fn internal_transfer_asset(
+			what: &Asset,
+			_from: &Location,
+			_to: &Location,
+			_context: &XcmContext,
+		) -> Result<AssetsInHolding, XcmError> {
+			Ok(what.clone().into())
+		}
+	}
+
+	parameter_types! {
+		pub const AllowedPurposes: u32 = 0b0110;
+		pub Enforcer: Location = Location::new(1, [Parachain(2000)]);
+	}
+
+	type Transactor = RestrictedInstancesAdapter<
+		TestTransactor,
+		Id,
+		TestMatcher,
+		UsageRestrictionsFromAttributes<TestNfts, u64>,
+		AllowedPurposes,
+		Equals<Enforcer>,
+		TestTime,
+	>;
+
+	fn licence(item: u32) -> Asset {
+		(GeneralIndex(1), Index(item as u128)).into()
+	}
+
+	fn set_attribute(item: u32, key: &[u8], value: impl Encode) {
+		ATTRIBUTES.with(|a| a.borrow_mut().insert(((1, item), key.to_vec()), value.encode()));
+	}
+
+	fn transact(asset: &Asset) -> [XcmResult; 3] {
+		let who = Location::new(0, [AccountIndex64 { network: None, index: 1 }]);
+		let context = XcmContext::with_message_id([0; 32]);
+		[
+			Transactor::deposit_asset(asset, &who, Some(&context)),
+			Transactor::withdraw_asset(asset, &who, Some(&context)).map(|_| ()),
+			Transactor::transfer_asset(asset, &who, &who, &context).map(|_| ()),
+		]
+	}
+
+	#[test]
+	fn unrestricted_instances_are_transacted() {
+		assert_eq!(transact(&licence(1)), [Ok(()), Ok(()), Ok(())]);
+		// Assets not matched by the matcher are not checked.
+		assert_eq!(transact(&(Here, 10u128).into()), [Ok(()), Ok(()), Ok(())]);
+	}
+
+	#[test]
+	fn non_transferable_instances_are_rejected() {
+		set_attribute(1, NON_TRANSFERABLE_ATTRIBUTE, true);
+		set_attribute(2, NON_TRANSFERABLE_ATTRIBUTE, false);
+
+		let no_permission = Err(XcmError::NoPermission);
+		assert_eq!(
+			transact(&licence(1)),
+			[no_permission.clone(), no_permission.clone(), no_permission]
+		);
+		assert_eq!(transact(&licence(2)), [Ok(()), Ok(()), Ok(())]);
+	}
+
+	#[test]
+	fn expired_instances_are_rejected() {
+		set_attribute(1, EXPIRY_ATTRIBUTE, 100u64);
+		NOW.with(|now| *now.borrow_mut() = 99);
+		assert_eq!(transact(&licence(1)), [Ok(()), Ok(()), Ok(())]);
+
+		NOW.with(|now| *now.borrow_mut() = 100);
+		assert_eq!(
+			transact(&licence(1)),
+			[
+				Err(XcmError::NotDepositable),
+				Err(XcmError::NotWithdrawable),
+				Err(XcmError::NotWithdrawable)
+			]
+		);
+	}
+
+	#[test]
+	fn instances_are_only_deposited_for_allowed_purposes() {
+		set_attribute(1, PURPOSES_ATTRIBUTE, 0b0011u32);
+		set_attribute(2, PURPOSES_ATTRIBUTE, 0b1001u32);
+
+		assert_eq!(transact(&licence(1)), [Ok(()), Ok(()), Ok(())]);
+		assert_eq!(transact(&licence(2)), [Err(XcmError::NotDepositable), Ok(()), Ok(())]);
+	}
+
+	#[test]
+	fn undecodable_attributes_restrict_the_instance() {
+		set_attribute(1, EXPIRY_ATTRIBUTE, [1u8; 2]);
+		set_attribute(2, PURPOSES_ATTRIBUTE, [1u8; 2]);
+		set_attribute(3, NON_TRANSFERABLE_ATTRIBUTE, 2u8);
+
+		let restrictions =
+			|item| UsageRestrictionsFromAttributes::<TestNfts, u64>::usage_restrictions(&(1, item));
+		assert_eq!(restrictions(1).expiry, Some(0));
+		assert_eq!(restrictions(2).purposes, Some(0));
+		assert!(restrictions(3).non_transferable);
+	}
+
+	#[test]
+	fn restricted_instances_only_leave_for_enforcers() {
+		set_attribute(1, EXPIRY_ATTRIBUTE, u64::MAX);
+
+		let context = XcmContext::with_message_id([0; 32]);
+		let owner = Location::new(0, [AccountIndex64 { network: None, index: 1 }]);
+		let leave = |item, to: Location| {
+			[
+				Transactor::deposit_asset(&licence(item), &to, Some(&context)),
+				Transactor::transfer_asset(&licence(item), &owner, &to, &context).map(|_| ()),
+				Transactor::can_check_out(&to, &licence(item), &context),
+			]
+		};
+
+		assert_eq!(leave(1, Enforcer::get()), [Ok(()), Ok(()), Ok(())]);
+		assert_eq!(
+			leave(1, Location::new(1, [Parachain(3000)])),
+			[
+				Err(XcmError::NotDepositable),
+				Err(XcmError::NotDepositable),
+				Err(XcmError::UntrustedTeleportLocation)
+			]
+		);
+		assert_eq!(leave(1, Location::new(0, [Parachain(3000)]))[0], Err(XcmError::NotDepositable));
+		// Accounts on this chain and unrestricted instances are not affected.
+		assert_eq!(leave(1, owner.clone()), [Ok(()), Ok(()), Ok(())]);
+		assert_eq!(leave(2, Location::new(1, [Parachain(3000)])), [Ok(()), Ok(()), Ok(())]);
+	}
+}
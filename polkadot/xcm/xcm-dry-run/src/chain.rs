@@ -0,0 +1,280 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The chains of the simulated network and how to call into their runtimes.
+
+use codec::Decode;
+use remote_externalities::{Builder, Mode, OfflineConfig, OnlineConfig, SnapshotConfig};
+use sc_cli::Result;
+use sc_executor::WasmExecutor;
+use sp_core::{storage::well_known_keys, traits::CallContext};
+use sp_runtime::{generic, traits::BlakeTwo256, OpaqueExtrinsic};
+use sp_state_machine::{
+	backend::BackendRuntimeCode, Backend, InMemoryBackend, OverlayedChanges, StateMachine,
+};
+use std::{borrow::Cow, cell::RefCell, fmt, fs, path::Path, str::FromStr};
+use subxt::{ext::scale_value, Metadata};
+use xcm::latest::prelude::*;
+
+/// Host functions the runtimes may use.
+pub type HostFunctions = (
+	sp_io::SubstrateHostFunctions,
+	cumulus_primitives_proof_size_hostfunction::storage_proof_size::HostFunctions,
+);
+
+/// The block type of all relay and parachains, only used to fetch the state.
+type Block = generic::Block<generic::Header<u32, BlakeTwo256>, OpaqueExtrinsic>;
+
+/// Identifies a chain of the simulated network.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ChainId {
+	/// The relay chain.
+	Relay,
+	/// The parachain with the given id.
+	Para(u32),
+}
+
+impl ChainId {
+	/// Location of this chain, relative to the relay chain.
+	pub fn location(&self) -> Location {
+		match self {
+			Self::Relay => Location::here(),
+			Self::Para(id) => Location::new(0, [Parachain(*id)]),
+		}
+	}
+
+	/// Returns the chain `dest` points to, if `dest` is relative to this chain.
+	///
+	/// Returns `None` for destinations outside of the consensus of the relay chain.
+	pub fn resolve(&self, dest: &Location) -> Option<Self> {
+		let dest = self.location().appended_with(dest.clone()).ok()?;
+		if dest.parent_count() > 0 {
+			return None
+		}
+		match dest.first_interior() {
+			None => Some(Self::Relay),
+			Some(Parachain(id)) => Some(Self::Para(*id)),
+			Some(_) => None,
+		}
+	}
+
+	/// Location of this chain as seen from `other`.
+	pub fn seen_from(&self, other: &Self) -> Location {
+		match (self, other) {
+			(this, other) if this == other => Location::here(),
+			(Self::Relay, Self::Para(_)) => Location::parent(),
+			(Self::Para(id), Self::Relay) => Location::new(0, [Parachain(*id)]),
+			(Self::Para(id), Self::Para(_)) => Location::new(1, [Parachain(*id)]),
+			_ => unreachable!("all combinations are covered; qed"),
+		}
+	}
+}
+
+impl FromStr for ChainId {
+	type Err = String;
+
+	fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+		match s {
+			"relay" => Ok(Self::Relay),
+			id => id
+				.parse()
+				.map(Self::Para)
+				.map_err(|_| format!("Invalid chain `{id}`, expected `relay` or a para id")),
+		}
+	}
+}
+
+impl fmt::Display for ChainId {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Self::Relay => write!(f, "relay"),
+			Self::Para(id) => write!(f, "para-{id}"),
+		}
+	}
+}
+
+/// The runtime of a chain of the simulated network.
+///
+/// Implemented by [`Chain`], the path is followed through this trait so that it can be tested
+/// without loading real chains.
+pub trait ChainRuntime {
+	/// The id of the chain.
+	fn id(&self) -> ChainId;
+
+	/// `spec_name` and `spec_version` of the runtime.
+	fn runtime(&self) -> &str;
+
+	/// Calls `method` of the runtime and keeps its changes, so that later calls see them.
+	fn call(&self, method: &str, data: &[u8]) -> Result<Vec<u8>>;
+
+	/// Calls `method` of the runtime and throws its changes away.
+	fn query(&self, method: &str, data: &[u8]) -> Result<Vec<u8>>;
+
+	/// Decodes a list of runtime events from `input` with the metadata of the runtime.
+	fn decode_events(&self, input: &mut &[u8]) -> Result<Vec<String>>;
+}
+
+/// A chain of the simulated network, with its state and runtime.
+pub struct Chain {
+	id: ChainId,
+	runtime: String,
+	backend: InMemoryBackend<BlakeTwo256>,
+	/// The changes of all calls on top of the loaded state.
+	overlay: RefCell<OverlayedChanges<BlakeTwo256>>,
+	executor: WasmExecutor<HostFunctions>,
+	metadata: Metadata,
+}
+
+impl Chain {
+	/// Loads the state of a chain.
+	///
+	/// `source` is either the RPC url of a node, in which case the latest state is downloaded, or
+	/// the path to a state snapshot. The runtime in the state can be replaced with `runtime`.
+	pub async fn load(id: ChainId, source: &str, runtime: Option<&Path>) -> Result<Self> {
+		log::info!("Loading the state of {id} from {source}");
+		let mode = if source.starts_with("ws://") || source.starts_with("wss://") {
+			Mode::Online(OnlineConfig {
+				transport: source.to_string().into(),
+				..Default::default()
+			})
+		} else {
+			Mode::Offline(OfflineConfig { state_snapshot: SnapshotConfig::new(source) })
+		};
+		let mut ext = Builder::<Block>::new().mode(mode).build().await?;
+		if let Some(path) = runtime {
+			log::info!("Overriding the runtime of {id} with {}", path.display());
+			ext.insert(well_known_keys::CODE.to_vec(), fs::read(path)?);
+		}
+		Self::new(id, ext.as_backend())
+	}
+
+	/// Creates a chain with the given state.
+	pub fn new(id: ChainId, backend: InMemoryBackend<BlakeTwo256>) -> Result<Self> {
+		let executor = WasmExecutor::<HostFunctions>::builder()
+			.with_allow_missing_host_functions(true)
+			.build();
+		let code = backend
+			.storage(well_known_keys::CODE)
+			.map_err(|e| format!("Failed to read the runtime code of {id}: {e}"))?
+			.ok_or_else(|| format!("Runtime code of {id} not found in state"))?;
+		let metadata =
+			sc_runtime_utilities::fetch_latest_metadata_from_code_blob(&executor, Cow::Owned(code))
+				.map_err(|e| format!("Failed to fetch the metadata of {id}: {e}"))?;
+		let metadata = Metadata::decode(&mut (*metadata).as_slice())?;
+
+		let mut chain = Self {
+			id,
+			runtime: String::new(),
+			backend,
+			overlay: Default::default(),
+			executor,
+			metadata,
+		};
+		let version =
+			sp_version::RuntimeVersion::decode(&mut &chain.query("Core_version", &[])?[..])?;
+		chain.runtime = format!("{}-{}", version.spec_name, version.spec_version);
+
+		Ok(chain)
+	}
+
+	/// Calls `method` of the runtime on top of the loaded state and the changes in `overlay`.
+	fn execute(
+		&self,
+		overlay: &mut OverlayedChanges<BlakeTwo256>,
+		method: &str,
+		data: &[u8],
+	) -> Result<Vec<u8>> {
+		let runtime_code = BackendRuntimeCode::new(&self.backend);
+		let runtime_code = runtime_code.runtime_code()?;
+
+		StateMachine::new(
+			&self.backend,
+			overlay,
+			&self.executor,
+			method,
+			data,
+			&mut Default::default(),
+			&runtime_code,
+			CallContext::Offchain,
+		)
+		.execute()
+		.map_err(|e| format!("Failed to call `{method}` of {}: {e}", self.id).into())
+	}
+}
+
+impl ChainRuntime for Chain {
+	fn id(&self) -> ChainId {
+		self.id
+	}
+
+	fn runtime(&self) -> &str {
+		&self.runtime
+	}
+
+	fn call(&self, method: &str, data: &[u8]) -> Result<Vec<u8>> {
+		self.execute(&mut self.overlay.borrow_mut(), method, data)
+	}
+
+	fn query(&self, method: &str, data: &[u8]) -> Result<Vec<u8>> {
+		let mut overlay = self.overlay.borrow_mut();
+		overlay.start_transaction();
+		let result = self.execute(&mut overlay, method, data);
+		overlay.rollback_transaction().expect("the transaction was started above; qed");
+		result
+	}
+
+	fn decode_events(&self, input: &mut &[u8]) -> Result<Vec<String>> {
+		let len = codec::Compact::<u32>::decode(input)?.0;
+		let ty = self.metadata.outer_enums().event_enum_ty();
+
+		(0..len)
+			.map(|_| {
+				scale_value::scale::decode_as_type(input, ty, self.metadata.types())
+					.map(|event| event.to_string())
+					.map_err(|e| format!("Failed to decode an event of {}: {e}", self.id).into())
+			})
+			.collect()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn resolve_works() {
+		let relay = ChainId::Relay;
+		let para = ChainId::Para(1000);
+
+		assert_eq!(relay.resolve(&Location::here()), Some(relay));
+		assert_eq!(relay.resolve(&Location::new(0, [Parachain(1000)])), Some(para));
+		assert_eq!(relay.resolve(&Location::parent()), None);
+		assert_eq!(para.resolve(&Location::parent()), Some(relay));
+		assert_eq!(para.resolve(&Location::new(1, [Parachain(2000)])), Some(ChainId::Para(2000)));
+		assert_eq!(para.resolve(&Location::new(2, [GlobalConsensus(Kusama)])), None);
+	}
+
+	#[test]
+	fn seen_from_is_inverse_of_resolve() {
+		let chains = [ChainId::Relay, ChainId::Para(1000), ChainId::Para(2000)];
+		for from in chains {
+			for to in chains {
+				// The location of `from` as seen by `to` leads back to `from`.
+				assert_eq!(to.resolve(&from.seen_from(&to)), Some(from));
+			}
+		}
+	}
+}
@@ -0,0 +1,220 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{
+	chain::{Chain, ChainId, ChainRuntime},
+	path::{self, Start},
+	weigh,
+};
+use clap::{Parser, Subcommand};
+use codec::Decode;
+use sc_cli::Result;
+use serde::Serialize;
+use std::{collections::BTreeMap, fs, path::PathBuf};
+use xcm::{VersionedLocation, VersionedXcm};
+
+/// # XCM dry-run
+///
+/// Dry-runs an extrinsic or XCM program on one chain and follows the messages it sends through
+/// the other chains of the network, hop by hop:
+///
+/// ```sh
+/// xcm-dry-run follow --chain relay=wss://rpc.polkadot.io --chain 1000=asset-hub.snap \
+///     --chain 2000=wss://rpc.parachain.io --from 2000 --origin-caller 0x... --call 0x...
+/// ```
+///
+/// Every hop is dry-run with the `DryRunApi` of its chain. The `forwarded_xcms` of a hop are
+/// dry-run on their destination with `DryRunApi::dry_run_xcm`, if the destination is one of the
+/// given chains. The execution and delivery fees of every message are queried with the
+/// `XcmPaymentApi` and summed per asset. The report with the fees, events and outcome of every hop
+/// is printed as JSON.
+///
+/// Every hop is executed on top of the changes of the earlier hops on the same chain.
+///
+/// The weight of every instruction of an XCM program is reported with `weigh`, using the
+/// `XcmWeightBreakdownApi` of the chain:
+///
+/// ```sh
+/// xcm-dry-run weigh --chain relay=wss://rpc.polkadot.io --xcm 0x...
+/// ```
+#[derive(Debug, Parser)]
+#[clap(author, version, about, verbatim_doc_comment)]
+pub struct Command {
+	#[command(subcommand)]
+	sub: SubCommand,
+}
+
+#[derive(Debug, Subcommand)]
+enum SubCommand {
+	/// Follow an extrinsic or XCM program through the chains of the network.
+	Follow(FollowCmd),
+	/// Report the weight of every instruction of an XCM program.
+	Weigh(WeighCmd),
+}
+
+#[derive(Debug, Parser)]
+struct FollowCmd {
+	/// A chain of the network, as `<CHAIN>=<SOURCE>`.
+	///
+	/// `CHAIN` is `relay` or a para id. `SOURCE` is the RPC url of a node to download the latest
+	/// state from, or the path to a state snapshot.
+	#[arg(long = "chain", value_parser = parse_pair::<String>, required = true)]
+	chains: Vec<(ChainId, String)>,
+
+	/// Execute a chain with this runtime, as `<CHAIN>=<PATH>`.
+	#[arg(long = "runtime", value_parser = parse_pair::<PathBuf>)]
+	runtimes: Vec<(ChainId, PathBuf)>,
+
+	/// The chain where the path starts.
+	#[arg(long)]
+	from: ChainId,
+
+	/// The SCALE encoded `OriginCaller` of the extrinsic to dry-run.
+	#[arg(long, requires = "call", value_parser = parse_hex)]
+	origin_caller: Option<Vec<u8>>,
+
+	/// The SCALE encoded `RuntimeCall` of the extrinsic to dry-run.
+	#[arg(long, requires = "origin_caller", conflicts_with = "xcm", value_parser = parse_hex)]
+	call: Option<Vec<u8>>,
+
+	/// The SCALE encoded `VersionedLocation` origin of the XCM program to dry-run.
+	#[arg(long, requires = "xcm", value_parser = parse_hex)]
+	origin: Option<Vec<u8>>,
+
+	/// The SCALE encoded `VersionedXcm` program to dry-run.
+	#[arg(long, requires = "origin", required_unless_present = "call", value_parser = parse_hex)]
+	xcm: Option<Vec<u8>>,
+
+	/// Stop following messages after this many hops.
+	#[arg(long, default_value_t = 8)]
+	max_hops: u32,
+
+	/// Write the report to this file instead of stdout.
+	#[arg(long)]
+	output: Option<PathBuf>,
+}
+
+#[derive(Debug, Parser)]
+struct WeighCmd {
+	/// The chain that weighs the program, as `<CHAIN>=<SOURCE>`.
+	///
+	/// `CHAIN` is `relay` or a para id. `SOURCE` is the RPC url of a node to download the latest
+	/// state from, or the path to a state snapshot.
+	#[arg(long, value_parser = parse_pair::<String>)]
+	chain: (ChainId, String),
+
+	/// Weigh the program with this runtime instead of the one in the state.
+	#[arg(long)]
+	runtime: Option<PathBuf>,
+
+	/// The SCALE encoded `VersionedXcm` program to weigh.
+	#[arg(long, value_parser = parse_hex)]
+	xcm: Vec<u8>,
+
+	/// Exit with an error if any instruction is not benchmarked.
+	#[arg(long)]
+	deny_unbenchmarked: bool,
+
+	/// Write the report to this file instead of stdout.
+	#[arg(long)]
+	output: Option<PathBuf>,
+}
+
+impl Command {
+	pub fn run(self) -> Result<()> {
+		let runtime = tokio::runtime::Builder::new_multi_thread().enable_all().build()?;
+
+		match self.sub {
+			SubCommand::Follow(cmd) => runtime.block_on(cmd.run()),
+			SubCommand::Weigh(cmd) => runtime.block_on(cmd.run()),
+		}
+	}
+}
+
+impl FollowCmd {
+	async fn run(self) -> Result<()> {
+		let start = match (self.origin_caller, self.call, self.origin, self.xcm) {
+			(Some(origin), Some(call), _, _) => Start::Call { origin, call },
+			(_, _, Some(origin), Some(xcm)) => Start::Xcm {
+				origin: VersionedLocation::decode(&mut &origin[..])?,
+				xcm: VersionedXcm::decode(&mut &xcm[..])?,
+			},
+			_ => return Err("Either `--call` or `--xcm` must be given".into()),
+		};
+
+		let mut runtimes: BTreeMap<_, _> = self.runtimes.into_iter().collect();
+		let mut chains = Vec::with_capacity(self.chains.len());
+		for (id, source) in self.chains {
+			if chains.iter().any(|chain: &Chain| chain.id() == id) {
+				return Err(format!("Chain {id} is given more than once").into())
+			}
+			chains.push(Chain::load(id, &source, runtimes.remove(&id).as_deref()).await?);
+		}
+		if let Some(id) = runtimes.keys().next() {
+			return Err(format!("Runtime given for {id}, which is not a loaded chain").into())
+		}
+
+		let report = path::follow(&chains, self.from, start, self.max_hops)?;
+		match &report.failure {
+			Some(failure) => log::warn!("{failure}"),
+			None => log::info!("All {} hops succeeded", report.hops.len()),
+		}
+
+		write_report(&report, self.output.as_ref())
+	}
+}
+
+impl WeighCmd {
+	async fn run(self) -> Result<()> {
+		let xcm = VersionedXcm::decode(&mut &self.xcm[..])?;
+		let (id, source) = self.chain;
+		let chain = Chain::load(id, &source, self.runtime.as_deref()).await?;
+
+		let report = weigh::weigh(&chain, xcm)?;
+		write_report(&report, self.output.as_ref())?;
+
+		if self.deny_unbenchmarked && report.flagged > 0 {
+			return Err(format!("{} instructions are not benchmarked", report.flagged).into())
+		}
+		Ok(())
+	}
+}
+
+/// Writes `report` as JSON to `output`, or to stdout.
+fn write_report(report: &impl Serialize, output: Option<&PathBuf>) -> Result<()> {
+	let json = serde_json::to_string_pretty(report)
+		.map_err(|e| format!("Failed to serialize the report: {e}"))?;
+	match output {
+		Some(path) => {
+			fs::write(path, json)?;
+			log::info!("Report written to {}", path.display());
+		},
+		None => println!("{json}"),
+	}
+
+	Ok(())
+}
+
+fn parse_pair<T: From<String>>(s: &str) -> std::result::Result<(ChainId, T), String> {
+	let (chain, value) = s
+		.split_once('=')
+		.ok_or_else(|| format!("Expected `<CHAIN>=<VALUE>`, got `{s}`"))?;
+	Ok((chain.parse()?, T::from(value.to_string())))
+}
+
+fn parse_hex(s: &str) -> std::result::Result<Vec<u8>, String> {
+	array_bytes::hex2bytes(s).map_err(|e| format!("Invalid hex: {e:?}"))
+}
@@ -0,0 +1,605 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Follows the messages of a dry-run from chain to chain.
+
+use crate::chain::{ChainId, ChainRuntime};
+use codec::{Decode, Encode};
+use frame_support::dispatch::DispatchResultWithPostInfo;
+use sc_cli::Result;
+use serde::Serialize;
+use sp_weights::Weight;
+use std::collections::{BTreeMap, VecDeque};
+use xcm::{latest::prelude::*, VersionedAssetId, VersionedAssets, VersionedLocation, VersionedXcm};
+use xcm_runtime_apis::{dry_run, fees};
+
+/// Messages forwarded from one chain to another.
+type ForwardedXcms = Vec<(VersionedLocation, Vec<VersionedXcm<()>>)>;
+
+/// Where the path starts.
+pub enum Start {
+	/// Dry-run an extrinsic with `DryRunApi::dry_run_call`.
+	Call {
+		/// The SCALE encoded `OriginCaller` of the runtime.
+		origin: Vec<u8>,
+		/// The SCALE encoded `RuntimeCall` of the runtime.
+		call: Vec<u8>,
+	},
+	/// Dry-run an XCM program with `DryRunApi::dry_run_xcm`.
+	Xcm {
+		/// The origin of the program.
+		origin: VersionedLocation,
+		/// The program.
+		xcm: VersionedXcm<()>,
+	},
+}
+
+/// The report of a whole path.
+#[derive(Debug, Serialize)]
+pub struct PathReport {
+	/// Whether every hop of the path succeeded.
+	pub success: bool,
+	/// Description of the first hop that failed.
+	pub failure: Option<String>,
+	/// The execution and delivery fees of all hops, summed per asset.
+	pub total_fees: Vec<TotalFee>,
+	/// Every executed hop, in the order they were executed.
+	pub hops: Vec<HopReport>,
+}
+
+/// The fees of the whole path in one asset.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TotalFee {
+	/// The asset, as seen from the relay chain.
+	pub asset: String,
+	/// The summed amount.
+	pub amount: u128,
+}
+
+/// The report of a single hop.
+#[derive(Debug, Serialize)]
+pub struct HopReport {
+	/// Index of the hop that sent the message of this hop. `None` for the first hop.
+	pub parent: Option<usize>,
+	/// The chain that executed the hop.
+	pub chain: String,
+	/// The runtime of the chain.
+	pub runtime: String,
+	/// The origin of the call or XCM program.
+	pub origin: String,
+	/// The executed XCM program. `None` if the hop dry-ran an extrinsic.
+	pub xcm: Option<String>,
+	/// The result of executing the extrinsic or program.
+	pub result: String,
+	/// Whether the execution succeeded.
+	pub success: bool,
+	/// The weight and fee of executing the program.
+	pub execution_fees: Option<ExecutionFees>,
+	/// The events emitted by the execution.
+	pub events: Vec<String>,
+	/// The messages sent by the execution.
+	pub forwarded: Vec<ForwardedReport>,
+}
+
+/// Weight and fee of executing an XCM program.
+#[derive(Debug, Serialize)]
+pub struct ExecutionFees {
+	/// The weight of the program, from `XcmPaymentApi::query_xcm_weight`.
+	pub weight: String,
+	/// The fee for the weight in the first acceptable payment asset of the chain.
+	pub fee: String,
+	/// The fee as asset of the executing chain, if it could be queried.
+	#[serde(skip)]
+	pub paid: Option<Asset>,
+}
+
+/// A message sent by a hop.
+#[derive(Debug, Serialize)]
+pub struct ForwardedReport {
+	/// The destination, relative to the sending chain.
+	pub destination: String,
+	/// The index of the hop that executed the message. `None` if the destination is not part of
+	/// the simulated network.
+	pub hop: Option<usize>,
+	/// The fees the sending chain charges for delivering the message.
+	pub delivery_fees: String,
+	/// The fees as assets of the sending chain, if they could be queried.
+	#[serde(skip)]
+	pub paid: Vec<Asset>,
+}
+
+/// A message waiting to be executed.
+struct Pending {
+	/// Index of the hop that sent the message.
+	parent: usize,
+	/// Index of the message in the forwarded messages of the parent.
+	slot: usize,
+	depth: u32,
+	chain: ChainId,
+	origin: Location,
+	xcm: VersionedXcm<()>,
+}
+
+/// Dry-runs `start` on `first` and follows all forwarded messages through `chains`.
+///
+/// Messages to chains that are not in `chains` are reported but not followed. Messages are not
+/// followed further than `max_hops` hops from the start. Every hop sees the changes of the
+/// earlier hops on the same chain.
+pub fn follow<C: ChainRuntime>(
+	chains: &[C],
+	first: ChainId,
+	start: Start,
+	max_hops: u32,
+) -> Result<PathReport> {
+	let mut follower = Follower {
+		chains,
+		max_hops,
+		hops: Vec::new(),
+		queue: VecDeque::new(),
+		fees: BTreeMap::new(),
+	};
+	let chain = follower.chain(first).ok_or_else(|| format!("Chain {first} was not loaded"))?;
+
+	let (hop, forwarded) = match start {
+		Start::Call { origin, call } => dry_run_call(chain, origin, call)?,
+		Start::Xcm { origin, xcm } => {
+			let origin = Location::try_from(origin)
+				.map_err(|()| "The origin can not be converted to the latest version")?;
+			dry_run_xcm(chain, None, origin, xcm)?
+		},
+	};
+	follower.push(first, hop);
+	follower.enqueue(0, 0, first, forwarded);
+
+	while let Some(Pending { parent, slot, depth, chain, origin, xcm }) = follower.queue.pop_front()
+	{
+		let chain = follower.chain(chain).expect("only loaded chains are enqueued; qed");
+		let (hop, forwarded) = dry_run_xcm(chain, Some(parent), origin, xcm)?;
+		let index = follower.push(chain.id(), hop);
+		follower.hops[parent].forwarded[slot].hop = Some(index);
+		follower.enqueue(index, depth + 1, chain.id(), forwarded);
+	}
+
+	let hops = follower.hops;
+	let failure = hops.iter().position(|hop| !hop.success).map(|index| {
+		format!("Hop {index} on {} failed: {}", hops[index].chain, hops[index].result)
+	});
+	let total_fees = follower
+		.fees
+		.into_iter()
+		.map(|(asset, amount)| TotalFee { asset: format!("{asset:?}"), amount })
+		.collect();
+	Ok(PathReport { success: failure.is_none(), failure, total_fees, hops })
+}
+
+/// The state of following a path.
+struct Follower<'a, C> {
+	chains: &'a [C],
+	max_hops: u32,
+	hops: Vec<HopReport>,
+	queue: VecDeque<Pending>,
+	/// The fees paid so far, per asset as seen from the relay chain.
+	fees: BTreeMap<AssetId, u128>,
+}
+
+impl<'a, C: ChainRuntime> Follower<'a, C> {
+	fn chain(&self, id: ChainId) -> Option<&'a C> {
+		self.chains.iter().find(|chain| chain.id() == id)
+	}
+
+	/// Adds the report of a hop executed on `chain` and returns its index.
+	fn push(&mut self, chain: ChainId, hop: HopReport) -> usize {
+		if let Some(paid) = hop.execution_fees.as_ref().and_then(|fees| fees.paid.clone()) {
+			self.add_fee(chain, paid);
+		}
+		self.hops.push(hop);
+		self.hops.len() - 1
+	}
+
+	/// Adds a fungible `fee` paid on `chain` to the total fees.
+	fn add_fee(&mut self, chain: ChainId, fee: Asset) {
+		let Fungible(amount) = fee.fun else { return };
+		let Ok(asset) = chain.location().appended_with(fee.id.0) else {
+			log::warn!("Not counting a fee on {chain}, the asset can not be seen from the relay");
+			return
+		};
+		let total = self.fees.entry(AssetId(asset)).or_default();
+		*total = total.saturating_add(amount);
+	}
+
+	/// Reports the messages forwarded by hop `index` and enqueues the ones that can be followed.
+	fn enqueue(&mut self, index: usize, depth: u32, from: ChainId, forwarded: ForwardedXcms) {
+		let sender = self.chain(from).expect("only loaded chains send messages; qed");
+		for (destination, messages) in forwarded {
+			let target = Location::try_from(destination.clone())
+				.ok()
+				.and_then(|dest| from.resolve(&dest))
+				.filter(|target| self.chain(*target).is_some());
+
+			for xcm in messages {
+				let report = delivery_fees(sender, &destination, &xcm);
+				for fee in report.paid.clone() {
+					self.add_fee(from, fee);
+				}
+				let forwarded = &mut self.hops[index].forwarded;
+				forwarded.push(report);
+				let slot = forwarded.len() - 1;
+
+				match target {
+					Some(chain) if depth < self.max_hops => {
+						let origin = from.seen_from(&chain);
+						self.queue.push_back(Pending {
+							parent: index,
+							slot,
+							depth,
+							chain,
+							origin,
+							xcm,
+						});
+					},
+					Some(_) =>
+						log::warn!("Not following the message to {destination:?}, too many hops"),
+					None => log::info!(
+						"Not following the message to {destination:?}, the chain is not loaded"
+					),
+				}
+			}
+		}
+	}
+}
+
+/// Dry-runs an extrinsic on `chain`.
+fn dry_run_call(
+	chain: &impl ChainRuntime,
+	origin: Vec<u8>,
+	call: Vec<u8>,
+) -> Result<(HopReport, ForwardedXcms)> {
+	log::info!("Dry-running the call on {}", chain.id());
+	// The origin and call are already encoded, so they are concatenated.
+	let mut data = origin.clone();
+	data.extend(&call);
+	data.extend(XCM_VERSION.encode());
+	let output = chain.call("DryRunApi_dry_run_call", &data)?;
+
+	let mut input = &output[..];
+	let mut hop = HopReport {
+		parent: None,
+		chain: chain.id().to_string(),
+		runtime: chain.runtime().to_string(),
+		origin: array_bytes::bytes2hex("0x", &origin),
+		xcm: None,
+		result: String::new(),
+		success: false,
+		execution_fees: None,
+		events: Vec::new(),
+		forwarded: Vec::new(),
+	};
+	if let Err(e) = decode_api_result(&mut input)? {
+		hop.result = format!("DryRunApi error: {e:?}");
+		return Ok((hop, Vec::new()))
+	}
+
+	let result = DispatchResultWithPostInfo::decode(&mut input)?;
+	hop.success = result.is_ok();
+	hop.result = format!("{result:?}");
+	hop.events = chain.decode_events(&mut input)?;
+	let local_xcm = Option::<VersionedXcm<()>>::decode(&mut input)?;
+	hop.xcm = local_xcm.as_ref().map(|xcm| format!("{xcm:?}"));
+	hop.execution_fees = local_xcm.map(|xcm| execution_fees(chain, xcm));
+	let forwarded = ForwardedXcms::decode(&mut input)?;
+
+	Ok((hop, forwarded))
+}
+
+/// Dry-runs an XCM program on `chain`.
+fn dry_run_xcm(
+	chain: &impl ChainRuntime,
+	parent: Option<usize>,
+	origin: Location,
+	xcm: VersionedXcm<()>,
+) -> Result<(HopReport, ForwardedXcms)> {
+	log::info!("Dry-running an XCM from {origin:?} on {}", chain.id());
+	// `VersionedXcm<()>` and `VersionedXcm<RuntimeCall>` have the same encoding.
+	let data = (VersionedLocation::from(origin.clone()), &xcm).encode();
+	let output = chain.call("DryRunApi_dry_run_xcm", &data)?;
+
+	let mut input = &output[..];
+	let mut hop = HopReport {
+		parent,
+		chain: chain.id().to_string(),
+		runtime: chain.runtime().to_string(),
+		origin: format!("{origin:?}"),
+		xcm: Some(format!("{xcm:?}")),
+		result: String::new(),
+		success: false,
+		execution_fees: Some(execution_fees(chain, xcm)),
+		events: Vec::new(),
+		forwarded: Vec::new(),
+	};
+	if let Err(e) = decode_api_result(&mut input)? {
+		hop.result = format!("DryRunApi error: {e:?}");
+		return Ok((hop, Vec::new()))
+	}
+
+	let outcome = Outcome::decode(&mut input)?;
+	hop.success = matches!(outcome, Outcome::Complete { .. });
+	hop.result = format!("{outcome:?}");
+	hop.events = chain.decode_events(&mut input)?;
+	let forwarded = ForwardedXcms::decode(&mut input)?;
+
+	Ok((hop, forwarded))
+}
+
+/// Decodes the `Result` variant of a `DryRunApi` result.
+///
+/// On `Ok`, the input is left at the effects, which are decoded field by field because the
+/// events are only known to the metadata.
+fn decode_api_result(input: &mut &[u8]) -> Result<std::result::Result<(), dry_run::Error>> {
+	match u8::decode(input)? {
+		0 => Ok(Ok(())),
+		1 => Ok(Err(dry_run::Error::decode(input)?)),
+		_ => Err("Invalid `DryRunApi` result".into()),
+	}
+}
+
+/// Returns the weight of `xcm` on `chain` and its fee in the first acceptable payment asset.
+fn execution_fees(chain: &impl ChainRuntime, xcm: VersionedXcm<()>) -> ExecutionFees {
+	let weight = query::<Weight>(chain, "query_xcm_weight", xcm.encode());
+	let fee = weight.clone().and_then(|weight| {
+		let asset = fee_asset(chain)?;
+		let fee = query::<u128>(chain, "query_weight_to_asset_fee", (weight, &asset).encode())?;
+		let asset = AssetId::try_from(asset)
+			.map_err(|()| "The fee asset can not be converted to the latest version")?;
+		Ok((asset, fee).into())
+	});
+
+	ExecutionFees {
+		weight: weight.map(|w| w.to_string()).unwrap_or_else(|e| e),
+		fee: match &fee {
+			Ok(Asset { id, fun: Fungible(amount) }) => format!("{amount} of {id:?}"),
+			Ok(asset) => format!("{asset:?}"),
+			Err(e) => e.clone(),
+		},
+		paid: fee.ok(),
+	}
+}
+
+/// Returns the fees `chain` charges for delivering `xcm` to `destination`.
+fn delivery_fees(
+	chain: &impl ChainRuntime,
+	destination: &VersionedLocation,
+	xcm: &VersionedXcm<()>,
+) -> ForwardedReport {
+	let fees = fee_asset(chain).and_then(|asset| {
+		query::<VersionedAssets>(chain, "query_delivery_fees", (destination, xcm, asset).encode())
+	});
+
+	ForwardedReport {
+		destination: format!("{destination:?}"),
+		hop: None,
+		delivery_fees: fees
+			.as_ref()
+			.map(|assets| format!("{assets:?}"))
+			.unwrap_or_else(|e| e.clone()),
+		paid: fees
+			.ok()
+			.and_then(|assets| Assets::try_from(assets).ok())
+			.map(|assets| assets.into_inner())
+			.unwrap_or_default(),
+	}
+}
+
+/// Returns the first acceptable payment asset of `chain`.
+fn fee_asset(chain: &impl ChainRuntime) -> std::result::Result<VersionedAssetId, String> {
+	query::<Vec<VersionedAssetId>>(chain, "query_acceptable_payment_assets", XCM_VERSION.encode())?
+		.into_iter()
+		.next()
+		.ok_or_else(|| format!("{} accepts no payment assets", chain.id()))
+}
+
+/// Calls `method` of the `XcmPaymentApi` and flattens all errors into a string.
+///
+/// The changes of the call are thrown away.
+fn query<T: Decode>(
+	chain: &impl ChainRuntime,
+	method: &str,
+	data: Vec<u8>,
+) -> std::result::Result<T, String> {
+	let output = chain
+		.query(&format!("XcmPaymentApi_{method}"), &data)
+		.map_err(|e| e.to_string())?;
+	std::result::Result::<T, fees::Error>::decode(&mut &output[..])
+		.map_err(|e| format!("Failed to decode the result of `{method}`: {e}"))?
+		.map_err(|e| format!("XcmPaymentApi error: {e:?}"))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use codec::Compact;
+	use frame_support::dispatch::PostDispatchInfo;
+	use std::cell::RefCell;
+
+	const WEIGHT_FEE: u128 = 100;
+	const DELIVERY_FEE: u128 = 10;
+
+	/// A chain that executes every message by forwarding `forwards`.
+	struct MockChain {
+		id: ChainId,
+		forwards: ForwardedXcms,
+		fails: bool,
+		/// The called runtime methods and whether their changes were kept.
+		calls: RefCell<Vec<(String, bool)>>,
+	}
+
+	impl MockChain {
+		fn new(id: ChainId, destinations: &[Location]) -> Self {
+			let forwards =
+				destinations.iter().map(|dest| (dest.clone().into(), vec![program()])).collect();
+			Self { id, forwards, fails: false, calls: Default::default() }
+		}
+
+		fn failing(self) -> Self {
+			Self { fails: true, ..self }
+		}
+
+		/// The asset all fees are paid in, the native asset of the relay chain.
+		fn fee_asset(&self) -> AssetId {
+			AssetId(ChainId::Relay.seen_from(&self.id))
+		}
+	}
+
+	impl ChainRuntime for MockChain {
+		fn id(&self) -> ChainId {
+			self.id
+		}
+
+		fn runtime(&self) -> &str {
+			"mock-1"
+		}
+
+		fn call(&self, method: &str, _data: &[u8]) -> Result<Vec<u8>> {
+			self.calls.borrow_mut().push((method.into(), true));
+			let forwards = if self.fails { Vec::new() } else { self.forwards.clone() };
+			Ok(match method {
+				"DryRunApi_dry_run_call" => {
+					let result: DispatchResultWithPostInfo = Ok(PostDispatchInfo::default());
+					(0u8, result, Compact(0u32), None::<VersionedXcm<()>>, forwards).encode()
+				},
+				"DryRunApi_dry_run_xcm" => {
+					let used = Weight::zero();
+					let outcome = match self.fails {
+						false => Outcome::Complete { used },
+						true => Outcome::Incomplete {
+							used,
+							error: InstructionError { index: 0, error: XcmError::Barrier },
+						},
+					};
+					(0u8, outcome, Compact(0u32), forwards).encode()
+				},
+				_ => return Err(format!("Unexpected call of {method}").into()),
+			})
+		}
+
+		fn query(&self, method: &str, _data: &[u8]) -> Result<Vec<u8>> {
+			self.calls.borrow_mut().push((method.into(), false));
+			let asset = self.fee_asset();
+			Ok(match method {
+				"XcmPaymentApi_query_acceptable_payment_assets" =>
+					ok(vec![VersionedAssetId::from(asset)]),
+				"XcmPaymentApi_query_xcm_weight" => ok(Weight::from_parts(1_000, 0)),
+				"XcmPaymentApi_query_weight_to_asset_fee" => ok(WEIGHT_FEE),
+				"XcmPaymentApi_query_delivery_fees" =>
+					ok(VersionedAssets::from(Assets::from((asset, DELIVERY_FEE)))),
+				_ => return Err(format!("Unexpected query of {method}").into()),
+			})
+		}
+
+		fn decode_events(&self, input: &mut &[u8]) -> Result<Vec<String>> {
+			assert_eq!(Compact::<u32>::decode(input)?.0, 0);
+			Ok(Vec::new())
+		}
+	}
+
+	fn ok<T: Encode>(value: T) -> Vec<u8> {
+		std::result::Result::<T, fees::Error>::Ok(value).encode()
+	}
+
+	fn program() -> VersionedXcm<()> {
+		VersionedXcm::from(Xcm::<()>(vec![ClearOrigin]))
+	}
+
+	fn start() -> Start {
+		Start::Call { origin: vec![0], call: vec![1] }
+	}
+
+	fn total(amount: u128) -> Vec<TotalFee> {
+		vec![TotalFee { asset: format!("{:?}", AssetId(Location::here())), amount }]
+	}
+
+	#[test]
+	fn follow_works() {
+		let chains = [
+			MockChain::new(ChainId::Para(2000), &[Location::parent()]),
+			MockChain::new(ChainId::Relay, &[Location::new(0, [Parachain(1000)])]),
+			MockChain::new(ChainId::Para(1000), &[]),
+		];
+
+		let report = follow(&chains, ChainId::Para(2000), start(), 8).unwrap();
+		assert!(report.success, "{:?}", report.failure);
+
+		let hops = &report.hops;
+		let visited: Vec<_> = hops.iter().map(|hop| (hop.parent, hop.chain.as_str())).collect();
+		assert_eq!(visited, [(None, "para-2000"), (Some(0), "relay"), (Some(1), "para-1000")]);
+		assert_eq!(hops[0].origin, "0x00");
+		assert_eq!(hops[1].origin, format!("{:?}", Location::new(0, [Parachain(2000)])));
+		assert_eq!(hops[2].origin, format!("{:?}", Location::parent()));
+		assert_eq!(hops[0].forwarded[0].hop, Some(1));
+		assert_eq!(hops[1].forwarded[0].hop, Some(2));
+		assert!(hops[2].forwarded.is_empty());
+
+		// The extrinsic pays no execution fee, both messages pay for execution and delivery.
+		assert!(hops[0].execution_fees.is_none());
+		assert_eq!(report.total_fees, total(2 * WEIGHT_FEE + 2 * DELIVERY_FEE));
+
+		// Dry-runs keep their changes for later hops, fee queries do not.
+		for chain in &chains {
+			for (method, kept) in chain.calls.borrow().iter() {
+				assert_eq!(*kept, method.starts_with("DryRunApi_"), "{method}");
+			}
+		}
+	}
+
+	#[test]
+	fn messages_to_unloaded_chains_are_not_followed() {
+		let chains = [MockChain::new(ChainId::Para(2000), &[Location::new(1, [Parachain(3000)])])];
+
+		let report = follow(&chains, ChainId::Para(2000), start(), 8).unwrap();
+		assert!(report.success);
+		assert_eq!(report.hops.len(), 1);
+		assert_eq!(report.hops[0].forwarded.len(), 1);
+		assert_eq!(report.hops[0].forwarded[0].hop, None);
+		assert_eq!(report.total_fees, total(DELIVERY_FEE));
+	}
+
+	#[test]
+	fn follow_stops_after_max_hops() {
+		let chains = [
+			MockChain::new(ChainId::Para(1000), &[Location::new(1, [Parachain(2000)])]),
+			MockChain::new(ChainId::Para(2000), &[Location::new(1, [Parachain(1000)])]),
+		];
+
+		let report = follow(&chains, ChainId::Para(2000), start(), 3).unwrap();
+		assert_eq!(report.hops.len(), 4);
+		let last = report.hops.last().unwrap();
+		assert_eq!(last.forwarded.len(), 1);
+		assert_eq!(last.forwarded[0].hop, None);
+	}
+
+	#[test]
+	fn failed_hop_is_reported() {
+		let chains = [
+			MockChain::new(ChainId::Para(2000), &[Location::parent()]),
+			MockChain::new(ChainId::Relay, &[Location::new(0, [Parachain(1000)])]).failing(),
+			MockChain::new(ChainId::Para(1000), &[]),
+		];
+
+		let report = follow(&chains, ChainId::Para(2000), start(), 8).unwrap();
+		assert!(!report.success);
+		assert_eq!(report.hops.len(), 2);
+		assert!(report.failure.unwrap().starts_with("Hop 1 on relay failed: Incomplete"));
+	}
+}
@@ -0,0 +1,238 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Breaks down the weight of an XCM program into the weights of its instructions.
+
+use crate::chain::{Chain, ChainRuntime};
+use codec::{Decode, Encode};
+use sc_cli::Result;
+use serde::Serialize;
+use sp_weights::Weight;
+use xcm::{latest::prelude::*, VersionedXcm};
+use xcm_runtime_apis::{
+	fees,
+	weight_breakdown::{InstructionWeight, WeightSource},
+};
+
+/// The weight breakdown of a whole program.
+#[derive(Debug, Serialize)]
+pub struct WeightReport {
+	/// The chain that weighed the program.
+	pub chain: String,
+	/// `spec_name` and `spec_version` of the runtime.
+	pub runtime: String,
+	/// The weight of the whole program.
+	pub total: String,
+	/// Number of instructions, including nested ones, that are not benchmarked.
+	pub flagged: usize,
+	/// The weight of every instruction.
+	pub instructions: Vec<InstructionReport>,
+}
+
+/// The weight of a single instruction.
+#[derive(Debug, Serialize)]
+pub struct InstructionReport {
+	/// Index of the instruction in its program.
+	pub index: usize,
+	/// Name of the instruction.
+	pub instruction: String,
+	/// The weight of the instruction.
+	pub weight: String,
+	/// Weight of the call of a `Transact` or the program of a `SetErrorHandler` or
+	/// `SetAppendix`.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub additional_weight: Option<String>,
+	/// `benchmarked`, `unmetered` if the weight is zero or `unsupported` if the weight is
+	/// `Weight::MAX`.
+	pub source: &'static str,
+	/// The weights of the nested program.
+	#[serde(skip_serializing_if = "Vec::is_empty")]
+	pub nested: Vec<InstructionReport>,
+}
+
+/// Weighs `xcm` with the `XcmWeightBreakdownApi` of `chain`.
+pub fn weigh(chain: &Chain, xcm: VersionedXcm<()>) -> Result<WeightReport> {
+	log::info!("Weighing the program on {}", chain.id());
+	let output = chain.query("XcmWeightBreakdownApi_query_xcm_weight_breakdown", &xcm.encode())?;
+	let breakdown =
+		std::result::Result::<Vec<InstructionWeight>, fees::Error>::decode(&mut &output[..])?
+			.map_err(|e| format!("{} failed to weigh the program: {e:?}", chain.id()))?;
+	let program = Xcm::<()>::try_from(xcm)
+		.map_err(|_| format!("The program can not be converted to XCM v{XCM_VERSION}"))?;
+
+	let instructions = report(&program, &breakdown)
+		.map_err(|e| format!("{} returned an invalid breakdown: {e}", chain.id()))?;
+	let total = breakdown.iter().fold(Weight::zero(), |acc, i| acc.saturating_add(i.total()));
+	let flagged = count_flagged(&breakdown);
+	if flagged > 0 {
+		log::warn!("{flagged} instructions are not benchmarked on {}", chain.id());
+	}
+
+	Ok(WeightReport {
+		chain: chain.id().to_string(),
+		runtime: chain.runtime().to_string(),
+		total: total.to_string(),
+		flagged,
+		instructions,
+	})
+}
+
+/// Pairs the instructions of `program` with their weights.
+///
+/// Fails if `breakdown` does not have exactly one weight per instruction, at any nesting level.
+fn report(
+	program: &Xcm<()>,
+	breakdown: &[InstructionWeight],
+) -> std::result::Result<Vec<InstructionReport>, String> {
+	if program.0.len() != breakdown.len() {
+		return Err(format!(
+			"{} weights for a program of {} instructions",
+			breakdown.len(),
+			program.0.len()
+		));
+	}
+	program
+		.0
+		.iter()
+		.zip(breakdown)
+		.enumerate()
+		.map(|(index, (instruction, weight))| {
+			let nested = match instruction {
+				SetErrorHandler(xcm) | SetAppendix(xcm) =>
+					report(xcm, &weight.nested).map_err(|e| format!("instruction {index}: {e}"))?,
+				_ if !weight.nested.is_empty() =>
+					return Err(format!("instruction {index} has no nested program")),
+				_ => Vec::new(),
+			};
+			Ok(InstructionReport {
+				index,
+				instruction: instruction_name(instruction),
+				weight: weight.weight.to_string(),
+				additional_weight: (!weight.additional_weight.is_zero())
+					.then(|| weight.additional_weight.to_string()),
+				source: match weight.source {
+					WeightSource::Benchmarked => "benchmarked",
+					WeightSource::Unmetered => "unmetered",
+					WeightSource::Unsupported => "unsupported",
+				},
+				nested,
+			})
+		})
+		.collect()
+}
+
+/// Counts the instructions in `breakdown` and their nested programs that are not benchmarked.
+fn count_flagged(breakdown: &[InstructionWeight]) -> usize {
+	breakdown
+		.iter()
+		.map(|i| usize::from(i.source != WeightSource::Benchmarked) + count_flagged(&i.nested))
+		.sum()
+}
+
+/// Returns the name of the variant of `instruction`.
+fn instruction_name(instruction: &Instruction<()>) -> String {
+	let debug = format!("{instruction:?}");
+	match debug.find(|c: char| !c.is_alphanumeric()) {
+		Some(end) => debug[..end].to_string(),
+		None => debug,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn weight(source: WeightSource, nested: Vec<InstructionWeight>) -> InstructionWeight {
+		InstructionWeight {
+			weight: Weight::from_parts(1, 1),
+			additional_weight: Weight::zero(),
+			source,
+			nested,
+		}
+	}
+
+	#[test]
+	fn instruction_name_works() {
+		assert_eq!(instruction_name(&ClearOrigin), "ClearOrigin");
+		assert_eq!(instruction_name(&WithdrawAsset((Here, 1u128).into())), "WithdrawAsset");
+		assert_eq!(
+			instruction_name(&DepositAsset {
+				assets: AllCounted(1).into(),
+				beneficiary: Here.into()
+			}),
+			"DepositAsset"
+		);
+	}
+
+	#[test]
+	fn nested_instructions_are_flagged() {
+		let program = Xcm::<()>(vec![
+			ClearOrigin,
+			SetAppendix(Xcm(vec![
+				ClearTopic,
+				ExpectPallet {
+					index: 0,
+					name: vec![],
+					module_name: vec![],
+					crate_major: 0,
+					min_crate_minor: 0,
+				},
+			])),
+		]);
+		let breakdown = vec![
+			weight(WeightSource::Benchmarked, vec![]),
+			weight(
+				WeightSource::Benchmarked,
+				vec![
+					weight(WeightSource::Unmetered, vec![]),
+					weight(WeightSource::Unsupported, vec![]),
+				],
+			),
+		];
+
+		let instructions = report(&program, &breakdown).unwrap();
+		assert_eq!(instructions[1].instruction, "SetAppendix");
+		assert_eq!(instructions[1].nested[1].instruction, "ExpectPallet");
+		assert_eq!(instructions[1].nested[1].source, "unsupported");
+		assert_eq!(count_flagged(&breakdown), 2);
+	}
+
+	#[test]
+	fn mismatched_breakdown_is_rejected() {
+		let program = Xcm::<()>(vec![ClearOrigin, SetAppendix(Xcm(vec![ClearTopic]))]);
+
+		let breakdown = vec![weight(WeightSource::Benchmarked, vec![])];
+		assert!(report(&program, &breakdown).is_err());
+
+		let breakdown = vec![
+			weight(WeightSource::Benchmarked, vec![]),
+			weight(WeightSource::Benchmarked, vec![]),
+		];
+		assert_eq!(
+			report(&program, &breakdown).unwrap_err(),
+			"instruction 1: 0 weights for a program of 1 instructions"
+		);
+
+		let breakdown = vec![
+			weight(WeightSource::Benchmarked, vec![weight(WeightSource::Benchmarked, vec![])]),
+			weight(WeightSource::Benchmarked, vec![weight(WeightSource::Benchmarked, vec![])]),
+		];
+		assert_eq!(
+			report(&program, &breakdown).unwrap_err(),
+			"instruction 0 has no nested program"
+		);
+	}
+}
@@ -0,0 +1,775 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! RPC for estimating the fees of calls that send XCMs.
+//!
+//! Wallets that build a call which sends XCMs, like `pallet_xcm::transfer_assets`, need to know
+//! what the call costs on every chain its messages reach, for example to put the right amount
+//! into `BuyExecution`. [`XcmFeeEstimation`] quotes all of these fees in one request:
+//!
+//! 1. The call is dry-run on the local chain with the [`DryRunApi`].
+//! 2. The execution fee of the local XCM and the delivery fees of all forwarded messages are quoted
+//!    with the [`XcmPaymentApi`] of the local chain.
+//! 3. The execution fee of every forwarded message is quoted with the [`XcmPaymentApi`] of its
+//!    destination. The destination is called through the RPC endpoint configured for it with a
+//!    [`Destination`], which can also be a local node running the runtime of the destination. The
+//!    destination is quoted at the block given for it in `remoteAt`, or else at its best block.
+//! 4. Every fee is converted into the requested asset with a [`ConvertFee`], e.g.
+//!    [`AssetConversion`] which uses the `AssetConversionApi` of the local chain.
+//!
+//! Messages that the destinations send on are not followed, so their fees are not part of the
+//! estimate. Neither is the transaction fee of the call itself, which is quoted by
+//! `payment_queryFeeDetails`.
+
+mod remote;
+
+pub use remote::{RemoteChain, RemoteRuntime};
+
+use codec::{Codec, Decode, Encode};
+use frame_support::traits::tokens::Balance as BalanceT;
+use jsonrpsee::{
+	core::async_trait,
+	proc_macros::rpc,
+	types::{error::ErrorCode, ErrorObject, ErrorObjectOwned},
+};
+use pallet_asset_conversion::AssetConversionApi;
+use serde::{Deserialize, Serialize};
+use sp_api::{ApiError, ApiExt, ProvideRuntimeApi};
+use sp_blockchain::HeaderBackend;
+use sp_core::{Bytes, H256};
+use sp_rpc::number::NumberOrHex;
+use sp_runtime::traits::{Block as BlockT, MaybeDisplay};
+use sp_weights::Weight;
+use std::{collections::BTreeMap, marker::PhantomData, str::FromStr, sync::Arc};
+use xcm::{
+	latest::{prelude::*, VERSION as XCM_VERSION},
+	VersionedAssetId, VersionedLocation, VersionedXcm,
+};
+use xcm_runtime_apis::{
+	dry_run::DryRunApi,
+	fees::{Error as XcmPaymentApiError, XcmPaymentApi},
+};
+
+const LOG_TARGET: &str = "rpc::xcm-fee-estimation";
+
+const XCM_FEE_ESTIMATION_ERROR: i32 = 9800;
+
+#[rpc(client, server)]
+pub trait XcmFeeEstimationApi<BlockHash> {
+	/// Estimates the fees of dispatching `call` with `origin` on all chains it sends messages
+	/// to, in `asset`.
+	///
+	/// `origin`, `call` and `asset` are the SCALE encoded `OriginCaller`, `RuntimeCall` and
+	/// `VersionedAssetId` of the local chain. The local chain is called at block `at`.
+	///
+	/// The block hashes of the local chain mean nothing to other chains, so they are called at the
+	/// blocks in `remote_at`, e.g. the ones that were current at `at`. A chain without a block in
+	/// `remote_at` is called at its best block, which is looked up once, so that all messages to
+	/// it are quoted at the same state.
+	#[method(name = "xcm_estimateFees")]
+	async fn estimate_fees(
+		&self,
+		origin: Bytes,
+		call: Bytes,
+		asset: Bytes,
+		at: Option<BlockHash>,
+		remote_at: Option<Vec<RemoteBlock>>,
+	) -> Result<FeeEstimate, Error>;
+}
+
+/// The fees of a call, in the requested asset.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeeEstimate {
+	/// The sum of all fees.
+	pub total: NumberOrHex,
+	/// Every single fee.
+	pub fees: Vec<Fee>,
+}
+
+/// A single fee of a call.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Fee {
+	/// What the fee is charged for.
+	pub kind: FeeKind,
+	/// The SCALE encoded `VersionedLocation` of the chain that charges the fee, relative to the
+	/// local chain.
+	pub chain: Bytes,
+	/// The SCALE encoded `VersionedAssetId` the chain charges the fee in, relative to the local
+	/// chain.
+	pub asset: Bytes,
+	/// The fee in `asset`.
+	pub amount: NumberOrHex,
+	/// The fee in the requested asset.
+	pub converted: NumberOrHex,
+}
+
+/// What a fee is charged for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FeeKind {
+	/// Executing the XCM of the call on the local chain.
+	LocalExecution,
+	/// Delivering a message from the local chain to one of its destinations.
+	Delivery,
+	/// Executing a message on its destination.
+	RemoteExecution,
+}
+
+/// The block of another chain that fees are quoted at.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteBlock {
+	/// The SCALE encoded `VersionedLocation` of the chain, relative to the local chain.
+	pub chain: Bytes,
+	/// The hash of the block.
+	pub hash: H256,
+}
+
+/// A chain the fees of forwarded messages are quoted on.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Destination {
+	/// The location of the chain, relative to the local chain.
+	pub location: Location,
+	/// The RPC endpoint of a node of the chain.
+	pub url: String,
+}
+
+/// Parses `relay=URL` for the relay chain and `PARA_ID=URL` for a sibling parachain.
+impl FromStr for Destination {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let (chain, url) = s.split_once('=').ok_or("Expected `relay=URL` or `PARA_ID=URL`")?;
+		let location = match chain {
+			"relay" => Location::parent(),
+			para_id => {
+				let para_id = para_id.parse().map_err(|e| format!("Invalid para id: {e}"))?;
+				Location::new(1, [Parachain(para_id)])
+			},
+		};
+		Ok(Self { location, url: url.into() })
+	}
+}
+
+/// Converts fees into the asset they are estimated in.
+pub trait ConvertFee<Block: BlockT>: Send + Sync + 'static {
+	/// Converts `amount` of `from` into `to` at the block `at` of the local chain.
+	///
+	/// Returns `None` if there is no price.
+	fn convert(
+		&self,
+		at: Block::Hash,
+		amount: u128,
+		from: &AssetId,
+		to: &AssetId,
+	) -> Result<Option<u128>, Error>;
+}
+
+/// Does not convert, so only fees that are charged in the requested asset can be estimated.
+impl<Block: BlockT> ConvertFee<Block> for () {
+	fn convert(
+		&self,
+		_: Block::Hash,
+		_: u128,
+		_: &AssetId,
+		_: &AssetId,
+	) -> Result<Option<u128>, Error> {
+		Ok(None)
+	}
+}
+
+/// Converts fees with the `AssetConversionApi` of the local chain.
+pub struct AssetConversion<C, Balance, AssetKind> {
+	client: Arc<C>,
+	_marker: PhantomData<fn() -> (Balance, AssetKind)>,
+}
+
+impl<C, Balance, AssetKind> AssetConversion<C, Balance, AssetKind> {
+	/// Creates a converter that calls the runtime of `client`.
+	pub fn new(client: Arc<C>) -> Self {
+		Self { client, _marker: Default::default() }
+	}
+}
+
+impl<C, Block, Balance, AssetKind> ConvertFee<Block> for AssetConversion<C, Balance, AssetKind>
+where
+	Block: BlockT,
+	C: ProvideRuntimeApi<Block> + Send + Sync + 'static,
+	C::Api: AssetConversionApi<Block, Balance, AssetKind>,
+	Balance: BalanceT + MaybeDisplay,
+	AssetKind: Codec + From<Location> + 'static,
+{
+	fn convert(
+		&self,
+		at: Block::Hash,
+		amount: u128,
+		from: &AssetId,
+		to: &AssetId,
+	) -> Result<Option<u128>, Error> {
+		let amount = Balance::try_from(amount).map_err(|_| Error::Overflow)?;
+		self.client
+			.runtime_api()
+			.quote_price_exact_tokens_for_tokens(
+				at,
+				from.0.clone().into(),
+				to.0.clone().into(),
+				amount,
+				true,
+			)?
+			.map(|amount| amount.try_into().map_err(|_| Error::Overflow))
+			.transpose()
+	}
+}
+
+/// A fee, before it is converted into the requested asset.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Quote {
+	kind: FeeKind,
+	chain: Location,
+	asset: AssetId,
+	amount: u128,
+}
+
+/// Provides RPC methods to estimate the fees of calls that send XCMs.
+pub struct XcmFeeEstimation<C, Block, Call, Event, OriginCaller, Converter> {
+	/// Shared reference to the client.
+	client: Arc<C>,
+	/// Converts the fees into the requested asset.
+	converter: Converter,
+	/// The chains forwarded messages can be quoted on.
+	destinations: BTreeMap<Location, RemoteChain>,
+	_marker: PhantomData<fn() -> (Block, Call, Event, OriginCaller)>,
+}
+
+impl<C, Block, Call, Event, OriginCaller, Converter>
+	XcmFeeEstimation<C, Block, Call, Event, OriginCaller, Converter>
+{
+	/// Creates a new instance of the XCM fee estimation RPC helper.
+	///
+	/// Connections to the `destinations` are established on first use.
+	pub fn new(client: Arc<C>, converter: Converter, destinations: Vec<Destination>) -> Self {
+		let destinations = destinations
+			.into_iter()
+			.map(|Destination { location, url }| (location, RemoteChain::new(url)))
+			.collect();
+		Self { client, converter, destinations, _marker: Default::default() }
+	}
+}
+
+#[async_trait]
+impl<C, Block, Call, Event, OriginCaller, Converter>
+	XcmFeeEstimationApiServer<<Block as BlockT>::Hash>
+	for XcmFeeEstimation<C, Block, Call, Event, OriginCaller, Converter>
+where
+	Block: BlockT,
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: DryRunApi<Block, Call, Event, OriginCaller> + XcmPaymentApi<Block>,
+	Call: Codec,
+	Event: Codec,
+	OriginCaller: Codec,
+	Converter: ConvertFee<Block>,
+{
+	async fn estimate_fees(
+		&self,
+		origin: Bytes,
+		call: Bytes,
+		asset: Bytes,
+		at: Option<Block::Hash>,
+		remote_at: Option<Vec<RemoteBlock>>,
+	) -> Result<FeeEstimate, Error> {
+		let at = at.unwrap_or_else(|| self.client.info().best_hash);
+		let origin =
+			OriginCaller::decode(&mut &origin[..]).map_err(|e| Error::Decode("origin", e))?;
+		let call = Call::decode(&mut &call[..]).map_err(|e| Error::Decode("call", e))?;
+		let asset: AssetId = VersionedAssetId::decode(&mut &asset[..])
+			.map_err(|e| Error::Decode("asset", e))?
+			.try_into()
+			.map_err(|_| Error::UnsupportedVersion)?;
+		let remote_at = remote_at
+			.unwrap_or_default()
+			.into_iter()
+			.map(|RemoteBlock { chain, hash }| {
+				let chain = VersionedLocation::decode(&mut &chain[..])
+					.map_err(|e| Error::Decode("remoteAt", e))?
+					.try_into()
+					.map_err(|_| Error::UnsupportedVersion)?;
+				Ok((chain, hash))
+			})
+			.collect::<Result<BTreeMap<Location, H256>, Error>>()?;
+
+		let (mut quotes, forwarded) = self.local_fees(at, origin, call, &asset)?;
+		for (destination, messages) in forwarded {
+			let destination: Location =
+				destination.try_into().map_err(|_| Error::UnsupportedVersion)?;
+			let remote = self
+				.destinations
+				.get(&destination)
+				.ok_or_else(|| Error::UnknownDestination(format!("{destination:?}")))?;
+			let remote_at = match remote_at.get(&destination) {
+				Some(hash) => *hash,
+				None => remote.best_hash().await?,
+			};
+			for message in messages {
+				quotes.push(remote_execution_fee(remote, &destination, message, remote_at).await?);
+			}
+		}
+
+		let mut total = 0u128;
+		let mut fees = Vec::with_capacity(quotes.len());
+		for Quote { kind, chain, asset: fee_asset, amount } in quotes {
+			let converted = self.convert(at, amount, &fee_asset, &asset)?;
+			total = total.checked_add(converted).ok_or(Error::Overflow)?;
+			fees.push(Fee {
+				kind,
+				chain: VersionedLocation::from(chain).encode().into(),
+				asset: VersionedAssetId::from(fee_asset).encode().into(),
+				amount: amount.into(),
+				converted: converted.into(),
+			});
+		}
+
+		Ok(FeeEstimate { total: total.into(), fees })
+	}
+}
+
+impl<C, Block, Call, Event, OriginCaller, Converter>
+	XcmFeeEstimation<C, Block, Call, Event, OriginCaller, Converter>
+where
+	Block: BlockT,
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: DryRunApi<Block, Call, Event, OriginCaller> + XcmPaymentApi<Block>,
+	Call: Codec,
+	Event: Codec,
+	OriginCaller: Codec,
+	Converter: ConvertFee<Block>,
+{
+	/// Dry-runs `call` and quotes the fees charged by the local chain.
+	///
+	/// Returns the quotes and the messages the call sends.
+	fn local_fees(
+		&self,
+		at: Block::Hash,
+		origin: OriginCaller,
+		call: Call,
+		asset: &AssetId,
+	) -> Result<(Vec<Quote>, Vec<(VersionedLocation, Vec<VersionedXcm<()>>)>), Error> {
+		let api = self.client.runtime_api();
+
+		let version = api
+			.api_version::<dyn DryRunApi<Block, Call, Event, OriginCaller>>(at)?
+			.ok_or(Error::MissingApi("DryRunApi"))?;
+		let effects = if version < 2 {
+			#[allow(deprecated)]
+			api.dry_run_call_before_version_2(at, origin, call)?
+		} else {
+			api.dry_run_call(at, origin, call, XCM_VERSION)?
+		}
+		.map_err(|e| Error::DryRun(format!("{e:?}")))?;
+		if let Err(e) = effects.execution_result {
+			return Err(Error::DryRun(format!("The call failed: {:?}", e.error)))
+		}
+
+		let mut quotes = Vec::new();
+		let payment_assets = payment_assets::<Block, _>(&*api, at, asset)?;
+
+		if let Some(local_xcm) = effects.local_xcm {
+			let weight = api.query_xcm_weight(at, local_xcm)?.map_err(Error::Fees)?;
+			let (asset, amount) = first_accepted(&payment_assets, |asset| {
+				api.query_weight_to_asset_fee(at, weight, asset.clone().into())
+			})?;
+			quotes.push(Quote { kind: FeeKind::LocalExecution, chain: Here.into(), asset, amount });
+		}
+
+		let version = api
+			.api_version::<dyn XcmPaymentApi<Block>>(at)?
+			.ok_or(Error::MissingApi("XcmPaymentApi"))?;
+		for (destination, messages) in &effects.forwarded_xcms {
+			for message in messages {
+				let assets = if version < 2 {
+					#[allow(deprecated)]
+					api.query_delivery_fees_before_version_2(
+						at,
+						destination.clone(),
+						message.clone(),
+					)?
+					.map_err(Error::Fees)?
+				} else {
+					first_accepted(&payment_assets, |asset| {
+						api.query_delivery_fees(
+							at,
+							destination.clone(),
+							message.clone(),
+							asset.clone().into(),
+						)
+					})?
+					.1
+				};
+				let assets: Assets = assets.try_into().map_err(|_| Error::UnsupportedVersion)?;
+				for Asset { id, fun } in assets.into_inner() {
+					if let Fungible(amount) = fun {
+						quotes.push(Quote {
+							kind: FeeKind::Delivery,
+							chain: Here.into(),
+							asset: id,
+							amount,
+						});
+					}
+				}
+			}
+		}
+
+		Ok((quotes, effects.forwarded_xcms))
+	}
+
+	/// Converts `amount` of `from` into `to`.
+	fn convert(
+		&self,
+		at: Block::Hash,
+		amount: u128,
+		from: &AssetId,
+		to: &AssetId,
+	) -> Result<u128, Error> {
+		if from == to {
+			return Ok(amount)
+		}
+		<Converter as ConvertFee<Block>>::convert(&self.converter, at, amount, from, to)?
+			.ok_or_else(|| Error::NoPrice(format!("{from:?}"), format!("{to:?}")))
+	}
+}
+
+/// Quotes the execution of `message` with the `XcmPaymentApi` of `destination`, which is called
+/// through `remote` at block `at`.
+async fn remote_execution_fee(
+	remote: &(impl RemoteRuntime + ?Sized),
+	destination: &Location,
+	message: VersionedXcm<()>,
+	at: H256,
+) -> Result<Quote, Error> {
+	let weight: Result<Weight, XcmPaymentApiError> =
+		remote::call(remote, "XcmPaymentApi_query_xcm_weight", &message, at).await?;
+	let weight = weight.map_err(Error::Fees)?;
+
+	let fee_asset = match fee_asset(&message) {
+		Some(asset) => asset,
+		None => {
+			let assets: Result<Vec<VersionedAssetId>, XcmPaymentApiError> = remote::call(
+				remote,
+				"XcmPaymentApi_query_acceptable_payment_assets",
+				XCM_VERSION,
+				at,
+			)
+			.await?;
+			assets
+				.map_err(Error::Fees)?
+				.into_iter()
+				.find_map(|asset| asset.try_into().ok())
+				.ok_or(Error::Fees(XcmPaymentApiError::AssetNotFound))?
+		},
+	};
+	let amount: Result<u128, XcmPaymentApiError> = remote::call(
+		remote,
+		"XcmPaymentApi_query_weight_to_asset_fee",
+		(weight, VersionedAssetId::from(fee_asset.clone())),
+		at,
+	)
+	.await?;
+
+	Ok(Quote {
+		kind: FeeKind::RemoteExecution,
+		chain: destination.clone(),
+		asset: reanchor_to_local(destination, fee_asset)?,
+		amount: amount.map_err(Error::Fees)?,
+	})
+}
+
+/// The assets the local chain is asked to quote fees in: `preferred` first, followed by all
+/// other assets the chain accepts for fee payment.
+fn payment_assets<Block: BlockT, Api: XcmPaymentApi<Block>>(
+	api: &Api,
+	at: Block::Hash,
+	preferred: &AssetId,
+) -> Result<Vec<AssetId>, Error> {
+	let accepted = api.query_acceptable_payment_assets(at, XCM_VERSION)?.map_err(Error::Fees)?;
+	let mut assets = vec![preferred.clone()];
+	assets.extend(
+		accepted
+			.into_iter()
+			.filter_map(|asset| AssetId::try_from(asset).ok())
+			.filter(|asset| asset != preferred),
+	);
+	Ok(assets)
+}
+
+/// Calls `quote` with every asset of `assets` until one of them is accepted.
+fn first_accepted<T>(
+	assets: &[AssetId],
+	quote: impl Fn(&AssetId) -> Result<Result<T, XcmPaymentApiError>, ApiError>,
+) -> Result<(AssetId, T), Error> {
+	for asset in assets {
+		match quote(asset)? {
+			Ok(fee) => return Ok((asset.clone(), fee)),
+			Err(XcmPaymentApiError::AssetNotFound) => continue,
+			Err(e) => return Err(Error::Fees(e)),
+		}
+	}
+	Err(Error::Fees(XcmPaymentApiError::AssetNotFound))
+}
+
+/// The asset `message` pays its execution with, relative to the destination of the message.
+fn fee_asset(message: &VersionedXcm<()>) -> Option<AssetId> {
+	let message: Xcm<()> = message.clone().try_into().ok()?;
+	message.iter().find_map(|instruction| match instruction {
+		BuyExecution { fees, .. } => Some(fees.id.clone()),
+		PayFees { asset } => Some(asset.id.clone()),
+		_ => None,
+	})
+}
+
+/// Expresses `asset`, which is relative to `destination`, relative to the local chain.
+fn reanchor_to_local(destination: &Location, asset: AssetId) -> Result<AssetId, Error> {
+	destination
+		.clone()
+		.appended_with(asset.0)
+		.map(AssetId)
+		.map_err(|(_, asset)| Error::NoPrice(format!("{asset:?}"), "the local chain".into()))
+}
+
+/// Top-level error type for the RPC handler.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+	/// A parameter could not be decoded.
+	#[error("Failed to decode `{0}`: {1}")]
+	Decode(&'static str, codec::Error),
+	/// A versioned XCM type could not be converted into the latest version.
+	#[error("Unsupported XCM version")]
+	UnsupportedVersion,
+	/// A runtime API is not provided by the local runtime.
+	#[error("The runtime does not provide the `{0}` runtime API")]
+	MissingApi(&'static str),
+	/// Calling the local runtime failed.
+	#[error(transparent)]
+	RuntimeApi(#[from] ApiError),
+	/// The dry-run of the call failed.
+	#[error("Dry-run failed: {0}")]
+	DryRun(String),
+	/// The `XcmPaymentApi` of a chain returned an error.
+	#[error("Failed to quote a fee: {0:?}")]
+	Fees(XcmPaymentApiError),
+	/// A message is sent to a chain without a configured RPC endpoint.
+	#[error("No RPC endpoint configured for {0}")]
+	UnknownDestination(String),
+	/// Calling the node of another chain failed.
+	#[error("Call to {0} failed: {1}")]
+	Remote(String, String),
+	/// A fee could not be converted into the requested asset.
+	#[error("No price to convert {0} into {1}")]
+	NoPrice(String, String),
+	/// An amount does not fit into the balance type.
+	#[error("Arithmetic overflow")]
+	Overflow,
+}
+
+impl From<Error> for ErrorObjectOwned {
+	fn from(error: Error) -> Self {
+		let code = match error {
+			Error::Decode(..) | Error::UnsupportedVersion => ErrorCode::InvalidParams.code(),
+			Error::MissingApi(_) | Error::RuntimeApi(_) => XCM_FEE_ESTIMATION_ERROR + 1,
+			Error::DryRun(_) => XCM_FEE_ESTIMATION_ERROR + 2,
+			Error::Fees(_) | Error::NoPrice(..) | Error::Overflow => XCM_FEE_ESTIMATION_ERROR + 3,
+			Error::UnknownDestination(_) | Error::Remote(..) => XCM_FEE_ESTIMATION_ERROR + 4,
+		};
+		ErrorObject::owned(code, error.to_string(), None::<()>)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::sync::Mutex;
+
+	/// Charges 2 units of `accepted` per unit of `ref_time` and records the blocks it is called at.
+	struct MockRemote {
+		accepted: AssetId,
+		calls: Mutex<Vec<(String, H256)>>,
+	}
+
+	impl MockRemote {
+		fn new(accepted: impl Into<Location>) -> Self {
+			Self { accepted: AssetId(accepted.into()), calls: Default::default() }
+		}
+	}
+
+	#[async_trait]
+	impl RemoteRuntime for MockRemote {
+		async fn best_hash(&self) -> Result<H256, Error> {
+			Ok(H256::repeat_byte(9))
+		}
+
+		async fn call_raw(&self, method: &str, args: Vec<u8>, at: H256) -> Result<Vec<u8>, Error> {
+			self.calls.lock().unwrap().push((method.into(), at));
+			let result = match method {
+				"XcmPaymentApi_query_xcm_weight" => {
+					let message = VersionedXcm::<()>::decode(&mut &args[..]).unwrap();
+					let message: Xcm<()> = message.try_into().unwrap();
+					Ok::<_, XcmPaymentApiError>(Weight::from_parts(message.len() as u64 * 10, 0))
+						.encode()
+				},
+				"XcmPaymentApi_query_acceptable_payment_assets" =>
+					Ok::<_, XcmPaymentApiError>(vec![VersionedAssetId::from(self.accepted.clone())])
+						.encode(),
+				"XcmPaymentApi_query_weight_to_asset_fee" => {
+					let (weight, asset) =
+						<(Weight, VersionedAssetId)>::decode(&mut &args[..]).unwrap();
+					if AssetId::try_from(asset).unwrap() == self.accepted {
+						Ok(weight.ref_time() as u128 * 2)
+					} else {
+						Err(XcmPaymentApiError::AssetNotFound)
+					}
+					.encode()
+				},
+				_ => return Err(Error::Remote("mock".into(), format!("Unknown method {method}"))),
+			};
+			Ok(result)
+		}
+	}
+
+	#[tokio::test]
+	async fn remote_execution_is_quoted_in_the_fee_asset() {
+		let sibling = Location::new(1, [Parachain(2000)]);
+		let remote = MockRemote::new(Parent);
+		let at = H256::repeat_byte(1);
+		let fees: Asset = (Parent, 100u128).into();
+		let message = Xcm::<()>(vec![
+			WithdrawAsset(fees.clone().into()),
+			BuyExecution { fees, weight_limit: Unlimited },
+			ClearOrigin,
+		]);
+
+		let quote = remote_execution_fee(&remote, &sibling, message.into(), at).await.unwrap();
+		assert_eq!(
+			quote,
+			Quote {
+				kind: FeeKind::RemoteExecution,
+				chain: sibling,
+				asset: AssetId(Location::parent()),
+				amount: 60,
+			}
+		);
+		// All calls are made at the given block.
+		let calls = remote.calls.lock().unwrap();
+		assert_eq!(calls.len(), 2);
+		assert!(calls.iter().all(|(_, block)| *block == at));
+	}
+
+	#[tokio::test]
+	async fn unpaid_execution_is_quoted_in_an_accepted_asset() {
+		let sibling = Location::new(1, [Parachain(2000)]);
+		// The sibling only accepts its native token.
+		let remote = MockRemote::new(Here);
+		let message =
+			Xcm::<()>(vec![UnpaidExecution { weight_limit: Unlimited, check_origin: None }]);
+
+		let quote = remote_execution_fee(&remote, &sibling, message.into(), H256::zero())
+			.await
+			.unwrap();
+		assert_eq!(quote.asset, AssetId(sibling));
+		assert_eq!(quote.amount, 20);
+	}
+
+	#[tokio::test]
+	async fn fee_asset_not_accepted_by_the_destination_fails() {
+		let remote = MockRemote::new(Here);
+		let fees: Asset = (Parent, 100u128).into();
+		let message = Xcm::<()>(vec![BuyExecution { fees, weight_limit: Unlimited }]);
+
+		let result =
+			remote_execution_fee(&remote, &Location::parent(), message.into(), H256::zero()).await;
+		assert!(matches!(result, Err(Error::Fees(XcmPaymentApiError::AssetNotFound))));
+	}
+
+	#[test]
+	fn first_accepted_asset_is_used() {
+		let assets = [AssetId(Here.into()), AssetId(Location::parent())];
+		let quote = |asset: &AssetId| {
+			Ok(if asset.0 == Location::parent() {
+				Ok(5u128)
+			} else {
+				Err(XcmPaymentApiError::AssetNotFound)
+			})
+		};
+		assert_eq!(first_accepted(&assets, quote).unwrap(), (AssetId(Location::parent()), 5));
+
+		let unsupported =
+			|_: &AssetId| Ok(Err::<u128, _>(XcmPaymentApiError::VersionedConversionFailed));
+		assert!(matches!(
+			first_accepted(&assets, unsupported),
+			Err(Error::Fees(XcmPaymentApiError::VersionedConversionFailed))
+		));
+	}
+
+	#[test]
+	fn destinations_are_parsed() {
+		assert_eq!(
+			"relay=ws://localhost:9944".parse(),
+			Ok(Destination { location: Location::parent(), url: "ws://localhost:9944".into() })
+		);
+		assert_eq!(
+			"2000=ws://localhost:9945".parse(),
+			Ok(Destination {
+				location: Location::new(1, [Parachain(2000)]),
+				url: "ws://localhost:9945".into()
+			})
+		);
+		assert!("ws://localhost:9944".parse::<Destination>().is_err());
+		assert!("asset-hub=ws://localhost:9944".parse::<Destination>().is_err());
+	}
+
+	#[test]
+	fn fee_asset_is_taken_from_the_message() {
+		let fees: Asset = (Parent, 100u128).into();
+		let buy_execution = Xcm::<()>(vec![
+			WithdrawAsset(fees.clone().into()),
+			BuyExecution { fees: fees.clone(), weight_limit: Unlimited },
+		]);
+		let pay_fees = Xcm::<()>(vec![WithdrawAsset(fees.clone().into()), PayFees { asset: fees }]);
+		let unpaid =
+			Xcm::<()>(vec![UnpaidExecution { weight_limit: Unlimited, check_origin: None }]);
+
+		assert_eq!(fee_asset(&buy_execution.into()), Some(AssetId(Location::parent())));
+		assert_eq!(fee_asset(&pay_fees.into()), Some(AssetId(Location::parent())));
+		assert_eq!(fee_asset(&unpaid.into()), None);
+	}
+
+	#[test]
+	fn fee_asset_is_reanchored_to_the_local_chain() {
+		let sibling = Location::new(1, [Parachain(2000)]);
+
+		// The relay chain token, as seen from a sibling.
+		assert_eq!(
+			reanchor_to_local(&sibling, AssetId(Location::parent())).unwrap(),
+			AssetId(Location::parent())
+		);
+		// The native token of the sibling.
+		assert_eq!(reanchor_to_local(&sibling, AssetId(Here.into())).unwrap(), AssetId(sibling));
+		// The native token of the relay chain, as seen from the relay chain.
+		assert_eq!(
+			reanchor_to_local(&Location::parent(), AssetId(Here.into())).unwrap(),
+			AssetId(Location::parent())
+		);
+	}
+}
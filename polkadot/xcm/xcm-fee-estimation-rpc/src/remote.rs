@@ -0,0 +1,99 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Runtime API calls to the nodes of other chains.
+
+use crate::Error;
+use codec::{Decode, Encode};
+use jsonrpsee::core::async_trait;
+use sp_core::H256;
+use sp_rpc::list::ListOrValue;
+use std::sync::Arc;
+use substrate_rpc_client::{ws_client, ChainApi, StateApi, WsClient};
+use tokio::sync::Mutex;
+
+/// The runtime of another chain.
+#[async_trait]
+pub trait RemoteRuntime: Send + Sync {
+	/// The hash of the best block of the chain.
+	async fn best_hash(&self) -> Result<H256, Error>;
+
+	/// Calls `method` of the runtime API with the SCALE encoded `args` at block `at`.
+	async fn call_raw(&self, method: &str, args: Vec<u8>, at: H256) -> Result<Vec<u8>, Error>;
+}
+
+/// Calls `method` of the runtime API of `remote` at block `at` and decodes the result.
+pub async fn call<R: Decode>(
+	remote: &(impl RemoteRuntime + ?Sized),
+	method: &str,
+	args: impl Encode,
+	at: H256,
+) -> Result<R, Error> {
+	let result = remote.call_raw(method, args.encode(), at).await?;
+	R::decode(&mut &result[..]).map_err(|e| {
+		Error::Remote(format!("`{method}`"), format!("Failed to decode the result: {e}"))
+	})
+}
+
+/// A chain whose runtime APIs are called through the `state_call` RPC of one of its nodes.
+pub struct RemoteChain {
+	url: String,
+	client: Mutex<Option<Arc<WsClient>>>,
+}
+
+impl RemoteChain {
+	/// Creates a new remote chain. The connection is established on the first call.
+	pub fn new(url: String) -> Self {
+		Self { url, client: Mutex::new(None) }
+	}
+
+	/// Returns the connection to the node, reconnecting if it was lost.
+	async fn client(&self) -> Result<Arc<WsClient>, Error> {
+		let mut client = self.client.lock().await;
+		match &*client {
+			Some(connected) if connected.is_connected() => Ok(connected.clone()),
+			_ => {
+				let connected = Arc::new(
+					ws_client(&self.url).await.map_err(|e| Error::Remote(self.url.clone(), e))?,
+				);
+				*client = Some(connected.clone());
+				Ok(connected)
+			},
+		}
+	}
+}
+
+#[async_trait]
+impl RemoteRuntime for RemoteChain {
+	async fn best_hash(&self) -> Result<H256, Error> {
+		let client = self.client().await?;
+		match ChainApi::<(), H256, (), ()>::block_hash(&*client, None).await {
+			Ok(ListOrValue::Value(Some(hash))) => Ok(hash),
+			Ok(_) => Err(Error::Remote(self.url.clone(), "No best block".into())),
+			Err(e) => Err(Error::Remote(self.url.clone(), e.to_string())),
+		}
+	}
+
+	async fn call_raw(&self, method: &str, args: Vec<u8>, at: H256) -> Result<Vec<u8>, Error> {
+		let client = self.client().await?;
+		log::debug!(target: crate::LOG_TARGET, "Calling `{method}` on {} at {at:?}", self.url);
+
+		StateApi::<H256>::call(&*client, method.into(), args.into(), Some(at))
+			.await
+			.map(|result| result.0)
+			.map_err(|e| Error::Remote(self.url.clone(), e.to_string()))
+	}
+}
@@ -33,3 +33,6 @@ pub mod fees;
 /// Exposes runtime API for querying whether a Location is trusted as a reserve or teleporter for a
 /// given Asset.
 pub mod trusted_query;
+/// Weight breakdown API.
+/// Given an XCM program, it returns the weight of every instruction and where it comes from.
+pub mod weight_breakdown;
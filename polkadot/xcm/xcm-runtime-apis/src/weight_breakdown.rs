@@ -0,0 +1,99 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Runtime API definition for breaking down the weight of an XCM program.
+
+use alloc::vec::Vec;
+use codec::{Decode, Encode};
+use frame_support::pallet_prelude::TypeInfo;
+use sp_weights::Weight;
+use xcm::VersionedXcm;
+
+pub use crate::fees::Error;
+
+/// The weight of a single instruction of an XCM program.
+#[derive(Clone, Encode, Decode, Eq, PartialEq, Debug, TypeInfo)]
+pub struct InstructionWeight {
+	/// The weight the `XcmWeightInfo` of the runtime assigns to the instruction.
+	pub weight: Weight,
+	/// Weight that is charged on top of `weight`.
+	///
+	/// This is the weight of the call of a `Transact` and the weight of the program of a
+	/// `SetErrorHandler` or `SetAppendix`.
+	pub additional_weight: Weight,
+	/// Where `weight` comes from.
+	pub source: WeightSource,
+	/// The weights of the instructions of the program of a `SetErrorHandler` or `SetAppendix`.
+	pub nested: Vec<InstructionWeight>,
+}
+
+impl InstructionWeight {
+	/// The total weight charged for the instruction.
+	pub fn total(&self) -> Weight {
+		self.weight.saturating_add(self.additional_weight)
+	}
+}
+
+/// Where the weight of an instruction comes from.
+#[derive(Clone, Copy, Encode, Decode, Eq, PartialEq, Debug, TypeInfo)]
+pub enum WeightSource {
+	/// The weight is neither zero nor `Weight::MAX`.
+	///
+	/// This is expected to be computed from the benchmarked weights of the runtime, but a
+	/// hard-coded constant weight is reported the same way.
+	#[codec(index = 0)]
+	Benchmarked,
+	/// The weight is zero, the instruction is not metered.
+	///
+	/// This usually means the instruction was not benchmarked and falls back to a default.
+	#[codec(index = 1)]
+	Unmetered,
+	/// The weight is `Weight::MAX` in at least one dimension.
+	///
+	/// Runtimes use this for instructions and assets they do not support, so any program
+	/// containing the instruction is rejected.
+	#[codec(index = 2)]
+	Unsupported,
+}
+
+impl WeightSource {
+	/// Classifies `weight`, as returned by an `XcmWeightInfo`.
+	///
+	/// Only the value of the weight is looked at. A hard-coded constant weight, e.g. one an
+	/// `XcmWeightInfo` returns for an instruction it never benchmarked, is indistinguishable from
+	/// a benchmarked one and is classified as [`Self::Benchmarked`], unless it is zero or
+	/// `Weight::MAX`.
+	pub fn of(weight: Weight) -> Self {
+		if weight.ref_time() == u64::MAX || weight.proof_size() == u64::MAX {
+			Self::Unsupported
+		} else if weight.is_zero() {
+			Self::Unmetered
+		} else {
+			Self::Benchmarked
+		}
+	}
+}
+
+sp_api::decl_runtime_apis! {
+	/// API for breaking down the weight of an XCM program into the weights of its instructions.
+	///
+	/// The sum of the [`InstructionWeight::total`] of all instructions is the weight
+	/// `XcmPaymentApi::query_xcm_weight` returns for the same program.
+	pub trait XcmWeightBreakdownApi {
+		/// Returns the weight of every instruction of `message`, in order.
+		fn query_xcm_weight_breakdown(message: VersionedXcm<()>) -> Result<Vec<InstructionWeight>, Error>;
+	}
+}
@@ -19,22 +19,26 @@
 mod xcm_config;
 pub use xcm_config::*;
 
+use codec::{Decode, Encode, MaxEncodedLen};
 use core::marker::PhantomData;
 use frame_support::{
 	construct_runtime, derive_impl, parameter_types,
 	traits::{
-		ConstU128, ContainsPair, Disabled, EnsureOrigin, EnsureOriginWithArg, Everything, Nothing,
+		ConstU128, Contains, ContainsPair, Disabled, EnsureOrigin, EnsureOriginWithArg, Everything,
+		LockIdentifier, Nothing,
 	},
 	weights::{constants::WEIGHT_REF_TIME_PER_SECOND, Weight},
 };
 use frame_system::EnsureRoot;
+use polkadot_parachain_primitives::primitives::Id as ParaId;
+use scale_info::TypeInfo;
 use sp_core::ConstU32;
 use sp_runtime::{
 	traits::{Get, IdentityLookup},
 	AccountId32,
 };
 use xcm::latest::prelude::*;
-use xcm_builder::{EnsureXcmOrigin, SignedToAccountId32};
+use xcm_builder::{EnsureXcmOrigin, ProcessXcmMessage, SignedToAccountId32};
 use xcm_executor::{traits::ConvertLocation, XcmExecutor};
 use xcm_simulator::mock_message_queue;
 
@@ -127,6 +131,43 @@ impl mock_message_queue::Config for Runtime {
 	type XcmExecutor = XcmExecutor<XcmConfig>;
 }
 
+parameter_types! {
+	/// Amount of weight that can be spent per block to service messages.
+	pub static MessageQueueServiceWeight: Option<Weight> =
+		Some(Weight::from_parts(1_000_000_000, 1_000_000));
+}
+
+/// Origin of the messages in the `MessageQueue` pallet.
+#[derive(Encode, Decode, MaxEncodedLen, Clone, Eq, PartialEq, TypeInfo, Debug)]
+pub enum MessageOrigin {
+	/// Downward messages from the relay chain.
+	Parent,
+	/// XCMP messages from a sibling parachain.
+	Sibling(ParaId),
+}
+
+impl From<MessageOrigin> for Location {
+	fn from(origin: MessageOrigin) -> Self {
+		match origin {
+			MessageOrigin::Parent => Location::parent(),
+			MessageOrigin::Sibling(para_id) => Location::new(1, [Parachain(para_id.into())]),
+		}
+	}
+}
+
+impl pallet_message_queue::Config for Runtime {
+	type RuntimeEvent = RuntimeEvent;
+	type Size = u32;
+	type HeapSize = ConstU32<65_536>;
+	type MaxStale = ConstU32<16>;
+	type ServiceWeight = MessageQueueServiceWeight;
+	type IdleMaxServiceWeight = ();
+	type MessageProcessor = ProcessXcmMessage<MessageOrigin, XcmExecutor<XcmConfig>, RuntimeCall>;
+	type QueueChangeHandler = ();
+	type QueuePausedQuery = ();
+	type WeightInfo = ();
+}
+
 pub type LocalOriginToLocation =
 	SignedToAccountId32<RuntimeOrigin, AccountId, constants::RelayNetwork>;
 
@@ -164,13 +205,100 @@ impl pallet_xcm::Config for Runtime {
 	type TrustedLockers = TrustedLockers;
 	type SovereignAccountOf = location_converter::LocationConverter;
 	type MaxLockers = ConstU32<8>;
-	type MaxRemoteLockConsumers = ConstU32<0>;
-	type RemoteLockConsumerIdentifier = ();
+	type MaxRemoteLockConsumers = ConstU32<8>;
+	type RemoteLockConsumerIdentifier = LockIdentifier;
 	type WeightInfo = pallet_xcm::TestWeightInfo;
 	type AdminOrigin = EnsureRoot<AccountId>;
 	type AuthorizedAliasConsideration = Disabled;
 }
 
+/// Only the calls of `System` can answer queries in this example.
+pub struct AnswerableCalls;
+impl Contains<RuntimeCall> for AnswerableCalls {
+	fn contains(call: &RuntimeCall) -> bool {
+		matches!(call, RuntimeCall::System(_))
+	}
+}
+
+impl pallet_xcm_remote_query::Config for Runtime {
+	type RuntimeEvent = RuntimeEvent;
+	type RuntimeCall = RuntimeCall;
+	type ResponseOrigin = pallet_xcm::EnsureResponse<Everything>;
+	type OnQueryResult = ();
+	type AnswerableCalls = AnswerableCalls;
+	type MaxQueriesPerBlock = ConstU32<8>;
+	type WeightInfo = ();
+}
+
+/// The relay chain locks collateral for deals on the parachain.
+pub struct RelayLocker;
+impl pallet_xcm_collateral::CollateralLockers for RelayLocker {
+	fn pallet_index(locker: &Location) -> Option<u8> {
+		use frame_support::traits::PalletInfoAccess;
+		(*locker == Location::parent()).then(|| crate::relay_chain::Collateral::index() as u8)
+	}
+
+	fn fees(_: &Location) -> Option<Asset> {
+		None
+	}
+}
+
+/// Accepts a proof of delivery if it is `true`.
+pub struct MockDeliveryProof;
+impl pallet_xcm_collateral::VerifyDelivery<AccountId> for MockDeliveryProof {
+	type Proof = bool;
+
+	fn weight() -> Weight {
+		Weight::zero()
+	}
+
+	fn verify(
+		_: pallet_xcm_collateral::DealId,
+		_: &AccountId,
+		_: &AccountId,
+		proof: &bool,
+	) -> bool {
+		*proof
+	}
+}
+
+parameter_types! {
+	pub const DisputePeriod: u64 = 5;
+	pub const CollateralLockConsumer: LockIdentifier = *b"collatrl";
+}
+
+impl pallet_xcm_collateral::Config for Runtime {
+	type RuntimeEvent = RuntimeEvent;
+	type UnlockerOrigin = pallet_xcm::EnsureXcm<Everything>;
+	type Lockers = RelayLocker;
+	type LockConsumer = CollateralLockConsumer;
+	type VerifyDelivery = MockDeliveryProof;
+	type DisputeOrigin = EnsureRoot<AccountId>;
+	type DisputePeriod = DisputePeriod;
+	type WeightInfo = ();
+	#[cfg(feature = "runtime-benchmarks")]
+	type BenchmarkHelper = CollateralHelper;
+}
+
+#[cfg(feature = "runtime-benchmarks")]
+pub struct CollateralHelper;
+#[cfg(feature = "runtime-benchmarks")]
+impl pallet_xcm_collateral::BenchmarkHelper<AccountId, bool> for CollateralHelper {
+	fn lockable_asset() -> Asset {
+		(Here, 1_000u128).into()
+	}
+
+	fn remote_lock() -> (Location, Asset) {
+		(Location::parent(), (Parent, 1_000u128).into())
+	}
+
+	fn proof(_: pallet_xcm_collateral::DealId, _: &AccountId, _: &AccountId) -> bool {
+		true
+	}
+
+	fn ensure_delivery(_: &Location, _: &Location) {}
+}
+
 type Block = frame_system::mocking::MockBlock<Runtime>;
 
 construct_runtime!(
@@ -180,5 +308,8 @@ construct_runtime!(
 		MsgQueue: mock_message_queue,
 		PolkadotXcm: pallet_xcm,
 		ForeignUniques: pallet_uniques,
+		RemoteQuery: pallet_xcm_remote_query,
+		MessageQueue: pallet_message_queue,
+		Collateral: pallet_xcm_collateral,
 	}
 );
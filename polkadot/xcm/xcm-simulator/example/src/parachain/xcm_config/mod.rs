@@ -43,7 +43,7 @@ impl xcm_executor::Config for XcmConfig {
 	type Barrier = barrier::Barrier;
 	type Weigher = weigher::Weigher;
 	type Trader = FixedRateOfFungible<constants::KsmPerSecondPerByte, ()>;
-	type ResponseHandler = ();
+	type ResponseHandler = PolkadotXcm;
 	type AssetTrap = ();
 	type AssetLocker = PolkadotXcm;
 	type AssetExchanger = ();
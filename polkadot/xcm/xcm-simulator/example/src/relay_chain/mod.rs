@@ -123,6 +123,19 @@ impl pallet_xcm::Config for Runtime {
 	type AuthorizedAliasConsideration = Disabled;
 }
 
+impl pallet_xcm_collateral::Config for Runtime {
+	type RuntimeEvent = RuntimeEvent;
+	type UnlockerOrigin = pallet_xcm::EnsureXcm<Everything>;
+	type Lockers = ();
+	type LockConsumer = ();
+	type VerifyDelivery = ();
+	type DisputeOrigin = EnsureRoot<AccountId>;
+	type DisputePeriod = frame_support::traits::ConstU64<0>;
+	type WeightInfo = ();
+	#[cfg(feature = "runtime-benchmarks")]
+	type BenchmarkHelper = ();
+}
+
 impl origin::Config for Runtime {}
 
 type Block = frame_system::mocking::MockBlock<Runtime>;
@@ -178,5 +191,6 @@ construct_runtime!(
 		XcmPallet: pallet_xcm,
 		Uniques: pallet_uniques,
 		MessageQueue: pallet_message_queue,
+		Collateral: pallet_xcm_collateral,
 	}
 );
@@ -17,6 +17,7 @@
 use crate::relay_chain::{
 	constants::RelayNetwork, location_converter::LocationConverter, RuntimeOrigin,
 };
+use pallet_xcm::XcmPassthrough;
 use polkadot_parachain_primitives::primitives::Id as ParaId;
 use polkadot_runtime_parachains::origin;
 use xcm_builder::{
@@ -29,6 +30,7 @@ type LocalOriginConverter = (
 	ChildParachainAsNative<origin::Origin, RuntimeOrigin>,
 	SignedAccountId32AsNative<RelayNetwork, RuntimeOrigin>,
 	ChildSystemParachainAsSuperuser<ParaId, RuntimeOrigin>,
+	XcmPassthrough<RuntimeOrigin>,
 );
 
 pub type OriginConverter = LocalOriginConverter;
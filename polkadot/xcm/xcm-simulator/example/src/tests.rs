@@ -17,7 +17,12 @@
 use crate::*;
 
 use codec::Encode;
-use frame_support::{assert_ok, weights::Weight};
+use frame_support::{
+	assert_noop, assert_ok,
+	traits::{EnqueueMessage, Hooks},
+	weights::Weight,
+};
+use pallet_xcm_remote_query::QueryError;
 use xcm::latest::QueryResponseInfo;
 use xcm_simulator::{mock_message_queue::ReceivedDmp, TestExt};
 
@@ -577,3 +582,450 @@ fn query_holding() {
 		);
 	});
 }
+
+/// Encodes `call` wrapped into `RemoteQuery::answer`, so that it is dispatched without changing
+/// the state of the responder.
+fn read_only(call: impl Into<parachain::RuntimeCall>) -> Vec<u8> {
+	let call = Box::new(call.into());
+	parachain::RuntimeCall::from(pallet_xcm_remote_query::Call::<parachain::Runtime>::answer {
+		call,
+	})
+	.encode()
+}
+
+/// Returns the `QueryResolved` events of the parachain.
+fn resolved_queries() -> Vec<(QueryId, pallet_xcm_remote_query::QueryResult)> {
+	parachain::System::events()
+		.into_iter()
+		.filter_map(|record| match record.event {
+			parachain::RuntimeEvent::RemoteQuery(
+				pallet_xcm_remote_query::Event::QueryResolved { query_id, result },
+			) => Some((query_id, result)),
+			_ => None,
+		})
+		.collect()
+}
+
+/// Scenario:
+/// Parachain A asks parachain B whether two calls would succeed.
+///
+/// Asserts that parachain B does not change its state and that both answers are reported back.
+#[test]
+fn remote_query_is_answered() {
+	MockNet::reset();
+
+	let remark = frame_system::Call::<parachain::Runtime>::remark_with_event { remark: vec![1] };
+	let set_storage = frame_system::Call::<parachain::Runtime>::set_storage { items: vec![] };
+
+	let (allowed, denied) = ParaA::execute_with(|| {
+		let responder = Location::new(1, [Parachain(2)]);
+		let allowed =
+			parachain::RemoteQuery::query(responder.clone(), read_only(remark), None, 10).unwrap();
+		let denied =
+			parachain::RemoteQuery::query(responder, read_only(set_storage), None, 10).unwrap();
+		(allowed, denied)
+	});
+
+	ParaB::execute_with(|| {
+		assert!(!system_contains_event!(parachain, System(frame_system::Event::Remarked { .. })));
+	});
+
+	ParaA::execute_with(|| {
+		assert_eq!(
+			resolved_queries(),
+			vec![
+				(allowed, Ok(())),
+				(denied, Err(QueryError::Dispatch(sp_runtime::DispatchError::BadOrigin))),
+			]
+		);
+		assert_eq!(
+			pallet_xcm_remote_query::PendingQueries::<parachain::Runtime>::iter().count(),
+			0
+		);
+	});
+}
+
+/// Scenario:
+/// Parachain A asks parachain B with a call that parachain B can not decode.
+///
+/// Asserts that the query times out.
+#[test]
+fn remote_query_times_out() {
+	MockNet::reset();
+
+	let query_id = ParaA::execute_with(|| {
+		let responder = Location::new(1, [Parachain(2)]);
+		parachain::RemoteQuery::query(responder, vec![255, 255], None, 3).unwrap()
+	});
+
+	// Parachain B fails to execute the message and does not answer.
+	ParaB::execute_with(|| {});
+
+	ParaA::execute_with(|| {
+		assert!(resolved_queries().is_empty());
+
+		parachain::System::set_block_number(3);
+		parachain::RemoteQuery::on_initialize(3);
+
+		assert_eq!(resolved_queries(), vec![(query_id, Err(QueryError::TimedOut))]);
+		assert!(!pallet_xcm_remote_query::PendingQueries::<parachain::Runtime>::contains_key(
+			query_id
+		));
+		assert!(ParachainPalletXcm::query(&query_id).is_none());
+	});
+}
+
+/// Sends a `Transact` of `call` from parachain A to `destination`.
+fn send_transact(
+	destination: impl Into<Location>,
+	call: impl Into<parachain::RuntimeCall>,
+) -> Result<XcmHash, SendError> {
+	ParachainPalletXcm::send_xcm(
+		Here,
+		destination,
+		Xcm(vec![Transact {
+			origin_kind: OriginKind::SovereignAccount,
+			call: call.into().encode().into(),
+			fallback_max_weight: None,
+		}]),
+	)
+}
+
+/// Scenario:
+/// Parachain A sends two messages to parachain C, which has no weight to service its queue.
+///
+/// Asserts that the messages stay in the queue until parachain C has enough weight again.
+#[test]
+fn message_queue_backlog_is_serviced_later() {
+	MockNet::reset();
+
+	let origin = parachain::MessageOrigin::Sibling(1.into());
+	let remark = frame_system::Call::<parachain::Runtime>::remark_with_event { remark: vec![1] };
+	let service_weight = parachain::MessageQueueServiceWeight::get();
+	parachain::MessageQueueServiceWeight::set(Some(Weight::zero()));
+
+	ParaA::execute_with(|| {
+		assert_ok!(send_transact((Parent, Parachain(3)), remark.clone()));
+		assert_ok!(send_transact((Parent, Parachain(3)), remark));
+	});
+
+	ParaC::execute_with(|| {
+		assert!(!system_contains_event!(parachain, System(frame_system::Event::Remarked { .. })));
+		let footprint = <parachain::MessageQueue as EnqueueMessage<_>>::footprint(origin.clone());
+		assert_eq!((footprint.ready_pages, footprint.storage.count), (1, 2));
+	});
+
+	parachain::MessageQueueServiceWeight::set(service_weight);
+	ParaC::service_queues();
+
+	ParaC::execute_with(|| {
+		let remarks = parachain::System::events()
+			.into_iter()
+			.filter(|record| {
+				matches!(
+					record.event,
+					parachain::RuntimeEvent::System(frame_system::Event::Remarked { .. })
+				)
+			})
+			.count();
+		assert_eq!(remarks, 2);
+		let footprint = <parachain::MessageQueue as EnqueueMessage<_>>::footprint(origin);
+		assert_eq!(footprint.storage.count, 0);
+	});
+}
+
+/// Scenario:
+/// Parachain A sends a message to parachain C that needs more weight than parachain C may spend
+/// on servicing its queue.
+///
+/// Asserts that the message is marked as overweight and can be executed manually.
+#[test]
+fn overweight_message_is_executed_manually() {
+	MockNet::reset();
+
+	let origin = parachain::MessageOrigin::Sibling(1.into());
+	let set_code = frame_system::Call::<parachain::Runtime>::set_code { code: vec![] };
+
+	ParaA::execute_with(|| {
+		assert_ok!(send_transact((Parent, Parachain(3)), set_code));
+	});
+
+	// Servicing the queue again does not pick up the message.
+	ParaC::service_queues();
+
+	ParaC::execute_with(|| {
+		assert!(system_contains_event!(
+			parachain,
+			MessageQueue(pallet_message_queue::Event::OverweightEnqueued { .. })
+		));
+		assert!(!system_contains_event!(
+			parachain,
+			MessageQueue(pallet_message_queue::Event::Processed { .. })
+		));
+		let footprint = <parachain::MessageQueue as EnqueueMessage<_>>::footprint(origin.clone());
+		assert_eq!((footprint.pages, footprint.ready_pages), (1, 0));
+
+		assert_ok!(parachain::MessageQueue::execute_overweight(
+			parachain::RuntimeOrigin::signed(ALICE),
+			origin,
+			0,
+			0,
+			Weight::MAX,
+		));
+		assert!(system_contains_event!(
+			parachain,
+			MessageQueue(pallet_message_queue::Event::Processed { success: true, .. })
+		));
+	});
+}
+
+/// Scenario:
+/// Parachain A sends more messages to parachain B than the HRMP channel between them can hold.
+///
+/// Asserts that sending fails once the channel is full and succeeds again after delivery.
+#[test]
+fn hrmp_channel_capacity_is_enforced() {
+	MockNet::reset();
+	MockNet::set_hrmp_channel_capacity(Some(1));
+
+	let remark = frame_system::Call::<parachain::Runtime>::remark_with_event { remark: vec![1] };
+
+	ParaA::execute_with(|| {
+		assert_ok!(send_transact((Parent, Parachain(2)), remark.clone()));
+		assert_eq!(
+			send_transact((Parent, Parachain(2)), remark.clone()),
+			Err(SendError::Transport("HRMP channel is full"))
+		);
+		// Other channels are not affected.
+		assert_ok!(send_transact((Parent, Parachain(3)), remark.clone()));
+	});
+
+	// The channel has room again once its messages were delivered.
+	ParaA::execute_with(|| {
+		assert_ok!(send_transact((Parent, Parachain(2)), remark));
+	});
+}
+
+/// Locks `amount` of `ALICE` on the relay chain for parachain A and opens a deal with `BOB` for it.
+///
+/// Returns the account of `ALICE` on parachain A.
+fn open_collateralized_deal(amount: u128, deadline: u64) -> parachain::AccountId {
+	Relay::execute_with(|| {
+		assert_ok!(relay_chain::Collateral::lock(
+			relay_chain::RuntimeOrigin::signed(ALICE),
+			Box::new((Here, amount).into()),
+			Box::new(Parachain(1).into()),
+		));
+		assert_eq!(relay_chain::Balances::locks(&ALICE)[0].amount, amount);
+	});
+
+	let buyer = parent_account_account_id(ALICE);
+	ParaA::execute_with(|| {
+		assert_ok!(parachain::Collateral::open_deal(
+			parachain::RuntimeOrigin::signed(buyer.clone()),
+			Box::new(Parent.into()),
+			Box::new((Parent, amount).into()),
+			BOB,
+			deadline,
+		));
+	});
+	buyer
+}
+
+fn bob_on_para_a() -> Box<VersionedLocation> {
+	Box::new(AccountId32 { network: None, id: BOB.into() }.into())
+}
+
+/// Scenario:
+/// ALICE locks funds on the relay chain as collateral for a deal with BOB on parachain A. BOB
+/// proves the delivery and ALICE does not dispute it.
+///
+/// Asserts that the collateral is transferred to BOB on the relay chain.
+#[test]
+fn collateral_is_claimed_after_delivery() {
+	MockNet::reset();
+
+	let buyer = open_collateralized_deal(100, 10);
+
+	ParaA::execute_with(|| {
+		// The collateral is held by the deal and can not be used twice.
+		assert_noop!(
+			parachain::Collateral::open_deal(
+				parachain::RuntimeOrigin::signed(buyer.clone()),
+				Box::new(Parent.into()),
+				Box::new((Parent, 1).into()),
+				BOB,
+				10,
+			),
+			pallet_xcm::Error::<parachain::Runtime>::LowBalance
+		);
+
+		assert_ok!(parachain::Collateral::confirm_delivery(
+			parachain::RuntimeOrigin::signed(BOB),
+			0,
+			true,
+			bob_on_para_a(),
+		));
+		assert_noop!(
+			parachain::Collateral::settle(parachain::RuntimeOrigin::signed(BOB), 0),
+			pallet_xcm_collateral::Error::<parachain::Runtime>::NotSettleable
+		);
+
+		parachain::System::set_block_number(1 + parachain::DisputePeriod::get());
+		assert_ok!(parachain::Collateral::settle(parachain::RuntimeOrigin::signed(BOB), 0));
+		assert!(pallet_xcm_collateral::Deals::<parachain::Runtime>::get(0).is_none());
+	});
+
+	Relay::execute_with(|| {
+		assert!(relay_chain::Balances::locks(&ALICE).is_empty());
+		assert_eq!(relay_chain::Balances::free_balance(&ALICE), INITIAL_BALANCE - 100);
+		assert_eq!(relay_chain::Balances::free_balance(&child_account_account_id(1, BOB)), 100);
+	});
+}
+
+/// Scenario:
+/// ALICE locks funds on the relay chain as collateral for a deal with BOB on parachain A. BOB does
+/// not deliver before the deadline.
+///
+/// Asserts that the collateral is unlocked for ALICE on the relay chain.
+#[test]
+fn collateral_is_released_after_deadline() {
+	MockNet::reset();
+
+	open_collateralized_deal(100, 10);
+
+	ParaA::execute_with(|| {
+		assert_noop!(
+			parachain::Collateral::confirm_delivery(
+				parachain::RuntimeOrigin::signed(BOB),
+				0,
+				false,
+				bob_on_para_a(),
+			),
+			pallet_xcm_collateral::Error::<parachain::Runtime>::InvalidProof
+		);
+
+		parachain::System::set_block_number(10);
+		assert_noop!(
+			parachain::Collateral::confirm_delivery(
+				parachain::RuntimeOrigin::signed(BOB),
+				0,
+				true,
+				bob_on_para_a(),
+			),
+			pallet_xcm_collateral::Error::<parachain::Runtime>::DeadlinePassed
+		);
+		assert_ok!(parachain::Collateral::settle(parachain::RuntimeOrigin::signed(ALICE), 0));
+	});
+
+	Relay::execute_with(|| {
+		assert!(relay_chain::Balances::locks(&ALICE).is_empty());
+		assert_eq!(relay_chain::Balances::free_balance(&ALICE), INITIAL_BALANCE);
+	});
+}
+
+/// Scenario:
+/// ALICE disputes the delivery of a deal with BOB on parachain A, which governance resolves in
+/// favour of ALICE.
+///
+/// Asserts that the deal can only be settled after governance resolved it and that the collateral
+/// is unlocked.
+#[test]
+fn disputed_delivery_is_resolved_by_governance() {
+	MockNet::reset();
+
+	let buyer = open_collateralized_deal(100, 10);
+
+	ParaA::execute_with(|| {
+		assert_ok!(parachain::Collateral::confirm_delivery(
+			parachain::RuntimeOrigin::signed(BOB),
+			0,
+			true,
+			bob_on_para_a(),
+		));
+		assert_ok!(parachain::Collateral::dispute(parachain::RuntimeOrigin::signed(buyer), 0));
+
+		parachain::System::set_block_number(1 + parachain::DisputePeriod::get());
+		assert_noop!(
+			parachain::Collateral::settle(parachain::RuntimeOrigin::signed(BOB), 0),
+			pallet_xcm_collateral::Error::<parachain::Runtime>::NotSettleable
+		);
+		assert_ok!(parachain::Collateral::resolve_dispute(
+			parachain::RuntimeOrigin::root(),
+			0,
+			false
+		));
+		assert!(system_contains_event!(
+			parachain,
+			Collateral(pallet_xcm_collateral::Event::DisputeResolved { deal_id: 0, claim: false })
+		));
+		assert_ok!(parachain::Collateral::settle(parachain::RuntimeOrigin::signed(ALICE), 0));
+		assert!(system_contains_event!(
+			parachain,
+			Collateral(pallet_xcm_collateral::Event::DealSettled { deal_id: 0, claimed: false })
+		));
+	});
+
+	Relay::execute_with(|| {
+		assert!(relay_chain::Balances::locks(&ALICE).is_empty());
+		assert_eq!(relay_chain::Balances::free_balance(&ALICE), INITIAL_BALANCE);
+	});
+}
+
+/// Scenario:
+/// ALICE locks funds on the relay chain once and backs two deals with BOB on parachain A with
+/// them. BOB delivers the first deal but not the second one.
+///
+/// Asserts that the deals can be settled in any order and that BOB only gets the collateral of
+/// the delivered deal.
+#[test]
+fn one_lock_backs_several_deals() {
+	MockNet::reset();
+
+	Relay::execute_with(|| {
+		assert_ok!(relay_chain::Collateral::lock(
+			relay_chain::RuntimeOrigin::signed(ALICE),
+			Box::new((Here, 100).into()),
+			Box::new(Parachain(1).into()),
+		));
+	});
+
+	let buyer = parent_account_account_id(ALICE);
+	ParaA::execute_with(|| {
+		for amount in [40, 60] {
+			assert_ok!(parachain::Collateral::open_deal(
+				parachain::RuntimeOrigin::signed(buyer.clone()),
+				Box::new(Parent.into()),
+				Box::new((Parent, amount).into()),
+				BOB,
+				10,
+			));
+		}
+		assert_ok!(parachain::Collateral::confirm_delivery(
+			parachain::RuntimeOrigin::signed(BOB),
+			0,
+			true,
+			bob_on_para_a(),
+		));
+
+		// The delivered deal is settled while the other one still holds its collateral.
+		parachain::System::set_block_number(1 + parachain::DisputePeriod::get());
+		assert_ok!(parachain::Collateral::settle(parachain::RuntimeOrigin::signed(BOB), 0));
+		assert_eq!(
+			pallet_xcm_collateral::HeldCollateral::<parachain::Runtime>::get(
+				&buyer,
+				VersionedAssetId::from(AssetId(Parent.into())),
+			),
+			60
+		);
+
+		parachain::System::set_block_number(10);
+		assert_ok!(parachain::Collateral::settle(parachain::RuntimeOrigin::signed(ALICE), 1));
+	});
+
+	Relay::execute_with(|| {
+		assert!(relay_chain::Balances::locks(&ALICE).is_empty());
+		assert_eq!(relay_chain::Balances::free_balance(&ALICE), INITIAL_BALANCE - 40);
+		assert_eq!(relay_chain::Balances::free_balance(&child_account_account_id(1, BOB)), 40);
+	});
+}
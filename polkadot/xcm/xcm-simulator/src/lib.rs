@@ -23,6 +23,7 @@ pub mod mock_message_queue;
 extern crate alloc;
 
 pub use codec::Encode;
+pub use pallet_message_queue;
 pub use paste;
 
 pub use alloc::collections::vec_deque::VecDeque;
@@ -46,6 +47,10 @@ pub use xcm::{latest::prelude::*, VersionedXcm};
 pub use xcm_builder::ProcessXcmMessage;
 pub use xcm_executor::XcmExecutor;
 
+use codec::Decode;
+use frame_support::BoundedSlice;
+use pallet_message_queue::MessageOriginOf;
+
 pub trait TestExt {
 	/// Initialize the test environment.
 	fn new_ext() -> sp_io::TestExternalities;
@@ -92,6 +97,62 @@ pub fn fake_message_hash<T>(message: &Xcm<T>) -> XcmHash {
 	message.using_encoded(blake2_256)
 }
 
+/// Splits an XCMP message into the encoded `VersionedXcm`s it carries.
+///
+/// Only the `ConcatenatedVersionedXcm` format is supported, which is the one
+/// [`encode_xcm`] produces.
+pub fn split_xcmp_message(mut data: &[u8]) -> Vec<Vec<u8>> {
+	let format = XcmpMessageFormat::decode(&mut data).expect("XCMP message has a format");
+	assert_eq!(format, XcmpMessageFormat::ConcatenatedVersionedXcm, "Unsupported XCMP format");
+
+	let mut messages = Vec::new();
+	while !data.is_empty() {
+		let remaining = data;
+		VersionedXcm::<()>::decode(&mut data).expect("XCMP message contains valid XCMs");
+		messages.push(remaining[..remaining.len() - data.len()].to_vec());
+	}
+	messages
+}
+
+/// Services the `pallet_message_queue` of `T` once, like at the start of a block.
+///
+/// The used weight is limited by `max_weight` and by the `ServiceWeight` of `T`. Messages
+/// that do not fit stay in the queue until the next call and messages that can never fit are
+/// marked as overweight.
+pub fn service_message_queue<T: pallet_message_queue::Config>(max_weight: Weight) -> Weight {
+	let limit = T::ServiceWeight::get().map_or(max_weight, |w| w.min(max_weight));
+	<pallet_message_queue::Pallet<T> as ServiceQueues>::service_queues(limit)
+}
+
+/// Enqueues `messages` into the `pallet_message_queue` of `T` and services it once.
+///
+/// See [`service_message_queue`].
+pub fn enqueue_and_service<T: pallet_message_queue::Config>(
+	messages: impl Iterator<Item = (MessageOriginOf<T>, Vec<u8>)>,
+	max_weight: Weight,
+) -> Weight {
+	for (origin, message) in messages {
+		let message = BoundedSlice::try_from(&message[..]).expect("Message too long");
+		<pallet_message_queue::Pallet<T> as EnqueueMessage<_>>::enqueue_message(message, origin);
+	}
+	service_message_queue::<T>(max_weight)
+}
+
+/// Whether the HRMP channel from `sender` to `destination` is full.
+///
+/// A channel is full once it holds [`HRMP_CHANNEL_CAPACITY`] messages that were not yet
+/// delivered by the network. Channels are unbounded if no capacity is set.
+pub fn hrmp_channel_is_full(sender: ParaId, destination: &Location) -> bool {
+	let Some(capacity) = HRMP_CHANNEL_CAPACITY.with(|c| *c.borrow()) else { return false };
+	let queued = PARA_MESSAGE_BUS.with(|b| {
+		b.borrow()
+			.iter()
+			.filter(|(from, to, _)| *from == sender && to == destination)
+			.count()
+	});
+	queued >= capacity as usize
+}
+
 /// The macro is implementing upward message passing(UMP) for the provided relay
 /// chain struct. The struct has to provide the XCM configuration for the relay
 /// chain.
@@ -105,6 +166,13 @@ pub fn fake_message_hash<T>(message: &Xcm<T>) -> XcmHash {
 ///	    }
 ///	}
 /// ```
+///
+/// Every upward message is processed right away, without any weight limit, and must be
+/// processed successfully. Add `LimitServiceWeight = true` after `System` to service the
+/// message queue like a real relay chain instead: the used weight is limited by the
+/// `ServiceWeight` of the runtime, messages that do not fit stay in the queue until
+/// `Relay::service_queues()` is called and messages that can never fit are marked as
+/// overweight.
 #[macro_export]
 #[rustfmt::skip]
 macro_rules! decl_test_relay_chain {
@@ -159,6 +227,75 @@ macro_rules! decl_test_relay_chain {
 			}
 		}
 	};
+	(
+		pub struct $name:ident {
+			Runtime = $runtime:path,
+			RuntimeCall = $runtime_call:path,
+			RuntimeEvent = $runtime_event:path,
+			XcmConfig = $xcm_config:path,
+			MessageQueue = $mq:path,
+			System = $system:path,
+			LimitServiceWeight = true,
+			new_ext = $new_ext:expr,
+		}
+	) => {
+		pub struct $name;
+
+		$crate::__impl_ext!($name, $new_ext);
+
+		impl $crate::ProcessMessage for $name {
+			type Origin = $crate::ParaId;
+
+			fn process_message(
+				msg: &[u8],
+				para: Self::Origin,
+				meter: &mut $crate::WeightMeter,
+				id: &mut [u8; 32],
+			) -> Result<bool, $crate::ProcessMessageError> {
+				use $crate::{Weight, AggregateMessageOrigin, UmpQueueId, EnqueueMessage};
+				use $crate::pallet_message_queue::Event as MessageQueueEvent;
+				use $runtime_event as runtime_event;
+
+				Self::execute_with(|| {
+					<$mq as EnqueueMessage<AggregateMessageOrigin>>::enqueue_message(
+						msg.try_into().expect("Message too long"),
+						AggregateMessageOrigin::Ump(UmpQueueId::Para(para.clone()))
+					);
+
+					let expected_origin = AggregateMessageOrigin::Ump(UmpQueueId::Para(para));
+					<$system>::reset_events();
+					$crate::service_message_queue::<$runtime>(Weight::MAX);
+					for record in <$system>::events() {
+						match &record.event {
+							runtime_event::MessageQueue(
+								MessageQueueEvent::Processed { origin, .. } |
+								MessageQueueEvent::OverweightEnqueued { origin, .. }
+							) => {
+								assert_eq!(origin, &expected_origin);
+							},
+							runtime_event::MessageQueue(
+								event @ MessageQueueEvent::ProcessingFailed { .. }
+							) => panic!("Unexpected event: {:#?}", event),
+							_ => {},
+						}
+					}
+					Ok(true)
+				})
+			}
+		}
+
+		impl $name {
+			/// Services the message queue once, like at the start of a new block.
+			///
+			/// Use this to process messages that were left in the queue because they did not
+			/// fit into the `ServiceWeight` of the runtime.
+			pub fn service_queues() -> $crate::Weight {
+				use $crate::TestExt;
+
+				Self::execute_with(|| $crate::service_message_queue::<$runtime>($crate::Weight::MAX))
+			}
+		}
+	};
 }
 
 /// The macro is implementing the `XcmMessageHandlerT` and `DmpMessageHandlerT`
@@ -176,6 +313,25 @@ macro_rules! decl_test_relay_chain {
 /// 	    }
 /// }
 /// ```
+///
+/// Alternatively, incoming messages can be enqueued into the `pallet_message_queue` of the
+/// runtime. `ParentOrigin` is the queue origin of downward messages and `SiblingOrigin` maps
+/// the `ParaId` of a sender to the queue origin of its messages. Every delivery services the
+/// queue once, limited by the `ServiceWeight` of the runtime. Messages that do not fit stay
+/// in the queue until `ParaA::service_queues()` is called and messages that can never fit
+/// are marked as overweight.
+///
+/// ```ignore
+/// decl_test_parachain! {
+/// 	    pub struct ParaA {
+/// 	        Runtime = parachain::Runtime,
+/// 	        MessageQueue = parachain::MessageQueue,
+/// 	        ParentOrigin = AggregateMessageOrigin::Parent,
+/// 	        SiblingOrigin = AggregateMessageOrigin::Sibling,
+/// 	        new_ext = para_ext(),
+/// 	    }
+/// }
+/// ```
 #[macro_export]
 macro_rules! decl_test_parachain {
 	(
@@ -219,6 +375,68 @@ macro_rules! decl_test_parachain {
 			}
 		}
 	};
+	(
+		pub struct $name:ident {
+			Runtime = $runtime:path,
+			MessageQueue = $message_queue:path,
+			ParentOrigin = $parent_origin:expr,
+			SiblingOrigin = $sibling_origin:expr,
+			new_ext = $new_ext:expr,
+		}
+	) => {
+		pub struct $name;
+
+		$crate::__impl_ext!($name, $new_ext);
+
+		impl $name {
+			/// Services the message queue once, like at the start of a new block.
+			///
+			/// Use this to process messages that were left in the queue because they did not
+			/// fit into the `ServiceWeight` of the runtime.
+			pub fn service_queues() -> $crate::Weight {
+				use $crate::TestExt;
+
+				$name::execute_with(|| {
+					$crate::service_message_queue::<$runtime>($crate::Weight::MAX)
+				})
+			}
+		}
+
+		impl $crate::XcmpMessageHandlerT for $name {
+			fn handle_xcmp_messages<
+				'a,
+				I: Iterator<Item = ($crate::ParaId, $crate::RelayBlockNumber, &'a [u8])>,
+			>(
+				iter: I,
+				max_weight: $crate::Weight,
+			) -> $crate::Weight {
+				use $crate::TestExt;
+
+				$name::execute_with(|| {
+					let messages = iter.flat_map(|(sender, _, data)| {
+						$crate::split_xcmp_message(data)
+							.into_iter()
+							.map(move |message| (($sibling_origin)(sender), message))
+					});
+					$crate::enqueue_and_service::<$runtime>(messages, max_weight)
+				})
+			}
+		}
+
+		impl $crate::DmpMessageHandlerT for $name {
+			fn handle_dmp_messages(
+				iter: impl Iterator<Item = ($crate::RelayBlockNumber, Vec<u8>)>,
+				max_weight: $crate::Weight,
+			) -> $crate::Weight {
+				use $crate::TestExt;
+
+				$name::execute_with(|| {
+					let messages = iter.map(|(_, message)| ($parent_origin, message));
+					$crate::enqueue_and_service::<$runtime>(messages, max_weight)
+				})
+			}
+		}
+	};
 }
 
 /// Implements the `TestExt` trait for a specified struct.
@@ -269,6 +487,8 @@ thread_local! {
 		= RefCell::new(VecDeque::new());
 	pub static RELAY_MESSAGE_BUS: RefCell<VecDeque<(Location, Xcm<()>)>>
 		= RefCell::new(VecDeque::new());
+	/// Maximum number of undelivered messages per HRMP channel. `None` means unbounded.
+	pub static HRMP_CHANNEL_CAPACITY: RefCell<Option<u32>> = RefCell::new(None);
 }
 
 /// Declares a test network that consists of a relay chain and multiple
@@ -306,9 +526,20 @@ macro_rules! decl_test_network {
 				$crate::RELAY_MESSAGE_BUS.with(|b| b.replace(VecDeque::new()));
 				// Reset parachain message bus.
 				$crate::PARA_MESSAGE_BUS.with(|b| b.replace(VecDeque::new()));
+				// Reset HRMP channel capacity.
+				$crate::HRMP_CHANNEL_CAPACITY.with(|c| c.replace(None));
 				<$relay_chain>::reset_ext();
 				$( <$parachain>::reset_ext(); )*
 			}
+
+			/// Limits the number of messages every HRMP channel can hold, `None` removes the limit.
+			///
+			/// Messages stay in a channel until the network delivers them, which happens once the
+			/// closure passed to `execute_with` of the sending parachain completes. Sending to a
+			/// sibling over a full channel fails with `SendError::Transport`.
+			pub fn set_hrmp_channel_capacity(capacity: Option<u32>) {
+				$crate::HRMP_CHANNEL_CAPACITY.with(|c| c.replace(capacity));
+			}
 		}
 
 		/// Check if any messages exist in either message bus.
@@ -407,6 +638,11 @@ macro_rules! decl_test_network {
 						return Err($crate::SendError::NotApplicable)
 					},
 				}
+				if matches!(d.unpack(), (1, [$crate::Parachain(_)])) &&
+					$crate::hrmp_channel_is_full(T::get(), &d)
+				{
+					return Err($crate::SendError::Transport("HRMP channel is full"))
+				}
 				let m = message.take().ok_or($crate::SendError::MissingArgument)?;
 				Ok(((T::get(), d, m), $crate::Assets::new()))
 			}
@@ -289,11 +289,20 @@ where
 			max_duration,
 			block_size_limit,
 			ignored_nodes_by_proof_recording: None,
+			extrinsics: Vec::new(),
+			closing_extrinsic: None,
 		})
 		.boxed()
 	}
 }
 
+/// Creates the extrinsic that closes [`ProposeArgs::extrinsics`].
+///
+/// It is called with the indices of the extrinsics that were applied, and nothing is applied if
+/// it returns `None`.
+pub type ClosingExtrinsic<Block> =
+	Box<dyn FnOnce(&[usize]) -> Option<<Block as BlockT>::Extrinsic> + Send>;
+
 /// Arguments for [`Proposer::propose_block`].
 pub struct ProposeArgs<Block: BlockT> {
 	/// The inherent data to pass to the block production.
@@ -311,6 +320,14 @@ pub struct ProposeArgs<Block: BlockT> {
 	///
 	/// Only applies when proof recording is enabled.
 	pub ignored_nodes_by_proof_recording: Option<IgnoredNodes<Block::Hash>>,
+	/// Extrinsics that are applied after the inherents and before the transactions of the pool.
+	///
+	/// These extrinsics never enter the transaction pool. Extrinsics that fail to apply are
+	/// dropped.
+	pub extrinsics: Vec<Block::Extrinsic>,
+	/// Creates an extrinsic that is applied right after [`Self::extrinsics`], depending on which
+	/// of them were applied.
+	pub closing_extrinsic: Option<ClosingExtrinsic<Block>>,
 }
 
 impl<Block: BlockT> Default for ProposeArgs<Block> {
@@ -321,6 +338,8 @@ impl<Block: BlockT> Default for ProposeArgs<Block> {
 			max_duration: Default::default(),
 			block_size_limit: None,
 			ignored_nodes_by_proof_recording: None,
+			extrinsics: Vec::new(),
+			closing_extrinsic: None,
 		}
 	}
 }
@@ -373,6 +392,8 @@ where
 			max_duration,
 			block_size_limit,
 			ignored_nodes_by_proof_recording,
+			extrinsics,
+			closing_extrinsic,
 		}: ProposeArgs<Block>,
 	) -> Result<Proposal<Block, PR::Proof>, sp_blockchain::Error> {
 		// leave some time for evaluation and block finalization (10%)
@@ -393,8 +414,15 @@ where
 
 		let mode = block_builder.extrinsic_inclusion_mode();
 		let end_reason = match mode {
-			ExtrinsicInclusionMode::AllExtrinsics =>
-				self.apply_extrinsics(&mut block_builder, deadline, block_size_limit).await?,
+			ExtrinsicInclusionMode::AllExtrinsics => {
+				self.apply_proposer_extrinsics(
+					&mut block_builder,
+					extrinsics,
+					closing_extrinsic,
+					block_size_limit,
+				);
+				self.apply_extrinsics(&mut block_builder, deadline, block_size_limit).await?
+			},
 			ExtrinsicInclusionMode::OnlyInherents => EndProposingReason::TransactionForbidden,
 		};
 		let (block, storage_changes, proof) = block_builder.build()?.into_inner();
@@ -461,6 +489,58 @@ where
 		Ok(())
 	}
 
+	/// Apply the extrinsics that were passed to the proposer, followed by their closing
+	/// extrinsic.
+	fn apply_proposer_extrinsics(
+		&self,
+		block_builder: &mut sc_block_builder::BlockBuilder<'_, Block, C>,
+		extrinsics: Vec<Block::Extrinsic>,
+		closing_extrinsic: Option<ClosingExtrinsic<Block>>,
+		block_size_limit: Option<usize>,
+	) {
+		let block_size_limit = block_size_limit.unwrap_or(self.default_block_size_limit);
+
+		let mut applied = Vec::new();
+		for (index, extrinsic) in extrinsics.into_iter().enumerate() {
+			if self.apply_proposer_extrinsic(block_builder, extrinsic, block_size_limit) {
+				applied.push(index);
+			}
+		}
+
+		if let Some(extrinsic) = closing_extrinsic.and_then(|closing| closing(&applied)) {
+			self.apply_proposer_extrinsic(block_builder, extrinsic, block_size_limit);
+		}
+	}
+
+	/// Apply a single extrinsic that was passed to the proposer.
+	///
+	/// Returns whether it was applied.
+	fn apply_proposer_extrinsic(
+		&self,
+		block_builder: &mut sc_block_builder::BlockBuilder<'_, Block, C>,
+		extrinsic: Block::Extrinsic,
+		block_size_limit: usize,
+	) -> bool {
+		let block_size =
+			block_builder.estimate_block_size(self.include_proof_in_block_size_estimation);
+		if block_size + extrinsic.encoded_size() > block_size_limit {
+			debug!(
+				target: LOG_TARGET,
+				"Proposer extrinsic would overflow the block size limit. Dropping."
+			);
+			return false
+		}
+
+		if let Err(e) = block_builder.push(extrinsic) {
+			debug!(
+				target: LOG_TARGET,
+				"Proposer extrinsic returned error: {} at: {}. Dropping.", e, self.parent_hash
+			);
+			return false
+		}
+		true
+	}
+
 	/// Apply as many extrinsics as possible to the block.
 	async fn apply_extrinsics(
 		&self,
@@ -805,6 +885,49 @@ mod tests {
 		.unwrap();
 	}
 
+	#[test]
+	fn should_apply_proposer_extrinsics_without_the_pool() {
+		let client = Arc::new(substrate_test_runtime_client::new());
+		let spawner = sp_core::testing::TaskExecutor::new();
+		let txpool = Arc::from(BasicPool::new_full(
+			Default::default(),
+			true.into(),
+			None,
+			spawner.clone(),
+			client.clone(),
+		));
+
+		let mut proposer_factory =
+			ProposerFactory::new(spawner.clone(), client.clone(), txpool.clone(), None, None);
+
+		let proposer = proposer_factory.init_with_now(
+			&client.expect_header(client.info().genesis_hash).unwrap(),
+			Box::new(time::Instant::now),
+		);
+
+		// The second extrinsic reuses the nonce of the first one and is dropped, which the closing
+		// extrinsic is told about.
+		let xt = extrinsic(0);
+		let closing = extrinsic(1);
+		let block = block_on(proposer.propose_block(ProposeArgs {
+			max_duration: time::Duration::from_secs(3),
+			extrinsics: vec![xt.clone(), extrinsic(0)],
+			closing_extrinsic: Some(Box::new({
+				let closing = closing.clone();
+				move |applied: &[usize]| {
+					assert_eq!(applied, &[0]);
+					Some(closing)
+				}
+			})),
+			..Default::default()
+		}))
+		.map(|r| r.block)
+		.unwrap();
+
+		assert_eq!(block.extrinsics(), &[xt, closing]);
+		assert_eq!(txpool.ready().count(), 0);
+	}
+
 	#[test]
 	fn proposed_storage_changes_should_match_execute_block_storage_changes() {
 		let (client, backend) = TestClientBuilder::new().build_with_backend();
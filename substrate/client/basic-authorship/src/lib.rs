@@ -74,5 +74,5 @@
 mod basic_authorship;
 
 pub use crate::basic_authorship::{
-	ProposeArgs, Proposer, ProposerFactory, DEFAULT_BLOCK_SIZE_LIMIT,
+	ClosingExtrinsic, ProposeArgs, Proposer, ProposerFactory, DEFAULT_BLOCK_SIZE_LIMIT,
 };
@@ -183,6 +183,30 @@ pub use v1::*;
 /// yet—`#[extrinsic_call]` and `#[block]` are parsed and consumed as part of the benchmark
 /// definition parsing code, so they never expand as their own attribute macros.
 ///
+/// ### `#[task]`
+///
+/// Tasks defined with `#[pallet::tasks_experimental]` are benchmarked by attaching `#[task]` to
+/// an expression that creates the task:
+///
+/// ```ignore
+/// #[benchmark]
+/// fn add_number_into_total() {
+/// 	Numbers::<T>::insert(0, 1);
+///
+/// 	#[task]
+/// 	Task::<T>::AddNumberIntoTotal { i: 0 };
+///
+/// 	assert_eq!(Numbers::<T>::get(0), None);
+/// }
+/// ```
+///
+/// The task is created before the measurement starts. The measured portion checks that the task
+/// is valid and runs it, which is the work `frame_system::do_task` does for the task. The
+/// benchmark fails if the task is invalid or returns an error.
+///
+/// The benchmark should be named like the task function, so that the generated weight function
+/// is picked up by `#[pallet::tasks_experimental(weight(..))]`.
+///
 /// ### Optional Attributes
 ///
 /// The keywords `extra` and `skip_meta` can be provided as optional arguments to the
@@ -391,7 +415,7 @@ pub use v1::*;
 pub mod v2 {
 	pub use super::*;
 	pub use frame_support_procedural::{
-		benchmark, benchmarks, block, extrinsic_call, instance_benchmarks,
+		benchmark, benchmarks, block, extrinsic_call, instance_benchmarks, task,
 	};
 
 	// Used in #[benchmark] implementation to ensure that benchmark function arguments
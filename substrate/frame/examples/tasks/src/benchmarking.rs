@@ -30,10 +30,8 @@ mod benchmarks {
 	fn add_number_into_total() {
 		Numbers::<T>::insert(0, 1);
 
-		#[block]
-		{
-			Task::<T>::add_number_into_total(0).unwrap();
-		}
+		#[task]
+		Task::<T>::AddNumberIntoTotal { i: 0 };
 
 		assert_eq!(Numbers::<T>::get(0), None);
 	}
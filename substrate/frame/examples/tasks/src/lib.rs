@@ -21,7 +21,7 @@
 use frame_support::dispatch::DispatchResult;
 use frame_system::offchain::CreateBare;
 #[cfg(feature = "experimental")]
-use frame_system::offchain::SubmitTransaction;
+use frame_system::offchain::TaskRunner;
 // Re-export pallet items so that they can be accessed from the crate namespace.
 pub use pallet::*;
 
@@ -49,12 +49,13 @@ pub mod pallet {
 		NotFound,
 	}
 
-	#[pallet::tasks_experimental]
+	// Tasks without a `#[pallet::task_weight]` use the weight function of the same name, which is
+	// generated by the `#[task]` benchmark of the task.
+	#[pallet::tasks_experimental(weight(<T as Config>::WeightInfo))]
 	impl<T: Config> Pallet<T> {
 		/// Add a pair of numbers into the totals and remove them.
 		#[pallet::task_list(Numbers::<T>::iter_keys())]
 		#[pallet::task_condition(|i| Numbers::<T>::contains_key(i))]
-		#[pallet::task_weight(T::WeightInfo::add_number_into_total())]
 		#[pallet::task_index(0)]
 		pub fn add_number_into_total(i: u32) -> DispatchResult {
 			let v = Numbers::<T>::take(i).ok_or(Error::<T>::NotFound)?;
@@ -69,21 +70,12 @@ pub mod pallet {
 	#[pallet::hooks]
 	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
 		#[cfg(feature = "experimental")]
-		fn offchain_worker(_block_number: BlockNumberFor<T>) {
-			if let Some(key) = Numbers::<T>::iter_keys().next() {
-				// Create a valid task
-				let task = Task::<T>::AddNumberIntoTotal { i: key };
-				let runtime_task = <T as Config>::RuntimeTask::from(task);
-				let call = frame_system::Call::<T>::do_task { task: runtime_task.into() };
-
-				// Submit the task as an unsigned transaction
-				let xt = <T as CreateBare<frame_system::Call<T>>>::create_bare(call.into());
-				let res = SubmitTransaction::<T, frame_system::Call<T>>::submit_transaction(xt);
-				match res {
-					Ok(_) => log::info!(target: LOG_TARGET, "Submitted the task."),
-					Err(e) => log::error!(target: LOG_TARGET, "Error submitting task: {:?}", e),
-				}
-			}
+		fn offchain_worker(block_number: BlockNumberFor<T>) {
+			// Submit the runnable tasks of this pallet as unsigned transactions.
+			let tasks = <Task<T> as frame_support::traits::Task>::iter()
+				.map(|task| <T as Config>::RuntimeTask::from(task).into());
+			let tasks = TaskRunner::<T>::new().submit_tasks(block_number, tasks);
+			log::info!(target: LOG_TARGET, "Submitted {} tasks.", tasks.len());
 		}
 
 		#[cfg(not(feature = "experimental"))]
@@ -23,7 +23,7 @@ use crate::{mock::*, Numbers};
 use codec::Decode;
 use frame_support::traits::Task;
 #[cfg(feature = "experimental")]
-use sp_core::offchain::{testing, OffchainWorkerExt, TransactionPoolExt};
+use sp_core::offchain::{testing, OffchainDbExt, OffchainWorkerExt, TransactionPoolExt};
 use sp_runtime::BuildStorage;
 
 #[cfg(feature = "experimental")]
@@ -142,6 +142,7 @@ fn task_with_offchain_worker() {
 	let (pool, pool_state) = testing::TestTransactionPoolExt::new();
 
 	let mut t = sp_io::TestExternalities::default();
+	t.register_extension(OffchainDbExt::new(offchain.clone()));
 	t.register_extension(OffchainWorkerExt::new(offchain));
 	t.register_extension(TransactionPoolExt::new(pool));
 
@@ -161,3 +162,94 @@ fn task_with_offchain_worker() {
 		assert!(tx.is_bare());
 	});
 }
+
+#[cfg(feature = "experimental")]
+#[test]
+fn task_runner_limits_submissions() {
+	use frame_system::offchain::TaskRunner;
+
+	let (offchain, _offchain_state) = testing::TestOffchainExt::new();
+	let (pool, pool_state) = testing::TestTransactionPoolExt::new();
+
+	let mut t = sp_io::TestExternalities::default();
+	t.register_extension(OffchainDbExt::new(offchain.clone()));
+	t.register_extension(OffchainWorkerExt::new(offchain));
+	t.register_extension(TransactionPoolExt::new(pool));
+
+	t.execute_with(|| {
+		for i in 0..3 {
+			Numbers::<Runtime>::insert(i, 10);
+		}
+		let runner = TaskRunner::<Runtime>::new().max_per_block(2).resubmit_after(3);
+
+		// At most two tasks are submitted per block.
+		let submitted = runner.submit(1);
+		assert_eq!(submitted.len(), 2);
+		assert_eq!(pool_state.read().transactions.len(), 2);
+
+		// The submitted tasks are skipped, only the remaining one is submitted.
+		assert_eq!(runner.submit(2).len(), 1);
+		assert_eq!(runner.submit(3).len(), 0);
+		assert_eq!(pool_state.read().transactions.len(), 3);
+
+		// Tasks that are still valid are submitted again, the others are not.
+		let crate::pallet::Task::AddNumberIntoTotal { i } =
+			submitted[0].clone().try_into().unwrap()
+		else {
+			unreachable!()
+		};
+		Numbers::<Runtime>::remove(i);
+		assert_eq!(runner.submit(4), vec![submitted[1].clone()]);
+
+		let tx = pool_state.write().transactions.pop().unwrap();
+		let tx = Extrinsic::decode(&mut &*tx).unwrap();
+		assert_eq!(
+			tx.function,
+			RuntimeCall::System(frame_system::Call::do_task { task: submitted[1].clone() })
+		);
+	});
+}
+
+#[cfg(feature = "experimental")]
+#[test]
+fn task_runner_retries_rejected_submissions() {
+	use frame_system::offchain::TaskRunner;
+	use sp_core::offchain::TransactionPool;
+	use std::sync::{
+		atomic::{AtomicBool, Ordering},
+		Arc,
+	};
+
+	/// A transaction pool that rejects all transactions while the flag is set.
+	struct RejectingPool(Arc<AtomicBool>);
+
+	impl TransactionPool for RejectingPool {
+		fn submit_transaction(&mut self, _extrinsic: Vec<u8>) -> Result<(), ()> {
+			if self.0.load(Ordering::Relaxed) {
+				Err(())
+			} else {
+				Ok(())
+			}
+		}
+	}
+
+	let (offchain, _offchain_state) = testing::TestOffchainExt::new();
+	let reject = Arc::new(AtomicBool::new(true));
+
+	let mut t = sp_io::TestExternalities::default();
+	t.register_extension(OffchainDbExt::new(offchain.clone()));
+	t.register_extension(OffchainWorkerExt::new(offchain));
+	t.register_extension(TransactionPoolExt::new(RejectingPool(reject.clone())));
+
+	t.execute_with(|| {
+		Numbers::<Runtime>::insert(0, 10);
+		let runner = TaskRunner::<Runtime>::new().resubmit_after(5);
+
+		// A rejected task is not remembered as submitted.
+		assert!(runner.submit(1).is_empty());
+
+		reject.store(false, Ordering::Relaxed);
+		assert_eq!(runner.submit(2).len(), 1);
+		assert!(runner.submit(3).is_empty());
+	});
+}
@@ -43,6 +43,7 @@ mod keywords {
 	custom_keyword!(pov_mode);
 	custom_keyword!(extrinsic_call);
 	custom_keyword!(skip_meta);
+	custom_keyword!(task);
 	custom_keyword!(BenchmarkError);
 	custom_keyword!(Result);
 	custom_keyword!(MaxEncodedLen);
@@ -237,6 +238,7 @@ impl syn::parse::Parse for BenchmarkAttrs {
 enum BenchmarkCallDef {
 	ExtrinsicCall { origin: Expr, expr_call: ExprCall, attr_span: Span }, // #[extrinsic_call]
 	Block { block: ExprBlock, attr_span: Span },                          // #[block]
+	Task { expr: Expr, attr_span: Span },                                 // #[task]
 }
 
 impl BenchmarkCallDef {
@@ -245,6 +247,7 @@ impl BenchmarkCallDef {
 		match self {
 			BenchmarkCallDef::ExtrinsicCall { origin: _, expr_call: _, attr_span } => *attr_span,
 			BenchmarkCallDef::Block { block: _, attr_span } => *attr_span,
+			BenchmarkCallDef::Task { expr: _, attr_span } => *attr_span,
 		}
 	}
 }
@@ -368,17 +371,20 @@ fn parse_params(item_fn: &ItemFn) -> Result<Vec<ParamDef>> {
 fn missing_call<T>(item_fn: &ItemFn) -> Result<T> {
 	return Err(Error::new(
 		item_fn.block.brace_token.span.join(),
-		"No valid #[extrinsic_call] or #[block] annotation could be found in benchmark function body."
+		"No valid #[extrinsic_call], #[block] or #[task] annotation could be found in benchmark function body."
 	));
 }
 
 /// Finds the `BenchmarkCallDef` and its index (within the list of stmts for the fn) and
 /// returns them. Also handles parsing errors for invalid / extra call defs. AKA this is
-/// general handling for `#[extrinsic_call]` and `#[block]`
+/// general handling for `#[extrinsic_call]`, `#[block]` and `#[task]`
 fn parse_call_def(item_fn: &ItemFn) -> Result<(usize, BenchmarkCallDef)> {
-	// #[extrinsic_call] / #[block] handling
+	// #[extrinsic_call] / #[block] / #[task] handling
 	let call_defs = item_fn.block.stmts.iter().enumerate().filter_map(|(i, child)| {
-			if let Stmt::Expr(Expr::Call(expr_call), _semi) = child {
+			if let Some(task) = parse_task_def(child) {
+				// #[task] case
+				Some(Ok((i, task)))
+			} else if let Stmt::Expr(Expr::Call(expr_call), _semi) = child {
 				// #[extrinsic_call] case
 				expr_call.attrs.iter().enumerate().find_map(|(k, attr)| {
 					let segment = attr.path().segments.last()?;
@@ -414,14 +420,38 @@ fn parse_call_def(item_fn: &ItemFn) -> Result<(usize, BenchmarkCallDef)> {
 	Ok(match &call_defs[..] {
 		[(i, call_def)] => (*i, call_def.clone()), // = 1
 		[] => return missing_call(item_fn),
-		_ =>
-			return Err(Error::new(
-				call_defs[1].1.attr_span(),
-				"Only one #[extrinsic_call] or #[block] attribute is allowed per benchmark.",
-			)),
+		_ => return Err(Error::new(
+			call_defs[1].1.attr_span(),
+			"Only one #[extrinsic_call], #[block] or #[task] attribute is allowed per benchmark.",
+		)),
 	})
 }
 
+/// Parses a statement like `#[task] Task::<T>::Foo { i: 0 };` into a `BenchmarkCallDef::Task`.
+///
+/// Returns `None` if the statement is not annotated with `#[task]`.
+fn parse_task_def(stmt: &Stmt) -> Option<BenchmarkCallDef> {
+	let Stmt::Expr(expr, _semi) = stmt else { return None };
+	let mut expr = expr.clone();
+	let attrs = match &mut expr {
+		Expr::Struct(expr) => &mut expr.attrs,
+		Expr::Path(expr) => &mut expr.attrs,
+		Expr::Call(expr) => &mut expr.attrs,
+		Expr::MethodCall(expr) => &mut expr.attrs,
+		_ => return None,
+	};
+	let k = attrs.iter().position(|attr| {
+		attr.path().segments.last().map_or(false, |segment| {
+			syn::parse::<keywords::task>(segment.ident.to_token_stream().into()).is_ok()
+		})
+	})?;
+
+	// consume #[task] tokens
+	let attr_span = attrs.remove(k).span();
+
+	Some(BenchmarkCallDef::Task { expr, attr_span })
+}
+
 impl BenchmarkDef {
 	/// Constructs a [`BenchmarkDef`] by traversing an existing [`ItemFn`] node.
 	pub fn from(item_fn: &ItemFn) -> Result<BenchmarkDef> {
@@ -439,7 +469,7 @@ impl BenchmarkDef {
 				if i + 1 >= item_fn.block.stmts.len() {
 					return Err(Error::new(
 						item_fn.block.span(),
-						"Benchmark `#[block]` or `#[extrinsic_call]` item cannot be the \
+						"Benchmark `#[block]`, `#[extrinsic_call]` or `#[task]` item cannot be the \
 						last statement of your benchmark function definition if you have \
 						defined a return type. You should return something compatible \
 						with Result<(), BenchmarkError> (i.e. `Ok(())`) as the last statement \
@@ -1028,6 +1058,29 @@ fn expand_benchmark(
 		},
 		BenchmarkCallDef::Block { block, attr_span: _ } =>
 			(quote!(), quote!(#block), quote!(#block)),
+		BenchmarkCallDef::Task { expr, attr_span: _ } => {
+			// The task is created before the measurement starts. Like `frame_system::do_task`,
+			// the measured part checks that the task is valid and then runs it.
+			let pre_call = quote! {
+				let __task = #expr;
+			};
+			let post_call = quote! {
+				(if #traits::Task::is_valid(&__task) {
+					#traits::Task::run(&__task)
+				} else {
+					Err("benchmarked task is not valid".into())
+				})
+			};
+			(
+				// (pre_call, post_call, fn_call_body):
+				pre_call.clone(),
+				quote!(#post_call?;),
+				quote! {
+					#pre_call
+					#post_call.unwrap();
+				},
+			)
+		},
 	};
 
 	let vis = benchmark_def.fn_vis;
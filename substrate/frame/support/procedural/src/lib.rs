@@ -265,6 +265,19 @@ pub fn block(_attrs: TokenStream, _tokens: TokenStream) -> TokenStream {
 	.into()
 }
 
+/// An attribute macro used to specify the task that is benchmarked by the enclosing benchmark
+/// function. Like `#[block]`, this attribute is also used as a boundary designating where the
+/// benchmark setup code ends, and the benchmark verification code begins.
+///
+/// See `frame_benchmarking::v2` for more info.
+#[proc_macro_attribute]
+pub fn task(_attrs: TokenStream, _tokens: TokenStream) -> TokenStream {
+	quote!(compile_error!(
+		"`#[task]` must be in a benchmark function definition labeled with `#[benchmark]`."
+	))
+	.into()
+}
+
 /// Execute the annotated function in a new storage transaction.
 ///
 /// The return type of the annotated function must be `Result`. All changes to storage performed
@@ -1137,6 +1150,12 @@ pub fn composite_enum(_: TokenStream, _: TokenStream) -> TokenStream {
 /// * [`pallet::task_weight`](macro@task_weight)
 /// * [`pallet::task_index`](macro@task_index)
 ///
+/// The [`pallet::task_weight`](macro@task_weight) can be omitted if the weight functions are
+/// given as `#[pallet::tasks_experimental(weight($type))]`, similar to
+/// `#[pallet::call(weight($type))]`. Tasks without a weight then use the function of the same
+/// name in `$type`, like `<$type>::task_name()`. These functions are generated by benchmarking
+/// the tasks with `#[task]`, see `frame_benchmarking::v2`.
+///
 /// All of such Tasks are then aggregated into a `RuntimeTask` by
 /// [`construct_runtime`](macro@construct_runtime).
 ///
@@ -145,7 +145,7 @@ impl TasksDef {
 			.collect::<Vec<_>>();
 		let task_indices = self.tasks.iter().map(|task| &task.index_attr.meta.index);
 		let task_conditions = self.tasks.iter().map(|task| &task.condition_attr.meta.expr);
-		let task_weights = self.tasks.iter().map(|task| &task.weight_attr.meta.expr);
+		let task_weights = self.tasks.iter().map(|task| self.task_weight(task));
 		let task_iters = self.tasks.iter().map(|task| &task.list_attr.meta.expr);
 
 		let task_fn_impls = self.tasks.iter().map(|task| {
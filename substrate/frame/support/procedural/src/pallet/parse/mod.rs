@@ -134,12 +134,16 @@ impl Def {
 				},
 				Some(PalletAttr::RuntimeCall(cw, span)) if call.is_none() =>
 					call = Some(call::CallDef::try_from(span, index, item, dev_mode, cw)?),
-				Some(PalletAttr::Tasks(span)) if tasks.is_none() => {
+				Some(PalletAttr::Tasks(tw, span)) if tasks.is_none() => {
 					let item_tokens = item.to_token_stream();
+					let weight = tw.map(|tw| {
+						let typename = tw.typename;
+						quote::quote!((weight(#typename)))
+					});
 					// `TasksDef::parse` needs to know if attr was provided so we artificially
 					// re-insert it here
 					tasks = Some(syn::parse2::<tasks::TasksDef>(quote::quote_spanned! { span =>
-						#[pallet::tasks_experimental]
+						#[pallet::tasks_experimental #weight]
 						#item_tokens
 					})?);
 
@@ -636,7 +640,11 @@ enum PalletAttr {
 	/// instead of the zero weight. So to say: it works together with `dev_mode`.
 	RuntimeCall(Option<InheritedCallWeightAttr>, proc_macro2::Span),
 	Error(proc_macro2::Span),
-	Tasks(proc_macro2::Span),
+	/// A `#[pallet::tasks_experimental]` with an optional inherited weight.
+	///
+	/// Like for `#[pallet::call(weight($type))]`, tasks without a `#[pallet::task_weight]`
+	/// annotation then use the weight function of the same name in `$type`.
+	Tasks(Option<InheritedCallWeightAttr>, proc_macro2::Span),
 	TaskList(proc_macro2::Span),
 	TaskCondition(proc_macro2::Span),
 	TaskIndex(proc_macro2::Span),
@@ -660,7 +668,7 @@ impl PalletAttr {
 			Self::Config { span, .. } => *span,
 			Self::Pallet(span) => *span,
 			Self::Hooks(span) => *span,
-			Self::Tasks(span) => *span,
+			Self::Tasks(_, span) => *span,
 			Self::TaskCondition(span) => *span,
 			Self::TaskIndex(span) => *span,
 			Self::TaskList(span) => *span,
@@ -764,7 +772,12 @@ impl syn::parse::Parse for PalletAttr {
 			};
 			Ok(PalletAttr::RuntimeCall(attr, span))
 		} else if lookahead.peek(keyword::tasks_experimental) {
-			Ok(PalletAttr::Tasks(content.parse::<keyword::tasks_experimental>()?.span()))
+			let span = content.parse::<keyword::tasks_experimental>().expect("peeked").span();
+			let attr = match content.is_empty() {
+				true => None,
+				false => Some(InheritedCallWeightAttr::parse(&content)?),
+			};
+			Ok(PalletAttr::Tasks(attr, span))
 		} else if lookahead.peek(keyword::task_enum) {
 			Ok(PalletAttr::RuntimeTask(content.parse::<keyword::task_enum>()?.span()))
 		} else if lookahead.peek(keyword::task_condition) {
@@ -25,7 +25,7 @@ use crate::assert_parse_error_matches;
 #[cfg(test)]
 use crate::pallet::parse::tests::simulate_manifest_dir;
 
-use super::helper;
+use super::{helper, InheritedCallWeightAttr};
 use derive_syn_parse::Parse;
 use proc_macro2::TokenStream as TokenStream2;
 use quote::{quote, ToTokens};
@@ -35,7 +35,7 @@ use syn::{
 	spanned::Spanned,
 	token::{Bracket, Paren, PathSep, Pound},
 	Error, Expr, Ident, ImplItem, ImplItemFn, ItemEnum, ItemImpl, LitInt, PathArguments, Result,
-	TypePath,
+	Type, TypePath,
 };
 
 pub mod keywords {
@@ -86,6 +86,15 @@ impl syn::parse::Parse for TasksDef {
 		} else {
 			Vec::new()
 		};
+		let inherited_weight = tasks_attr.as_ref().and_then(|attr| attr.meta.weight.as_ref());
+		if let (Some(task), None) =
+			(tasks.iter().find(|task| task.weight_attr.is_none()), inherited_weight)
+		{
+			return Err(Error::new(
+				task.item.sig.ident.span(),
+				"missing `#[pallet::task_weight(..)]` attribute",
+			))
+		}
 		let mut task_indices = HashSet::<LitInt>::new();
 		for task in tasks.iter() {
 			let task_index = &task.index_attr.meta.index;
@@ -116,8 +125,55 @@ impl syn::parse::Parse for TasksDef {
 	}
 }
 
+impl TasksDef {
+	/// Returns the weight of `task`.
+	///
+	/// Tasks without a `#[pallet::task_weight]` attribute use the weight function of the same
+	/// name in the type from `#[pallet::tasks_experimental(weight($type))]`.
+	pub fn task_weight(&self, task: &TaskDef) -> TokenStream2 {
+		match (&task.weight_attr, self.tasks_attr.as_ref().and_then(|a| a.meta.weight.as_ref())) {
+			(Some(weight_attr), _) => weight_attr.meta.expr.to_token_stream(),
+			(None, Some(typename)) => {
+				let name = &task.item.sig.ident;
+				quote!({ < #typename > :: #name () })
+			},
+			(None, None) => unreachable!("checked when parsing the tasks; qed"),
+		}
+	}
+}
+
 /// Parsing for a `#[pallet::tasks_experimental]` attr.
-pub type PalletTasksAttr = PalletTaskAttr<keywords::tasks_experimental>;
+pub type PalletTasksAttr = PalletTaskAttr<TasksAttrMeta>;
+
+/// The contents of a `#[pallet::tasks_experimental]` attribute.
+#[derive(Debug, Clone)]
+pub struct TasksAttrMeta {
+	pub tasks_experimental: keywords::tasks_experimental,
+	/// The `$type` of an optional `(weight($type))` argument.
+	pub weight: Option<Type>,
+}
+
+impl syn::parse::Parse for TasksAttrMeta {
+	fn parse(input: ParseStream) -> Result<Self> {
+		let tasks_experimental = input.parse()?;
+		let weight = match input.is_empty() {
+			true => None,
+			false => Some(InheritedCallWeightAttr::parse(input)?.typename),
+		};
+
+		Ok(TasksAttrMeta { tasks_experimental, weight })
+	}
+}
+
+impl ToTokens for TasksAttrMeta {
+	fn to_tokens(&self, tokens: &mut TokenStream2) {
+		let tasks_experimental = self.tasks_experimental;
+		tokens.extend(quote!(#tasks_experimental));
+		if let Some(weight) = &self.weight {
+			tokens.extend(quote!((weight(#weight))));
+		}
+	}
+}
 
 /// Parsing for any of the attributes that can be used within a `#[pallet::tasks_experimental]`
 /// [`ItemImpl`].
@@ -169,7 +225,7 @@ pub struct TaskDef {
 	pub index_attr: TaskIndexAttr,
 	pub condition_attr: TaskConditionAttr,
 	pub list_attr: TaskListAttr,
-	pub weight_attr: TaskWeightAttr,
+	pub weight_attr: Option<TaskWeightAttr>,
 	pub item: ImplItemFn,
 	pub arg_names: Vec<Ident>,
 }
@@ -219,16 +275,11 @@ impl syn::parse::Parse for TaskDef {
 			))
 		};
 
-		let Some(weight_attr) = task_attrs
+		// A missing weight is checked by `TasksDef`, which knows about the inherited weight.
+		let weight_attr = task_attrs
 			.iter()
 			.find(|attr| matches!(attr.meta, TaskAttrMeta::TaskWeight(_)))
-			.cloned()
-		else {
-			return Err(Error::new(
-				item.sig.ident.span(),
-				"missing `#[pallet::task_weight(..)]` attribute",
-			))
-		};
+			.cloned();
 
 		if let Some(duplicate) = task_attrs
 			.iter()
@@ -280,7 +331,8 @@ impl syn::parse::Parse for TaskDef {
 		let index_attr = index_attr.try_into().expect("we check the type above; QED");
 		let condition_attr = condition_attr.try_into().expect("we check the type above; QED");
 		let list_attr = list_attr.try_into().expect("we check the type above; QED");
-		let weight_attr = weight_attr.try_into().expect("we check the type above; QED");
+		let weight_attr =
+			weight_attr.map(|attr| attr.try_into().expect("we check the type above; QED"));
 
 		Ok(TaskDef { index_attr, condition_attr, list_attr, weight_attr, item, arg_names })
 	}
@@ -585,6 +637,12 @@ fn test_parse_task_condition() {
 #[test]
 fn test_parse_tasks_attr() {
 	parse2::<PalletTasksAttr>(quote!(#[pallet::tasks_experimental])).unwrap();
+	parse2::<PalletTasksAttr>(quote!(#[pallet::tasks_experimental(weight(T::WeightInfo))]))
+		.unwrap();
+	parse2::<PalletTasksAttr>(
+		quote!(#[pallet::tasks_experimental(weight = <T as Config>::WeightInfo)]),
+	)
+	.unwrap();
 	assert_parse_error_matches!(
 		parse2::<PalletTasksAttr>(quote!(#[pallet::taskss])),
 		"expected `tasks_experimental`"
@@ -599,10 +657,42 @@ fn test_parse_tasks_attr() {
 	);
 	assert_parse_error_matches!(
 		parse2::<PalletTasksAttr>(quote!(#[pallet::tasks_experimental()])),
-		"unexpected token"
+		"expected `weight`"
 	);
 }
 
+#[test]
+fn test_parse_tasks_def_inherited_weight() {
+	simulate_manifest_dir("../../examples/basic", || {
+		let parsed = parse2::<TasksDef>(quote! {
+			#[pallet::tasks_experimental(weight(<T as Config<I>>::WeightInfo))]
+			impl<T: Config<I>, I: 'static> Pallet<T, I> {
+				#[pallet::task_list(Numbers::<T, I>::iter_keys())]
+				#[pallet::task_condition(|i| Numbers::<T, I>::contains_key(i))]
+				#[pallet::task_index(0)]
+				pub fn add_number_into_total(i: u32) -> DispatchResult {
+					Ok(())
+				}
+
+				#[pallet::task_list(Numbers::<T, I>::iter_keys())]
+				#[pallet::task_condition(|i| Numbers::<T, I>::contains_key(i))]
+				#[pallet::task_index(1)]
+				#[pallet::task_weight(0)]
+				pub fn remove_number(i: u32) -> DispatchResult {
+					Ok(())
+				}
+			}
+		})
+		.unwrap();
+		assert_eq!(parsed.tasks.len(), 2);
+		assert_eq!(
+			parsed.task_weight(&parsed.tasks[0]).to_string(),
+			quote!({ <<T as Config<I>>::WeightInfo>::add_number_into_total() }).to_string(),
+		);
+		assert_eq!(parsed.task_weight(&parsed.tasks[1]).to_string(), quote!(0).to_string());
+	});
+}
+
 #[test]
 fn test_parse_tasks_def_basic() {
 	simulate_manifest_dir("../../examples/basic", || {
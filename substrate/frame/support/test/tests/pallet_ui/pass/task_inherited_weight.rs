@@ -0,0 +1,58 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+#[frame_support::pallet]
+pub mod pallet {
+	use frame_support::{pallet_prelude::*, weights::Weight};
+
+	pub trait WeightInfo {
+		fn foo() -> Weight;
+	}
+
+	impl WeightInfo for () {
+		fn foo() -> Weight {
+			Weight::from_parts(10, 0)
+		}
+	}
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		type WeightInfo: WeightInfo;
+	}
+
+	#[pallet::pallet]
+	pub struct Pallet<T>(core::marker::PhantomData<T>);
+
+	#[pallet::tasks_experimental(weight(<T as Config>::WeightInfo))]
+	impl<T: Config> Pallet<T> {
+		#[pallet::task_index(0)]
+		#[pallet::task_condition(|i| i == 0u32)]
+		#[pallet::task_list(vec![0u32, 1u32].into_iter())]
+		fn foo(_i: u32) -> DispatchResult {
+			Ok(())
+		}
+
+		#[pallet::task_index(1)]
+		#[pallet::task_condition(|| true)]
+		#[pallet::task_list(vec![()].into_iter())]
+		#[pallet::task_weight(Weight::zero())]
+		fn bar() -> DispatchResult {
+			Ok(())
+		}
+	}
+}
+
+fn main() {}
@@ -815,6 +815,11 @@ pub mod pallet {
 			Ok(().into())
 		}
 
+		/// Do some specified task.
+		///
+		/// The weight is the [`Task::weight`](frame_support::traits::Task::weight) of `task`.
+		/// Pallets generate it by benchmarking their tasks with `#[task]` and inheriting the
+		/// generated weight functions with `#[pallet::tasks_experimental(weight($type))]`.
 		#[cfg(feature = "experimental")]
 		#[pallet::call_index(8)]
 		#[pallet::weight(task.weight())]
@@ -53,6 +53,11 @@
 //! #### Submit a signed transaction
 //!
 //! [`Signer`](./struct.Signer.html) can be used to sign/verify payloads
+//!
+//! ### Submit runnable tasks
+//!
+//! With the `experimental` feature, `TaskRunner` submits the runnable tasks of all pallets as
+//! unsigned `do_task` transactions.
 
 #![warn(missing_docs)]
 
@@ -64,6 +69,15 @@ use sp_runtime::{
 	traits::{ExtrinsicLike, IdentifyAccount, One},
 	RuntimeDebug,
 };
+#[cfg(feature = "experimental")]
+use {
+	crate::{pallet_prelude::BlockNumberFor, LOG_TARGET},
+	frame_support::traits::Task,
+	sp_runtime::{
+		offchain::storage::{StorageRetrievalError, StorageValueRef},
+		traits::{Hash, Saturating},
+	},
+};
 
 /// Marker struct used to flag using all supported keys to sign a payload.
 pub struct ForAll {}
@@ -656,6 +670,147 @@ pub trait SignedPayload<T: SigningTypes>: Encode {
 	}
 }
 
+/// Key in the persistent offchain storage under which [`TaskRunner`] keeps the recently
+/// submitted tasks.
+#[cfg(feature = "experimental")]
+pub const TASK_RUNNER_STORAGE_KEY: &[u8] = b"frame_system::task_runner::submitted";
+
+/// Submits the runnable tasks of the runtime as unsigned [`do_task`](crate::Call::do_task)
+/// transactions.
+///
+/// The tasks of all pallets are enumerated through [`Task::iter`] of the aggregated
+/// `RuntimeTask`, tasks that are not [`Task::is_valid`] are skipped. To not flood the transaction
+/// pool, at most `max_per_block` tasks are submitted per call and a submitted task is not
+/// submitted again within `resubmit_after` blocks. A task is only remembered if it was accepted
+/// by the transaction pool. The recently submitted tasks are kept in the persistent offchain
+/// storage under [`TASK_RUNNER_STORAGE_KEY`], so they are remembered across offchain worker runs.
+///
+/// Meant to be called from the `offchain_worker` hook of a pallet. A pallet that is generic over
+/// the runtime enumerates its own tasks with [`TaskRunner::submit_tasks`]:
+///
+/// ```ignore
+/// fn offchain_worker(now: BlockNumberFor<T>) {
+/// 	let tasks = Task::<T>::iter().map(|task| <T as Config>::RuntimeTask::from(task).into());
+/// 	frame_system::offchain::TaskRunner::<T>::new().max_per_block(5).submit_tasks(now, tasks);
+/// }
+/// ```
+#[cfg(feature = "experimental")]
+pub struct TaskRunner<T: crate::Config> {
+	max_per_block: u32,
+	resubmit_after: BlockNumberFor<T>,
+}
+
+#[cfg(feature = "experimental")]
+impl<T: crate::Config> Default for TaskRunner<T> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+#[cfg(feature = "experimental")]
+impl<T: crate::Config> TaskRunner<T> {
+	/// Creates a runner that submits up to 10 tasks per block and resubmits a task that is still
+	/// runnable after 5 blocks.
+	pub fn new() -> Self {
+		Self { max_per_block: 10, resubmit_after: 5u32.into() }
+	}
+
+	/// Sets the maximum number of tasks that are submitted by one call to [`Self::submit`].
+	pub fn max_per_block(mut self, max: u32) -> Self {
+		self.max_per_block = max;
+		self
+	}
+
+	/// Sets the number of blocks after which a task that is still runnable is submitted again.
+	pub fn resubmit_after(mut self, blocks: BlockNumberFor<T>) -> Self {
+		self.resubmit_after = blocks;
+		self
+	}
+
+	/// Submits the runnable tasks of all pallets at block `now`.
+	///
+	/// Returns the tasks that were submitted to the transaction pool. Nothing is submitted if
+	/// the offchain worker of another block is submitting tasks at the same time.
+	pub fn submit(&self, now: BlockNumberFor<T>) -> Vec<T::RuntimeTask>
+	where
+		T: CreateBare<crate::Call<T>>,
+		<T::RuntimeTask as Task>::Enumeration: Iterator<Item = T::RuntimeTask>,
+	{
+		self.submit_tasks(now, T::RuntimeTask::iter())
+	}
+
+	/// Submits the runnable tasks out of `tasks` at block `now`.
+	///
+	/// Like [`Self::submit`], but for pallets that are generic over the runtime and enumerate
+	/// their own tasks.
+	pub fn submit_tasks(
+		&self,
+		now: BlockNumberFor<T>,
+		candidates: impl IntoIterator<Item = T::RuntimeTask>,
+	) -> Vec<T::RuntimeTask>
+	where
+		T: CreateBare<crate::Call<T>>,
+	{
+		let mut tasks = Vec::new();
+		let storage = StorageValueRef::persistent(TASK_RUNNER_STORAGE_KEY);
+		let res = storage.mutate(
+			|recent: Result<Option<Vec<(T::Hash, BlockNumberFor<T>)>>, StorageRetrievalError>| {
+				let mut recent = recent.ok().flatten().unwrap_or_default();
+				recent.retain(|(_, at)| now < at.saturating_add(self.resubmit_after));
+
+				for task in candidates {
+					if tasks.len() >= self.max_per_block as usize {
+						break
+					}
+					let hash = T::Hashing::hash_of(&task);
+					if recent.iter().any(|(h, _)| *h == hash) || !task.is_valid() {
+						continue
+					}
+					recent.push((hash, now));
+					tasks.push(task);
+				}
+
+				Ok::<_, ()>(recent)
+			},
+		);
+		if res.is_err() {
+			log::debug!(target: LOG_TARGET, "Tasks are submitted by another offchain worker");
+			return Vec::new()
+		}
+
+		let mut failed = Vec::new();
+		tasks.retain(|task| {
+			let call = crate::Call::<T>::do_task { task: task.clone() };
+			let xt = T::create_bare(call.into());
+			let res = SubmitTransaction::<T, crate::Call<T>>::submit_transaction(xt);
+			if res.is_err() {
+				log::error!(target: LOG_TARGET, "Failed to submit task {:?}", task);
+				failed.push(T::Hashing::hash_of(task));
+			}
+			res.is_ok()
+		});
+
+		// Tasks that could not be submitted are tried again at the next block.
+		if !failed.is_empty() {
+			let res = storage.mutate(
+				|recent: Result<
+					Option<Vec<(T::Hash, BlockNumberFor<T>)>>,
+					StorageRetrievalError,
+				>| {
+					let mut recent = recent.ok().flatten().unwrap_or_default();
+					recent.retain(|(hash, at)| *at != now || !failed.contains(hash));
+					Ok::<_, ()>(recent)
+				},
+			);
+			if res.is_err() {
+				log::warn!(target: LOG_TARGET, "Failed to forget {} unsubmitted tasks", failed.len());
+			}
+		}
+
+		tasks
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -0,0 +1,611 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Breaks the storage benchmark results down by pallet and storage item.
+//!
+//! Keys are grouped by their `twox128(pallet) ++ twox128(item)` prefix, which is resolved to
+//! human readable names with the runtime metadata. For every group the read and write costs are
+//! measured separately and the trie depth and value sizes are collected into histograms.
+
+use clap::ValueEnum;
+use codec::Decode;
+use comfy_table::Table;
+use log::info;
+use rand::prelude::*;
+use sc_cli::{Error, Result};
+use sc_client_api::{Backend as ClientBackend, StorageProvider, UsageProvider};
+use sc_client_db::DbHash;
+use sc_executor::WasmExecutor;
+use sc_runtime_utilities::fetch_latest_metadata_from_code_blob;
+use sc_service::Configuration;
+use serde::Serialize;
+use sp_blockchain::HeaderBackend;
+use sp_core::{hashing::twox_128, storage::well_known_keys};
+use sp_database::ColumnId;
+use sp_runtime::traits::{Block as BlockT, HashingFor, Header as HeaderT};
+use sp_state_machine::{backend::AsTrieBackend, Backend};
+use sp_storage::StorageKey;
+use std::{
+	collections::{BTreeMap, HashMap},
+	fmt::{self, Debug},
+	fs,
+	path::PathBuf,
+	sync::Arc,
+	time::Instant,
+};
+
+use super::{cmd::StorageCmd, write::check_new_value};
+use crate::shared::{new_rng, BenchRecord, Stats};
+
+/// Granularity of the per-prefix breakdown.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, ValueEnum)]
+pub enum BreakdownLevel {
+	/// Group keys by pallet, i.e. by the first 16 bytes of the key.
+	Pallet,
+	/// Group keys by storage item, i.e. by the first 32 bytes of the key.
+	#[default]
+	Item,
+}
+
+/// The pallet and storage item that a key belongs to.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+pub struct StoragePrefix {
+	/// Name of the pallet as declared in `construct_runtime!`.
+	pub pallet: String,
+	/// Name of the storage item. `None` when grouping by pallet.
+	pub item: Option<String>,
+}
+
+impl StoragePrefix {
+	/// Prefix for keys that could not be resolved with the metadata.
+	fn unknown() -> Self {
+		Self { pallet: "Unknown".into(), item: None }
+	}
+
+	/// Returns a name that can be used as a Rust identifier in the weight template.
+	pub fn ident(&self) -> String {
+		let mut ident: String = self.pallet.chars().filter(|c| c.is_alphanumeric()).collect();
+		if let Some(item) = &self.item {
+			ident.extend(item.chars().filter(|c| c.is_alphanumeric()));
+		}
+		ident
+	}
+}
+
+impl fmt::Display for StoragePrefix {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match &self.item {
+			Some(item) => write!(f, "{}::{}", self.pallet, item),
+			None => write!(f, "{}", self.pallet),
+		}
+	}
+}
+
+/// Maps raw storage keys to the [`StoragePrefix`] they belong to.
+pub struct PrefixResolver {
+	level: BreakdownLevel,
+	pallets: HashMap<[u8; 16], String>,
+	items: HashMap<[u8; 32], (String, String)>,
+}
+
+impl PrefixResolver {
+	/// Builds the lookup tables from the storage section of the runtime metadata.
+	pub fn from_metadata(metadata: &subxt::Metadata, level: BreakdownLevel) -> Self {
+		let mut pallets = HashMap::new();
+		let mut items = HashMap::new();
+
+		for pallet in metadata.pallets() {
+			let Some(storage) = pallet.storage() else { continue };
+			let pallet_hash = twox_128(storage.prefix().as_bytes());
+			pallets.insert(pallet_hash, storage.prefix().to_string());
+
+			for entry in storage.entries() {
+				let mut item_hash = [0u8; 32];
+				item_hash[..16].copy_from_slice(&pallet_hash);
+				item_hash[16..].copy_from_slice(&twox_128(entry.name().as_bytes()));
+				items.insert(item_hash, (storage.prefix().to_string(), entry.name().to_string()));
+			}
+		}
+
+		Self { level, pallets, items }
+	}
+
+	/// Returns the prefix of `key`.
+	///
+	/// Keys of the pallets in the metadata are resolved first, since the hash of a pallet name can
+	/// start with a colon. Other well-known keys like `:code` are grouped under their own name and
+	/// the roots of all child tries under `:child_storage:`.
+	pub fn resolve(&self, key: &[u8]) -> StoragePrefix {
+		if let Some(pallet) = key.get(..16).and_then(|p| self.pallets.get(p)) {
+			return match self.level {
+				BreakdownLevel::Pallet => StoragePrefix { pallet: pallet.clone(), item: None },
+				BreakdownLevel::Item => key
+					.get(..32)
+					.and_then(|p| self.items.get(p))
+					.map(|(pallet, item)| StoragePrefix {
+						pallet: pallet.clone(),
+						item: Some(item.clone()),
+					})
+					.unwrap_or_else(StoragePrefix::unknown),
+			}
+		}
+
+		if well_known_keys::is_child_storage_key(key) {
+			StoragePrefix {
+				pallet: String::from_utf8_lossy(well_known_keys::CHILD_STORAGE_KEY_PREFIX)
+					.into_owned(),
+				item: None,
+			}
+		} else if key.starts_with(b":") {
+			StoragePrefix { pallet: String::from_utf8_lossy(key).into_owned(), item: None }
+		} else {
+			StoragePrefix::unknown()
+		}
+	}
+}
+
+/// A single bucket of a [`Histogram`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HistogramBucket {
+	/// Inclusive lower bound of the bucket.
+	pub from: u64,
+	/// Exclusive upper bound of the bucket.
+	pub to: u64,
+	/// Number of values that fell into this bucket.
+	pub count: u64,
+}
+
+/// Histogram over `u64` values.
+///
+/// Either uses one bucket per value (for the trie depth) or power-of-two buckets (for the value
+/// sizes).
+#[derive(Debug, Clone, Default)]
+pub struct Histogram {
+	exponential: bool,
+	buckets: BTreeMap<u64, u64>,
+}
+
+impl Histogram {
+	/// Histogram with one bucket per observed value.
+	pub fn linear() -> Self {
+		Self { exponential: false, buckets: Default::default() }
+	}
+
+	/// Histogram with buckets `[0, 1), [1, 2), [2, 4), [4, 8), ...`.
+	pub fn exponential() -> Self {
+		Self { exponential: true, buckets: Default::default() }
+	}
+
+	/// Adds a value to the histogram.
+	pub fn insert(&mut self, value: u64) {
+		*self.buckets.entry(self.bucket_start(value)).or_default() += 1;
+	}
+
+	/// Returns the non-empty buckets in ascending order.
+	pub fn buckets(&self) -> Vec<HistogramBucket> {
+		self.buckets
+			.iter()
+			.map(|(from, count)| HistogramBucket {
+				from: *from,
+				to: self.bucket_end(*from),
+				count: *count,
+			})
+			.collect()
+	}
+
+	fn bucket_start(&self, value: u64) -> u64 {
+		match (self.exponential, value) {
+			(false, v) | (true, v @ 0) => v,
+			(true, v) => 1 << v.ilog2(),
+		}
+	}
+
+	fn bucket_end(&self, from: u64) -> u64 {
+		match (self.exponential, from) {
+			(false, f) | (true, f @ 0) => f.saturating_add(1),
+			(true, f) => f.saturating_mul(2),
+		}
+	}
+}
+
+impl Serialize for Histogram {
+	fn serialize<S: serde::Serializer>(
+		&self,
+		serializer: S,
+	) -> std::result::Result<S::Ok, S::Error> {
+		self.buckets().serialize(serializer)
+	}
+}
+
+/// Benchmark results of a single [`StoragePrefix`].
+#[derive(Debug, Clone, Serialize)]
+pub struct PrefixResult {
+	/// The prefix that these results belong to.
+	pub prefix: StoragePrefix,
+	/// The prefix as Rust identifier, used for naming the weight constants.
+	pub ident: String,
+	/// Number of keys that were found under this prefix.
+	pub keys: usize,
+	/// Time stats of reading a single key, in nanoseconds.
+	pub read: Stats,
+	/// Time stats of writing a single key, in nanoseconds.
+	///
+	/// `None` if the write benchmark was skipped or there were not enough keys.
+	pub write: Option<Stats>,
+	/// Value size stats, in bytes.
+	pub value_size: Stats,
+	/// Number of trie nodes that need to be accessed to read a key.
+	pub depth_histogram: Histogram,
+	/// Value sizes in bytes.
+	pub value_size_histogram: Histogram,
+	/// The resulting read weight. Only set if the prefix has enough keys.
+	pub read_weight: Option<u64>,
+	/// The resulting write weight. Only set if the prefix has enough keys.
+	pub write_weight: Option<u64>,
+}
+
+/// The results of the per-prefix breakdown, sorted by number of keys.
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct Breakdown {
+	/// The granularity that was used to group keys.
+	pub level: BreakdownLevel,
+	/// Results for each prefix.
+	pub prefixes: Vec<PrefixResult>,
+}
+
+impl Breakdown {
+	/// Prints a summary table of the breakdown.
+	pub fn print_summary(&self) {
+		let mut table = Table::new();
+		table.set_header([
+			"Prefix",
+			"Keys",
+			"Read avg [ns]",
+			"Write avg [ns]",
+			"Depth min/max",
+			"Size avg [B]",
+		]);
+
+		for result in &self.prefixes {
+			let depth = result.depth_histogram.buckets();
+			table.add_row([
+				result.prefix.to_string(),
+				result.keys.to_string(),
+				result.read.avg.to_string(),
+				result.write.as_ref().map_or("-".into(), |w| w.avg.to_string()),
+				format!(
+					"{}/{}",
+					depth.first().map_or(0, |b| b.from),
+					depth.last().map_or(0, |b| b.from),
+				),
+				result.value_size.avg.to_string(),
+			]);
+		}
+
+		info!("Per-prefix breakdown:\n{}", table);
+	}
+
+	/// Saves the breakdown as JSON. Works like [`BenchRecord::save_json`].
+	pub fn save_json(&self, cfg: &Configuration, out_path: &PathBuf) -> Result<()> {
+		let mut path = PathBuf::from(out_path);
+		if path.is_dir() || path.as_os_str().is_empty() {
+			path.push(&format!("{}_breakdown", cfg.database).to_lowercase());
+			path.set_extension("json");
+		}
+
+		let json = serde_json::to_string_pretty(&self)
+			.map_err(|e| format!("Serializing as JSON: {:?}", e))?;
+
+		fs::write(&path, json)?;
+		info!("Breakdown written to {:?}", fs::canonicalize(&path)?);
+		Ok(())
+	}
+}
+
+/// Intermediate measurements of one prefix.
+#[derive(Default)]
+struct PrefixMeasurement {
+	/// The keys of the prefix and the length of their values.
+	keys: Vec<(StorageKey, usize)>,
+	read: BenchRecord,
+	write: Option<BenchRecord>,
+	depth: Option<Histogram>,
+	value_size: Option<Histogram>,
+}
+
+impl StorageCmd {
+	/// Benchmarks reads and writes separately for every storage prefix of the latest state.
+	///
+	/// Only the `import-block` mode is supported, since the validation of a block does not
+	/// depend on the prefix of the accessed keys. Child trees are not included.
+	pub(crate) fn bench_breakdown<Block, BA, C>(
+		&self,
+		client: Arc<C>,
+		(db, state_col): (Arc<dyn sp_database::Database<DbHash>>, ColumnId),
+		storage: Arc<dyn sp_state_machine::Storage<HashingFor<Block>>>,
+		shared_trie_cache: Option<sp_trie::cache::SharedTrieCache<HashingFor<Block>>>,
+	) -> Result<Breakdown>
+	where
+		Block: BlockT<Hash = DbHash> + Debug,
+		BA: ClientBackend<Block>,
+		C: UsageProvider<Block> + HeaderBackend<Block> + StorageProvider<Block, BA>,
+	{
+		if !self.params.is_import_block_mode() {
+			return Err("The per-prefix breakdown is only supported in `import-block` mode.".into())
+		}
+
+		let best_hash = client.usage_info().chain.best_hash;
+		let resolver = self.prefix_resolver(&client, best_hash)?;
+
+		info!("Grouping keys of block {} by {:?}", best_hash, self.params.breakdown_level);
+		let mut groups = BTreeMap::<StoragePrefix, PrefixMeasurement>::new();
+		for (key, value) in client.storage_pairs(best_hash, None, None)? {
+			groups
+				.entry(resolver.resolve(&key.0))
+				.or_default()
+				.keys
+				.push((key, value.0.len()));
+		}
+		if groups.is_empty() {
+			return Err("Can't process benchmarking with empty storage".into())
+		}
+
+		let (mut rng, _) = new_rng(None);
+		let state = client
+			.state_at(best_hash)
+			.map_err(|_err| Error::Input("State not found".into()))?;
+
+		for (prefix, measurement) in groups.iter_mut() {
+			info!("Reading {} keys of {}", measurement.keys.len(), prefix);
+			measurement.keys.shuffle(&mut rng);
+			let mut depth = Histogram::linear();
+			let mut value_size = Histogram::exponential();
+
+			for batch in measurement.keys.chunks(self.params.batch_size) {
+				// Same as in `bench_read`: one recorder per batch to amortize the cost.
+				let recorder = (!self.params.disable_pov_recorder)
+					.then(sp_trie::recorder::Recorder::<HashingFor<Block>>::default);
+				let backend = sp_state_machine::TrieBackendBuilder::wrap(state.as_trie_backend())
+					.with_optional_recorder(recorder)
+					.build();
+
+				for (key, _) in batch {
+					let start = Instant::now();
+					let v = backend
+						.storage(key.0.as_ref())
+						.map_err(|e| format!("Reading key: {}", e))?
+						.ok_or("Value unexpectedly empty")?;
+					measurement.read.append(v.len(), start.elapsed())?;
+					value_size.insert(v.len() as u64);
+				}
+			}
+
+			// The depth is measured in a separate pass so that it does not influence the timings.
+			for (key, _) in measurement.keys.iter() {
+				let recorder = sp_trie::recorder::Recorder::<HashingFor<Block>>::default();
+				let backend = sp_state_machine::TrieBackendBuilder::wrap(state.as_trie_backend())
+					.with_recorder(recorder.clone())
+					.build();
+				backend.storage(key.0.as_ref()).map_err(|e| format!("Reading key: {}", e))?;
+				depth.insert(recorder.drain_storage_proof().len() as u64);
+			}
+
+			measurement.depth = Some(depth);
+			measurement.value_size = Some(value_size);
+		}
+
+		if !self.params.skip_write {
+			let header = client.header(best_hash)?.ok_or("Header not found")?;
+			let original_root = *header.state_root();
+			let (trie, _) = self.create_trie_backend::<Block, Block::Header>(
+				original_root,
+				&storage,
+				shared_trie_cache.as_ref(),
+			);
+
+			for (prefix, measurement) in groups.iter_mut() {
+				// Writes are only measured in full batches, see `bench_write`.
+				let batch_size = self.params.batch_size.min(measurement.keys.len());
+				info!(
+					"Writing {} keys of {} in batches of {}",
+					measurement.keys.len(),
+					prefix,
+					batch_size
+				);
+				let mut record = BenchRecord::default();
+
+				for batch in measurement.keys.chunks_exact(batch_size) {
+					let mut changes = Vec::with_capacity(batch.len());
+					for (key, len) in batch {
+						let mut new_v = vec![0; *len];
+						loop {
+							rng.fill_bytes(&mut new_v[..]);
+							if check_new_value::<Block>(
+								db.clone(),
+								&trie,
+								&key.0,
+								&new_v,
+								self.state_version(),
+								state_col,
+								None,
+							) {
+								break
+							}
+						}
+						changes.push((key.0.clone(), new_v));
+					}
+
+					let (size, duration) = self
+						.measure_per_key_amortised_import_block_write_cost::<Block, Block::Header>(
+							original_root,
+							&storage,
+							shared_trie_cache.as_ref(),
+							db.clone(),
+							changes,
+							self.state_version(),
+							state_col,
+							None,
+						)?;
+					record.append(size, duration)?;
+				}
+				measurement.write = Some(record);
+			}
+		}
+
+		let mut prefixes = Vec::with_capacity(groups.len());
+		for (prefix, measurement) in groups {
+			let keys = measurement.keys.len();
+			let (read, value_size) = measurement.read.calculate_stats()?;
+			let write = measurement.write.map(|w| w.calculate_stats().map(|s| s.0)).transpose()?;
+			let has_enough_keys = keys >= self.params.breakdown_min_keys;
+
+			let read_weight = has_enough_keys
+				.then(|| self.params.weight_params.calc_weight(&read))
+				.transpose()?;
+			let write_weight = match (has_enough_keys, &write) {
+				(true, Some(write)) => Some(self.params.weight_params.calc_weight(write)?),
+				_ => None,
+			};
+
+			prefixes.push(PrefixResult {
+				ident: prefix.ident(),
+				prefix,
+				keys,
+				read,
+				write,
+				value_size,
+				depth_histogram: measurement.depth.unwrap_or_else(Histogram::linear),
+				value_size_histogram: measurement.value_size.unwrap_or_else(Histogram::exponential),
+				read_weight,
+				write_weight,
+			});
+		}
+		prefixes.sort_by(|a, b| b.keys.cmp(&a.keys).then_with(|| a.prefix.cmp(&b.prefix)));
+
+		Ok(Breakdown { level: self.params.breakdown_level, prefixes })
+	}
+
+	/// Creates a [`PrefixResolver`] from the metadata of the runtime at `hash`.
+	fn prefix_resolver<Block, BA, C>(
+		&self,
+		client: &Arc<C>,
+		hash: Block::Hash,
+	) -> Result<PrefixResolver>
+	where
+		Block: BlockT,
+		BA: ClientBackend<Block>,
+		C: StorageProvider<Block, BA>,
+	{
+		let code = client
+			.storage(hash, &StorageKey(well_known_keys::CODE.to_vec()))?
+			.ok_or("Runtime code not found in state")?;
+		let executor = WasmExecutor::<sp_io::SubstrateHostFunctions>::builder()
+			.with_allow_missing_host_functions(true)
+			.build();
+		let opaque_metadata = fetch_latest_metadata_from_code_blob(&executor, code.0.into())
+			.map_err(|_| <&str as Into<Error>>::into("Unable to fetch latest stable metadata"))?;
+		let metadata = subxt::Metadata::decode(&mut (*opaque_metadata).as_slice())?;
+
+		Ok(PrefixResolver::from_metadata(&metadata, self.params.breakdown_level))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn resolver(level: BreakdownLevel) -> PrefixResolver {
+		let pallet = twox_128(b"System");
+		let mut item = [0u8; 32];
+		item[..16].copy_from_slice(&pallet);
+		item[16..].copy_from_slice(&twox_128(b"Account"));
+
+		PrefixResolver {
+			level,
+			pallets: [(pallet, "System".to_string())].into_iter().collect(),
+			items: [(item, ("System".to_string(), "Account".to_string()))].into_iter().collect(),
+		}
+	}
+
+	#[test]
+	fn resolve_works() {
+		let mut key = twox_128(b"System").to_vec();
+		key.extend(twox_128(b"Account"));
+		key.extend([1u8; 48]);
+
+		let by_item = resolver(BreakdownLevel::Item);
+		let prefix = by_item.resolve(&key);
+		assert_eq!(prefix.to_string(), "System::Account");
+		assert_eq!(prefix.ident(), "SystemAccount");
+		assert_eq!(by_item.resolve(&[0u8; 32]), StoragePrefix::unknown());
+		assert_eq!(by_item.resolve(well_known_keys::CODE).to_string(), ":code");
+		assert_eq!(
+			by_item.resolve(b":child_storage:default:first").to_string(),
+			by_item.resolve(b":child_storage:default:second").to_string(),
+		);
+		assert_eq!(by_item.resolve(b":child_storage:default:first").to_string(), ":child_storage:");
+
+		let by_pallet = resolver(BreakdownLevel::Pallet);
+		assert_eq!(by_pallet.resolve(&key).to_string(), "System");
+		assert_eq!(by_pallet.resolve(&key[..8]), StoragePrefix::unknown());
+	}
+
+	#[test]
+	fn pallets_are_resolved_before_well_known_keys() {
+		// A pallet whose name hashes to a prefix that starts with a colon.
+		let pallet = *b":colon_pallet___";
+		let mut item = [0u8; 32];
+		item[..16].copy_from_slice(&pallet);
+		item[16..].copy_from_slice(&twox_128(b"Value"));
+		let resolver = |level| PrefixResolver {
+			level,
+			pallets: [(pallet, "Colon".to_string())].into_iter().collect(),
+			items: [(item, ("Colon".to_string(), "Value".to_string()))].into_iter().collect(),
+		};
+
+		let by_item = resolver(BreakdownLevel::Item);
+		assert_eq!(by_item.resolve(&item).to_string(), "Colon::Value");
+		assert_eq!(by_item.resolve(&pallet), StoragePrefix::unknown());
+		assert_eq!(by_item.resolve(well_known_keys::CODE).to_string(), ":code");
+		assert_eq!(resolver(BreakdownLevel::Pallet).resolve(&item).to_string(), "Colon");
+	}
+
+	#[test]
+	fn exponential_histogram_works() {
+		let mut hist = Histogram::exponential();
+		for v in [0, 1, 2, 3, 4, 7, 8, 1000] {
+			hist.insert(v);
+		}
+
+		let buckets: Vec<_> = hist.buckets().into_iter().map(|b| (b.from, b.to, b.count)).collect();
+		assert_eq!(
+			buckets,
+			vec![(0, 1, 1), (1, 2, 1), (2, 4, 2), (4, 8, 2), (8, 16, 1), (512, 1024, 1)]
+		);
+	}
+
+	#[test]
+	fn linear_histogram_works() {
+		let mut hist = Histogram::linear();
+		for v in [3, 5, 5, 5, 6] {
+			hist.insert(v);
+		}
+
+		let buckets: Vec<_> = hist.buckets().into_iter().map(|b| (b.from, b.to, b.count)).collect();
+		assert_eq!(buckets, vec![(3, 4, 1), (5, 6, 3), (6, 7, 1)]);
+	}
+}
@@ -33,7 +33,7 @@ use serde::Serialize;
 use sp_runtime::generic::BlockId;
 use std::{fmt::Debug, path::PathBuf, sync::Arc};
 
-use super::template::TemplateData;
+use super::{breakdown::BreakdownLevel, template::TemplateData};
 use crate::shared::{new_rng, HostInfoParams, WeightParams};
 
 /// The mode in which to run the storage benchmark.
@@ -159,6 +159,33 @@ pub struct StorageParams {
 	/// This is only used when `mode` is `validate-block`.
 	#[arg(long, default_value_t = 20)]
 	pub validate_block_rounds: u32,
+
+	/// Break the results down by storage prefix.
+	///
+	/// The key prefixes are resolved to pallet and storage item names with the runtime metadata.
+	/// Every prefix is benchmarked separately and trie-depth and value-size histograms are
+	/// collected. Only supported in `import-block` mode.
+	#[arg(long)]
+	pub breakdown: bool,
+
+	/// Whether the `--breakdown` groups keys by pallet or by storage item.
+	#[arg(long, value_enum, default_value_t = BreakdownLevel::Item)]
+	pub breakdown_level: BreakdownLevel,
+
+	/// Minimal number of keys that a prefix needs to get its own weight.
+	///
+	/// Prefixes with fewer keys are still reported, but their stats are not meaningful enough to
+	/// derive a weight from.
+	#[arg(long, default_value_t = 100)]
+	pub breakdown_min_keys: usize,
+
+	/// Add a `RuntimeDbWeight` constant for every prefix of the `--breakdown` to the weight file.
+	#[arg(long, requires = "breakdown")]
+	pub per_prefix_weights: bool,
+
+	/// Path to write the `--breakdown` results in JSON format to. Can be a file or directory.
+	#[arg(long, requires = "breakdown")]
+	pub json_breakdown_path: Option<PathBuf>,
 }
 
 impl StorageParams {
@@ -206,6 +233,21 @@ impl StorageCmd {
 			template.set_stats(Some(stats), None)?;
 		}
 
+		if self.params.breakdown {
+			self.bench_warmup(&client)?;
+			let breakdown = self.bench_breakdown(
+				client.clone(),
+				db.clone(),
+				storage.clone(),
+				shared_trie_cache.clone(),
+			)?;
+			if let Some(path) = &self.params.json_breakdown_path {
+				breakdown.save_json(&cfg, path)?;
+			}
+			breakdown.print_summary();
+			template.set_breakdown(breakdown);
+		}
+
 		if !self.params.skip_write {
 			self.bench_warmup(&client)?;
 			let record = self.bench_write(client, db, storage, shared_trie_cache)?;
@@ -15,6 +15,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+pub mod breakdown;
 pub mod cmd;
 pub mod read;
 pub mod template;
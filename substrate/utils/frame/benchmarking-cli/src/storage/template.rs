@@ -22,7 +22,10 @@ use log::info;
 use serde::Serialize;
 use std::{env, fs, path::PathBuf};
 
-use super::cmd::StorageParams;
+use super::{
+	breakdown::{Breakdown, PrefixResult},
+	cmd::StorageParams,
+};
 use crate::shared::{Stats, UnderscoreHelper};
 
 static VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -61,6 +64,10 @@ pub(crate) struct TemplateData {
 	/// Stats about a `write` benchmark. Contains *time* and *value size* stats.
 	/// The *value size* stats are currently not used in the template.
 	write: Option<(Stats, Stats)>,
+	/// Per-prefix results that get their own weight constant.
+	///
+	/// Only filled when `--per-prefix-weights` is passed.
+	prefixes: Vec<PrefixResult>,
 }
 
 impl TemplateData {
@@ -108,6 +115,16 @@ impl TemplateData {
 		Ok(())
 	}
 
+	/// Sets the per-prefix results. Prefixes without a read weight are skipped.
+	///
+	/// Prefixes without a write weight fall back to the global write weight in the template.
+	pub fn set_breakdown(&mut self, breakdown: Breakdown) {
+		if self.params.per_prefix_weights {
+			self.prefixes =
+				breakdown.prefixes.into_iter().filter(|p| p.read_weight.is_some()).collect();
+		}
+	}
+
 	/// Sets the block id that was used.
 	pub fn set_block_number(&mut self, block_number: String) {
 		self.block_number = block_number
@@ -232,7 +232,7 @@ impl StorageCmd {
 		Ok(record)
 	}
 
-	fn create_trie_backend<Block, H>(
+	pub(super) fn create_trie_backend<Block, H>(
 		&self,
 		original_root: Block::Hash,
 		storage: &Arc<dyn sp_state_machine::Storage<HashingFor<Block>>>,
@@ -253,7 +253,7 @@ impl StorageCmd {
 
 	/// Measures write benchmark
 	/// if `child_info` exist then it means this is a child tree key
-	fn measure_per_key_amortised_import_block_write_cost<Block, H>(
+	pub(super) fn measure_per_key_amortised_import_block_write_cost<Block, H>(
 		&self,
 		original_root: Block::Hash,
 		storage: &Arc<dyn sp_state_machine::Storage<HashingFor<Block>>>,
@@ -409,7 +409,7 @@ fn convert_tx<B: BlockT>(
 /// Checks if a new value causes any collision in tree updates
 /// returns true if there is no collision
 /// if `child_info` exist then it means this is a child tree key
-fn check_new_value<Block: BlockT>(
+pub(super) fn check_new_value<Block: BlockT>(
 	db: Arc<dyn sp_database::Database<DbHash>>,
 	trie: &DbState<HashingFor<Block>>,
 	key: &Vec<u8>,
@@ -0,0 +1,224 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{
+	decode::MetadataDecoder,
+	trace::{call_runtime, BlockTracer, HostFunctions},
+};
+use clap::{Parser, Subcommand, ValueEnum};
+use codec::{Decode, Encode};
+use frame_try_runtime::TryStateSelect;
+use remote_externalities::{Builder, Mode, OnlineConfig};
+use sc_cli::Result;
+use sc_executor::WasmExecutor;
+use serde::de::DeserializeOwned;
+use sp_core::{storage::well_known_keys, H256};
+use sp_runtime::{
+	generic::{self, SignedBlock},
+	traits::{BlakeTwo256, Block as BlockT, BlockNumber, Header as HeaderT},
+	OpaqueExtrinsic,
+};
+use sp_state_machine::{Backend, OverlayedChanges};
+use std::{borrow::Cow, fs, path::PathBuf};
+use substrate_rpc_client::{ws_client, ChainApi};
+
+/// The block type of a chain with block number `N`. Extrinsics are decoded with the metadata.
+type Block<N> = generic::Block<Header<N>, OpaqueExtrinsic>;
+type Header<N> = generic::Header<N, BlakeTwo256>;
+
+/// # Block trace
+///
+/// Re-executes a block of a live chain on top of the state of its parent.
+///
+/// Without `--trace` the block is executed through the `TryRuntime_execute_block` runtime API,
+/// which requires a runtime built with the `try-runtime` feature.
+///
+/// With `--trace` the block is executed extrinsic by extrinsic. For every extrinsic the decoded
+/// call, the emitted events and the storage diff are recorded and printed as JSON. Calls, events,
+/// keys and values are decoded with the metadata of the executing runtime:
+///
+/// ```sh
+/// frame-block-trace execute-block --uri wss://rpc.polkadot.io --at 0x... --trace \
+///     --trace-output trace.json
+/// ```
+///
+/// Passing `--runtime` executes the block with another runtime, so that the traces of two runtime
+/// versions can be diffed.
+///
+/// The try-runtime CLI lives outside of this repository, so this is a standalone binary. Without
+/// `--trace` it executes a block like `try-runtime execute-block`.
+#[derive(Parser, Debug)]
+#[clap(author, version, about, verbatim_doc_comment)]
+pub struct Command {
+	#[command(subcommand)]
+	sub: SubCommand,
+}
+
+#[derive(Debug, Subcommand)]
+enum SubCommand {
+	/// Execute a block on top of the state of its parent.
+	ExecuteBlock(ExecuteBlockCmd),
+}
+
+/// The type of the block number of a chain.
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum BlockNumberType {
+	U32,
+	U64,
+}
+
+#[derive(Debug, Parser)]
+struct ExecuteBlockCmd {
+	/// The RPC url of the node to fetch the block and state from.
+	#[arg(long, default_value = "ws://localhost:9944")]
+	uri: String,
+
+	/// Hash of the block to execute. Defaults to the latest finalized block.
+	#[arg(long, value_parser = parse_hash)]
+	at: Option<H256>,
+
+	/// The type of the block number of the chain. Relay and system parachains use `u32`.
+	#[arg(long, value_enum, default_value_t = BlockNumberType::U32)]
+	block_number: BlockNumberType,
+
+	/// Execute the block with this runtime instead of the on-chain one.
+	#[arg(long)]
+	runtime: Option<PathBuf>,
+
+	/// Record the decoded call, events and storage diff of every extrinsic.
+	#[arg(long)]
+	trace: bool,
+
+	/// Write the trace to this file instead of stdout.
+	#[arg(long, requires = "trace")]
+	trace_output: Option<PathBuf>,
+
+	/// Which `try_state` hooks to run after the block. Ignored with `--trace`.
+	#[arg(long, default_value = "all")]
+	try_state: TryStateSelect,
+
+	/// Whether to check the signatures of the extrinsics. Ignored with `--trace`.
+	#[arg(long, default_value_t = true, action = clap::ArgAction::Set)]
+	signature_check: bool,
+}
+
+impl Command {
+	pub fn run(self) -> Result<()> {
+		let runtime = tokio::runtime::Builder::new_multi_thread().enable_all().build()?;
+
+		match self.sub {
+			SubCommand::ExecuteBlock(cmd) => match cmd.block_number {
+				BlockNumberType::U32 => runtime.block_on(cmd.run::<u32>()),
+				BlockNumberType::U64 => runtime.block_on(cmd.run::<u64>()),
+			},
+		}
+	}
+}
+
+impl ExecuteBlockCmd {
+	async fn run<N>(self) -> Result<()>
+	where
+		N: BlockNumber,
+		Header<N>: DeserializeOwned,
+		Block<N>: DeserializeOwned,
+	{
+		let rpc = ws_client(&self.uri).await?;
+		let hash = match self.at {
+			Some(hash) => hash,
+			None => ChainApi::<(), H256, Header<N>, ()>::finalized_head(&rpc)
+				.await
+				.map_err(|e| format!("Failed to fetch the finalized head: {e}"))?,
+		};
+		let block = ChainApi::<(), H256, Header<N>, SignedBlock<Block<N>>>::block(&rpc, Some(hash))
+			.await
+			.map_err(|e| format!("Failed to fetch block {hash:?}: {e}"))?
+			.ok_or_else(|| format!("Block {hash:?} not found"))?
+			.block;
+		let parent = *block.header().parent_hash();
+		log::info!("Executing block #{} ({hash:?})", block.header().number());
+
+		let mut ext = Builder::<Block<N>>::new()
+			.mode(Mode::Online(OnlineConfig {
+				transport: self.uri.clone().into(),
+				at: Some(parent),
+				..Default::default()
+			}))
+			.build()
+			.await?;
+		if let Some(path) = &self.runtime {
+			log::info!("Overriding the runtime with {}", path.display());
+			ext.insert(well_known_keys::CODE.to_vec(), fs::read(path)?);
+		}
+		let backend = ext.as_backend();
+
+		let executor = WasmExecutor::<HostFunctions>::builder()
+			.with_allow_missing_host_functions(true)
+			.build();
+		let version = call_runtime(
+			&backend,
+			&mut OverlayedChanges::default(),
+			&executor,
+			"Core_version",
+			&[],
+		)?;
+		let version = sp_version::RuntimeVersion::decode(&mut &version[..])?;
+		let version = format!("{}-{}", version.spec_name, version.spec_version);
+
+		if !self.trace {
+			let weight = call_runtime(
+				&backend,
+				&mut OverlayedChanges::default(),
+				&executor,
+				"TryRuntime_execute_block",
+				&(block, true, self.signature_check, self.try_state).encode(),
+			)?;
+			let weight = sp_weights::Weight::decode(&mut &weight[..])?;
+			log::info!("Block executed with {version}, consumed weight: {weight}");
+			return Ok(())
+		}
+
+		let code = backend
+			.storage(well_known_keys::CODE)
+			.map_err(|e| format!("Failed to read the runtime code: {e}"))?
+			.ok_or("Runtime code not found in state")?;
+		let metadata =
+			sc_runtime_utilities::fetch_latest_metadata_from_code_blob(&executor, Cow::Owned(code))
+				.map_err(|e| format!("Failed to fetch the metadata: {e}"))?;
+		let metadata = subxt::Metadata::decode(&mut (*metadata).as_slice())?;
+		let decoder = MetadataDecoder::new(metadata);
+
+		let trace = BlockTracer::new(&backend, &executor, &decoder).trace(block, version)?;
+		let json = serde_json::to_string_pretty(&trace)
+			.map_err(|e| format!("Failed to serialize the trace: {e}"))?;
+		match &self.trace_output {
+			Some(path) => {
+				fs::write(path, json)?;
+				log::info!("Trace written to {}", path.display());
+			},
+			None => println!("{json}"),
+		}
+
+		Ok(())
+	}
+}
+
+fn parse_hash(s: &str) -> std::result::Result<H256, String> {
+	let bytes = array_bytes::hex2bytes(s).map_err(|e| format!("Invalid hex: {e:?}"))?;
+	(bytes.len() == 32)
+		.then(|| H256::from_slice(&bytes))
+		.ok_or_else(|| "Block hash must be 32 bytes".into())
+}
@@ -0,0 +1,211 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Decoding of calls, events and storage with the runtime metadata.
+
+use codec::{Compact, Decode};
+use serde::Serialize;
+use sp_core::hashing::twox_128;
+use std::collections::HashMap;
+use subxt::{
+	ext::scale_value::{self, Composite, Value, ValueDef},
+	metadata::types::{StorageEntryType, StorageHasher},
+	Metadata,
+};
+
+/// Bit that marks a signed extrinsic in the version byte.
+const SIGNED_EXTRINSIC_BIT: u8 = 0b1000_0000;
+/// Bits that mark a general extrinsic in the version byte.
+const GENERAL_EXTRINSIC_BITS: u8 = 0b0100_0000;
+
+/// A call decoded with the metadata.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DecodedCall {
+	/// Name of the pallet.
+	pub pallet: String,
+	/// Name of the call.
+	pub call: String,
+	/// The arguments of the call.
+	pub args: String,
+}
+
+/// A storage key, and optionally its value, decoded with the metadata.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DecodedStorage {
+	/// Name of the pallet. `None` if the key does not belong to any pallet.
+	pub pallet: Option<String>,
+	/// Name of the storage item.
+	pub item: Option<String>,
+	/// The hex encoded raw key.
+	pub key: String,
+	/// The decoded map keys. Keys behind opaque hashers are left out.
+	pub map_keys: Vec<String>,
+}
+
+/// Decodes calls, events and storage with the metadata of a runtime.
+pub struct MetadataDecoder {
+	metadata: Metadata,
+	/// Maps `twox128(pallet) ++ twox128(item)` to the pallet and item name.
+	items: HashMap<[u8; 32], (String, String)>,
+}
+
+impl MetadataDecoder {
+	/// Creates a new decoder.
+	pub fn new(metadata: Metadata) -> Self {
+		let mut items = HashMap::new();
+		for pallet in metadata.pallets() {
+			let Some(storage) = pallet.storage() else { continue };
+			for entry in storage.entries() {
+				let mut prefix = [0u8; 32];
+				prefix[..16].copy_from_slice(&twox_128(storage.prefix().as_bytes()));
+				prefix[16..].copy_from_slice(&twox_128(entry.name().as_bytes()));
+				items.insert(prefix, (storage.prefix().to_string(), entry.name().to_string()));
+			}
+		}
+
+		Self { metadata, items }
+	}
+
+	/// Decodes the call of an encoded extrinsic.
+	///
+	/// Returns `None` if the extrinsic can not be decoded with the metadata.
+	pub fn decode_extrinsic(&self, mut xt: &[u8]) -> Option<DecodedCall> {
+		let _len = Compact::<u32>::decode(&mut xt).ok()?;
+		let version = u8::decode(&mut xt).ok()?;
+		let types = self.metadata.types();
+		let extrinsic = self.metadata.extrinsic();
+
+		let extension_version = if version & SIGNED_EXTRINSIC_BIT != 0 {
+			scale_value::scale::decode_as_type(&mut xt, extrinsic.address_ty(), types).ok()?;
+			scale_value::scale::decode_as_type(&mut xt, extrinsic.signature_ty(), types).ok()?;
+			Some(0)
+		} else if version & GENERAL_EXTRINSIC_BITS != 0 {
+			Some(u8::decode(&mut xt).ok()?)
+		} else {
+			None
+		};
+		if let Some(extension_version) = extension_version {
+			for extension in extrinsic.transaction_extensions_by_version(extension_version)? {
+				scale_value::scale::decode_as_type(&mut xt, extension.extra_ty(), types).ok()?;
+			}
+		}
+
+		let call_ty = self.metadata.outer_enums().call_enum_ty();
+		let call = scale_value::scale::decode_as_type(&mut xt, call_ty, types).ok()?;
+		let (pallet, inner) = as_variant(&call)?;
+		let (call, args) = inner.values().next().and_then(as_variant)?;
+
+		Some(DecodedCall { pallet: pallet.into(), call: call.into(), args: args.to_string() })
+	}
+
+	/// Decodes the `System::Events` storage value into a list of events.
+	///
+	/// Every event is rendered with its phase and topics, like it is stored.
+	pub fn decode_events(&self, value: &[u8]) -> Option<Vec<String>> {
+		let ty = self.value_type("System", "Events")?;
+		let events =
+			scale_value::scale::decode_as_type(&mut &*value, ty, self.metadata.types()).ok()?;
+
+		match events.value {
+			ValueDef::Composite(composite) =>
+				Some(composite.into_values().map(|e| e.to_string()).collect()),
+			_ => None,
+		}
+	}
+
+	/// Decodes a storage key.
+	pub fn decode_key(&self, key: &[u8]) -> DecodedStorage {
+		let mut decoded = DecodedStorage {
+			pallet: None,
+			item: None,
+			key: array_bytes::bytes2hex("0x", key),
+			map_keys: Vec::new(),
+		};
+		let Some((pallet, item)) = key.get(..32).and_then(|prefix| self.items.get(prefix)) else {
+			return decoded
+		};
+		decoded.pallet = Some(pallet.clone());
+		decoded.item = Some(item.clone());
+
+		let Some(StorageEntryType::Map { hashers, key_ty, .. }) =
+			self.entry_type(pallet, item).cloned()
+		else {
+			return decoded
+		};
+
+		// Maps with multiple hashers use a tuple as key type.
+		let key_types = if hashers.len() == 1 {
+			vec![key_ty]
+		} else {
+			match self.metadata.types().resolve(key_ty).map(|ty| &ty.type_def) {
+				Some(scale_info::TypeDef::Tuple(tuple)) =>
+					tuple.fields.iter().map(|f| f.id).collect(),
+				_ => return decoded,
+			}
+		};
+
+		let mut cursor = &key[32..];
+		for (hasher, ty) in hashers.iter().zip(key_types) {
+			let (hash_len, concat) = match hasher {
+				StorageHasher::Blake2_128Concat => (16, true),
+				StorageHasher::Twox64Concat => (8, true),
+				StorageHasher::Identity => (0, true),
+				StorageHasher::Blake2_128 | StorageHasher::Twox128 => (16, false),
+				StorageHasher::Blake2_256 | StorageHasher::Twox256 => (32, false),
+			};
+			if !concat || cursor.len() < hash_len {
+				break
+			}
+			cursor = &cursor[hash_len..];
+			match scale_value::scale::decode_as_type(&mut cursor, ty, self.metadata.types()) {
+				Ok(value) => decoded.map_keys.push(value.to_string()),
+				Err(_) => break,
+			}
+		}
+
+		decoded
+	}
+
+	/// Decodes a storage value. Falls back to hex if the key is unknown.
+	pub fn decode_value(&self, key: &[u8], value: &[u8]) -> String {
+		key.get(..32)
+			.and_then(|prefix| self.items.get(prefix))
+			.and_then(|(pallet, item)| self.value_type(pallet, item))
+			.and_then(|ty| {
+				scale_value::scale::decode_as_type(&mut &*value, ty, self.metadata.types()).ok()
+			})
+			.map(|value| value.to_string())
+			.unwrap_or_else(|| array_bytes::bytes2hex("0x", value))
+	}
+
+	fn entry_type(&self, pallet: &str, item: &str) -> Option<&StorageEntryType> {
+		let storage = self.metadata.pallet_by_name(pallet)?.storage()?;
+		storage.entry_by_name(item).map(|entry| entry.entry_type())
+	}
+
+	fn value_type(&self, pallet: &str, item: &str) -> Option<u32> {
+		self.entry_type(pallet, item).map(|ty| ty.value_ty())
+	}
+}
+
+/// Returns the name and fields of a variant value.
+fn as_variant(value: &Value<u32>) -> Option<(&str, &Composite<u32>)> {
+	match &value.value {
+		ValueDef::Variant(variant) => Some((variant.name.as_str(), &variant.values)),
+		_ => None,
+	}
+}
@@ -0,0 +1,156 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Decodes and traces blocks of the Substrate test runtime.
+
+use crate::{
+	decode::MetadataDecoder,
+	trace::{BlockTracer, HostFunctions},
+};
+use codec::{Decode, Encode};
+use sc_executor::WasmExecutor;
+use sp_core::{
+	hashing::{blake2_128, twox_128},
+	storage::StateVersion,
+};
+use sp_keyring::Sr25519Keyring;
+use sp_runtime::traits::{Block as BlockT, Header as HeaderT};
+use sp_state_machine::InMemoryBackend;
+use std::borrow::Cow;
+use substrate_test_runtime::{
+	genesismap::GenesisStorageBuilder, Block, ExtrinsicBuilder, Header, Transfer,
+};
+
+fn executor() -> WasmExecutor<HostFunctions> {
+	WasmExecutor::<HostFunctions>::builder()
+		.with_allow_missing_host_functions(true)
+		.build()
+}
+
+fn decoder() -> MetadataDecoder {
+	let code = substrate_test_runtime::wasm_binary_unwrap();
+	let metadata = sc_runtime_utilities::fetch_latest_metadata_from_code_blob(
+		&executor(),
+		Cow::Borrowed(code),
+	)
+	.unwrap();
+	MetadataDecoder::new(subxt::Metadata::decode(&mut (*metadata).as_slice()).unwrap())
+}
+
+fn genesis() -> InMemoryBackend<sp_runtime::traits::BlakeTwo256> {
+	let storage = GenesisStorageBuilder::new(
+		vec![Sr25519Keyring::Alice.public().into()],
+		vec![Sr25519Keyring::Alice.into(), Sr25519Keyring::Bob.into()],
+		1000 * substrate_test_runtime::currency::DOLLARS,
+	)
+	.build();
+	(storage, StateVersion::V1).into()
+}
+
+fn transfer() -> substrate_test_runtime::Extrinsic {
+	ExtrinsicBuilder::new_transfer(Transfer {
+		from: Sr25519Keyring::Alice.pair(),
+		to: Sr25519Keyring::Bob.into(),
+		amount: 42,
+		nonce: 0,
+	})
+	.build()
+}
+
+fn account_key(who: Sr25519Keyring) -> Vec<u8> {
+	let who: [u8; 32] = who.into();
+	[&twox_128(b"System")[..], &twox_128(b"Account"), &blake2_128(&who), &who].concat()
+}
+
+#[test]
+fn decode_extrinsic_works() {
+	let decoder = decoder();
+
+	let call = decoder.decode_extrinsic(&transfer().encode()).unwrap();
+	assert_eq!(call.pallet, "Balances");
+	assert_eq!(call.call, "transfer_allow_death");
+	assert!(call.args.contains("42"));
+
+	let unsigned = ExtrinsicBuilder::new_read(7).build();
+	let call = decoder.decode_extrinsic(&unsigned.encode()).unwrap();
+	assert_eq!((call.pallet.as_str(), call.call.as_str()), ("SubstrateTest", "read"));
+
+	assert_eq!(decoder.decode_extrinsic(&[4, 0xff]), None);
+}
+
+#[test]
+fn decode_key_works() {
+	let decoder = decoder();
+
+	let decoded = decoder.decode_key(&account_key(Sr25519Keyring::Alice));
+	assert_eq!(decoded.pallet.as_deref(), Some("System"));
+	assert_eq!(decoded.item.as_deref(), Some("Account"));
+	assert_eq!(decoded.map_keys.len(), 1);
+
+	let number = [twox_128(b"System"), twox_128(b"Number")].concat();
+	let decoded = decoder.decode_key(&number);
+	assert_eq!(decoded.item.as_deref(), Some("Number"));
+	assert!(decoded.map_keys.is_empty());
+	assert_eq!(decoder.decode_value(&number, &5u64.encode()), "5");
+
+	let decoded = decoder.decode_key(b":code");
+	assert_eq!((decoded.pallet, decoded.item), (None, None));
+	assert_eq!(decoded.key, "0x3a636f6465");
+	assert_eq!(decoder.decode_value(b":code", &[1, 2]), "0x0102");
+}
+
+#[test]
+fn trace_block_works() {
+	let backend = genesis();
+	let executor = executor();
+	let decoder = decoder();
+
+	let header = Header::new(
+		1,
+		Default::default(),
+		Default::default(),
+		Default::default(),
+		Default::default(),
+	);
+	let block = Block::new(header, vec![transfer()]);
+	let hash = block.hash();
+
+	let trace = BlockTracer::new(&backend, &executor, &decoder)
+		.trace(block, "test-2".into())
+		.unwrap();
+	assert_eq!(trace.number, "1");
+	assert_eq!(trace.hash, format!("{hash:?}"));
+
+	let number = trace
+		.initialize
+		.storage
+		.iter()
+		.find(|change| change.item.as_deref() == Some("Number"))
+		.unwrap();
+	assert_eq!(number.new.as_deref(), Some("1"));
+
+	assert_eq!(trace.extrinsics.len(), 1);
+	let xt = &trace.extrinsics[0];
+	assert_eq!(xt.call.as_ref().map(|c| c.call.as_str()), Some("transfer_allow_death"));
+	assert!(xt.result.starts_with("Ok(Ok("), "{}", xt.result);
+	assert!(xt.step.events.iter().any(|e| e.contains("Transfer")));
+	assert!(xt.step.storage.iter().all(|change| change.item.as_deref() != Some("Events")));
+
+	let bob = array_bytes::bytes2hex("0x", account_key(Sr25519Keyring::Bob));
+	let bob = xt.step.storage.iter().find(|change| change.key == bob).unwrap();
+	assert_ne!(bob.old, bob.new);
+}
@@ -0,0 +1,246 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Step-wise execution of a block that records what every extrinsic changed.
+
+use crate::decode::{DecodedCall, MetadataDecoder};
+use codec::{Decode, Encode};
+use sc_executor::WasmExecutor;
+use serde::Serialize;
+use sp_core::{hashing::twox_128, traits::CallContext};
+use sp_runtime::{
+	traits::{Block as BlockT, Header as HeaderT},
+	ApplyExtrinsicResult,
+};
+use sp_state_machine::{
+	backend::BackendRuntimeCode, Backend, InMemoryBackend, OverlayedChanges, StateMachine,
+};
+use std::collections::BTreeMap;
+
+/// Host functions the traced runtime may use.
+pub type HostFunctions = (
+	sp_io::SubstrateHostFunctions,
+	cumulus_primitives_proof_size_hostfunction::storage_proof_size::HostFunctions,
+);
+
+type Hashing = sp_runtime::traits::BlakeTwo256;
+
+/// The trace of a whole block.
+#[derive(Debug, Clone, Serialize)]
+pub struct BlockTrace {
+	/// Number of the block.
+	pub number: String,
+	/// Hash of the block.
+	pub hash: String,
+	/// `spec_name` and `spec_version` of the runtime that executed the block.
+	pub runtime: String,
+	/// What `Core_initialize_block` did. This includes the `on_initialize` hooks.
+	pub initialize: StepTrace,
+	/// What every extrinsic did, in block order.
+	pub extrinsics: Vec<ExtrinsicTrace>,
+	/// What `BlockBuilder_finalize_block` did. This includes the `on_finalize` hooks.
+	pub finalize: StepTrace,
+}
+
+/// The trace of a single extrinsic.
+#[derive(Debug, Clone, Serialize)]
+pub struct ExtrinsicTrace {
+	/// Index of the extrinsic in the block.
+	pub index: u32,
+	/// The decoded call. `None` if it could not be decoded with the metadata.
+	pub call: Option<DecodedCall>,
+	/// The hex encoded extrinsic.
+	pub raw: String,
+	/// The result of `BlockBuilder_apply_extrinsic`.
+	pub result: String,
+	/// Events and storage changes of the extrinsic.
+	#[serde(flatten)]
+	pub step: StepTrace,
+}
+
+/// Events and storage changes of one step of the block execution.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct StepTrace {
+	/// The events that were deposited in this step.
+	pub events: Vec<String>,
+	/// The storage changes of this step, sorted by key.
+	///
+	/// `System::Events` is left out, it is reported through [`Self::events`].
+	pub storage: Vec<StorageChange>,
+}
+
+/// A single changed storage key.
+#[derive(Debug, Clone, Serialize)]
+pub struct StorageChange {
+	/// Name of the pallet, if the key belongs to a storage item in the metadata.
+	pub pallet: Option<String>,
+	/// Name of the storage item.
+	pub item: Option<String>,
+	/// The hex encoded raw key.
+	pub key: String,
+	/// The decoded map keys.
+	pub map_keys: Vec<String>,
+	/// The decoded value before the step. `None` if the key did not exist.
+	pub old: Option<String>,
+	/// The decoded value after the step. `None` if the key was removed.
+	pub new: Option<String>,
+}
+
+/// Executes a block extrinsic by extrinsic on top of the state of its parent.
+pub struct BlockTracer<'a> {
+	backend: &'a InMemoryBackend<Hashing>,
+	executor: &'a WasmExecutor<HostFunctions>,
+	decoder: &'a MetadataDecoder,
+	overlay: OverlayedChanges<Hashing>,
+	/// The value of every changed key after the last step.
+	seen: BTreeMap<Vec<u8>, Option<Vec<u8>>>,
+	/// Number of events after the last step.
+	events: usize,
+}
+
+impl<'a> BlockTracer<'a> {
+	/// Creates a tracer on top of `backend`, which must hold the state of the parent block.
+	pub fn new(
+		backend: &'a InMemoryBackend<Hashing>,
+		executor: &'a WasmExecutor<HostFunctions>,
+		decoder: &'a MetadataDecoder,
+	) -> Self {
+		Self {
+			backend,
+			executor,
+			decoder,
+			overlay: Default::default(),
+			seen: Default::default(),
+			events: 0,
+		}
+	}
+
+	/// Executes `block` and returns its trace.
+	pub fn trace<Block: BlockT>(
+		mut self,
+		block: Block,
+		runtime: String,
+	) -> sc_cli::Result<BlockTrace> {
+		let hash = block.hash();
+		let (mut header, extrinsics) = block.deconstruct();
+		// The seal is removed before import, the runtime never sees it.
+		header.digest_mut().logs.retain(|item| item.as_seal().is_none());
+
+		self.call("Core_initialize_block", &header.encode())?;
+		let initialize = self.step();
+
+		let mut traces = Vec::with_capacity(extrinsics.len());
+		for (index, xt) in extrinsics.into_iter().enumerate() {
+			let encoded = xt.encode();
+			let result = self.call("BlockBuilder_apply_extrinsic", &encoded)?;
+			let result = ApplyExtrinsicResult::decode(&mut &result[..])
+				.map(|r| format!("{r:?}"))
+				.unwrap_or_else(|_| array_bytes::bytes2hex("0x", &result));
+
+			traces.push(ExtrinsicTrace {
+				index: index as u32,
+				call: self.decoder.decode_extrinsic(&encoded),
+				raw: array_bytes::bytes2hex("0x", &encoded),
+				result,
+				step: self.step(),
+			});
+		}
+
+		self.call("BlockBuilder_finalize_block", &[])?;
+		let finalize = self.step();
+
+		Ok(BlockTrace {
+			number: header.number().to_string(),
+			hash: format!("{hash:?}"),
+			runtime,
+			initialize,
+			extrinsics: traces,
+			finalize,
+		})
+	}
+
+	/// Calls `method` of the runtime and keeps its changes in the overlay.
+	fn call(&mut self, method: &str, data: &[u8]) -> sc_cli::Result<Vec<u8>> {
+		call_runtime(self.backend, &mut self.overlay, self.executor, method, data)
+	}
+
+	/// Collects the events and storage changes since the last step.
+	fn step(&mut self) -> StepTrace {
+		let events_key = [twox_128(b"System"), twox_128(b"Events")].concat();
+		let mut trace = StepTrace::default();
+
+		for (key, value) in self.overlay.changes_mut() {
+			let new = value.value().cloned();
+
+			if *key == events_key {
+				let events = new.as_deref().and_then(|v| self.decoder.decode_events(v));
+				let events = events.unwrap_or_default();
+				// `System::Events` is reset when the block is initialized.
+				let known = self.events.min(events.len());
+				trace.events = events[known..].to_vec();
+				self.events = events.len();
+				continue
+			}
+
+			let old = match self.seen.get(key) {
+				Some(old) => old.clone(),
+				None => self.backend.storage(key).ok().flatten(),
+			};
+			if old == new {
+				continue
+			}
+
+			let decoded = self.decoder.decode_key(key);
+			trace.storage.push(StorageChange {
+				pallet: decoded.pallet,
+				item: decoded.item,
+				key: decoded.key,
+				map_keys: decoded.map_keys,
+				old: old.as_deref().map(|v| self.decoder.decode_value(key, v)),
+				new: new.as_deref().map(|v| self.decoder.decode_value(key, v)),
+			});
+			self.seen.insert(key.clone(), new);
+		}
+
+		trace
+	}
+}
+
+/// Calls `method` of the runtime in `backend` and keeps its changes in `overlay`.
+pub fn call_runtime(
+	backend: &InMemoryBackend<Hashing>,
+	overlay: &mut OverlayedChanges<Hashing>,
+	executor: &WasmExecutor<HostFunctions>,
+	method: &str,
+	data: &[u8],
+) -> sc_cli::Result<Vec<u8>> {
+	let runtime_code = BackendRuntimeCode::new(backend);
+	let runtime_code = runtime_code.runtime_code()?;
+
+	StateMachine::new(
+		backend,
+		overlay,
+		executor,
+		method,
+		data,
+		&mut Default::default(),
+		&runtime_code,
+		CallContext::Onchain,
+	)
+	.execute()
+	.map_err(|e| format!("Failed to call `{method}`: {e}").into())
+}
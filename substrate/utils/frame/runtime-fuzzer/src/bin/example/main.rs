@@ -0,0 +1,191 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Running
+//! Running this fuzzer can be done with `cargo hfuzz run example-runtime-fuzzer`. `honggfuzz` CLI
+//! options can be used by setting `HFUZZ_RUN_ARGS`, such as `-n 4` to use 4 threads.
+//!
+//! # Minimising a failure
+//! Once a failure is found, it can be minimised with
+//! `cargo run --features=try-runtime -- hfuzz_workspace/example-runtime-fuzzer/*.fuzz`.
+//! The printed sequence of steps can be replayed with
+//! `cargo run --features=try-runtime -- --replay <hex>`.
+
+// We do not declare all features used by `construct_runtime`
+#[allow(unexpected_cfgs)]
+mod runtime;
+
+use frame_runtime_fuzzer::{invariants, FuzzRuntime, Harness};
+use runtime::*;
+use sp_runtime::{
+	testing::UintAuthorityId, traits::Header as _, ApplyExtrinsicResult, BuildStorage,
+	TryRuntimeError,
+};
+
+const ACCOUNTS: u64 = 8;
+const INITIAL_BALANCE: u64 = 1_000_000;
+
+struct ExampleRuntime;
+
+impl FuzzRuntime for ExampleRuntime {
+	type RuntimeCall = RuntimeCall;
+	type AccountId = u64;
+
+	fn accounts() -> Vec<u64> {
+		(1..=ACCOUNTS).collect()
+	}
+
+	fn genesis() -> sp_io::TestExternalities {
+		RuntimeGenesisConfig {
+			balances: pallet_balances::GenesisConfig {
+				balances: Self::accounts().into_iter().map(|a| (a, INITIAL_BALANCE)).collect(),
+				..Default::default()
+			},
+			..Default::default()
+		}
+		.build_storage()
+		.unwrap()
+		.into()
+	}
+
+	fn initialize_block(n: u32) {
+		let header = Header::new(
+			n.into(),
+			Default::default(),
+			Default::default(),
+			System::parent_hash(),
+			Default::default(),
+		);
+		Executive::initialize_block(&header);
+	}
+
+	fn finalize_block() {
+		Executive::finalize_block();
+	}
+
+	fn apply_extrinsic(call: RuntimeCall, who: u64) -> ApplyExtrinsicResult {
+		let xt = UncheckedExtrinsic::new_signed(
+			call,
+			who,
+			UintAuthorityId(who),
+			frame_system::CheckNonZeroSender::new(),
+		);
+		Executive::apply_extrinsic(xt)
+	}
+
+	fn is_allowed(call: &RuntimeCall) -> bool {
+		// `System` calls like `set_storage` would only find false positives.
+		!matches!(call, RuntimeCall::System(_))
+	}
+
+	#[cfg(feature = "try-runtime")]
+	fn try_state(n: u32) -> Result<(), TryRuntimeError> {
+		use frame_support::traits::{TryState, TryStateSelect};
+		AllPalletsWithSystem::try_state(n.into(), TryStateSelect::All)
+	}
+
+	fn invariants() -> Result<(), TryRuntimeError> {
+		invariants::total_issuance_matches_balances::<Runtime, ()>()
+	}
+}
+
+fn main() {
+	let harness = Harness::<ExampleRuntime>::new();
+
+	#[cfg(fuzzing)]
+	{
+		loop {
+			honggfuzz::fuzz!(|data: &[u8]| {
+				harness.run(data);
+			})
+		}
+	}
+	#[cfg(not(fuzzing))]
+	{
+		use std::{env, fs};
+		let args: Vec<_> = env::args().collect();
+		if args.get(1).map(String::as_str) == Some("--replay") {
+			let steps = match Harness::<ExampleRuntime>::decode_steps(&args[2]) {
+				Ok(steps) => steps,
+				Err(e) => return println!("{e}"),
+			};
+			for (index, step) in steps.iter().enumerate() {
+				println!("  {index}: {step:?}");
+			}
+			match harness.execute(&steps) {
+				Ok(()) => println!("Replay succeeded"),
+				Err(failure) => println!("Replay failed: {failure}"),
+			}
+			return
+		}
+
+		for path in &args[1..] {
+			println!("Now doing file {:?}", path);
+			let data = fs::read(path).unwrap();
+			match harness.minimise(&data) {
+				Some((steps, failure)) => {
+					println!("  {failure}");
+					println!("  minimal steps: {:?}", steps);
+					println!(
+						"  replay: {}",
+						array_bytes::bytes2hex("0x", codec::Encode::encode(&steps))
+					);
+				},
+				None => println!("  passed"),
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use frame_runtime_fuzzer::Step;
+
+	#[test]
+	fn generated_steps_execute() {
+		let harness = Harness::<ExampleRuntime>::new();
+
+		let mut calls = 0;
+		for seed in 0..64u32 {
+			let data: Vec<u8> = (0..512u32)
+				.map(|i| (seed.wrapping_mul(31).wrapping_add(i * 7) % 256) as u8)
+				.collect();
+			let steps = harness.generate(&mut arbitrary::Unstructured::new(&data)).unwrap();
+			calls += steps.iter().filter(|s| matches!(s, Step::Call { .. })).count();
+
+			assert_eq!(harness.execute(&steps), Ok(()));
+		}
+		assert!(calls > 0);
+	}
+
+	#[test]
+	fn transfers_execute() {
+		let harness = Harness::<ExampleRuntime>::new();
+		let transfer = |dest, value| {
+			RuntimeCall::Balances(pallet_balances::Call::transfer_allow_death { dest, value })
+		};
+		let steps = vec![
+			Step::Call { origin: 0, call: transfer(2, 1_000) },
+			Step::NextBlock,
+			Step::Call { origin: 1, call: transfer(1, INITIAL_BALANCE * 2) },
+			Step::Call { origin: 7, call: transfer(9, 1) },
+		];
+
+		assert_eq!(harness.execute(&steps), Ok(()));
+	}
+}
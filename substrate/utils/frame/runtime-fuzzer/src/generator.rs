@@ -0,0 +1,253 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Generates random SCALE encoded values from `scale-info` type metadata.
+
+use arbitrary::{Error, Result, Unstructured};
+use codec::{Compact, Encode};
+use scale_info::{
+	form::PortableForm, meta_type, Field, PortableRegistry, Registry, TypeDef, TypeDefPrimitive,
+	TypeInfo,
+};
+
+/// Maximum nesting depth of generated values.
+///
+/// Beyond half of this depth, variants without fields are preferred to keep recursive types
+/// finite.
+pub const MAX_DEPTH: u32 = 32;
+
+/// Maximum number of elements in generated sequences and strings.
+const MAX_SEQUENCE_LEN: u32 = 8;
+
+/// Generates random values of a root type and all types it references.
+pub struct ValueGenerator {
+	registry: PortableRegistry,
+	root: u32,
+	account: u32,
+	accounts: Vec<Vec<u8>>,
+}
+
+impl ValueGenerator {
+	/// Creates a generator for values of type `T`.
+	///
+	/// Values of type `AccountId` are picked from `accounts` most of the time, so that generated
+	/// calls refer to accounts that exist.
+	pub fn new<T, AccountId>(accounts: &[AccountId]) -> Self
+	where
+		T: TypeInfo + 'static,
+		AccountId: TypeInfo + Encode + 'static,
+	{
+		let mut registry = Registry::new();
+		let root = registry.register_type(&meta_type::<T>()).id;
+		let account = registry.register_type(&meta_type::<AccountId>()).id;
+
+		Self {
+			registry: registry.into(),
+			root,
+			account,
+			accounts: accounts.iter().map(Encode::encode).collect(),
+		}
+	}
+
+	/// Returns the SCALE encoding of a random value of the root type.
+	pub fn generate(&self, u: &mut Unstructured) -> Result<Vec<u8>> {
+		let mut out = Vec::new();
+		self.encode_type(self.root, 0, u, &mut out)?;
+		Ok(out)
+	}
+
+	fn encode_type(
+		&self,
+		id: u32,
+		depth: u32,
+		u: &mut Unstructured,
+		out: &mut Vec<u8>,
+	) -> Result<()> {
+		if depth > MAX_DEPTH {
+			return Err(Error::IncorrectFormat)
+		}
+		if id == self.account && !self.accounts.is_empty() && u.ratio(7, 8)? {
+			out.extend_from_slice(u.choose(&self.accounts)?);
+			return Ok(())
+		}
+
+		let ty = self.registry.resolve(id).ok_or(Error::IncorrectFormat)?;
+		match &ty.type_def {
+			TypeDef::Composite(composite) => self.encode_fields(&composite.fields, depth, u, out),
+			TypeDef::Variant(variant) => {
+				let variant = if depth > MAX_DEPTH / 2 {
+					variant
+						.variants
+						.iter()
+						.min_by_key(|v| v.fields.len())
+						.ok_or(Error::EmptyChoose)?
+				} else {
+					u.choose(&variant.variants)?
+				};
+				out.push(variant.index);
+				self.encode_fields(&variant.fields, depth, u, out)
+			},
+			TypeDef::Sequence(sequence) => {
+				let len =
+					if depth > MAX_DEPTH / 2 { 0 } else { u.int_in_range(0..=MAX_SEQUENCE_LEN)? };
+				Compact(len).encode_to(out);
+				(0..len)
+					.try_for_each(|_| self.encode_type(sequence.type_param.id, depth + 1, u, out))
+			},
+			TypeDef::Array(array) => (0..array.len)
+				.try_for_each(|_| self.encode_type(array.type_param.id, depth + 1, u, out)),
+			TypeDef::Tuple(tuple) =>
+				tuple.fields.iter().try_for_each(|f| self.encode_type(f.id, depth + 1, u, out)),
+			TypeDef::Primitive(primitive) => self.encode_primitive(primitive, u, out),
+			TypeDef::Compact(compact) => {
+				let bits = self.compact_bits(compact.type_param.id)?;
+				Compact(arbitrary_uint(bits, u)?).encode_to(out);
+				Ok(())
+			},
+			TypeDef::BitSequence(_) => {
+				Compact(0u32).encode_to(out);
+				Ok(())
+			},
+		}
+	}
+
+	fn encode_fields(
+		&self,
+		fields: &[Field<PortableForm>],
+		depth: u32,
+		u: &mut Unstructured,
+		out: &mut Vec<u8>,
+	) -> Result<()> {
+		fields.iter().try_for_each(|f| self.encode_type(f.ty.id, depth + 1, u, out))
+	}
+
+	fn encode_primitive(
+		&self,
+		primitive: &TypeDefPrimitive,
+		u: &mut Unstructured,
+		out: &mut Vec<u8>,
+	) -> Result<()> {
+		match primitive {
+			TypeDefPrimitive::Bool => u.arbitrary::<bool>()?.encode_to(out),
+			TypeDefPrimitive::Char => (u.arbitrary::<char>()? as u32).encode_to(out),
+			TypeDefPrimitive::Str => {
+				let len = u.int_in_range(0..=MAX_SEQUENCE_LEN)?;
+				let s = (0..len)
+					.map(|_| u.int_in_range(b'a'..=b'z').map(char::from))
+					.collect::<Result<String>>()?;
+				s.encode_to(out)
+			},
+			TypeDefPrimitive::U8 => out.push(arbitrary_uint(8, u)? as u8),
+			TypeDefPrimitive::U16 => (arbitrary_uint(16, u)? as u16).encode_to(out),
+			TypeDefPrimitive::U32 => (arbitrary_uint(32, u)? as u32).encode_to(out),
+			TypeDefPrimitive::U64 => (arbitrary_uint(64, u)? as u64).encode_to(out),
+			TypeDefPrimitive::U128 => arbitrary_uint(128, u)?.encode_to(out),
+			TypeDefPrimitive::I8 => u.arbitrary::<i8>()?.encode_to(out),
+			TypeDefPrimitive::I16 => u.arbitrary::<i16>()?.encode_to(out),
+			TypeDefPrimitive::I32 => u.arbitrary::<i32>()?.encode_to(out),
+			TypeDefPrimitive::I64 => u.arbitrary::<i64>()?.encode_to(out),
+			TypeDefPrimitive::I128 => u.arbitrary::<i128>()?.encode_to(out),
+			TypeDefPrimitive::U256 | TypeDefPrimitive::I256 => out.extend_from_slice(u.bytes(32)?),
+		}
+		Ok(())
+	}
+
+	/// Returns the bit width of the unsigned integer behind a compact encoded type.
+	///
+	/// Compact encoding is also used for single field wrappers like `Perbill`.
+	fn compact_bits(&self, id: u32) -> Result<u32> {
+		let ty = self.registry.resolve(id).ok_or(Error::IncorrectFormat)?;
+		match &ty.type_def {
+			TypeDef::Primitive(TypeDefPrimitive::U8) => Ok(8),
+			TypeDef::Primitive(TypeDefPrimitive::U16) => Ok(16),
+			TypeDef::Primitive(TypeDefPrimitive::U32) => Ok(32),
+			TypeDef::Primitive(TypeDefPrimitive::U64) => Ok(64),
+			TypeDef::Primitive(TypeDefPrimitive::U128) => Ok(128),
+			TypeDef::Composite(c) if c.fields.len() == 1 => self.compact_bits(c.fields[0].ty.id),
+			TypeDef::Composite(c) if c.fields.is_empty() => Ok(0),
+			TypeDef::Tuple(t) if t.fields.is_empty() => Ok(0),
+			_ => Err(Error::IncorrectFormat),
+		}
+	}
+}
+
+/// Returns an unsigned integer of `bits` width that is biased towards edge cases.
+fn arbitrary_uint(bits: u32, u: &mut Unstructured) -> Result<u128> {
+	let max = match bits {
+		0 => return Ok(0),
+		128.. => u128::MAX,
+		bits => (1u128 << bits) - 1,
+	};
+
+	Ok(match u.int_in_range(0u8..=7)? {
+		0 => 0,
+		1 => 1,
+		2 => max,
+		3 => u.int_in_range(0..=max.min(1_000))?,
+		_ => u.int_in_range(0..=max)?,
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use codec::{Decode, DecodeLimit};
+
+	#[derive(Encode, Decode, TypeInfo, Debug, PartialEq)]
+	enum Call {
+		Transfer {
+			dest: u64,
+			#[codec(compact)]
+			value: u128,
+		},
+		Batch {
+			calls: Vec<Call>,
+		},
+		Remark {
+			remark: Vec<u8>,
+			flag: Option<bool>,
+		},
+		Tuple((u8, [u16; 3]), String),
+	}
+
+	#[test]
+	fn generated_values_decode() {
+		let generator = ValueGenerator::new::<Call, u64>(&[1, 2, 3]);
+
+		let mut decoded = 0;
+		for seed in 0..1_000u32 {
+			let data: Vec<u8> = (0..256u32)
+				.map(|i| (seed.wrapping_mul(31).wrapping_add(i * 7) % 256) as u8)
+				.collect();
+			let Ok(encoded) = generator.generate(&mut Unstructured::new(&data)) else { continue };
+
+			Call::decode_all_with_depth_limit(MAX_DEPTH, &mut &encoded[..])
+				.expect("generated values must be decodable");
+			decoded += 1;
+		}
+		assert!(decoded > 0);
+	}
+
+	#[test]
+	fn accounts_are_preferred() {
+		let generator = ValueGenerator::new::<u64, u64>(&[42]);
+
+		let data = [0u8; 64];
+		let encoded = generator.generate(&mut Unstructured::new(&data)).unwrap();
+		assert_eq!(u64::decode(&mut &encoded[..]).unwrap(), 42);
+	}
+}
@@ -0,0 +1,45 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Runtime invariants that can be checked by [`crate::FuzzRuntime::invariants`].
+
+use frame_support::{ensure, traits::fungible::Inspect};
+use sp_runtime::{
+	traits::{Saturating, Zero},
+	TryRuntimeError,
+};
+
+/// Checks that the total issuance of `pallet_balances` equals the sum of all account balances.
+///
+/// Any call that creates or destroys funds without updating the total issuance breaks this.
+/// Accounts are enumerated through `frame_system::Account`, so every account with a balance
+/// must have a provider reference.
+pub fn total_issuance_matches_balances<T, I>() -> Result<(), TryRuntimeError>
+where
+	T: pallet_balances::Config<I>,
+	I: 'static,
+{
+	let total = frame_system::Account::<T>::iter_keys().fold(T::Balance::zero(), |acc, who| {
+		acc.saturating_add(pallet_balances::Pallet::<T, I>::total_balance(&who))
+	});
+
+	ensure!(
+		total == pallet_balances::TotalIssuance::<T, I>::get(),
+		"total issuance does not match the sum of all balances"
+	);
+	Ok(())
+}
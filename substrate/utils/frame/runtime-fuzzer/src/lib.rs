@@ -0,0 +1,347 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Runtime fuzzer
+//!
+//! A generic fuzzer for runtimes built with `construct_runtime!`.
+//!
+//! The fuzzer does not need to know anything about the pallets of a runtime. Random
+//! `RuntimeCall`s are generated from their `scale-info` type metadata by the [`ValueGenerator`]
+//! and applied as signed extrinsics of random accounts. Blocks are initialized and finalized and
+//! extrinsics are applied through `frame_executive::Executive`, so that the transaction
+//! extensions and the hooks of all pallets are exercised. After every block the `try_state` hooks
+//! of all pallets and the [`FuzzRuntime::invariants`] are checked.
+//!
+//! A runtime is plugged in by implementing [`FuzzRuntime`], see the `example-runtime-fuzzer`
+//! binary of this crate.
+//!
+//! ## Failures
+//!
+//! A failing input is shrunk with [`minimise`] to a short sequence of [`Step`]s. The hex encoded
+//! SCALE encoding of that sequence can be decoded with [`Harness::decode_steps`] and executed
+//! with [`Harness::execute`] to reproduce the failure.
+
+pub mod generator;
+pub mod invariants;
+pub mod minimise;
+
+pub use generator::ValueGenerator;
+pub use minimise::minimise;
+
+use arbitrary::Unstructured;
+use codec::{Decode, DecodeLimit, Encode, FullCodec};
+use scale_info::TypeInfo;
+use sp_runtime::{ApplyExtrinsicResult, TryRuntimeError};
+use std::{
+	fmt::{self, Debug},
+	panic::{self, AssertUnwindSafe},
+};
+
+/// Maximum number of steps that are generated from a single input.
+const MAX_STEPS: u32 = 64;
+
+/// A runtime that can be fuzzed by the [`Harness`].
+pub trait FuzzRuntime {
+	/// The aggregated `RuntimeCall` of the runtime.
+	type RuntimeCall: FullCodec + TypeInfo + Clone + Debug + 'static;
+	/// The account id of the runtime.
+	type AccountId: FullCodec + TypeInfo + Clone + Debug + 'static;
+
+	/// Accounts that are used as signed origins. At most 256 accounts are supported.
+	fn accounts() -> Vec<Self::AccountId>;
+
+	/// Builds the genesis state. All [`Self::accounts`] should be endowed.
+	fn genesis() -> sp_io::TestExternalities;
+
+	/// Initializes block `n` through `Executive::initialize_block` and applies the inherents
+	/// that the runtime requires.
+	fn initialize_block(n: u32);
+
+	/// Finalizes the current block through `Executive::finalize_block`.
+	fn finalize_block();
+
+	/// Applies `call` as a signed extrinsic of `who` through `Executive::apply_extrinsic`.
+	///
+	/// The extrinsic must pass the signature check, e.g. by using a runtime with
+	/// `sp_runtime::testing::UintAuthorityId` signatures. Errors are expected for random calls
+	/// and therefore ignored. Only panics are failures.
+	fn apply_extrinsic(call: Self::RuntimeCall, who: Self::AccountId) -> ApplyExtrinsicResult;
+
+	/// Returns whether the fuzzer may dispatch `call`.
+	///
+	/// Use this to exclude calls that legitimately break invariants, like `System::set_code`.
+	fn is_allowed(_call: &Self::RuntimeCall) -> bool {
+		true
+	}
+
+	/// Runs the `try_state` hooks of all pallets for block `n`.
+	fn try_state(_n: u32) -> Result<(), TryRuntimeError> {
+		Ok(())
+	}
+
+	/// Additional invariants that are checked after every block.
+	///
+	/// See [`invariants`] for generic implementations.
+	fn invariants() -> Result<(), TryRuntimeError> {
+		Ok(())
+	}
+}
+
+/// A single step of a fuzzing run.
+#[derive(Encode, Decode, Clone, Debug, PartialEq)]
+pub enum Step<Call> {
+	/// Apply `call` as a signed extrinsic of the account at index `origin`.
+	Call {
+		/// Index into [`FuzzRuntime::accounts`].
+		origin: u8,
+		/// The call to apply.
+		call: Call,
+	},
+	/// Finalize the current block and initialize the next one.
+	NextBlock,
+}
+
+/// Why and where a sequence of steps failed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Failure {
+	/// Index of the step that failed. Equal to the number of steps if the final block failed.
+	pub step: usize,
+	/// Block in which the failure happened.
+	pub block: u32,
+	/// Panic message or the violated invariant.
+	pub reason: String,
+}
+
+impl fmt::Display for Failure {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "step {} in block {} failed: {}", self.step, self.block, self.reason)
+	}
+}
+
+/// Generates, executes and minimises sequences of calls for a [`FuzzRuntime`].
+pub struct Harness<R: FuzzRuntime> {
+	generator: ValueGenerator,
+	accounts: Vec<R::AccountId>,
+}
+
+impl<R: FuzzRuntime> Default for Harness<R> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl<R: FuzzRuntime> Harness<R> {
+	/// Creates a new harness. Builds the type registry of `R::RuntimeCall`.
+	pub fn new() -> Self {
+		let accounts = R::accounts();
+		assert!(
+			!accounts.is_empty() && accounts.len() <= u8::MAX as usize + 1,
+			"FuzzRuntime::accounts must return between 1 and 256 accounts"
+		);
+		let generator = ValueGenerator::new::<R::RuntimeCall, R::AccountId>(&accounts);
+
+		Self { generator, accounts }
+	}
+
+	/// Generates a sequence of steps from unstructured fuzzer input.
+	///
+	/// Generated calls that cannot be generated or decoded, or are not
+	/// [`FuzzRuntime::is_allowed`], are skipped. Generation stops with the steps generated so far
+	/// once the input is used up.
+	pub fn generate(&self, u: &mut Unstructured) -> arbitrary::Result<Vec<Step<R::RuntimeCall>>> {
+		let mut steps = Vec::new();
+
+		for _ in 0..u.int_in_range(1..=MAX_STEPS)? {
+			if u.ratio(1, 8)? {
+				steps.push(Step::NextBlock);
+				continue
+			}
+
+			let origin = u.int_in_range(0..=(self.accounts.len() - 1) as u8)?;
+			let encoded = match self.generator.generate(u) {
+				Ok(encoded) => encoded,
+				Err(arbitrary::Error::NotEnoughData) => break,
+				// E.g. a value nested deeper than `MAX_DEPTH`.
+				Err(_) => continue,
+			};
+			let Ok(call) = R::RuntimeCall::decode_all_with_depth_limit(
+				generator::MAX_DEPTH,
+				&mut &encoded[..],
+			) else {
+				continue
+			};
+
+			if R::is_allowed(&call) {
+				steps.push(Step::Call { origin, call });
+			}
+		}
+
+		Ok(steps)
+	}
+
+	/// Executes `steps` on a fresh genesis state.
+	///
+	/// Returns the first panic or violated invariant.
+	pub fn execute(&self, steps: &[Step<R::RuntimeCall>]) -> Result<(), Failure> {
+		let mut block = 1;
+		let fail = |step, block, reason| Failure { step, block, reason };
+
+		R::genesis().execute_with(|| {
+			catch(|| R::initialize_block(block)).map_err(|e| fail(0, block, e))?;
+
+			for (index, step) in steps.iter().enumerate() {
+				match step {
+					Step::Call { origin, call } => {
+						let who = self.accounts[*origin as usize % self.accounts.len()].clone();
+						catch(|| {
+							let _ = R::apply_extrinsic(call.clone(), who);
+						})
+						.map_err(|e| fail(index, block, e))?;
+					},
+					Step::NextBlock => {
+						Self::end_block(block).map_err(|e| fail(index, block, e))?;
+						block += 1;
+						catch(|| R::initialize_block(block)).map_err(|e| fail(index, block, e))?;
+					},
+				}
+			}
+
+			Self::end_block(block).map_err(|e| fail(steps.len(), block, e))
+		})
+	}
+
+	/// Runs a single fuzzer input and panics on failure.
+	///
+	/// The panic message contains the hex encoded sequence of steps, which can be decoded with
+	/// [`Self::decode_steps`] and reproduced with [`Self::execute`].
+	pub fn run(&self, data: &[u8]) {
+		let Ok(steps) = self.generate(&mut Unstructured::new(data)) else { return };
+
+		if let Err(failure) = self.execute(&steps) {
+			panic!("{}\nsteps: {}", failure, array_bytes::bytes2hex("0x", steps.encode()));
+		}
+	}
+
+	/// Shrinks the steps generated from `data` to a short failing sequence with [`minimise`].
+	///
+	/// Returns `None` if the input does not fail.
+	pub fn minimise(&self, data: &[u8]) -> Option<(Vec<Step<R::RuntimeCall>>, Failure)> {
+		let steps = self.generate(&mut Unstructured::new(data)).ok()?;
+		self.execute(&steps).err()?;
+
+		// Silence the panics of the intermediate runs.
+		let hook = panic::take_hook();
+		panic::set_hook(Box::new(|_| {}));
+		let minimal = minimise(steps, |s| self.execute(s).is_err());
+		panic::set_hook(hook);
+
+		let failure = self.execute(&minimal).expect_err("the minimised sequence fails; qed");
+		Some((minimal, failure))
+	}
+
+	/// Decodes a hex encoded sequence of steps as printed by [`Self::run`].
+	pub fn decode_steps(hex: &str) -> Result<Vec<Step<R::RuntimeCall>>, String> {
+		let encoded =
+			array_bytes::hex2bytes(hex).map_err(|e| format!("Invalid hex input: {:?}", e))?;
+		Vec::<Step<R::RuntimeCall>>::decode(&mut &encoded[..])
+			.map_err(|e| format!("Input is not a SCALE encoded sequence of steps: {}", e))
+	}
+
+	/// Finalizes block `n` and checks all invariants.
+	fn end_block(n: u32) -> Result<(), String> {
+		catch(R::finalize_block)?;
+		R::try_state(n).map_err(|e| format!("try_state: {:?}", e))?;
+		R::invariants().map_err(|e| format!("invariant: {:?}", e))
+	}
+}
+
+/// Calls `f` and turns a panic into an error.
+fn catch(f: impl FnOnce()) -> Result<(), String> {
+	panic::catch_unwind(AssertUnwindSafe(f)).map_err(|e| {
+		e.downcast_ref::<&str>()
+			.map(|s| s.to_string())
+			.or_else(|| e.downcast_ref::<String>().cloned())
+			.unwrap_or_else(|| "unknown panic".into())
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use scale_info::{Type, TypeDefPrimitive};
+
+	/// A 256 bit word, generated from 32 bytes of input.
+	#[derive(Encode, Decode, Clone, Debug, PartialEq)]
+	struct Word([u8; 32]);
+
+	impl TypeInfo for Word {
+		type Identity = Self;
+
+		fn type_info() -> Type {
+			TypeDefPrimitive::U256.into()
+		}
+	}
+
+	#[derive(Encode, Decode, TypeInfo, Clone, Debug, PartialEq)]
+	enum Call {
+		Remark(u8),
+		Word(Word),
+	}
+
+	struct TestRuntime;
+
+	impl FuzzRuntime for TestRuntime {
+		type RuntimeCall = Call;
+		type AccountId = u64;
+
+		fn accounts() -> Vec<u64> {
+			vec![1, 2]
+		}
+
+		fn genesis() -> sp_io::TestExternalities {
+			Default::default()
+		}
+
+		fn initialize_block(_: u32) {}
+
+		fn finalize_block() {}
+
+		fn apply_extrinsic(_: Call, _: u64) -> ApplyExtrinsicResult {
+			Ok(Ok(()))
+		}
+	}
+
+	#[test]
+	fn generation_keeps_the_calls_before_the_input_is_used_up() {
+		let harness = Harness::<TestRuntime>::new();
+
+		let mut remarks = 0;
+		for seed in 0..256u32 {
+			let data: Vec<u8> = (0..48u32)
+				.map(|i| (seed.wrapping_mul(37).wrapping_add(i * 11) % 256) as u8)
+				.collect();
+			let steps = harness
+				.generate(&mut Unstructured::new(&data))
+				.expect("running out of input is not an error");
+			remarks += steps
+				.iter()
+				.filter(|s| matches!(s, Step::Call { call: Call::Remark(_), .. }))
+				.count();
+		}
+		assert!(remarks > 0);
+	}
+}
@@ -0,0 +1,73 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Shrinks failing sequences of steps.
+
+/// Removes as many elements from `steps` as possible while `fails` still holds.
+///
+/// This is a simplified delta debugging: chunks of halving size are removed one after another
+/// and the removal is kept whenever the remaining sequence still fails. The result is usually
+/// short, but it is not guaranteed to be 1-minimal: an element that was needed when its removal
+/// was tried may become removable after later elements are removed.
+pub fn minimise<T: Clone>(mut steps: Vec<T>, mut fails: impl FnMut(&[T]) -> bool) -> Vec<T> {
+	let mut chunk = steps.len().div_ceil(2);
+
+	while chunk > 0 {
+		let mut start = 0;
+		while start < steps.len() {
+			let end = (start + chunk).min(steps.len());
+			let candidate: Vec<T> = steps[..start].iter().chain(&steps[end..]).cloned().collect();
+			if fails(&candidate) {
+				steps = candidate;
+			} else {
+				start += chunk;
+			}
+		}
+		chunk /= 2;
+	}
+
+	steps
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn minimise_single_culprit() {
+		let steps: Vec<u32> = (0..100).collect();
+		assert_eq!(minimise(steps, |s| s.contains(&42)), vec![42]);
+	}
+
+	#[test]
+	fn minimise_keeps_order_of_dependent_steps() {
+		// Fails only if `3` happens before `7`.
+		let fails = |s: &[u32]| {
+			let three = s.iter().position(|x| *x == 3);
+			let seven = s.iter().position(|x| *x == 7);
+			matches!((three, seven), (Some(a), Some(b)) if a < b)
+		};
+
+		let steps: Vec<u32> = (0..20).collect();
+		assert_eq!(minimise(steps, fails), vec![3, 7]);
+	}
+
+	#[test]
+	fn minimise_empty_is_noop() {
+		assert!(minimise(Vec::<u32>::new(), |_| true).is_empty());
+	}
+}
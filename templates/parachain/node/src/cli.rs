@@ -85,6 +85,13 @@ pub struct Cli {
 	#[arg(long)]
 	pub no_hardware_benchmarks: bool,
 
+	/// The RPC endpoint of a chain this chain sends XCMs to, as `relay=URL` or `PARA_ID=URL`.
+	///
+	/// `xcm_estimateFees` quotes the execution of messages on these chains. Can be given
+	/// multiple times.
+	#[arg(long = "xcm-fee-destination", value_name = "CHAIN=URL")]
+	pub xcm_fee_destinations: Vec<xcm_fee_estimation_rpc::Destination>,
+
 	/// Relay chain arguments
 	#[arg(raw = true)]
 	pub relay_chain_args: Vec<String>,
@@ -254,6 +254,7 @@ pub fn run() -> Result<()> {
 					polkadot_config,
 					collator_options,
 					hwbench,
+					cli.xcm_fee_destinations.clone(),
 				)
 				.await
 				.map(|r| r.0)
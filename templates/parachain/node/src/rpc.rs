@@ -7,7 +7,9 @@
 
 use std::sync::Arc;
 
-use parachain_template_runtime::{opaque::Block, AccountId, Balance, Nonce};
+use parachain_template_runtime::{
+	opaque::Block, AccountId, Balance, Nonce, OriginCaller, RuntimeCall, RuntimeEvent,
+};
 
 use polkadot_sdk::*;
 
@@ -25,6 +27,8 @@ pub struct FullDeps<C, P> {
 	pub client: Arc<C>,
 	/// Transaction pool instance.
 	pub pool: Arc<P>,
+	/// The chains `xcm_estimateFees` quotes the execution of messages on.
+	pub xcm_fee_destinations: Vec<xcm_fee_estimation_rpc::Destination>,
 }
 
 /// Instantiate all RPC extensions.
@@ -41,15 +45,28 @@ where
 	C::Api: pallet_transaction_payment_rpc::TransactionPaymentRuntimeApi<Block, Balance>,
 	C::Api: substrate_frame_rpc_system::AccountNonceApi<Block, AccountId, Nonce>,
 	C::Api: BlockBuilder<Block>,
+	C::Api: xcm_runtime_apis::dry_run::DryRunApi<Block, RuntimeCall, RuntimeEvent, OriginCaller>,
+	C::Api: xcm_runtime_apis::fees::XcmPaymentApi<Block>,
 	P: TransactionPool + Sync + Send + 'static,
 {
 	use pallet_transaction_payment_rpc::{TransactionPayment, TransactionPaymentApiServer};
 	use substrate_frame_rpc_system::{System, SystemApiServer};
+	use xcm_fee_estimation_rpc::{XcmFeeEstimation, XcmFeeEstimationApiServer};
 
 	let mut module = RpcExtension::new(());
-	let FullDeps { client, pool } = deps;
+	let FullDeps { client, pool, xcm_fee_destinations } = deps;
 
 	module.merge(System::new(client.clone(), pool).into_rpc())?;
-	module.merge(TransactionPayment::new(client).into_rpc())?;
+	module.merge(TransactionPayment::new(client.clone()).into_rpc())?;
+	// The template has no asset conversion, so fees are only estimated in the asset they are
+	// charged in.
+	module.merge(
+		XcmFeeEstimation::<_, _, RuntimeCall, RuntimeEvent, OriginCaller, ()>::new(
+			client,
+			(),
+			xcm_fee_destinations,
+		)
+		.into_rpc(),
+	)?;
 	Ok(module)
 }
@@ -238,6 +238,7 @@ pub async fn start_parachain_node(
 	polkadot_config: Configuration,
 	collator_options: CollatorOptions,
 	hwbench: Option<sc_sysinfo::HwBench>,
+	xcm_fee_destinations: Vec<xcm_fee_estimation_rpc::Destination>,
 ) -> sc_service::error::Result<(TaskManager, Arc<ParachainClient>)> {
 	let parachain_config = prepare_node_config(parachain_config);
 
@@ -331,8 +332,11 @@ pub async fn start_parachain_node(
 		let transaction_pool = transaction_pool.clone();
 
 		Box::new(move |_| {
-			let deps =
-				crate::rpc::FullDeps { client: client.clone(), pool: transaction_pool.clone() };
+			let deps = crate::rpc::FullDeps {
+				client: client.clone(),
+				pool: transaction_pool.clone(),
+				xcm_fee_destinations: xcm_fee_destinations.clone(),
+			};
 
 			crate::rpc::create_full(deps).map_err(Into::into)
 		})
@@ -406,6 +410,7 @@ pub async fn start_parachain_node(
 		recovery_handle: Box::new(overseer_handle.clone()),
 		sync_service: sync_service.clone(),
 		prometheus_registry: prometheus_registry.as_ref(),
+		pov_recovery_handle: None,
 	})?;
 
 	start_bootnode_tasks(StartBootnodeTasksParams {
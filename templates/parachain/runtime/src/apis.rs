@@ -24,9 +24,12 @@
 // For more information, please refer to <http://unlicense.org>
 
 // External crates imports
-use alloc::vec::Vec;
+use alloc::{vec, vec::Vec};
 
-use polkadot_sdk::{staging_parachain_info as parachain_info, *};
+use polkadot_sdk::{
+	staging_parachain_info as parachain_info, staging_xcm as xcm,
+	staging_xcm_executor as xcm_executor, *,
+};
 
 use cumulus_primitives_core::ParaId;
 use frame_support::{
@@ -43,12 +46,20 @@ use sp_runtime::{
 	ApplyExtrinsicResult,
 };
 use sp_version::RuntimeVersion;
+use xcm::{
+	latest::prelude::AssetId, VersionedAssetId, VersionedAssets, VersionedLocation, VersionedXcm,
+};
+use xcm_runtime_apis::{
+	dry_run::{CallDryRunEffects, Error as XcmDryRunApiError, XcmDryRunEffects},
+	fees::Error as XcmPaymentApiError,
+};
 
 // Local module imports
 use super::{
-	AccountId, Balance, Block, ConsensusHook, Executive, InherentDataExt, Nonce, ParachainSystem,
-	Runtime, RuntimeCall, RuntimeGenesisConfig, SessionKeys, System, TransactionPayment,
-	SLOT_DURATION, VERSION,
+	configs::xcm_config::{RelayLocation, XcmConfig, XcmRouter},
+	AccountId, Balance, Block, ConsensusHook, Executive, InherentDataExt, Nonce, OriginCaller,
+	ParachainSystem, PolkadotXcm, Runtime, RuntimeCall, RuntimeEvent, RuntimeGenesisConfig,
+	SessionKeys, System, TransactionPayment, UncheckedExtrinsic, SLOT_DURATION, VERSION,
 };
 
 // we move some impls outside so we can easily use them with `docify`.
@@ -84,6 +95,38 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl cumulus_primitives_core::RelayStorageKeysApi<Block> for Runtime {
+		fn relay_storage_keys() -> Vec<Vec<u8>> {
+			// List the pallets that read relay chain storage here, e.g. `(Pallet1, Pallet2)`.
+			<() as cumulus_pallet_parachain_system::RelayStorageKeys>::relay_storage_keys()
+		}
+	}
+
+	impl cumulus_primitives_core::UpgradePreflightApi<Block> for Runtime {
+		fn staged_validation_code() -> Option<Vec<u8>> {
+			ParachainSystem::staged_validation_code()
+		}
+
+		fn upgrade_preflight_report_extrinsic(
+			report: cumulus_primitives_core::UpgradePreflightReport,
+		) -> <Block as BlockT>::Extrinsic {
+			UncheckedExtrinsic::new_bare(
+				cumulus_pallet_parachain_system::Call::report_upgrade_preflight { report }.into(),
+			)
+		}
+	}
+
+	impl cumulus_primitives_core::EncryptedExtrinsicsApi<Block> for Runtime {
+		fn encrypted_inclusion_extrinsic(
+			commitments: cumulus_primitives_core::EncryptedExtrinsicCommitments,
+		) -> <Block as BlockT>::Extrinsic {
+			UncheckedExtrinsic::new_bare(
+				cumulus_pallet_parachain_system::Call::note_encrypted_extrinsics { commitments }
+					.into(),
+			)
+		}
+	}
+
 	impl cumulus_primitives_aura::AuraUnincludedSegmentApi<Block> for Runtime {
 		fn can_build_upon(
 			included_hash: <Block as BlockT>::Hash,
@@ -226,6 +269,42 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl pallet_collator_selection::runtime_api::CollatorSelectionApi<Block, AccountId> for Runtime {
+		fn collator_scores() -> Vec<pallet_collator_selection::CollatorScore<AccountId>> {
+			pallet_collator_selection::Pallet::<Runtime>::collator_scores()
+		}
+	}
+
+	impl xcm_runtime_apis::fees::XcmPaymentApi<Block> for Runtime {
+		fn query_acceptable_payment_assets(xcm_version: xcm::Version) -> Result<Vec<VersionedAssetId>, XcmPaymentApiError> {
+			PolkadotXcm::query_acceptable_payment_assets(xcm_version, vec![AssetId(RelayLocation::get())])
+		}
+
+		fn query_weight_to_asset_fee(weight: Weight, asset: VersionedAssetId) -> Result<u128, XcmPaymentApiError> {
+			type Trader = <XcmConfig as xcm_executor::Config>::Trader;
+			PolkadotXcm::query_weight_to_asset_fee::<Trader>(weight, asset)
+		}
+
+		fn query_xcm_weight(message: VersionedXcm<()>) -> Result<Weight, XcmPaymentApiError> {
+			PolkadotXcm::query_xcm_weight(message)
+		}
+
+		fn query_delivery_fees(destination: VersionedLocation, message: VersionedXcm<()>, asset_id: VersionedAssetId) -> Result<VersionedAssets, XcmPaymentApiError> {
+			type AssetExchanger = <XcmConfig as xcm_executor::Config>::AssetExchanger;
+			PolkadotXcm::query_delivery_fees::<AssetExchanger>(destination, message, asset_id)
+		}
+	}
+
+	impl xcm_runtime_apis::dry_run::DryRunApi<Block, RuntimeCall, RuntimeEvent, OriginCaller> for Runtime {
+		fn dry_run_call(origin: OriginCaller, call: RuntimeCall, result_xcms_version: xcm::Version) -> Result<CallDryRunEffects<RuntimeEvent>, XcmDryRunApiError> {
+			PolkadotXcm::dry_run_call::<Runtime, XcmRouter, OriginCaller, RuntimeCall>(origin, call, result_xcms_version)
+		}
+
+		fn dry_run_xcm(origin_location: VersionedLocation, xcm: VersionedXcm<RuntimeCall>) -> Result<XcmDryRunEffects<RuntimeEvent>, XcmDryRunApiError> {
+			PolkadotXcm::dry_run_xcm::<XcmRouter>(origin_location, xcm)
+		}
+	}
+
 	impl cumulus_primitives_core::CollectCollationInfo<Block> for Runtime {
 		fn collect_collation_info(header: &<Block as BlockT>::Header) -> cumulus_primitives_core::CollationInfo {
 			ParachainSystem::collect_collation_info(header)
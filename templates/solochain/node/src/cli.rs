@@ -34,6 +34,12 @@ pub enum Subcommand {
 	/// Export the state of a given block into a chain spec.
 	ExportState(sc_cli::ExportStateCmd),
 
+	/// Export the finalized state into the chain spec of a parachain.
+	ExportParachainGenesis(cumulus_client_solo_to_para::ExportParachainGenesisCmd),
+
+	/// Verify the head data registered on the relay chain against the parachain genesis.
+	VerifyParachainHead(cumulus_client_solo_to_para::VerifyParachainHeadCmd),
+
 	/// Import blocks.
 	ImportBlocks(sc_cli::ImportBlocksCmd),
 
@@ -6,8 +6,8 @@ use crate::{
 };
 use frame_benchmarking_cli::{BenchmarkCmd, ExtrinsicFactory, SUBSTRATE_REFERENCE_HARDWARE};
 use sc_cli::SubstrateCli;
-use sc_service::PartialComponents;
-use solochain_template_runtime::{Block, EXISTENTIAL_DEPOSIT};
+use sc_service::{PartialComponents, TaskManager};
+use solochain_template_runtime::{Block, Nonce, Runtime, EXISTENTIAL_DEPOSIT};
 use sp_keyring::Sr25519Keyring;
 
 impl SubstrateCli for Cli {
@@ -82,6 +82,27 @@ pub fn run() -> sc_cli::Result<()> {
 				Ok((cmd.run(client, config.chain_spec), task_manager))
 			})
 		},
+		Some(Subcommand::ExportParachainGenesis(cmd)) => {
+			let runner = cli.create_runner(cmd)?;
+			runner.async_run(|config| {
+				let PartialComponents { client, task_manager, .. } = service::new_partial(&config)?;
+				Ok((
+					cmd.run::<Block, _, _, Nonce, <Runtime as frame_system::Config>::AccountData>(
+						client,
+						config.chain_spec,
+					),
+					task_manager,
+				))
+			})
+		},
+		Some(Subcommand::VerifyParachainHead(cmd)) => {
+			let runner = cli.create_runner(cmd)?;
+			runner.sync_run(|config| {
+				let mut task_manager = TaskManager::new(config.tokio_handle.clone(), None)
+					.map_err(sc_service::Error::from)?;
+				config.tokio_handle.block_on(cmd.run::<Block>(&mut task_manager))
+			})
+		},
 		Some(Subcommand::ImportBlocks(cmd)) => {
 			let runner = cli.create_runner(cmd)?;
 			runner.async_run(|config| {